@@ -0,0 +1,36 @@
+DO $$ BEGIN
+    CREATE TYPE actor_type AS ENUM ('customer', 'staff', 'system');
+EXCEPTION
+    WHEN duplicate_object THEN null;
+END $$;
+
+CREATE TABLE audit_log (
+    id SERIAL PRIMARY KEY,
+    actor_type actor_type NOT NULL,
+    actor_id INTEGER NOT NULL,
+    action VARCHAR(64) NOT NULL,
+    entity_type VARCHAR(32) NOT NULL,
+    entity_id INTEGER,
+    before_snapshot TEXT,
+    after_snapshot TEXT,
+    request_method VARCHAR(8) NOT NULL,
+    request_path TEXT NOT NULL,
+    request_ip VARCHAR(64),
+    user_agent TEXT,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    prev_hash VARCHAR(64) NOT NULL,
+    hash VARCHAR(64) UNIQUE NOT NULL
+);
+
+CREATE INDEX audit_log_entity_idx ON audit_log (entity_type, entity_id);
+CREATE INDEX audit_log_actor_idx ON audit_log (actor_type, actor_id);
+
+-- append only, the hash chain catches anything that gets around this
+CREATE OR REPLACE FUNCTION audit_log_reject_modification() RETURNS trigger AS $$
+BEGIN
+    RAISE EXCEPTION 'audit_log is append only';
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER audit_log_no_update BEFORE UPDATE OR DELETE ON audit_log
+    FOR EACH ROW EXECUTE PROCEDURE audit_log_reject_modification();
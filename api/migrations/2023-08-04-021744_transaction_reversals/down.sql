@@ -0,0 +1,11 @@
+DROP INDEX transactions_reversal_of_idx;
+
+ALTER TABLE transactions
+    DROP CONSTRAINT reversed_amount_valid,
+    DROP COLUMN reversal_reason,
+    DROP COLUMN reversed_amount_cents,
+    DROP COLUMN reversal_of;
+
+-- postgres can't drop enum values, rows using them have to go back to success
+UPDATE transactions SET transaction_status = 'success'
+    WHERE transaction_status IN ('partially_reversed', 'reversed');
@@ -0,0 +1,10 @@
+ALTER TYPE transaction_status ADD VALUE IF NOT EXISTS 'partially_reversed';
+ALTER TYPE transaction_status ADD VALUE IF NOT EXISTS 'reversed';
+
+ALTER TABLE transactions
+    ADD COLUMN reversal_of INTEGER REFERENCES transactions (id),
+    ADD COLUMN reversed_amount_cents BIGINT NOT NULL DEFAULT 0,
+    ADD COLUMN reversal_reason VARCHAR(140),
+    ADD CONSTRAINT reversed_amount_valid CHECK (reversed_amount_cents >= 0 AND reversed_amount_cents <= amount_cents);
+
+CREATE INDEX transactions_reversal_of_idx ON transactions (reversal_of);
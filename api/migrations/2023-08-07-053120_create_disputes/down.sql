@@ -0,0 +1,3 @@
+DROP TABLE dispute_events;
+DROP TABLE disputes;
+DROP TYPE dispute_status;
@@ -0,0 +1,45 @@
+DO $$ BEGIN
+    CREATE TYPE dispute_status AS ENUM (
+        'open',
+        'investigating',
+        'provisional_credit',
+        'resolved_for_customer',
+        'resolved_against_customer'
+    );
+EXCEPTION
+    WHEN duplicate_object THEN null;
+END $$;
+
+CREATE TABLE disputes (
+    id SERIAL PRIMARY KEY,
+    customer_id INTEGER NOT NULL,
+    transaction_id INTEGER NOT NULL REFERENCES transactions (id),
+    amount_cents BIGINT NOT NULL,
+    reason VARCHAR(500) NOT NULL,
+    dispute_status dispute_status NOT NULL DEFAULT 'open',
+    -- net amount credited to the customer so far, provisional or final
+    credited_cents BIGINT NOT NULL DEFAULT 0,
+    date_opened TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT current_timestamp,
+    date_resolved TIMESTAMP WITH TIME ZONE,
+    CONSTRAINT amount_valid CHECK (amount_cents > 0)
+);
+
+CREATE INDEX disputes_customer_idx ON disputes (customer_id);
+
+-- one live dispute per transaction
+CREATE UNIQUE INDEX disputes_transaction_unresolved_idx ON disputes (transaction_id)
+    WHERE dispute_status NOT IN ('resolved_for_customer', 'resolved_against_customer');
+
+CREATE TABLE dispute_events (
+    id SERIAL PRIMARY KEY,
+    dispute_id INTEGER NOT NULL REFERENCES disputes (id),
+    dispute_status dispute_status NOT NULL,
+    note VARCHAR(500),
+    actor_type actor_type NOT NULL,
+    actor_id INTEGER NOT NULL,
+    -- credit/debit posted as part of this step, if any
+    transaction_id INTEGER REFERENCES transactions (id),
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT current_timestamp
+);
+
+CREATE INDEX dispute_events_dispute_idx ON dispute_events (dispute_id);
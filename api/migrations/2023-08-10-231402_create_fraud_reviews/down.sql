@@ -0,0 +1,6 @@
+DROP INDEX transactions_from_number_date_idx;
+DROP TABLE fraud_reviews;
+DROP TYPE fraud_review_status;
+
+-- postgres can't drop enum values, anything still held goes back to pending
+UPDATE transactions SET transaction_status = 'pending' WHERE transaction_status = 'held';
@@ -0,0 +1,23 @@
+ALTER TYPE transaction_status ADD VALUE IF NOT EXISTS 'held';
+
+DO $$ BEGIN
+    CREATE TYPE fraud_review_status AS ENUM ('pending', 'approved', 'rejected');
+EXCEPTION
+    WHEN duplicate_object THEN null;
+END $$;
+
+CREATE TABLE fraud_reviews (
+    id SERIAL PRIMARY KEY,
+    transaction_id INTEGER UNIQUE NOT NULL REFERENCES transactions (id),
+    customer_id INTEGER NOT NULL,
+    -- json array of the rule reasons that triggered the hold
+    reasons TEXT NOT NULL,
+    review_status fraud_review_status NOT NULL DEFAULT 'pending',
+    reviewer_id INTEGER,
+    review_note VARCHAR(500),
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT current_timestamp,
+    reviewed_at TIMESTAMP WITH TIME ZONE
+);
+
+CREATE INDEX fraud_reviews_status_idx ON fraud_reviews (review_status);
+CREATE INDEX transactions_from_number_date_idx ON transactions (from_number, date_start);
@@ -0,0 +1,5 @@
+DROP INDEX transactions_customer_date_idx;
+DROP TABLE transfer_limit_requests;
+DROP TABLE customer_transfer_limits;
+DROP TYPE limit_request_status;
+DROP TYPE transfer_limit_kind;
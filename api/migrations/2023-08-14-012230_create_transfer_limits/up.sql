@@ -0,0 +1,53 @@
+DO $$ BEGIN
+    CREATE TYPE transfer_limit_kind AS ENUM (
+        'per_transaction',
+        'daily',
+        'per_payee_daily',
+        'daily_internal',
+        'daily_external'
+    );
+EXCEPTION
+    WHEN duplicate_object THEN null;
+END $$;
+
+DO $$ BEGIN
+    CREATE TYPE limit_request_status AS ENUM ('pending', 'approved', 'declined');
+EXCEPTION
+    WHEN duplicate_object THEN null;
+END $$;
+
+-- per customer overrides, null means the bank wide default applies
+CREATE TABLE customer_transfer_limits (
+    customer_id INTEGER PRIMARY KEY,
+    per_transaction_cents BIGINT,
+    daily_cents BIGINT,
+    per_payee_daily_cents BIGINT,
+    daily_internal_cents BIGINT,
+    daily_external_cents BIGINT,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT current_timestamp,
+    CONSTRAINT limits_valid CHECK (
+        per_transaction_cents >= 0
+        AND daily_cents >= 0
+        AND per_payee_daily_cents >= 0
+        AND daily_internal_cents >= 0
+        AND daily_external_cents >= 0
+    )
+);
+
+CREATE TABLE transfer_limit_requests (
+    id SERIAL PRIMARY KEY,
+    customer_id INTEGER NOT NULL,
+    limit_kind transfer_limit_kind NOT NULL,
+    requested_cents BIGINT NOT NULL,
+    reason VARCHAR(500) NOT NULL,
+    request_status limit_request_status NOT NULL DEFAULT 'pending',
+    reviewer_id INTEGER,
+    review_note VARCHAR(500),
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT current_timestamp,
+    reviewed_at TIMESTAMP WITH TIME ZONE,
+    CONSTRAINT requested_valid CHECK (requested_cents > 0)
+);
+
+CREATE INDEX transfer_limit_requests_customer_idx ON transfer_limit_requests (customer_id);
+CREATE INDEX transfer_limit_requests_status_idx ON transfer_limit_requests (request_status);
+CREATE INDEX transactions_customer_date_idx ON transactions (customer_id, date_start);
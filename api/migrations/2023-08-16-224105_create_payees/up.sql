@@ -0,0 +1,13 @@
+CREATE TABLE payees (
+    id SERIAL PRIMARY KEY,
+    customer_id INTEGER NOT NULL,
+    nickname VARCHAR(40) NOT NULL,
+    account_number VARCHAR(9) NOT NULL,
+    bsb VARCHAR(6) NOT NULL,
+    default_reference VARCHAR(18),
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT current_timestamp,
+    -- null until the first transfer to them, screening treats them as new until then
+    first_used_at TIMESTAMP WITH TIME ZONE,
+    last_used_at TIMESTAMP WITH TIME ZONE,
+    CONSTRAINT payee_unique UNIQUE (customer_id, bsb, account_number)
+);
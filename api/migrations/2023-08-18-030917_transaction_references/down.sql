@@ -0,0 +1,4 @@
+ALTER TABLE transactions
+    DROP COLUMN payee_reference,
+    DROP COLUMN payer_reference,
+    DROP COLUMN description;
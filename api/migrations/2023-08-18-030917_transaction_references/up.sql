@@ -0,0 +1,6 @@
+-- description is the customer's own note, NPP carries up to 280 characters of remittance info.
+-- references go out over BECS direct entry too, so they're held to its 18 character lodgement ref
+ALTER TABLE transactions
+    ADD COLUMN description VARCHAR(280),
+    ADD COLUMN payer_reference VARCHAR(18),
+    ADD COLUMN payee_reference VARCHAR(18);
@@ -0,0 +1,6 @@
+DROP INDEX transactions_counterpart_of_idx;
+
+DELETE FROM transactions WHERE counterpart_of IS NOT NULL;
+
+ALTER TABLE transactions
+    DROP COLUMN counterpart_of;
@@ -0,0 +1,6 @@
+-- the receiving customer's entry of an internal transfer points at the sender's entry.
+-- older internal transfers never moved money so there's nothing to backfill
+ALTER TABLE transactions
+    ADD COLUMN counterpart_of INTEGER REFERENCES transactions (id);
+
+CREATE UNIQUE INDEX transactions_counterpart_of_idx ON transactions (counterpart_of);
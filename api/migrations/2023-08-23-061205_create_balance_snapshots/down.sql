@@ -0,0 +1,4 @@
+ALTER TABLE transactions
+    DROP COLUMN running_balance_cents;
+
+DROP TABLE account_balance_snapshots;
@@ -0,0 +1,13 @@
+-- end of day (UTC) balances, today's row is kept up to date as money moves
+CREATE TABLE account_balance_snapshots (
+    account_id INTEGER NOT NULL REFERENCES accounts (id) ON DELETE CASCADE,
+    snapshot_date DATE NOT NULL,
+    balance_cents BIGINT NOT NULL,
+    available_balance_cents BIGINT NOT NULL,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT current_timestamp,
+    PRIMARY KEY (account_id, snapshot_date)
+);
+
+-- ledger balance of the row owner's account straight after the entry, null until money has moved
+ALTER TABLE transactions
+    ADD COLUMN running_balance_cents BIGINT;
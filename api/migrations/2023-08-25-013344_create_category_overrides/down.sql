@@ -0,0 +1,3 @@
+DROP TABLE category_overrides;
+
+DROP TYPE spending_category;
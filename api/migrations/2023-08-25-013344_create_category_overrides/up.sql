@@ -0,0 +1,29 @@
+DO $$ BEGIN CREATE TYPE spending_category AS ENUM (
+    'groceries',
+    'dining',
+    'transport',
+    'utilities',
+    'housing',
+    'entertainment',
+    'shopping',
+    'health',
+    'transfers',
+    'other'
+);
+
+EXCEPTION
+WHEN duplicate_object THEN null;
+
+END $$;
+
+-- a customer's choice of category for everything sent to a payee account, beats the built in rules
+CREATE TABLE category_overrides (
+    id SERIAL PRIMARY KEY,
+    customer_id INTEGER NOT NULL,
+    account_number VARCHAR(9) NOT NULL,
+    bsb VARCHAR(6) NOT NULL,
+    category spending_category NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT current_timestamp,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT current_timestamp,
+    UNIQUE (customer_id, bsb, account_number)
+);
@@ -0,0 +1,33 @@
+-- monthly (UTC calendar month) spending budget. no category or account means all spending
+CREATE TABLE budgets (
+    id SERIAL PRIMARY KEY,
+    customer_id INTEGER NOT NULL,
+    name VARCHAR(40) NOT NULL,
+    category spending_category,
+    account_number VARCHAR(9),
+    amount_cents BIGINT NOT NULL CHECK (amount_cents > 0),
+    -- percentages of amount_cents, ascending
+    alert_thresholds INTEGER [] NOT NULL DEFAULT '{80,100}',
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT current_timestamp,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT current_timestamp
+);
+
+CREATE INDEX budgets_customer_id_idx ON budgets (customer_id);
+
+-- each threshold is only raised once per budget per month
+CREATE TABLE budget_alerts (
+    id SERIAL PRIMARY KEY,
+    budget_id INTEGER NOT NULL REFERENCES budgets(id) ON DELETE CASCADE,
+    customer_id INTEGER NOT NULL,
+    period_start DATE NOT NULL,
+    threshold_percent INTEGER NOT NULL,
+    spent_cents BIGINT NOT NULL,
+    budget_cents BIGINT NOT NULL,
+    -- the transaction that pushed spending over
+    transaction_id INTEGER REFERENCES transactions(id),
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT current_timestamp,
+    delivered_at TIMESTAMP WITH TIME ZONE,
+    UNIQUE (budget_id, period_start, threshold_percent)
+);
+
+CREATE INDEX budget_alerts_customer_id_idx ON budget_alerts (customer_id);
@@ -0,0 +1,5 @@
+DROP TRIGGER accounts_buckets_total ON accounts;
+
+DROP TABLE account_buckets;
+
+DROP FUNCTION account_buckets_check_total;
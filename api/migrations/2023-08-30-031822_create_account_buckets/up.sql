@@ -0,0 +1,59 @@
+-- named slices of a savings account's balance. once an account has buckets one of them is the
+-- default (unallocated) bucket, which takes every deposit and withdrawal
+CREATE TABLE account_buckets (
+    id SERIAL PRIMARY KEY,
+    account_id INTEGER NOT NULL REFERENCES accounts (id) ON DELETE CASCADE,
+    name VARCHAR(40) NOT NULL,
+    -- only the default bucket can go below zero, with the account
+    balance_cents BIGINT NOT NULL DEFAULT 0 CHECK (balance_cents >= 0 OR is_default),
+    goal_cents BIGINT CHECK (goal_cents > 0),
+    target_date DATE,
+    is_default BOOLEAN NOT NULL DEFAULT false,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT current_timestamp,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT current_timestamp,
+    UNIQUE (account_id, name)
+);
+
+CREATE UNIQUE INDEX account_buckets_one_default_idx ON account_buckets (account_id)
+WHERE
+    is_default;
+
+-- checked at commit so balance and buckets can be moved in either order within a transaction
+CREATE OR REPLACE FUNCTION account_buckets_check_total() RETURNS trigger AS $$
+DECLARE
+    checked_account_id INTEGER;
+    account_balance BIGINT;
+    buckets_total BIGINT;
+BEGIN
+    IF TG_TABLE_NAME = 'accounts' THEN
+        checked_account_id := NEW.id;
+    ELSIF TG_OP = 'DELETE' THEN
+        checked_account_id := OLD.account_id;
+    ELSE
+        checked_account_id := NEW.account_id;
+    END IF;
+
+    SELECT balance_cents INTO account_balance FROM accounts WHERE id = checked_account_id;
+    SELECT sum(balance_cents) INTO buckets_total FROM account_buckets WHERE account_id = checked_account_id;
+
+    -- account gone, or never split into buckets
+    IF account_balance IS NULL OR buckets_total IS NULL THEN
+        RETURN NULL;
+    END IF;
+
+    IF account_balance <> buckets_total THEN
+        RAISE EXCEPTION 'buckets of account % total % but its balance is %',
+            checked_account_id, buckets_total, account_balance;
+    END IF;
+
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE CONSTRAINT TRIGGER account_buckets_total AFTER INSERT OR UPDATE OR DELETE ON account_buckets
+    DEFERRABLE INITIALLY DEFERRED
+    FOR EACH ROW EXECUTE PROCEDURE account_buckets_check_total();
+
+CREATE CONSTRAINT TRIGGER accounts_buckets_total AFTER UPDATE OF balance_cents ON accounts
+    DEFERRABLE INITIALLY DEFERRED
+    FOR EACH ROW EXECUTE PROCEDURE account_buckets_check_total();
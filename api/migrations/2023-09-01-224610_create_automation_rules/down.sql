@@ -0,0 +1,3 @@
+DROP TABLE automation_runs;
+DROP TABLE automation_rules;
+DROP TYPE automation_rule_kind;
@@ -0,0 +1,47 @@
+DO $$ BEGIN
+    CREATE TYPE automation_rule_kind AS ENUM ('round_up', 'sweep_above');
+EXCEPTION
+    WHEN duplicate_object THEN null;
+END $$;
+
+-- moves money between two of a customer's own accounts when something happens. round ups fire on
+-- each settled debit from from_account_id, sweeps at end of day
+CREATE TABLE automation_rules (
+    id SERIAL PRIMARY KEY,
+    customer_id INTEGER NOT NULL,
+    kind automation_rule_kind NOT NULL,
+    from_account_id INTEGER NOT NULL REFERENCES accounts (id) ON DELETE CASCADE,
+    to_account_id INTEGER NOT NULL REFERENCES accounts (id) ON DELETE CASCADE,
+    -- round up: debits are rounded up to a multiple of this
+    round_to_cents BIGINT CHECK (round_to_cents > 0),
+    -- sweep: anything above this is moved
+    threshold_cents BIGINT CHECK (threshold_cents >= 0),
+    enabled BOOLEAN NOT NULL DEFAULT true,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT current_timestamp,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT current_timestamp,
+    CHECK (from_account_id <> to_account_id),
+    CHECK (
+        (kind = 'round_up' AND round_to_cents IS NOT NULL)
+        OR (kind = 'sweep_above' AND threshold_cents IS NOT NULL)
+    )
+);
+
+CREATE INDEX automation_rules_customer_id_idx ON automation_rules (customer_id);
+
+-- each transfer a rule made, also what stops a rule firing twice for the same thing
+CREATE TABLE automation_runs (
+    id SERIAL PRIMARY KEY,
+    rule_id INTEGER NOT NULL REFERENCES automation_rules (id) ON DELETE CASCADE,
+    customer_id INTEGER NOT NULL,
+    -- the debit a round up was for
+    trigger_transaction_id INTEGER REFERENCES transactions (id),
+    -- the day a sweep was for
+    run_date DATE,
+    transaction_id INTEGER NOT NULL REFERENCES transactions (id),
+    amount_cents BIGINT NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT current_timestamp,
+    UNIQUE (rule_id, trigger_transaction_id),
+    UNIQUE (rule_id, run_date)
+);
+
+CREATE INDEX automation_runs_customer_id_idx ON automation_runs (customer_id);
@@ -0,0 +1,9 @@
+DROP TABLE overdraft_charges;
+DROP TYPE overdraft_charge_kind;
+
+ALTER TABLE accounts
+    DROP CONSTRAINT overdraft_limit_valid,
+    DROP COLUMN overdraft_limit_cents;
+
+-- postgres can't drop enum values, charges stay on statements as external debits
+UPDATE transactions SET transaction_type = 'external' WHERE transaction_type = 'fee';
@@ -0,0 +1,28 @@
+ALTER TYPE transaction_type ADD VALUE IF NOT EXISTS 'fee';
+
+-- arranged overdraft, the available balance can go this far below zero
+ALTER TABLE accounts
+    ADD COLUMN overdraft_limit_cents BIGINT NOT NULL DEFAULT 0,
+    ADD CONSTRAINT overdraft_limit_valid CHECK (overdraft_limit_cents >= 0);
+
+DO $$ BEGIN
+    CREATE TYPE overdraft_charge_kind AS ENUM ('interest', 'dishonour_fee');
+EXCEPTION
+    WHEN duplicate_object THEN null;
+END $$;
+
+-- interest and fees taken for being overdrawn, also what stops the same charge going on twice
+CREATE TABLE overdraft_charges (
+    id SERIAL PRIMARY KEY,
+    account_id INTEGER NOT NULL REFERENCES accounts (id) ON DELETE CASCADE,
+    customer_id INTEGER NOT NULL,
+    kind overdraft_charge_kind NOT NULL,
+    -- the day interest was for, or the day the fee was charged
+    charge_date DATE NOT NULL,
+    amount_cents BIGINT NOT NULL CHECK (amount_cents > 0),
+    transaction_id INTEGER NOT NULL REFERENCES transactions (id),
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT current_timestamp,
+    UNIQUE (account_id, kind, charge_date)
+);
+
+CREATE INDEX overdraft_charges_customer_id_idx ON overdraft_charges (customer_id);
@@ -0,0 +1,3 @@
+DROP TABLE fee_charges;
+DROP TABLE fee_schedule;
+DROP TYPE fee_kind;
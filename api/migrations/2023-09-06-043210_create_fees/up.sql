@@ -0,0 +1,44 @@
+DO $$ BEGIN
+    CREATE TYPE fee_kind AS ENUM ('account_keeping', 'external_transfer');
+EXCEPTION
+    WHEN duplicate_object THEN null;
+END $$;
+
+-- what each kind of account is charged, staff can change the amounts. waived for the month once
+-- deposits into the account reach waiver_deposit_cents
+CREATE TABLE fee_schedule (
+    id SERIAL PRIMARY KEY,
+    kind fee_kind NOT NULL,
+    account_type account_type NOT NULL,
+    amount_cents BIGINT NOT NULL CHECK (amount_cents >= 0),
+    waiver_deposit_cents BIGINT CHECK (waiver_deposit_cents > 0),
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT current_timestamp,
+    UNIQUE (kind, account_type)
+);
+
+INSERT INTO fee_schedule (kind, account_type, amount_cents, waiver_deposit_cents) VALUES
+    ('account_keeping', 'transaction', 500, 200000),
+    ('account_keeping', 'savings', 0, NULL),
+    ('account_keeping', 'term_deposit', 0, NULL),
+    ('external_transfer', 'transaction', 50, 200000),
+    ('external_transfer', 'savings', 250, NULL),
+    ('external_transfer', 'term_deposit', 0, NULL);
+
+-- fees taken, also what stops the same fee going on twice
+CREATE TABLE fee_charges (
+    id SERIAL PRIMARY KEY,
+    account_id INTEGER NOT NULL REFERENCES accounts (id) ON DELETE CASCADE,
+    customer_id INTEGER NOT NULL,
+    kind fee_kind NOT NULL,
+    -- account keeping: first day of the month it was for
+    period_start DATE,
+    -- transfer fees: the transfer it was for
+    trigger_transaction_id INTEGER UNIQUE REFERENCES transactions (id),
+    amount_cents BIGINT NOT NULL CHECK (amount_cents > 0),
+    transaction_id INTEGER NOT NULL REFERENCES transactions (id),
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT current_timestamp,
+    UNIQUE (account_id, kind, period_start),
+    CHECK ((period_start IS NULL) <> (trigger_transaction_id IS NULL))
+);
+
+CREATE INDEX fee_charges_customer_id_idx ON fee_charges (customer_id);
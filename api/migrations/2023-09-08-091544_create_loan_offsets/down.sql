@@ -0,0 +1,7 @@
+DROP TABLE loan_interest_charges;
+DROP TABLE loan_offsets;
+DROP TABLE loans;
+
+-- postgres can't drop enum values, loan accounts are left closed as transaction accounts
+UPDATE accounts SET account_type = 'transaction', account_status = 'inactive'
+    WHERE account_type = 'loan';
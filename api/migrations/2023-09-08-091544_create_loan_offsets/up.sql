@@ -0,0 +1,39 @@
+ALTER TYPE account_type ADD VALUE IF NOT EXISTS 'loan';
+
+-- a loan account's balance is what's owed, kept negative like an overdrawn account
+CREATE TABLE loans (
+    id SERIAL PRIMARY KEY,
+    account_id INTEGER NOT NULL UNIQUE REFERENCES accounts (id) ON DELETE CASCADE,
+    customer_id INTEGER NOT NULL,
+    interest_rate_bps INTEGER NOT NULL CHECK (interest_rate_bps > 0),
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT current_timestamp
+);
+
+CREATE INDEX loans_customer_id_idx ON loans (customer_id);
+
+-- an account offsets at most one loan
+CREATE TABLE loan_offsets (
+    id SERIAL PRIMARY KEY,
+    loan_id INTEGER NOT NULL REFERENCES loans (id) ON DELETE CASCADE,
+    account_id INTEGER NOT NULL UNIQUE REFERENCES accounts (id) ON DELETE CASCADE,
+    customer_id INTEGER NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT current_timestamp
+);
+
+CREATE INDEX loan_offsets_loan_id_idx ON loan_offsets (loan_id);
+
+-- a day's interest and what it was worked out on, also what stops a day being charged twice
+CREATE TABLE loan_interest_charges (
+    id SERIAL PRIMARY KEY,
+    loan_id INTEGER NOT NULL REFERENCES loans (id) ON DELETE CASCADE,
+    customer_id INTEGER NOT NULL,
+    charge_date DATE NOT NULL,
+    owed_cents BIGINT NOT NULL,
+    offset_cents BIGINT NOT NULL,
+    amount_cents BIGINT NOT NULL CHECK (amount_cents > 0),
+    transaction_id INTEGER NOT NULL REFERENCES transactions (id),
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT current_timestamp,
+    UNIQUE (loan_id, charge_date)
+);
+
+CREATE INDEX loan_interest_charges_customer_id_idx ON loan_interest_charges (customer_id);
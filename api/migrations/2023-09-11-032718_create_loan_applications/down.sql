@@ -0,0 +1,12 @@
+DROP TABLE loan_schedule_entries;
+DROP TABLE loan_applications;
+
+ALTER TABLE loans
+    DROP COLUMN principal_cents,
+    DROP COLUMN term_months,
+    DROP COLUMN repayment_type,
+    DROP COLUMN first_repayment_date,
+    DROP COLUMN repayment_account_id;
+
+DROP TYPE loan_application_status;
+DROP TYPE loan_repayment_type;
@@ -0,0 +1,57 @@
+DO $$ BEGIN
+    CREATE TYPE loan_repayment_type AS ENUM ('principal_and_interest', 'interest_only');
+EXCEPTION
+    WHEN duplicate_object THEN null;
+END $$;
+
+DO $$ BEGIN
+    CREATE TYPE loan_application_status AS ENUM ('pending', 'approved', 'declined');
+EXCEPTION
+    WHEN duplicate_object THEN null;
+END $$;
+
+-- a loan the bank wrote has its terms, loans opened already owing (moved over from another bank)
+-- have none and no schedule
+ALTER TABLE loans
+    ADD COLUMN principal_cents BIGINT CHECK (principal_cents > 0),
+    ADD COLUMN term_months INTEGER CHECK (term_months > 0),
+    ADD COLUMN repayment_type loan_repayment_type,
+    ADD COLUMN first_repayment_date DATE,
+    ADD COLUMN repayment_account_id INTEGER REFERENCES accounts (id) ON DELETE SET NULL;
+
+CREATE TABLE loan_applications (
+    id SERIAL PRIMARY KEY,
+    customer_id INTEGER NOT NULL,
+    principal_cents BIGINT NOT NULL CHECK (principal_cents > 0),
+    term_months INTEGER NOT NULL CHECK (term_months > 0),
+    repayment_type loan_repayment_type NOT NULL,
+    disbursement_account_id INTEGER NOT NULL REFERENCES accounts (id) ON DELETE CASCADE,
+    account_name VARCHAR(40),
+    application_status loan_application_status NOT NULL DEFAULT 'pending',
+    interest_rate_bps INTEGER CHECK (interest_rate_bps > 0),
+    loan_id INTEGER REFERENCES loans (id) ON DELETE SET NULL,
+    reviewer_id INTEGER,
+    review_note VARCHAR(500),
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT current_timestamp,
+    reviewed_at TIMESTAMP WITH TIME ZONE
+);
+
+CREATE INDEX loan_applications_customer_id_idx ON loan_applications (customer_id);
+CREATE INDEX loan_applications_status_idx ON loan_applications (application_status);
+
+-- one row per installment. installments not yet due are rewritten when the loan is paid down
+-- early, paid_cents is what's come in against it so far
+CREATE TABLE loan_schedule_entries (
+    id SERIAL PRIMARY KEY,
+    loan_id INTEGER NOT NULL REFERENCES loans (id) ON DELETE CASCADE,
+    installment INTEGER NOT NULL CHECK (installment > 0),
+    due_date DATE NOT NULL,
+    amount_cents BIGINT NOT NULL CHECK (amount_cents >= 0),
+    principal_cents BIGINT NOT NULL,
+    interest_cents BIGINT NOT NULL,
+    paid_cents BIGINT NOT NULL DEFAULT 0,
+    CONSTRAINT paid_valid CHECK (paid_cents >= 0 AND paid_cents <= amount_cents),
+    UNIQUE (loan_id, installment)
+);
+
+CREATE INDEX loan_schedule_entries_due_date_idx ON loan_schedule_entries (due_date);
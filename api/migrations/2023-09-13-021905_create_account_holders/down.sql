@@ -0,0 +1,9 @@
+DROP TABLE transfer_signatures;
+DROP TABLE account_holders;
+ALTER TABLE accounts DROP COLUMN signing_rule;
+DROP TYPE signing_rule;
+DROP TYPE holder_role;
+
+-- postgres can't drop enum values, anything still waiting on a signature is abandoned
+UPDATE transactions SET transaction_status = 'error', date_end = current_timestamp
+WHERE transaction_status = 'awaiting_signature';
@@ -0,0 +1,41 @@
+ALTER TYPE transaction_status ADD VALUE IF NOT EXISTS 'awaiting_signature';
+
+DO $$ BEGIN
+    CREATE TYPE holder_role AS ENUM ('owner', 'signatory');
+EXCEPTION
+    WHEN duplicate_object THEN null;
+END $$;
+
+DO $$ BEGIN
+    CREATE TYPE signing_rule AS ENUM ('either_to_sign', 'both_to_sign');
+EXCEPTION
+    WHEN duplicate_object THEN null;
+END $$;
+
+-- how many of the holders have to sign a transfer before it goes
+ALTER TABLE accounts ADD COLUMN signing_rule signing_rule NOT NULL DEFAULT 'either_to_sign';
+
+-- everyone with a say on the account. accounts.customer_id stays as the primary holder, whose
+-- ledger the account's transactions are kept against
+CREATE TABLE account_holders (
+    id SERIAL PRIMARY KEY,
+    account_id INTEGER NOT NULL REFERENCES accounts (id) ON DELETE CASCADE,
+    customer_id INTEGER NOT NULL,
+    holder_role holder_role NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT current_timestamp,
+    UNIQUE (account_id, customer_id)
+);
+
+CREATE INDEX account_holders_customer_id_idx ON account_holders (customer_id);
+
+INSERT INTO account_holders (account_id, customer_id, holder_role)
+SELECT id, customer_id, 'owner' FROM accounts;
+
+-- who has signed a transfer from a both to sign account
+CREATE TABLE transfer_signatures (
+    id SERIAL PRIMARY KEY,
+    transaction_id INTEGER NOT NULL REFERENCES transactions (id) ON DELETE CASCADE,
+    customer_id INTEGER NOT NULL,
+    signed_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT current_timestamp,
+    UNIQUE (transaction_id, customer_id)
+);
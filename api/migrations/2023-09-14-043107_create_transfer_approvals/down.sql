@@ -0,0 +1,8 @@
+DROP TABLE transfer_approval_events;
+DROP TABLE transfer_approvals;
+DROP TABLE approval_policies;
+DROP TYPE approval_status;
+
+-- postgres can't drop enum values, anything still waiting on approval is abandoned
+UPDATE transactions SET transaction_status = 'error', date_end = current_timestamp
+WHERE transaction_status = 'pending_approval';
@@ -0,0 +1,47 @@
+ALTER TYPE transaction_status ADD VALUE IF NOT EXISTS 'pending_approval';
+
+DO $$ BEGIN
+    CREATE TYPE approval_status AS ENUM ('pending', 'approved', 'rejected', 'expired');
+EXCEPTION
+    WHEN duplicate_object THEN null;
+END $$;
+
+-- transfers from the account above threshold_cents wait for a second holder to approve them
+CREATE TABLE approval_policies (
+    account_id INTEGER PRIMARY KEY REFERENCES accounts (id) ON DELETE CASCADE,
+    threshold_cents BIGINT NOT NULL CHECK (threshold_cents > 0),
+    expiry_hours INTEGER NOT NULL CHECK (expiry_hours BETWEEN 1 AND 720),
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT current_timestamp
+);
+
+-- one per transfer that a policy caught
+CREATE TABLE transfer_approvals (
+    id SERIAL PRIMARY KEY,
+    transaction_id INTEGER NOT NULL UNIQUE REFERENCES transactions (id) ON DELETE CASCADE,
+    account_id INTEGER NOT NULL REFERENCES accounts (id) ON DELETE CASCADE,
+    requested_by INTEGER NOT NULL,
+    -- the policy's threshold when the transfer was caught
+    threshold_cents BIGINT NOT NULL,
+    approval_status approval_status NOT NULL DEFAULT 'pending',
+    expires_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    decided_by INTEGER,
+    decided_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT current_timestamp
+);
+
+CREATE INDEX transfer_approvals_account_idx ON transfer_approvals (account_id);
+CREATE INDEX transfer_approvals_pending_expiry_idx ON transfer_approvals (expires_at)
+WHERE approval_status = 'pending';
+
+-- the approval's trail, starting with the request. a pending event is the transfer being caught
+CREATE TABLE transfer_approval_events (
+    id SERIAL PRIMARY KEY,
+    approval_id INTEGER NOT NULL REFERENCES transfer_approvals (id) ON DELETE CASCADE,
+    approval_status approval_status NOT NULL,
+    note VARCHAR(500),
+    actor_type actor_type NOT NULL,
+    actor_id INTEGER NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT current_timestamp
+);
+
+CREATE INDEX transfer_approval_events_approval_idx ON transfer_approval_events (approval_id);
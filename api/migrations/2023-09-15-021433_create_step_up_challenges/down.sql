@@ -0,0 +1,4 @@
+DROP TABLE step_up_challenges;
+DROP TABLE totp_enrolments;
+DROP TYPE otp_method;
+DROP TYPE step_up_action;
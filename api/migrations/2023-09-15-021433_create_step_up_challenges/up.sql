@@ -0,0 +1,41 @@
+DO $$ BEGIN
+    CREATE TYPE step_up_action AS ENUM ('new_payee_transfer', 'close_account', 'remove_totp');
+EXCEPTION
+    WHEN duplicate_object THEN null;
+END $$;
+
+DO $$ BEGIN
+    CREATE TYPE otp_method AS ENUM ('totp', 'delivered');
+EXCEPTION
+    WHEN duplicate_object THEN null;
+END $$;
+
+-- a customer's authenticator app, unconfirmed until they've entered a code from it. the secret
+-- has to be kept as is to check codes against
+CREATE TABLE totp_enrolments (
+    customer_id INTEGER PRIMARY KEY,
+    secret VARCHAR(32) NOT NULL,
+    confirmed_at TIMESTAMP WITH TIME ZONE,
+    -- time step of the last code accepted, a code can't be used twice
+    last_used_step BIGINT,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT current_timestamp
+);
+
+-- one per attempt at a high-risk action, redeemed by retrying the action once it's verified
+CREATE TABLE step_up_challenges (
+    id SERIAL PRIMARY KEY,
+    customer_id INTEGER NOT NULL,
+    step_up_action step_up_action NOT NULL,
+    -- the payee, account or customer the action is on
+    entity_id INTEGER NOT NULL,
+    otp_method otp_method NOT NULL,
+    -- sha256 of a delivered code, nothing to keep for totp
+    code_hash VARCHAR(64),
+    attempts INTEGER NOT NULL DEFAULT 0,
+    expires_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    verified_at TIMESTAMP WITH TIME ZONE,
+    redeemed_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT current_timestamp
+);
+
+CREATE INDEX step_up_challenges_customer_idx ON step_up_challenges (customer_id);
@@ -0,0 +1,5 @@
+DROP TABLE oauth_tokens;
+DROP TABLE oauth_authorisation_codes;
+DROP TABLE oauth_grants;
+DROP TABLE oauth_clients;
+DROP TYPE oauth_token_kind;
@@ -0,0 +1,62 @@
+DO $$ BEGIN
+    CREATE TYPE oauth_token_kind AS ENUM ('access', 'refresh');
+EXCEPTION
+    WHEN duplicate_object THEN null;
+END $$;
+
+-- third-party apps, registered by staff. public clients (no secret) still have to use pkce like
+-- everyone else
+CREATE TABLE oauth_clients (
+    id SERIAL PRIMARY KEY,
+    client_id VARCHAR(32) NOT NULL UNIQUE,
+    client_secret_hash VARCHAR(64),
+    client_name VARCHAR(100) NOT NULL,
+    redirect_uri VARCHAR(500) NOT NULL,
+    -- the most the app can ask a customer for, space separated
+    scope VARCHAR(200) NOT NULL,
+    created_by INTEGER NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT current_timestamp,
+    revoked_at TIMESTAMP WITH TIME ZONE
+);
+
+-- a customer letting an app in. authorising the same app again replaces the scope
+CREATE TABLE oauth_grants (
+    id SERIAL PRIMARY KEY,
+    client_id INTEGER NOT NULL REFERENCES oauth_clients (id) ON DELETE CASCADE,
+    customer_id INTEGER NOT NULL,
+    scope VARCHAR(200) NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT current_timestamp,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT current_timestamp,
+    revoked_at TIMESTAMP WITH TIME ZONE
+);
+
+CREATE UNIQUE INDEX oauth_grants_active_idx ON oauth_grants (client_id, customer_id)
+WHERE revoked_at IS NULL;
+
+-- codes and tokens are only kept hashed
+CREATE TABLE oauth_authorisation_codes (
+    id SERIAL PRIMARY KEY,
+    grant_id INTEGER NOT NULL REFERENCES oauth_grants (id) ON DELETE CASCADE,
+    code_hash VARCHAR(64) NOT NULL UNIQUE,
+    redirect_uri VARCHAR(500) NOT NULL,
+    scope VARCHAR(200) NOT NULL,
+    -- base64url sha256 of the app's code verifier
+    code_challenge VARCHAR(128) NOT NULL,
+    expires_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    used_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT current_timestamp
+);
+
+CREATE TABLE oauth_tokens (
+    id SERIAL PRIMARY KEY,
+    grant_id INTEGER NOT NULL REFERENCES oauth_grants (id) ON DELETE CASCADE,
+    customer_id INTEGER NOT NULL,
+    token_kind oauth_token_kind NOT NULL,
+    token_hash VARCHAR(64) NOT NULL UNIQUE,
+    scope VARCHAR(200) NOT NULL,
+    expires_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    revoked_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT current_timestamp
+);
+
+CREATE INDEX oauth_tokens_grant_idx ON oauth_tokens (grant_id);
@@ -0,0 +1,3 @@
+DROP TABLE oauth_grant_accounts;
+
+ALTER TABLE oauth_grants DROP COLUMN expires_at;
@@ -0,0 +1,20 @@
+-- a grant is what the customer consented to. how long they agreed to share for, tokens never
+-- outlive it. grants from before this get the longest a consent can run
+ALTER TABLE oauth_grants
+ADD COLUMN expires_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT (current_timestamp + INTERVAL '365 days');
+
+ALTER TABLE oauth_grants ALTER COLUMN expires_at DROP DEFAULT;
+
+-- the accounts a grant shares, the app sees none of the customer's others
+CREATE TABLE oauth_grant_accounts (
+    grant_id INTEGER NOT NULL REFERENCES oauth_grants (id) ON DELETE CASCADE,
+    account_id INTEGER NOT NULL REFERENCES accounts (id) ON DELETE CASCADE,
+    PRIMARY KEY (grant_id, account_id)
+);
+
+-- grants from before this shared everything the customer held
+INSERT INTO oauth_grant_accounts (grant_id, account_id)
+SELECT oauth_grants.id, account_holders.account_id
+FROM oauth_grants
+INNER JOIN account_holders ON account_holders.customer_id = oauth_grants.customer_id
+WHERE oauth_grants.revoked_at IS NULL;
@@ -0,0 +1,18 @@
+-- events about account and transaction changes, written in the same db transaction as the change
+-- so neither can be committed without the other. the relay publishes them once they're in and
+-- keeps trying until they're taken, so consumers see each at least once and dedupe on event_id
+CREATE TABLE outbox_events (
+    id SERIAL PRIMARY KEY,
+    event_id VARCHAR(36) NOT NULL UNIQUE,
+    event_type VARCHAR(50) NOT NULL,
+    payload TEXT NOT NULL,
+    attempts INTEGER NOT NULL DEFAULT 0,
+    last_error VARCHAR(200),
+    -- also how long the relay has it to itself while it's being published
+    next_attempt_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT current_timestamp,
+    published_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT current_timestamp
+);
+
+CREATE INDEX outbox_events_unpublished_idx ON outbox_events (next_attempt_at)
+WHERE published_at IS NULL;
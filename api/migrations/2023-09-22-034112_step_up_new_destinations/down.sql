@@ -0,0 +1,2 @@
+-- postgres can't drop enum values, challenges for one can't be redeemed once the code's rolled back
+DELETE FROM step_up_challenges WHERE step_up_action = 'new_destination_transfer';
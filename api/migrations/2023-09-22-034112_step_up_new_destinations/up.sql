@@ -0,0 +1,2 @@
+-- a transfer to account details the customer has never paid, payee or not
+ALTER TYPE step_up_action ADD VALUE IF NOT EXISTS 'new_destination_transfer';
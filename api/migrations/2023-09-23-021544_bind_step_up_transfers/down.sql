@@ -0,0 +1,4 @@
+ALTER TABLE step_up_challenges
+    DROP COLUMN to_number,
+    DROP COLUMN to_bsb,
+    DROP COLUMN amount_cents;
@@ -0,0 +1,6 @@
+-- a transfer's challenge is for paying that much to that account, a code verified for one payment
+-- can't be spent on another
+ALTER TABLE step_up_challenges
+    ADD COLUMN to_number VARCHAR(9),
+    ADD COLUMN to_bsb VARCHAR(6),
+    ADD COLUMN amount_cents BIGINT;
@@ -0,0 +1 @@
+ALTER TABLE loan_schedule_entries DROP COLUMN dishonoured_at;
@@ -0,0 +1,3 @@
+-- when the bank first failed to collect the installment, it's charged a dishonour fee once for it
+-- rather than on every run it stays unpaid
+ALTER TABLE loan_schedule_entries ADD COLUMN dishonoured_at TIMESTAMPTZ;
@@ -0,0 +1 @@
+ALTER TABLE accounts DROP CONSTRAINT account_number_not_bank_charges;
@@ -0,0 +1,4 @@
+-- the bank's charges account that fees and interest are paid to. it's never a customer's, so no
+-- account can be opened with its number
+ALTER TABLE accounts
+    ADD CONSTRAINT account_number_not_bank_charges CHECK (account_number <> '000000000');
@@ -1,22 +1,56 @@
 use actix_web::http::header::ContentType;
 use actix_web::web::{Data, Path, Query};
-use actix_web::{web, HttpResponse};
+use actix_web::{web, HttpRequest, HttpResponse};
 
-use super::models::{AccountRest, AccountsRest, FindAccountQueryRest, NewAccountRest};
+use super::models::{
+    AccountHolderRest, AccountHoldersRest, AccountRest, AccountsRest, BalanceBackfillRest,
+    BalanceGranularityRest, BalanceHistoryQueryRest, BalanceHistoryRest, BucketMoveRest,
+    BucketRest, BucketUpdateRest, BucketsRest, FindAccountQueryRest, NewAccountHolderRest,
+    NewAccountRest, NewBucketRest, SigningRuleUpdateRest,
+};
 
-use crate::api::accounts::util::get_random_account_number;
+use crate::api::accounts::util::{authorise_holder, get_random_account_number};
+use crate::api::audit::util::{new_audit_entry, record_audit, snapshot};
 use crate::api::error::ApiError;
+use crate::api::oauth::util::{require_consented_account, require_scope};
+use crate::api::step_up::util::require_step_up;
 use crate::error::RepoError;
-use crate::models::account::{Account, FindAccountQuery, NewAccount};
-use crate::traits::{RepoCreate, RepoDeleteById, RepoFind, RepoGetById};
-
-pub async fn create_account<AR>(
+use crate::models::account::{
+    Account, AccountStatus, AccountType, FindAccountQuery, NewAccount, SigningRuleUpdate,
+};
+use crate::models::account_holder::{
+    AccountHolder, AccountPermission, FindAccountHolderQuery, NewAccountHolder,
+};
+use crate::models::audit::{ActorType, AuditAction, AuditEntity, AuditEntry, NewAuditEntry};
+use crate::models::balance_snapshot::{
+    balance_series, BalanceBackfill, BalanceBackfillSummary, BalanceSnapshot,
+    FindBalanceSnapshotQuery,
+};
+use crate::models::bucket::{
+    AccountBucket, BucketMove, BucketUpdate, FindBucketQuery, NewAccountBucket, DEFAULT_BUCKET_NAME,
+};
+use crate::models::consent::Consent;
+use crate::models::oauth::{FindOAuthTokenQuery, OAuthScope, OAuthToken};
+use crate::models::step_up::{
+    NewStepUpChallenge, StepUpAction, StepUpChallenge, StepUpRedemption, TotpEnrolment,
+};
+use crate::step_up::CodeSender;
+use crate::traits::{RepoCreate, RepoDeleteById, RepoFind, RepoGetById, RepoUpdate};
+
+const DEFAULT_BALANCE_HISTORY_DAYS: i64 = 30;
+// ten years of daily points is already more than any chart needs
+const MAX_BALANCE_HISTORY_DAYS: i64 = 3660;
+
+pub async fn create_account<AR, AuR>(
     accounts_repo: Data<AR>,
+    audit_repo: Data<AuR>,
+    req: HttpRequest,
     path: Path<i32>,
     payload: web::Json<NewAccountRest>,
 ) -> Result<HttpResponse, actix_web::Error>
 where
     AR: RepoCreate<Account, NewAccount>,
+    AuR: RepoCreate<AuditEntry, NewAuditEntry>,
 {
     let customer_id = path.into_inner();
 
@@ -26,6 +60,11 @@ where
         return Err(ApiError::Unauthorized.into());
     }
 
+    // loans are opened by staff, they come with terms
+    if new_account.account_type == AccountType::Loan {
+        return Err(ApiError::BadRequest.into());
+    }
+
     // completely random for now
     let account_number = get_random_account_number();
     new_account.account_number = account_number;
@@ -40,21 +79,41 @@ where
         .map_err(|_| ApiError::InternalError)?
         .map_err(|_| ApiError::InternalError)?;
 
+    let account_rest: AccountRest = (&account).into();
+
+    let mut audit_entry = new_audit_entry(
+        &req,
+        ActorType::Customer,
+        customer_id,
+        AuditAction::CreateAccount,
+        AuditEntity::Account,
+        Some(account.id),
+    );
+    audit_entry.after_snapshot = snapshot(&account_rest);
+    record_audit(audit_repo, audit_entry).await;
+
     Ok(HttpResponse::Created()
         .insert_header(ContentType::json())
-        .json(web::Json::<AccountRest>((&account).into())))
+        .json(web::Json(account_rest)))
 }
 
-pub async fn find_accounts<AR>(
+pub async fn find_accounts<AR, HR, OR>(
     accounts_repo: Data<AR>,
+    holders_repo: Data<HR>,
+    oauth_repo: Data<OR>,
+    req: HttpRequest,
     path: Path<i32>,
     query: Query<FindAccountQueryRest>,
 ) -> Result<HttpResponse, actix_web::Error>
 where
     AR: RepoFind<Account, FindAccountQuery>,
+    HR: RepoFind<AccountHolder, FindAccountHolderQuery>,
+    OR: RepoFind<OAuthToken, FindOAuthTokenQuery> + RepoGetById<Consent>,
 {
     let customer_id = path.into_inner();
 
+    let consent = require_scope(oauth_repo, &req, customer_id, OAuthScope::AccountsRead).await?;
+
     let query = FindAccountQuery {
         account_id: query.account_id,
         customer_id,
@@ -67,18 +126,35 @@ where
 
     println!("Trying to get accounts for customer {}", customer_id);
 
-    let accounts = web::block(move || accounts_repo.find(query))
-        .await
-        .map_err(|_| ApiError::InternalError)?
-        .map_err(|_| ApiError::InternalError)?;
+    let holder_query = FindAccountHolderQuery {
+        account_id: None,
+        customer_id: Some(customer_id),
+    };
+
+    let (mut accounts, holders) = web::block(move || {
+        let accounts = accounts_repo.find(query)?;
+        let holders = holders_repo.find(holder_query)?;
+        Ok((accounts, holders))
+    })
+    .await
+    .map_err(|_| ApiError::InternalError)?
+    .map_err(|_: RepoError| ApiError::InternalError)?;
 
-    // enforced query on customer_id so should always be fine, but safe > sorry
+    // enforced query on holders so should always be fine, but safe > sorry
     for acc in accounts.iter() {
-        if acc.customer_id != customer_id {
+        if !holders
+            .iter()
+            .any(|h| h.account_id == acc.id && h.customer_id == customer_id)
+        {
             return Err(ApiError::BadRequest.into());
         }
     }
 
+    // a third party only sees the accounts the customer shared with it
+    if let Some(consent) = &consent {
+        accounts.retain(|acc| consent.includes_account(acc.id));
+    }
+
     println!("Got accounts for customer {}", customer_id);
 
     Ok(HttpResponse::Ok()
@@ -86,43 +162,63 @@ where
         .json(web::Json::<AccountsRest>(accounts.into())))
 }
 
-pub async fn get_account<AR>(
+pub async fn get_account<AR, HR, OR>(
     accounts_repo: Data<AR>,
+    holders_repo: Data<HR>,
+    oauth_repo: Data<OR>,
+    req: HttpRequest,
     path: Path<(i32, i32)>,
 ) -> Result<HttpResponse, actix_web::Error>
 where
     AR: RepoGetById<Account>,
+    HR: RepoFind<AccountHolder, FindAccountHolderQuery>,
+    OR: RepoFind<OAuthToken, FindOAuthTokenQuery> + RepoGetById<Consent>,
 {
     let (customer_id, account_id) = path.into_inner();
 
+    let consent = require_scope(oauth_repo, &req, customer_id, OAuthScope::AccountsRead).await?;
+    require_consented_account(&consent, account_id)?;
+
     println!(
         "Trying to get account {}, for customer {}",
         account_id, customer_id
     );
 
-    let account = web::block(move || accounts_repo.get_by_id(account_id))
-        .await
-        .map_err(|_| ApiError::InternalError)?
-        .map_err(|err| match err {
-            RepoError::NotFound => ApiError::NotFound,
-            _ => ApiError::InternalError,
-        })?;
-
-    if account.customer_id != customer_id {
-        return Err(ApiError::Unauthorized.into());
-    }
+    let account = web::block(move || {
+        held_account(
+            accounts_repo.as_ref(),
+            holders_repo.as_ref(),
+            (customer_id, account_id),
+            AccountPermission::View,
+        )
+    })
+    .await
+    .map_err(|_| ApiError::InternalError)??;
 
     Ok(HttpResponse::Ok()
         .insert_header(ContentType::json())
         .json(web::Json::<AccountRest>((&account).into())))
 }
 
-pub async fn delete_account<AR>(
+// closing is stepped up, the request is answered with a challenge until it's retried with a
+// verified one
+pub async fn delete_account<AR, HR, SR, CS, AuR>(
     accounts_repo: Data<AR>,
+    holders_repo: Data<HR>,
+    step_up_repo: Data<SR>,
+    code_sender: Data<CS>,
+    audit_repo: Data<AuR>,
+    req: HttpRequest,
     path: Path<(i32, i32)>,
 ) -> Result<HttpResponse, actix_web::Error>
 where
     AR: RepoGetById<Account> + RepoDeleteById<Account>,
+    HR: RepoFind<AccountHolder, FindAccountHolderQuery>,
+    SR: RepoGetById<TotpEnrolment>
+        + RepoCreate<StepUpChallenge, NewStepUpChallenge>
+        + RepoUpdate<StepUpChallenge, StepUpRedemption>,
+    CS: CodeSender,
+    AuR: RepoCreate<AuditEntry, NewAuditEntry>,
 {
     let (customer_id, account_id) = path.into_inner();
 
@@ -131,212 +227,1207 @@ where
         account_id, customer_id
     );
 
-    web::block(move || {
-        let get_acc_res = accounts_repo.get_by_id(account_id);
-
-        let check_account_error: Option<ApiError> = match get_acc_res {
-            Ok(acc) => {
-                if acc.customer_id != customer_id {
-                    Some(ApiError::Unauthorized)
-                } else {
-                    None
-                }
-            }
-            Err(RepoError::NotFound) => None,
-            _ => Some(ApiError::InternalError),
-        };
-
-        if check_account_error.is_some() {
-            let cc = check_account_error.unwrap();
-            return Err(cc);
+    let lookup_repo = accounts_repo.clone();
+    let existing_account = web::block(move || match lookup_repo.get_by_id(account_id) {
+        Ok(acc) => {
+            authorise_holder(
+                holders_repo.as_ref(),
+                acc.id,
+                customer_id,
+                AccountPermission::Manage,
+            )?;
+            Ok(Some(acc))
         }
-
-        accounts_repo
-            .delete_by_id(account_id)
-            .map_err(|_| ApiError::InternalError)
+        Err(RepoError::NotFound) => Ok(None),
+        _ => Err(ApiError::InternalError),
     })
     .await
-    .map_err(|_| ApiError::InternalError)?
-    .map_err(|err| err)?;
+    .map_err(|_| ApiError::InternalError)??;
+
+    // closing something that isn't there or is already closed is a no-op, nothing to step up or
+    // audit
+    let Some(acc) = existing_account.filter(|acc| acc.account_status == AccountStatus::Active)
+    else {
+        return Ok(HttpResponse::NoContent().body(""));
+    };
+
+    // the borrower can't close the account that holds what they owe, and nobody can close one with
+    // money in it or owing on it
+    if acc.account_type == AccountType::Loan
+        || acc.balance_cents != 0
+        || acc.available_balance_cents != 0
+    {
+        return Err(ApiError::Conflict.into());
+    }
+
+    if let Some(challenge) = require_step_up(
+        step_up_repo,
+        code_sender,
+        &req,
+        customer_id,
+        StepUpAction::CloseAccount,
+        acc.id,
+    )
+    .await?
+    {
+        return Ok(challenge);
+    }
+
+    web::block(move || accounts_repo.delete_by_id(account_id))
+        .await
+        .map_err(|_| ApiError::InternalError)?
+        .map_err(|err| match err {
+            RepoError::Conflict => ApiError::Conflict,
+            _ => ApiError::InternalError,
+        })?;
+
+    let mut audit_entry = new_audit_entry(
+        &req,
+        ActorType::Customer,
+        customer_id,
+        AuditAction::DeleteAccount,
+        AuditEntity::Account,
+        Some(acc.id),
+    );
+    audit_entry.before_snapshot = snapshot(&AccountRest::from(&acc));
+    record_audit(audit_repo, audit_entry).await;
 
     Ok(HttpResponse::NoContent().body(""))
 }
 
-#[cfg(test)]
-mod tests {
-    use std::vec;
+pub async fn get_balance_history<AR, HR, BR, OR>(
+    accounts_repo: Data<AR>,
+    holders_repo: Data<HR>,
+    balance_repo: Data<BR>,
+    oauth_repo: Data<OR>,
+    req: HttpRequest,
+    path: Path<(i32, i32)>,
+    query: Query<BalanceHistoryQueryRest>,
+) -> Result<HttpResponse, actix_web::Error>
+where
+    AR: RepoGetById<Account>,
+    HR: RepoFind<AccountHolder, FindAccountHolderQuery>,
+    BR: RepoFind<BalanceSnapshot, FindBalanceSnapshotQuery>,
+    OR: RepoFind<OAuthToken, FindOAuthTokenQuery> + RepoGetById<Consent>,
+{
+    let (customer_id, account_id) = path.into_inner();
 
-    use crate::{
-        api::{
-            accounts::{
-                handlers::{create_account, delete_account, find_accounts, get_account},
-                models::{
-                    AccountRest, AccountStatusRest, AccountTypeRest, AccountsRest, NewAccountRest,
-                },
-            },
-            error::ApiError,
-        },
-        error::RepoError,
-        models::account::{Account, AccountStatus, AccountType, FindAccountQuery, NewAccount},
-        traits::{MockRepoCreate, MockRepoFind, MockRepoGetById, RepoDeleteById, RepoGetById},
+    let parse_date = |date: &Option<String>| -> Result<Option<chrono::NaiveDate>, ApiError> {
+        date.as_ref()
+            .map(|d| chrono::NaiveDate::parse_from_str(d, "%Y-%m-%d"))
+            .transpose()
+            .map_err(|_| ApiError::BadRequest)
     };
 
-    use actix_web::{
-        body::to_bytes,
-        http::StatusCode,
-        test,
-        web::{self, Data, Json},
-        App,
-    };
-    use chrono::{NaiveDate, NaiveDateTime};
-    use mockall::{mock, predicate::eq, Sequence};
+    let to = parse_date(&query.to)?.unwrap_or(chrono::Utc::now().date_naive());
+    let from = parse_date(&query.from)?
+        .unwrap_or(to - chrono::Duration::days(DEFAULT_BALANCE_HISTORY_DAYS));
+    let granularity = query.granularity.unwrap_or(BalanceGranularityRest::Daily);
 
-    #[actix_web::test]
-    async fn test_create_account_success() {
-        let customer_id = 1;
-        let account_id = 2;
+    if from > to || (to - from).num_days() > MAX_BALANCE_HISTORY_DAYS {
+        return Err(ApiError::BadRequest.into());
+    }
 
-        let dt: NaiveDateTime = NaiveDate::from_ymd_opt(2016, 7, 8)
-            .unwrap()
-            .and_hms_opt(9, 10, 11)
-            .unwrap();
+    let consent = require_scope(oauth_repo, &req, customer_id, OAuthScope::AccountsRead).await?;
+    require_consented_account(&consent, account_id)?;
 
-        let mut mock_accounts_repo = MockRepoCreate::<Account, NewAccount>::new();
-        mock_accounts_repo
-            .expect_create()
-            .withf(move |acc| {
-                acc.customer_id == customer_id
-                    && acc.balance_cents == 34343
-                    && acc.account_type == AccountType::Savings
-                    && acc.account_name == Some("abc".to_string())
-                    && acc.available_balance_cents == 3444
-            })
-            .times(1)
-            .returning(move |_| {
-                Ok(Account {
-                    id: account_id,
-                    customer_id,
-                    balance_cents: 13424234234,
-                    account_type: AccountType::Savings,
-                    date_opened: dt,
-                    account_status: AccountStatus::Active,
-                    account_name: Some("abc".to_string()),
-                    account_number: "012345678".to_string(),
-                    available_balance_cents: 3444,
-                    bsb: "123456".to_string(),
-                })
-            });
+    println!(
+        "Trying to get balance history of account {} for customer {}",
+        account_id, customer_id
+    );
 
-        let mut app = test::init_service(
-            App::new().app_data(Data::new(mock_accounts_repo)).service(
-                web::resource("/customers/{customer_id}/accounts")
-                    .route(web::post().to(create_account::<MockRepoCreate<Account, NewAccount>>)),
-            ),
+    web::block(move || {
+        held_account(
+            accounts_repo.as_ref(),
+            holders_repo.as_ref(),
+            (customer_id, account_id),
+            AccountPermission::View,
         )
-        .await;
+    })
+    .await
+    .map_err(|_| ApiError::InternalError)??;
 
-        let testpayload = NewAccountRest {
-            customer_id,
-            balance_cents: 34343,
-            available_balance_cents: 3444,
-            account_type: AccountTypeRest::Savings,
-            account_name: Some("abc".to_string()),
-        };
+    let snapshot_query = FindBalanceSnapshotQuery {
+        account_id,
+        from,
+        to,
+    };
 
-        let resp = test::TestRequest::post()
-            .uri("/customers/1/accounts")
-            .append_header(("Content-Type", "application/json"))
-            .set_payload(serde_json::to_string(&testpayload).unwrap())
-            .send_request(&mut app)
-            .await;
+    let snapshots = web::block(move || balance_repo.find(snapshot_query))
+        .await
+        .map_err(|_| ApiError::InternalError)?
+        .map_err(|_| ApiError::InternalError)?;
 
-        let actual_status = resp.status();
-        assert_eq!(StatusCode::CREATED, actual_status);
+    let points = balance_series(&snapshots, from, to, granularity.into());
 
-        let expected_account = AccountRest {
-            id: account_id,
-            customer_id,
-            balance_cents: 13424234234,
-            account_type: AccountTypeRest::Savings,
-            date_opened: dt.to_string(),
-            account_status: AccountStatusRest::Active,
-            account_name: Some("abc".to_string()),
-            account_number: "012345678".to_string(),
-            available_balance_cents: 3444,
-            bsb: "123456".to_string(),
-        };
+    Ok(HttpResponse::Ok()
+        .insert_header(ContentType::json())
+        .json(web::Json(BalanceHistoryRest {
+            account_id,
+            granularity,
+            points: points.iter().map(|p| p.into()).collect(),
+        })))
+}
 
-        let actual_account: AccountRest = test::read_body_json(resp).await;
-        assert_eq!(expected_account, actual_account)
-    }
+pub async fn backfill_balance_history<BR, AuR>(
+    balance_repo: Data<BR>,
+    audit_repo: Data<AuR>,
+    req: HttpRequest,
+    path: Path<(i32, i32)>,
+) -> Result<HttpResponse, actix_web::Error>
+where
+    BR: RepoCreate<BalanceBackfillSummary, BalanceBackfill>,
+    AuR: RepoCreate<AuditEntry, NewAuditEntry>,
+{
+    let (staff_id, account_id) = path.into_inner();
 
-    #[actix_web::test]
-    async fn test_create_account_internal_error() {
-        let customer_id = 5;
+    println!(
+        "Staff {} trying to backfill balance history of account {}",
+        staff_id, account_id
+    );
 
-        let new_account = NewAccount {
-            customer_id,
-            balance_cents: 13424234234,
-            account_type: AccountType::Savings,
-            account_name: Some("abc".to_string()),
-            available_balance_cents: 34343,
-            account_number: "".to_string(),
-        };
+    let summary = web::block(move || balance_repo.create(BalanceBackfill { account_id }))
+        .await
+        .map_err(|_| ApiError::InternalError)?
+        .map_err(|err| match err {
+            RepoError::NotFound => ApiError::NotFound,
+            _ => ApiError::InternalError,
+        })?;
 
-        let mut mock_accounts_repo = MockRepoCreate::<Account, NewAccount>::new();
-        mock_accounts_repo
-            .expect_create()
-            .with(eq(new_account))
-            .times(1)
-            .returning(move |_| Err(RepoError::Other));
+    let backfill_rest: BalanceBackfillRest = summary.into();
 
-        let res = create_account(
-            Data::new(mock_accounts_repo),
-            customer_id.into(),
-            Json(NewAccountRest {
-                customer_id,
-                balance_cents: 13424234234,
-                available_balance_cents: 3444,
-                account_type: AccountTypeRest::Savings,
-                account_name: Some("abc".to_string()),
-            }),
-        )
-        .await;
+    let mut audit_entry = new_audit_entry(
+        &req,
+        ActorType::Staff,
+        staff_id,
+        AuditAction::BackfillBalanceHistory,
+        AuditEntity::Account,
+        Some(account_id),
+    );
+    audit_entry.after_snapshot = snapshot(&backfill_rest);
+    record_audit(audit_repo, audit_entry).await;
 
-        assert!(res.is_err_and(|e| { e.to_string() == ApiError::InternalError.to_string() }));
+    Ok(HttpResponse::Ok()
+        .insert_header(ContentType::json())
+        .json(web::Json(backfill_rest)))
+}
+
+const MAX_BUCKET_NAME_LEN: usize = 40;
+
+fn valid_bucket_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.chars().count() <= MAX_BUCKET_NAME_LEN
+        && !name.eq_ignore_ascii_case(DEFAULT_BUCKET_NAME)
+}
+
+fn parse_target_date(date: &str) -> Result<chrono::NaiveDate, ApiError> {
+    chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d").map_err(|_| ApiError::BadRequest)
+}
+
+// the account, if the customer holds it and their role on it allows what they're doing
+fn held_account<AR, HR>(
+    accounts_repo: &AR,
+    holders_repo: &HR,
+    (customer_id, account_id): (i32, i32),
+    permission: AccountPermission,
+) -> Result<Account, ApiError>
+where
+    AR: RepoGetById<Account>,
+    HR: RepoFind<AccountHolder, FindAccountHolderQuery>,
+{
+    let account = accounts_repo
+        .get_by_id(account_id)
+        .map_err(|err| match err {
+            RepoError::NotFound => ApiError::NotFound,
+            _ => ApiError::InternalError,
+        })?;
+
+    authorise_holder(holders_repo, account.id, customer_id, permission)?;
+
+    Ok(account)
+}
+
+// buckets only make sense on a savings account the customer holds
+fn held_savings_account<AR, HR>(
+    accounts_repo: &AR,
+    holders_repo: &HR,
+    (customer_id, account_id): (i32, i32),
+    permission: AccountPermission,
+) -> Result<Account, ApiError>
+where
+    AR: RepoGetById<Account>,
+    HR: RepoFind<AccountHolder, FindAccountHolderQuery>,
+{
+    let account = held_account(
+        accounts_repo,
+        holders_repo,
+        (customer_id, account_id),
+        permission,
+    )?;
+
+    if account.account_type != AccountType::Savings {
+        return Err(ApiError::BadRequest);
     }
 
-    #[actix_web::test]
-    async fn test_find_accounts_by_customer_id_success() {
-        let customer_id = 1;
+    Ok(account)
+}
 
-        let expected_query = FindAccountQuery {
-            account_id: None,
-            customer_id,
-            account_number: None,
-        };
+fn bucket_of_account<BkR>(
+    buckets_repo: &BkR,
+    account_id: i32,
+    bucket_id: i32,
+) -> Result<AccountBucket, ApiError>
+where
+    BkR: RepoGetById<AccountBucket>,
+{
+    let bucket = buckets_repo.get_by_id(bucket_id).map_err(|err| match err {
+        RepoError::NotFound => ApiError::NotFound,
+        _ => ApiError::InternalError,
+    })?;
 
-        let mut mock_accounts_repo = MockRepoFind::<Account, FindAccountQuery>::new();
-        mock_accounts_repo
-            .expect_find()
-            .with(eq(expected_query))
-            .times(1)
-            .returning(move |_| {
-                Ok(vec![
-                    Account {
-                        id: 1,
-                        customer_id: 1,
-                        balance_cents: 13424234234,
-                        account_type: AccountType::Savings,
-                        date_opened: NaiveDate::from_ymd_opt(2016, 7, 8)
-                            .unwrap()
-                            .and_hms_opt(9, 10, 11)
-                            .unwrap(),
+    if bucket.account_id != account_id {
+        return Err(ApiError::NotFound);
+    }
+
+    Ok(bucket)
+}
+
+pub async fn find_buckets<AR, HR, BkR>(
+    accounts_repo: Data<AR>,
+    holders_repo: Data<HR>,
+    buckets_repo: Data<BkR>,
+    path: Path<(i32, i32)>,
+) -> Result<HttpResponse, actix_web::Error>
+where
+    AR: RepoGetById<Account>,
+    HR: RepoFind<AccountHolder, FindAccountHolderQuery>,
+    BkR: RepoFind<AccountBucket, FindBucketQuery>,
+{
+    let (customer_id, account_id) = path.into_inner();
+
+    println!(
+        "Trying to get buckets of account {} for customer {}",
+        account_id, customer_id
+    );
+
+    let buckets = web::block(move || {
+        held_savings_account(
+            accounts_repo.as_ref(),
+            holders_repo.as_ref(),
+            (customer_id, account_id),
+            AccountPermission::View,
+        )?;
+
+        buckets_repo
+            .find(FindBucketQuery { account_id })
+            .map_err(|_| ApiError::InternalError)
+    })
+    .await
+    .map_err(|_| ApiError::InternalError)??;
+
+    // enforced query on account_id so should always be fine, but safe > sorry
+    if buckets.iter().any(|b| b.account_id != account_id) {
+        return Err(ApiError::BadRequest.into());
+    }
+
+    Ok(HttpResponse::Ok()
+        .insert_header(ContentType::json())
+        .json(web::Json(BucketsRest {
+            buckets: buckets.iter().map(|b| b.into()).collect(),
+        })))
+}
+
+pub async fn create_bucket<AR, HR, BkR, AuR>(
+    accounts_repo: Data<AR>,
+    holders_repo: Data<HR>,
+    buckets_repo: Data<BkR>,
+    audit_repo: Data<AuR>,
+    req: HttpRequest,
+    path: Path<(i32, i32)>,
+    payload: web::Json<NewBucketRest>,
+) -> Result<HttpResponse, actix_web::Error>
+where
+    AR: RepoGetById<Account>,
+    HR: RepoFind<AccountHolder, FindAccountHolderQuery>,
+    BkR: RepoCreate<AccountBucket, NewAccountBucket>,
+    AuR: RepoCreate<AuditEntry, NewAuditEntry>,
+{
+    let (customer_id, account_id) = path.into_inner();
+    let payload = payload.into_inner();
+
+    let new_bucket = NewAccountBucket {
+        account_id,
+        name: payload.name.trim().to_string(),
+        balance_cents: payload.initial_amount_cents.unwrap_or(0),
+        goal_cents: payload.goal_cents,
+        target_date: payload
+            .target_date
+            .as_deref()
+            .map(parse_target_date)
+            .transpose()?,
+    };
+
+    if !valid_bucket_name(&new_bucket.name)
+        || new_bucket.balance_cents < 0
+        || new_bucket.goal_cents.is_some_and(|goal| goal <= 0)
+    {
+        return Err(ApiError::BadRequest.into());
+    }
+
+    println!(
+        "Trying to create bucket on account {} for customer {}",
+        account_id, customer_id
+    );
+
+    let bucket = web::block(move || {
+        held_savings_account(
+            accounts_repo.as_ref(),
+            holders_repo.as_ref(),
+            (customer_id, account_id),
+            AccountPermission::Manage,
+        )?;
+
+        buckets_repo.create(new_bucket).map_err(|err| match err {
+            // name taken or not enough unallocated
+            RepoError::Conflict => ApiError::Conflict,
+            RepoError::NotFound => ApiError::NotFound,
+            _ => ApiError::InternalError,
+        })
+    })
+    .await
+    .map_err(|_| ApiError::InternalError)??;
+
+    let bucket_rest: BucketRest = (&bucket).into();
+
+    let mut audit_entry = new_audit_entry(
+        &req,
+        ActorType::Customer,
+        customer_id,
+        AuditAction::CreateBucket,
+        AuditEntity::Bucket,
+        Some(bucket.id),
+    );
+    audit_entry.after_snapshot = snapshot(&bucket_rest);
+    record_audit(audit_repo, audit_entry).await;
+
+    Ok(HttpResponse::Created()
+        .insert_header(ContentType::json())
+        .json(web::Json(bucket_rest)))
+}
+
+pub async fn update_bucket<AR, HR, BkR, AuR>(
+    accounts_repo: Data<AR>,
+    holders_repo: Data<HR>,
+    buckets_repo: Data<BkR>,
+    audit_repo: Data<AuR>,
+    req: HttpRequest,
+    path: Path<(i32, i32, i32)>,
+    payload: web::Json<BucketUpdateRest>,
+) -> Result<HttpResponse, actix_web::Error>
+where
+    AR: RepoGetById<Account>,
+    HR: RepoFind<AccountHolder, FindAccountHolderQuery>,
+    BkR: RepoGetById<AccountBucket> + RepoUpdate<AccountBucket, BucketUpdate>,
+    AuR: RepoCreate<AuditEntry, NewAuditEntry>,
+{
+    let (customer_id, account_id, bucket_id) = path.into_inner();
+    let payload = payload.into_inner();
+
+    let update = BucketUpdate {
+        name: payload.name.map(|n| n.trim().to_string()),
+        goal_cents: payload.goal_cents.map(|g| Some(g).filter(|g| *g != 0)),
+        target_date: payload
+            .target_date
+            .map(|d| {
+                if d.is_empty() {
+                    Ok(None)
+                } else {
+                    parse_target_date(&d).map(Some)
+                }
+            })
+            .transpose()?,
+    };
+
+    if update
+        .name
+        .as_ref()
+        .is_some_and(|name| !valid_bucket_name(name))
+        || update.goal_cents.flatten().is_some_and(|goal| goal < 0)
+    {
+        return Err(ApiError::BadRequest.into());
+    }
+
+    println!(
+        "Trying to update bucket {} of account {} for customer {}",
+        bucket_id, account_id, customer_id
+    );
+
+    let (before, after) = web::block(move || {
+        held_savings_account(
+            accounts_repo.as_ref(),
+            holders_repo.as_ref(),
+            (customer_id, account_id),
+            AccountPermission::Manage,
+        )?;
+        let before = bucket_of_account(buckets_repo.as_ref(), account_id, bucket_id)?;
+
+        // the unallocated bucket keeps its name
+        if before.is_default && update.name.is_some() {
+            return Err(ApiError::BadRequest);
+        }
+
+        let after = buckets_repo
+            .update(bucket_id, update)
+            .map_err(|err| match err {
+                RepoError::Conflict => ApiError::Conflict,
+                _ => ApiError::InternalError,
+            })?;
+
+        Ok((before, after))
+    })
+    .await
+    .map_err(|_| ApiError::InternalError)??;
+
+    let after_rest: BucketRest = (&after).into();
+
+    let mut audit_entry = new_audit_entry(
+        &req,
+        ActorType::Customer,
+        customer_id,
+        AuditAction::UpdateBucket,
+        AuditEntity::Bucket,
+        Some(bucket_id),
+    );
+    audit_entry.before_snapshot = snapshot(&BucketRest::from(&before));
+    audit_entry.after_snapshot = snapshot(&after_rest);
+    record_audit(audit_repo, audit_entry).await;
+
+    Ok(HttpResponse::Ok()
+        .insert_header(ContentType::json())
+        .json(web::Json(after_rest)))
+}
+
+pub async fn delete_bucket<AR, HR, BkR, AuR>(
+    accounts_repo: Data<AR>,
+    holders_repo: Data<HR>,
+    buckets_repo: Data<BkR>,
+    audit_repo: Data<AuR>,
+    req: HttpRequest,
+    path: Path<(i32, i32, i32)>,
+) -> Result<HttpResponse, actix_web::Error>
+where
+    AR: RepoGetById<Account>,
+    HR: RepoFind<AccountHolder, FindAccountHolderQuery>,
+    BkR: RepoGetById<AccountBucket> + RepoDeleteById<AccountBucket>,
+    AuR: RepoCreate<AuditEntry, NewAuditEntry>,
+{
+    let (customer_id, account_id, bucket_id) = path.into_inner();
+
+    println!(
+        "Trying to delete bucket {} of account {} for customer {}",
+        bucket_id, account_id, customer_id
+    );
+
+    let deleted_bucket = web::block(move || {
+        held_savings_account(
+            accounts_repo.as_ref(),
+            holders_repo.as_ref(),
+            (customer_id, account_id),
+            AccountPermission::Manage,
+        )?;
+
+        let existing_bucket = match bucket_of_account(buckets_repo.as_ref(), account_id, bucket_id)
+        {
+            Ok(bucket) => {
+                if bucket.is_default {
+                    return Err(ApiError::BadRequest);
+                }
+                Some(bucket)
+            }
+            Err(ApiError::NotFound) => None,
+            Err(err) => return Err(err),
+        };
+
+        // balance goes back to the unallocated bucket
+        buckets_repo
+            .delete_by_id(bucket_id)
+            .map_err(|_| ApiError::InternalError)?;
+
+        Ok(existing_bucket)
+    })
+    .await
+    .map_err(|_| ApiError::InternalError)??;
+
+    // deleting something that isn't there is a no-op, nothing to audit
+    if let Some(bucket) = deleted_bucket {
+        let mut audit_entry = new_audit_entry(
+            &req,
+            ActorType::Customer,
+            customer_id,
+            AuditAction::DeleteBucket,
+            AuditEntity::Bucket,
+            Some(bucket.id),
+        );
+        audit_entry.before_snapshot = snapshot(&BucketRest::from(&bucket));
+        record_audit(audit_repo, audit_entry).await;
+    }
+
+    Ok(HttpResponse::NoContent().body(""))
+}
+
+pub async fn move_bucket_funds<AR, HR, BkR, AuR>(
+    accounts_repo: Data<AR>,
+    holders_repo: Data<HR>,
+    buckets_repo: Data<BkR>,
+    audit_repo: Data<AuR>,
+    req: HttpRequest,
+    path: Path<(i32, i32)>,
+    payload: web::Json<BucketMoveRest>,
+) -> Result<HttpResponse, actix_web::Error>
+where
+    AR: RepoGetById<Account>,
+    HR: RepoFind<AccountHolder, FindAccountHolderQuery>,
+    BkR: RepoCreate<Vec<AccountBucket>, BucketMove>,
+    AuR: RepoCreate<AuditEntry, NewAuditEntry>,
+{
+    let (customer_id, account_id) = path.into_inner();
+    let payload = payload.into_inner();
+
+    if payload.amount_cents <= 0 || payload.from_bucket_id == payload.to_bucket_id {
+        return Err(ApiError::BadRequest.into());
+    }
+
+    let bucket_move = BucketMove {
+        account_id,
+        from_bucket_id: payload.from_bucket_id,
+        to_bucket_id: payload.to_bucket_id,
+        amount_cents: payload.amount_cents,
+    };
+
+    println!(
+        "Trying to move {} cents between buckets of account {} for customer {}",
+        bucket_move.amount_cents, account_id, customer_id
+    );
+
+    let buckets = web::block(move || {
+        held_savings_account(
+            accounts_repo.as_ref(),
+            holders_repo.as_ref(),
+            (customer_id, account_id),
+            AccountPermission::Transact,
+        )?;
+
+        buckets_repo.create(bucket_move).map_err(|err| match err {
+            RepoError::NotFound => ApiError::NotFound,
+            // not enough in the from bucket
+            RepoError::Conflict => ApiError::Conflict,
+            _ => ApiError::InternalError,
+        })
+    })
+    .await
+    .map_err(|_| ApiError::InternalError)??;
+
+    let buckets_rest = BucketsRest {
+        buckets: buckets.iter().map(|b| b.into()).collect(),
+    };
+
+    let mut audit_entry = new_audit_entry(
+        &req,
+        ActorType::Customer,
+        customer_id,
+        AuditAction::MoveBucketFunds,
+        AuditEntity::Bucket,
+        Some(payload.from_bucket_id),
+    );
+    audit_entry.after_snapshot = snapshot(&buckets_rest);
+    record_audit(audit_repo, audit_entry).await;
+
+    Ok(HttpResponse::Ok()
+        .insert_header(ContentType::json())
+        .json(web::Json(buckets_rest)))
+}
+
+pub async fn find_account_holders<HR>(
+    holders_repo: Data<HR>,
+    path: Path<(i32, i32)>,
+) -> Result<HttpResponse, actix_web::Error>
+where
+    HR: RepoFind<AccountHolder, FindAccountHolderQuery>,
+{
+    let (customer_id, account_id) = path.into_inner();
+
+    println!(
+        "Trying to get holders of account {} for customer {}",
+        account_id, customer_id
+    );
+
+    let holders = web::block(move || {
+        authorise_holder(
+            holders_repo.as_ref(),
+            account_id,
+            customer_id,
+            AccountPermission::View,
+        )?;
+
+        holders_repo
+            .find(FindAccountHolderQuery {
+                account_id: Some(account_id),
+                customer_id: None,
+            })
+            .map_err(|_| ApiError::InternalError)
+    })
+    .await
+    .map_err(|_| ApiError::InternalError)??;
+
+    // enforced query on account_id so should always be fine, but safe > sorry
+    if holders.iter().any(|h| h.account_id != account_id) {
+        return Err(ApiError::BadRequest.into());
+    }
+
+    Ok(HttpResponse::Ok()
+        .insert_header(ContentType::json())
+        .json(web::Json::<AccountHoldersRest>(holders.into())))
+}
+
+pub async fn add_account_holder<HR, AuR>(
+    holders_repo: Data<HR>,
+    audit_repo: Data<AuR>,
+    req: HttpRequest,
+    path: Path<(i32, i32)>,
+    payload: web::Json<NewAccountHolderRest>,
+) -> Result<HttpResponse, actix_web::Error>
+where
+    HR: RepoFind<AccountHolder, FindAccountHolderQuery>
+        + RepoCreate<AccountHolder, NewAccountHolder>,
+    AuR: RepoCreate<AuditEntry, NewAuditEntry>,
+{
+    let (customer_id, account_id) = path.into_inner();
+    let payload = payload.into_inner();
+
+    let new_holder = NewAccountHolder {
+        account_id,
+        customer_id: payload.customer_id,
+        holder_role: payload.role.into(),
+    };
+
+    println!(
+        "Trying to add customer {} to account {} for customer {}",
+        new_holder.customer_id, account_id, customer_id
+    );
+
+    let holder = web::block(move || {
+        authorise_holder(
+            holders_repo.as_ref(),
+            account_id,
+            customer_id,
+            AccountPermission::Manage,
+        )?;
+
+        holders_repo.create(new_holder).map_err(|err| match err {
+            // already holds it
+            RepoError::Conflict => ApiError::Conflict,
+            _ => ApiError::InternalError,
+        })
+    })
+    .await
+    .map_err(|_| ApiError::InternalError)??;
+
+    let holder_rest: AccountHolderRest = (&holder).into();
+
+    let mut audit_entry = new_audit_entry(
+        &req,
+        ActorType::Customer,
+        customer_id,
+        AuditAction::AddAccountHolder,
+        AuditEntity::AccountHolder,
+        Some(holder.id),
+    );
+    audit_entry.after_snapshot = snapshot(&holder_rest);
+    record_audit(audit_repo, audit_entry).await;
+
+    Ok(HttpResponse::Created()
+        .insert_header(ContentType::json())
+        .json(web::Json(holder_rest)))
+}
+
+pub async fn remove_account_holder<HR, AuR>(
+    holders_repo: Data<HR>,
+    audit_repo: Data<AuR>,
+    req: HttpRequest,
+    path: Path<(i32, i32, i32)>,
+) -> Result<HttpResponse, actix_web::Error>
+where
+    HR: RepoFind<AccountHolder, FindAccountHolderQuery>
+        + RepoGetById<AccountHolder>
+        + RepoDeleteById<AccountHolder>,
+    AuR: RepoCreate<AuditEntry, NewAuditEntry>,
+{
+    let (customer_id, account_id, holder_id) = path.into_inner();
+
+    println!(
+        "Trying to remove holder {} of account {} for customer {}",
+        holder_id, account_id, customer_id
+    );
+
+    let removed_holder = web::block(move || {
+        authorise_holder(
+            holders_repo.as_ref(),
+            account_id,
+            customer_id,
+            AccountPermission::Manage,
+        )?;
+
+        let existing_holder = match holders_repo.get_by_id(holder_id) {
+            Ok(holder) if holder.account_id == account_id => Some(holder),
+            Ok(_) | Err(RepoError::NotFound) => None,
+            _ => return Err(ApiError::InternalError),
+        };
+
+        if existing_holder.is_some() {
+            holders_repo
+                .delete_by_id(holder_id)
+                .map_err(|err| match err {
+                    // primary holder, or a both to sign account would be left short
+                    RepoError::Conflict => ApiError::Conflict,
+                    _ => ApiError::InternalError,
+                })?;
+        }
+
+        Ok(existing_holder)
+    })
+    .await
+    .map_err(|_| ApiError::InternalError)??;
+
+    // removing someone who isn't there is a no-op, nothing to audit
+    if let Some(holder) = removed_holder {
+        let mut audit_entry = new_audit_entry(
+            &req,
+            ActorType::Customer,
+            customer_id,
+            AuditAction::RemoveAccountHolder,
+            AuditEntity::AccountHolder,
+            Some(holder.id),
+        );
+        audit_entry.before_snapshot = snapshot(&AccountHolderRest::from(&holder));
+        record_audit(audit_repo, audit_entry).await;
+    }
+
+    Ok(HttpResponse::NoContent().body(""))
+}
+
+pub async fn update_signing_rule<AR, HR, AuR>(
+    accounts_repo: Data<AR>,
+    holders_repo: Data<HR>,
+    audit_repo: Data<AuR>,
+    req: HttpRequest,
+    path: Path<(i32, i32)>,
+    payload: web::Json<SigningRuleUpdateRest>,
+) -> Result<HttpResponse, actix_web::Error>
+where
+    AR: RepoGetById<Account> + RepoUpdate<Account, SigningRuleUpdate>,
+    HR: RepoFind<AccountHolder, FindAccountHolderQuery>,
+    AuR: RepoCreate<AuditEntry, NewAuditEntry>,
+{
+    let (customer_id, account_id) = path.into_inner();
+
+    let update = SigningRuleUpdate {
+        signing_rule: payload.into_inner().signing_rule.into(),
+    };
+
+    println!(
+        "Trying to set signing rule of account {} to {:?} for customer {}",
+        account_id, update.signing_rule, customer_id
+    );
+
+    let (before, after) = web::block(move || {
+        let before = held_account(
+            accounts_repo.as_ref(),
+            holders_repo.as_ref(),
+            (customer_id, account_id),
+            AccountPermission::Manage,
+        )?;
+
+        let after = accounts_repo
+            .update(account_id, update)
+            .map_err(|err| match err {
+                // both to sign with nobody to be the second signature
+                RepoError::Conflict => ApiError::Conflict,
+                RepoError::NotFound => ApiError::NotFound,
+                _ => ApiError::InternalError,
+            })?;
+
+        Ok::<_, ApiError>((before, after))
+    })
+    .await
+    .map_err(|_| ApiError::InternalError)??;
+
+    let after_rest: AccountRest = (&after).into();
+
+    let mut audit_entry = new_audit_entry(
+        &req,
+        ActorType::Customer,
+        customer_id,
+        AuditAction::UpdateSigningRule,
+        AuditEntity::Account,
+        Some(account_id),
+    );
+    audit_entry.before_snapshot = snapshot(&AccountRest::from(&before));
+    audit_entry.after_snapshot = snapshot(&after_rest);
+    record_audit(audit_repo, audit_entry).await;
+
+    Ok(HttpResponse::Ok()
+        .insert_header(ContentType::json())
+        .json(web::Json(after_rest)))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::audit::util::appended;
+    use crate::fixtures::holders_repo_for;
+    use std::vec;
+
+    use crate::{
+        api::{
+            accounts::{
+                handlers::{
+                    add_account_holder, backfill_balance_history, create_account, create_bucket,
+                    delete_account, delete_bucket, find_account_holders, find_accounts,
+                    find_buckets, get_account, get_balance_history, move_bucket_funds,
+                    update_signing_rule,
+                },
+                models::{
+                    AccountHoldersRest, AccountRest, AccountStatusRest, AccountTypeRest,
+                    AccountsRest, BalanceBackfillRest, BalanceGranularityRest,
+                    BalanceHistoryQueryRest, BalanceHistoryRest, BucketMoveRest, BucketRest,
+                    BucketsRest, FindAccountQueryRest, HolderRoleRest, NewAccountHolderRest,
+                    NewAccountRest, NewBucketRest, SigningRuleRest, SigningRuleUpdateRest,
+                },
+            },
+            error::ApiError,
+            step_up::{
+                models::{OtpMethodRest, StepUpActionRest, StepUpChallengeRest},
+                util::STEP_UP_HEADER,
+            },
+        },
+        error::RepoError,
+        models::{
+            account::{
+                Account, AccountStatus, AccountType, FindAccountQuery, NewAccount, SigningRule,
+                SigningRuleUpdate,
+            },
+            account_holder::{AccountHolder, FindAccountHolderQuery, HolderRole, NewAccountHolder},
+            audit::{ActorType, AuditEntry, NewAuditEntry},
+            balance_snapshot::{
+                BalanceBackfill, BalanceBackfillSummary, BalanceSnapshot, FindBalanceSnapshotQuery,
+            },
+            bucket::{AccountBucket, BucketMove, FindBucketQuery, NewAccountBucket},
+            consent::Consent,
+            oauth::{FindOAuthTokenQuery, OAuthToken, OAuthTokenKind},
+            step_up::{
+                NewStepUpChallenge, OtpMethod, StepUpAction, StepUpChallenge, StepUpRedemption,
+                TotpEnrolment,
+            },
+        },
+        step_up::MockCodeSender,
+        traits::{
+            MockRepoCreate, MockRepoFind, MockRepoGetById, RepoCreate, RepoDeleteById, RepoFind,
+            RepoGetById, RepoUpdate,
+        },
+    };
+
+    use actix_web::{
+        body::to_bytes,
+        http::StatusCode,
+        test,
+        web::{self, Data, Json, Query},
+        App,
+    };
+    use chrono::{NaiveDate, NaiveDateTime};
+    use mockall::{mock, predicate::eq, Sequence};
+
+    fn holder(
+        id: i32,
+        account_id: i32,
+        customer_id: i32,
+        holder_role: HolderRole,
+    ) -> AccountHolder {
+        AccountHolder {
+            id,
+            account_id,
+            customer_id,
+            holder_role,
+            created_at: NaiveDate::from_ymd_opt(2023, 9, 13)
+                .unwrap()
+                .and_hms_opt(9, 10, 11)
+                .unwrap(),
+        }
+    }
+
+    mock! {
+        pub OR { }
+        impl RepoFind<OAuthToken, FindOAuthTokenQuery> for OR {
+            fn find(&self, query: FindOAuthTokenQuery) -> Result<Vec<OAuthToken>, RepoError>;
+        }
+        impl RepoGetById<Consent> for OR {
+            fn get_by_id(&self, id: i32) -> Result<Consent, RepoError>;
+        }
+    }
+
+    mock! {
+        pub SuR { }
+        impl RepoGetById<TotpEnrolment> for SuR {
+            fn get_by_id(&self, id: i32) -> Result<TotpEnrolment, RepoError>;
+        }
+        impl RepoCreate<StepUpChallenge, NewStepUpChallenge> for SuR {
+            fn create(&self, new: NewStepUpChallenge) -> Result<StepUpChallenge, RepoError>;
+        }
+        impl RepoUpdate<StepUpChallenge, StepUpRedemption> for SuR {
+            fn update(&self, id: i32, update: StepUpRedemption) -> Result<StepUpChallenge, RepoError>;
+        }
+    }
+
+    fn challenge(customer_id: i32, account_id: i32) -> StepUpChallenge {
+        let created_at = NaiveDate::from_ymd_opt(2023, 9, 15)
+            .unwrap()
+            .and_hms_opt(9, 10, 11)
+            .unwrap();
+        StepUpChallenge {
+            id: 9,
+            customer_id,
+            step_up_action: StepUpAction::CloseAccount,
+            entity_id: account_id,
+            otp_method: OtpMethod::Delivered,
+            code_hash: None,
+            attempts: 0,
+            expires_at: created_at + chrono::Duration::minutes(5),
+            verified_at: None,
+            redeemed_at: None,
+            created_at,
+            to_number: None,
+            to_bsb: None,
+            amount_cents: None,
+        }
+    }
+
+    // challenge 9 was verified for closing the account
+    fn redeeming_step_up_repo(customer_id: i32, account_id: i32) -> MockSuR {
+        let mut mock_step_up_repo = MockSuR::new();
+        mock_step_up_repo
+            .expect_update()
+            .with(
+                eq(9),
+                eq(StepUpRedemption::action(
+                    customer_id,
+                    StepUpAction::CloseAccount,
+                    account_id,
+                )),
+            )
+            .times(1)
+            .returning(move |_, _| Ok(challenge(customer_id, account_id)));
+        mock_step_up_repo
+    }
+
+    #[actix_web::test]
+    async fn test_create_account_success() {
+        let customer_id = 1;
+        let account_id = 2;
+
+        let dt: NaiveDateTime = NaiveDate::from_ymd_opt(2016, 7, 8)
+            .unwrap()
+            .and_hms_opt(9, 10, 11)
+            .unwrap();
+
+        let mut mock_accounts_repo = MockRepoCreate::<Account, NewAccount>::new();
+        mock_accounts_repo
+            .expect_create()
+            .withf(move |acc| {
+                acc.customer_id == customer_id
+                    && acc.balance_cents == 34343
+                    && acc.account_type == AccountType::Savings
+                    && acc.account_name == Some("abc".to_string())
+                    && acc.available_balance_cents == 3444
+            })
+            .times(1)
+            .returning(move |_| {
+                Ok(Account {
+                    id: account_id,
+                    customer_id,
+                    balance_cents: 13424234234,
+                    account_type: AccountType::Savings,
+                    date_opened: dt,
+                    account_status: AccountStatus::Active,
+                    account_name: Some("abc".to_string()),
+                    account_number: "012345678".to_string(),
+                    available_balance_cents: 3444,
+                    bsb: "123456".to_string(),
+                    overdraft_limit_cents: 0,
+                    signing_rule: SigningRule::EitherToSign,
+                })
+            });
+
+        let mut mock_audit_repo = MockRepoCreate::<AuditEntry, NewAuditEntry>::new();
+        mock_audit_repo
+            .expect_create()
+            .withf(move |entry| {
+                entry.actor_type == ActorType::Customer
+                    && entry.actor_id == customer_id
+                    && entry.action == "create_account"
+                    && entry.entity_id == Some(account_id)
+                    && entry.before_snapshot.is_none()
+                    && entry.after_snapshot.is_some()
+                    && entry.request_method == "POST"
+                    && entry.request_path == "/customers/1/accounts"
+            })
+            .times(1)
+            .returning(|entry| Ok(appended(entry)));
+
+        let app =
+            test::init_service(
+                App::new()
+                    .app_data(Data::new(mock_accounts_repo))
+                    .app_data(Data::new(mock_audit_repo))
+                    .service(web::resource("/customers/{customer_id}/accounts").route(
+                        web::post().to(create_account::<
+                            MockRepoCreate<Account, NewAccount>,
+                            MockRepoCreate<AuditEntry, NewAuditEntry>,
+                        >),
+                    )),
+            )
+            .await;
+
+        let testpayload = NewAccountRest {
+            customer_id,
+            balance_cents: 34343,
+            available_balance_cents: 3444,
+            account_type: AccountTypeRest::Savings,
+            account_name: Some("abc".to_string()),
+        };
+
+        let resp = test::TestRequest::post()
+            .uri("/customers/1/accounts")
+            .append_header(("Content-Type", "application/json"))
+            .set_payload(serde_json::to_string(&testpayload).unwrap())
+            .send_request(&app)
+            .await;
+
+        let actual_status = resp.status();
+        assert_eq!(StatusCode::CREATED, actual_status);
+
+        let expected_account = AccountRest {
+            id: account_id,
+            customer_id,
+            balance_cents: 13424234234,
+            account_type: AccountTypeRest::Savings,
+            date_opened: dt.to_string(),
+            account_status: AccountStatusRest::Active,
+            account_name: Some("abc".to_string()),
+            account_number: "012345678".to_string(),
+            available_balance_cents: 3444,
+            bsb: "123456".to_string(),
+            overdraft: None,
+            signing_rule: SigningRuleRest::EitherToSign,
+        };
+
+        let actual_account: AccountRest = test::read_body_json(resp).await;
+        assert_eq!(expected_account, actual_account)
+    }
+
+    #[actix_web::test]
+    async fn test_create_account_internal_error() {
+        let customer_id = 5;
+
+        let new_account = NewAccount {
+            customer_id,
+            balance_cents: 13424234234,
+            account_type: AccountType::Savings,
+            account_name: Some("abc".to_string()),
+            available_balance_cents: 34343,
+            account_number: "".to_string(),
+        };
+
+        let mut mock_accounts_repo = MockRepoCreate::<Account, NewAccount>::new();
+        mock_accounts_repo
+            .expect_create()
+            .with(eq(new_account))
+            .times(1)
+            .returning(move |_| Err(RepoError::Other));
+
+        let mock_audit_repo = MockRepoCreate::<AuditEntry, NewAuditEntry>::new();
+
+        let res = create_account(
+            Data::new(mock_accounts_repo),
+            Data::new(mock_audit_repo),
+            test::TestRequest::default().to_http_request(),
+            customer_id.into(),
+            Json(NewAccountRest {
+                customer_id,
+                balance_cents: 13424234234,
+                available_balance_cents: 3444,
+                account_type: AccountTypeRest::Savings,
+                account_name: Some("abc".to_string()),
+            }),
+        )
+        .await;
+
+        assert!(res.is_err_and(|e| { e.to_string() == ApiError::InternalError.to_string() }));
+    }
+
+    #[actix_web::test]
+    async fn test_create_account_loan_error() {
+        let mut mock_accounts_repo = MockRepoCreate::<Account, NewAccount>::new();
+        mock_accounts_repo.expect_create().times(0);
+
+        let res = create_account(
+            Data::new(mock_accounts_repo),
+            Data::new(MockRepoCreate::<AuditEntry, NewAuditEntry>::new()),
+            test::TestRequest::default().to_http_request(),
+            5.into(),
+            Json(NewAccountRest {
+                customer_id: 5,
+                balance_cents: -50_000_000,
+                available_balance_cents: -50_000_000,
+                account_type: AccountTypeRest::Loan,
+                account_name: Some("Home loan".to_string()),
+            }),
+        )
+        .await;
+
+        assert!(res.is_err_and(|e| { e.to_string() == ApiError::BadRequest.to_string() }));
+    }
+
+    #[actix_web::test]
+    async fn test_find_accounts_by_customer_id_success() {
+        let customer_id = 1;
+
+        let expected_query = FindAccountQuery {
+            account_id: None,
+            customer_id,
+            account_number: None,
+        };
+
+        let mut mock_accounts_repo = MockRepoFind::<Account, FindAccountQuery>::new();
+        mock_accounts_repo
+            .expect_find()
+            .with(eq(expected_query))
+            .times(1)
+            .returning(move |_| {
+                Ok(vec![
+                    Account {
+                        id: 1,
+                        customer_id: 1,
+                        balance_cents: 13424234234,
+                        account_type: AccountType::Savings,
+                        date_opened: NaiveDate::from_ymd_opt(2016, 7, 8)
+                            .unwrap()
+                            .and_hms_opt(9, 10, 11)
+                            .unwrap(),
                         account_status: AccountStatus::Active,
                         account_name: Some("abc".to_string()),
                         available_balance_cents: 34343,
                         account_number: "012345678".to_string(),
                         bsb: "123456".to_string(),
+                        overdraft_limit_cents: 0,
+                        signing_rule: SigningRule::EitherToSign,
                     },
                     Account {
                         id: 2,
@@ -352,242 +1443,1326 @@ mod tests {
                         account_name: Some("abc".to_string()),
                         account_number: "012345678".to_string(),
                         bsb: "123456".to_string(),
+                        overdraft_limit_cents: 0,
+                        signing_rule: SigningRule::EitherToSign,
                     },
                 ])
             });
 
-        let mut app = test::init_service(
-            App::new()
-                .app_data(Data::new(mock_accounts_repo))
-                .service(web::resource("/customers/{customer_id}/accounts").route(
-                    web::get().to(find_accounts::<MockRepoFind<Account, FindAccountQuery>>),
-                )),
+        let mut mock_holders_repo = MockRepoFind::<AccountHolder, FindAccountHolderQuery>::new();
+        mock_holders_repo
+            .expect_find()
+            .with(eq(FindAccountHolderQuery {
+                account_id: None,
+                customer_id: Some(customer_id),
+            }))
+            .times(1)
+            .returning(move |_| {
+                Ok(vec![
+                    holder(1, 1, customer_id, HolderRole::Owner),
+                    holder(2, 2, customer_id, HolderRole::Signatory),
+                ])
+            });
+
+        let app =
+            test::init_service(
+                App::new()
+                    .app_data(Data::new(mock_accounts_repo))
+                    .app_data(Data::new(mock_holders_repo))
+                    .app_data(Data::new(MockOR::new()))
+                    .service(web::resource("/customers/{customer_id}/accounts").route(
+                        web::get().to(find_accounts::<
+                            MockRepoFind<Account, FindAccountQuery>,
+                            MockRepoFind<AccountHolder, FindAccountHolderQuery>,
+                            MockOR,
+                        >),
+                    )),
+            )
+            .await;
+
+        let resp = test::TestRequest::get()
+            .uri(format!("/customers/{0}/accounts?customerId={0}", customer_id).as_str())
+            .append_header(("Content-Type", "application/json"))
+            .send_request(&app)
+            .await;
+
+        let actual_status = resp.status();
+        assert_eq!(StatusCode::OK, actual_status);
+
+        let expected_res = AccountsRest {
+            accounts: vec![
+                AccountRest {
+                    id: 1,
+                    customer_id: 1,
+                    balance_cents: 13424234234,
+                    account_type: AccountTypeRest::Savings,
+                    date_opened: "2016-07-08 09:10:11".to_string(),
+                    account_status: AccountStatusRest::Active,
+                    account_name: Some("abc".to_string()),
+                    available_balance_cents: 34343,
+                    account_number: "012345678".to_string(),
+                    bsb: "123456".to_string(),
+                    overdraft: None,
+                    signing_rule: SigningRuleRest::EitherToSign,
+                },
+                AccountRest {
+                    id: 2,
+                    customer_id: 1,
+                    balance_cents: 13424234234,
+                    account_type: AccountTypeRest::Savings,
+                    date_opened: "2016-07-08 09:10:11".to_string(),
+                    account_status: AccountStatusRest::Active,
+                    account_name: Some("abc".to_string()),
+                    available_balance_cents: 34343,
+                    account_number: "012345678".to_string(),
+                    bsb: "123456".to_string(),
+                    overdraft: None,
+                    signing_rule: SigningRuleRest::EitherToSign,
+                },
+            ],
+        };
+
+        let actual_res: AccountsRest = test::read_body_json(resp).await;
+        assert_eq!(expected_res.accounts[0], actual_res.accounts[0]);
+        assert_eq!(expected_res.accounts[1], actual_res.accounts[1]);
+    }
+
+    #[actix_web::test]
+    async fn test_find_accounts_token_only_shared_accounts() {
+        let customer_id = 1;
+
+        let mut mock_oauth_repo = MockOR::new();
+        mock_oauth_repo
+            .expect_find()
+            .times(1)
+            .returning(move |query| {
+                let now = chrono::Utc::now().naive_utc();
+                Ok(vec![OAuthToken {
+                    id: 12,
+                    grant_id: 4,
+                    customer_id,
+                    token_kind: OAuthTokenKind::Access,
+                    token_hash: query.token_hash,
+                    scope: "accounts:read".to_string(),
+                    expires_at: now + chrono::Duration::minutes(60),
+                    revoked_at: None,
+                    created_at: now,
+                }])
+            });
+        mock_oauth_repo
+            .expect_get_by_id()
+            .with(eq(4))
+            .times(1)
+            .returning(move |id| {
+                let now = chrono::Utc::now().naive_utc();
+                Ok(Consent {
+                    id,
+                    customer_id,
+                    client_id: "budget-app".to_string(),
+                    client_name: "Budget App".to_string(),
+                    scope: "accounts:read".to_string(),
+                    account_ids: vec![2],
+                    created_at: now,
+                    updated_at: now,
+                    expires_at: now + chrono::Duration::days(30),
+                    revoked_at: None,
+                })
+            });
+
+        let account = move |id| Account {
+            id,
+            customer_id,
+            balance_cents: 5000,
+            account_type: AccountType::Savings,
+            date_opened: NaiveDate::from_ymd_opt(2016, 7, 8)
+                .unwrap()
+                .and_hms_opt(9, 10, 11)
+                .unwrap(),
+            account_status: AccountStatus::Active,
+            account_name: None,
+            available_balance_cents: 5000,
+            account_number: format!("01234567{}", id),
+            bsb: "123456".to_string(),
+            overdraft_limit_cents: 0,
+            signing_rule: SigningRule::EitherToSign,
+        };
+
+        let mut mock_accounts_repo = MockRepoFind::<Account, FindAccountQuery>::new();
+        mock_accounts_repo
+            .expect_find()
+            .times(1)
+            .returning(move |_| Ok(vec![account(1), account(2)]));
+
+        let mut mock_holders_repo = MockRepoFind::<AccountHolder, FindAccountHolderQuery>::new();
+        mock_holders_repo
+            .expect_find()
+            .times(1)
+            .returning(move |_| {
+                Ok(vec![
+                    holder(1, 1, customer_id, HolderRole::Owner),
+                    holder(2, 2, customer_id, HolderRole::Owner),
+                ])
+            });
+
+        let res = find_accounts(
+            Data::new(mock_accounts_repo),
+            Data::new(mock_holders_repo),
+            Data::new(mock_oauth_repo),
+            test::TestRequest::get()
+                .insert_header(("Authorization", "Bearer the-token"))
+                .to_http_request(),
+            customer_id.into(),
+            Query(FindAccountQueryRest {
+                account_id: None,
+                customer_id: None,
+                account_number: None,
+            }),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(StatusCode::OK, res.status());
+
+        let actual: AccountsRest =
+            serde_json::from_slice(&to_bytes(res.into_body()).await.unwrap()).unwrap();
+        assert_eq!(1, actual.accounts.len());
+        assert_eq!(2, actual.accounts[0].id);
+    }
+
+    #[actix_web::test]
+    async fn test_find_accounts_internal_error() {
+        let customer_id = 2;
+
+        let expected_query = FindAccountQuery {
+            account_id: None,
+            customer_id,
+            account_number: None,
+        };
+
+        let mut mock_accounts_repo = MockRepoFind::<Account, FindAccountQuery>::new();
+        mock_accounts_repo
+            .expect_find()
+            .with(eq(expected_query))
+            .times(1)
+            .returning(move |_| Err(RepoError::Other));
+
+        let mock_holders_repo = MockRepoFind::<AccountHolder, FindAccountHolderQuery>::new();
+
+        let app =
+            test::init_service(
+                App::new()
+                    .app_data(Data::new(mock_accounts_repo))
+                    .app_data(Data::new(mock_holders_repo))
+                    .app_data(Data::new(MockOR::new()))
+                    .service(web::resource("/customers/{customer_id}/accounts").route(
+                        web::get().to(find_accounts::<
+                            MockRepoFind<Account, FindAccountQuery>,
+                            MockRepoFind<AccountHolder, FindAccountHolderQuery>,
+                            MockOR,
+                        >),
+                    )),
+            )
+            .await;
+
+        let resp = test::TestRequest::get()
+            .uri(format!("/customers/{0}/accounts?customerId={0}", customer_id).as_str())
+            .append_header(("Content-Type", "application/json"))
+            .send_request(&app)
+            .await;
+
+        let actual_status = resp.status();
+        assert_eq!(StatusCode::INTERNAL_SERVER_ERROR, actual_status);
+    }
+
+    #[actix_web::test]
+    async fn test_get_account_success() {
+        let customer_id = 1;
+        let account_id = 2;
+
+        let mut mock_accounts_repo = MockRepoGetById::<Account>::new();
+        mock_accounts_repo
+            .expect_get_by_id()
+            .with(eq(account_id))
+            .times(1)
+            .returning(move |_| {
+                Ok(Account {
+                    id: account_id,
+                    customer_id,
+                    balance_cents: 13424234234,
+                    account_type: AccountType::Savings,
+                    date_opened: NaiveDate::from_ymd_opt(2016, 7, 8)
+                        .unwrap()
+                        .and_hms_opt(9, 10, 11)
+                        .unwrap(),
+                    account_status: AccountStatus::Active,
+                    account_name: Some("abc".to_string()),
+                    available_balance_cents: 34343,
+                    account_number: "012345678".to_string(),
+                    bsb: "123456".to_string(),
+                    overdraft_limit_cents: 0,
+                    signing_rule: SigningRule::EitherToSign,
+                })
+            });
+
+        let res = get_account(
+            Data::new(mock_accounts_repo),
+            Data::new(holders_repo_for(customer_id, HolderRole::Owner)),
+            Data::new(MockOR::new()),
+            test::TestRequest::get().to_http_request(),
+            (customer_id, account_id).into(),
+        )
+        .await
+        .unwrap();
+
+        let actual_status = res.status();
+        let actual_body = to_bytes(res.into_body()).await.unwrap();
+
+        let expected_status = StatusCode::OK;
+        let expected_body = serde_json::to_string::<AccountRest>(&AccountRest {
+            id: account_id,
+            customer_id,
+            balance_cents: 13424234234,
+            account_type: AccountTypeRest::Savings,
+            date_opened: "2016-07-08 09:10:11".to_string(),
+            account_status: AccountStatusRest::Active,
+            account_name: Some("abc".to_string()),
+            available_balance_cents: 34343,
+            account_number: "012345678".to_string(),
+            bsb: "123456".to_string(),
+            overdraft: None,
+            signing_rule: SigningRuleRest::EitherToSign,
+        })
+        .unwrap();
+
+        assert_eq!(expected_status, actual_status);
+        assert_eq!(expected_body, actual_body)
+    }
+
+    #[actix_web::test]
+    async fn test_get_account_internal_error() {
+        let customer_id = 1;
+        let account_id = 4;
+
+        let mut mock_accounts_repo = MockRepoGetById::<Account>::new();
+        mock_accounts_repo
+            .expect_get_by_id()
+            .with(eq(account_id))
+            .times(1)
+            .returning(move |_| Err(RepoError::Other));
+
+        let res = get_account(
+            Data::new(mock_accounts_repo),
+            Data::new(holders_repo_for(customer_id, HolderRole::Owner)),
+            Data::new(MockOR::new()),
+            test::TestRequest::get().to_http_request(),
+            (customer_id, account_id).into(),
+        )
+        .await;
+
+        assert!(res.is_err_and(|e| { e.to_string() == ApiError::InternalError.to_string() }));
+    }
+
+    #[actix_web::test]
+    async fn test_get_account_not_found_error() {
+        let customer_id = 1;
+        let account_id = 4;
+
+        let mut mock_accounts_repo = MockRepoGetById::<Account>::new();
+        mock_accounts_repo
+            .expect_get_by_id()
+            .with(eq(account_id))
+            .times(1)
+            .returning(move |_| Err(RepoError::NotFound));
+
+        let res = get_account(
+            Data::new(mock_accounts_repo),
+            Data::new(holders_repo_for(customer_id, HolderRole::Owner)),
+            Data::new(MockOR::new()),
+            test::TestRequest::get().to_http_request(),
+            (customer_id, account_id).into(),
+        )
+        .await;
+
+        assert!(res.is_err_and(|e| { e.to_string() == ApiError::NotFound.to_string() }));
+    }
+
+    #[actix_web::test]
+    async fn test_get_account_unauthorized_error() {
+        let customer_id = 1;
+        let wrong_customer_id = 5;
+        let account_id = 4;
+
+        let mut mock_accounts_repo = MockRepoGetById::<Account>::new();
+        mock_accounts_repo
+            .expect_get_by_id()
+            .with(eq(account_id))
+            .times(1)
+            .returning(move |_| {
+                Ok(Account {
+                    id: account_id,
+                    customer_id,
+                    balance_cents: 13424234234,
+                    account_type: AccountType::Savings,
+                    date_opened: NaiveDate::from_ymd_opt(2016, 7, 8)
+                        .unwrap()
+                        .and_hms_opt(9, 10, 11)
+                        .unwrap(),
+                    account_status: AccountStatus::Active,
+                    account_name: Some("abc".to_string()),
+                    available_balance_cents: 34343,
+                    account_number: "012345678".to_string(),
+                    bsb: "123456".to_string(),
+                    overdraft_limit_cents: 0,
+                    signing_rule: SigningRule::EitherToSign,
+                })
+            });
+
+        let res = get_account(
+            Data::new(mock_accounts_repo),
+            Data::new(holders_repo_for(customer_id, HolderRole::Owner)),
+            Data::new(MockOR::new()),
+            test::TestRequest::get().to_http_request(),
+            (wrong_customer_id, account_id).into(),
+        )
+        .await;
+
+        assert!(res.is_err_and(|e| { e.to_string() == ApiError::Unauthorized.to_string() }));
+    }
+
+    #[actix_web::test]
+    async fn test_get_account_token_without_scope_forbidden() {
+        let mut mock_oauth_repo = MockOR::new();
+        mock_oauth_repo.expect_find().times(1).returning(|query| {
+            let now = chrono::Utc::now().naive_utc();
+            Ok(vec![OAuthToken {
+                id: 12,
+                grant_id: 4,
+                customer_id: 1,
+                token_kind: OAuthTokenKind::Access,
+                token_hash: query.token_hash,
+                scope: "transactions:read".to_string(),
+                expires_at: now + chrono::Duration::minutes(60),
+                revoked_at: None,
+                created_at: now,
+            }])
+        });
+
+        let mut mock_accounts_repo = MockRepoGetById::<Account>::new();
+        mock_accounts_repo.expect_get_by_id().never();
+
+        let res = get_account(
+            Data::new(mock_accounts_repo),
+            Data::new(MockRepoFind::<AccountHolder, FindAccountHolderQuery>::new()),
+            Data::new(mock_oauth_repo),
+            test::TestRequest::get()
+                .insert_header(("Authorization", "Bearer the-token"))
+                .to_http_request(),
+            (1, 2).into(),
+        )
+        .await;
+
+        assert!(res.is_err_and(|e| { e.to_string() == ApiError::Forbidden.to_string() }));
+    }
+
+    #[actix_web::test]
+    async fn test_delete_account_success() {
+        let customer_id = 1;
+        let account_id = 2;
+
+        let mut seq = Sequence::new();
+
+        mock! {
+            pub AR { }
+            impl RepoGetById<Account> for AR {
+                fn get_by_id(&self, new: i32) -> Result<Account, RepoError>;
+            }
+            impl RepoDeleteById<Account> for AR {
+                fn delete_by_id(&self, id: i32) -> Result<(), RepoError>;
+            }
+        };
+
+        let mut mock_audit_repo = MockRepoCreate::<AuditEntry, NewAuditEntry>::new();
+        mock_audit_repo
+            .expect_create()
+            .withf(move |entry| {
+                entry.actor_id == customer_id
+                    && entry.action == "delete_account"
+                    && entry.entity_id == Some(account_id)
+                    && entry.before_snapshot.is_some()
+                    && entry.after_snapshot.is_none()
+            })
+            .times(1)
+            .returning(|entry| Ok(appended(entry)));
+
+        let mut mock_accounts_repo = MockAR::new();
+
+        mock_accounts_repo
+            .expect_get_by_id()
+            .with(eq(account_id))
+            .times(1)
+            .returning(move |_| {
+                Ok(Account {
+                    id: account_id,
+                    customer_id,
+                    balance_cents: 0,
+                    account_type: AccountType::Savings,
+                    date_opened: NaiveDate::from_ymd_opt(2016, 7, 8)
+                        .unwrap()
+                        .and_hms_opt(9, 10, 11)
+                        .unwrap(),
+                    account_status: AccountStatus::Active,
+                    account_name: Some("abc".to_string()),
+                    available_balance_cents: 0,
+                    account_number: "012345678".to_string(),
+                    bsb: "123456".to_string(),
+                    overdraft_limit_cents: 0,
+                    signing_rule: SigningRule::EitherToSign,
+                })
+            })
+            .in_sequence(&mut seq);
+
+        mock_accounts_repo
+            .expect_delete_by_id()
+            .with(eq(account_id))
+            .times(1)
+            .returning(|_| Ok(()))
+            .in_sequence(&mut seq);
+
+        let res = delete_account(
+            Data::new(mock_accounts_repo),
+            Data::new(holders_repo_for(customer_id, HolderRole::Owner)),
+            Data::new(redeeming_step_up_repo(customer_id, account_id)),
+            Data::new(MockCodeSender::new()),
+            Data::new(mock_audit_repo),
+            test::TestRequest::delete()
+                .insert_header((STEP_UP_HEADER, "9"))
+                .to_http_request(),
+            (customer_id, account_id).into(),
+        )
+        .await
+        .unwrap();
+
+        let actual_status = res.status();
+        let actual_body = to_bytes(res.into_body()).await.unwrap();
+
+        let expected_status = StatusCode::NO_CONTENT;
+
+        assert_eq!(expected_status, actual_status);
+        assert_eq!("", actual_body)
+    }
+
+    #[actix_web::test]
+    async fn test_delete_account_not_found_success() {
+        let customer_id = 5;
+        let account_id = 2;
+
+        let mut seq = Sequence::new();
+
+        mock! {
+            pub AR { }
+            impl RepoGetById<Account> for AR {
+                fn get_by_id(&self, new: i32) -> Result<Account, RepoError>;
+            }
+            impl RepoDeleteById<Account> for AR {
+                fn delete_by_id(&self, id: i32) -> Result<(), RepoError>;
+            }
+        };
+
+        let mock_audit_repo = MockRepoCreate::<AuditEntry, NewAuditEntry>::new();
+
+        let mut mock_accounts_repo = MockAR::new();
+
+        mock_accounts_repo
+            .expect_get_by_id()
+            .with(eq(account_id))
+            .times(1)
+            .returning(move |_| Err(crate::error::RepoError::NotFound))
+            .in_sequence(&mut seq);
+
+        // nothing to close, so nothing to step up either
+        mock_accounts_repo.expect_delete_by_id().never();
+
+        let res = delete_account(
+            Data::new(mock_accounts_repo),
+            Data::new(holders_repo_for(customer_id, HolderRole::Owner)),
+            Data::new(MockSuR::new()),
+            Data::new(MockCodeSender::new()),
+            Data::new(mock_audit_repo),
+            test::TestRequest::delete().to_http_request(),
+            (customer_id, account_id).into(),
+        )
+        .await
+        .unwrap();
+
+        let actual_status = res.status();
+        let actual_body = to_bytes(res.into_body()).await.unwrap();
+
+        let expected_status = StatusCode::NO_CONTENT;
+
+        assert_eq!(expected_status, actual_status);
+        assert_eq!("", actual_body)
+    }
+
+    #[actix_web::test]
+    async fn test_delete_account_unauthorized_error() {
+        let customer_id = 5;
+        let account_id = 2;
+        let wrong_customer_id = 2;
+
+        mock! {
+            pub AR { }
+            impl RepoGetById<Account> for AR {
+                fn get_by_id(&self, new: i32) -> Result<Account, RepoError>;
+            }
+            impl RepoDeleteById<Account> for AR {
+                fn delete_by_id(&self, id: i32) -> Result<(), RepoError>;
+            }
+        };
+
+        let mock_audit_repo = MockRepoCreate::<AuditEntry, NewAuditEntry>::new();
+
+        let mut mock_accounts_repo = MockAR::new();
+        mock_accounts_repo
+            .expect_get_by_id()
+            .with(eq(account_id))
+            .times(1)
+            .returning(move |_| {
+                Ok(Account {
+                    id: account_id,
+                    customer_id,
+                    balance_cents: 13424234234,
+                    account_type: AccountType::Savings,
+                    date_opened: NaiveDate::from_ymd_opt(2016, 7, 8)
+                        .unwrap()
+                        .and_hms_opt(9, 10, 11)
+                        .unwrap(),
+                    account_status: AccountStatus::Active,
+                    account_name: Some("abc".to_string()),
+                    available_balance_cents: 34343,
+                    account_number: "012345678".to_string(),
+                    bsb: "123456".to_string(),
+                    overdraft_limit_cents: 0,
+                    signing_rule: SigningRule::EitherToSign,
+                })
+            });
+
+        let res = delete_account(
+            Data::new(mock_accounts_repo),
+            Data::new(holders_repo_for(customer_id, HolderRole::Owner)),
+            Data::new(MockSuR::new()),
+            Data::new(MockCodeSender::new()),
+            Data::new(mock_audit_repo),
+            test::TestRequest::delete().to_http_request(),
+            (wrong_customer_id, account_id).into(),
+        )
+        .await;
+
+        assert!(res.is_err_and(|e| { e.to_string() == ApiError::Unauthorized.to_string() }));
+    }
+    #[actix_web::test]
+    async fn test_delete_account_internal_error() {
+        let customer_id = 5;
+        let account_id = 2;
+
+        mock! {
+            pub AR { }
+            impl RepoGetById<Account> for AR {
+                fn get_by_id(&self, new: i32) -> Result<Account, RepoError>;
+            }
+            impl RepoDeleteById<Account> for AR {
+                fn delete_by_id(&self, id: i32) -> Result<(), RepoError>;
+            }
+        };
+
+        let mock_audit_repo = MockRepoCreate::<AuditEntry, NewAuditEntry>::new();
+
+        let mut mock_accounts_repo = MockAR::new();
+        mock_accounts_repo
+            .expect_get_by_id()
+            .with(eq(account_id))
+            .times(1)
+            .returning(move |_| Err(RepoError::Other));
+
+        let res = delete_account(
+            Data::new(mock_accounts_repo),
+            Data::new(holders_repo_for(customer_id, HolderRole::Owner)),
+            Data::new(MockSuR::new()),
+            Data::new(MockCodeSender::new()),
+            Data::new(mock_audit_repo),
+            test::TestRequest::delete().to_http_request(),
+            (customer_id, account_id).into(),
+        )
+        .await;
+
+        assert!(res.is_err_and(|e| { e.to_string() == ApiError::InternalError.to_string() }));
+    }
+
+    fn balance_account(customer_id: i32) -> Account {
+        Account {
+            id: 52,
+            customer_id,
+            balance_cents: 300,
+            account_type: AccountType::Transaction,
+            date_opened: NaiveDate::from_ymd_opt(2016, 7, 8)
+                .unwrap()
+                .and_hms_opt(9, 10, 11)
+                .unwrap(),
+            account_status: AccountStatus::Active,
+            account_name: Some("Everyday".to_string()),
+            available_balance_cents: 300,
+            account_number: "012345678".to_string(),
+            bsb: "123456".to_string(),
+            overdraft_limit_cents: 0,
+            signing_rule: SigningRule::EitherToSign,
+        }
+    }
+
+    // only an account with nothing in it can be closed
+    fn empty_account(customer_id: i32) -> Account {
+        Account {
+            balance_cents: 0,
+            available_balance_cents: 0,
+            ..balance_account(customer_id)
+        }
+    }
+
+    fn balance_snapshot(month: u32, day: u32, balance_cents: i64) -> BalanceSnapshot {
+        BalanceSnapshot {
+            account_id: 52,
+            snapshot_date: NaiveDate::from_ymd_opt(2023, month, day).unwrap(),
+            balance_cents,
+            available_balance_cents: balance_cents,
+            updated_at: NaiveDate::from_ymd_opt(2023, 8, 23)
+                .unwrap()
+                .and_hms_opt(0, 0, 0)
+                .unwrap(),
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_get_balance_history_success() {
+        let customer_id = 5;
+
+        let mut mock_accounts_repo = MockRepoGetById::<Account>::new();
+        mock_accounts_repo
+            .expect_get_by_id()
+            .with(eq(52))
+            .times(1)
+            .returning(move |_| Ok(balance_account(customer_id)));
+
+        let mut mock_balance_repo =
+            MockRepoFind::<BalanceSnapshot, FindBalanceSnapshotQuery>::new();
+        mock_balance_repo
+            .expect_find()
+            .with(eq(FindBalanceSnapshotQuery {
+                account_id: 52,
+                from: NaiveDate::from_ymd_opt(2023, 8, 1).unwrap(),
+                to: NaiveDate::from_ymd_opt(2023, 8, 3).unwrap(),
+            }))
+            .times(1)
+            // opening balance from before the range, then one change inside it
+            .returning(|_| {
+                Ok(vec![
+                    balance_snapshot(7, 30, 100),
+                    balance_snapshot(8, 2, 300),
+                ])
+            });
+
+        let res = get_balance_history(
+            Data::new(mock_accounts_repo),
+            Data::new(holders_repo_for(customer_id, HolderRole::Owner)),
+            Data::new(mock_balance_repo),
+            Data::new(MockOR::new()),
+            test::TestRequest::get().to_http_request(),
+            (customer_id, 52).into(),
+            Query(BalanceHistoryQueryRest {
+                from: Some("2023-08-01".to_string()),
+                to: Some("2023-08-03".to_string()),
+                granularity: None,
+            }),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(StatusCode::OK, res.status());
+
+        let actual: BalanceHistoryRest =
+            serde_json::from_slice(&to_bytes(res.into_body()).await.unwrap()).unwrap();
+        let points: Vec<(String, i64)> = actual
+            .points
+            .iter()
+            .map(|p| (p.date.clone(), p.balance_cents))
+            .collect();
+        assert_eq!(BalanceGranularityRest::Daily, actual.granularity);
+        assert_eq!(
+            vec![
+                ("2023-08-01".to_string(), 100),
+                ("2023-08-02".to_string(), 300),
+                ("2023-08-03".to_string(), 300)
+            ],
+            points
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_get_balance_history_bad_range_error() {
+        let res = get_balance_history(
+            Data::new(MockRepoGetById::<Account>::new()),
+            Data::new(MockRepoFind::<AccountHolder, FindAccountHolderQuery>::new()),
+            Data::new(MockRepoFind::<BalanceSnapshot, FindBalanceSnapshotQuery>::new()),
+            Data::new(MockOR::new()),
+            test::TestRequest::get().to_http_request(),
+            (5, 52).into(),
+            Query(BalanceHistoryQueryRest {
+                from: Some("2023-08-03".to_string()),
+                to: Some("2023-08-01".to_string()),
+                granularity: Some(BalanceGranularityRest::Weekly),
+            }),
         )
         .await;
 
-        let resp = test::TestRequest::get()
-            .uri(format!("/customers/{0}/accounts?customerId={0}", customer_id).as_str())
-            .append_header(("Content-Type", "application/json"))
-            .send_request(&mut app)
-            .await;
+        assert!(res.is_err_and(|e| { e.to_string() == ApiError::BadRequest.to_string() }));
+    }
 
-        let actual_status = resp.status();
-        assert_eq!(StatusCode::OK, actual_status);
+    #[actix_web::test]
+    async fn test_get_balance_history_unauthorized_error() {
+        let mut mock_accounts_repo = MockRepoGetById::<Account>::new();
+        mock_accounts_repo
+            .expect_get_by_id()
+            .times(1)
+            .returning(|_| Ok(balance_account(6)));
 
-        let expected_res = AccountsRest {
-            accounts: vec![
-                AccountRest {
-                    id: 1,
-                    customer_id: 1,
-                    balance_cents: 13424234234,
-                    account_type: AccountTypeRest::Savings,
-                    date_opened: "2016-07-08 09:10:11".to_string(),
-                    account_status: AccountStatusRest::Active,
-                    account_name: Some("abc".to_string()),
-                    available_balance_cents: 34343,
-                    account_number: "012345678".to_string(),
-                    bsb: "123456".to_string(),
-                },
-                AccountRest {
-                    id: 2,
-                    customer_id: 1,
-                    balance_cents: 13424234234,
-                    account_type: AccountTypeRest::Savings,
-                    date_opened: "2016-07-08 09:10:11".to_string(),
-                    account_status: AccountStatusRest::Active,
-                    account_name: Some("abc".to_string()),
-                    available_balance_cents: 34343,
-                    account_number: "012345678".to_string(),
-                    bsb: "123456".to_string(),
-                },
-            ],
-        };
+        let mut mock_balance_repo =
+            MockRepoFind::<BalanceSnapshot, FindBalanceSnapshotQuery>::new();
+        mock_balance_repo.expect_find().times(0);
 
-        let actual_res: AccountsRest = test::read_body_json(resp).await;
-        assert_eq!(expected_res.accounts[0], actual_res.accounts[0]);
-        assert_eq!(expected_res.accounts[1], actual_res.accounts[1]);
+        let res = get_balance_history(
+            Data::new(mock_accounts_repo),
+            Data::new(holders_repo_for(6, HolderRole::Owner)),
+            Data::new(mock_balance_repo),
+            Data::new(MockOR::new()),
+            test::TestRequest::get().to_http_request(),
+            (5, 52).into(),
+            Query(BalanceHistoryQueryRest {
+                from: None,
+                to: None,
+                granularity: None,
+            }),
+        )
+        .await;
+
+        assert!(res.is_err_and(|e| { e.to_string() == ApiError::Unauthorized.to_string() }));
     }
 
     #[actix_web::test]
-    async fn test_find_accounts_internal_error() {
-        let customer_id = 2;
+    async fn test_backfill_balance_history_success() {
+        let staff_id = 900;
 
-        let expected_query = FindAccountQuery {
-            account_id: None,
-            customer_id,
-            account_number: None,
-        };
+        let mut mock_balance_repo =
+            MockRepoCreate::<BalanceBackfillSummary, BalanceBackfill>::new();
+        mock_balance_repo
+            .expect_create()
+            .with(eq(BalanceBackfill { account_id: 52 }))
+            .times(1)
+            .returning(|_| {
+                Ok(BalanceBackfillSummary {
+                    account_id: 52,
+                    transactions_updated: 12,
+                    snapshots_written: 4,
+                })
+            });
 
-        let mut mock_accounts_repo = MockRepoFind::<Account, FindAccountQuery>::new();
+        let mut mock_audit_repo = MockRepoCreate::<AuditEntry, NewAuditEntry>::new();
+        mock_audit_repo
+            .expect_create()
+            .withf(move |entry| {
+                entry.actor_type == ActorType::Staff
+                    && entry.actor_id == staff_id
+                    && entry.action == "backfill_balance_history"
+                    && entry.entity_id == Some(52)
+            })
+            .times(1)
+            .returning(|entry| Ok(appended(entry)));
+
+        let res = backfill_balance_history(
+            Data::new(mock_balance_repo),
+            Data::new(mock_audit_repo),
+            test::TestRequest::post().to_http_request(),
+            (staff_id, 52).into(),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(StatusCode::OK, res.status());
+
+        let actual: BalanceBackfillRest =
+            serde_json::from_slice(&to_bytes(res.into_body()).await.unwrap()).unwrap();
+        assert_eq!(12, actual.transactions_updated);
+    }
+
+    fn savings_account(customer_id: i32) -> Account {
+        Account {
+            account_type: AccountType::Savings,
+            ..balance_account(customer_id)
+        }
+    }
+
+    fn bucket(id: i32, balance_cents: i64, is_default: bool) -> AccountBucket {
+        let dt = NaiveDate::from_ymd_opt(2023, 8, 30)
+            .unwrap()
+            .and_hms_opt(3, 18, 22)
+            .unwrap();
+        AccountBucket {
+            id,
+            account_id: 52,
+            name: if is_default {
+                "Unallocated".to_string()
+            } else {
+                "Holiday".to_string()
+            },
+            balance_cents,
+            goal_cents: None,
+            target_date: None,
+            is_default,
+            created_at: dt,
+            updated_at: dt,
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_create_bucket_success() {
+        let customer_id = 5;
+
+        let mut mock_accounts_repo = MockRepoGetById::<Account>::new();
         mock_accounts_repo
-            .expect_find()
-            .with(eq(expected_query))
+            .expect_get_by_id()
+            .with(eq(52))
             .times(1)
-            .returning(move |_| Err(RepoError::Other));
+            .returning(move |_| Ok(savings_account(customer_id)));
 
-        let mut app = test::init_service(
-            App::new()
-                .app_data(Data::new(mock_accounts_repo))
-                .service(web::resource("/customers/{customer_id}/accounts").route(
-                    web::get().to(find_accounts::<MockRepoFind<Account, FindAccountQuery>>),
-                )),
+        let mut mock_buckets_repo = MockRepoCreate::<AccountBucket, NewAccountBucket>::new();
+        mock_buckets_repo
+            .expect_create()
+            .with(eq(NewAccountBucket {
+                account_id: 52,
+                name: "Holiday".to_string(),
+                balance_cents: 100,
+                goal_cents: Some(400000),
+                target_date: NaiveDate::from_ymd_opt(2023, 12, 1),
+            }))
+            .times(1)
+            .returning(|_| Ok(bucket(3, 100, false)));
+
+        let mut mock_audit_repo = MockRepoCreate::<AuditEntry, NewAuditEntry>::new();
+        mock_audit_repo
+            .expect_create()
+            .withf(move |entry| {
+                entry.actor_id == customer_id
+                    && entry.action == "create_bucket"
+                    && entry.entity_type == "bucket"
+                    && entry.entity_id == Some(3)
+                    && entry.after_snapshot.is_some()
+            })
+            .times(1)
+            .returning(|entry| Ok(appended(entry)));
+
+        let res = create_bucket(
+            Data::new(mock_accounts_repo),
+            Data::new(holders_repo_for(customer_id, HolderRole::Owner)),
+            Data::new(mock_buckets_repo),
+            Data::new(mock_audit_repo),
+            test::TestRequest::post().to_http_request(),
+            (customer_id, 52).into(),
+            Json(NewBucketRest {
+                name: " Holiday ".to_string(),
+                goal_cents: Some(400000),
+                target_date: Some("2023-12-01".to_string()),
+                initial_amount_cents: Some(100),
+            }),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(StatusCode::CREATED, res.status());
+
+        let actual: BucketRest =
+            serde_json::from_slice(&to_bytes(res.into_body()).await.unwrap()).unwrap();
+        assert_eq!(3, actual.id);
+        assert_eq!(100, actual.balance_cents);
+        assert!(!actual.is_default);
+    }
+
+    #[actix_web::test]
+    async fn test_create_bucket_not_savings_error() {
+        let mut mock_accounts_repo = MockRepoGetById::<Account>::new();
+        mock_accounts_repo
+            .expect_get_by_id()
+            .times(1)
+            .returning(|_| Ok(balance_account(5)));
+
+        let res = create_bucket(
+            Data::new(mock_accounts_repo),
+            Data::new(holders_repo_for(5, HolderRole::Owner)),
+            Data::new(MockRepoCreate::<AccountBucket, NewAccountBucket>::new()),
+            Data::new(MockRepoCreate::<AuditEntry, NewAuditEntry>::new()),
+            test::TestRequest::post().to_http_request(),
+            (5, 52).into(),
+            Json(NewBucketRest {
+                name: "Holiday".to_string(),
+                goal_cents: None,
+                target_date: None,
+                initial_amount_cents: None,
+            }),
         )
         .await;
 
-        let resp = test::TestRequest::get()
-            .uri(format!("/customers/{0}/accounts?customerId={0}", customer_id).as_str())
-            .append_header(("Content-Type", "application/json"))
-            .send_request(&mut app)
-            .await;
+        assert!(res.is_err_and(|e| { e.to_string() == ApiError::BadRequest.to_string() }));
+    }
 
-        let actual_status = resp.status();
-        assert_eq!(StatusCode::INTERNAL_SERVER_ERROR, actual_status);
+    #[actix_web::test]
+    async fn test_create_bucket_reserved_name_error() {
+        let res = create_bucket(
+            Data::new(MockRepoGetById::<Account>::new()),
+            Data::new(MockRepoFind::<AccountHolder, FindAccountHolderQuery>::new()),
+            Data::new(MockRepoCreate::<AccountBucket, NewAccountBucket>::new()),
+            Data::new(MockRepoCreate::<AuditEntry, NewAuditEntry>::new()),
+            test::TestRequest::post().to_http_request(),
+            (5, 52).into(),
+            Json(NewBucketRest {
+                name: "unallocated".to_string(),
+                goal_cents: None,
+                target_date: None,
+                initial_amount_cents: None,
+            }),
+        )
+        .await;
+
+        assert!(res.is_err_and(|e| { e.to_string() == ApiError::BadRequest.to_string() }));
     }
 
     #[actix_web::test]
-    async fn test_get_account_success() {
-        let customer_id = 1;
-        let account_id = 2;
+    async fn test_create_bucket_insufficient_unallocated_error() {
+        let mut mock_accounts_repo = MockRepoGetById::<Account>::new();
+        mock_accounts_repo
+            .expect_get_by_id()
+            .times(1)
+            .returning(|_| Ok(savings_account(5)));
+
+        let mut mock_buckets_repo = MockRepoCreate::<AccountBucket, NewAccountBucket>::new();
+        mock_buckets_repo
+            .expect_create()
+            .times(1)
+            .returning(|_| Err(RepoError::Conflict));
+
+        let res = create_bucket(
+            Data::new(mock_accounts_repo),
+            Data::new(holders_repo_for(5, HolderRole::Owner)),
+            Data::new(mock_buckets_repo),
+            Data::new(MockRepoCreate::<AuditEntry, NewAuditEntry>::new()),
+            test::TestRequest::post().to_http_request(),
+            (5, 52).into(),
+            Json(NewBucketRest {
+                name: "Holiday".to_string(),
+                goal_cents: None,
+                target_date: None,
+                initial_amount_cents: Some(100000),
+            }),
+        )
+        .await;
+
+        assert!(res.is_err_and(|e| { e.to_string() == ApiError::Conflict.to_string() }));
+    }
 
+    #[actix_web::test]
+    async fn test_find_buckets_unauthorized_error() {
         let mut mock_accounts_repo = MockRepoGetById::<Account>::new();
         mock_accounts_repo
             .expect_get_by_id()
-            .with(eq(account_id))
             .times(1)
-            .returning(move |_| {
-                Ok(Account {
-                    id: account_id,
-                    customer_id,
-                    balance_cents: 13424234234,
-                    account_type: AccountType::Savings,
-                    date_opened: NaiveDate::from_ymd_opt(2016, 7, 8)
-                        .unwrap()
-                        .and_hms_opt(9, 10, 11)
-                        .unwrap(),
-                    account_status: AccountStatus::Active,
-                    account_name: Some("abc".to_string()),
-                    available_balance_cents: 34343,
-                    account_number: "012345678".to_string(),
-                    bsb: "123456".to_string(),
-                })
-            });
+            .returning(|_| Ok(savings_account(6)));
 
-        let res = get_account(
+        let res = find_buckets(
             Data::new(mock_accounts_repo),
-            (customer_id, account_id).into(),
+            Data::new(holders_repo_for(6, HolderRole::Owner)),
+            Data::new(MockRepoFind::<AccountBucket, FindBucketQuery>::new()),
+            (5, 52).into(),
+        )
+        .await;
+
+        assert!(res.is_err_and(|e| { e.to_string() == ApiError::Unauthorized.to_string() }));
+    }
+
+    #[actix_web::test]
+    async fn test_move_bucket_funds_success() {
+        let customer_id = 5;
+
+        let mut mock_accounts_repo = MockRepoGetById::<Account>::new();
+        mock_accounts_repo
+            .expect_get_by_id()
+            .times(1)
+            .returning(move |_| Ok(savings_account(customer_id)));
+
+        let mut mock_buckets_repo = MockRepoCreate::<Vec<AccountBucket>, BucketMove>::new();
+        mock_buckets_repo
+            .expect_create()
+            .with(eq(BucketMove {
+                account_id: 52,
+                from_bucket_id: 2,
+                to_bucket_id: 3,
+                amount_cents: 150,
+            }))
+            .times(1)
+            .returning(|_| Ok(vec![bucket(2, 50, true), bucket(3, 250, false)]));
+
+        let mut mock_audit_repo = MockRepoCreate::<AuditEntry, NewAuditEntry>::new();
+        mock_audit_repo
+            .expect_create()
+            .withf(move |entry| {
+                entry.actor_id == customer_id
+                    && entry.action == "move_bucket_funds"
+                    && entry.entity_id == Some(2)
+            })
+            .times(1)
+            .returning(|entry| Ok(appended(entry)));
+
+        let res = move_bucket_funds(
+            Data::new(mock_accounts_repo),
+            Data::new(holders_repo_for(customer_id, HolderRole::Owner)),
+            Data::new(mock_buckets_repo),
+            Data::new(mock_audit_repo),
+            test::TestRequest::post().to_http_request(),
+            (customer_id, 52).into(),
+            Json(BucketMoveRest {
+                from_bucket_id: 2,
+                to_bucket_id: 3,
+                amount_cents: 150,
+            }),
         )
         .await
         .unwrap();
 
-        let actual_status = res.status();
-        let actual_body = to_bytes(res.into_body()).await.unwrap();
+        assert_eq!(StatusCode::OK, res.status());
 
-        let expected_status = StatusCode::OK;
-        let expected_body = serde_json::to_string::<AccountRest>(&AccountRest {
-            id: account_id,
-            customer_id,
-            balance_cents: 13424234234,
-            account_type: AccountTypeRest::Savings,
-            date_opened: "2016-07-08 09:10:11".to_string(),
-            account_status: AccountStatusRest::Active,
-            account_name: Some("abc".to_string()),
-            available_balance_cents: 34343,
-            account_number: "012345678".to_string(),
-            bsb: "123456".to_string(),
-        })
-        .unwrap();
+        let actual: BucketsRest =
+            serde_json::from_slice(&to_bytes(res.into_body()).await.unwrap()).unwrap();
+        let balances: Vec<(i32, i64)> = actual
+            .buckets
+            .iter()
+            .map(|b| (b.id, b.balance_cents))
+            .collect();
+        assert_eq!(vec![(2, 50), (3, 250)], balances);
+    }
 
-        assert_eq!(expected_status, actual_status);
-        assert_eq!(expected_body, actual_body)
+    #[actix_web::test]
+    async fn test_move_bucket_funds_same_bucket_error() {
+        let res = move_bucket_funds(
+            Data::new(MockRepoGetById::<Account>::new()),
+            Data::new(MockRepoFind::<AccountHolder, FindAccountHolderQuery>::new()),
+            Data::new(MockRepoCreate::<Vec<AccountBucket>, BucketMove>::new()),
+            Data::new(MockRepoCreate::<AuditEntry, NewAuditEntry>::new()),
+            test::TestRequest::post().to_http_request(),
+            (5, 52).into(),
+            Json(BucketMoveRest {
+                from_bucket_id: 2,
+                to_bucket_id: 2,
+                amount_cents: 150,
+            }),
+        )
+        .await;
+
+        assert!(res.is_err_and(|e| { e.to_string() == ApiError::BadRequest.to_string() }));
     }
 
     #[actix_web::test]
-    async fn test_get_account_internal_error() {
-        let customer_id = 1;
-        let account_id = 4;
+    async fn test_delete_default_bucket_error() {
+        mock! {
+            pub BkR { }
+            impl RepoGetById<AccountBucket> for BkR {
+                fn get_by_id(&self, id: i32) -> Result<AccountBucket, RepoError>;
+            }
+            impl RepoDeleteById<AccountBucket> for BkR {
+                fn delete_by_id(&self, id: i32) -> Result<(), RepoError>;
+            }
+        };
+
+        let mut mock_accounts_repo = MockRepoGetById::<Account>::new();
+        mock_accounts_repo
+            .expect_get_by_id()
+            .times(1)
+            .returning(|_| Ok(savings_account(5)));
+
+        let mut mock_buckets_repo = MockBkR::new();
+        mock_buckets_repo
+            .expect_get_by_id()
+            .with(eq(2))
+            .times(1)
+            .returning(|_| Ok(bucket(2, 300, true)));
+        mock_buckets_repo.expect_delete_by_id().never();
+
+        let res = delete_bucket(
+            Data::new(mock_accounts_repo),
+            Data::new(holders_repo_for(5, HolderRole::Owner)),
+            Data::new(mock_buckets_repo),
+            Data::new(MockRepoCreate::<AuditEntry, NewAuditEntry>::new()),
+            test::TestRequest::delete().to_http_request(),
+            (5, 52, 2).into(),
+        )
+        .await;
+
+        assert!(res.is_err_and(|e| { e.to_string() == ApiError::BadRequest.to_string() }));
+    }
+
+    #[actix_web::test]
+    async fn test_delete_account_signatory_forbidden_error() {
+        mock! {
+            pub AR { }
+            impl RepoGetById<Account> for AR {
+                fn get_by_id(&self, new: i32) -> Result<Account, RepoError>;
+            }
+            impl RepoDeleteById<Account> for AR {
+                fn delete_by_id(&self, id: i32) -> Result<(), RepoError>;
+            }
+        };
 
-        let mut mock_accounts_repo = MockRepoGetById::<Account>::new();
+        let mut mock_accounts_repo = MockAR::new();
         mock_accounts_repo
             .expect_get_by_id()
-            .with(eq(account_id))
+            .with(eq(52))
             .times(1)
-            .returning(move |_| Err(RepoError::Other));
+            .returning(|_| Ok(balance_account(6)));
+        mock_accounts_repo.expect_delete_by_id().never();
 
-        let res = get_account(
+        let res = delete_account(
             Data::new(mock_accounts_repo),
-            (customer_id, account_id).into(),
+            Data::new(holders_repo_for(5, HolderRole::Signatory)),
+            Data::new(MockSuR::new()),
+            Data::new(MockCodeSender::new()),
+            Data::new(MockRepoCreate::<AuditEntry, NewAuditEntry>::new()),
+            test::TestRequest::delete().to_http_request(),
+            (5, 52).into(),
         )
         .await;
 
-        assert!(res.is_err_and(|e| { e.to_string() == ApiError::InternalError.to_string() }));
+        assert!(res.is_err_and(|e| { e.to_string() == ApiError::Forbidden.to_string() }));
     }
 
     #[actix_web::test]
-    async fn test_get_account_not_found_error() {
-        let customer_id = 1;
-        let account_id = 4;
+    async fn test_delete_loan_account_conflict_error() {
+        mock! {
+            pub AR { }
+            impl RepoGetById<Account> for AR {
+                fn get_by_id(&self, new: i32) -> Result<Account, RepoError>;
+            }
+            impl RepoDeleteById<Account> for AR {
+                fn delete_by_id(&self, id: i32) -> Result<(), RepoError>;
+            }
+        };
 
-        let mut mock_accounts_repo = MockRepoGetById::<Account>::new();
+        // the borrower is an owner of the loan's account
+        let mut mock_accounts_repo = MockAR::new();
         mock_accounts_repo
             .expect_get_by_id()
-            .with(eq(account_id))
             .times(1)
-            .returning(move |_| Err(RepoError::NotFound));
+            .returning(|_| {
+                Ok(Account {
+                    account_type: AccountType::Loan,
+                    balance_cents: -2_000_000,
+                    available_balance_cents: -2_000_000,
+                    ..balance_account(5)
+                })
+            });
+        mock_accounts_repo.expect_delete_by_id().never();
 
-        let res = get_account(
+        let res = delete_account(
             Data::new(mock_accounts_repo),
-            (customer_id, account_id).into(),
+            Data::new(holders_repo_for(5, HolderRole::Owner)),
+            Data::new(MockSuR::new()),
+            Data::new(MockCodeSender::new()),
+            Data::new(MockRepoCreate::<AuditEntry, NewAuditEntry>::new()),
+            test::TestRequest::delete().to_http_request(),
+            (5, 52).into(),
         )
         .await;
 
-        assert!(res.is_err_and(|e| { e.to_string() == ApiError::NotFound.to_string() }));
+        assert!(res.is_err_and(|e| { e.to_string() == ApiError::Conflict.to_string() }));
     }
 
     #[actix_web::test]
-    async fn test_get_account_unauthorized_error() {
-        let customer_id = 1;
-        let wrong_customer_id = 5;
-        let account_id = 4;
+    async fn test_delete_overdrawn_account_conflict_error() {
+        mock! {
+            pub AR { }
+            impl RepoGetById<Account> for AR {
+                fn get_by_id(&self, new: i32) -> Result<Account, RepoError>;
+            }
+            impl RepoDeleteById<Account> for AR {
+                fn delete_by_id(&self, id: i32) -> Result<(), RepoError>;
+            }
+        };
 
-        let mut mock_accounts_repo = MockRepoGetById::<Account>::new();
+        // inside an arranged overdraft, what's owed on it can't be closed away
+        let mut mock_accounts_repo = MockAR::new();
         mock_accounts_repo
             .expect_get_by_id()
-            .with(eq(account_id))
             .times(1)
-            .returning(move |_| {
+            .returning(|_| {
                 Ok(Account {
-                    id: account_id,
-                    customer_id,
-                    balance_cents: 13424234234,
-                    account_type: AccountType::Savings,
-                    date_opened: NaiveDate::from_ymd_opt(2016, 7, 8)
-                        .unwrap()
-                        .and_hms_opt(9, 10, 11)
-                        .unwrap(),
-                    account_status: AccountStatus::Active,
-                    account_name: Some("abc".to_string()),
-                    available_balance_cents: 34343,
-                    account_number: "012345678".to_string(),
-                    bsb: "123456".to_string(),
+                    balance_cents: -5_000,
+                    available_balance_cents: -5_000,
+                    overdraft_limit_cents: 50_000,
+                    ..balance_account(5)
                 })
             });
+        mock_accounts_repo.expect_delete_by_id().never();
 
-        let res = get_account(
+        let res = delete_account(
             Data::new(mock_accounts_repo),
-            (wrong_customer_id, account_id).into(),
+            Data::new(holders_repo_for(5, HolderRole::Owner)),
+            Data::new(MockSuR::new()),
+            Data::new(MockCodeSender::new()),
+            Data::new(MockRepoCreate::<AuditEntry, NewAuditEntry>::new()),
+            test::TestRequest::delete().to_http_request(),
+            (5, 52).into(),
         )
         .await;
 
-        assert!(res.is_err_and(|e| { e.to_string() == ApiError::Unauthorized.to_string() }));
+        assert!(res.is_err_and(|e| { e.to_string() == ApiError::Conflict.to_string() }));
     }
 
     #[actix_web::test]
-    async fn test_delete_account_success() {
-        let customer_id = 1;
-        let account_id = 2;
+    async fn test_delete_account_with_money_in_it_conflict_error() {
+        mock! {
+            pub AR { }
+            impl RepoGetById<Account> for AR {
+                fn get_by_id(&self, new: i32) -> Result<Account, RepoError>;
+            }
+            impl RepoDeleteById<Account> for AR {
+                fn delete_by_id(&self, id: i32) -> Result<(), RepoError>;
+            }
+        };
 
-        let mut seq = Sequence::new();
+        let mut mock_accounts_repo = MockAR::new();
+        mock_accounts_repo
+            .expect_get_by_id()
+            .times(1)
+            .returning(|_| Ok(balance_account(5)));
+        mock_accounts_repo.expect_delete_by_id().never();
+
+        let res = delete_account(
+            Data::new(mock_accounts_repo),
+            Data::new(holders_repo_for(5, HolderRole::Owner)),
+            Data::new(MockSuR::new()),
+            Data::new(MockCodeSender::new()),
+            Data::new(MockRepoCreate::<AuditEntry, NewAuditEntry>::new()),
+            test::TestRequest::delete().to_http_request(),
+            (5, 52).into(),
+        )
+        .await;
 
+        assert!(res.is_err_and(|e| { e.to_string() == ApiError::Conflict.to_string() }));
+    }
+
+    #[actix_web::test]
+    async fn test_delete_closed_account_success() {
         mock! {
             pub AR { }
             impl RepoGetById<Account> for AR {
@@ -599,60 +2774,35 @@ mod tests {
         };
 
         let mut mock_accounts_repo = MockAR::new();
-
         mock_accounts_repo
             .expect_get_by_id()
-            .with(eq(account_id))
             .times(1)
-            .returning(move |_| {
+            .returning(|_| {
                 Ok(Account {
-                    id: account_id,
-                    customer_id,
-                    balance_cents: 13424234234,
-                    account_type: AccountType::Savings,
-                    date_opened: NaiveDate::from_ymd_opt(2016, 7, 8)
-                        .unwrap()
-                        .and_hms_opt(9, 10, 11)
-                        .unwrap(),
-                    account_status: AccountStatus::Active,
-                    account_name: Some("abc".to_string()),
-                    available_balance_cents: 34343,
-                    account_number: "012345678".to_string(),
-                    bsb: "123456".to_string(),
+                    account_status: AccountStatus::Inactive,
+                    ..balance_account(5)
                 })
-            })
-            .in_sequence(&mut seq);
-
-        mock_accounts_repo
-            .expect_delete_by_id()
-            .with(eq(account_id))
-            .times(1)
-            .returning(|_| Ok(()))
-            .in_sequence(&mut seq);
+            });
+        // already closed, nothing to step up either
+        mock_accounts_repo.expect_delete_by_id().never();
 
         let res = delete_account(
             Data::new(mock_accounts_repo),
-            (customer_id, account_id).into(),
+            Data::new(holders_repo_for(5, HolderRole::Owner)),
+            Data::new(MockSuR::new()),
+            Data::new(MockCodeSender::new()),
+            Data::new(MockRepoCreate::<AuditEntry, NewAuditEntry>::new()),
+            test::TestRequest::delete().to_http_request(),
+            (5, 52).into(),
         )
         .await
         .unwrap();
 
-        let actual_status = res.status();
-        let actual_body = to_bytes(res.into_body()).await.unwrap();
-
-        let expected_status = StatusCode::NO_CONTENT;
-
-        assert_eq!(expected_status, actual_status);
-        assert_eq!("", actual_body)
+        assert_eq!(StatusCode::NO_CONTENT, res.status());
     }
 
     #[actix_web::test]
-    async fn test_delete_account_not_found_success() {
-        let customer_id = 5;
-        let account_id = 2;
-
-        let mut seq = Sequence::new();
-
+    async fn test_delete_account_without_step_up_challenged() {
         mock! {
             pub AR { }
             impl RepoGetById<Account> for AR {
@@ -664,43 +2814,60 @@ mod tests {
         };
 
         let mut mock_accounts_repo = MockAR::new();
-
         mock_accounts_repo
             .expect_get_by_id()
-            .with(eq(account_id))
             .times(1)
-            .returning(move |_| Err(crate::error::RepoError::NotFound))
-            .in_sequence(&mut seq);
+            .returning(|_| Ok(empty_account(5)));
+        mock_accounts_repo.expect_delete_by_id().never();
 
-        mock_accounts_repo
-            .expect_delete_by_id()
-            .with(eq(account_id))
+        let mut mock_step_up_repo = MockSuR::new();
+        mock_step_up_repo
+            .expect_get_by_id()
+            .with(eq(5))
             .times(1)
-            .returning(|_| Ok(()))
-            .in_sequence(&mut seq);
+            .returning(|_| Err(RepoError::NotFound));
+        mock_step_up_repo
+            .expect_create()
+            .withf(|new| {
+                new.customer_id == 5
+                    && new.step_up_action == StepUpAction::CloseAccount
+                    && new.entity_id == 52
+                    && new.otp_method == OtpMethod::Delivered
+                    && new.code_hash.is_some()
+            })
+            .times(1)
+            .returning(|_| Ok(challenge(5, 52)));
+
+        let mut mock_code_sender = MockCodeSender::new();
+        mock_code_sender
+            .expect_send_code()
+            .withf(|customer_id, code| *customer_id == 5 && code.len() == 6)
+            .times(1)
+            .returning(|_, _| Ok(()));
 
         let res = delete_account(
             Data::new(mock_accounts_repo),
-            (customer_id, account_id).into(),
+            Data::new(holders_repo_for(5, HolderRole::Owner)),
+            Data::new(mock_step_up_repo),
+            Data::new(mock_code_sender),
+            Data::new(MockRepoCreate::<AuditEntry, NewAuditEntry>::new()),
+            test::TestRequest::delete().to_http_request(),
+            (5, 52).into(),
         )
         .await
         .unwrap();
 
-        let actual_status = res.status();
-        let actual_body = to_bytes(res.into_body()).await.unwrap();
-
-        let expected_status = StatusCode::NO_CONTENT;
+        assert_eq!(StatusCode::UNAUTHORIZED, res.status());
 
-        assert_eq!(expected_status, actual_status);
-        assert_eq!("", actual_body)
+        let actual: StepUpChallengeRest =
+            serde_json::from_slice(&to_bytes(res.into_body()).await.unwrap()).unwrap();
+        assert_eq!(9, actual.id);
+        assert_eq!(StepUpActionRest::CloseAccount, actual.step_up_action);
+        assert_eq!(OtpMethodRest::Delivered, actual.otp_method);
     }
 
     #[actix_web::test]
-    async fn test_delete_account_unauthorized_error() {
-        let customer_id = 5;
-        let account_id = 2;
-        let wrong_customer_id = 2;
-
+    async fn test_delete_account_unverified_step_up_forbidden_error() {
         mock! {
             pub AR { }
             impl RepoGetById<Account> for AR {
@@ -714,62 +2881,211 @@ mod tests {
         let mut mock_accounts_repo = MockAR::new();
         mock_accounts_repo
             .expect_get_by_id()
-            .with(eq(account_id))
             .times(1)
-            .returning(move |_| {
-                Ok(Account {
-                    id: account_id,
-                    customer_id,
-                    balance_cents: 13424234234,
-                    account_type: AccountType::Savings,
-                    date_opened: NaiveDate::from_ymd_opt(2016, 7, 8)
-                        .unwrap()
-                        .and_hms_opt(9, 10, 11)
-                        .unwrap(),
-                    account_status: AccountStatus::Active,
-                    account_name: Some("abc".to_string()),
-                    available_balance_cents: 34343,
-                    account_number: "012345678".to_string(),
-                    bsb: "123456".to_string(),
-                })
-            });
+            .returning(|_| Ok(empty_account(5)));
+        mock_accounts_repo.expect_delete_by_id().never();
+
+        let mut mock_step_up_repo = MockSuR::new();
+        mock_step_up_repo
+            .expect_update()
+            .times(1)
+            .returning(|_, _| Err(RepoError::NotFound));
 
         let res = delete_account(
             Data::new(mock_accounts_repo),
-            (wrong_customer_id, account_id).into(),
+            Data::new(holders_repo_for(5, HolderRole::Owner)),
+            Data::new(mock_step_up_repo),
+            Data::new(MockCodeSender::new()),
+            Data::new(MockRepoCreate::<AuditEntry, NewAuditEntry>::new()),
+            test::TestRequest::delete()
+                .insert_header((STEP_UP_HEADER, "9"))
+                .to_http_request(),
+            (5, 52).into(),
         )
         .await;
 
-        assert!(res.is_err_and(|e| { e.to_string() == ApiError::Unauthorized.to_string() }));
+        assert!(res.is_err_and(|e| { e.to_string() == ApiError::Forbidden.to_string() }));
     }
+
     #[actix_web::test]
-    async fn test_delete_account_internal_error() {
-        let customer_id = 5;
-        let account_id = 2;
+    async fn test_find_account_holders_success() {
+        let mut mock_holders_repo = MockRepoFind::<AccountHolder, FindAccountHolderQuery>::new();
+        mock_holders_repo
+            .expect_find()
+            .with(eq(FindAccountHolderQuery {
+                account_id: Some(52),
+                customer_id: Some(5),
+            }))
+            .times(1)
+            .returning(|_| Ok(vec![holder(2, 52, 5, HolderRole::Signatory)]));
+        mock_holders_repo
+            .expect_find()
+            .with(eq(FindAccountHolderQuery {
+                account_id: Some(52),
+                customer_id: None,
+            }))
+            .times(1)
+            .returning(|_| {
+                Ok(vec![
+                    holder(1, 52, 6, HolderRole::Owner),
+                    holder(2, 52, 5, HolderRole::Signatory),
+                ])
+            });
+
+        let res = find_account_holders(Data::new(mock_holders_repo), (5, 52).into())
+            .await
+            .unwrap();
+
+        assert_eq!(StatusCode::OK, res.status());
+
+        let actual: AccountHoldersRest =
+            serde_json::from_slice(&to_bytes(res.into_body()).await.unwrap()).unwrap();
+        let holders: Vec<(i32, HolderRoleRest)> = actual
+            .holders
+            .into_iter()
+            .map(|h| (h.customer_id, h.role))
+            .collect();
+        assert_eq!(
+            vec![(6, HolderRoleRest::Owner), (5, HolderRoleRest::Signatory)],
+            holders
+        );
+    }
 
+    #[actix_web::test]
+    async fn test_add_account_holder_conflict_error() {
         mock! {
-            pub AR { }
-            impl RepoGetById<Account> for AR {
-                fn get_by_id(&self, new: i32) -> Result<Account, RepoError>;
+            pub HR { }
+            impl RepoFind<AccountHolder, FindAccountHolderQuery> for HR {
+                fn find(&self, query: FindAccountHolderQuery) -> Result<Vec<AccountHolder>, RepoError>;
             }
-            impl RepoDeleteById<Account> for AR {
-                fn delete_by_id(&self, id: i32) -> Result<(), RepoError>;
+            impl RepoCreate<AccountHolder, NewAccountHolder> for HR {
+                fn create(&self, new: NewAccountHolder) -> Result<AccountHolder, RepoError>;
             }
         };
 
-        let mut mock_accounts_repo = MockAR::new();
+        let mut mock_holders_repo = MockHR::new();
+        mock_holders_repo
+            .expect_find()
+            .times(1)
+            .returning(|_| Ok(vec![holder(1, 52, 5, HolderRole::Owner)]));
+        mock_holders_repo
+            .expect_create()
+            .with(eq(NewAccountHolder {
+                account_id: 52,
+                customer_id: 6,
+                holder_role: HolderRole::Signatory,
+            }))
+            .times(1)
+            .returning(|_| Err(RepoError::Conflict));
+
+        let mut mock_audit_repo = MockRepoCreate::<AuditEntry, NewAuditEntry>::new();
+        mock_audit_repo.expect_create().never();
+
+        let res = add_account_holder(
+            Data::new(mock_holders_repo),
+            Data::new(mock_audit_repo),
+            test::TestRequest::post().to_http_request(),
+            (5, 52).into(),
+            Json(NewAccountHolderRest {
+                customer_id: 6,
+                role: HolderRoleRest::Signatory,
+            }),
+        )
+        .await;
+
+        assert!(res.is_err_and(|e| { e.to_string() == ApiError::Conflict.to_string() }));
+    }
+
+    mock! {
+        pub SigningAR { }
+        impl RepoGetById<Account> for SigningAR {
+            fn get_by_id(&self, id: i32) -> Result<Account, RepoError>;
+        }
+        impl RepoUpdate<Account, SigningRuleUpdate> for SigningAR {
+            fn update(&self, id: i32, update: SigningRuleUpdate) -> Result<Account, RepoError>;
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_update_signing_rule_success() {
+        let mut mock_accounts_repo = MockSigningAR::new();
         mock_accounts_repo
             .expect_get_by_id()
-            .with(eq(account_id))
+            .with(eq(52))
             .times(1)
-            .returning(move |_| Err(RepoError::Other));
+            .returning(|_| Ok(balance_account(5)));
+        mock_accounts_repo
+            .expect_update()
+            .with(
+                eq(52),
+                eq(SigningRuleUpdate {
+                    signing_rule: SigningRule::BothToSign,
+                }),
+            )
+            .times(1)
+            .returning(|_, _| {
+                Ok(Account {
+                    signing_rule: SigningRule::BothToSign,
+                    ..balance_account(5)
+                })
+            });
 
-        let res = delete_account(
+        let mut mock_audit_repo = MockRepoCreate::<AuditEntry, NewAuditEntry>::new();
+        mock_audit_repo
+            .expect_create()
+            .withf(|entry| {
+                entry.action == "update_signing_rule"
+                    && entry.entity_id == Some(52)
+                    && entry.before_snapshot.is_some()
+                    && entry.after_snapshot.is_some()
+            })
+            .times(1)
+            .returning(|entry| Ok(appended(entry)));
+
+        let res = update_signing_rule(
             Data::new(mock_accounts_repo),
-            (customer_id, account_id).into(),
+            Data::new(holders_repo_for(5, HolderRole::Owner)),
+            Data::new(mock_audit_repo),
+            test::TestRequest::put().to_http_request(),
+            (5, 52).into(),
+            Json(SigningRuleUpdateRest {
+                signing_rule: SigningRuleRest::BothToSign,
+            }),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(StatusCode::OK, res.status());
+
+        let actual: AccountRest =
+            serde_json::from_slice(&to_bytes(res.into_body()).await.unwrap()).unwrap();
+        assert_eq!(SigningRuleRest::BothToSign, actual.signing_rule);
+    }
+
+    #[actix_web::test]
+    async fn test_update_signing_rule_sole_holder_conflict_error() {
+        let mut mock_accounts_repo = MockSigningAR::new();
+        mock_accounts_repo
+            .expect_get_by_id()
+            .times(1)
+            .returning(|_| Ok(balance_account(5)));
+        mock_accounts_repo
+            .expect_update()
+            .times(1)
+            .returning(|_, _| Err(RepoError::Conflict));
+
+        let res = update_signing_rule(
+            Data::new(mock_accounts_repo),
+            Data::new(holders_repo_for(5, HolderRole::Owner)),
+            Data::new(MockRepoCreate::<AuditEntry, NewAuditEntry>::new()),
+            test::TestRequest::put().to_http_request(),
+            (5, 52).into(),
+            Json(SigningRuleUpdateRest {
+                signing_rule: SigningRuleRest::BothToSign,
+            }),
         )
         .await;
 
-        assert!(res.is_err_and(|e| { e.to_string() == ApiError::InternalError.to_string() }));
+        assert!(res.is_err_and(|e| { e.to_string() == ApiError::Conflict.to_string() }));
     }
 }
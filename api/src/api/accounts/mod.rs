@@ -7,28 +7,105 @@ use actix_web::web;
 
 use crate::{
     api::accounts,
-    models::account::{Account, FindAccountQuery, NewAccount},
-    traits::{RepoCreate, RepoDeleteById, RepoFind, RepoGetById},
+    models::{
+        account::{Account, FindAccountQuery, NewAccount, SigningRuleUpdate},
+        account_holder::{AccountHolder, FindAccountHolderQuery, NewAccountHolder},
+        audit::{AuditEntry, NewAuditEntry},
+        balance_snapshot::{
+            BalanceBackfill, BalanceBackfillSummary, BalanceSnapshot, FindBalanceSnapshotQuery,
+        },
+        bucket::{AccountBucket, BucketMove, BucketUpdate, FindBucketQuery, NewAccountBucket},
+        consent::Consent,
+        oauth::{FindOAuthTokenQuery, OAuthToken},
+        step_up::{NewStepUpChallenge, StepUpChallenge, StepUpRedemption, TotpEnrolment},
+    },
+    step_up::CodeSender,
+    traits::{RepoCreate, RepoDeleteById, RepoFind, RepoGetById, RepoUpdate},
 };
 
-pub fn configure_accounts_api<AR>(cfg: &mut web::ServiceConfig)
+pub fn configure_accounts_api<AR, HR, BR, BkR, OR, SR, CS, AuR>(cfg: &mut web::ServiceConfig)
 where
     AR: RepoCreate<Account, NewAccount>
         + RepoFind<Account, FindAccountQuery>
         + RepoGetById<Account>
-        + RepoDeleteById<Account>,
+        + RepoDeleteById<Account>
+        + RepoUpdate<Account, SigningRuleUpdate>,
+    HR: RepoFind<AccountHolder, FindAccountHolderQuery>
+        + RepoCreate<AccountHolder, NewAccountHolder>
+        + RepoGetById<AccountHolder>
+        + RepoDeleteById<AccountHolder>,
+    BR: RepoFind<BalanceSnapshot, FindBalanceSnapshotQuery>
+        + RepoCreate<BalanceBackfillSummary, BalanceBackfill>,
+    BkR: RepoCreate<AccountBucket, NewAccountBucket>
+        + RepoFind<AccountBucket, FindBucketQuery>
+        + RepoGetById<AccountBucket>
+        + RepoUpdate<AccountBucket, BucketUpdate>
+        + RepoDeleteById<AccountBucket>
+        + RepoCreate<Vec<AccountBucket>, BucketMove>,
+    OR: RepoFind<OAuthToken, FindOAuthTokenQuery> + RepoGetById<Consent>,
+    SR: RepoGetById<TotpEnrolment>
+        + RepoCreate<StepUpChallenge, NewStepUpChallenge>
+        + RepoUpdate<StepUpChallenge, StepUpRedemption>,
+    CS: CodeSender,
+    AuR: RepoCreate<AuditEntry, NewAuditEntry>,
 {
     cfg.service(
         web::scope("/api/customers/{customer_id}/accounts")
             .service(
                 web::resource("")
-                    .route(web::post().to(accounts::handlers::create_account::<AR>))
-                    .route(web::get().to(accounts::handlers::find_accounts::<AR>)),
+                    .route(web::post().to(accounts::handlers::create_account::<AR, AuR>))
+                    .route(web::get().to(accounts::handlers::find_accounts::<AR, HR, OR>)),
             )
             .service(
                 web::resource("/{account_id}")
-                    .route(web::get().to(accounts::handlers::get_account::<AR>))
-                    .route(web::delete().to(accounts::handlers::delete_account::<AR>)),
+                    .route(web::get().to(accounts::handlers::get_account::<AR, HR, OR>))
+                    .route(web::delete().to(accounts::handlers::delete_account::<
+                        AR,
+                        HR,
+                        SR,
+                        CS,
+                        AuR,
+                    >)),
+            )
+            .service(
+                web::resource("/{account_id}/balance-history").route(
+                    web::get().to(accounts::handlers::get_balance_history::<AR, HR, BR, OR>),
+                ),
+            )
+            .service(
+                web::resource("/{account_id}/buckets")
+                    .route(web::get().to(accounts::handlers::find_buckets::<AR, HR, BkR>))
+                    .route(web::post().to(accounts::handlers::create_bucket::<AR, HR, BkR, AuR>)),
+            )
+            // before /{bucket_id} so it isn't taken for an id
+            .service(
+                web::resource("/{account_id}/buckets/transfers").route(
+                    web::post().to(accounts::handlers::move_bucket_funds::<AR, HR, BkR, AuR>),
+                ),
+            )
+            .service(
+                web::resource("/{account_id}/buckets/{bucket_id}")
+                    .route(web::patch().to(accounts::handlers::update_bucket::<AR, HR, BkR, AuR>))
+                    .route(web::delete().to(accounts::handlers::delete_bucket::<AR, HR, BkR, AuR>)),
+            )
+            .service(
+                web::resource("/{account_id}/holders")
+                    .route(web::get().to(accounts::handlers::find_account_holders::<HR>))
+                    .route(web::post().to(accounts::handlers::add_account_holder::<HR, AuR>)),
+            )
+            .service(
+                web::resource("/{account_id}/holders/{holder_id}")
+                    .route(web::delete().to(accounts::handlers::remove_account_holder::<HR, AuR>)),
+            )
+            .service(
+                web::resource("/{account_id}/signing-rule")
+                    .route(web::put().to(accounts::handlers::update_signing_rule::<AR, HR, AuR>)),
             ),
+    )
+    .service(
+        web::scope("/api/staff/{staff_id}/accounts").service(
+            web::resource("/{account_id}/balance-history/backfill")
+                .route(web::post().to(accounts::handlers::backfill_balance_history::<BR, AuR>)),
+        ),
     );
 }
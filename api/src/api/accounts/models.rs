@@ -6,6 +6,7 @@ pub enum AccountTypeRest {
     Savings,
     Transaction,
     TermDeposit,
+    Loan,
 }
 
 #[derive(Deserialize, Serialize, Copy, Clone, Debug, PartialEq)]
@@ -15,6 +16,20 @@ pub enum AccountStatusRest {
     Inactive,
 }
 
+#[derive(Deserialize, Serialize, Copy, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum SigningRuleRest {
+    EitherToSign,
+    BothToSign,
+}
+
+#[derive(Deserialize, Serialize, Copy, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum HolderRoleRest {
+    Owner,
+    Signatory,
+}
+
 #[cfg_attr(test, derive(Deserialize, PartialEq, Debug))]
 #[derive(Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -29,6 +44,21 @@ pub struct AccountRest {
     pub available_balance_cents: i64,
     pub account_name: Option<String>,
     pub bsb: String,
+    // only on accounts with an arranged overdraft
+    pub overdraft: Option<OverdraftRest>,
+    pub signing_rule: SigningRuleRest,
+}
+
+#[cfg_attr(test, derive(Deserialize, PartialEq, Debug))]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OverdraftRest {
+    pub limit_cents: i64,
+    pub used_cents: i64,
+    // left to spend before transfers are declined
+    pub remaining_cents: i64,
+    pub interest_rate_bps: i64,
+    pub dishonour_fee_cents: i64,
 }
 
 #[cfg_attr(test, derive(Serialize))]
@@ -55,6 +85,150 @@ pub struct FindAccountQueryRest {
     pub account_id: Option<i32>,
     // TODO: consider removing
     // if queries come from other than person owning account this is useful but i doubt we'll use it
+    #[allow(dead_code)]
     pub customer_id: Option<i32>,
     pub account_number: Option<String>,
 }
+
+#[derive(Deserialize, Serialize, Copy, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum BalanceGranularityRest {
+    Daily,
+    Weekly,
+    Monthly,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BalanceHistoryQueryRest {
+    // YYYY-MM-DD, inclusive. last 30 days up to today if not given
+    pub from: Option<String>,
+    pub to: Option<String>,
+    pub granularity: Option<BalanceGranularityRest>,
+}
+
+#[cfg_attr(test, derive(Deserialize, PartialEq, Debug))]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BalancePointRest {
+    pub date: String,
+    pub balance_cents: i64,
+    pub available_balance_cents: i64,
+}
+
+#[cfg_attr(test, derive(Deserialize, PartialEq, Debug))]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BalanceHistoryRest {
+    pub account_id: i32,
+    pub granularity: BalanceGranularityRest,
+    pub points: Vec<BalancePointRest>,
+}
+
+#[cfg_attr(test, derive(Deserialize, PartialEq, Debug))]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BalanceBackfillRest {
+    pub account_id: i32,
+    pub transactions_updated: usize,
+    pub snapshots_written: usize,
+}
+
+#[cfg_attr(test, derive(Deserialize, PartialEq, Debug))]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BucketProgressRest {
+    pub percent: i64,
+    pub remaining_cents: i64,
+    pub days_left: Option<i64>,
+    pub monthly_needed_cents: Option<i64>,
+}
+
+#[cfg_attr(test, derive(Deserialize, PartialEq, Debug))]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BucketRest {
+    pub id: i32,
+    pub account_id: i32,
+    pub name: String,
+    pub balance_cents: i64,
+    pub goal_cents: Option<i64>,
+    pub target_date: Option<String>,
+    pub is_default: bool,
+    // only for buckets with a goal
+    pub progress: Option<BucketProgressRest>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[cfg_attr(test, derive(Deserialize, PartialEq, Debug))]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BucketsRest {
+    pub buckets: Vec<BucketRest>,
+}
+
+#[cfg_attr(test, derive(Serialize))]
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct NewBucketRest {
+    pub name: String,
+    pub goal_cents: Option<i64>,
+    // YYYY-MM-DD
+    pub target_date: Option<String>,
+    // moved in from the unallocated bucket, nothing if not given
+    pub initial_amount_cents: Option<i64>,
+}
+
+#[cfg_attr(test, derive(Serialize))]
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct BucketUpdateRest {
+    pub name: Option<String>,
+    // 0 clears it
+    pub goal_cents: Option<i64>,
+    // empty string clears it
+    pub target_date: Option<String>,
+}
+
+#[cfg_attr(test, derive(Serialize))]
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct BucketMoveRest {
+    pub from_bucket_id: i32,
+    pub to_bucket_id: i32,
+    pub amount_cents: i64,
+}
+
+#[cfg_attr(test, derive(Deserialize, PartialEq, Debug))]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountHolderRest {
+    pub id: i32,
+    pub account_id: i32,
+    pub customer_id: i32,
+    pub role: HolderRoleRest,
+    pub created_at: String,
+}
+
+#[cfg_attr(test, derive(Deserialize))]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountHoldersRest {
+    pub holders: Vec<AccountHolderRest>,
+}
+
+#[cfg_attr(test, derive(Serialize))]
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct NewAccountHolderRest {
+    pub customer_id: i32,
+    pub role: HolderRoleRest,
+}
+
+#[cfg_attr(test, derive(Serialize))]
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SigningRuleUpdateRest {
+    pub signing_rule: SigningRuleRest,
+}
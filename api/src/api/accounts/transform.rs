@@ -1,7 +1,13 @@
-use crate::models::account::{Account, AccountStatus, AccountType, NewAccount};
+use crate::models::account::{Account, AccountStatus, AccountType, NewAccount, SigningRule};
+use crate::models::account_holder::{AccountHolder, HolderRole};
+use crate::models::balance_snapshot::{BalanceBackfillSummary, BalanceGranularity, BalancePoint};
+use crate::models::bucket::{AccountBucket, BucketProgress};
+use crate::models::overdraft::{DISHONOUR_FEE_CENTS, OVERDRAFT_INTEREST_RATE_BPS};
 
 use super::models::{
-    AccountRest, AccountStatusRest, AccountTypeRest, AccountsRest, NewAccountRest,
+    AccountHolderRest, AccountHoldersRest, AccountRest, AccountStatusRest, AccountTypeRest,
+    AccountsRest, BalanceBackfillRest, BalanceGranularityRest, BalancePointRest,
+    BucketProgressRest, BucketRest, HolderRoleRest, NewAccountRest, OverdraftRest, SigningRuleRest,
 };
 
 impl From<AccountType> for AccountTypeRest {
@@ -10,6 +16,7 @@ impl From<AccountType> for AccountTypeRest {
             AccountType::Savings => AccountTypeRest::Savings,
             AccountType::Transaction => AccountTypeRest::Transaction,
             AccountType::TermDeposit => AccountTypeRest::TermDeposit,
+            AccountType::Loan => AccountTypeRest::Loan,
         }
     }
 }
@@ -36,6 +43,22 @@ impl From<&Account> for AccountRest {
             available_balance_cents: account.available_balance_cents,
             account_name: account.account_name.clone(),
             bsb: account.bsb.clone(),
+            overdraft: (account.overdraft_limit_cents > 0).then(|| OverdraftRest::from(account)),
+            signing_rule: account.signing_rule.into(),
+        }
+    }
+}
+
+impl From<&Account> for OverdraftRest {
+    fn from(account: &Account) -> Self {
+        Self {
+            limit_cents: account.overdraft_limit_cents,
+            used_cents: account.overdrawn_cents(),
+            remaining_cents: account
+                .spendable_cents()
+                .clamp(0, account.overdraft_limit_cents),
+            interest_rate_bps: OVERDRAFT_INTEREST_RATE_BPS,
+            dishonour_fee_cents: DISHONOUR_FEE_CENTS,
         }
     }
 }
@@ -43,7 +66,7 @@ impl From<&Account> for AccountRest {
 impl From<Vec<Account>> for AccountsRest {
     fn from(accounts: Vec<Account>) -> Self {
         Self {
-            accounts: accounts.iter().map(|acc| AccountRest::from(acc)).collect(),
+            accounts: accounts.iter().map(AccountRest::from).collect(),
         }
     }
 }
@@ -54,6 +77,7 @@ impl From<AccountTypeRest> for AccountType {
             AccountTypeRest::Savings => AccountType::Savings,
             AccountTypeRest::Transaction => AccountType::Transaction,
             AccountTypeRest::TermDeposit => AccountType::TermDeposit,
+            AccountTypeRest::Loan => AccountType::Loan,
         }
     }
 }
@@ -71,3 +95,119 @@ impl From<NewAccountRest> for NewAccount {
         }
     }
 }
+
+impl From<BalanceGranularityRest> for BalanceGranularity {
+    fn from(granularity: BalanceGranularityRest) -> Self {
+        match granularity {
+            BalanceGranularityRest::Daily => BalanceGranularity::Daily,
+            BalanceGranularityRest::Weekly => BalanceGranularity::Weekly,
+            BalanceGranularityRest::Monthly => BalanceGranularity::Monthly,
+        }
+    }
+}
+
+impl From<&BalancePoint> for BalancePointRest {
+    fn from(point: &BalancePoint) -> Self {
+        Self {
+            date: point.date.to_string(),
+            balance_cents: point.balance_cents,
+            available_balance_cents: point.available_balance_cents,
+        }
+    }
+}
+
+impl From<BalanceBackfillSummary> for BalanceBackfillRest {
+    fn from(summary: BalanceBackfillSummary) -> Self {
+        Self {
+            account_id: summary.account_id,
+            transactions_updated: summary.transactions_updated,
+            snapshots_written: summary.snapshots_written,
+        }
+    }
+}
+
+impl From<BucketProgress> for BucketProgressRest {
+    fn from(progress: BucketProgress) -> Self {
+        Self {
+            percent: progress.percent,
+            remaining_cents: progress.remaining_cents,
+            days_left: progress.days_left,
+            monthly_needed_cents: progress.monthly_needed_cents,
+        }
+    }
+}
+
+impl From<&AccountBucket> for BucketRest {
+    fn from(bucket: &AccountBucket) -> Self {
+        Self {
+            id: bucket.id,
+            account_id: bucket.account_id,
+            name: bucket.name.clone(),
+            balance_cents: bucket.balance_cents,
+            goal_cents: bucket.goal_cents,
+            target_date: bucket.target_date.map(|d| d.to_string()),
+            is_default: bucket.is_default,
+            progress: bucket
+                .progress(chrono::Utc::now().date_naive())
+                .map(|p| p.into()),
+            created_at: bucket.created_at.to_string(),
+            updated_at: bucket.updated_at.to_string(),
+        }
+    }
+}
+
+impl From<SigningRule> for SigningRuleRest {
+    fn from(signing_rule: SigningRule) -> Self {
+        match signing_rule {
+            SigningRule::EitherToSign => SigningRuleRest::EitherToSign,
+            SigningRule::BothToSign => SigningRuleRest::BothToSign,
+        }
+    }
+}
+
+impl From<SigningRuleRest> for SigningRule {
+    fn from(signing_rule: SigningRuleRest) -> Self {
+        match signing_rule {
+            SigningRuleRest::EitherToSign => SigningRule::EitherToSign,
+            SigningRuleRest::BothToSign => SigningRule::BothToSign,
+        }
+    }
+}
+
+impl From<HolderRole> for HolderRoleRest {
+    fn from(role: HolderRole) -> Self {
+        match role {
+            HolderRole::Owner => HolderRoleRest::Owner,
+            HolderRole::Signatory => HolderRoleRest::Signatory,
+        }
+    }
+}
+
+impl From<HolderRoleRest> for HolderRole {
+    fn from(role: HolderRoleRest) -> Self {
+        match role {
+            HolderRoleRest::Owner => HolderRole::Owner,
+            HolderRoleRest::Signatory => HolderRole::Signatory,
+        }
+    }
+}
+
+impl From<&AccountHolder> for AccountHolderRest {
+    fn from(holder: &AccountHolder) -> Self {
+        Self {
+            id: holder.id,
+            account_id: holder.account_id,
+            customer_id: holder.customer_id,
+            role: holder.holder_role.into(),
+            created_at: holder.created_at.to_string(),
+        }
+    }
+}
+
+impl From<Vec<AccountHolder>> for AccountHoldersRest {
+    fn from(holders: Vec<AccountHolder>) -> Self {
+        Self {
+            holders: holders.iter().map(AccountHolderRest::from).collect(),
+        }
+    }
+}
@@ -1,15 +1,53 @@
 use rand::Rng;
 
+use crate::api::error::ApiError;
+use crate::models::account_holder::{AccountHolder, AccountPermission, FindAccountHolderQuery};
+use crate::models::transaction::BANK_CHARGES_NUMBER;
+use crate::traits::RepoFind;
+
+// never the bank's charges account, fees and interest paid to a customer's account would be theirs
 pub fn get_random_account_number() -> String {
-    let mut an: String = "".to_string();
+    loop {
+        let mut an: String = "".to_string();
+
+        for _ in 1..10 {
+            let num = rand::thread_rng().gen_range(0..10);
+            let str = num.to_string();
+            an.push_str(&str);
+        }
+
+        if an != BANK_CHARGES_NUMBER {
+            return an;
+        }
+    }
+}
+
+// what a customer can do with an account comes from their place on it, not who opened it. meant
+// to be called inside web::block
+pub fn authorise_holder<HR>(
+    holders_repo: &HR,
+    account_id: i32,
+    customer_id: i32,
+    permission: AccountPermission,
+) -> Result<AccountHolder, ApiError>
+where
+    HR: RepoFind<AccountHolder, FindAccountHolderQuery>,
+{
+    let holder = holders_repo
+        .find(FindAccountHolderQuery {
+            account_id: Some(account_id),
+            customer_id: Some(customer_id),
+        })
+        .map_err(|_| ApiError::InternalError)?
+        .into_iter()
+        .find(|h| h.account_id == account_id && h.customer_id == customer_id)
+        .ok_or(ApiError::Unauthorized)?;
 
-    for _ in 1..10 {
-        let num = rand::thread_rng().gen_range(0..10);
-        let str = num.to_string();
-        an.push_str(&str);
+    if !holder.holder_role.allows(permission) {
+        return Err(ApiError::Forbidden);
     }
 
-    an
+    Ok(holder)
 }
 
 #[cfg(test)]
@@ -0,0 +1,892 @@
+use actix_web::http::header::ContentType;
+use actix_web::web::{Data, Path, Query};
+use actix_web::{web, HttpRequest, HttpResponse};
+
+use super::models::{
+    ApprovalNoteRest, ApprovalPolicyRest, ApprovalPolicyUpdateRest, FindTransferApprovalQueryRest,
+    TransferApprovalEventRest, TransferApprovalRest, TransferApprovalWithTrailRest,
+    TransferApprovalsRest,
+};
+use crate::api::accounts::util::authorise_holder;
+use crate::api::audit::util::{new_audit_entry, record_audit, snapshot};
+use crate::api::automation::util::run_automations;
+use crate::api::budgets::util::check_budgets;
+use crate::api::error::ApiError;
+use crate::error::RepoError;
+use crate::models::account_holder::{AccountHolder, AccountPermission, FindAccountHolderQuery};
+use crate::models::approval::{
+    ApprovalDecision, ApprovalExpiryRun, ApprovalPolicy, ApprovalStatus,
+    FindTransferApprovalEventQuery, FindTransferApprovalQuery, NewApprovalPolicy, TransferApproval,
+    TransferApprovalEvent, DEFAULT_EXPIRY_HOURS, MAX_EXPIRY_HOURS,
+};
+use crate::models::audit::{ActorType, AuditAction, AuditEntity, AuditEntry, NewAuditEntry};
+use crate::models::automation::{AutomationRun, AutomationTrigger};
+use crate::models::budget::{BudgetAlert, BudgetAlertDelivered, BudgetCheck};
+use crate::notifications::Notifier;
+use crate::traits::{RepoCreate, RepoDeleteById, RepoFind, RepoGetById, RepoUpdate};
+
+const MAX_NOTE_LEN: usize = 500;
+
+pub async fn get_approval_policy<HR, ApR>(
+    holders_repo: Data<HR>,
+    approvals_repo: Data<ApR>,
+    path: Path<(i32, i32)>,
+) -> Result<HttpResponse, actix_web::Error>
+where
+    HR: RepoFind<AccountHolder, FindAccountHolderQuery>,
+    ApR: RepoGetById<ApprovalPolicy>,
+{
+    let (customer_id, account_id) = path.into_inner();
+
+    println!(
+        "Trying to get approval policy of account {} for customer {}",
+        account_id, customer_id
+    );
+
+    let policy = web::block(move || {
+        authorise_holder(
+            holders_repo.as_ref(),
+            account_id,
+            customer_id,
+            AccountPermission::View,
+        )?;
+
+        approvals_repo
+            .get_by_id(account_id)
+            .map_err(|err| match err {
+                RepoError::NotFound => ApiError::NotFound,
+                _ => ApiError::InternalError,
+            })
+    })
+    .await
+    .map_err(|_| ApiError::InternalError)??;
+
+    Ok(HttpResponse::Ok()
+        .insert_header(ContentType::json())
+        .json(web::Json::<ApprovalPolicyRest>((&policy).into())))
+}
+
+pub async fn set_approval_policy<HR, ApR, AuR>(
+    holders_repo: Data<HR>,
+    approvals_repo: Data<ApR>,
+    audit_repo: Data<AuR>,
+    req: HttpRequest,
+    path: Path<(i32, i32)>,
+    payload: web::Json<ApprovalPolicyUpdateRest>,
+) -> Result<HttpResponse, actix_web::Error>
+where
+    HR: RepoFind<AccountHolder, FindAccountHolderQuery>,
+    ApR: RepoCreate<ApprovalPolicy, NewApprovalPolicy>,
+    AuR: RepoCreate<AuditEntry, NewAuditEntry>,
+{
+    let (customer_id, account_id) = path.into_inner();
+    let payload = payload.into_inner();
+
+    let new_policy = NewApprovalPolicy {
+        account_id,
+        threshold_cents: payload.threshold_cents,
+        expiry_hours: payload.expiry_hours.unwrap_or(DEFAULT_EXPIRY_HOURS),
+    };
+
+    if new_policy.threshold_cents <= 0 || !(1..=MAX_EXPIRY_HOURS).contains(&new_policy.expiry_hours)
+    {
+        return Err(ApiError::BadRequest.into());
+    }
+
+    println!(
+        "Trying to set approval policy of account {} for customer {}",
+        account_id, customer_id
+    );
+
+    let policy = web::block(move || {
+        authorise_holder(
+            holders_repo.as_ref(),
+            account_id,
+            customer_id,
+            AccountPermission::Manage,
+        )?;
+
+        approvals_repo.create(new_policy).map_err(|err| match err {
+            // nobody besides the one holder to approve anything
+            RepoError::Conflict => ApiError::Conflict,
+            _ => ApiError::InternalError,
+        })
+    })
+    .await
+    .map_err(|_| ApiError::InternalError)??;
+
+    let policy_rest: ApprovalPolicyRest = (&policy).into();
+
+    let mut audit_entry = new_audit_entry(
+        &req,
+        ActorType::Customer,
+        customer_id,
+        AuditAction::SetApprovalPolicy,
+        AuditEntity::ApprovalPolicy,
+        Some(account_id),
+    );
+    audit_entry.after_snapshot = snapshot(&policy_rest);
+    record_audit(audit_repo, audit_entry).await;
+
+    Ok(HttpResponse::Ok()
+        .insert_header(ContentType::json())
+        .json(web::Json(policy_rest)))
+}
+
+pub async fn delete_approval_policy<HR, ApR, AuR>(
+    holders_repo: Data<HR>,
+    approvals_repo: Data<ApR>,
+    audit_repo: Data<AuR>,
+    req: HttpRequest,
+    path: Path<(i32, i32)>,
+) -> Result<HttpResponse, actix_web::Error>
+where
+    HR: RepoFind<AccountHolder, FindAccountHolderQuery>,
+    ApR: RepoGetById<ApprovalPolicy> + RepoDeleteById<ApprovalPolicy>,
+    AuR: RepoCreate<AuditEntry, NewAuditEntry>,
+{
+    let (customer_id, account_id) = path.into_inner();
+
+    println!(
+        "Trying to delete approval policy of account {} for customer {}",
+        account_id, customer_id
+    );
+
+    let deleted_policy = web::block(move || {
+        authorise_holder(
+            holders_repo.as_ref(),
+            account_id,
+            customer_id,
+            AccountPermission::Manage,
+        )?;
+
+        let existing_policy = match approvals_repo.get_by_id(account_id) {
+            Ok(policy) => Some(policy),
+            Err(RepoError::NotFound) => None,
+            _ => return Err(ApiError::InternalError),
+        };
+
+        approvals_repo
+            .delete_by_id(account_id)
+            .map_err(|_| ApiError::InternalError)?;
+
+        Ok(existing_policy)
+    })
+    .await
+    .map_err(|_| ApiError::InternalError)??;
+
+    // deleting something that isn't there is a no-op, nothing to audit
+    if let Some(policy) = deleted_policy {
+        let mut audit_entry = new_audit_entry(
+            &req,
+            ActorType::Customer,
+            customer_id,
+            AuditAction::DeleteApprovalPolicy,
+            AuditEntity::ApprovalPolicy,
+            Some(account_id),
+        );
+        audit_entry.before_snapshot = snapshot(&ApprovalPolicyRest::from(&policy));
+        record_audit(audit_repo, audit_entry).await;
+    }
+
+    Ok(HttpResponse::NoContent().body(""))
+}
+
+pub async fn find_transfer_approvals<HR, ApR>(
+    holders_repo: Data<HR>,
+    approvals_repo: Data<ApR>,
+    path: Path<i32>,
+    query: Query<FindTransferApprovalQueryRest>,
+) -> Result<HttpResponse, actix_web::Error>
+where
+    HR: RepoFind<AccountHolder, FindAccountHolderQuery>,
+    ApR: RepoFind<TransferApproval, FindTransferApprovalQuery>,
+{
+    let customer_id = path.into_inner();
+
+    let query = FindTransferApprovalQuery {
+        customer_id,
+        approval_status: query.approval_status.map(ApprovalStatus::from),
+        transaction_id: query.transaction_id,
+    };
+
+    println!(
+        "Trying to get transfer approvals for customer {}",
+        customer_id
+    );
+
+    let holder_query = FindAccountHolderQuery {
+        account_id: None,
+        customer_id: Some(customer_id),
+    };
+
+    let (approvals, holders) = web::block(move || {
+        let approvals = approvals_repo.find(query)?;
+        let holders = holders_repo.find(holder_query)?;
+        Ok((approvals, holders))
+    })
+    .await
+    .map_err(|_| ApiError::InternalError)?
+    .map_err(|_: RepoError| ApiError::InternalError)?;
+
+    // enforced query on holders so should always be fine, but safe > sorry
+    for approval in approvals.iter() {
+        if !holders.iter().any(|h| h.account_id == approval.account_id) {
+            return Err(ApiError::BadRequest.into());
+        }
+    }
+
+    Ok(HttpResponse::Ok()
+        .insert_header(ContentType::json())
+        .json(web::Json::<TransferApprovalsRest>(approvals.into())))
+}
+
+pub async fn get_transfer_approval<HR, ApR>(
+    holders_repo: Data<HR>,
+    approvals_repo: Data<ApR>,
+    path: Path<(i32, i32)>,
+) -> Result<HttpResponse, actix_web::Error>
+where
+    HR: RepoFind<AccountHolder, FindAccountHolderQuery>,
+    ApR: RepoGetById<TransferApproval>
+        + RepoFind<TransferApprovalEvent, FindTransferApprovalEventQuery>,
+{
+    let (customer_id, approval_id) = path.into_inner();
+
+    println!(
+        "Trying to get transfer approval {}, for customer {}",
+        approval_id, customer_id
+    );
+
+    let (approval, events) = web::block(move || {
+        let approval = held_approval(
+            holders_repo.as_ref(),
+            approvals_repo.as_ref(),
+            (customer_id, approval_id),
+            AccountPermission::View,
+        )?;
+
+        let events = approvals_repo
+            .find(FindTransferApprovalEventQuery { approval_id })
+            .map_err(|_| ApiError::InternalError)?;
+
+        Ok::<_, ApiError>((approval, events))
+    })
+    .await
+    .map_err(|_| ApiError::InternalError)??;
+
+    Ok(HttpResponse::Ok()
+        .insert_header(ContentType::json())
+        .json(web::Json(TransferApprovalWithTrailRest {
+            approval: (&approval).into(),
+            trail: events.iter().map(TransferApprovalEventRest::from).collect(),
+        })))
+}
+
+#[allow(clippy::too_many_arguments)]
+pub async fn approve_transfer<HR, ApR, BuR, AtR, N, AuR>(
+    holders_repo: Data<HR>,
+    approvals_repo: Data<ApR>,
+    budgets_repo: Data<BuR>,
+    automation_repo: Data<AtR>,
+    notifier: Data<N>,
+    audit_repo: Data<AuR>,
+    req: HttpRequest,
+    path: Path<(i32, i32)>,
+    payload: web::Json<ApprovalNoteRest>,
+) -> Result<HttpResponse, actix_web::Error>
+where
+    HR: RepoFind<AccountHolder, FindAccountHolderQuery>,
+    ApR: RepoGetById<TransferApproval> + RepoUpdate<TransferApproval, ApprovalDecision>,
+    BuR: RepoCreate<Vec<BudgetAlert>, BudgetCheck> + RepoUpdate<BudgetAlert, BudgetAlertDelivered>,
+    AtR: RepoCreate<Vec<AutomationRun>, AutomationTrigger>,
+    N: Notifier,
+    AuR: RepoCreate<AuditEntry, NewAuditEntry>,
+{
+    let approval = decide_transfer_approval(
+        holders_repo,
+        approvals_repo,
+        audit_repo.clone(),
+        &req,
+        path.into_inner(),
+        payload.into_inner().note,
+        true,
+    )
+    .await?;
+
+    // approving settles internal transfers, the check and the rules skip anything still pending
+    check_budgets(
+        budgets_repo.clone(),
+        notifier.clone(),
+        approval.transaction_id,
+    )
+    .await;
+
+    let trigger = AutomationTrigger::Settled {
+        transaction_id: approval.transaction_id,
+    };
+    run_automations(
+        automation_repo,
+        (budgets_repo, notifier),
+        audit_repo,
+        &req,
+        trigger,
+    )
+    .await;
+
+    Ok(HttpResponse::Ok()
+        .insert_header(ContentType::json())
+        .json(web::Json::<TransferApprovalRest>((&approval).into())))
+}
+
+pub async fn reject_transfer<HR, ApR, AuR>(
+    holders_repo: Data<HR>,
+    approvals_repo: Data<ApR>,
+    audit_repo: Data<AuR>,
+    req: HttpRequest,
+    path: Path<(i32, i32)>,
+    payload: web::Json<ApprovalNoteRest>,
+) -> Result<HttpResponse, actix_web::Error>
+where
+    HR: RepoFind<AccountHolder, FindAccountHolderQuery>,
+    ApR: RepoGetById<TransferApproval> + RepoUpdate<TransferApproval, ApprovalDecision>,
+    AuR: RepoCreate<AuditEntry, NewAuditEntry>,
+{
+    let approval = decide_transfer_approval(
+        holders_repo,
+        approvals_repo,
+        audit_repo,
+        &req,
+        path.into_inner(),
+        payload.into_inner().note,
+        false,
+    )
+    .await?;
+
+    Ok(HttpResponse::Ok()
+        .insert_header(ContentType::json())
+        .json(web::Json::<TransferApprovalRest>((&approval).into())))
+}
+
+pub async fn staff_expire_approvals<ApR, AuR>(
+    approvals_repo: Data<ApR>,
+    audit_repo: Data<AuR>,
+    req: HttpRequest,
+    path: Path<i32>,
+) -> Result<HttpResponse, actix_web::Error>
+where
+    ApR: RepoCreate<Vec<TransferApproval>, ApprovalExpiryRun>,
+    AuR: RepoCreate<AuditEntry, NewAuditEntry>,
+{
+    let staff_id = path.into_inner();
+
+    let run = ApprovalExpiryRun {
+        staff_id,
+        now: chrono::Utc::now().naive_utc(),
+    };
+
+    println!(
+        "Staff {} trying to expire transfer approvals as of {}",
+        staff_id, run.now
+    );
+
+    let expired = web::block(move || approvals_repo.create(run))
+        .await
+        .map_err(|_| ApiError::InternalError)?
+        .map_err(|_| ApiError::InternalError)?;
+
+    for approval in expired.iter() {
+        let mut audit_entry = new_audit_entry(
+            &req,
+            ActorType::Staff,
+            staff_id,
+            AuditAction::ExpireTransferApproval,
+            AuditEntity::TransferApproval,
+            Some(approval.id),
+        );
+        audit_entry.after_snapshot = snapshot(&TransferApprovalRest::from(approval));
+        record_audit(audit_repo.clone(), audit_entry).await;
+    }
+
+    Ok(HttpResponse::Ok()
+        .insert_header(ContentType::json())
+        .json(web::Json::<TransferApprovalsRest>(expired.into())))
+}
+
+// the approval, if it's on an account the customer holds and their role on it allows what they're
+// doing
+fn held_approval<HR, ApR>(
+    holders_repo: &HR,
+    approvals_repo: &ApR,
+    (customer_id, approval_id): (i32, i32),
+    permission: AccountPermission,
+) -> Result<TransferApproval, ApiError>
+where
+    HR: RepoFind<AccountHolder, FindAccountHolderQuery>,
+    ApR: RepoGetById<TransferApproval>,
+{
+    let approval = approvals_repo
+        .get_by_id(approval_id)
+        .map_err(|err| match err {
+            RepoError::NotFound => ApiError::NotFound,
+            _ => ApiError::InternalError,
+        })?;
+
+    authorise_holder(holders_repo, approval.account_id, customer_id, permission)?;
+
+    Ok(approval)
+}
+
+// any holder who can transact on the account can reject, withdrawing their own transfer included.
+// approving has to be someone other than whoever made it
+async fn decide_transfer_approval<HR, ApR, AuR>(
+    holders_repo: Data<HR>,
+    approvals_repo: Data<ApR>,
+    audit_repo: Data<AuR>,
+    req: &HttpRequest,
+    (customer_id, approval_id): (i32, i32),
+    note: Option<String>,
+    approve: bool,
+) -> Result<TransferApproval, ApiError>
+where
+    HR: RepoFind<AccountHolder, FindAccountHolderQuery>,
+    ApR: RepoGetById<TransferApproval> + RepoUpdate<TransferApproval, ApprovalDecision>,
+    AuR: RepoCreate<AuditEntry, NewAuditEntry>,
+{
+    if note.as_ref().is_some_and(|note| note.len() > MAX_NOTE_LEN) {
+        return Err(ApiError::BadRequest);
+    }
+
+    let decision = ApprovalDecision {
+        approve,
+        customer_id,
+        note,
+    };
+
+    println!(
+        "Trying to {} transfer approval {} for customer {}",
+        if approve { "approve" } else { "reject" },
+        approval_id,
+        customer_id
+    );
+
+    let (before, after) = web::block(move || {
+        let before = held_approval(
+            holders_repo.as_ref(),
+            approvals_repo.as_ref(),
+            (customer_id, approval_id),
+            AccountPermission::Transact,
+        )?;
+
+        if approve && before.requested_by == customer_id {
+            return Err(ApiError::Forbidden);
+        }
+
+        if before.approval_status != ApprovalStatus::Pending {
+            return Err(ApiError::Conflict);
+        }
+
+        let after = approvals_repo
+            .update(approval_id, decision)
+            .map_err(|err| match err {
+                RepoError::NotFound => ApiError::NotFound,
+                // decided or expired under us, or an approved transfer can no longer be made
+                RepoError::Conflict => ApiError::Conflict,
+                _ => ApiError::InternalError,
+            })?;
+
+        Ok((before, after))
+    })
+    .await
+    .map_err(|_| ApiError::InternalError)??;
+
+    let mut audit_entry = new_audit_entry(
+        req,
+        ActorType::Customer,
+        customer_id,
+        AuditAction::DecideTransferApproval,
+        AuditEntity::TransferApproval,
+        Some(approval_id),
+    );
+    audit_entry.before_snapshot = snapshot(&TransferApprovalRest::from(&before));
+    audit_entry.after_snapshot = snapshot(&TransferApprovalRest::from(&after));
+    record_audit(audit_repo, audit_entry).await;
+
+    Ok(after)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::audit::util::appended;
+    use crate::fixtures::{dt, holders_repo};
+    use crate::{
+        api::{
+            approvals::{
+                handlers::{
+                    approve_transfer, get_transfer_approval, reject_transfer, set_approval_policy,
+                },
+                models::{
+                    ApprovalNoteRest, ApprovalPolicyRest, ApprovalPolicyUpdateRest,
+                    ApprovalStatusRest, TransferApprovalRest, TransferApprovalWithTrailRest,
+                },
+            },
+            error::ApiError,
+        },
+        error::RepoError,
+        models::{
+            account_holder::{AccountHolder, FindAccountHolderQuery, HolderRole},
+            approval::{
+                ApprovalDecision, ApprovalPolicy, ApprovalStatus, FindTransferApprovalEventQuery,
+                NewApprovalPolicy, TransferApproval, TransferApprovalEvent, DEFAULT_EXPIRY_HOURS,
+            },
+            audit::{ActorType, AuditEntry, NewAuditEntry},
+            automation::{AutomationRun, AutomationTrigger},
+            budget::{BudgetAlert, BudgetAlertDelivered, BudgetCheck},
+        },
+        notifications::MockNotifier,
+        traits::{MockRepoCreate, MockRepoFind, RepoCreate, RepoFind, RepoGetById, RepoUpdate},
+    };
+
+    use actix_web::{
+        body::to_bytes,
+        http::StatusCode,
+        test,
+        web::{Data, Json},
+    };
+
+    use mockall::{mock, predicate::eq};
+
+    mock! {
+        pub ApR { }
+        impl RepoGetById<TransferApproval> for ApR {
+            fn get_by_id(&self, id: i32) -> Result<TransferApproval, RepoError>;
+        }
+        impl RepoUpdate<TransferApproval, ApprovalDecision> for ApR {
+            fn update(&self, id: i32, update: ApprovalDecision) -> Result<TransferApproval, RepoError>;
+        }
+        impl RepoFind<TransferApprovalEvent, FindTransferApprovalEventQuery> for ApR {
+            fn find(&self, query: FindTransferApprovalEventQuery) -> Result<Vec<TransferApprovalEvent>, RepoError>;
+        }
+    }
+
+    mock! {
+        pub BuR { }
+        impl RepoCreate<Vec<BudgetAlert>, BudgetCheck> for BuR {
+            fn create(&self, new: BudgetCheck) -> Result<Vec<BudgetAlert>, RepoError>;
+        }
+        impl RepoUpdate<BudgetAlert, BudgetAlertDelivered> for BuR {
+            fn update(&self, id: i32, update: BudgetAlertDelivered) -> Result<BudgetAlert, RepoError>;
+        }
+    }
+
+    // customer_id holds account 52 and nobody else holds anything
+    // made by customer 5, waiting on someone else
+    fn pending_approval() -> TransferApproval {
+        TransferApproval {
+            id: 7,
+            transaction_id: 70,
+            account_id: 52,
+            requested_by: 5,
+            threshold_cents: 1_000_000,
+            approval_status: ApprovalStatus::Pending,
+            expires_at: dt() + chrono::Duration::hours(48),
+            decided_by: None,
+            decided_at: None,
+            created_at: dt(),
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_set_approval_policy_success() {
+        let mut mock_approvals_repo = MockRepoCreate::<ApprovalPolicy, NewApprovalPolicy>::new();
+        mock_approvals_repo
+            .expect_create()
+            .with(eq(NewApprovalPolicy {
+                account_id: 52,
+                threshold_cents: 1_000_000,
+                expiry_hours: DEFAULT_EXPIRY_HOURS,
+            }))
+            .times(1)
+            .returning(|new| {
+                Ok(ApprovalPolicy {
+                    account_id: new.account_id,
+                    threshold_cents: new.threshold_cents,
+                    expiry_hours: new.expiry_hours,
+                    updated_at: dt(),
+                })
+            });
+
+        let mut mock_audit_repo = MockRepoCreate::<AuditEntry, NewAuditEntry>::new();
+        mock_audit_repo
+            .expect_create()
+            .withf(|entry| {
+                entry.action == "set_approval_policy"
+                    && entry.entity_id == Some(52)
+                    && entry.after_snapshot.is_some()
+            })
+            .times(1)
+            .returning(|entry| Ok(appended(entry)));
+
+        let res = set_approval_policy(
+            Data::new(holders_repo([(52, 5, HolderRole::Owner)])),
+            Data::new(mock_approvals_repo),
+            Data::new(mock_audit_repo),
+            test::TestRequest::put().to_http_request(),
+            (5, 52).into(),
+            Json(ApprovalPolicyUpdateRest {
+                threshold_cents: 1_000_000,
+                expiry_hours: None,
+            }),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(StatusCode::OK, res.status());
+
+        let actual: ApprovalPolicyRest =
+            serde_json::from_slice(&to_bytes(res.into_body()).await.unwrap()).unwrap();
+        assert_eq!(
+            ApprovalPolicyRest {
+                account_id: 52,
+                threshold_cents: 1_000_000,
+                expiry_hours: DEFAULT_EXPIRY_HOURS,
+                updated_at: "2023-09-14 09:10:11".to_string(),
+            },
+            actual
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_set_approval_policy_expiry_too_long_bad_request_error() {
+        let res = set_approval_policy(
+            Data::new(MockRepoFind::<AccountHolder, FindAccountHolderQuery>::new()),
+            Data::new(MockRepoCreate::<ApprovalPolicy, NewApprovalPolicy>::new()),
+            Data::new(MockRepoCreate::<AuditEntry, NewAuditEntry>::new()),
+            test::TestRequest::put().to_http_request(),
+            (5, 52).into(),
+            Json(ApprovalPolicyUpdateRest {
+                threshold_cents: 1_000_000,
+                expiry_hours: Some(721),
+            }),
+        )
+        .await;
+
+        assert!(res.is_err_and(|e| { e.to_string() == ApiError::BadRequest.to_string() }));
+    }
+
+    #[actix_web::test]
+    async fn test_set_approval_policy_sole_holder_conflict_error() {
+        let mut mock_approvals_repo = MockRepoCreate::<ApprovalPolicy, NewApprovalPolicy>::new();
+        mock_approvals_repo
+            .expect_create()
+            .times(1)
+            .returning(|_| Err(RepoError::Conflict));
+
+        let res = set_approval_policy(
+            Data::new(holders_repo([(52, 5, HolderRole::Owner)])),
+            Data::new(mock_approvals_repo),
+            Data::new(MockRepoCreate::<AuditEntry, NewAuditEntry>::new()),
+            test::TestRequest::put().to_http_request(),
+            (5, 52).into(),
+            Json(ApprovalPolicyUpdateRest {
+                threshold_cents: 1_000_000,
+                expiry_hours: Some(24),
+            }),
+        )
+        .await;
+
+        assert!(res.is_err_and(|e| { e.to_string() == ApiError::Conflict.to_string() }));
+    }
+
+    #[actix_web::test]
+    async fn test_set_approval_policy_signatory_forbidden_error() {
+        let res = set_approval_policy(
+            Data::new(holders_repo([(52, 6, HolderRole::Signatory)])),
+            Data::new(MockRepoCreate::<ApprovalPolicy, NewApprovalPolicy>::new()),
+            Data::new(MockRepoCreate::<AuditEntry, NewAuditEntry>::new()),
+            test::TestRequest::put().to_http_request(),
+            (6, 52).into(),
+            Json(ApprovalPolicyUpdateRest {
+                threshold_cents: 1_000_000,
+                expiry_hours: None,
+            }),
+        )
+        .await;
+
+        assert!(res.is_err_and(|e| { e.to_string() == ApiError::Forbidden.to_string() }));
+    }
+
+    #[actix_web::test]
+    async fn test_get_transfer_approval_with_trail() {
+        let mut mock_approvals_repo = MockApR::new();
+        mock_approvals_repo
+            .expect_get_by_id()
+            .with(eq(7))
+            .times(1)
+            .returning(|_| Ok(pending_approval()));
+        mock_approvals_repo
+            .expect_find()
+            .with(eq(FindTransferApprovalEventQuery { approval_id: 7 }))
+            .times(1)
+            .returning(|_| {
+                Ok(vec![TransferApprovalEvent {
+                    id: 1,
+                    approval_id: 7,
+                    approval_status: ApprovalStatus::Pending,
+                    note: None,
+                    actor_type: ActorType::Customer,
+                    actor_id: 5,
+                    created_at: dt(),
+                }])
+            });
+
+        let res = get_transfer_approval(
+            Data::new(holders_repo([(52, 6, HolderRole::Signatory)])),
+            Data::new(mock_approvals_repo),
+            (6, 7).into(),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(StatusCode::OK, res.status());
+
+        let actual: TransferApprovalWithTrailRest =
+            serde_json::from_slice(&to_bytes(res.into_body()).await.unwrap()).unwrap();
+        assert_eq!(ApprovalStatusRest::Pending, actual.approval.approval_status);
+        assert_eq!(1, actual.trail.len());
+        assert_eq!(5, actual.trail[0].actor_id);
+    }
+
+    #[actix_web::test]
+    async fn test_get_transfer_approval_not_holder_unauthorized_error() {
+        let mut mock_approvals_repo = MockApR::new();
+        mock_approvals_repo
+            .expect_get_by_id()
+            .times(1)
+            .returning(|_| Ok(pending_approval()));
+
+        let res = get_transfer_approval(
+            Data::new(holders_repo([(52, 6, HolderRole::Owner)])),
+            Data::new(mock_approvals_repo),
+            (9, 7).into(),
+        )
+        .await;
+
+        assert!(res.is_err_and(|e| { e.to_string() == ApiError::Unauthorized.to_string() }));
+    }
+
+    #[actix_web::test]
+    async fn test_approve_transfer_own_transfer_forbidden_error() {
+        let mut mock_approvals_repo = MockApR::new();
+        mock_approvals_repo
+            .expect_get_by_id()
+            .times(1)
+            .returning(|_| Ok(pending_approval()));
+
+        let res = approve_transfer(
+            Data::new(holders_repo([(52, 5, HolderRole::Owner)])),
+            Data::new(mock_approvals_repo),
+            Data::new(MockBuR::new()),
+            Data::new(MockRepoCreate::<Vec<AutomationRun>, AutomationTrigger>::new()),
+            Data::new(MockNotifier::new()),
+            Data::new(MockRepoCreate::<AuditEntry, NewAuditEntry>::new()),
+            test::TestRequest::post().to_http_request(),
+            (5, 7).into(),
+            Json(ApprovalNoteRest { note: None }),
+        )
+        .await;
+
+        assert!(res.is_err_and(|e| { e.to_string() == ApiError::Forbidden.to_string() }));
+    }
+
+    #[actix_web::test]
+    async fn test_approve_transfer_already_decided_conflict_error() {
+        let mut mock_approvals_repo = MockApR::new();
+        mock_approvals_repo
+            .expect_get_by_id()
+            .times(1)
+            .returning(|_| {
+                Ok(TransferApproval {
+                    approval_status: ApprovalStatus::Rejected,
+                    decided_by: Some(5),
+                    decided_at: Some(dt()),
+                    ..pending_approval()
+                })
+            });
+
+        let res = approve_transfer(
+            Data::new(holders_repo([(52, 6, HolderRole::Signatory)])),
+            Data::new(mock_approvals_repo),
+            Data::new(MockBuR::new()),
+            Data::new(MockRepoCreate::<Vec<AutomationRun>, AutomationTrigger>::new()),
+            Data::new(MockNotifier::new()),
+            Data::new(MockRepoCreate::<AuditEntry, NewAuditEntry>::new()),
+            test::TestRequest::post().to_http_request(),
+            (6, 7).into(),
+            Json(ApprovalNoteRest { note: None }),
+        )
+        .await;
+
+        assert!(res.is_err_and(|e| { e.to_string() == ApiError::Conflict.to_string() }));
+    }
+
+    #[actix_web::test]
+    async fn test_reject_transfer_own_transfer_success() {
+        let mut mock_approvals_repo = MockApR::new();
+        mock_approvals_repo
+            .expect_get_by_id()
+            .times(1)
+            .returning(|_| Ok(pending_approval()));
+        mock_approvals_repo
+            .expect_update()
+            .with(
+                eq(7),
+                eq(ApprovalDecision {
+                    approve: false,
+                    customer_id: 5,
+                    note: Some("wrong account".to_string()),
+                }),
+            )
+            .times(1)
+            .returning(|_, _| {
+                Ok(TransferApproval {
+                    approval_status: ApprovalStatus::Rejected,
+                    decided_by: Some(5),
+                    decided_at: Some(dt()),
+                    ..pending_approval()
+                })
+            });
+
+        let mut mock_audit_repo = MockRepoCreate::<AuditEntry, NewAuditEntry>::new();
+        mock_audit_repo
+            .expect_create()
+            .withf(|entry| {
+                entry.action == "decide_transfer_approval"
+                    && entry.entity_id == Some(7)
+                    && entry.before_snapshot.is_some()
+                    && entry.after_snapshot.is_some()
+            })
+            .times(1)
+            .returning(|entry| Ok(appended(entry)));
+
+        let res = reject_transfer(
+            Data::new(holders_repo([(52, 5, HolderRole::Owner)])),
+            Data::new(mock_approvals_repo),
+            Data::new(mock_audit_repo),
+            test::TestRequest::post().to_http_request(),
+            (5, 7).into(),
+            Json(ApprovalNoteRest {
+                note: Some("wrong account".to_string()),
+            }),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(StatusCode::OK, res.status());
+
+        let actual: TransferApprovalRest =
+            serde_json::from_slice(&to_bytes(res.into_body()).await.unwrap()).unwrap();
+        assert_eq!(ApprovalStatusRest::Rejected, actual.approval_status);
+        assert_eq!(Some(5), actual.decided_by);
+    }
+}
@@ -0,0 +1,73 @@
+pub mod handlers;
+pub mod models;
+pub mod transform;
+
+use actix_web::web;
+
+use crate::{
+    api::approvals,
+    models::{
+        account_holder::{AccountHolder, FindAccountHolderQuery},
+        approval::{
+            ApprovalDecision, ApprovalExpiryRun, ApprovalPolicy, FindTransferApprovalEventQuery,
+            FindTransferApprovalQuery, NewApprovalPolicy, TransferApproval, TransferApprovalEvent,
+        },
+        audit::{AuditEntry, NewAuditEntry},
+        automation::{AutomationRun, AutomationTrigger},
+        budget::{BudgetAlert, BudgetAlertDelivered, BudgetCheck},
+    },
+    notifications::Notifier,
+    traits::{RepoCreate, RepoDeleteById, RepoFind, RepoGetById, RepoUpdate},
+};
+
+pub fn configure_approvals_api<HR, ApR, BuR, AtR, N, AuR>(cfg: &mut web::ServiceConfig)
+where
+    HR: RepoFind<AccountHolder, FindAccountHolderQuery>,
+    ApR: RepoGetById<ApprovalPolicy>
+        + RepoCreate<ApprovalPolicy, NewApprovalPolicy>
+        + RepoDeleteById<ApprovalPolicy>
+        + RepoFind<TransferApproval, FindTransferApprovalQuery>
+        + RepoGetById<TransferApproval>
+        + RepoFind<TransferApprovalEvent, FindTransferApprovalEventQuery>
+        + RepoUpdate<TransferApproval, ApprovalDecision>
+        + RepoCreate<Vec<TransferApproval>, ApprovalExpiryRun>,
+    BuR: RepoCreate<Vec<BudgetAlert>, BudgetCheck> + RepoUpdate<BudgetAlert, BudgetAlertDelivered>,
+    AtR: RepoCreate<Vec<AutomationRun>, AutomationTrigger>,
+    N: Notifier,
+    AuR: RepoCreate<AuditEntry, NewAuditEntry>,
+{
+    cfg.service(
+        web::scope("/api/customers/{customer_id}/approval-policies").service(
+            web::resource("/{account_id}")
+                .route(web::get().to(approvals::handlers::get_approval_policy::<HR, ApR>))
+                .route(web::put().to(approvals::handlers::set_approval_policy::<HR, ApR, AuR>))
+                .route(
+                    web::delete().to(approvals::handlers::delete_approval_policy::<HR, ApR, AuR>),
+                ),
+        ),
+    )
+    .service(
+        web::scope("/api/customers/{customer_id}/transfer-approvals")
+            .service(
+                web::resource("")
+                    .route(web::get().to(approvals::handlers::find_transfer_approvals::<HR, ApR>)),
+            )
+            .service(
+                web::resource("/{approval_id}")
+                    .route(web::get().to(approvals::handlers::get_transfer_approval::<HR, ApR>)),
+            )
+            .service(web::resource("/{approval_id}/approve").route(
+                web::post().to(approvals::handlers::approve_transfer::<HR, ApR, BuR, AtR, N, AuR>),
+            ))
+            .service(
+                web::resource("/{approval_id}/reject")
+                    .route(web::post().to(approvals::handlers::reject_transfer::<HR, ApR, AuR>)),
+            ),
+    )
+    .service(
+        web::scope("/api/staff/{staff_id}/transfer-approvals").service(
+            web::resource("/expiry-run")
+                .route(web::post().to(approvals::handlers::staff_expire_approvals::<ApR, AuR>)),
+        ),
+    );
+}
@@ -0,0 +1,89 @@
+use serde::{Deserialize, Serialize};
+
+use crate::api::audit::models::ActorTypeRest;
+
+#[derive(Deserialize, Serialize, Copy, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum ApprovalStatusRest {
+    Pending,
+    Approved,
+    Rejected,
+    Expired,
+}
+
+#[cfg_attr(test, derive(Deserialize, PartialEq, Debug))]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApprovalPolicyRest {
+    pub account_id: i32,
+    pub threshold_cents: i64,
+    pub expiry_hours: i32,
+    pub updated_at: String,
+}
+
+#[cfg_attr(test, derive(Serialize))]
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ApprovalPolicyUpdateRest {
+    pub threshold_cents: i64,
+    // DEFAULT_EXPIRY_HOURS if not given
+    pub expiry_hours: Option<i32>,
+}
+
+#[cfg_attr(test, derive(Deserialize, PartialEq, Debug))]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransferApprovalRest {
+    pub id: i32,
+    pub transaction_id: i32,
+    pub account_id: i32,
+    pub requested_by: i32,
+    pub threshold_cents: i64,
+    pub approval_status: ApprovalStatusRest,
+    pub expires_at: String,
+    pub decided_by: Option<i32>,
+    pub decided_at: Option<String>,
+    pub created_at: String,
+}
+
+#[cfg_attr(test, derive(Deserialize))]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransferApprovalsRest {
+    pub approvals: Vec<TransferApprovalRest>,
+}
+
+#[cfg_attr(test, derive(Deserialize, PartialEq, Debug))]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransferApprovalEventRest {
+    pub id: i32,
+    pub approval_id: i32,
+    pub approval_status: ApprovalStatusRest,
+    pub note: Option<String>,
+    pub actor_type: ActorTypeRest,
+    pub actor_id: i32,
+    pub created_at: String,
+}
+
+#[cfg_attr(test, derive(Deserialize))]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransferApprovalWithTrailRest {
+    pub approval: TransferApprovalRest,
+    pub trail: Vec<TransferApprovalEventRest>,
+}
+
+#[cfg_attr(test, derive(Serialize))]
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ApprovalNoteRest {
+    pub note: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FindTransferApprovalQueryRest {
+    pub approval_status: Option<ApprovalStatusRest>,
+    pub transaction_id: Option<i32>,
+}
@@ -0,0 +1,81 @@
+use crate::models::approval::{
+    ApprovalPolicy, ApprovalStatus, TransferApproval, TransferApprovalEvent,
+};
+use crate::util::string_opt_from_naive_dt_opt;
+
+use super::models::{
+    ApprovalPolicyRest, ApprovalStatusRest, TransferApprovalEventRest, TransferApprovalRest,
+    TransferApprovalsRest,
+};
+
+impl From<ApprovalStatus> for ApprovalStatusRest {
+    fn from(status: ApprovalStatus) -> Self {
+        match status {
+            ApprovalStatus::Pending => ApprovalStatusRest::Pending,
+            ApprovalStatus::Approved => ApprovalStatusRest::Approved,
+            ApprovalStatus::Rejected => ApprovalStatusRest::Rejected,
+            ApprovalStatus::Expired => ApprovalStatusRest::Expired,
+        }
+    }
+}
+
+impl From<ApprovalStatusRest> for ApprovalStatus {
+    fn from(status: ApprovalStatusRest) -> Self {
+        match status {
+            ApprovalStatusRest::Pending => ApprovalStatus::Pending,
+            ApprovalStatusRest::Approved => ApprovalStatus::Approved,
+            ApprovalStatusRest::Rejected => ApprovalStatus::Rejected,
+            ApprovalStatusRest::Expired => ApprovalStatus::Expired,
+        }
+    }
+}
+
+impl From<&ApprovalPolicy> for ApprovalPolicyRest {
+    fn from(policy: &ApprovalPolicy) -> Self {
+        Self {
+            account_id: policy.account_id,
+            threshold_cents: policy.threshold_cents,
+            expiry_hours: policy.expiry_hours,
+            updated_at: policy.updated_at.to_string(),
+        }
+    }
+}
+
+impl From<&TransferApproval> for TransferApprovalRest {
+    fn from(approval: &TransferApproval) -> Self {
+        Self {
+            id: approval.id,
+            transaction_id: approval.transaction_id,
+            account_id: approval.account_id,
+            requested_by: approval.requested_by,
+            threshold_cents: approval.threshold_cents,
+            approval_status: approval.approval_status.into(),
+            expires_at: approval.expires_at.to_string(),
+            decided_by: approval.decided_by,
+            decided_at: string_opt_from_naive_dt_opt(approval.decided_at),
+            created_at: approval.created_at.to_string(),
+        }
+    }
+}
+
+impl From<Vec<TransferApproval>> for TransferApprovalsRest {
+    fn from(approvals: Vec<TransferApproval>) -> Self {
+        Self {
+            approvals: approvals.iter().map(TransferApprovalRest::from).collect(),
+        }
+    }
+}
+
+impl From<&TransferApprovalEvent> for TransferApprovalEventRest {
+    fn from(event: &TransferApprovalEvent) -> Self {
+        Self {
+            id: event.id,
+            approval_id: event.approval_id,
+            approval_status: event.approval_status.into(),
+            note: event.note.clone(),
+            actor_type: event.actor_type.into(),
+            actor_id: event.actor_id,
+            created_at: event.created_at.to_string(),
+        }
+    }
+}
@@ -0,0 +1,263 @@
+use actix_web::http::header::ContentType;
+use actix_web::web::{Data, Path, Query};
+use actix_web::{web, HttpResponse};
+
+use super::models::{
+    AuditEntriesRest, AuditVerificationRest, FindAuditQueryRest, VerifyAuditQueryRest,
+};
+use crate::api::error::ApiError;
+use crate::models::audit::{verify_audit_chain, AuditEntry, FindAuditQuery};
+use crate::traits::RepoFind;
+
+const DEFAULT_LIMIT: i64 = 50;
+const MAX_LIMIT: i64 = 500;
+
+fn clamp_limit(limit: Option<i64>) -> i64 {
+    limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT)
+}
+
+pub async fn find_audit_entries<AuR>(
+    audit_repo: Data<AuR>,
+    path: Path<i32>,
+    query: Query<FindAuditQueryRest>,
+) -> Result<HttpResponse, actix_web::Error>
+where
+    AuR: RepoFind<AuditEntry, FindAuditQuery>,
+{
+    let staff_id = path.into_inner();
+    let query = query.into_inner();
+
+    let query = FindAuditQuery {
+        actor_type: query.actor_type.map(|at| at.into()),
+        actor_id: query.actor_id,
+        action: query.action,
+        entity_type: query.entity_type,
+        entity_id: query.entity_id,
+        from_id: query.from_id,
+        limit: clamp_limit(query.limit),
+    };
+
+    println!("Trying to get audit entries for staff {}", staff_id);
+
+    let entries = web::block(move || audit_repo.find(query))
+        .await
+        .map_err(|_| ApiError::InternalError)?
+        .map_err(|_| ApiError::InternalError)?;
+
+    Ok(HttpResponse::Ok()
+        .insert_header(ContentType::json())
+        .json(web::Json::<AuditEntriesRest>(entries.into())))
+}
+
+pub async fn verify_audit_log<AuR>(
+    audit_repo: Data<AuR>,
+    path: Path<i32>,
+    query: Query<VerifyAuditQueryRest>,
+) -> Result<HttpResponse, actix_web::Error>
+where
+    AuR: RepoFind<AuditEntry, FindAuditQuery>,
+{
+    let staff_id = path.into_inner();
+
+    let query = FindAuditQuery {
+        actor_type: None,
+        actor_id: None,
+        action: None,
+        entity_type: None,
+        entity_id: None,
+        from_id: query.from_id,
+        limit: clamp_limit(query.limit),
+    };
+
+    println!(
+        "Trying to verify audit log from {:?} for staff {}",
+        query.from_id, staff_id
+    );
+
+    let entries = web::block(move || audit_repo.find(query))
+        .await
+        .map_err(|_| ApiError::InternalError)?
+        .map_err(|_| ApiError::InternalError)?;
+
+    let first_invalid_id = verify_audit_chain(&entries);
+
+    if let Some(id) = first_invalid_id {
+        println!("Audit log chain broken at entry {}", id);
+    }
+
+    Ok(HttpResponse::Ok()
+        .insert_header(ContentType::json())
+        .json(web::Json(AuditVerificationRest {
+            valid: first_invalid_id.is_none(),
+            first_invalid_id,
+            entries_checked: entries.len(),
+            last_id: entries.last().map(|e| e.id),
+        })))
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{
+        http::StatusCode,
+        test,
+        web::{self, Data},
+        App,
+    };
+    use chrono::NaiveDate;
+    use mockall::predicate::eq;
+
+    use crate::{
+        api::audit::{
+            handlers::{find_audit_entries, verify_audit_log},
+            models::{ActorTypeRest, AuditEntriesRest, AuditVerificationRest},
+        },
+        error::RepoError,
+        models::audit::{ActorType, AuditEntry, FindAuditQuery, NewAuditEntry, GENESIS_HASH},
+        traits::MockRepoFind,
+    };
+
+    fn entry(id: i32, prev_hash: &str) -> AuditEntry {
+        let mut new_entry = NewAuditEntry {
+            actor_type: ActorType::Customer,
+            actor_id: 5,
+            action: "delete_account".to_string(),
+            entity_type: "account".to_string(),
+            entity_id: Some(50),
+            before_snapshot: Some("{\"id\":50}".to_string()),
+            after_snapshot: None,
+            request_method: "DELETE".to_string(),
+            request_path: "/api/customers/5/accounts/50".to_string(),
+            request_ip: Some("127.0.0.1".to_string()),
+            user_agent: None,
+            created_at: NaiveDate::from_ymd_opt(2023, 8, 2)
+                .unwrap()
+                .and_hms_opt(9, 10, 11)
+                .unwrap(),
+            prev_hash: "".to_string(),
+            hash: "".to_string(),
+        };
+        new_entry.seal(prev_hash.to_string());
+
+        AuditEntry {
+            id,
+            actor_type: new_entry.actor_type,
+            actor_id: new_entry.actor_id,
+            action: new_entry.action,
+            entity_type: new_entry.entity_type,
+            entity_id: new_entry.entity_id,
+            before_snapshot: new_entry.before_snapshot,
+            after_snapshot: new_entry.after_snapshot,
+            request_method: new_entry.request_method,
+            request_path: new_entry.request_path,
+            request_ip: new_entry.request_ip,
+            user_agent: new_entry.user_agent,
+            created_at: new_entry.created_at,
+            prev_hash: new_entry.prev_hash,
+            hash: new_entry.hash,
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_find_audit_entries_success() {
+        let expected_query = FindAuditQuery {
+            actor_type: Some(ActorType::Customer),
+            actor_id: Some(5),
+            action: None,
+            entity_type: Some("account".to_string()),
+            entity_id: None,
+            from_id: None,
+            limit: 50,
+        };
+
+        let mut mock_audit_repo = MockRepoFind::<AuditEntry, FindAuditQuery>::new();
+        mock_audit_repo
+            .expect_find()
+            .with(eq(expected_query))
+            .times(1)
+            .returning(|_| Ok(vec![entry(1, GENESIS_HASH)]));
+
+        let app = test::init_service(App::new().app_data(Data::new(mock_audit_repo)).service(
+            web::resource("/staff/{staff_id}/audit").route(
+                web::get().to(find_audit_entries::<MockRepoFind<AuditEntry, FindAuditQuery>>),
+            ),
+        ))
+        .await;
+
+        let resp = test::TestRequest::get()
+            .uri("/staff/1/audit?actorType=customer&actorId=5&entityType=account")
+            .send_request(&app)
+            .await;
+
+        assert_eq!(StatusCode::OK, resp.status());
+
+        let actual: AuditEntriesRest = test::read_body_json(resp).await;
+        assert_eq!(1, actual.entries.len());
+        assert_eq!(ActorTypeRest::Customer, actual.entries[0].actor_type);
+        assert_eq!(Some(50), actual.entries[0].entity_id);
+    }
+
+    #[actix_web::test]
+    async fn test_find_audit_entries_internal_error() {
+        let mut mock_audit_repo = MockRepoFind::<AuditEntry, FindAuditQuery>::new();
+        mock_audit_repo
+            .expect_find()
+            .times(1)
+            .returning(|_| Err(RepoError::Other));
+
+        let app = test::init_service(App::new().app_data(Data::new(mock_audit_repo)).service(
+            web::resource("/staff/{staff_id}/audit").route(
+                web::get().to(find_audit_entries::<MockRepoFind<AuditEntry, FindAuditQuery>>),
+            ),
+        ))
+        .await;
+
+        let resp = test::TestRequest::get()
+            .uri("/staff/1/audit")
+            .send_request(&app)
+            .await;
+
+        assert_eq!(StatusCode::INTERNAL_SERVER_ERROR, resp.status());
+    }
+
+    #[actix_web::test]
+    async fn test_verify_audit_log_detects_tampering() {
+        let mut mock_audit_repo = MockRepoFind::<AuditEntry, FindAuditQuery>::new();
+        mock_audit_repo
+            .expect_find()
+            .withf(|q| q.from_id == Some(1) && q.limit == 500)
+            .times(1)
+            .returning(|_| {
+                let first = entry(1, GENESIS_HASH);
+                let mut second = entry(2, &first.hash);
+                let third = entry(3, &second.hash);
+                second.actor_id = 6;
+                Ok(vec![first, second, third])
+            });
+
+        let app =
+            test::init_service(App::new().app_data(Data::new(mock_audit_repo)).service(
+                web::resource("/staff/{staff_id}/audit/verify").route(
+                    web::get().to(verify_audit_log::<MockRepoFind<AuditEntry, FindAuditQuery>>),
+                ),
+            ))
+            .await;
+
+        let resp = test::TestRequest::get()
+            .uri("/staff/1/audit/verify?fromId=1&limit=1000")
+            .send_request(&app)
+            .await;
+
+        assert_eq!(StatusCode::OK, resp.status());
+
+        let actual: AuditVerificationRest = test::read_body_json(resp).await;
+        assert_eq!(
+            AuditVerificationRest {
+                valid: false,
+                first_invalid_id: Some(2),
+                entries_checked: 3,
+                last_id: Some(3),
+            },
+            actual
+        );
+    }
+}
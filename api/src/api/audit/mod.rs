@@ -0,0 +1,28 @@
+pub mod handlers;
+pub mod models;
+pub mod transform;
+pub mod util;
+
+use actix_web::web;
+
+use crate::{
+    api::audit,
+    models::audit::{AuditEntry, FindAuditQuery},
+    traits::RepoFind,
+};
+
+pub fn configure_audit_api<AuR>(cfg: &mut web::ServiceConfig)
+where
+    AuR: RepoFind<AuditEntry, FindAuditQuery>,
+{
+    cfg.service(
+        web::scope("/api/staff/{staff_id}/audit")
+            .service(
+                web::resource("").route(web::get().to(audit::handlers::find_audit_entries::<AuR>)),
+            )
+            .service(
+                web::resource("/verify")
+                    .route(web::get().to(audit::handlers::verify_audit_log::<AuR>)),
+            ),
+    );
+}
@@ -0,0 +1,67 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Serialize, Copy, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum ActorTypeRest {
+    Customer,
+    Staff,
+    System,
+}
+
+#[cfg_attr(test, derive(Deserialize, PartialEq, Debug))]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditEntryRest {
+    pub id: i32,
+    pub actor_type: ActorTypeRest,
+    pub actor_id: i32,
+    pub action: String,
+    pub entity_type: String,
+    pub entity_id: Option<i32>,
+    pub before_snapshot: Option<String>,
+    pub after_snapshot: Option<String>,
+    pub request_method: String,
+    pub request_path: String,
+    pub request_ip: Option<String>,
+    pub user_agent: Option<String>,
+    pub created_at: String,
+    pub prev_hash: String,
+    pub hash: String,
+}
+
+#[cfg_attr(test, derive(Deserialize))]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditEntriesRest {
+    pub entries: Vec<AuditEntryRest>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FindAuditQueryRest {
+    pub actor_type: Option<ActorTypeRest>,
+    pub actor_id: Option<i32>,
+    pub action: Option<String>,
+    pub entity_type: Option<String>,
+    pub entity_id: Option<i32>,
+    pub from_id: Option<i32>,
+    pub limit: Option<i64>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VerifyAuditQueryRest {
+    // pass lastId of the previous page to check the link between pages
+    pub from_id: Option<i32>,
+    pub limit: Option<i64>,
+}
+
+#[cfg_attr(test, derive(Deserialize, PartialEq, Debug))]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditVerificationRest {
+    pub valid: bool,
+    pub first_invalid_id: Option<i32>,
+    pub entries_checked: usize,
+    pub last_id: Option<i32>,
+}
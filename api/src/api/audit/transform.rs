@@ -0,0 +1,53 @@
+use crate::models::audit::{ActorType, AuditEntry};
+
+use super::models::{ActorTypeRest, AuditEntriesRest, AuditEntryRest};
+
+impl From<ActorType> for ActorTypeRest {
+    fn from(actor_type: ActorType) -> Self {
+        match actor_type {
+            ActorType::Customer => ActorTypeRest::Customer,
+            ActorType::Staff => ActorTypeRest::Staff,
+            ActorType::System => ActorTypeRest::System,
+        }
+    }
+}
+
+impl From<ActorTypeRest> for ActorType {
+    fn from(actor_type: ActorTypeRest) -> Self {
+        match actor_type {
+            ActorTypeRest::Customer => ActorType::Customer,
+            ActorTypeRest::Staff => ActorType::Staff,
+            ActorTypeRest::System => ActorType::System,
+        }
+    }
+}
+
+impl From<&AuditEntry> for AuditEntryRest {
+    fn from(entry: &AuditEntry) -> Self {
+        Self {
+            id: entry.id,
+            actor_type: entry.actor_type.into(),
+            actor_id: entry.actor_id,
+            action: entry.action.clone(),
+            entity_type: entry.entity_type.clone(),
+            entity_id: entry.entity_id,
+            before_snapshot: entry.before_snapshot.clone(),
+            after_snapshot: entry.after_snapshot.clone(),
+            request_method: entry.request_method.clone(),
+            request_path: entry.request_path.clone(),
+            request_ip: entry.request_ip.clone(),
+            user_agent: entry.user_agent.clone(),
+            created_at: entry.created_at.to_string(),
+            prev_hash: entry.prev_hash.clone(),
+            hash: entry.hash.clone(),
+        }
+    }
+}
+
+impl From<Vec<AuditEntry>> for AuditEntriesRest {
+    fn from(entries: Vec<AuditEntry>) -> Self {
+        Self {
+            entries: entries.iter().map(AuditEntryRest::from).collect(),
+        }
+    }
+}
@@ -0,0 +1,85 @@
+use actix_web::{http::header::USER_AGENT, web, web::Data, HttpRequest};
+use chrono::SubsecRound;
+use serde::Serialize;
+
+use crate::{
+    models::audit::{ActorType, AuditAction, AuditEntity, AuditEntry, NewAuditEntry},
+    traits::RepoCreate,
+};
+
+// request metadata filled in here, snapshots are set by the caller
+pub fn new_audit_entry(
+    req: &HttpRequest,
+    actor_type: ActorType,
+    actor_id: i32,
+    action: AuditAction,
+    entity: AuditEntity,
+    entity_id: Option<i32>,
+) -> NewAuditEntry {
+    NewAuditEntry {
+        actor_type,
+        actor_id,
+        action: action.as_str().to_string(),
+        entity_type: entity.as_str().to_string(),
+        entity_id,
+        before_snapshot: None,
+        after_snapshot: None,
+        request_method: req.method().to_string(),
+        request_path: req.path().to_string(),
+        request_ip: req
+            .connection_info()
+            .realip_remote_addr()
+            .map(|ip| ip.to_string()),
+        user_agent: req
+            .headers()
+            .get(USER_AGENT)
+            .and_then(|ua| ua.to_str().ok())
+            .map(|ua| ua.to_string()),
+        // postgres only keeps microseconds, hash has to survive the round trip
+        created_at: chrono::Utc::now().naive_utc().trunc_subsecs(6),
+        prev_hash: "".to_string(),
+        hash: "".to_string(),
+    }
+}
+
+pub fn snapshot<T: Serialize>(value: &T) -> Option<String> {
+    serde_json::to_string(value).ok()
+}
+
+// the change has already committed by the time we get here so a failure is logged, not returned.
+// failing the request would tell the client a change that went through didn't, and a retry would
+// make it twice
+pub async fn record_audit<AuR>(audit_repo: Data<AuR>, entry: NewAuditEntry)
+where
+    AuR: RepoCreate<AuditEntry, NewAuditEntry>,
+{
+    let action = entry.action.clone();
+
+    let res = web::block(move || audit_repo.create(entry)).await;
+
+    if !matches!(res, Ok(Ok(_))) {
+        println!("couldn't write audit entry for {}", action);
+    }
+}
+
+// the entry as the repo hands it back once it's on the chain, for mocking a write that goes through
+#[cfg(test)]
+pub fn appended(entry: NewAuditEntry) -> AuditEntry {
+    AuditEntry {
+        id: 1,
+        actor_type: entry.actor_type,
+        actor_id: entry.actor_id,
+        action: entry.action,
+        entity_type: entry.entity_type,
+        entity_id: entry.entity_id,
+        before_snapshot: entry.before_snapshot,
+        after_snapshot: entry.after_snapshot,
+        request_method: entry.request_method,
+        request_path: entry.request_path,
+        request_ip: entry.request_ip,
+        user_agent: entry.user_agent,
+        created_at: entry.created_at,
+        prev_hash: entry.prev_hash,
+        hash: entry.hash,
+    }
+}
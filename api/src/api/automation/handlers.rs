@@ -0,0 +1,706 @@
+use actix_web::http::header::ContentType;
+use actix_web::web::{Data, Path, Query};
+use actix_web::{web, HttpRequest, HttpResponse};
+
+use super::models::{
+    AutomationRuleRest, AutomationRuleUpdateRest, AutomationRulesRest, AutomationRunsRest,
+    EndOfDayQueryRest, FindAutomationRunQueryRest, NewAutomationRuleRest,
+};
+use super::util::follow_up_automation_runs;
+use crate::api::accounts::util::authorise_holder;
+use crate::api::audit::util::{new_audit_entry, record_audit, snapshot};
+use crate::api::error::ApiError;
+use crate::error::RepoError;
+use crate::models::account::Account;
+use crate::models::account_holder::{AccountHolder, AccountPermission, FindAccountHolderQuery};
+use crate::models::audit::{ActorType, AuditAction, AuditEntity, AuditEntry, NewAuditEntry};
+use crate::models::automation::{
+    AutomationRule, AutomationRuleKind, AutomationRuleUpdate, AutomationRun, AutomationTrigger,
+    FindAutomationRuleQuery, FindAutomationRunQuery, NewAutomationRule,
+};
+use crate::models::budget::{BudgetAlert, BudgetAlertDelivered, BudgetCheck};
+use crate::notifications::Notifier;
+use crate::traits::{RepoCreate, RepoDeleteById, RepoFind, RepoGetById, RepoUpdate};
+
+// rounding up to more than $10 isn't really rounding
+const MAX_ROUND_TO_CENTS: i64 = 1000;
+
+fn valid_round_to(round_to_cents: i64) -> bool {
+    (1..=MAX_ROUND_TO_CENTS).contains(&round_to_cents)
+}
+
+// each amount is only for the kind of rule it goes with
+fn valid_amounts(
+    kind: AutomationRuleKind,
+    round_to_cents: Option<i64>,
+    threshold_cents: Option<i64>,
+) -> bool {
+    match kind {
+        AutomationRuleKind::RoundUp => {
+            round_to_cents.map_or(true, valid_round_to) && threshold_cents.is_none()
+        }
+        AutomationRuleKind::SweepAbove => {
+            threshold_cents.map_or(true, |t| t >= 0) && round_to_cents.is_none()
+        }
+    }
+}
+
+pub async fn create_automation_rule<AtR, AR, HR, AuR>(
+    automation_repo: Data<AtR>,
+    accounts_repo: Data<AR>,
+    holders_repo: Data<HR>,
+    audit_repo: Data<AuR>,
+    req: HttpRequest,
+    path: Path<i32>,
+    payload: web::Json<NewAutomationRuleRest>,
+) -> Result<HttpResponse, actix_web::Error>
+where
+    AtR: RepoCreate<AutomationRule, NewAutomationRule>,
+    AR: RepoGetById<Account>,
+    HR: RepoFind<AccountHolder, FindAccountHolderQuery>,
+    AuR: RepoCreate<AuditEntry, NewAuditEntry>,
+{
+    let customer_id = path.into_inner();
+
+    let mut new_rule: NewAutomationRule = payload.into_inner().into();
+    new_rule.customer_id = customer_id;
+
+    if new_rule.from_account_id == new_rule.to_account_id
+        || !valid_amounts(
+            new_rule.kind,
+            new_rule.round_to_cents,
+            new_rule.threshold_cents,
+        )
+        || (new_rule.kind == AutomationRuleKind::SweepAbove && new_rule.threshold_cents.is_none())
+    {
+        return Err(ApiError::BadRequest.into());
+    }
+
+    println!(
+        "Trying to create {:?} rule for customer {}",
+        new_rule.kind, customer_id
+    );
+
+    let rule = web::block(move || {
+        // money only moves between accounts the customer holds, and they have to be able to move
+        // it out of the one it comes from
+        for (account_id, permission) in [
+            (new_rule.from_account_id, AccountPermission::Transact),
+            (new_rule.to_account_id, AccountPermission::View),
+        ] {
+            let account = accounts_repo
+                .get_by_id(account_id)
+                .map_err(|err| match err {
+                    RepoError::NotFound => ApiError::BadRequest,
+                    _ => ApiError::InternalError,
+                })?;
+
+            authorise_holder(holders_repo.as_ref(), account.id, customer_id, permission)?;
+        }
+
+        automation_repo
+            .create(new_rule)
+            .map_err(|_| ApiError::InternalError)
+    })
+    .await
+    .map_err(|_| ApiError::InternalError)??;
+
+    let rule_rest: AutomationRuleRest = (&rule).into();
+
+    let mut audit_entry = new_audit_entry(
+        &req,
+        ActorType::Customer,
+        customer_id,
+        AuditAction::CreateAutomationRule,
+        AuditEntity::AutomationRule,
+        Some(rule.id),
+    );
+    audit_entry.after_snapshot = snapshot(&rule_rest);
+    record_audit(audit_repo, audit_entry).await;
+
+    Ok(HttpResponse::Created()
+        .insert_header(ContentType::json())
+        .json(web::Json(rule_rest)))
+}
+
+pub async fn find_automation_rules<AtR>(
+    automation_repo: Data<AtR>,
+    path: Path<i32>,
+) -> Result<HttpResponse, actix_web::Error>
+where
+    AtR: RepoFind<AutomationRule, FindAutomationRuleQuery>,
+{
+    let customer_id = path.into_inner();
+
+    println!(
+        "Trying to get automation rules for customer {}",
+        customer_id
+    );
+
+    let rules = web::block(move || automation_repo.find(FindAutomationRuleQuery { customer_id }))
+        .await
+        .map_err(|_| ApiError::InternalError)?
+        .map_err(|_| ApiError::InternalError)?;
+
+    // enforced query on customer_id so should always be fine, but safe > sorry
+    for rule in rules.iter() {
+        if rule.customer_id != customer_id {
+            return Err(ApiError::BadRequest.into());
+        }
+    }
+
+    Ok(HttpResponse::Ok()
+        .insert_header(ContentType::json())
+        .json(web::Json::<AutomationRulesRest>(rules.into())))
+}
+
+pub async fn get_automation_rule<AtR>(
+    automation_repo: Data<AtR>,
+    path: Path<(i32, i32)>,
+) -> Result<HttpResponse, actix_web::Error>
+where
+    AtR: RepoGetById<AutomationRule>,
+{
+    let (customer_id, rule_id) = path.into_inner();
+
+    println!(
+        "Trying to get automation rule {} for customer {}",
+        rule_id, customer_id
+    );
+
+    let rule = web::block(move || automation_repo.get_by_id(rule_id))
+        .await
+        .map_err(|_| ApiError::InternalError)?
+        .map_err(|err| match err {
+            RepoError::NotFound => ApiError::NotFound,
+            _ => ApiError::InternalError,
+        })?;
+
+    if rule.customer_id != customer_id {
+        return Err(ApiError::Unauthorized.into());
+    }
+
+    Ok(HttpResponse::Ok()
+        .insert_header(ContentType::json())
+        .json(web::Json(AutomationRuleRest::from(&rule))))
+}
+
+pub async fn update_automation_rule<AtR, AuR>(
+    automation_repo: Data<AtR>,
+    audit_repo: Data<AuR>,
+    req: HttpRequest,
+    path: Path<(i32, i32)>,
+    payload: web::Json<AutomationRuleUpdateRest>,
+) -> Result<HttpResponse, actix_web::Error>
+where
+    AtR: RepoGetById<AutomationRule> + RepoUpdate<AutomationRule, AutomationRuleUpdate>,
+    AuR: RepoCreate<AuditEntry, NewAuditEntry>,
+{
+    let (customer_id, rule_id) = path.into_inner();
+
+    let update: AutomationRuleUpdate = payload.into_inner().into();
+
+    println!(
+        "Trying to update automation rule {} for customer {}",
+        rule_id, customer_id
+    );
+
+    let (before, after) = web::block(move || {
+        let before = automation_repo
+            .get_by_id(rule_id)
+            .map_err(|err| match err {
+                RepoError::NotFound => ApiError::NotFound,
+                _ => ApiError::InternalError,
+            })?;
+
+        if before.customer_id != customer_id {
+            return Err(ApiError::Unauthorized);
+        }
+
+        if !valid_amounts(before.kind, update.round_to_cents, update.threshold_cents) {
+            return Err(ApiError::BadRequest);
+        }
+
+        let after = automation_repo
+            .update(rule_id, update)
+            .map_err(|_| ApiError::InternalError)?;
+
+        Ok((before, after))
+    })
+    .await
+    .map_err(|_| ApiError::InternalError)??;
+
+    let after_rest: AutomationRuleRest = (&after).into();
+
+    let mut audit_entry = new_audit_entry(
+        &req,
+        ActorType::Customer,
+        customer_id,
+        AuditAction::UpdateAutomationRule,
+        AuditEntity::AutomationRule,
+        Some(rule_id),
+    );
+    audit_entry.before_snapshot = snapshot(&AutomationRuleRest::from(&before));
+    audit_entry.after_snapshot = snapshot(&after_rest);
+    record_audit(audit_repo, audit_entry).await;
+
+    Ok(HttpResponse::Ok()
+        .insert_header(ContentType::json())
+        .json(web::Json(after_rest)))
+}
+
+pub async fn delete_automation_rule<AtR, AuR>(
+    automation_repo: Data<AtR>,
+    audit_repo: Data<AuR>,
+    req: HttpRequest,
+    path: Path<(i32, i32)>,
+) -> Result<HttpResponse, actix_web::Error>
+where
+    AtR: RepoGetById<AutomationRule> + RepoDeleteById<AutomationRule>,
+    AuR: RepoCreate<AuditEntry, NewAuditEntry>,
+{
+    let (customer_id, rule_id) = path.into_inner();
+
+    println!(
+        "Trying to delete automation rule {} for customer {}",
+        rule_id, customer_id
+    );
+
+    let deleted_rule = web::block(move || {
+        let existing_rule = match automation_repo.get_by_id(rule_id) {
+            Ok(rule) => {
+                if rule.customer_id != customer_id {
+                    return Err(ApiError::Unauthorized);
+                }
+                Some(rule)
+            }
+            Err(RepoError::NotFound) => None,
+            _ => return Err(ApiError::InternalError),
+        };
+
+        automation_repo
+            .delete_by_id(rule_id)
+            .map_err(|_| ApiError::InternalError)?;
+
+        Ok(existing_rule)
+    })
+    .await
+    .map_err(|_| ApiError::InternalError)??;
+
+    // deleting something that isn't there is a no-op, nothing to audit
+    if let Some(rule) = deleted_rule {
+        let mut audit_entry = new_audit_entry(
+            &req,
+            ActorType::Customer,
+            customer_id,
+            AuditAction::DeleteAutomationRule,
+            AuditEntity::AutomationRule,
+            Some(rule.id),
+        );
+        audit_entry.before_snapshot = snapshot(&AutomationRuleRest::from(&rule));
+        record_audit(audit_repo, audit_entry).await;
+    }
+
+    Ok(HttpResponse::NoContent().body(""))
+}
+
+pub async fn find_automation_runs<AtR>(
+    automation_repo: Data<AtR>,
+    path: Path<i32>,
+    query: Query<FindAutomationRunQueryRest>,
+) -> Result<HttpResponse, actix_web::Error>
+where
+    AtR: RepoFind<AutomationRun, FindAutomationRunQuery>,
+{
+    let customer_id = path.into_inner();
+
+    let run_query = FindAutomationRunQuery {
+        customer_id,
+        rule_id: query.rule_id,
+    };
+
+    println!("Trying to get automation runs for customer {}", customer_id);
+
+    let runs = web::block(move || automation_repo.find(run_query))
+        .await
+        .map_err(|_| ApiError::InternalError)?
+        .map_err(|_| ApiError::InternalError)?;
+
+    // enforced query on customer_id so should always be fine, but safe > sorry
+    for run in runs.iter() {
+        if run.customer_id != customer_id {
+            return Err(ApiError::BadRequest.into());
+        }
+    }
+
+    Ok(HttpResponse::Ok()
+        .insert_header(ContentType::json())
+        .json(web::Json::<AutomationRunsRest>(runs.into())))
+}
+
+// called by whatever schedules the bank's end of day. safe to call again for the same day, a
+// sweep only runs once per day
+pub async fn run_end_of_day<AtR, BuR, N, AuR>(
+    automation_repo: Data<AtR>,
+    // sweeps go through the same follow up as any other transfer, budgets then the audit entry
+    (budgets_repo, notifier): (Data<BuR>, Data<N>),
+    audit_repo: Data<AuR>,
+    req: HttpRequest,
+    path: Path<i32>,
+    query: Query<EndOfDayQueryRest>,
+) -> Result<HttpResponse, actix_web::Error>
+where
+    AtR: RepoCreate<Vec<AutomationRun>, AutomationTrigger>,
+    BuR: RepoCreate<Vec<BudgetAlert>, BudgetCheck> + RepoUpdate<BudgetAlert, BudgetAlertDelivered>,
+    N: Notifier,
+    AuR: RepoCreate<AuditEntry, NewAuditEntry>,
+{
+    let staff_id = path.into_inner();
+
+    let day = match &query.date {
+        Some(date) => {
+            chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d").map_err(|_| ApiError::BadRequest)?
+        }
+        None => chrono::Utc::now().date_naive(),
+    };
+
+    println!("Staff {} trying to run end of day for {}", staff_id, day);
+
+    let runs = web::block(move || automation_repo.create(AutomationTrigger::EndOfDay { day }))
+        .await
+        .map_err(|_| ApiError::InternalError)?
+        .map_err(|_| ApiError::InternalError)?;
+
+    follow_up_automation_runs((budgets_repo, notifier), audit_repo, &req, &runs).await;
+
+    Ok(HttpResponse::Ok()
+        .insert_header(ContentType::json())
+        .json(web::Json::<AutomationRunsRest>(runs.into())))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::audit::util::appended;
+    use crate::fixtures::{account, dt, holders_repo};
+    use actix_web::{
+        body::to_bytes,
+        http::StatusCode,
+        test,
+        web::{Data, Json, Query},
+    };
+    use chrono::NaiveDate;
+    use mockall::{mock, predicate::eq};
+
+    use crate::{
+        api::{
+            automation::{
+                handlers::{create_automation_rule, run_end_of_day, update_automation_rule},
+                models::{
+                    AutomationRuleKindRest, AutomationRuleRest, AutomationRuleUpdateRest,
+                    AutomationRunsRest, EndOfDayQueryRest, NewAutomationRuleRest,
+                },
+            },
+            error::ApiError,
+        },
+        error::RepoError,
+        models::{
+            account::{Account, AccountType},
+            account_holder::{AccountHolder, FindAccountHolderQuery, HolderRole},
+            audit::{ActorType, AuditEntry, NewAuditEntry},
+            automation::{
+                AutomationRule, AutomationRuleKind, AutomationRuleUpdate, AutomationRun,
+                AutomationTrigger, NewAutomationRule,
+            },
+            budget::{BudgetAlert, BudgetAlertDelivered, BudgetCheck},
+        },
+        notifications::MockNotifier,
+        traits::{
+            MockRepoCreate, MockRepoFind, MockRepoGetById, RepoCreate, RepoGetById, RepoUpdate,
+        },
+    };
+
+    mock! {
+        pub BuR { }
+        impl RepoCreate<Vec<BudgetAlert>, BudgetCheck> for BuR {
+            fn create(&self, new: BudgetCheck) -> Result<Vec<BudgetAlert>, RepoError>;
+        }
+        impl RepoUpdate<BudgetAlert, BudgetAlertDelivered> for BuR {
+            fn update(&self, id: i32, update: BudgetAlertDelivered) -> Result<BudgetAlert, RepoError>;
+        }
+    }
+
+    fn rule(kind: AutomationRuleKind) -> AutomationRule {
+        AutomationRule {
+            id: 4,
+            customer_id: 5,
+            kind,
+            from_account_id: 52,
+            to_account_id: 53,
+            round_to_cents: (kind == AutomationRuleKind::RoundUp).then_some(100),
+            threshold_cents: (kind == AutomationRuleKind::SweepAbove).then_some(200000),
+            enabled: true,
+            created_at: dt(),
+            updated_at: dt(),
+        }
+    }
+
+    fn accounts_repo(owners: [(i32, i32); 2]) -> MockRepoGetById<Account> {
+        let mut mock_accounts_repo = MockRepoGetById::<Account>::new();
+        for (account_id, customer_id) in owners {
+            mock_accounts_repo
+                .expect_get_by_id()
+                .with(eq(account_id))
+                .returning(move |id| {
+                    Ok(account(id, customer_id, AccountType::Transaction, 250000))
+                });
+        }
+        mock_accounts_repo
+    }
+
+    #[actix_web::test]
+    async fn test_create_round_up_rule_success() {
+        let customer_id = 5;
+
+        let mut mock_automation_repo = MockRepoCreate::<AutomationRule, NewAutomationRule>::new();
+        mock_automation_repo
+            .expect_create()
+            .with(eq(NewAutomationRule {
+                customer_id,
+                kind: AutomationRuleKind::RoundUp,
+                from_account_id: 52,
+                to_account_id: 53,
+                round_to_cents: Some(100),
+                threshold_cents: None,
+            }))
+            .times(1)
+            .returning(|_| Ok(rule(AutomationRuleKind::RoundUp)));
+
+        let mut mock_audit_repo = MockRepoCreate::<AuditEntry, NewAuditEntry>::new();
+        mock_audit_repo
+            .expect_create()
+            .withf(move |entry| {
+                entry.actor_id == customer_id
+                    && entry.action == "create_automation_rule"
+                    && entry.entity_type == "automation_rule"
+                    && entry.entity_id == Some(4)
+            })
+            .times(1)
+            .returning(|entry| Ok(appended(entry)));
+
+        let res = create_automation_rule(
+            Data::new(mock_automation_repo),
+            Data::new(accounts_repo([(52, customer_id), (53, customer_id)])),
+            Data::new(holders_repo([
+                (52, customer_id, HolderRole::Owner),
+                (53, customer_id, HolderRole::Owner),
+            ])),
+            Data::new(mock_audit_repo),
+            test::TestRequest::post().to_http_request(),
+            customer_id.into(),
+            Json(NewAutomationRuleRest {
+                kind: AutomationRuleKindRest::RoundUp,
+                from_account_id: 52,
+                to_account_id: 53,
+                round_to_cents: None,
+                threshold_cents: None,
+            }),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(StatusCode::CREATED, res.status());
+
+        let actual: AutomationRuleRest =
+            serde_json::from_slice(&to_bytes(res.into_body()).await.unwrap()).unwrap();
+        assert_eq!(AutomationRuleKindRest::RoundUp, actual.kind);
+        assert_eq!(Some(100), actual.round_to_cents);
+    }
+
+    #[actix_web::test]
+    async fn test_create_rule_someone_elses_account_unauthorized() {
+        let res = create_automation_rule(
+            Data::new(MockRepoCreate::<AutomationRule, NewAutomationRule>::new()),
+            Data::new(accounts_repo([(52, 5), (53, 6)])),
+            Data::new(holders_repo([
+                (52, 5, HolderRole::Owner),
+                (53, 6, HolderRole::Owner),
+            ])),
+            Data::new(MockRepoCreate::<AuditEntry, NewAuditEntry>::new()),
+            test::TestRequest::post().to_http_request(),
+            5.into(),
+            Json(NewAutomationRuleRest {
+                kind: AutomationRuleKindRest::SweepAbove,
+                from_account_id: 52,
+                to_account_id: 53,
+                round_to_cents: None,
+                threshold_cents: Some(200000),
+            }),
+        )
+        .await;
+
+        assert!(res.is_err_and(|e| { e.to_string() == ApiError::Unauthorized.to_string() }));
+    }
+
+    #[actix_web::test]
+    async fn test_create_rule_joint_account_signatory_success() {
+        let mut mock_automation_repo = MockRepoCreate::<AutomationRule, NewAutomationRule>::new();
+        mock_automation_repo
+            .expect_create()
+            .times(1)
+            .returning(|_| Ok(rule(AutomationRuleKind::SweepAbove)));
+
+        let mut mock_audit_repo = MockRepoCreate::<AuditEntry, NewAuditEntry>::new();
+        mock_audit_repo
+            .expect_create()
+            .times(1)
+            .returning(|entry| Ok(appended(entry)));
+
+        // 52 is customer 6's account that 5 can sign on
+        let res = create_automation_rule(
+            Data::new(mock_automation_repo),
+            Data::new(accounts_repo([(52, 6), (53, 5)])),
+            Data::new(holders_repo([
+                (52, 6, HolderRole::Owner),
+                (52, 5, HolderRole::Signatory),
+                (53, 5, HolderRole::Owner),
+            ])),
+            Data::new(mock_audit_repo),
+            test::TestRequest::post().to_http_request(),
+            5.into(),
+            Json(NewAutomationRuleRest {
+                kind: AutomationRuleKindRest::SweepAbove,
+                from_account_id: 52,
+                to_account_id: 53,
+                round_to_cents: None,
+                threshold_cents: Some(200000),
+            }),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(StatusCode::CREATED, res.status());
+    }
+
+    #[actix_web::test]
+    async fn test_create_sweep_without_threshold_error() {
+        let res = create_automation_rule(
+            Data::new(MockRepoCreate::<AutomationRule, NewAutomationRule>::new()),
+            Data::new(MockRepoGetById::<Account>::new()),
+            Data::new(MockRepoFind::<AccountHolder, FindAccountHolderQuery>::new()),
+            Data::new(MockRepoCreate::<AuditEntry, NewAuditEntry>::new()),
+            test::TestRequest::post().to_http_request(),
+            5.into(),
+            Json(NewAutomationRuleRest {
+                kind: AutomationRuleKindRest::SweepAbove,
+                from_account_id: 52,
+                to_account_id: 53,
+                round_to_cents: None,
+                threshold_cents: None,
+            }),
+        )
+        .await;
+
+        assert!(res.is_err_and(|e| { e.to_string() == ApiError::BadRequest.to_string() }));
+    }
+
+    #[actix_web::test]
+    async fn test_update_rule_wrong_amount_for_kind_error() {
+        mock! {
+            pub AtR { }
+            impl RepoGetById<AutomationRule> for AtR {
+                fn get_by_id(&self, id: i32) -> Result<AutomationRule, RepoError>;
+            }
+            impl RepoUpdate<AutomationRule, AutomationRuleUpdate> for AtR {
+                fn update(&self, id: i32, update: AutomationRuleUpdate) -> Result<AutomationRule, RepoError>;
+            }
+        };
+
+        let mut mock_automation_repo = MockAtR::new();
+        mock_automation_repo
+            .expect_get_by_id()
+            .with(eq(4))
+            .times(1)
+            .returning(|_| Ok(rule(AutomationRuleKind::SweepAbove)));
+        mock_automation_repo.expect_update().never();
+
+        let res = update_automation_rule(
+            Data::new(mock_automation_repo),
+            Data::new(MockRepoCreate::<AuditEntry, NewAuditEntry>::new()),
+            test::TestRequest::patch().to_http_request(),
+            (5, 4).into(),
+            Json(AutomationRuleUpdateRest {
+                enabled: None,
+                round_to_cents: Some(500),
+                threshold_cents: None,
+            }),
+        )
+        .await;
+
+        assert!(res.is_err_and(|e| { e.to_string() == ApiError::BadRequest.to_string() }));
+    }
+
+    #[actix_web::test]
+    async fn test_run_end_of_day_success() {
+        let day = NaiveDate::from_ymd_opt(2023, 9, 1).unwrap();
+
+        let mut mock_automation_repo =
+            MockRepoCreate::<Vec<AutomationRun>, AutomationTrigger>::new();
+        mock_automation_repo
+            .expect_create()
+            .with(eq(AutomationTrigger::EndOfDay { day }))
+            .times(1)
+            .returning(move |_| {
+                Ok(vec![AutomationRun {
+                    id: 9,
+                    rule_id: 4,
+                    customer_id: 5,
+                    trigger_transaction_id: None,
+                    run_date: Some(day),
+                    transaction_id: 80,
+                    amount_cents: 50000,
+                    created_at: dt(),
+                }])
+            });
+
+        let mut mock_audit_repo = MockRepoCreate::<AuditEntry, NewAuditEntry>::new();
+        mock_audit_repo
+            .expect_create()
+            .withf(|entry| {
+                entry.actor_type == ActorType::System
+                    && entry.actor_id == 4
+                    && entry.action == "run_automation_rule"
+                    && entry.entity_id == Some(80)
+            })
+            .times(1)
+            .returning(|entry| Ok(appended(entry)));
+
+        // the sweep gets the same budget check as any other transfer
+        let mut mock_budgets_repo = MockBuR::new();
+        mock_budgets_repo
+            .expect_create()
+            .with(eq(BudgetCheck { transaction_id: 80 }))
+            .times(1)
+            .returning(|_| Ok(vec![]));
+
+        let res = run_end_of_day(
+            Data::new(mock_automation_repo),
+            (Data::new(mock_budgets_repo), Data::new(MockNotifier::new())),
+            Data::new(mock_audit_repo),
+            test::TestRequest::post().to_http_request(),
+            2.into(),
+            Query(EndOfDayQueryRest {
+                date: Some("2023-09-01".to_string()),
+            }),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(StatusCode::OK, res.status());
+
+        let actual: AutomationRunsRest =
+            serde_json::from_slice(&to_bytes(res.into_body()).await.unwrap()).unwrap();
+        assert_eq!(1, actual.runs.len());
+        assert_eq!(50000, actual.runs[0].amount_cents);
+        assert_eq!(Some("2023-09-01".to_string()), actual.runs[0].run_date);
+    }
+}
@@ -0,0 +1,77 @@
+pub mod handlers;
+pub mod models;
+pub mod transform;
+pub mod util;
+
+use actix_web::web;
+
+use crate::{
+    api::automation,
+    models::{
+        account::Account,
+        account_holder::{AccountHolder, FindAccountHolderQuery},
+        audit::{AuditEntry, NewAuditEntry},
+        automation::{
+            AutomationRule, AutomationRuleUpdate, AutomationRun, AutomationTrigger,
+            FindAutomationRuleQuery, FindAutomationRunQuery, NewAutomationRule,
+        },
+        budget::{BudgetAlert, BudgetAlertDelivered, BudgetCheck},
+    },
+    notifications::Notifier,
+    traits::{RepoCreate, RepoDeleteById, RepoFind, RepoGetById, RepoUpdate},
+};
+
+pub fn configure_automation_api<AtR, AR, HR, BuR, N, AuR>(cfg: &mut web::ServiceConfig)
+where
+    AtR: RepoCreate<AutomationRule, NewAutomationRule>
+        + RepoFind<AutomationRule, FindAutomationRuleQuery>
+        + RepoGetById<AutomationRule>
+        + RepoUpdate<AutomationRule, AutomationRuleUpdate>
+        + RepoDeleteById<AutomationRule>
+        + RepoFind<AutomationRun, FindAutomationRunQuery>
+        + RepoCreate<Vec<AutomationRun>, AutomationTrigger>,
+    AR: RepoGetById<Account>,
+    HR: RepoFind<AccountHolder, FindAccountHolderQuery>,
+    BuR: RepoCreate<Vec<BudgetAlert>, BudgetCheck> + RepoUpdate<BudgetAlert, BudgetAlertDelivered>,
+    N: Notifier,
+    AuR: RepoCreate<AuditEntry, NewAuditEntry>,
+{
+    cfg.service(
+        web::scope("/api/customers/{customer_id}/automation-rules")
+            .service(
+                web::resource("")
+                    .route(
+                        web::post().to(automation::handlers::create_automation_rule::<
+                            AtR,
+                            AR,
+                            HR,
+                            AuR,
+                        >),
+                    )
+                    .route(web::get().to(automation::handlers::find_automation_rules::<AtR>)),
+            )
+            // before /{rule_id} so it isn't taken for an id
+            .service(
+                web::resource("/runs")
+                    .route(web::get().to(automation::handlers::find_automation_runs::<AtR>)),
+            )
+            .service(
+                web::resource("/{rule_id}")
+                    .route(web::get().to(automation::handlers::get_automation_rule::<AtR>))
+                    .route(
+                        web::patch().to(automation::handlers::update_automation_rule::<AtR, AuR>),
+                    )
+                    .route(
+                        web::delete().to(automation::handlers::delete_automation_rule::<AtR, AuR>),
+                    ),
+            ),
+    )
+    .service(web::scope("/api/staff/{staff_id}/automation").service(
+        web::resource("/end-of-day").route(web::post().to(automation::handlers::run_end_of_day::<
+            AtR,
+            BuR,
+            N,
+            AuR,
+        >)),
+    ));
+}
@@ -0,0 +1,87 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Serialize, Copy, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum AutomationRuleKindRest {
+    RoundUp,
+    SweepAbove,
+}
+
+#[cfg_attr(test, derive(Deserialize, PartialEq, Debug))]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AutomationRuleRest {
+    pub id: i32,
+    pub customer_id: i32,
+    pub kind: AutomationRuleKindRest,
+    pub from_account_id: i32,
+    pub to_account_id: i32,
+    pub round_to_cents: Option<i64>,
+    pub threshold_cents: Option<i64>,
+    pub enabled: bool,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[cfg_attr(test, derive(Deserialize))]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AutomationRulesRest {
+    pub rules: Vec<AutomationRuleRest>,
+}
+
+#[cfg_attr(test, derive(Serialize))]
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct NewAutomationRuleRest {
+    pub kind: AutomationRuleKindRest,
+    pub from_account_id: i32,
+    pub to_account_id: i32,
+    // round ups only, a dollar if not given
+    pub round_to_cents: Option<i64>,
+    // sweeps only, required
+    pub threshold_cents: Option<i64>,
+}
+
+#[cfg_attr(test, derive(Serialize))]
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct AutomationRuleUpdateRest {
+    pub enabled: Option<bool>,
+    pub round_to_cents: Option<i64>,
+    pub threshold_cents: Option<i64>,
+}
+
+#[cfg_attr(test, derive(Deserialize, PartialEq, Debug))]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AutomationRunRest {
+    pub id: i32,
+    pub rule_id: i32,
+    pub customer_id: i32,
+    pub trigger_transaction_id: Option<i32>,
+    pub run_date: Option<String>,
+    pub transaction_id: i32,
+    pub amount_cents: i64,
+    pub created_at: String,
+}
+
+#[cfg_attr(test, derive(Deserialize))]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AutomationRunsRest {
+    pub runs: Vec<AutomationRunRest>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FindAutomationRunQueryRest {
+    pub rule_id: Option<i32>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EndOfDayQueryRest {
+    // YYYY-MM-DD, today (UTC) if not given
+    pub date: Option<String>,
+}
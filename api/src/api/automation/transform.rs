@@ -0,0 +1,107 @@
+use crate::models::automation::{
+    AutomationRule, AutomationRuleKind, AutomationRuleUpdate, AutomationRun, NewAutomationRule,
+};
+
+use super::models::{
+    AutomationRuleKindRest, AutomationRuleRest, AutomationRuleUpdateRest, AutomationRulesRest,
+    AutomationRunRest, AutomationRunsRest, NewAutomationRuleRest,
+};
+
+pub const DEFAULT_ROUND_TO_CENTS: i64 = 100;
+
+impl From<AutomationRuleKind> for AutomationRuleKindRest {
+    fn from(kind: AutomationRuleKind) -> Self {
+        match kind {
+            AutomationRuleKind::RoundUp => AutomationRuleKindRest::RoundUp,
+            AutomationRuleKind::SweepAbove => AutomationRuleKindRest::SweepAbove,
+        }
+    }
+}
+
+impl From<AutomationRuleKindRest> for AutomationRuleKind {
+    fn from(kind: AutomationRuleKindRest) -> Self {
+        match kind {
+            AutomationRuleKindRest::RoundUp => AutomationRuleKind::RoundUp,
+            AutomationRuleKindRest::SweepAbove => AutomationRuleKind::SweepAbove,
+        }
+    }
+}
+
+impl From<&AutomationRule> for AutomationRuleRest {
+    fn from(rule: &AutomationRule) -> Self {
+        Self {
+            id: rule.id,
+            customer_id: rule.customer_id,
+            kind: rule.kind.into(),
+            from_account_id: rule.from_account_id,
+            to_account_id: rule.to_account_id,
+            round_to_cents: rule.round_to_cents,
+            threshold_cents: rule.threshold_cents,
+            enabled: rule.enabled,
+            created_at: rule.created_at.to_string(),
+            updated_at: rule.updated_at.to_string(),
+        }
+    }
+}
+
+impl From<Vec<AutomationRule>> for AutomationRulesRest {
+    fn from(rules: Vec<AutomationRule>) -> Self {
+        Self {
+            rules: rules.iter().map(AutomationRuleRest::from).collect(),
+        }
+    }
+}
+
+impl From<NewAutomationRuleRest> for NewAutomationRule {
+    fn from(rule: NewAutomationRuleRest) -> Self {
+        let round_to_cents = match rule.kind {
+            AutomationRuleKindRest::RoundUp => {
+                Some(rule.round_to_cents.unwrap_or(DEFAULT_ROUND_TO_CENTS))
+            }
+            AutomationRuleKindRest::SweepAbove => rule.round_to_cents,
+        };
+
+        NewAutomationRule {
+            // comes from path, set by handler
+            customer_id: 0,
+            kind: rule.kind.into(),
+            from_account_id: rule.from_account_id,
+            to_account_id: rule.to_account_id,
+            round_to_cents,
+            threshold_cents: rule.threshold_cents,
+        }
+    }
+}
+
+impl From<AutomationRuleUpdateRest> for AutomationRuleUpdate {
+    fn from(update: AutomationRuleUpdateRest) -> Self {
+        AutomationRuleUpdate {
+            enabled: update.enabled,
+            round_to_cents: update.round_to_cents,
+            threshold_cents: update.threshold_cents,
+        }
+    }
+}
+
+impl From<&AutomationRun> for AutomationRunRest {
+    fn from(run: &AutomationRun) -> Self {
+        Self {
+            id: run.id,
+            rule_id: run.rule_id,
+            customer_id: run.customer_id,
+            trigger_transaction_id: run.trigger_transaction_id,
+            run_date: run.run_date.map(|d| d.to_string()),
+            transaction_id: run.transaction_id,
+            amount_cents: run.amount_cents,
+            created_at: run.created_at.to_string(),
+        }
+    }
+}
+
+impl From<Vec<AutomationRun>> for AutomationRunsRest {
+    fn from(runs: Vec<AutomationRun>) -> Self {
+        Self {
+            runs: runs.iter().map(AutomationRunRest::from).collect(),
+        }
+    }
+}
@@ -0,0 +1,69 @@
+use actix_web::{web, web::Data, HttpRequest};
+
+use super::models::AutomationRunRest;
+use crate::{
+    api::{
+        audit::util::{new_audit_entry, record_audit, snapshot},
+        budgets::util::check_budgets,
+    },
+    models::{
+        audit::{ActorType, AuditAction, AuditEntity, AuditEntry, NewAuditEntry},
+        automation::{AutomationRun, AutomationTrigger},
+        budget::{BudgetAlert, BudgetAlertDelivered, BudgetCheck},
+    },
+    notifications::Notifier,
+    traits::{RepoCreate, RepoUpdate},
+};
+
+// what follows any transfer once it's made: budgets for the ones that settled (the check passes
+// over the rest) and an audit entry for each. a rule's transfer is the system acting, on behalf of
+// the rule
+pub async fn follow_up_automation_runs<BuR, N, AuR>(
+    (budgets_repo, notifier): (Data<BuR>, Data<N>),
+    audit_repo: Data<AuR>,
+    req: &HttpRequest,
+    runs: &[AutomationRun],
+) where
+    BuR: RepoCreate<Vec<BudgetAlert>, BudgetCheck> + RepoUpdate<BudgetAlert, BudgetAlertDelivered>,
+    N: Notifier,
+    AuR: RepoCreate<AuditEntry, NewAuditEntry>,
+{
+    for run in runs.iter() {
+        check_budgets(budgets_repo.clone(), notifier.clone(), run.transaction_id).await;
+
+        let mut audit_entry = new_audit_entry(
+            req,
+            ActorType::System,
+            run.rule_id,
+            AuditAction::RunAutomationRule,
+            AuditEntity::Transaction,
+            Some(run.transaction_id),
+        );
+        audit_entry.after_snapshot = snapshot(&AutomationRunRest::from(run));
+        record_audit(audit_repo.clone(), audit_entry).await;
+    }
+}
+
+// the transaction that set the rules off has settled by the time we get here so a failure is
+// logged, not returned
+pub async fn run_automations<AtR, BuR, N, AuR>(
+    automation_repo: Data<AtR>,
+    (budgets_repo, notifier): (Data<BuR>, Data<N>),
+    audit_repo: Data<AuR>,
+    req: &HttpRequest,
+    trigger: AutomationTrigger,
+) where
+    AtR: RepoCreate<Vec<AutomationRun>, AutomationTrigger>,
+    BuR: RepoCreate<Vec<BudgetAlert>, BudgetCheck> + RepoUpdate<BudgetAlert, BudgetAlertDelivered>,
+    N: Notifier,
+    AuR: RepoCreate<AuditEntry, NewAuditEntry>,
+{
+    let described = format!("{:?}", trigger);
+
+    match web::block(move || automation_repo.create(trigger)).await {
+        Ok(Ok(runs)) => {
+            follow_up_automation_runs((budgets_repo, notifier), audit_repo, req, &runs).await
+        }
+        _ => println!("couldn't run automation rules for {}", described),
+    }
+}
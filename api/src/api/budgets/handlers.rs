@@ -0,0 +1,509 @@
+use actix_web::http::header::ContentType;
+use actix_web::web::{Data, Path, Query};
+use actix_web::{web, HttpRequest, HttpResponse};
+
+use super::models::{
+    BudgetAlertsRest, BudgetProgressRest, BudgetRest, BudgetUpdateRest, BudgetsRest,
+    FindBudgetAlertQueryRest, NewBudgetRest,
+};
+use crate::api::audit::util::{new_audit_entry, record_audit, snapshot};
+use crate::api::error::ApiError;
+use crate::error::RepoError;
+use crate::models::audit::{ActorType, AuditAction, AuditEntity, AuditEntry, NewAuditEntry};
+use crate::models::budget::{
+    is_valid_thresholds, Budget, BudgetAlert, BudgetProgress, BudgetUpdate, FindBudgetAlertQuery,
+    FindBudgetProgressQuery, NewBudget,
+};
+use crate::traits::{RepoCreate, RepoDeleteById, RepoFind, RepoGetById, RepoUpdate};
+
+const MAX_NAME_LEN: usize = 40;
+
+fn valid_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().count() <= MAX_NAME_LEN
+}
+
+fn valid_account_number(account_number: &Option<String>) -> bool {
+    account_number.as_ref().map_or(true, |n| {
+        n.len() == 9 && n.chars().all(|c| c.is_ascii_digit())
+    })
+}
+
+pub async fn create_budget<BuR, AuR>(
+    budgets_repo: Data<BuR>,
+    audit_repo: Data<AuR>,
+    req: HttpRequest,
+    path: Path<i32>,
+    payload: web::Json<NewBudgetRest>,
+) -> Result<HttpResponse, actix_web::Error>
+where
+    BuR: RepoCreate<Budget, NewBudget>,
+    AuR: RepoCreate<AuditEntry, NewAuditEntry>,
+{
+    let customer_id = path.into_inner();
+
+    let mut new_budget: NewBudget = payload.into_inner().into();
+    new_budget.customer_id = customer_id;
+
+    if !valid_name(&new_budget.name)
+        || !valid_account_number(&new_budget.account_number)
+        || new_budget.amount_cents <= 0
+        || !is_valid_thresholds(&new_budget.alert_thresholds)
+    {
+        return Err(ApiError::BadRequest.into());
+    }
+
+    println!("Trying to create budget for customer {}", customer_id);
+
+    let budget = web::block(move || budgets_repo.create(new_budget))
+        .await
+        .map_err(|_| ApiError::InternalError)?
+        .map_err(|_| ApiError::InternalError)?;
+
+    let budget_rest: BudgetRest = (&budget).into();
+
+    let mut audit_entry = new_audit_entry(
+        &req,
+        ActorType::Customer,
+        customer_id,
+        AuditAction::CreateBudget,
+        AuditEntity::Budget,
+        Some(budget.id),
+    );
+    audit_entry.after_snapshot = snapshot(&budget_rest);
+    record_audit(audit_repo, audit_entry).await;
+
+    Ok(HttpResponse::Created()
+        .insert_header(ContentType::json())
+        .json(web::Json(budget_rest)))
+}
+
+pub async fn find_budgets<BuR>(
+    budgets_repo: Data<BuR>,
+    path: Path<i32>,
+) -> Result<HttpResponse, actix_web::Error>
+where
+    BuR: RepoFind<BudgetProgress, FindBudgetProgressQuery>,
+{
+    let customer_id = path.into_inner();
+
+    let progress_query = FindBudgetProgressQuery {
+        customer_id,
+        budget_id: None,
+        day: chrono::Utc::now().date_naive(),
+    };
+
+    println!("Trying to get budgets for customer {}", customer_id);
+
+    let budgets = web::block(move || budgets_repo.find(progress_query))
+        .await
+        .map_err(|_| ApiError::InternalError)?
+        .map_err(|_| ApiError::InternalError)?;
+
+    // enforced query on customer_id so should always be fine, but safe > sorry
+    for progress in budgets.iter() {
+        if progress.budget.customer_id != customer_id {
+            return Err(ApiError::BadRequest.into());
+        }
+    }
+
+    Ok(HttpResponse::Ok()
+        .insert_header(ContentType::json())
+        .json(web::Json::<BudgetsRest>(budgets.into())))
+}
+
+pub async fn get_budget<BuR>(
+    budgets_repo: Data<BuR>,
+    path: Path<(i32, i32)>,
+) -> Result<HttpResponse, actix_web::Error>
+where
+    BuR: RepoGetById<Budget> + RepoFind<BudgetProgress, FindBudgetProgressQuery>,
+{
+    let (customer_id, budget_id) = path.into_inner();
+
+    println!(
+        "Trying to get budget {} for customer {}",
+        budget_id, customer_id
+    );
+
+    let progress = web::block(move || {
+        let budget = budgets_repo.get_by_id(budget_id).map_err(|err| match err {
+            RepoError::NotFound => ApiError::NotFound,
+            _ => ApiError::InternalError,
+        })?;
+
+        if budget.customer_id != customer_id {
+            return Err(ApiError::Unauthorized);
+        }
+
+        budgets_repo
+            .find(FindBudgetProgressQuery {
+                customer_id,
+                budget_id: Some(budget_id),
+                day: chrono::Utc::now().date_naive(),
+            })
+            .map_err(|_| ApiError::InternalError)?
+            .into_iter()
+            .next()
+            // deleted in between
+            .ok_or(ApiError::NotFound)
+    })
+    .await
+    .map_err(|_| ApiError::InternalError)??;
+
+    Ok(HttpResponse::Ok()
+        .insert_header(ContentType::json())
+        .json(web::Json::<BudgetProgressRest>((&progress).into())))
+}
+
+pub async fn update_budget<BuR, AuR>(
+    budgets_repo: Data<BuR>,
+    audit_repo: Data<AuR>,
+    req: HttpRequest,
+    path: Path<(i32, i32)>,
+    payload: web::Json<BudgetUpdateRest>,
+) -> Result<HttpResponse, actix_web::Error>
+where
+    BuR: RepoGetById<Budget> + RepoUpdate<Budget, BudgetUpdate>,
+    AuR: RepoCreate<AuditEntry, NewAuditEntry>,
+{
+    let (customer_id, budget_id) = path.into_inner();
+
+    let update: BudgetUpdate = payload.into_inner().into();
+
+    if update.name.as_ref().is_some_and(|name| !valid_name(name))
+        || update.amount_cents.is_some_and(|amount| amount <= 0)
+        || update
+            .alert_thresholds
+            .as_ref()
+            .is_some_and(|thresholds| !is_valid_thresholds(thresholds))
+    {
+        return Err(ApiError::BadRequest.into());
+    }
+
+    println!(
+        "Trying to update budget {} for customer {}",
+        budget_id, customer_id
+    );
+
+    let (before, after) = web::block(move || {
+        let before = budgets_repo.get_by_id(budget_id).map_err(|err| match err {
+            RepoError::NotFound => ApiError::NotFound,
+            _ => ApiError::InternalError,
+        })?;
+
+        if before.customer_id != customer_id {
+            return Err(ApiError::Unauthorized);
+        }
+
+        let after = budgets_repo
+            .update(budget_id, update)
+            .map_err(|_| ApiError::InternalError)?;
+
+        Ok((before, after))
+    })
+    .await
+    .map_err(|_| ApiError::InternalError)??;
+
+    let after_rest: BudgetRest = (&after).into();
+
+    let mut audit_entry = new_audit_entry(
+        &req,
+        ActorType::Customer,
+        customer_id,
+        AuditAction::UpdateBudget,
+        AuditEntity::Budget,
+        Some(budget_id),
+    );
+    audit_entry.before_snapshot = snapshot(&BudgetRest::from(&before));
+    audit_entry.after_snapshot = snapshot(&after_rest);
+    record_audit(audit_repo, audit_entry).await;
+
+    Ok(HttpResponse::Ok()
+        .insert_header(ContentType::json())
+        .json(web::Json(after_rest)))
+}
+
+pub async fn delete_budget<BuR, AuR>(
+    budgets_repo: Data<BuR>,
+    audit_repo: Data<AuR>,
+    req: HttpRequest,
+    path: Path<(i32, i32)>,
+) -> Result<HttpResponse, actix_web::Error>
+where
+    BuR: RepoGetById<Budget> + RepoDeleteById<Budget>,
+    AuR: RepoCreate<AuditEntry, NewAuditEntry>,
+{
+    let (customer_id, budget_id) = path.into_inner();
+
+    println!(
+        "Trying to delete budget {} for customer {}",
+        budget_id, customer_id
+    );
+
+    let deleted_budget = web::block(move || {
+        let existing_budget = match budgets_repo.get_by_id(budget_id) {
+            Ok(budget) => {
+                if budget.customer_id != customer_id {
+                    return Err(ApiError::Unauthorized);
+                }
+                Some(budget)
+            }
+            Err(RepoError::NotFound) => None,
+            _ => return Err(ApiError::InternalError),
+        };
+
+        budgets_repo
+            .delete_by_id(budget_id)
+            .map_err(|_| ApiError::InternalError)?;
+
+        Ok(existing_budget)
+    })
+    .await
+    .map_err(|_| ApiError::InternalError)??;
+
+    // deleting something that isn't there is a no-op, nothing to audit
+    if let Some(budget) = deleted_budget {
+        let mut audit_entry = new_audit_entry(
+            &req,
+            ActorType::Customer,
+            customer_id,
+            AuditAction::DeleteBudget,
+            AuditEntity::Budget,
+            Some(budget.id),
+        );
+        audit_entry.before_snapshot = snapshot(&BudgetRest::from(&budget));
+        record_audit(audit_repo, audit_entry).await;
+    }
+
+    Ok(HttpResponse::NoContent().body(""))
+}
+
+pub async fn find_budget_alerts<BuR>(
+    budgets_repo: Data<BuR>,
+    path: Path<i32>,
+    query: Query<FindBudgetAlertQueryRest>,
+) -> Result<HttpResponse, actix_web::Error>
+where
+    BuR: RepoFind<BudgetAlert, FindBudgetAlertQuery>,
+{
+    let customer_id = path.into_inner();
+
+    let alert_query = FindBudgetAlertQuery {
+        customer_id,
+        budget_id: query.budget_id,
+    };
+
+    println!("Trying to get budget alerts for customer {}", customer_id);
+
+    let alerts = web::block(move || budgets_repo.find(alert_query))
+        .await
+        .map_err(|_| ApiError::InternalError)?
+        .map_err(|_| ApiError::InternalError)?;
+
+    // enforced query on customer_id so should always be fine, but safe > sorry
+    for alert in alerts.iter() {
+        if alert.customer_id != customer_id {
+            return Err(ApiError::BadRequest.into());
+        }
+    }
+
+    Ok(HttpResponse::Ok()
+        .insert_header(ContentType::json())
+        .json(web::Json::<BudgetAlertsRest>(alerts.into())))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::audit::util::appended;
+    use crate::fixtures::dt;
+    use actix_web::{
+        body::to_bytes,
+        http::StatusCode,
+        test,
+        web::{Data, Json},
+    };
+    use chrono::NaiveDate;
+    use mockall::{mock, predicate::eq};
+
+    use crate::{
+        api::{
+            budgets::{
+                handlers::{create_budget, find_budgets, get_budget, update_budget},
+                models::{BudgetRest, BudgetUpdateRest, BudgetsRest, NewBudgetRest},
+            },
+            error::ApiError,
+            spending::models::SpendingCategoryRest,
+        },
+        error::RepoError,
+        models::{
+            audit::{AuditEntry, NewAuditEntry},
+            budget::{Budget, BudgetProgress, BudgetUpdate, FindBudgetProgressQuery, NewBudget},
+            category::SpendingCategory,
+        },
+        traits::{MockRepoCreate, MockRepoFind, RepoFind, RepoGetById, RepoUpdate},
+    };
+
+    fn budget(id: i32, customer_id: i32) -> Budget {
+        Budget {
+            id,
+            customer_id,
+            name: "Eating out".to_string(),
+            category: Some(SpendingCategory::Dining),
+            account_number: None,
+            amount_cents: 40000,
+            alert_thresholds: vec![80, 100],
+            created_at: dt(),
+            updated_at: dt(),
+        }
+    }
+
+    mock! {
+        pub BuR { }
+        impl RepoGetById<Budget> for BuR {
+            fn get_by_id(&self, id: i32) -> Result<Budget, RepoError>;
+        }
+        impl RepoFind<BudgetProgress, FindBudgetProgressQuery> for BuR {
+            fn find(&self, query: FindBudgetProgressQuery) -> Result<Vec<BudgetProgress>, RepoError>;
+        }
+        impl RepoUpdate<Budget, BudgetUpdate> for BuR {
+            fn update(&self, id: i32, update: BudgetUpdate) -> Result<Budget, RepoError>;
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_create_budget_default_thresholds() {
+        let customer_id = 5;
+
+        let mut mock_budgets_repo = MockRepoCreate::<Budget, NewBudget>::new();
+        mock_budgets_repo
+            .expect_create()
+            .with(eq(NewBudget {
+                customer_id,
+                name: "Eating out".to_string(),
+                category: Some(SpendingCategory::Dining),
+                account_number: None,
+                amount_cents: 40000,
+                alert_thresholds: vec![80, 100],
+            }))
+            .times(1)
+            .returning(move |_| Ok(budget(1, customer_id)));
+
+        let mut mock_audit_repo = MockRepoCreate::<AuditEntry, NewAuditEntry>::new();
+        mock_audit_repo
+            .expect_create()
+            .withf(|entry| entry.action == "create_budget" && entry.entity_type == "budget")
+            .times(1)
+            .returning(|entry| Ok(appended(entry)));
+
+        let res = create_budget(
+            Data::new(mock_budgets_repo),
+            Data::new(mock_audit_repo),
+            test::TestRequest::post().to_http_request(),
+            customer_id.into(),
+            Json(NewBudgetRest {
+                name: " Eating out ".to_string(),
+                category: Some(SpendingCategoryRest::Dining),
+                account_number: None,
+                amount_cents: 40000,
+                alert_thresholds: None,
+            }),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(StatusCode::CREATED, res.status());
+
+        let actual: BudgetRest =
+            serde_json::from_slice(&to_bytes(res.into_body()).await.unwrap()).unwrap();
+        assert_eq!(Some(SpendingCategoryRest::Dining), actual.category);
+        assert_eq!(vec![80, 100], actual.alert_thresholds);
+    }
+
+    #[actix_web::test]
+    async fn test_create_budget_bad_thresholds_error() {
+        let mut mock_budgets_repo = MockRepoCreate::<Budget, NewBudget>::new();
+        mock_budgets_repo.expect_create().times(0);
+
+        let res = create_budget(
+            Data::new(mock_budgets_repo),
+            Data::new(MockRepoCreate::<AuditEntry, NewAuditEntry>::new()),
+            test::TestRequest::post().to_http_request(),
+            5.into(),
+            Json(NewBudgetRest {
+                name: "Eating out".to_string(),
+                category: Some(SpendingCategoryRest::Dining),
+                account_number: None,
+                amount_cents: 40000,
+                alert_thresholds: Some(vec![100, 80]),
+            }),
+        )
+        .await;
+
+        assert!(res.is_err_and(|e| { e.to_string() == ApiError::BadRequest.to_string() }));
+    }
+
+    #[actix_web::test]
+    async fn test_find_budgets_progress() {
+        let mut mock_budgets_repo = MockRepoFind::<BudgetProgress, FindBudgetProgressQuery>::new();
+        mock_budgets_repo
+            .expect_find()
+            .withf(|query| query.customer_id == 5 && query.budget_id.is_none())
+            .times(1)
+            .returning(|_| {
+                Ok(vec![BudgetProgress {
+                    budget: budget(1, 5),
+                    period_start: NaiveDate::from_ymd_opt(2023, 8, 1).unwrap(),
+                    spent_cents: 45000,
+                }])
+            });
+
+        let res = find_budgets(Data::new(mock_budgets_repo), 5.into())
+            .await
+            .unwrap();
+
+        assert_eq!(StatusCode::OK, res.status());
+
+        let actual: BudgetsRest =
+            serde_json::from_slice(&to_bytes(res.into_body()).await.unwrap()).unwrap();
+        assert_eq!(1, actual.budgets.len());
+        assert_eq!("2023-08-01", actual.budgets[0].period_start);
+        assert_eq!(-5000, actual.budgets[0].remaining_cents);
+        assert_eq!(112, actual.budgets[0].percent_used);
+    }
+
+    #[actix_web::test]
+    async fn test_get_budget_unauthorized_error() {
+        let mut mock_budgets_repo = MockBuR::new();
+        mock_budgets_repo
+            .expect_get_by_id()
+            .times(1)
+            .returning(|id| Ok(budget(id, 6)));
+        mock_budgets_repo.expect_find().times(0);
+
+        let res = get_budget(Data::new(mock_budgets_repo), (5, 1).into()).await;
+
+        assert!(res.is_err_and(|e| { e.to_string() == ApiError::Unauthorized.to_string() }));
+    }
+
+    #[actix_web::test]
+    async fn test_update_budget_bad_amount_error() {
+        let mut mock_budgets_repo = MockBuR::new();
+        mock_budgets_repo.expect_get_by_id().times(0);
+        mock_budgets_repo.expect_update().times(0);
+
+        let res = update_budget(
+            Data::new(mock_budgets_repo),
+            Data::new(MockRepoCreate::<AuditEntry, NewAuditEntry>::new()),
+            test::TestRequest::patch().to_http_request(),
+            (5, 1).into(),
+            Json(BudgetUpdateRest {
+                name: None,
+                amount_cents: Some(0),
+                alert_thresholds: None,
+            }),
+        )
+        .await;
+
+        assert!(res.is_err_and(|e| { e.to_string() == ApiError::BadRequest.to_string() }));
+    }
+}
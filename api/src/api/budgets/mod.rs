@@ -0,0 +1,49 @@
+pub mod handlers;
+pub mod models;
+pub mod transform;
+pub mod util;
+
+use actix_web::web;
+
+use crate::{
+    api::budgets,
+    models::{
+        audit::{AuditEntry, NewAuditEntry},
+        budget::{
+            Budget, BudgetAlert, BudgetProgress, BudgetUpdate, FindBudgetAlertQuery,
+            FindBudgetProgressQuery, NewBudget,
+        },
+    },
+    traits::{RepoCreate, RepoDeleteById, RepoFind, RepoGetById, RepoUpdate},
+};
+
+pub fn configure_budgets_api<BuR, AuR>(cfg: &mut web::ServiceConfig)
+where
+    BuR: RepoCreate<Budget, NewBudget>
+        + RepoFind<BudgetProgress, FindBudgetProgressQuery>
+        + RepoGetById<Budget>
+        + RepoUpdate<Budget, BudgetUpdate>
+        + RepoDeleteById<Budget>
+        + RepoFind<BudgetAlert, FindBudgetAlertQuery>,
+    AuR: RepoCreate<AuditEntry, NewAuditEntry>,
+{
+    cfg.service(
+        web::scope("/api/customers/{customer_id}/budgets")
+            .service(
+                web::resource("")
+                    .route(web::post().to(budgets::handlers::create_budget::<BuR, AuR>))
+                    .route(web::get().to(budgets::handlers::find_budgets::<BuR>)),
+            )
+            // before /{budget_id} so it isn't taken for an id
+            .service(
+                web::resource("/alerts")
+                    .route(web::get().to(budgets::handlers::find_budget_alerts::<BuR>)),
+            )
+            .service(
+                web::resource("/{budget_id}")
+                    .route(web::get().to(budgets::handlers::get_budget::<BuR>))
+                    .route(web::patch().to(budgets::handlers::update_budget::<BuR, AuR>))
+                    .route(web::delete().to(budgets::handlers::delete_budget::<BuR, AuR>)),
+            ),
+    );
+}
@@ -0,0 +1,88 @@
+use serde::{Deserialize, Serialize};
+
+use crate::api::spending::models::SpendingCategoryRest;
+
+#[cfg_attr(test, derive(Deserialize, PartialEq, Debug))]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BudgetRest {
+    pub id: i32,
+    pub customer_id: i32,
+    pub name: String,
+    pub category: Option<SpendingCategoryRest>,
+    pub account_number: Option<String>,
+    pub amount_cents: i64,
+    pub alert_thresholds: Vec<i32>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+// a budget with how this month is tracking against it
+#[cfg_attr(test, derive(Deserialize, PartialEq, Debug))]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BudgetProgressRest {
+    pub budget: BudgetRest,
+    pub period_start: String,
+    pub spent_cents: i64,
+    // negative once over budget
+    pub remaining_cents: i64,
+    pub percent_used: i64,
+}
+
+#[cfg_attr(test, derive(Deserialize))]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BudgetsRest {
+    pub budgets: Vec<BudgetProgressRest>,
+}
+
+#[cfg_attr(test, derive(Serialize))]
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct NewBudgetRest {
+    pub name: String,
+    pub category: Option<SpendingCategoryRest>,
+    pub account_number: Option<String>,
+    pub amount_cents: i64,
+    // percentages, 80 and 100 if not given
+    pub alert_thresholds: Option<Vec<i32>>,
+}
+
+#[cfg_attr(test, derive(Serialize))]
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct BudgetUpdateRest {
+    pub name: Option<String>,
+    pub amount_cents: Option<i64>,
+    pub alert_thresholds: Option<Vec<i32>>,
+}
+
+#[cfg_attr(test, derive(Deserialize, PartialEq, Debug))]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BudgetAlertRest {
+    pub id: i32,
+    pub budget_id: i32,
+    pub customer_id: i32,
+    pub period_start: String,
+    pub threshold_percent: i32,
+    pub spent_cents: i64,
+    pub budget_cents: i64,
+    pub transaction_id: Option<i32>,
+    pub created_at: String,
+    pub delivered_at: Option<String>,
+}
+
+#[cfg_attr(test, derive(Deserialize))]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BudgetAlertsRest {
+    pub alerts: Vec<BudgetAlertRest>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FindBudgetAlertQueryRest {
+    pub budget_id: Option<i32>,
+}
@@ -0,0 +1,96 @@
+use crate::models::budget::{
+    Budget, BudgetAlert, BudgetProgress, BudgetUpdate, NewBudget, DEFAULT_ALERT_THRESHOLDS,
+};
+use crate::util::string_opt_from_naive_dt_opt;
+
+use super::models::{
+    BudgetAlertRest, BudgetAlertsRest, BudgetProgressRest, BudgetRest, BudgetUpdateRest,
+    BudgetsRest, NewBudgetRest,
+};
+
+impl From<&Budget> for BudgetRest {
+    fn from(budget: &Budget) -> Self {
+        Self {
+            id: budget.id,
+            customer_id: budget.customer_id,
+            name: budget.name.clone(),
+            category: budget.category.map(|c| c.into()),
+            account_number: budget.account_number.clone(),
+            amount_cents: budget.amount_cents,
+            alert_thresholds: budget.alert_thresholds.clone(),
+            created_at: budget.created_at.to_string(),
+            updated_at: budget.updated_at.to_string(),
+        }
+    }
+}
+
+impl From<&BudgetProgress> for BudgetProgressRest {
+    fn from(progress: &BudgetProgress) -> Self {
+        Self {
+            budget: (&progress.budget).into(),
+            period_start: progress.period_start.to_string(),
+            spent_cents: progress.spent_cents,
+            remaining_cents: progress.budget.amount_cents - progress.spent_cents,
+            percent_used: progress.spent_cents * 100 / progress.budget.amount_cents,
+        }
+    }
+}
+
+impl From<Vec<BudgetProgress>> for BudgetsRest {
+    fn from(budgets: Vec<BudgetProgress>) -> Self {
+        Self {
+            budgets: budgets.iter().map(BudgetProgressRest::from).collect(),
+        }
+    }
+}
+
+impl From<NewBudgetRest> for NewBudget {
+    fn from(budget: NewBudgetRest) -> Self {
+        NewBudget {
+            // comes from path, set by handler
+            customer_id: 0,
+            name: budget.name.trim().to_string(),
+            category: budget.category.map(|c| c.into()),
+            account_number: budget.account_number.filter(|n| !n.is_empty()),
+            amount_cents: budget.amount_cents,
+            alert_thresholds: budget
+                .alert_thresholds
+                .unwrap_or(DEFAULT_ALERT_THRESHOLDS.to_vec()),
+        }
+    }
+}
+
+impl From<BudgetUpdateRest> for BudgetUpdate {
+    fn from(update: BudgetUpdateRest) -> Self {
+        BudgetUpdate {
+            name: update.name.map(|n| n.trim().to_string()),
+            amount_cents: update.amount_cents,
+            alert_thresholds: update.alert_thresholds,
+        }
+    }
+}
+
+impl From<&BudgetAlert> for BudgetAlertRest {
+    fn from(alert: &BudgetAlert) -> Self {
+        Self {
+            id: alert.id,
+            budget_id: alert.budget_id,
+            customer_id: alert.customer_id,
+            period_start: alert.period_start.to_string(),
+            threshold_percent: alert.threshold_percent,
+            spent_cents: alert.spent_cents,
+            budget_cents: alert.budget_cents,
+            transaction_id: alert.transaction_id,
+            created_at: alert.created_at.to_string(),
+            delivered_at: string_opt_from_naive_dt_opt(alert.delivered_at),
+        }
+    }
+}
+
+impl From<Vec<BudgetAlert>> for BudgetAlertsRest {
+    fn from(alerts: Vec<BudgetAlert>) -> Self {
+        Self {
+            alerts: alerts.iter().map(BudgetAlertRest::from).collect(),
+        }
+    }
+}
@@ -0,0 +1,56 @@
+use actix_web::{web, web::Data};
+
+use crate::{
+    error::RepoError,
+    models::budget::{BudgetAlert, BudgetAlertDelivered, BudgetCheck},
+    notifications::{Notification, Notifier},
+    traits::{RepoCreate, RepoUpdate},
+};
+
+fn dollars(cents: i64) -> String {
+    format!("${}.{:02}", cents / 100, (cents % 100).abs())
+}
+
+pub fn budget_alert_notification(alert: &BudgetAlert) -> Notification {
+    Notification {
+        customer_id: alert.customer_id,
+        kind: "budget_alert".to_string(),
+        subject: format!("{}% of a monthly budget used", alert.threshold_percent),
+        body: format!(
+            "You've spent {} of your {} budget this month",
+            dollars(alert.spent_cents),
+            dollars(alert.budget_cents)
+        ),
+    }
+}
+
+// the transaction has settled by the time we get here so a failure is logged, not returned.
+// alerts that couldn't be delivered keep a null delivered_at
+pub async fn check_budgets<BuR, N>(budgets_repo: Data<BuR>, notifier: Data<N>, transaction_id: i32)
+where
+    BuR: RepoCreate<Vec<BudgetAlert>, BudgetCheck> + RepoUpdate<BudgetAlert, BudgetAlertDelivered>,
+    N: Notifier,
+{
+    let res = web::block(move || {
+        let alerts = budgets_repo.create(BudgetCheck { transaction_id })?;
+
+        for alert in alerts.iter() {
+            match notifier.notify(&budget_alert_notification(alert)) {
+                Ok(()) => {
+                    let delivered = BudgetAlertDelivered {
+                        delivered_at: chrono::Utc::now().naive_utc(),
+                    };
+                    budgets_repo.update(alert.id, delivered)?;
+                }
+                Err(err) => println!("couldn't deliver budget alert {}: {}", alert.id, err),
+            }
+        }
+
+        Ok::<_, RepoError>(())
+    })
+    .await;
+
+    if !matches!(res, Ok(Ok(_))) {
+        println!("couldn't check budgets for transaction {}", transaction_id);
+    }
+}
@@ -0,0 +1,129 @@
+use std::fmt;
+
+use actix_web::{error, http::StatusCode, HttpResponse};
+use serde::Serialize;
+
+use crate::api::error::ApiError;
+
+// the cdr's error list (ResponseErrorListV2), standard error codes rather than our plaintext ones
+#[derive(Debug, PartialEq)]
+pub enum CdrError {
+    MissingHeader(&'static str),
+    InvalidHeader(&'static str),
+    UnsupportedVersion,
+    InvalidField(&'static str),
+    InvalidPageSize,
+    InvalidPage,
+    // not one of the customer's, or not shared with the recipient
+    InvalidBankingAccount,
+    Unauthorized,
+    Forbidden,
+    Unexpected,
+}
+
+impl CdrError {
+    fn code(&self) -> &'static str {
+        match self {
+            CdrError::MissingHeader(_) => "urn:au-cds:error:cds-all:Header/Missing",
+            CdrError::InvalidHeader(_) => "urn:au-cds:error:cds-all:Header/Invalid",
+            CdrError::UnsupportedVersion => "urn:au-cds:error:cds-all:Header/UnsupportedVersion",
+            CdrError::InvalidField(_) => "urn:au-cds:error:cds-all:Field/Invalid",
+            CdrError::InvalidPageSize => "urn:au-cds:error:cds-all:Field/InvalidPageSize",
+            CdrError::InvalidPage => "urn:au-cds:error:cds-all:Field/InvalidPage",
+            CdrError::InvalidBankingAccount => {
+                "urn:au-cds:error:cds-banking:Authorisation/InvalidBankingAccount"
+            }
+            CdrError::Unauthorized | CdrError::Forbidden => {
+                "urn:au-cds:error:cds-all:GeneralError/Expected"
+            }
+            CdrError::Unexpected => "urn:au-cds:error:cds-all:GeneralError/Unexpected",
+        }
+    }
+
+    fn title(&self) -> &'static str {
+        match self {
+            CdrError::MissingHeader(_) => "Missing Required Header",
+            CdrError::InvalidHeader(_) => "Invalid Header",
+            CdrError::UnsupportedVersion => "Unsupported Version",
+            CdrError::InvalidField(_) => "Invalid Field",
+            CdrError::InvalidPageSize => "Invalid Page Size",
+            CdrError::InvalidPage => "Invalid Page",
+            CdrError::InvalidBankingAccount => "Invalid Banking Account",
+            CdrError::Unauthorized | CdrError::Forbidden => "Expected Error Encountered",
+            CdrError::Unexpected => "Unexpected Error Encountered",
+        }
+    }
+
+    fn detail(&self) -> String {
+        match self {
+            CdrError::MissingHeader(name) | CdrError::InvalidHeader(name) => name.to_string(),
+            CdrError::InvalidField(name) => name.to_string(),
+            CdrError::UnsupportedVersion => {
+                "no supported version in the requested range".to_string()
+            }
+            CdrError::InvalidPageSize => "page-size must be between 1 and 1000".to_string(),
+            CdrError::InvalidPage => "page is past the last page".to_string(),
+            CdrError::InvalidBankingAccount => "accountId".to_string(),
+            CdrError::Unauthorized => "a valid access token is needed".to_string(),
+            CdrError::Forbidden => "the consent doesn't cover this".to_string(),
+            CdrError::Unexpected => "".to_string(),
+        }
+    }
+}
+
+impl fmt::Display for CdrError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", *self)
+    }
+}
+
+impl From<ApiError> for CdrError {
+    fn from(err: ApiError) -> Self {
+        match err {
+            ApiError::Unauthorized => CdrError::Unauthorized,
+            ApiError::Forbidden => CdrError::Forbidden,
+            _ => CdrError::Unexpected,
+        }
+    }
+}
+
+#[cfg_attr(test, derive(serde::Deserialize, PartialEq, Debug))]
+#[derive(Serialize)]
+pub struct CdrErrorRest {
+    pub code: String,
+    pub title: String,
+    pub detail: String,
+}
+
+#[cfg_attr(test, derive(serde::Deserialize, PartialEq, Debug))]
+#[derive(Serialize)]
+pub struct CdrErrorListRest {
+    pub errors: Vec<CdrErrorRest>,
+}
+
+impl error::ResponseError for CdrError {
+    fn error_response(&self) -> HttpResponse {
+        HttpResponse::build(self.status_code()).json(CdrErrorListRest {
+            errors: vec![CdrErrorRest {
+                code: self.code().to_string(),
+                title: self.title().to_string(),
+                detail: self.detail(),
+            }],
+        })
+    }
+
+    fn status_code(&self) -> StatusCode {
+        match *self {
+            CdrError::MissingHeader(_)
+            | CdrError::InvalidHeader(_)
+            | CdrError::InvalidField(_)
+            | CdrError::InvalidPageSize => StatusCode::BAD_REQUEST,
+            CdrError::UnsupportedVersion => StatusCode::NOT_ACCEPTABLE,
+            CdrError::InvalidPage => StatusCode::UNPROCESSABLE_ENTITY,
+            CdrError::InvalidBankingAccount => StatusCode::NOT_FOUND,
+            CdrError::Unauthorized => StatusCode::UNAUTHORIZED,
+            CdrError::Forbidden => StatusCode::FORBIDDEN,
+            CdrError::Unexpected => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
@@ -0,0 +1,677 @@
+use actix_web::web::{Data, Path, Query};
+use actix_web::{web, HttpRequest, HttpResponse};
+use chrono::{DateTime, Duration, NaiveDateTime, Utc};
+
+use super::error::CdrError;
+use super::models::{
+    BankingAccountListRest, BankingAccountRest, BankingBalanceListRest, BankingBalanceRest,
+    BankingPayeeListRest, BankingPayeeRest, BankingTransactionListRest, CdrPageQueryRest,
+    CdrResponseRest, CdrTransactionQueryRest,
+};
+use super::transform::banking_transaction;
+use super::util::{
+    cdr_consent, cdr_response, negotiate_version, paginate, parse_paging, single_links,
+};
+use crate::models::account::{Account, FindAccountQuery};
+use crate::models::consent::Consent;
+use crate::models::oauth::{FindOAuthTokenQuery, OAuthScope, OAuthToken};
+use crate::models::payee::{FindPayeeQuery, Payee};
+use crate::models::transaction::{FindAccountHistoryQuery, Transaction};
+use crate::traits::{RepoFind, RepoGetById};
+
+// how far back transactions go when the recipient doesn't say
+const DEFAULT_HISTORY_DAYS: i64 = 90;
+const MAX_TEXT_LEN: usize = 100;
+
+// accountId is ours, but a recipient can send anything. one that isn't a number, isn't one of the
+// customer's or wasn't shared is the same error
+async fn shared_account<AR>(
+    accounts_repo: Data<AR>,
+    consent: &Consent,
+    account_id: &str,
+) -> Result<Account, CdrError>
+where
+    AR: RepoFind<Account, FindAccountQuery>,
+{
+    let account_id = account_id
+        .parse::<i32>()
+        .ok()
+        .filter(|account_id| consent.includes_account(*account_id))
+        .ok_or(CdrError::InvalidBankingAccount)?;
+    let customer_id = consent.customer_id;
+
+    web::block(move || {
+        accounts_repo.find(FindAccountQuery {
+            account_id: Some(account_id),
+            customer_id,
+            account_number: None,
+        })
+    })
+    .await
+    .map_err(|_| CdrError::Unexpected)?
+    .map_err(|_| CdrError::Unexpected)?
+    .into_iter()
+    .next()
+    .ok_or(CdrError::InvalidBankingAccount)
+}
+
+async fn shared_accounts<AR>(
+    accounts_repo: Data<AR>,
+    consent: &Consent,
+) -> Result<Vec<Account>, CdrError>
+where
+    AR: RepoFind<Account, FindAccountQuery>,
+{
+    let customer_id = consent.customer_id;
+    let mut accounts = web::block(move || {
+        accounts_repo.find(FindAccountQuery {
+            account_id: None,
+            customer_id,
+            account_number: None,
+        })
+    })
+    .await
+    .map_err(|_| CdrError::Unexpected)?
+    .map_err(|_| CdrError::Unexpected)?;
+
+    accounts.retain(|account| consent.includes_account(account.id));
+    Ok(accounts)
+}
+
+fn parse_time(
+    value: &Option<String>,
+    field: &'static str,
+) -> Result<Option<NaiveDateTime>, CdrError> {
+    value
+        .as_ref()
+        .map(|v| {
+            DateTime::parse_from_rfc3339(v)
+                .map(|dt| dt.naive_utc())
+                .map_err(|_| CdrError::InvalidField(field))
+        })
+        .transpose()
+}
+
+pub async fn get_accounts<AR, OR>(
+    accounts_repo: Data<AR>,
+    oauth_repo: Data<OR>,
+    req: HttpRequest,
+    query: Query<CdrPageQueryRest>,
+) -> Result<HttpResponse, CdrError>
+where
+    AR: RepoFind<Account, FindAccountQuery>,
+    OR: RepoFind<OAuthToken, FindOAuthTokenQuery> + RepoGetById<Consent>,
+{
+    let version = negotiate_version(&req)?;
+    let paging = parse_paging(&query.page, &query.page_size)?;
+    let consent = cdr_consent(oauth_repo, &req, OAuthScope::AccountsRead).await?;
+
+    println!(
+        "Trying to share accounts of customer {}",
+        consent.customer_id
+    );
+
+    let accounts = shared_accounts(accounts_repo, &consent).await?;
+    let (accounts, links, meta) = paginate(&req, accounts, paging)?;
+
+    Ok(cdr_response(
+        &req,
+        version,
+        CdrResponseRest {
+            data: BankingAccountListRest {
+                accounts: accounts.iter().map(BankingAccountRest::from).collect(),
+            },
+            links,
+            meta,
+        },
+    ))
+}
+
+pub async fn get_balances<AR, OR>(
+    accounts_repo: Data<AR>,
+    oauth_repo: Data<OR>,
+    req: HttpRequest,
+    query: Query<CdrPageQueryRest>,
+) -> Result<HttpResponse, CdrError>
+where
+    AR: RepoFind<Account, FindAccountQuery>,
+    OR: RepoFind<OAuthToken, FindOAuthTokenQuery> + RepoGetById<Consent>,
+{
+    let version = negotiate_version(&req)?;
+    let paging = parse_paging(&query.page, &query.page_size)?;
+    let consent = cdr_consent(oauth_repo, &req, OAuthScope::AccountsRead).await?;
+
+    println!(
+        "Trying to share balances of customer {}",
+        consent.customer_id
+    );
+
+    let accounts = shared_accounts(accounts_repo, &consent).await?;
+    let (accounts, links, meta) = paginate(&req, accounts, paging)?;
+
+    Ok(cdr_response(
+        &req,
+        version,
+        CdrResponseRest {
+            data: BankingBalanceListRest {
+                balances: accounts.iter().map(BankingBalanceRest::from).collect(),
+            },
+            links,
+            meta,
+        },
+    ))
+}
+
+pub async fn get_balance<AR, OR>(
+    accounts_repo: Data<AR>,
+    oauth_repo: Data<OR>,
+    req: HttpRequest,
+    path: Path<String>,
+) -> Result<HttpResponse, CdrError>
+where
+    AR: RepoFind<Account, FindAccountQuery>,
+    OR: RepoFind<OAuthToken, FindOAuthTokenQuery> + RepoGetById<Consent>,
+{
+    let version = negotiate_version(&req)?;
+    let consent = cdr_consent(oauth_repo, &req, OAuthScope::AccountsRead).await?;
+    let account_id = path.into_inner();
+
+    println!(
+        "Trying to share balance of account {} of customer {}",
+        account_id, consent.customer_id
+    );
+
+    let account = shared_account(accounts_repo, &consent, &account_id).await?;
+    let (links, meta) = single_links(&req);
+
+    Ok(cdr_response(
+        &req,
+        version,
+        CdrResponseRest {
+            data: BankingBalanceRest::from(&account),
+            links,
+            meta,
+        },
+    ))
+}
+
+pub async fn get_transactions<AR, TR, OR>(
+    accounts_repo: Data<AR>,
+    transactions_repo: Data<TR>,
+    oauth_repo: Data<OR>,
+    req: HttpRequest,
+    path: Path<String>,
+    query: Query<CdrTransactionQueryRest>,
+) -> Result<HttpResponse, CdrError>
+where
+    AR: RepoFind<Account, FindAccountQuery>,
+    TR: RepoFind<Transaction, FindAccountHistoryQuery>,
+    OR: RepoFind<OAuthToken, FindOAuthTokenQuery> + RepoGetById<Consent>,
+{
+    let version = negotiate_version(&req)?;
+    let paging = parse_paging(&query.page, &query.page_size)?;
+
+    let to =
+        parse_time(&query.newest_time, "newest-time")?.unwrap_or_else(|| Utc::now().naive_utc());
+    let from = parse_time(&query.oldest_time, "oldest-time")?
+        .unwrap_or(to - Duration::days(DEFAULT_HISTORY_DAYS));
+    let search = query
+        .text
+        .as_ref()
+        .map(|text| text.trim().to_string())
+        .filter(|text| !text.is_empty());
+    if from > to {
+        return Err(CdrError::InvalidField("oldest-time"));
+    }
+    if search
+        .as_ref()
+        .is_some_and(|text| text.len() > MAX_TEXT_LEN)
+    {
+        return Err(CdrError::InvalidField("text"));
+    }
+
+    let consent = cdr_consent(oauth_repo, &req, OAuthScope::TransactionsRead).await?;
+    let account_id = path.into_inner();
+
+    println!(
+        "Trying to share transactions of account {} of customer {}",
+        account_id, consent.customer_id
+    );
+
+    let account = shared_account(accounts_repo, &consent, &account_id).await?;
+
+    let history_query = FindAccountHistoryQuery {
+        customer_id: consent.customer_id,
+        account_number: account.account_number.clone(),
+        from,
+        to,
+        search,
+    };
+    let transactions = web::block(move || transactions_repo.find(history_query))
+        .await
+        .map_err(|_| CdrError::Unexpected)?
+        .map_err(|_| CdrError::Unexpected)?;
+
+    let transactions = transactions
+        .iter()
+        .filter_map(|transaction| banking_transaction(&account, transaction))
+        .collect();
+    let (transactions, links, meta) = paginate(&req, transactions, paging)?;
+
+    Ok(cdr_response(
+        &req,
+        version,
+        CdrResponseRest {
+            data: BankingTransactionListRest { transactions },
+            links,
+            meta,
+        },
+    ))
+}
+
+pub async fn get_payees<PR, OR>(
+    payees_repo: Data<PR>,
+    oauth_repo: Data<OR>,
+    req: HttpRequest,
+    query: Query<CdrPageQueryRest>,
+) -> Result<HttpResponse, CdrError>
+where
+    PR: RepoFind<Payee, FindPayeeQuery>,
+    OR: RepoFind<OAuthToken, FindOAuthTokenQuery> + RepoGetById<Consent>,
+{
+    let version = negotiate_version(&req)?;
+    let paging = parse_paging(&query.page, &query.page_size)?;
+    let consent = cdr_consent(oauth_repo, &req, OAuthScope::PayeesRead).await?;
+    let customer_id = consent.customer_id;
+
+    println!("Trying to share payees of customer {}", customer_id);
+
+    let payees = web::block(move || payees_repo.find(FindPayeeQuery { customer_id }))
+        .await
+        .map_err(|_| CdrError::Unexpected)?
+        .map_err(|_| CdrError::Unexpected)?;
+    let (payees, links, meta) = paginate(&req, payees, paging)?;
+
+    Ok(cdr_response(
+        &req,
+        version,
+        CdrResponseRest {
+            data: BankingPayeeListRest {
+                payees: payees.iter().map(BankingPayeeRest::from).collect(),
+            },
+            links,
+            meta,
+        },
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fixtures::account;
+    use crate::{
+        api::cdr::{
+            error::CdrError,
+            handlers::{get_accounts, get_balance, get_transactions},
+            models::{
+                BankingAccountListRest, BankingProductCategoryRest, BankingTransactionListRest,
+                BankingTransactionStatusRest, BankingTransactionTypeRest, CdrPageQueryRest,
+                CdrResponseRest, CdrTransactionQueryRest, OpenStatusRest,
+            },
+        },
+        error::RepoError,
+        models::{
+            account::{Account, AccountType, FindAccountQuery},
+            consent::Consent,
+            oauth::{FindOAuthTokenQuery, OAuthToken, OAuthTokenKind},
+            transaction::{
+                FindAccountHistoryQuery, Transaction, TransactionStatus, TransactionType,
+            },
+        },
+        oauth::hash_token,
+        traits::{MockRepoFind, RepoFind, RepoGetById},
+    };
+
+    use actix_web::{
+        body::to_bytes,
+        http::StatusCode,
+        test,
+        web::{Data, Query},
+    };
+    use chrono::{Duration, NaiveDate, Utc};
+    use mockall::{mock, predicate::eq};
+
+    mock! {
+        pub OR { }
+        impl RepoFind<OAuthToken, FindOAuthTokenQuery> for OR {
+            fn find(&self, query: FindOAuthTokenQuery) -> Result<Vec<OAuthToken>, RepoError>;
+        }
+        impl RepoGetById<Consent> for OR {
+            fn get_by_id(&self, id: i32) -> Result<Consent, RepoError>;
+        }
+    }
+
+    // customer 5's token and the consent behind it, both for scope and sharing only account 52
+    fn token_repo(scope: &str) -> MockOR {
+        let now = Utc::now().naive_utc();
+        let token = OAuthToken {
+            id: 12,
+            grant_id: 4,
+            customer_id: 5,
+            token_kind: OAuthTokenKind::Access,
+            token_hash: hash_token("the-token"),
+            scope: scope.to_string(),
+            expires_at: now + Duration::minutes(60),
+            revoked_at: None,
+            created_at: now,
+        };
+        let consent = Consent {
+            id: 4,
+            customer_id: 5,
+            client_id: "0123456789abcdef0123456789abcdef".to_string(),
+            client_name: "Budget App".to_string(),
+            scope: scope.to_string(),
+            account_ids: vec![52],
+            created_at: now,
+            updated_at: now,
+            expires_at: now + Duration::days(30),
+            revoked_at: None,
+        };
+        let mut mock_oauth_repo = MockOR::new();
+        mock_oauth_repo
+            .expect_find()
+            .with(eq(FindOAuthTokenQuery {
+                token_hash: hash_token("the-token"),
+            }))
+            .returning(move |_| Ok(vec![token.clone()]));
+        mock_oauth_repo
+            .expect_get_by_id()
+            .with(eq(4))
+            .returning(move |_| Ok(consent.clone()));
+        mock_oauth_repo
+    }
+
+    // customer 5's everyday account, a little into its overdraft
+    fn everyday(id: i32) -> Account {
+        Account {
+            account_name: Some("Everyday".to_string()),
+            date_opened: NaiveDate::from_ymd_opt(2023, 8, 1)
+                .unwrap()
+                .and_hms_opt(9, 10, 11)
+                .unwrap(),
+            account_number: "123456789".to_string(),
+            overdraft_limit_cents: 50000,
+            ..account(id, 5, AccountType::Transaction, -1230)
+        }
+    }
+
+    fn transaction(id: i32, from_us: bool, transaction_status: TransactionStatus) -> Transaction {
+        let date = NaiveDate::from_ymd_opt(2023, 8, 21)
+            .unwrap()
+            .and_hms_opt(4, 46, 30)
+            .unwrap();
+        Transaction {
+            id,
+            customer_id: 5,
+            transaction_type: TransactionType::Internal,
+            from_us,
+            amount_cents: 1050,
+            from_number: "123456789".to_string(),
+            from_bsb: "123456".to_string(),
+            from_name: None,
+            to_number: "987654321".to_string(),
+            to_bsb: "123456".to_string(),
+            to_name: None,
+            available_balance_cents: 0,
+            date_start: date,
+            date_end: Some(date),
+            transaction_status,
+            reversal_of: None,
+            reversed_amount_cents: 0,
+            reversal_reason: None,
+            description: Some("Rent".to_string()),
+            payer_reference: None,
+            payee_reference: Some("unit 4".to_string()),
+            counterpart_of: None,
+            running_balance_cents: None,
+        }
+    }
+
+    fn cdr_request(uri: &str) -> actix_web::HttpRequest {
+        test::TestRequest::get()
+            .uri(uri)
+            .insert_header(("Authorization", "Bearer the-token"))
+            .insert_header(("x-v", "1"))
+            .insert_header(("x-fapi-interaction-id", "abc-123"))
+            .to_http_request()
+    }
+
+    fn no_paging() -> Query<CdrPageQueryRest> {
+        Query(CdrPageQueryRest {
+            page: None,
+            page_size: None,
+        })
+    }
+
+    #[actix_web::test]
+    async fn test_get_accounts() {
+        let mut mock_accounts_repo = MockRepoFind::<Account, FindAccountQuery>::new();
+        mock_accounts_repo
+            .expect_find()
+            .with(eq(FindAccountQuery {
+                account_id: None,
+                customer_id: 5,
+                account_number: None,
+            }))
+            .times(1)
+            // 53 is the customer's but wasn't shared
+            .returning(|_| Ok(vec![everyday(52), everyday(53)]));
+
+        let res = get_accounts(
+            Data::new(mock_accounts_repo),
+            Data::new(token_repo("accounts:read")),
+            cdr_request("/cds-au/v1/banking/accounts"),
+            no_paging(),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(StatusCode::OK, res.status());
+        assert_eq!(res.headers().get("x-v").unwrap(), "1");
+        assert_eq!(
+            res.headers().get("x-fapi-interaction-id").unwrap(),
+            "abc-123"
+        );
+
+        let actual: CdrResponseRest<BankingAccountListRest> =
+            serde_json::from_slice(&to_bytes(res.into_body()).await.unwrap()).unwrap();
+        assert_eq!(actual.data.accounts.len(), 1);
+        let account = &actual.data.accounts[0];
+        assert_eq!(account.account_id, "52");
+        assert_eq!(account.creation_date, "2023-08-01");
+        assert_eq!(account.masked_number, "xxxxx6789");
+        assert_eq!(account.open_status, OpenStatusRest::Open);
+        assert_eq!(
+            account.product_category,
+            BankingProductCategoryRest::TransAndSavingsAccounts
+        );
+        assert_eq!(actual.meta.total_records, Some(1));
+        assert_eq!(actual.meta.total_pages, Some(1));
+        assert_eq!(
+            actual.links.self_link,
+            "http://localhost:8080/cds-au/v1/banking/accounts"
+        );
+        assert_eq!(actual.links.next, None);
+    }
+
+    #[actix_web::test]
+    async fn test_get_accounts_needs_x_v() {
+        let mut mock_accounts_repo = MockRepoFind::<Account, FindAccountQuery>::new();
+        mock_accounts_repo.expect_find().never();
+        let mut mock_oauth_repo = MockOR::new();
+        mock_oauth_repo.expect_find().never();
+
+        let res = get_accounts(
+            Data::new(mock_accounts_repo),
+            Data::new(mock_oauth_repo),
+            test::TestRequest::get()
+                .insert_header(("Authorization", "Bearer the-token"))
+                .to_http_request(),
+            no_paging(),
+        )
+        .await;
+
+        assert_eq!(res.err(), Some(CdrError::MissingHeader("x-v")));
+    }
+
+    #[actix_web::test]
+    async fn test_get_accounts_needs_a_token() {
+        let mut mock_accounts_repo = MockRepoFind::<Account, FindAccountQuery>::new();
+        mock_accounts_repo.expect_find().never();
+
+        let res = get_accounts(
+            Data::new(mock_accounts_repo),
+            Data::new(MockOR::new()),
+            test::TestRequest::get()
+                .insert_header(("x-v", "1"))
+                .to_http_request(),
+            no_paging(),
+        )
+        .await;
+
+        assert_eq!(res.err(), Some(CdrError::Unauthorized));
+    }
+
+    #[actix_web::test]
+    async fn test_get_accounts_out_of_scope() {
+        let mut mock_accounts_repo = MockRepoFind::<Account, FindAccountQuery>::new();
+        mock_accounts_repo.expect_find().never();
+
+        let res = get_accounts(
+            Data::new(mock_accounts_repo),
+            Data::new(token_repo("transactions:read")),
+            cdr_request("/cds-au/v1/banking/accounts"),
+            no_paging(),
+        )
+        .await;
+
+        assert_eq!(res.err(), Some(CdrError::Forbidden));
+    }
+
+    #[actix_web::test]
+    async fn test_get_balance_not_held() {
+        let mut mock_accounts_repo = MockRepoFind::<Account, FindAccountQuery>::new();
+        mock_accounts_repo
+            .expect_find()
+            .with(eq(FindAccountQuery {
+                account_id: Some(52),
+                customer_id: 5,
+                account_number: None,
+            }))
+            .times(1)
+            .returning(|_| Ok(vec![]));
+
+        let res = get_balance(
+            Data::new(mock_accounts_repo),
+            Data::new(token_repo("accounts:read")),
+            cdr_request("/cds-au/v1/banking/accounts/52/balance"),
+            "52".to_string().into(),
+        )
+        .await;
+
+        assert_eq!(res.err(), Some(CdrError::InvalidBankingAccount));
+    }
+
+    #[actix_web::test]
+    async fn test_get_balance_not_shared() {
+        let mut mock_accounts_repo = MockRepoFind::<Account, FindAccountQuery>::new();
+        mock_accounts_repo.expect_find().never();
+
+        for account_id in ["53", "not-a-number"] {
+            let res = get_balance(
+                Data::new(MockRepoFind::<Account, FindAccountQuery>::new()),
+                Data::new(token_repo("accounts:read")),
+                cdr_request("/cds-au/v1/banking/accounts/53/balance"),
+                account_id.to_string().into(),
+            )
+            .await;
+
+            assert_eq!(res.err(), Some(CdrError::InvalidBankingAccount));
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_get_transactions() {
+        let mut mock_accounts_repo = MockRepoFind::<Account, FindAccountQuery>::new();
+        mock_accounts_repo
+            .expect_find()
+            .times(1)
+            .returning(|_| Ok(vec![everyday(52)]));
+
+        let newest = NaiveDate::from_ymd_opt(2023, 9, 1)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap();
+        let mut mock_transactions_repo =
+            MockRepoFind::<Transaction, FindAccountHistoryQuery>::new();
+        mock_transactions_repo
+            .expect_find()
+            .with(eq(FindAccountHistoryQuery {
+                customer_id: 5,
+                account_number: "123456789".to_string(),
+                from: newest - Duration::days(90),
+                to: newest,
+                search: None,
+            }))
+            .times(1)
+            .returning(|_| {
+                Ok(vec![
+                    transaction(63, true, TransactionStatus::Error),
+                    transaction(62, true, TransactionStatus::Pending),
+                    transaction(61, false, TransactionStatus::Success),
+                ])
+            });
+
+        let res = get_transactions(
+            Data::new(mock_accounts_repo),
+            Data::new(mock_transactions_repo),
+            Data::new(token_repo("transactions:read")),
+            cdr_request("/cds-au/v1/banking/accounts/52/transactions"),
+            "52".to_string().into(),
+            Query(CdrTransactionQueryRest {
+                oldest_time: None,
+                newest_time: Some("2023-09-01T00:00:00Z".to_string()),
+                text: None,
+                page: None,
+                page_size: None,
+            }),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(StatusCode::OK, res.status());
+
+        let actual: CdrResponseRest<BankingTransactionListRest> =
+            serde_json::from_slice(&to_bytes(res.into_body()).await.unwrap()).unwrap();
+        let transactions = actual.data.transactions;
+        // the failed one never happened as far as the recipient is concerned
+        assert_eq!(transactions.len(), 2);
+        assert_eq!(transactions[0].transaction_id, "62");
+        assert_eq!(transactions[0].amount, "-10.50");
+        assert_eq!(
+            transactions[0].transaction_type,
+            BankingTransactionTypeRest::TransferOutgoing
+        );
+        assert_eq!(
+            transactions[0].status,
+            BankingTransactionStatusRest::Pending
+        );
+        assert_eq!(transactions[0].posting_date_time, None);
+        assert_eq!(transactions[1].amount, "10.50");
+        assert_eq!(transactions[1].status, BankingTransactionStatusRest::Posted);
+        assert_eq!(
+            transactions[1].posting_date_time,
+            Some("2023-08-21T04:46:30Z".to_string())
+        );
+        assert_eq!(transactions[1].reference, "unit 4");
+    }
+}
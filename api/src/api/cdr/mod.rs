@@ -0,0 +1,53 @@
+pub mod error;
+pub mod handlers;
+pub mod models;
+pub mod transform;
+pub mod util;
+
+use actix_web::web;
+
+use crate::{
+    api::cdr,
+    models::{
+        account::{Account, FindAccountQuery},
+        consent::Consent,
+        oauth::{FindOAuthTokenQuery, OAuthToken},
+        payee::{FindPayeeQuery, Payee},
+        transaction::{FindAccountHistoryQuery, Transaction},
+    },
+    traits::{RepoFind, RepoGetById},
+};
+
+// the consumer data right's banking endpoints, for accredited data recipients holding one of our
+// oauth tokens rather than our own apps
+pub fn configure_cdr_api<AR, TR, PR, OR>(cfg: &mut web::ServiceConfig)
+where
+    AR: RepoFind<Account, FindAccountQuery>,
+    TR: RepoFind<Transaction, FindAccountHistoryQuery>,
+    PR: RepoFind<Payee, FindPayeeQuery>,
+    OR: RepoFind<OAuthToken, FindOAuthTokenQuery> + RepoGetById<Consent>,
+{
+    cfg.service(
+        web::scope("/cds-au/v1/banking")
+            .route(
+                "/accounts",
+                web::get().to(cdr::handlers::get_accounts::<AR, OR>),
+            )
+            .route(
+                "/accounts/balances",
+                web::get().to(cdr::handlers::get_balances::<AR, OR>),
+            )
+            .route(
+                "/accounts/{account_id}/balance",
+                web::get().to(cdr::handlers::get_balance::<AR, OR>),
+            )
+            .route(
+                "/accounts/{account_id}/transactions",
+                web::get().to(cdr::handlers::get_transactions::<AR, TR, OR>),
+            )
+            .route(
+                "/payees",
+                web::get().to(cdr::handlers::get_payees::<PR, OR>),
+            ),
+    );
+}
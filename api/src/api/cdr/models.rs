@@ -0,0 +1,177 @@
+// cdr banking schemas (v1 of each endpoint), field names as the standard has them
+
+use serde::{Deserialize, Serialize};
+
+#[cfg_attr(test, derive(Deserialize, PartialEq, Debug))]
+#[derive(Serialize)]
+pub struct CdrResponseRest<D> {
+    pub data: D,
+    pub links: CdrLinksRest,
+    pub meta: CdrMetaRest,
+}
+
+#[cfg_attr(test, derive(Deserialize, PartialEq, Debug))]
+#[derive(Serialize)]
+pub struct CdrLinksRest {
+    #[serde(rename = "self")]
+    pub self_link: String,
+    // the rest only on paginated responses, and only where there's such a page
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub first: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prev: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last: Option<String>,
+}
+
+// empty on single resources
+#[cfg_attr(test, derive(Deserialize, PartialEq, Debug))]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CdrMetaRest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total_records: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total_pages: Option<i64>,
+}
+
+#[cfg_attr(test, derive(Serialize))]
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct CdrPageQueryRest {
+    pub page: Option<String>,
+    #[serde(rename = "page-size")]
+    pub page_size: Option<String>,
+}
+
+#[cfg_attr(test, derive(Serialize))]
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct CdrTransactionQueryRest {
+    #[serde(rename = "oldest-time")]
+    pub oldest_time: Option<String>,
+    #[serde(rename = "newest-time")]
+    pub newest_time: Option<String>,
+    pub text: Option<String>,
+    pub page: Option<String>,
+    #[serde(rename = "page-size")]
+    pub page_size: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Copy, Clone, Debug, PartialEq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum BankingProductCategoryRest {
+    TransAndSavingsAccounts,
+    TermDeposits,
+    PersLoans,
+}
+
+#[derive(Deserialize, Serialize, Copy, Clone, Debug, PartialEq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum OpenStatusRest {
+    Open,
+    Closed,
+}
+
+#[cfg_attr(test, derive(Deserialize, PartialEq, Debug))]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BankingAccountRest {
+    pub account_id: String,
+    pub creation_date: String,
+    pub display_name: String,
+    pub nickname: Option<String>,
+    pub open_status: OpenStatusRest,
+    pub is_owned: bool,
+    pub masked_number: String,
+    pub product_category: BankingProductCategoryRest,
+    pub product_name: String,
+}
+
+#[cfg_attr(test, derive(Deserialize, PartialEq, Debug))]
+#[derive(Serialize)]
+pub struct BankingAccountListRest {
+    pub accounts: Vec<BankingAccountRest>,
+}
+
+// amounts are strings of dollars, as the standard has them
+#[cfg_attr(test, derive(Deserialize, PartialEq, Debug))]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BankingBalanceRest {
+    pub account_id: String,
+    pub current_balance: String,
+    pub available_balance: String,
+    pub credit_limit: Option<String>,
+    pub currency: String,
+}
+
+#[cfg_attr(test, derive(Deserialize, PartialEq, Debug))]
+#[derive(Serialize)]
+pub struct BankingBalanceListRest {
+    pub balances: Vec<BankingBalanceRest>,
+}
+
+#[derive(Deserialize, Serialize, Copy, Clone, Debug, PartialEq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum BankingTransactionTypeRest {
+    Fee,
+    TransferIncoming,
+    TransferOutgoing,
+}
+
+#[derive(Deserialize, Serialize, Copy, Clone, Debug, PartialEq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum BankingTransactionStatusRest {
+    Pending,
+    Posted,
+}
+
+#[cfg_attr(test, derive(Deserialize, PartialEq, Debug))]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BankingTransactionRest {
+    pub account_id: String,
+    pub transaction_id: String,
+    pub is_detail_available: bool,
+    #[serde(rename = "type")]
+    pub transaction_type: BankingTransactionTypeRest,
+    pub status: BankingTransactionStatusRest,
+    pub description: String,
+    // only once it's posted
+    pub posting_date_time: Option<String>,
+    pub execution_date_time: Option<String>,
+    // negative for money out
+    pub amount: String,
+    pub currency: String,
+    pub reference: String,
+}
+
+#[cfg_attr(test, derive(Deserialize, PartialEq, Debug))]
+#[derive(Serialize)]
+pub struct BankingTransactionListRest {
+    pub transactions: Vec<BankingTransactionRest>,
+}
+
+#[derive(Deserialize, Serialize, Copy, Clone, Debug, PartialEq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum BankingPayeeTypeRest {
+    Domestic,
+}
+
+#[cfg_attr(test, derive(Deserialize, PartialEq, Debug))]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BankingPayeeRest {
+    pub payee_id: String,
+    pub nickname: String,
+    #[serde(rename = "type")]
+    pub payee_type: BankingPayeeTypeRest,
+    pub creation_date: String,
+}
+
+#[cfg_attr(test, derive(Deserialize, PartialEq, Debug))]
+#[derive(Serialize)]
+pub struct BankingPayeeListRest {
+    pub payees: Vec<BankingPayeeRest>,
+}
@@ -0,0 +1,145 @@
+use crate::models::account::{Account, AccountStatus, AccountType};
+use crate::models::payee::Payee;
+use crate::models::transaction::{Transaction, TransactionStatus, TransactionType};
+
+use super::models::{
+    BankingAccountRest, BankingBalanceRest, BankingPayeeRest, BankingPayeeTypeRest,
+    BankingProductCategoryRest, BankingTransactionRest, BankingTransactionStatusRest,
+    BankingTransactionTypeRest, OpenStatusRest,
+};
+
+// everything we hold is in australian dollars
+pub const CURRENCY: &str = "AUD";
+
+// the standard's AmountString, dollars with two decimal places
+pub fn amount_string(cents: i64) -> String {
+    let sign = if cents < 0 { "-" } else { "" };
+    format!("{}{}.{:02}", sign, cents.abs() / 100, cents.abs() % 100)
+}
+
+// DateTimeString, we keep everything in utc
+pub fn date_time_string(date_time: chrono::NaiveDateTime) -> String {
+    date_time.format("%Y-%m-%dT%H:%M:%SZ").to_string()
+}
+
+fn date_string(date_time: chrono::NaiveDateTime) -> String {
+    date_time.format("%Y-%m-%d").to_string()
+}
+
+// only the last four digits go to the recipient
+fn masked_number(account_number: &str) -> String {
+    let keep = account_number.len().saturating_sub(4);
+    account_number
+        .chars()
+        .enumerate()
+        .map(|(i, c)| if i < keep { 'x' } else { c })
+        .collect()
+}
+
+fn product_name(account_type: AccountType) -> &'static str {
+    match account_type {
+        AccountType::Savings => "Savings Account",
+        AccountType::Transaction => "Transaction Account",
+        AccountType::TermDeposit => "Term Deposit",
+        AccountType::Loan => "Personal Loan",
+    }
+}
+
+impl From<AccountType> for BankingProductCategoryRest {
+    fn from(account_type: AccountType) -> Self {
+        match account_type {
+            AccountType::Savings | AccountType::Transaction => {
+                BankingProductCategoryRest::TransAndSavingsAccounts
+            }
+            AccountType::TermDeposit => BankingProductCategoryRest::TermDeposits,
+            AccountType::Loan => BankingProductCategoryRest::PersLoans,
+        }
+    }
+}
+
+impl From<AccountStatus> for OpenStatusRest {
+    fn from(account_status: AccountStatus) -> Self {
+        match account_status {
+            AccountStatus::Active => OpenStatusRest::Open,
+            AccountStatus::Inactive => OpenStatusRest::Closed,
+        }
+    }
+}
+
+impl From<&Account> for BankingAccountRest {
+    fn from(account: &Account) -> Self {
+        let product_name = product_name(account.account_type).to_string();
+        Self {
+            account_id: account.id.to_string(),
+            creation_date: date_string(account.date_opened),
+            display_name: account
+                .account_name
+                .clone()
+                .unwrap_or_else(|| product_name.clone()),
+            nickname: account.account_name.clone(),
+            open_status: account.account_status.into(),
+            // joint accounts too, any holder owns it
+            is_owned: true,
+            masked_number: masked_number(&account.account_number),
+            product_category: account.account_type.into(),
+            product_name,
+        }
+    }
+}
+
+impl From<&Account> for BankingBalanceRest {
+    fn from(account: &Account) -> Self {
+        Self {
+            account_id: account.id.to_string(),
+            current_balance: amount_string(account.balance_cents),
+            available_balance: amount_string(account.available_balance_cents),
+            credit_limit: (account.overdraft_limit_cents > 0)
+                .then(|| amount_string(account.overdraft_limit_cents)),
+            currency: CURRENCY.to_string(),
+        }
+    }
+}
+
+// None for transactions that never went anywhere, the standard has no status for them
+pub fn banking_transaction(
+    account: &Account,
+    transaction: &Transaction,
+) -> Option<BankingTransactionRest> {
+    let status = match transaction.transaction_status {
+        TransactionStatus::Error => return None,
+        _ if transaction.is_settled() => BankingTransactionStatusRest::Posted,
+        _ => BankingTransactionStatusRest::Pending,
+    };
+    let transaction_type = match transaction.transaction_type {
+        TransactionType::Fee => BankingTransactionTypeRest::Fee,
+        _ if transaction.from_us => BankingTransactionTypeRest::TransferOutgoing,
+        _ => BankingTransactionTypeRest::TransferIncoming,
+    };
+    let posted_at = transaction.date_end.unwrap_or(transaction.date_start);
+
+    Some(BankingTransactionRest {
+        account_id: account.id.to_string(),
+        transaction_id: transaction.id.to_string(),
+        is_detail_available: false,
+        transaction_type,
+        status,
+        description: transaction.description.clone().unwrap_or_default(),
+        posting_date_time: (status == BankingTransactionStatusRest::Posted)
+            .then(|| date_time_string(posted_at)),
+        execution_date_time: Some(date_time_string(transaction.date_start)),
+        amount: amount_string(transaction.balance_delta_cents()),
+        currency: CURRENCY.to_string(),
+        reference: transaction.payee_reference.clone().unwrap_or_default(),
+    })
+}
+
+impl From<&Payee> for BankingPayeeRest {
+    fn from(payee: &Payee) -> Self {
+        Self {
+            payee_id: payee.id.to_string(),
+            nickname: payee.nickname.clone(),
+            payee_type: BankingPayeeTypeRest::Domestic,
+            creation_date: date_string(payee.created_at),
+        }
+    }
+}
@@ -0,0 +1,303 @@
+use actix_web::web::Data;
+use actix_web::{HttpRequest, HttpResponse};
+use serde::Serialize;
+
+use super::error::CdrError;
+use super::models::{CdrLinksRest, CdrMetaRest, CdrResponseRest};
+use crate::api::oauth::util::{bearer_token, token_consent};
+use crate::models::consent::Consent;
+use crate::models::oauth::{scope_includes, FindOAuthTokenQuery, OAuthScope, OAuthToken};
+use crate::traits::{RepoFind, RepoGetById};
+use crate::util::random_uuid;
+
+// every endpoint here is still on its first version
+pub const SUPPORTED_VERSION: u32 = 1;
+
+const DEFAULT_PAGE_SIZE: usize = 25;
+const MAX_PAGE_SIZE: usize = 1000;
+
+fn header_version(req: &HttpRequest, name: &'static str) -> Result<Option<u32>, CdrError> {
+    let Some(value) = req.headers().get(name) else {
+        return Ok(None);
+    };
+    value
+        .to_str()
+        .ok()
+        .and_then(|v| v.trim().parse::<u32>().ok())
+        .filter(|v| *v > 0)
+        .map(Some)
+        .ok_or(CdrError::InvalidHeader(name))
+}
+
+// x-v is the highest version the recipient will take and x-min-v the lowest, we answer with the
+// highest we have in that range
+pub fn negotiate_version(req: &HttpRequest) -> Result<u32, CdrError> {
+    let max = header_version(req, "x-v")?.ok_or(CdrError::MissingHeader("x-v"))?;
+    // an x-min-v above x-v is ignored, as the standard has it
+    let min = header_version(req, "x-min-v")?
+        .filter(|min| *min <= max)
+        .unwrap_or(max);
+
+    (min..=max)
+        .contains(&SUPPORTED_VERSION)
+        .then_some(SUPPORTED_VERSION)
+        .ok_or(CdrError::UnsupportedVersion)
+}
+
+// echoed back if the recipient sent one, otherwise a fresh uuid
+pub fn interaction_id(req: &HttpRequest) -> String {
+    if let Some(id) = req
+        .headers()
+        .get("x-fapi-interaction-id")
+        .and_then(|v| v.to_str().ok())
+        .filter(|v| !v.trim().is_empty())
+    {
+        return id.trim().to_string();
+    }
+
+    random_uuid()
+}
+
+// the consent behind the recipient's bearer token, both have to cover the scope the endpoint needs
+pub async fn cdr_consent<OR>(
+    oauth_repo: Data<OR>,
+    req: &HttpRequest,
+    scope: OAuthScope,
+) -> Result<Consent, CdrError>
+where
+    OR: RepoFind<OAuthToken, FindOAuthTokenQuery> + RepoGetById<Consent>,
+{
+    let token = bearer_token(oauth_repo.clone(), req)
+        .await?
+        .ok_or(CdrError::Unauthorized)?;
+    if !scope_includes(&token.scope, scope) {
+        println!("Refusing token {}, wants {}", token.id, scope.as_str());
+        return Err(CdrError::Forbidden);
+    }
+
+    let consent = token_consent(oauth_repo, &token).await?;
+    if !consent.covers(scope) {
+        println!(
+            "Refusing token {}, consent {} no longer covers {}",
+            token.id,
+            consent.id,
+            scope.as_str()
+        );
+        return Err(CdrError::Forbidden);
+    }
+
+    Ok(consent)
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Paging {
+    pub page: usize,
+    pub page_size: usize,
+}
+
+pub fn parse_paging(page: &Option<String>, page_size: &Option<String>) -> Result<Paging, CdrError> {
+    let page = match page {
+        None => 1,
+        Some(page) => page
+            .parse::<usize>()
+            .ok()
+            .filter(|page| *page > 0)
+            .ok_or(CdrError::InvalidField("page"))?,
+    };
+    let page_size = match page_size {
+        None => DEFAULT_PAGE_SIZE,
+        Some(size) => size
+            .parse::<usize>()
+            .map_err(|_| CdrError::InvalidField("page-size"))
+            .and_then(|size| {
+                (1..=MAX_PAGE_SIZE)
+                    .contains(&size)
+                    .then_some(size)
+                    .ok_or(CdrError::InvalidPageSize)
+            })?,
+    };
+
+    Ok(Paging { page, page_size })
+}
+
+fn base_url(req: &HttpRequest) -> String {
+    let info = req.connection_info();
+    format!("{}://{}{}", info.scheme(), info.host(), req.path())
+}
+
+// the request's own query with page swapped for the one wanted
+fn page_url(req: &HttpRequest, page: usize) -> String {
+    let mut query: Vec<(String, String)> =
+        serde_urlencoded::from_str(req.query_string()).unwrap_or_default();
+    query.retain(|(name, _)| name != "page");
+    query.push(("page".to_string(), page.to_string()));
+
+    format!(
+        "{}?{}",
+        base_url(req),
+        serde_urlencoded::to_string(query).unwrap_or_default()
+    )
+}
+
+fn self_url(req: &HttpRequest) -> String {
+    match req.query_string() {
+        "" => base_url(req),
+        query => format!("{}?{}", base_url(req), query),
+    }
+}
+
+// the page asked for out of everything that matched, with links to its neighbours
+pub fn paginate<T>(
+    req: &HttpRequest,
+    items: Vec<T>,
+    paging: Paging,
+) -> Result<(Vec<T>, CdrLinksRest, CdrMetaRest), CdrError> {
+    let total_records = items.len();
+    let total_pages = (total_records + paging.page_size - 1) / paging.page_size;
+
+    // page 1 of nothing is still an answer
+    if paging.page > total_pages.max(1) {
+        return Err(CdrError::InvalidPage);
+    }
+
+    let page = items
+        .into_iter()
+        .skip((paging.page - 1) * paging.page_size)
+        .take(paging.page_size)
+        .collect();
+    let has_pages = total_pages > 0;
+
+    let links = CdrLinksRest {
+        self_link: self_url(req),
+        first: has_pages.then(|| page_url(req, 1)),
+        prev: (paging.page > 1).then(|| page_url(req, paging.page - 1)),
+        next: (paging.page < total_pages).then(|| page_url(req, paging.page + 1)),
+        last: has_pages.then(|| page_url(req, total_pages)),
+    };
+    let meta = CdrMetaRest {
+        total_records: Some(total_records as i64),
+        total_pages: Some(total_pages as i64),
+    };
+
+    Ok((page, links, meta))
+}
+
+// single resources have a self link and empty meta
+pub fn single_links(req: &HttpRequest) -> (CdrLinksRest, CdrMetaRest) {
+    (
+        CdrLinksRest {
+            self_link: self_url(req),
+            first: None,
+            prev: None,
+            next: None,
+            last: None,
+        },
+        CdrMetaRest {
+            total_records: None,
+            total_pages: None,
+        },
+    )
+}
+
+pub fn cdr_response<D: Serialize>(
+    req: &HttpRequest,
+    version: u32,
+    body: CdrResponseRest<D>,
+) -> HttpResponse {
+    HttpResponse::Ok()
+        .insert_header(("x-v", version.to_string()))
+        .insert_header(("x-fapi-interaction-id", interaction_id(req)))
+        .json(body)
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::test;
+
+    use super::{negotiate_version, paginate, parse_paging, Paging};
+    use crate::api::cdr::error::CdrError;
+
+    #[actix_web::test]
+    async fn version_negotiation() {
+        let req = test::TestRequest::get().to_http_request();
+        assert_eq!(negotiate_version(&req), Err(CdrError::MissingHeader("x-v")));
+
+        let req = test::TestRequest::get()
+            .insert_header(("x-v", "one"))
+            .to_http_request();
+        assert_eq!(negotiate_version(&req), Err(CdrError::InvalidHeader("x-v")));
+
+        let req = test::TestRequest::get()
+            .insert_header(("x-v", "1"))
+            .to_http_request();
+        assert_eq!(negotiate_version(&req), Ok(1));
+
+        let req = test::TestRequest::get()
+            .insert_header(("x-v", "3"))
+            .to_http_request();
+        assert_eq!(negotiate_version(&req), Err(CdrError::UnsupportedVersion));
+
+        let req = test::TestRequest::get()
+            .insert_header(("x-v", "3"))
+            .insert_header(("x-min-v", "1"))
+            .to_http_request();
+        assert_eq!(negotiate_version(&req), Ok(1));
+
+        // ignored when it's above x-v
+        let req = test::TestRequest::get()
+            .insert_header(("x-v", "1"))
+            .insert_header(("x-min-v", "4"))
+            .to_http_request();
+        assert_eq!(negotiate_version(&req), Ok(1));
+    }
+
+    #[actix_web::test]
+    async fn paging_rules() {
+        assert_eq!(
+            parse_paging(&None, &None),
+            Ok(Paging {
+                page: 1,
+                page_size: 25
+            })
+        );
+        assert_eq!(
+            parse_paging(&Some("0".to_string()), &None),
+            Err(CdrError::InvalidField("page"))
+        );
+        assert_eq!(
+            parse_paging(&None, &Some("1001".to_string())),
+            Err(CdrError::InvalidPageSize)
+        );
+
+        let req = test::TestRequest::get()
+            .uri("/cds-au/v1/banking/accounts?page=2&page-size=2")
+            .to_http_request();
+        let paging = Paging {
+            page: 2,
+            page_size: 2,
+        };
+
+        let (page, links, meta) = paginate(&req, vec![1, 2, 3, 4, 5], paging).unwrap();
+
+        assert_eq!(page, vec![3, 4]);
+        assert_eq!(meta.total_records, Some(5));
+        assert_eq!(meta.total_pages, Some(3));
+        assert_eq!(
+            links.next,
+            Some("http://localhost:8080/cds-au/v1/banking/accounts?page-size=2&page=3".to_string())
+        );
+        assert_eq!(
+            links.prev,
+            Some("http://localhost:8080/cds-au/v1/banking/accounts?page-size=2&page=1".to_string())
+        );
+
+        let paging = Paging {
+            page: 4,
+            page_size: 2,
+        };
+        assert_eq!(
+            paginate(&req, vec![1, 2, 3, 4, 5], paging).err(),
+            Some(CdrError::InvalidPage)
+        );
+    }
+}
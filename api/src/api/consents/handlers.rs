@@ -0,0 +1,456 @@
+use actix_web::http::header::ContentType;
+use actix_web::web::{Data, Path};
+use actix_web::{web, HttpRequest, HttpResponse};
+
+use super::models::{ConsentAmendmentRest, ConsentRest, ConsentsRest};
+use crate::api::audit::util::{new_audit_entry, record_audit, snapshot};
+use crate::api::error::ApiError;
+use crate::error::RepoError;
+use crate::models::audit::{ActorType, AuditAction, AuditEntity, AuditEntry, NewAuditEntry};
+use crate::models::consent::{Consent, ConsentAmendment, ConsentRevocation, FindConsentQuery};
+use crate::models::oauth::{parse_scopes, scope_string, MAX_SHARING_DAYS};
+use crate::traits::{RepoCreate, RepoFind, RepoGetById, RepoUpdate};
+
+// the customer's own, still live
+fn customer_consent<OR>(oauth_repo: &OR, customer_id: i32, id: i32) -> Result<Consent, ApiError>
+where
+    OR: RepoGetById<Consent>,
+{
+    let consent = oauth_repo.get_by_id(id).map_err(|err| match err {
+        RepoError::NotFound => ApiError::NotFound,
+        _ => ApiError::InternalError,
+    })?;
+
+    if consent.customer_id != customer_id {
+        return Err(ApiError::Unauthorized);
+    }
+    if !consent.is_active(chrono::Utc::now().naive_utc()) {
+        return Err(ApiError::Conflict);
+    }
+
+    Ok(consent)
+}
+
+// an amendment can only take away from what the customer agreed to, never add
+fn narrowed(
+    consent: &Consent,
+    amendment: ConsentAmendmentRest,
+    now: chrono::NaiveDateTime,
+) -> Result<ConsentAmendment, ApiError> {
+    let scopes = match amendment.scope {
+        Some(scope) => parse_scopes(&scope).ok_or(ApiError::BadRequest)?,
+        None => parse_scopes(&consent.scope).ok_or(ApiError::InternalError)?,
+    };
+    if !scopes.iter().all(|scope| consent.covers(*scope)) {
+        return Err(ApiError::BadRequest);
+    }
+
+    let mut account_ids = amendment
+        .account_ids
+        .unwrap_or_else(|| consent.account_ids.clone());
+    account_ids.sort_unstable();
+    account_ids.dedup();
+    if !account_ids
+        .iter()
+        .all(|account_id| consent.includes_account(*account_id))
+    {
+        return Err(ApiError::BadRequest);
+    }
+    if account_ids.is_empty() && scopes.iter().any(|scope| scope.is_account_bound()) {
+        return Err(ApiError::BadRequest);
+    }
+
+    let expires_at = match amendment.sharing_duration_days {
+        Some(days) if (1..=MAX_SHARING_DAYS).contains(&days) => now + chrono::Duration::days(days),
+        Some(_) => return Err(ApiError::BadRequest),
+        None => consent.expires_at,
+    };
+    if expires_at > consent.expires_at {
+        return Err(ApiError::BadRequest);
+    }
+
+    Ok(ConsentAmendment {
+        scope: scope_string(&scopes),
+        account_ids,
+        expires_at,
+    })
+}
+
+pub async fn find_consents<OR>(
+    oauth_repo: Data<OR>,
+    path: Path<i32>,
+) -> Result<HttpResponse, actix_web::Error>
+where
+    OR: RepoFind<Consent, FindConsentQuery>,
+{
+    let customer_id = path.into_inner();
+
+    println!("Trying to find consents for customer {}", customer_id);
+
+    let consents = web::block(move || oauth_repo.find(FindConsentQuery { customer_id }))
+        .await
+        .map_err(|_| ApiError::InternalError)?
+        .map_err(|_| ApiError::InternalError)?;
+
+    Ok(HttpResponse::Ok()
+        .insert_header(ContentType::json())
+        .json(web::Json::<ConsentsRest>(consents.into())))
+}
+
+pub async fn amend_consent<OR, AuR>(
+    oauth_repo: Data<OR>,
+    audit_repo: Data<AuR>,
+    req: HttpRequest,
+    path: Path<(i32, i32)>,
+    payload: web::Json<ConsentAmendmentRest>,
+) -> Result<HttpResponse, actix_web::Error>
+where
+    OR: RepoGetById<Consent> + RepoUpdate<Consent, ConsentAmendment>,
+    AuR: RepoCreate<AuditEntry, NewAuditEntry>,
+{
+    let (customer_id, consent_id) = path.into_inner();
+    let amendment = payload.into_inner();
+
+    println!(
+        "Trying to amend consent {} for customer {}",
+        consent_id, customer_id
+    );
+
+    let (before, after) = web::block(move || {
+        let before = customer_consent(oauth_repo.as_ref(), customer_id, consent_id)?;
+        let amendment = narrowed(&before, amendment, chrono::Utc::now().naive_utc())?;
+
+        let after = oauth_repo
+            .update(consent_id, amendment)
+            .map_err(|err| match err {
+                // lapsed or revoked since
+                RepoError::Conflict => ApiError::Conflict,
+                _ => ApiError::InternalError,
+            })?;
+
+        Ok::<_, ApiError>((before, after))
+    })
+    .await
+    .map_err(|_| ApiError::InternalError)??;
+
+    let after_rest: ConsentRest = (&after).into();
+
+    let mut audit_entry = new_audit_entry(
+        &req,
+        ActorType::Customer,
+        customer_id,
+        AuditAction::AmendConsent,
+        AuditEntity::OAuthGrant,
+        Some(consent_id),
+    );
+    audit_entry.before_snapshot = snapshot(&ConsentRest::from(&before));
+    audit_entry.after_snapshot = snapshot(&after_rest);
+    record_audit(audit_repo, audit_entry).await;
+
+    Ok(HttpResponse::Ok()
+        .insert_header(ContentType::json())
+        .json(web::Json(after_rest)))
+}
+
+// the app loses access straight away, every token it holds under the consent goes with it
+pub async fn revoke_consent<OR, AuR>(
+    oauth_repo: Data<OR>,
+    audit_repo: Data<AuR>,
+    req: HttpRequest,
+    path: Path<(i32, i32)>,
+) -> Result<HttpResponse, actix_web::Error>
+where
+    OR: RepoGetById<Consent> + RepoUpdate<Consent, ConsentRevocation>,
+    AuR: RepoCreate<AuditEntry, NewAuditEntry>,
+{
+    let (customer_id, consent_id) = path.into_inner();
+
+    println!(
+        "Trying to revoke consent {} for customer {}",
+        consent_id, customer_id
+    );
+
+    let (before, after) = web::block(move || {
+        let before = customer_consent(oauth_repo.as_ref(), customer_id, consent_id)?;
+
+        let after = oauth_repo
+            .update(consent_id, ConsentRevocation)
+            .map_err(|_| ApiError::InternalError)?;
+
+        Ok::<_, ApiError>((before, after))
+    })
+    .await
+    .map_err(|_| ApiError::InternalError)??;
+
+    let after_rest: ConsentRest = (&after).into();
+
+    let mut audit_entry = new_audit_entry(
+        &req,
+        ActorType::Customer,
+        customer_id,
+        AuditAction::RevokeConsent,
+        AuditEntity::OAuthGrant,
+        Some(consent_id),
+    );
+    audit_entry.before_snapshot = snapshot(&ConsentRest::from(&before));
+    audit_entry.after_snapshot = snapshot(&after_rest);
+    record_audit(audit_repo, audit_entry).await;
+
+    Ok(HttpResponse::Ok()
+        .insert_header(ContentType::json())
+        .json(web::Json(after_rest)))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::fixtures::audit_repo;
+    use actix_web::{
+        http::StatusCode,
+        test,
+        web::{Data, Json},
+    };
+    use chrono::{Duration, Utc};
+    use mockall::{mock, predicate::eq};
+
+    use crate::{
+        api::{
+            consents::{
+                handlers::{amend_consent, revoke_consent},
+                models::{ConsentAmendmentRest, ConsentRest, ConsentStatusRest},
+            },
+            error::ApiError,
+        },
+        error::RepoError,
+        models::consent::{Consent, ConsentAmendment, ConsentRevocation},
+        traits::{RepoGetById, RepoUpdate},
+    };
+
+    mock! {
+        pub AmendOR { }
+        impl RepoGetById<Consent> for AmendOR {
+            fn get_by_id(&self, id: i32) -> Result<Consent, RepoError>;
+        }
+        impl RepoUpdate<Consent, ConsentAmendment> for AmendOR {
+            fn update(&self, id: i32, update: ConsentAmendment) -> Result<Consent, RepoError>;
+        }
+    }
+
+    mock! {
+        pub RevokeOR { }
+        impl RepoGetById<Consent> for RevokeOR {
+            fn get_by_id(&self, id: i32) -> Result<Consent, RepoError>;
+        }
+        impl RepoUpdate<Consent, ConsentRevocation> for RevokeOR {
+            fn update(&self, id: i32, update: ConsentRevocation) -> Result<Consent, RepoError>;
+        }
+    }
+
+    fn consent(expires_at: chrono::NaiveDateTime) -> Consent {
+        let created_at = expires_at - Duration::days(90);
+        Consent {
+            id: 4,
+            customer_id: 5,
+            client_id: "budget-app".to_string(),
+            client_name: "Budget App".to_string(),
+            scope: "accounts:read transactions:read".to_string(),
+            account_ids: vec![52, 53],
+            created_at,
+            updated_at: created_at,
+            expires_at,
+            revoked_at: None,
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_amend_consent_narrows() {
+        let expires_at = Utc::now().naive_utc() + Duration::days(30);
+
+        let mut mock_oauth_repo = MockAmendOR::new();
+        mock_oauth_repo
+            .expect_get_by_id()
+            .with(eq(4))
+            .times(1)
+            .returning(move |_| Ok(consent(expires_at)));
+        mock_oauth_repo
+            .expect_update()
+            .with(
+                eq(4),
+                eq(ConsentAmendment {
+                    scope: "accounts:read".to_string(),
+                    account_ids: vec![52],
+                    expires_at,
+                }),
+            )
+            .times(1)
+            .returning(move |_, amendment| {
+                Ok(Consent {
+                    scope: amendment.scope,
+                    account_ids: amendment.account_ids,
+                    ..consent(expires_at)
+                })
+            });
+
+        let res = amend_consent(
+            Data::new(mock_oauth_repo),
+            Data::new(audit_repo(1)),
+            test::TestRequest::patch().to_http_request(),
+            (5, 4).into(),
+            Json(ConsentAmendmentRest {
+                scope: Some("accounts:read".to_string()),
+                account_ids: Some(vec![52]),
+                sharing_duration_days: None,
+            }),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(StatusCode::OK, res.status());
+
+        let actual: ConsentRest =
+            serde_json::from_slice(&actix_web::body::to_bytes(res.into_body()).await.unwrap())
+                .unwrap();
+        assert_eq!("accounts:read", actual.scope);
+        assert_eq!(vec![52], actual.account_ids);
+        assert_eq!(ConsentStatusRest::Active, actual.status);
+    }
+
+    #[actix_web::test]
+    async fn test_amend_consent_widening_bad_request() {
+        let expires_at = Utc::now().naive_utc() + Duration::days(30);
+
+        for amendment in [
+            ConsentAmendmentRest {
+                scope: Some("accounts:read payments:write".to_string()),
+                account_ids: None,
+                sharing_duration_days: None,
+            },
+            ConsentAmendmentRest {
+                scope: None,
+                account_ids: Some(vec![52, 54]),
+                sharing_duration_days: None,
+            },
+            ConsentAmendmentRest {
+                scope: None,
+                account_ids: Some(vec![]),
+                sharing_duration_days: None,
+            },
+            ConsentAmendmentRest {
+                scope: None,
+                account_ids: None,
+                sharing_duration_days: Some(90),
+            },
+        ] {
+            let mut mock_oauth_repo = MockAmendOR::new();
+            mock_oauth_repo
+                .expect_get_by_id()
+                .times(1)
+                .returning(move |_| Ok(consent(expires_at)));
+            mock_oauth_repo.expect_update().never();
+
+            let res = amend_consent(
+                Data::new(mock_oauth_repo),
+                Data::new(audit_repo(0)),
+                test::TestRequest::patch().to_http_request(),
+                (5, 4).into(),
+                Json(amendment),
+            )
+            .await;
+
+            assert!(res.is_err_and(|e| e.to_string() == ApiError::BadRequest.to_string()));
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_amend_consent_other_customer_unauthorized() {
+        let expires_at = Utc::now().naive_utc() + Duration::days(30);
+
+        let mut mock_oauth_repo = MockAmendOR::new();
+        mock_oauth_repo
+            .expect_get_by_id()
+            .times(1)
+            .returning(move |_| Ok(consent(expires_at)));
+        mock_oauth_repo.expect_update().never();
+
+        let res = amend_consent(
+            Data::new(mock_oauth_repo),
+            Data::new(audit_repo(0)),
+            test::TestRequest::patch().to_http_request(),
+            (6, 4).into(),
+            Json(ConsentAmendmentRest {
+                scope: Some("accounts:read".to_string()),
+                account_ids: None,
+                sharing_duration_days: None,
+            }),
+        )
+        .await;
+
+        assert!(res.is_err_and(|e| e.to_string() == ApiError::Unauthorized.to_string()));
+    }
+
+    #[actix_web::test]
+    async fn test_revoke_consent_success() {
+        let expires_at = Utc::now().naive_utc() + Duration::days(30);
+
+        let mut mock_oauth_repo = MockRevokeOR::new();
+        mock_oauth_repo
+            .expect_get_by_id()
+            .with(eq(4))
+            .times(1)
+            .returning(move |_| Ok(consent(expires_at)));
+        mock_oauth_repo
+            .expect_update()
+            .with(eq(4), eq(ConsentRevocation))
+            .times(1)
+            .returning(move |_, _| {
+                Ok(Consent {
+                    revoked_at: Some(Utc::now().naive_utc()),
+                    ..consent(expires_at)
+                })
+            });
+
+        let res = revoke_consent(
+            Data::new(mock_oauth_repo),
+            Data::new(audit_repo(1)),
+            test::TestRequest::post().to_http_request(),
+            (5, 4).into(),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(StatusCode::OK, res.status());
+
+        let actual: ConsentRest =
+            serde_json::from_slice(&actix_web::body::to_bytes(res.into_body()).await.unwrap())
+                .unwrap();
+        assert_eq!(ConsentStatusRest::Revoked, actual.status);
+        assert!(actual.revoked_at.is_some());
+    }
+
+    #[actix_web::test]
+    async fn test_revoke_consent_already_revoked_conflict() {
+        let expires_at = Utc::now().naive_utc() + Duration::days(30);
+
+        let mut mock_oauth_repo = MockRevokeOR::new();
+        mock_oauth_repo
+            .expect_get_by_id()
+            .times(1)
+            .returning(move |_| {
+                Ok(Consent {
+                    revoked_at: Some(Utc::now().naive_utc()),
+                    ..consent(expires_at)
+                })
+            });
+        mock_oauth_repo.expect_update().never();
+
+        let res = revoke_consent(
+            Data::new(mock_oauth_repo),
+            Data::new(audit_repo(0)),
+            test::TestRequest::post().to_http_request(),
+            (5, 4).into(),
+        )
+        .await;
+
+        assert!(res.is_err_and(|e| e.to_string() == ApiError::Conflict.to_string()));
+    }
+}
@@ -0,0 +1,38 @@
+pub mod handlers;
+pub mod models;
+pub mod transform;
+
+use actix_web::web;
+
+use crate::{
+    api::consents,
+    models::{
+        audit::{AuditEntry, NewAuditEntry},
+        consent::{Consent, ConsentAmendment, ConsentRevocation, FindConsentQuery},
+    },
+    traits::{RepoCreate, RepoFind, RepoGetById, RepoUpdate},
+};
+
+pub fn configure_consents_api<OR, AuR>(cfg: &mut web::ServiceConfig)
+where
+    OR: RepoFind<Consent, FindConsentQuery>
+        + RepoGetById<Consent>
+        + RepoUpdate<Consent, ConsentAmendment>
+        + RepoUpdate<Consent, ConsentRevocation>,
+    AuR: RepoCreate<AuditEntry, NewAuditEntry>,
+{
+    cfg.service(
+        web::scope("/api/customers/{customer_id}/consents")
+            .service(
+                web::resource("").route(web::get().to(consents::handlers::find_consents::<OR>)),
+            )
+            .service(
+                web::resource("/{consent_id}")
+                    .route(web::patch().to(consents::handlers::amend_consent::<OR, AuR>)),
+            )
+            .service(
+                web::resource("/{consent_id}/revoke")
+                    .route(web::post().to(consents::handlers::revoke_consent::<OR, AuR>)),
+            ),
+    );
+}
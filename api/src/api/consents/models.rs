@@ -0,0 +1,44 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Serialize, Copy, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum ConsentStatusRest {
+    Active,
+    Expired,
+    Revoked,
+}
+
+#[cfg_attr(test, derive(Deserialize, PartialEq, Debug))]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConsentRest {
+    pub id: i32,
+    pub customer_id: i32,
+    pub client_id: String,
+    pub client_name: String,
+    pub scope: String,
+    pub account_ids: Vec<i32>,
+    pub status: ConsentStatusRest,
+    pub created_at: String,
+    pub updated_at: String,
+    pub expires_at: String,
+    pub revoked_at: Option<String>,
+}
+
+#[cfg_attr(test, derive(Deserialize))]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConsentsRest {
+    pub consents: Vec<ConsentRest>,
+}
+
+// each one left out stays as it is
+#[cfg_attr(test, derive(Serialize))]
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ConsentAmendmentRest {
+    pub scope: Option<String>,
+    pub account_ids: Option<Vec<i32>>,
+    // from now, has to end it no later than it already does
+    pub sharing_duration_days: Option<i64>,
+}
@@ -0,0 +1,38 @@
+use crate::models::consent::Consent;
+use crate::util::string_opt_from_naive_dt_opt;
+
+use super::models::{ConsentRest, ConsentStatusRest, ConsentsRest};
+
+impl From<&Consent> for ConsentRest {
+    fn from(consent: &Consent) -> Self {
+        let status = if consent.revoked_at.is_some() {
+            ConsentStatusRest::Revoked
+        } else if consent.is_active(chrono::Utc::now().naive_utc()) {
+            ConsentStatusRest::Active
+        } else {
+            ConsentStatusRest::Expired
+        };
+
+        Self {
+            id: consent.id,
+            customer_id: consent.customer_id,
+            client_id: consent.client_id.clone(),
+            client_name: consent.client_name.clone(),
+            scope: consent.scope.clone(),
+            account_ids: consent.account_ids.clone(),
+            status,
+            created_at: consent.created_at.to_string(),
+            updated_at: consent.updated_at.to_string(),
+            expires_at: consent.expires_at.to_string(),
+            revoked_at: string_opt_from_naive_dt_opt(consent.revoked_at),
+        }
+    }
+}
+
+impl From<Vec<Consent>> for ConsentsRest {
+    fn from(consents: Vec<Consent>) -> Self {
+        Self {
+            consents: consents.iter().map(ConsentRest::from).collect(),
+        }
+    }
+}
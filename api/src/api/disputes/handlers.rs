@@ -0,0 +1,672 @@
+use actix_web::http::header::ContentType;
+use actix_web::web::{Data, Path, Query};
+use actix_web::{web, HttpRequest, HttpResponse};
+
+use super::models::{
+    DisputeEventRest, DisputeRest, DisputeTransitionRest, DisputeWithTimelineRest, DisputesRest,
+    FindDisputeQueryRest, NewDisputeRest,
+};
+use crate::api::audit::util::{new_audit_entry, record_audit, snapshot};
+use crate::api::error::ApiError;
+use crate::error::RepoError;
+use crate::models::audit::{ActorType, AuditAction, AuditEntity, AuditEntry, NewAuditEntry};
+use crate::models::dispute::{
+    Dispute, DisputeEvent, DisputeTransition, FindDisputeEventQuery, FindDisputeQuery, NewDispute,
+};
+use crate::models::transaction::{Transaction, TransactionStatus};
+use crate::traits::{RepoCreate, RepoFind, RepoGetById, RepoUpdate};
+
+const MAX_REASON_LEN: usize = 500;
+
+pub async fn open_dispute<DR, TR, AuR>(
+    disputes_repo: Data<DR>,
+    transactions_repo: Data<TR>,
+    audit_repo: Data<AuR>,
+    req: HttpRequest,
+    path: Path<i32>,
+    payload: web::Json<NewDisputeRest>,
+) -> Result<HttpResponse, actix_web::Error>
+where
+    DR: RepoCreate<Dispute, NewDispute>,
+    TR: RepoGetById<Transaction>,
+    AuR: RepoCreate<AuditEntry, NewAuditEntry>,
+{
+    let customer_id = path.into_inner();
+    let payload = payload.into_inner();
+
+    if payload.reason.trim().is_empty() || payload.reason.len() > MAX_REASON_LEN {
+        return Err(ApiError::BadRequest.into());
+    }
+
+    println!(
+        "Trying to open dispute on transaction {} for customer {}",
+        payload.transaction_id, customer_id
+    );
+
+    let transaction_id = payload.transaction_id;
+    let transaction = web::block(move || transactions_repo.get_by_id(transaction_id))
+        .await
+        .map_err(|_| ApiError::InternalError)?
+        .map_err(|err| match err {
+            RepoError::NotFound => ApiError::NotFound,
+            _ => ApiError::InternalError,
+        })?;
+
+    if transaction.customer_id != customer_id {
+        return Err(ApiError::Unauthorized.into());
+    }
+
+    // nothing settled to dispute yet, or already given back
+    if !matches!(
+        transaction.transaction_status,
+        TransactionStatus::Success | TransactionStatus::PartiallyReversed
+    ) {
+        return Err(ApiError::BadRequest.into());
+    }
+
+    let amount_cents = payload
+        .amount_cents
+        .unwrap_or(transaction.reversible_amount_cents());
+
+    if amount_cents <= 0 || amount_cents > transaction.reversible_amount_cents() {
+        return Err(ApiError::BadRequest.into());
+    }
+
+    let new_dispute = NewDispute {
+        customer_id,
+        transaction_id,
+        amount_cents,
+        reason: payload.reason,
+    };
+
+    let dispute = web::block(move || disputes_repo.create(new_dispute))
+        .await
+        .map_err(|_| ApiError::InternalError)?
+        .map_err(|err| match err {
+            // already an unresolved dispute on this transaction
+            RepoError::Conflict => ApiError::Conflict,
+            _ => ApiError::InternalError,
+        })?;
+
+    let dispute_rest: DisputeRest = (&dispute).into();
+
+    let mut audit_entry = new_audit_entry(
+        &req,
+        ActorType::Customer,
+        customer_id,
+        AuditAction::OpenDispute,
+        AuditEntity::Dispute,
+        Some(dispute.id),
+    );
+    audit_entry.after_snapshot = snapshot(&dispute_rest);
+    record_audit(audit_repo, audit_entry).await;
+
+    Ok(HttpResponse::Created()
+        .insert_header(ContentType::json())
+        .json(web::Json(dispute_rest)))
+}
+
+pub async fn find_disputes<DR>(
+    disputes_repo: Data<DR>,
+    path: Path<i32>,
+    query: Query<FindDisputeQueryRest>,
+) -> Result<HttpResponse, actix_web::Error>
+where
+    DR: RepoFind<Dispute, FindDisputeQuery>,
+{
+    let customer_id = path.into_inner();
+
+    let query = FindDisputeQuery {
+        customer_id,
+        transaction_id: query.transaction_id,
+    };
+
+    println!("Trying to get disputes for customer {}", customer_id);
+
+    let disputes = web::block(move || disputes_repo.find(query))
+        .await
+        .map_err(|_| ApiError::InternalError)?
+        .map_err(|_| ApiError::InternalError)?;
+
+    // enforced query on customer_id so should always be fine, but safe > sorry
+    for dispute in disputes.iter() {
+        if dispute.customer_id != customer_id {
+            return Err(ApiError::BadRequest.into());
+        }
+    }
+
+    Ok(HttpResponse::Ok()
+        .insert_header(ContentType::json())
+        .json(web::Json::<DisputesRest>(disputes.into())))
+}
+
+pub async fn get_dispute<DR>(
+    disputes_repo: Data<DR>,
+    path: Path<(i32, i32)>,
+) -> Result<HttpResponse, actix_web::Error>
+where
+    DR: RepoGetById<Dispute> + RepoFind<DisputeEvent, FindDisputeEventQuery>,
+{
+    let (customer_id, dispute_id) = path.into_inner();
+
+    println!(
+        "Trying to get dispute {}, for customer {}",
+        dispute_id, customer_id
+    );
+
+    let (dispute, events) = web::block(move || {
+        let dispute = disputes_repo.get_by_id(dispute_id)?;
+
+        if dispute.customer_id != customer_id {
+            return Ok((dispute, vec![]));
+        }
+
+        let events = disputes_repo.find(FindDisputeEventQuery { dispute_id })?;
+        Ok((dispute, events))
+    })
+    .await
+    .map_err(|_| ApiError::InternalError)?
+    .map_err(|err: RepoError| match err {
+        RepoError::NotFound => ApiError::NotFound,
+        _ => ApiError::InternalError,
+    })?;
+
+    if dispute.customer_id != customer_id {
+        return Err(ApiError::Unauthorized.into());
+    }
+
+    Ok(HttpResponse::Ok()
+        .insert_header(ContentType::json())
+        .json(web::Json(DisputeWithTimelineRest {
+            dispute: (&dispute).into(),
+            timeline: events.iter().map(DisputeEventRest::from).collect(),
+        })))
+}
+
+pub async fn staff_transition_dispute<DR, AuR>(
+    disputes_repo: Data<DR>,
+    audit_repo: Data<AuR>,
+    req: HttpRequest,
+    path: Path<(i32, i32)>,
+    payload: web::Json<DisputeTransitionRest>,
+) -> Result<HttpResponse, actix_web::Error>
+where
+    DR: RepoGetById<Dispute> + RepoUpdate<Dispute, DisputeTransition>,
+    AuR: RepoCreate<AuditEntry, NewAuditEntry>,
+{
+    let (staff_id, dispute_id) = path.into_inner();
+    let payload = payload.into_inner();
+
+    if payload
+        .note
+        .as_ref()
+        .is_some_and(|note| note.len() > MAX_REASON_LEN)
+    {
+        return Err(ApiError::BadRequest.into());
+    }
+
+    let transition = DisputeTransition {
+        dispute_status: payload.dispute_status.into(),
+        note: payload.note,
+        actor_type: ActorType::Staff,
+        actor_id: staff_id,
+    };
+
+    println!(
+        "Trying to move dispute {} to {:?} for staff {}",
+        dispute_id, transition.dispute_status, staff_id
+    );
+
+    let (before, after) = web::block(move || {
+        let before = disputes_repo.get_by_id(dispute_id)?;
+
+        if !before
+            .dispute_status
+            .can_transition_to(transition.dispute_status)
+        {
+            return Err(RepoError::Conflict);
+        }
+
+        let after = disputes_repo.update(dispute_id, transition)?;
+        Ok((before, after))
+    })
+    .await
+    .map_err(|_| ApiError::InternalError)?
+    .map_err(|err: RepoError| match err {
+        RepoError::NotFound => ApiError::NotFound,
+        RepoError::Conflict => ApiError::Conflict,
+        _ => ApiError::InternalError,
+    })?;
+
+    let after_rest: DisputeRest = (&after).into();
+
+    let mut audit_entry = new_audit_entry(
+        &req,
+        ActorType::Staff,
+        staff_id,
+        AuditAction::TransitionDispute,
+        AuditEntity::Dispute,
+        Some(dispute_id),
+    );
+    audit_entry.before_snapshot = snapshot(&DisputeRest::from(&before));
+    audit_entry.after_snapshot = snapshot(&after_rest);
+    record_audit(audit_repo, audit_entry).await;
+
+    Ok(HttpResponse::Ok()
+        .insert_header(ContentType::json())
+        .json(web::Json(after_rest)))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::audit::util::appended;
+    use crate::fixtures::dt;
+    use actix_web::{
+        body::to_bytes,
+        http::StatusCode,
+        test,
+        web::{self, Data, Json},
+        App,
+    };
+
+    use mockall::{mock, predicate::eq};
+
+    use crate::{
+        api::{
+            disputes::{
+                handlers::{find_disputes, get_dispute, open_dispute, staff_transition_dispute},
+                models::{
+                    DisputeRest, DisputeStatusRest, DisputeTransitionRest, DisputeWithTimelineRest,
+                    DisputesRest, NewDisputeRest,
+                },
+            },
+            error::ApiError,
+        },
+        error::RepoError,
+        models::{
+            audit::{ActorType, AuditEntry, NewAuditEntry},
+            dispute::{
+                Dispute, DisputeEvent, DisputeStatus, DisputeTransition, FindDisputeEventQuery,
+                FindDisputeQuery, NewDispute,
+            },
+            transaction::{Transaction, TransactionStatus, TransactionType},
+        },
+        traits::{
+            MockRepoCreate, MockRepoFind, MockRepoGetById, RepoFind, RepoGetById, RepoUpdate,
+        },
+    };
+
+    fn transaction(id: i32, customer_id: i32) -> Transaction {
+        Transaction {
+            id,
+            customer_id,
+            transaction_type: TransactionType::Internal,
+            from_us: true,
+            amount_cents: 10000,
+            from_number: "123456789".to_string(),
+            from_bsb: "123456".to_string(),
+            from_name: Some("Everyday".to_string()),
+            to_number: "987654321".to_string(),
+            to_bsb: "123456".to_string(),
+            to_name: None,
+            available_balance_cents: 5000,
+            date_start: dt(),
+            date_end: Some(dt()),
+            transaction_status: TransactionStatus::Success,
+            reversal_of: None,
+            reversed_amount_cents: 2000,
+            reversal_reason: None,
+            description: None,
+            payer_reference: None,
+            payee_reference: None,
+            counterpart_of: None,
+            running_balance_cents: None,
+        }
+    }
+
+    fn dispute(id: i32, customer_id: i32, dispute_status: DisputeStatus) -> Dispute {
+        Dispute {
+            id,
+            customer_id,
+            transaction_id: 60,
+            amount_cents: 8000,
+            reason: "didn't buy this".to_string(),
+            dispute_status,
+            credited_cents: 0,
+            date_opened: dt(),
+            date_resolved: None,
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_open_dispute_success() {
+        let customer_id = 5;
+        let transaction_id = 60;
+
+        let mut mock_transactions_repo = MockRepoGetById::<Transaction>::new();
+        mock_transactions_repo
+            .expect_get_by_id()
+            .with(eq(transaction_id))
+            .times(1)
+            .returning(move |_| Ok(transaction(transaction_id, customer_id)));
+
+        let mut mock_disputes_repo = MockRepoCreate::<Dispute, NewDispute>::new();
+        mock_disputes_repo
+            .expect_create()
+            .with(eq(NewDispute {
+                customer_id,
+                transaction_id,
+                // unreversed remainder
+                amount_cents: 8000,
+                reason: "didn't buy this".to_string(),
+            }))
+            .times(1)
+            .returning(move |_| Ok(dispute(1, customer_id, DisputeStatus::Open)));
+
+        let mut mock_audit_repo = MockRepoCreate::<AuditEntry, NewAuditEntry>::new();
+        mock_audit_repo
+            .expect_create()
+            .withf(|entry| entry.action == "open_dispute" && entry.entity_type == "dispute")
+            .times(1)
+            .returning(|entry| Ok(appended(entry)));
+
+        let res = open_dispute(
+            Data::new(mock_disputes_repo),
+            Data::new(mock_transactions_repo),
+            Data::new(mock_audit_repo),
+            test::TestRequest::post().to_http_request(),
+            customer_id.into(),
+            Json(NewDisputeRest {
+                transaction_id,
+                reason: "didn't buy this".to_string(),
+                amount_cents: None,
+            }),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(StatusCode::CREATED, res.status());
+
+        let actual: DisputeRest =
+            serde_json::from_slice(&to_bytes(res.into_body()).await.unwrap()).unwrap();
+        assert_eq!(DisputeStatusRest::Open, actual.dispute_status);
+        assert_eq!(8000, actual.amount_cents);
+    }
+
+    #[actix_web::test]
+    async fn test_open_dispute_unauthorized_error() {
+        let mut mock_transactions_repo = MockRepoGetById::<Transaction>::new();
+        mock_transactions_repo
+            .expect_get_by_id()
+            .times(1)
+            .returning(|_| Ok(transaction(60, 5)));
+
+        let mut mock_disputes_repo = MockRepoCreate::<Dispute, NewDispute>::new();
+        mock_disputes_repo.expect_create().times(0);
+
+        let res = open_dispute(
+            Data::new(mock_disputes_repo),
+            Data::new(mock_transactions_repo),
+            Data::new(MockRepoCreate::<AuditEntry, NewAuditEntry>::new()),
+            test::TestRequest::post().to_http_request(),
+            6.into(),
+            Json(NewDisputeRest {
+                transaction_id: 60,
+                reason: "didn't buy this".to_string(),
+                amount_cents: None,
+            }),
+        )
+        .await;
+
+        assert!(res.is_err_and(|e| { e.to_string() == ApiError::Unauthorized.to_string() }));
+    }
+
+    #[actix_web::test]
+    async fn test_open_dispute_already_open_conflict() {
+        let mut mock_transactions_repo = MockRepoGetById::<Transaction>::new();
+        mock_transactions_repo
+            .expect_get_by_id()
+            .times(1)
+            .returning(|_| Ok(transaction(60, 5)));
+
+        let mut mock_disputes_repo = MockRepoCreate::<Dispute, NewDispute>::new();
+        mock_disputes_repo
+            .expect_create()
+            .times(1)
+            .returning(|_| Err(RepoError::Conflict));
+
+        let res = open_dispute(
+            Data::new(mock_disputes_repo),
+            Data::new(mock_transactions_repo),
+            Data::new(MockRepoCreate::<AuditEntry, NewAuditEntry>::new()),
+            test::TestRequest::post().to_http_request(),
+            5.into(),
+            Json(NewDisputeRest {
+                transaction_id: 60,
+                reason: "didn't buy this".to_string(),
+                amount_cents: Some(500),
+            }),
+        )
+        .await;
+
+        assert!(res.is_err_and(|e| { e.to_string() == ApiError::Conflict.to_string() }));
+    }
+
+    #[actix_web::test]
+    async fn test_find_disputes_success() {
+        let customer_id = 5;
+
+        let mut mock_disputes_repo = MockRepoFind::<Dispute, FindDisputeQuery>::new();
+        mock_disputes_repo
+            .expect_find()
+            .with(eq(FindDisputeQuery {
+                customer_id,
+                transaction_id: None,
+            }))
+            .times(1)
+            .returning(move |_| {
+                Ok(vec![
+                    dispute(1, customer_id, DisputeStatus::Open),
+                    dispute(2, customer_id, DisputeStatus::ResolvedForCustomer),
+                ])
+            });
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(mock_disputes_repo))
+                .service(web::resource("/customers/{customer_id}/disputes").route(
+                    web::get().to(find_disputes::<MockRepoFind<Dispute, FindDisputeQuery>>),
+                )),
+        )
+        .await;
+
+        let resp = test::TestRequest::get()
+            .uri("/customers/5/disputes")
+            .send_request(&app)
+            .await;
+
+        assert_eq!(StatusCode::OK, resp.status());
+
+        let actual: DisputesRest = test::read_body_json(resp).await;
+        assert_eq!(2, actual.disputes.len());
+        assert_eq!(
+            DisputeStatusRest::ResolvedForCustomer,
+            actual.disputes[1].dispute_status
+        );
+    }
+
+    mock! {
+        pub DR { }
+        impl RepoGetById<Dispute> for DR {
+            fn get_by_id(&self, id: i32) -> Result<Dispute, RepoError>;
+        }
+        impl RepoFind<DisputeEvent, FindDisputeEventQuery> for DR {
+            fn find(&self, query: FindDisputeEventQuery) -> Result<Vec<DisputeEvent>, RepoError>;
+        }
+        impl RepoUpdate<Dispute, DisputeTransition> for DR {
+            fn update(&self, id: i32, update: DisputeTransition) -> Result<Dispute, RepoError>;
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_get_dispute_with_timeline_success() {
+        let customer_id = 5;
+        let dispute_id = 1;
+
+        let mut mock_disputes_repo = MockDR::new();
+        mock_disputes_repo
+            .expect_get_by_id()
+            .with(eq(dispute_id))
+            .times(1)
+            .returning(move |_| {
+                Ok(dispute(
+                    dispute_id,
+                    customer_id,
+                    DisputeStatus::Investigating,
+                ))
+            });
+        mock_disputes_repo
+            .expect_find()
+            .with(eq(FindDisputeEventQuery { dispute_id }))
+            .times(1)
+            .returning(move |_| {
+                Ok(vec![
+                    DisputeEvent {
+                        id: 1,
+                        dispute_id,
+                        dispute_status: DisputeStatus::Open,
+                        note: Some("didn't buy this".to_string()),
+                        actor_type: ActorType::Customer,
+                        actor_id: customer_id,
+                        transaction_id: None,
+                        created_at: dt(),
+                    },
+                    DisputeEvent {
+                        id: 2,
+                        dispute_id,
+                        dispute_status: DisputeStatus::Investigating,
+                        note: None,
+                        actor_type: ActorType::Staff,
+                        actor_id: 1,
+                        transaction_id: None,
+                        created_at: dt(),
+                    },
+                ])
+            });
+
+        let res = get_dispute(
+            Data::new(mock_disputes_repo),
+            (customer_id, dispute_id).into(),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(StatusCode::OK, res.status());
+
+        let actual: DisputeWithTimelineRest =
+            serde_json::from_slice(&to_bytes(res.into_body()).await.unwrap()).unwrap();
+        assert_eq!(2, actual.timeline.len());
+        assert_eq!(
+            DisputeStatusRest::Investigating,
+            actual.timeline[1].dispute_status
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_get_dispute_unauthorized_error() {
+        let mut mock_disputes_repo = MockDR::new();
+        mock_disputes_repo
+            .expect_get_by_id()
+            .times(1)
+            .returning(|_| Ok(dispute(1, 5, DisputeStatus::Open)));
+        mock_disputes_repo.expect_find().times(0);
+
+        let res = get_dispute(Data::new(mock_disputes_repo), (6, 1).into()).await;
+
+        assert!(res.is_err_and(|e| { e.to_string() == ApiError::Unauthorized.to_string() }));
+    }
+
+    #[actix_web::test]
+    async fn test_staff_transition_dispute_provisional_credit_success() {
+        let staff_id = 1;
+        let dispute_id = 1;
+
+        let mut mock_disputes_repo = MockDR::new();
+        mock_disputes_repo
+            .expect_get_by_id()
+            .times(1)
+            .returning(move |_| Ok(dispute(dispute_id, 5, DisputeStatus::Investigating)));
+        mock_disputes_repo
+            .expect_update()
+            .with(
+                eq(dispute_id),
+                eq(DisputeTransition {
+                    dispute_status: DisputeStatus::ProvisionalCredit,
+                    note: Some("credit while we chase merchant".to_string()),
+                    actor_type: ActorType::Staff,
+                    actor_id: staff_id,
+                }),
+            )
+            .times(1)
+            .returning(move |_, _| {
+                let mut d = dispute(dispute_id, 5, DisputeStatus::ProvisionalCredit);
+                d.credited_cents = d.amount_cents;
+                Ok(d)
+            });
+
+        let mut mock_audit_repo = MockRepoCreate::<AuditEntry, NewAuditEntry>::new();
+        mock_audit_repo
+            .expect_create()
+            .withf(move |entry| {
+                entry.action == "transition_dispute"
+                    && entry.actor_type == ActorType::Staff
+                    && entry.before_snapshot.is_some()
+                    && entry.after_snapshot.is_some()
+            })
+            .times(1)
+            .returning(|entry| Ok(appended(entry)));
+
+        let res = staff_transition_dispute(
+            Data::new(mock_disputes_repo),
+            Data::new(mock_audit_repo),
+            test::TestRequest::post().to_http_request(),
+            (staff_id, dispute_id).into(),
+            Json(DisputeTransitionRest {
+                dispute_status: DisputeStatusRest::ProvisionalCredit,
+                note: Some("credit while we chase merchant".to_string()),
+            }),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(StatusCode::OK, res.status());
+
+        let actual: DisputeRest =
+            serde_json::from_slice(&to_bytes(res.into_body()).await.unwrap()).unwrap();
+        assert_eq!(8000, actual.credited_cents);
+    }
+
+    #[actix_web::test]
+    async fn test_staff_transition_dispute_invalid_transition_conflict() {
+        let mut mock_disputes_repo = MockDR::new();
+        mock_disputes_repo
+            .expect_get_by_id()
+            .times(1)
+            .returning(|_| Ok(dispute(1, 5, DisputeStatus::ResolvedAgainstCustomer)));
+        mock_disputes_repo.expect_update().times(0);
+
+        let res = staff_transition_dispute(
+            Data::new(mock_disputes_repo),
+            Data::new(MockRepoCreate::<AuditEntry, NewAuditEntry>::new()),
+            test::TestRequest::post().to_http_request(),
+            (1, 1).into(),
+            Json(DisputeTransitionRest {
+                dispute_status: DisputeStatusRest::ProvisionalCredit,
+                note: None,
+            }),
+        )
+        .await;
+
+        assert!(res.is_err_and(|e| { e.to_string() == ApiError::Conflict.to_string() }));
+    }
+}
@@ -0,0 +1,48 @@
+pub mod handlers;
+pub mod models;
+pub mod transform;
+
+use actix_web::web;
+
+use crate::{
+    api::disputes,
+    models::{
+        audit::{AuditEntry, NewAuditEntry},
+        dispute::{
+            Dispute, DisputeEvent, DisputeTransition, FindDisputeEventQuery, FindDisputeQuery,
+            NewDispute,
+        },
+        transaction::Transaction,
+    },
+    traits::{RepoCreate, RepoFind, RepoGetById, RepoUpdate},
+};
+
+pub fn configure_disputes_api<DR, TR, AuR>(cfg: &mut web::ServiceConfig)
+where
+    DR: RepoCreate<Dispute, NewDispute>
+        + RepoFind<Dispute, FindDisputeQuery>
+        + RepoGetById<Dispute>
+        + RepoFind<DisputeEvent, FindDisputeEventQuery>
+        + RepoUpdate<Dispute, DisputeTransition>,
+    TR: RepoGetById<Transaction>,
+    AuR: RepoCreate<AuditEntry, NewAuditEntry>,
+{
+    cfg.service(
+        web::scope("/api/customers/{customer_id}/disputes")
+            .service(
+                web::resource("")
+                    .route(web::post().to(disputes::handlers::open_dispute::<DR, TR, AuR>))
+                    .route(web::get().to(disputes::handlers::find_disputes::<DR>)),
+            )
+            .service(
+                web::resource("/{dispute_id}")
+                    .route(web::get().to(disputes::handlers::get_dispute::<DR>)),
+            ),
+    )
+    .service(
+        web::scope("/api/staff/{staff_id}/disputes").service(
+            web::resource("/{dispute_id}/transitions")
+                .route(web::post().to(disputes::handlers::staff_transition_dispute::<DR, AuR>)),
+        ),
+    );
+}
@@ -0,0 +1,81 @@
+use serde::{Deserialize, Serialize};
+
+use crate::api::audit::models::ActorTypeRest;
+
+#[derive(Deserialize, Serialize, Copy, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum DisputeStatusRest {
+    Open,
+    Investigating,
+    ProvisionalCredit,
+    ResolvedForCustomer,
+    ResolvedAgainstCustomer,
+}
+
+#[cfg_attr(test, derive(Deserialize, PartialEq, Debug))]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DisputeRest {
+    pub id: i32,
+    pub customer_id: i32,
+    pub transaction_id: i32,
+    pub amount_cents: i64,
+    pub reason: String,
+    pub dispute_status: DisputeStatusRest,
+    pub credited_cents: i64,
+    pub date_opened: String,
+    pub date_resolved: Option<String>,
+}
+
+#[cfg_attr(test, derive(Deserialize))]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DisputesRest {
+    pub disputes: Vec<DisputeRest>,
+}
+
+#[cfg_attr(test, derive(Deserialize, PartialEq, Debug))]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DisputeEventRest {
+    pub id: i32,
+    pub dispute_id: i32,
+    pub dispute_status: DisputeStatusRest,
+    pub note: Option<String>,
+    pub actor_type: ActorTypeRest,
+    pub actor_id: i32,
+    pub transaction_id: Option<i32>,
+    pub created_at: String,
+}
+
+#[cfg_attr(test, derive(Deserialize))]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DisputeWithTimelineRest {
+    pub dispute: DisputeRest,
+    pub timeline: Vec<DisputeEventRest>,
+}
+
+#[cfg_attr(test, derive(Serialize))]
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct NewDisputeRest {
+    pub transaction_id: i32,
+    pub reason: String,
+    // whole (unreversed) transaction amount if not given
+    pub amount_cents: Option<i64>,
+}
+
+#[cfg_attr(test, derive(Serialize))]
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DisputeTransitionRest {
+    pub dispute_status: DisputeStatusRest,
+    pub note: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FindDisputeQueryRest {
+    pub transaction_id: Option<i32>,
+}
@@ -0,0 +1,67 @@
+use crate::models::dispute::{Dispute, DisputeEvent, DisputeStatus};
+use crate::util::string_opt_from_naive_dt_opt;
+
+use super::models::{DisputeEventRest, DisputeRest, DisputeStatusRest, DisputesRest};
+
+impl From<DisputeStatus> for DisputeStatusRest {
+    fn from(status: DisputeStatus) -> Self {
+        match status {
+            DisputeStatus::Open => DisputeStatusRest::Open,
+            DisputeStatus::Investigating => DisputeStatusRest::Investigating,
+            DisputeStatus::ProvisionalCredit => DisputeStatusRest::ProvisionalCredit,
+            DisputeStatus::ResolvedForCustomer => DisputeStatusRest::ResolvedForCustomer,
+            DisputeStatus::ResolvedAgainstCustomer => DisputeStatusRest::ResolvedAgainstCustomer,
+        }
+    }
+}
+
+impl From<DisputeStatusRest> for DisputeStatus {
+    fn from(status: DisputeStatusRest) -> Self {
+        match status {
+            DisputeStatusRest::Open => DisputeStatus::Open,
+            DisputeStatusRest::Investigating => DisputeStatus::Investigating,
+            DisputeStatusRest::ProvisionalCredit => DisputeStatus::ProvisionalCredit,
+            DisputeStatusRest::ResolvedForCustomer => DisputeStatus::ResolvedForCustomer,
+            DisputeStatusRest::ResolvedAgainstCustomer => DisputeStatus::ResolvedAgainstCustomer,
+        }
+    }
+}
+
+impl From<&Dispute> for DisputeRest {
+    fn from(dispute: &Dispute) -> Self {
+        Self {
+            id: dispute.id,
+            customer_id: dispute.customer_id,
+            transaction_id: dispute.transaction_id,
+            amount_cents: dispute.amount_cents,
+            reason: dispute.reason.clone(),
+            dispute_status: dispute.dispute_status.into(),
+            credited_cents: dispute.credited_cents,
+            date_opened: dispute.date_opened.to_string(),
+            date_resolved: string_opt_from_naive_dt_opt(dispute.date_resolved),
+        }
+    }
+}
+
+impl From<Vec<Dispute>> for DisputesRest {
+    fn from(disputes: Vec<Dispute>) -> Self {
+        Self {
+            disputes: disputes.iter().map(DisputeRest::from).collect(),
+        }
+    }
+}
+
+impl From<&DisputeEvent> for DisputeEventRest {
+    fn from(event: &DisputeEvent) -> Self {
+        Self {
+            id: event.id,
+            dispute_id: event.dispute_id,
+            dispute_status: event.dispute_status.into(),
+            note: event.note.clone(),
+            actor_type: event.actor_type.into(),
+            actor_id: event.actor_id,
+            transaction_id: event.transaction_id,
+            created_at: event.created_at.to_string(),
+        }
+    }
+}
@@ -11,7 +11,10 @@ pub enum ApiError {
     NotFound,
     InternalError,
     Unauthorized,
+    Forbidden,
     BadRequest,
+    Conflict,
+    LimitExceeded,
 }
 
 impl fmt::Display for ApiError {
@@ -28,7 +31,10 @@ impl error::ResponseError for ApiError {
                 Self::NotFound => "Entity not found",
                 Self::InternalError => "Internal server error",
                 Self::Unauthorized => "Unauthorized",
+                Self::Forbidden => "Forbidden",
                 Self::BadRequest => "Bad Request",
+                Self::Conflict => "Conflict",
+                Self::LimitExceeded => "Transfer limit exceeded",
             })
     }
 
@@ -37,7 +43,10 @@ impl error::ResponseError for ApiError {
             ApiError::InternalError => StatusCode::INTERNAL_SERVER_ERROR,
             ApiError::NotFound => StatusCode::NOT_FOUND,
             ApiError::Unauthorized => StatusCode::UNAUTHORIZED,
+            ApiError::Forbidden => StatusCode::FORBIDDEN,
             ApiError::BadRequest => StatusCode::BAD_REQUEST,
+            ApiError::Conflict => StatusCode::CONFLICT,
+            ApiError::LimitExceeded => StatusCode::UNPROCESSABLE_ENTITY,
         }
     }
 }
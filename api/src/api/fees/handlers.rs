@@ -0,0 +1,441 @@
+use actix_web::http::header::ContentType;
+use actix_web::web::{Data, Path, Query};
+use actix_web::{web, HttpRequest, HttpResponse};
+
+use super::models::{
+    FeeChargesRest, FeeScheduleEntryRest, FeeScheduleRest, FeeScheduleUpdateRest,
+    FindFeeChargeQueryRest, MonthlyFeeRunQueryRest,
+};
+use super::util::audit_fee_charges;
+use crate::api::audit::util::{new_audit_entry, record_audit, snapshot};
+use crate::api::error::ApiError;
+use crate::error::RepoError;
+use crate::models::audit::{ActorType, AuditAction, AuditEntity, AuditEntry, NewAuditEntry};
+use crate::models::fee::{
+    FeeCharge, FeeScheduleEntry, FeeScheduleUpdate, FindFeeChargeQuery, FindFeeScheduleQuery,
+    MonthlyFeeRun,
+};
+use crate::traits::{RepoCreate, RepoFind, RepoGetById, RepoUpdate};
+
+pub async fn find_fee_charges<FR>(
+    fees_repo: Data<FR>,
+    path: Path<i32>,
+    query: Query<FindFeeChargeQueryRest>,
+) -> Result<HttpResponse, actix_web::Error>
+where
+    FR: RepoFind<FeeCharge, FindFeeChargeQuery>,
+{
+    let customer_id = path.into_inner();
+
+    let charge_query = FindFeeChargeQuery {
+        customer_id,
+        account_id: query.account_id,
+    };
+
+    println!("Trying to get fee charges for customer {}", customer_id);
+
+    let charges = web::block(move || fees_repo.find(charge_query))
+        .await
+        .map_err(|_| ApiError::InternalError)?
+        .map_err(|_| ApiError::InternalError)?;
+
+    // enforced query on customer_id so should always be fine, but safe > sorry
+    for charge in charges.iter() {
+        if charge.customer_id != customer_id {
+            return Err(ApiError::BadRequest.into());
+        }
+    }
+
+    Ok(HttpResponse::Ok()
+        .insert_header(ContentType::json())
+        .json(web::Json::<FeeChargesRest>(charges.into())))
+}
+
+pub async fn find_fee_schedule<FR>(
+    fees_repo: Data<FR>,
+    path: Path<i32>,
+) -> Result<HttpResponse, actix_web::Error>
+where
+    FR: RepoFind<FeeScheduleEntry, FindFeeScheduleQuery>,
+{
+    let staff_id = path.into_inner();
+
+    println!("Staff {} trying to get the fee schedule", staff_id);
+
+    let schedule_query = FindFeeScheduleQuery {
+        kind: None,
+        account_type: None,
+    };
+
+    let entries = web::block(move || fees_repo.find(schedule_query))
+        .await
+        .map_err(|_| ApiError::InternalError)?
+        .map_err(|_| ApiError::InternalError)?;
+
+    Ok(HttpResponse::Ok()
+        .insert_header(ContentType::json())
+        .json(web::Json::<FeeScheduleRest>(entries.into())))
+}
+
+// new amounts apply from the next charge, nothing already charged is touched
+pub async fn update_fee_schedule<FR, AuR>(
+    fees_repo: Data<FR>,
+    audit_repo: Data<AuR>,
+    req: HttpRequest,
+    path: Path<(i32, i32)>,
+    payload: web::Json<FeeScheduleUpdateRest>,
+) -> Result<HttpResponse, actix_web::Error>
+where
+    FR: RepoGetById<FeeScheduleEntry> + RepoUpdate<FeeScheduleEntry, FeeScheduleUpdate>,
+    AuR: RepoCreate<AuditEntry, NewAuditEntry>,
+{
+    let (staff_id, entry_id) = path.into_inner();
+
+    let update: FeeScheduleUpdate = payload.into_inner().into();
+    if !update.is_valid() {
+        return Err(ApiError::BadRequest.into());
+    }
+
+    println!(
+        "Staff {} trying to update fee schedule entry {}",
+        staff_id, entry_id
+    );
+
+    let (before, after) = web::block(move || {
+        let before = fees_repo.get_by_id(entry_id).map_err(|err| match err {
+            RepoError::NotFound => ApiError::NotFound,
+            _ => ApiError::InternalError,
+        })?;
+
+        let after = fees_repo
+            .update(entry_id, update)
+            .map_err(|err| match err {
+                RepoError::NotFound => ApiError::NotFound,
+                _ => ApiError::InternalError,
+            })?;
+
+        Ok::<_, ApiError>((before, after))
+    })
+    .await
+    .map_err(|_| ApiError::InternalError)??;
+
+    let entry_rest: FeeScheduleEntryRest = (&after).into();
+
+    let mut audit_entry = new_audit_entry(
+        &req,
+        ActorType::Staff,
+        staff_id,
+        AuditAction::UpdateFeeSchedule,
+        AuditEntity::FeeSchedule,
+        Some(entry_id),
+    );
+    audit_entry.before_snapshot = snapshot(&FeeScheduleEntryRest::from(&before));
+    audit_entry.after_snapshot = snapshot(&entry_rest);
+    record_audit(audit_repo, audit_entry).await;
+
+    Ok(HttpResponse::Ok()
+        .insert_header(ContentType::json())
+        .json(web::Json(entry_rest)))
+}
+
+// called by whatever schedules the bank's month end. safe to call again for the same month, each
+// account is only charged once per month
+pub async fn run_monthly_fees<FR, AuR>(
+    fees_repo: Data<FR>,
+    audit_repo: Data<AuR>,
+    req: HttpRequest,
+    path: Path<i32>,
+    query: Query<MonthlyFeeRunQueryRest>,
+) -> Result<HttpResponse, actix_web::Error>
+where
+    FR: RepoCreate<Vec<FeeCharge>, MonthlyFeeRun>,
+    AuR: RepoCreate<AuditEntry, NewAuditEntry>,
+{
+    let staff_id = path.into_inner();
+
+    let day = match &query.date {
+        Some(date) => {
+            chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d").map_err(|_| ApiError::BadRequest)?
+        }
+        None => chrono::Utc::now().date_naive(),
+    };
+
+    println!(
+        "Staff {} trying to charge account keeping fees for the month of {}",
+        staff_id, day
+    );
+
+    let charges = web::block(move || fees_repo.create(MonthlyFeeRun { day }))
+        .await
+        .map_err(|_| ApiError::InternalError)?
+        .map_err(|_| ApiError::InternalError)?;
+
+    audit_fee_charges(audit_repo, &req, &charges).await;
+
+    Ok(HttpResponse::Ok()
+        .insert_header(ContentType::json())
+        .json(web::Json::<FeeChargesRest>(charges.into())))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::audit::util::appended;
+    use crate::fixtures::dt;
+    use actix_web::{
+        body::to_bytes,
+        http::StatusCode,
+        test,
+        web::{Data, Json, Query},
+    };
+    use chrono::NaiveDate;
+    use mockall::{mock, predicate::eq};
+
+    use crate::{
+        api::{
+            error::ApiError,
+            fees::{
+                handlers::{
+                    find_fee_charges, find_fee_schedule, run_monthly_fees, update_fee_schedule,
+                },
+                models::{
+                    FeeChargesRest, FeeKindRest, FeeScheduleEntryRest, FeeScheduleRest,
+                    FeeScheduleUpdateRest, FindFeeChargeQueryRest, MonthlyFeeRunQueryRest,
+                },
+            },
+        },
+        error::RepoError,
+        models::{
+            account::AccountType,
+            audit::{ActorType, AuditEntry, NewAuditEntry},
+            fee::{
+                FeeCharge, FeeKind, FeeScheduleEntry, FeeScheduleUpdate, FindFeeChargeQuery,
+                FindFeeScheduleQuery, MonthlyFeeRun,
+            },
+        },
+        traits::{MockRepoCreate, MockRepoFind, RepoGetById, RepoUpdate},
+    };
+
+    mock! {
+        pub FR { }
+        impl RepoGetById<FeeScheduleEntry> for FR {
+            fn get_by_id(&self, id: i32) -> Result<FeeScheduleEntry, RepoError>;
+        }
+        impl RepoUpdate<FeeScheduleEntry, FeeScheduleUpdate> for FR {
+            fn update(&self, id: i32, update: FeeScheduleUpdate) -> Result<FeeScheduleEntry, RepoError>;
+        }
+    }
+
+    fn entry(id: i32, amount_cents: i64, waiver_deposit_cents: Option<i64>) -> FeeScheduleEntry {
+        FeeScheduleEntry {
+            id,
+            kind: FeeKind::AccountKeeping,
+            account_type: AccountType::Transaction,
+            amount_cents,
+            waiver_deposit_cents,
+            updated_at: dt(),
+        }
+    }
+
+    fn charge(id: i32, account_id: i32, kind: FeeKind) -> FeeCharge {
+        FeeCharge {
+            id,
+            account_id,
+            customer_id: 5,
+            kind,
+            period_start: match kind {
+                FeeKind::AccountKeeping => Some(NaiveDate::from_ymd_opt(2023, 8, 1).unwrap()),
+                FeeKind::ExternalTransfer => None,
+            },
+            trigger_transaction_id: match kind {
+                FeeKind::AccountKeeping => None,
+                FeeKind::ExternalTransfer => Some(90 + id),
+            },
+            amount_cents: 500,
+            transaction_id: 70 + id,
+            created_at: dt(),
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_find_fee_schedule_success() {
+        let mut mock_fees_repo = MockRepoFind::<FeeScheduleEntry, FindFeeScheduleQuery>::new();
+        mock_fees_repo
+            .expect_find()
+            .with(eq(FindFeeScheduleQuery {
+                kind: None,
+                account_type: None,
+            }))
+            .times(1)
+            .returning(|_| Ok(vec![entry(1, 500, Some(200000)), entry(2, 0, None)]));
+
+        let res = find_fee_schedule(Data::new(mock_fees_repo), 2.into())
+            .await
+            .unwrap();
+
+        assert_eq!(StatusCode::OK, res.status());
+
+        let actual: FeeScheduleRest =
+            serde_json::from_slice(&to_bytes(res.into_body()).await.unwrap()).unwrap();
+        assert_eq!(2, actual.entries.len());
+        assert_eq!(Some(200000), actual.entries[0].waiver_deposit_cents);
+    }
+
+    #[actix_web::test]
+    async fn test_update_fee_schedule_success() {
+        let mut mock_fees_repo = MockFR::new();
+        mock_fees_repo
+            .expect_get_by_id()
+            .with(eq(1))
+            .times(1)
+            .returning(|_| Ok(entry(1, 500, Some(200000))));
+        mock_fees_repo
+            .expect_update()
+            .with(
+                eq(1),
+                eq(FeeScheduleUpdate {
+                    amount_cents: 600,
+                    waiver_deposit_cents: None,
+                }),
+            )
+            .times(1)
+            .returning(|_, update| Ok(entry(1, update.amount_cents, update.waiver_deposit_cents)));
+
+        let mut mock_audit_repo = MockRepoCreate::<AuditEntry, NewAuditEntry>::new();
+        mock_audit_repo
+            .expect_create()
+            .withf(|entry| {
+                entry.actor_type == ActorType::Staff
+                    && entry.actor_id == 2
+                    && entry.action == "update_fee_schedule"
+                    && entry.entity_type == "fee_schedule"
+                    && entry.entity_id == Some(1)
+                    && entry.before_snapshot.is_some()
+                    && entry.after_snapshot.is_some()
+            })
+            .times(1)
+            .returning(|entry| Ok(appended(entry)));
+
+        let res = update_fee_schedule(
+            Data::new(mock_fees_repo),
+            Data::new(mock_audit_repo),
+            test::TestRequest::put().to_http_request(),
+            (2, 1).into(),
+            Json(FeeScheduleUpdateRest {
+                amount_cents: 600,
+                waiver_deposit_cents: None,
+            }),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(StatusCode::OK, res.status());
+
+        let actual: FeeScheduleEntryRest =
+            serde_json::from_slice(&to_bytes(res.into_body()).await.unwrap()).unwrap();
+        assert_eq!(600, actual.amount_cents);
+        assert_eq!(None, actual.waiver_deposit_cents);
+    }
+
+    #[actix_web::test]
+    async fn test_update_fee_schedule_invalid_error() {
+        let mut mock_fees_repo = MockFR::new();
+        mock_fees_repo.expect_update().times(0);
+
+        let res = update_fee_schedule(
+            Data::new(mock_fees_repo),
+            Data::new(MockRepoCreate::<AuditEntry, NewAuditEntry>::new()),
+            test::TestRequest::put().to_http_request(),
+            (2, 1).into(),
+            Json(FeeScheduleUpdateRest {
+                amount_cents: 500,
+                waiver_deposit_cents: Some(0),
+            }),
+        )
+        .await;
+
+        assert!(res.is_err_and(|e| { e.to_string() == ApiError::BadRequest.to_string() }));
+    }
+
+    #[actix_web::test]
+    async fn test_find_fee_charges_success() {
+        let mut mock_fees_repo = MockRepoFind::<FeeCharge, FindFeeChargeQuery>::new();
+        mock_fees_repo
+            .expect_find()
+            .with(eq(FindFeeChargeQuery {
+                customer_id: 5,
+                account_id: None,
+            }))
+            .times(1)
+            .returning(|_| {
+                Ok(vec![
+                    charge(2, 52, FeeKind::ExternalTransfer),
+                    charge(1, 52, FeeKind::AccountKeeping),
+                ])
+            });
+
+        let res = find_fee_charges(
+            Data::new(mock_fees_repo),
+            5.into(),
+            Query(FindFeeChargeQueryRest { account_id: None }),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(StatusCode::OK, res.status());
+
+        let actual: FeeChargesRest =
+            serde_json::from_slice(&to_bytes(res.into_body()).await.unwrap()).unwrap();
+        assert_eq!(2, actual.charges.len());
+        assert_eq!(FeeKindRest::ExternalTransfer, actual.charges[0].kind);
+        assert_eq!(
+            Some("2023-08-01".to_string()),
+            actual.charges[1].period_start
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_run_monthly_fees_audits_each_charge() {
+        let day = NaiveDate::from_ymd_opt(2023, 8, 31).unwrap();
+
+        let mut mock_fees_repo = MockRepoCreate::<Vec<FeeCharge>, MonthlyFeeRun>::new();
+        mock_fees_repo
+            .expect_create()
+            .with(eq(MonthlyFeeRun { day }))
+            .times(1)
+            .returning(|_| {
+                Ok(vec![
+                    charge(1, 52, FeeKind::AccountKeeping),
+                    charge(2, 53, FeeKind::AccountKeeping),
+                ])
+            });
+
+        let mut mock_audit_repo = MockRepoCreate::<AuditEntry, NewAuditEntry>::new();
+        mock_audit_repo
+            .expect_create()
+            .withf(|entry| {
+                entry.actor_type == ActorType::System
+                    && entry.action == "charge_fee"
+                    && entry.entity_type == "transaction"
+            })
+            .times(2)
+            .returning(|entry| Ok(appended(entry)));
+
+        let res = run_monthly_fees(
+            Data::new(mock_fees_repo),
+            Data::new(mock_audit_repo),
+            test::TestRequest::post().to_http_request(),
+            2.into(),
+            Query(MonthlyFeeRunQueryRest {
+                date: Some("2023-08-31".to_string()),
+            }),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(StatusCode::OK, res.status());
+
+        let actual: FeeChargesRest =
+            serde_json::from_slice(&to_bytes(res.into_body()).await.unwrap()).unwrap();
+        assert_eq!(2, actual.charges.len());
+    }
+}
@@ -0,0 +1,49 @@
+pub mod handlers;
+pub mod models;
+pub mod transform;
+pub mod util;
+
+use actix_web::web;
+
+use crate::{
+    api::fees,
+    models::{
+        audit::{AuditEntry, NewAuditEntry},
+        fee::{
+            FeeCharge, FeeScheduleEntry, FeeScheduleUpdate, FindFeeChargeQuery,
+            FindFeeScheduleQuery, MonthlyFeeRun,
+        },
+    },
+    traits::{RepoCreate, RepoFind, RepoGetById, RepoUpdate},
+};
+
+pub fn configure_fees_api<FR, AuR>(cfg: &mut web::ServiceConfig)
+where
+    FR: RepoFind<FeeScheduleEntry, FindFeeScheduleQuery>
+        + RepoGetById<FeeScheduleEntry>
+        + RepoUpdate<FeeScheduleEntry, FeeScheduleUpdate>
+        + RepoFind<FeeCharge, FindFeeChargeQuery>
+        + RepoCreate<Vec<FeeCharge>, MonthlyFeeRun>,
+    AuR: RepoCreate<AuditEntry, NewAuditEntry>,
+{
+    cfg.service(
+        web::scope("/api/customers/{customer_id}/fee-charges").service(
+            web::resource("").route(web::get().to(fees::handlers::find_fee_charges::<FR>)),
+        ),
+    )
+    .service(
+        web::scope("/api/staff/{staff_id}/fees")
+            .service(
+                web::resource("/schedule")
+                    .route(web::get().to(fees::handlers::find_fee_schedule::<FR>)),
+            )
+            .service(
+                web::resource("/schedule/{entry_id}")
+                    .route(web::put().to(fees::handlers::update_fee_schedule::<FR, AuR>)),
+            )
+            .service(
+                web::resource("/monthly-run")
+                    .route(web::post().to(fees::handlers::run_monthly_fees::<FR, AuR>)),
+            ),
+    );
+}
@@ -0,0 +1,84 @@
+use serde::{Deserialize, Serialize};
+
+use crate::api::accounts::models::AccountTypeRest;
+
+#[derive(Deserialize, Serialize, Copy, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum FeeKindRest {
+    AccountKeeping,
+    ExternalTransfer,
+}
+
+#[cfg_attr(test, derive(Deserialize, PartialEq, Debug))]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FeeScheduleEntryRest {
+    pub id: i32,
+    pub kind: FeeKindRest,
+    pub account_type: AccountTypeRest,
+    pub amount_cents: i64,
+    pub waiver_deposit_cents: Option<i64>,
+    pub updated_at: String,
+}
+
+#[cfg_attr(test, derive(Deserialize))]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FeeScheduleRest {
+    pub entries: Vec<FeeScheduleEntryRest>,
+}
+
+#[cfg_attr(test, derive(Serialize))]
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct FeeScheduleUpdateRest {
+    pub amount_cents: i64,
+    // left out takes the waiver away
+    pub waiver_deposit_cents: Option<i64>,
+}
+
+#[cfg_attr(test, derive(Deserialize, PartialEq, Debug))]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FeeChargeRest {
+    pub id: i32,
+    pub account_id: i32,
+    pub customer_id: i32,
+    pub kind: FeeKindRest,
+    pub period_start: Option<String>,
+    pub trigger_transaction_id: Option<i32>,
+    pub amount_cents: i64,
+    pub transaction_id: i32,
+    pub created_at: String,
+}
+
+#[cfg_attr(test, derive(Deserialize))]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FeeChargesRest {
+    pub charges: Vec<FeeChargeRest>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FindFeeChargeQueryRest {
+    pub account_id: Option<i32>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MonthlyFeeRunQueryRest {
+    // any day in the month, this month if not given
+    pub date: Option<String>,
+}
+
+#[cfg_attr(test, derive(Deserialize, PartialEq, Debug))]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AppliedFeeRest {
+    pub kind: FeeKindRest,
+    pub description: String,
+    pub schedule_cents: i64,
+    pub amount_cents: i64,
+    pub waived: bool,
+}
@@ -0,0 +1,81 @@
+use crate::models::fee::{AppliedFee, FeeCharge, FeeKind, FeeScheduleEntry, FeeScheduleUpdate};
+
+use super::models::{
+    AppliedFeeRest, FeeChargeRest, FeeChargesRest, FeeKindRest, FeeScheduleEntryRest,
+    FeeScheduleRest, FeeScheduleUpdateRest,
+};
+
+impl From<FeeKind> for FeeKindRest {
+    fn from(kind: FeeKind) -> Self {
+        match kind {
+            FeeKind::AccountKeeping => FeeKindRest::AccountKeeping,
+            FeeKind::ExternalTransfer => FeeKindRest::ExternalTransfer,
+        }
+    }
+}
+
+impl From<&FeeScheduleEntry> for FeeScheduleEntryRest {
+    fn from(entry: &FeeScheduleEntry) -> Self {
+        Self {
+            id: entry.id,
+            kind: entry.kind.into(),
+            account_type: entry.account_type.into(),
+            amount_cents: entry.amount_cents,
+            waiver_deposit_cents: entry.waiver_deposit_cents,
+            updated_at: entry.updated_at.to_string(),
+        }
+    }
+}
+
+impl From<Vec<FeeScheduleEntry>> for FeeScheduleRest {
+    fn from(entries: Vec<FeeScheduleEntry>) -> Self {
+        Self {
+            entries: entries.iter().map(FeeScheduleEntryRest::from).collect(),
+        }
+    }
+}
+
+impl From<FeeScheduleUpdateRest> for FeeScheduleUpdate {
+    fn from(update: FeeScheduleUpdateRest) -> Self {
+        Self {
+            amount_cents: update.amount_cents,
+            waiver_deposit_cents: update.waiver_deposit_cents,
+        }
+    }
+}
+
+impl From<&FeeCharge> for FeeChargeRest {
+    fn from(charge: &FeeCharge) -> Self {
+        Self {
+            id: charge.id,
+            account_id: charge.account_id,
+            customer_id: charge.customer_id,
+            kind: charge.kind.into(),
+            period_start: charge.period_start.map(|day| day.to_string()),
+            trigger_transaction_id: charge.trigger_transaction_id,
+            amount_cents: charge.amount_cents,
+            transaction_id: charge.transaction_id,
+            created_at: charge.created_at.to_string(),
+        }
+    }
+}
+
+impl From<Vec<FeeCharge>> for FeeChargesRest {
+    fn from(charges: Vec<FeeCharge>) -> Self {
+        Self {
+            charges: charges.iter().map(FeeChargeRest::from).collect(),
+        }
+    }
+}
+
+impl From<&AppliedFee> for AppliedFeeRest {
+    fn from(fee: &AppliedFee) -> Self {
+        Self {
+            kind: fee.kind.into(),
+            description: fee.kind.description().to_string(),
+            schedule_cents: fee.schedule_cents,
+            amount_cents: fee.amount_cents,
+            waived: fee.waived,
+        }
+    }
+}
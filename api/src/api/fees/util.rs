@@ -0,0 +1,30 @@
+use actix_web::{web::Data, HttpRequest};
+
+use super::models::FeeChargeRest;
+use crate::{
+    api::audit::util::{new_audit_entry, record_audit, snapshot},
+    models::{
+        audit::{ActorType, AuditAction, AuditEntity, AuditEntry, NewAuditEntry},
+        fee::FeeCharge,
+    },
+    traits::RepoCreate,
+};
+
+// fees are the system acting, on the account they came out of
+pub async fn audit_fee_charges<AuR>(audit_repo: Data<AuR>, req: &HttpRequest, charges: &[FeeCharge])
+where
+    AuR: RepoCreate<AuditEntry, NewAuditEntry>,
+{
+    for charge in charges.iter() {
+        let mut audit_entry = new_audit_entry(
+            req,
+            ActorType::System,
+            charge.account_id,
+            AuditAction::ChargeFee,
+            AuditEntity::Transaction,
+            Some(charge.transaction_id),
+        );
+        audit_entry.after_snapshot = snapshot(&FeeChargeRest::from(charge));
+        record_audit(audit_repo.clone(), audit_entry).await;
+    }
+}
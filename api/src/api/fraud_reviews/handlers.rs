@@ -0,0 +1,427 @@
+use actix_web::http::header::ContentType;
+use actix_web::web::{Data, Path, Query};
+use actix_web::{web, HttpRequest, HttpResponse};
+
+use super::models::{
+    FindFraudReviewQueryRest, FraudReviewDecisionRest, FraudReviewRest, FraudReviewsRest,
+};
+use crate::api::audit::util::{new_audit_entry, record_audit, snapshot};
+use crate::api::automation::util::run_automations;
+use crate::api::budgets::util::check_budgets;
+use crate::api::error::ApiError;
+use crate::error::RepoError;
+use crate::models::audit::{ActorType, AuditAction, AuditEntity, AuditEntry, NewAuditEntry};
+use crate::models::automation::{AutomationRun, AutomationTrigger};
+use crate::models::budget::{BudgetAlert, BudgetAlertDelivered, BudgetCheck};
+use crate::models::fraud_review::{
+    FindFraudReviewQuery, FraudReview, FraudReviewDecision, FraudReviewStatus,
+};
+use crate::notifications::Notifier;
+use crate::traits::{RepoCreate, RepoFind, RepoGetById, RepoUpdate};
+
+const MAX_NOTE_LEN: usize = 500;
+
+pub async fn find_fraud_reviews<FR>(
+    fraud_reviews_repo: Data<FR>,
+    path: Path<i32>,
+    query: Query<FindFraudReviewQueryRest>,
+) -> Result<HttpResponse, actix_web::Error>
+where
+    FR: RepoFind<FraudReview, FindFraudReviewQuery>,
+{
+    let staff_id = path.into_inner();
+
+    let query = FindFraudReviewQuery {
+        review_status: Some(
+            query
+                .review_status
+                .map(FraudReviewStatus::from)
+                .unwrap_or(FraudReviewStatus::Pending),
+        ),
+        customer_id: query.customer_id,
+    };
+
+    println!("Trying to get fraud reviews for staff {}", staff_id);
+
+    let reviews = web::block(move || fraud_reviews_repo.find(query))
+        .await
+        .map_err(|_| ApiError::InternalError)?
+        .map_err(|_| ApiError::InternalError)?;
+
+    Ok(HttpResponse::Ok()
+        .insert_header(ContentType::json())
+        .json(web::Json::<FraudReviewsRest>(reviews.into())))
+}
+
+#[allow(clippy::too_many_arguments)]
+pub async fn decide_fraud_review<FR, BuR, AtR, N, AuR>(
+    fraud_reviews_repo: Data<FR>,
+    budgets_repo: Data<BuR>,
+    automation_repo: Data<AtR>,
+    audit_repo: Data<AuR>,
+    notifier: Data<N>,
+    req: HttpRequest,
+    path: Path<(i32, i32)>,
+    payload: web::Json<FraudReviewDecisionRest>,
+) -> Result<HttpResponse, actix_web::Error>
+where
+    FR: RepoGetById<FraudReview> + RepoUpdate<FraudReview, FraudReviewDecision>,
+    BuR: RepoCreate<Vec<BudgetAlert>, BudgetCheck> + RepoUpdate<BudgetAlert, BudgetAlertDelivered>,
+    AtR: RepoCreate<Vec<AutomationRun>, AutomationTrigger>,
+    N: Notifier,
+    AuR: RepoCreate<AuditEntry, NewAuditEntry>,
+{
+    let (staff_id, review_id) = path.into_inner();
+    let payload = payload.into_inner();
+
+    if payload
+        .note
+        .as_ref()
+        .is_some_and(|note| note.len() > MAX_NOTE_LEN)
+    {
+        return Err(ApiError::BadRequest.into());
+    }
+
+    let decision = FraudReviewDecision {
+        approve: payload.approve,
+        reviewer_id: staff_id,
+        note: payload.note,
+    };
+
+    println!(
+        "Trying to {} fraud review {} for staff {}",
+        if decision.approve {
+            "approve"
+        } else {
+            "reject"
+        },
+        review_id,
+        staff_id
+    );
+
+    let (before, after) = web::block(move || {
+        let before = fraud_reviews_repo.get_by_id(review_id)?;
+
+        if before.review_status != FraudReviewStatus::Pending {
+            return Err(RepoError::Conflict);
+        }
+
+        let after = fraud_reviews_repo.update(review_id, decision)?;
+        Ok((before, after))
+    })
+    .await
+    .map_err(|_| ApiError::InternalError)?
+    .map_err(|err: RepoError| match err {
+        RepoError::NotFound => ApiError::NotFound,
+        RepoError::Conflict => ApiError::Conflict,
+        _ => ApiError::InternalError,
+    })?;
+
+    let after_rest: FraudReviewRest = (&after).into();
+
+    let mut audit_entry = new_audit_entry(
+        &req,
+        ActorType::Staff,
+        staff_id,
+        AuditAction::ReviewFraudHold,
+        AuditEntity::FraudReview,
+        Some(review_id),
+    );
+    audit_entry.before_snapshot = snapshot(&FraudReviewRest::from(&before));
+    audit_entry.after_snapshot = snapshot(&after_rest);
+    record_audit(audit_repo.clone(), audit_entry).await;
+
+    // approving settles internal transfers, the check and the rules skip anything still pending
+    if after.review_status == FraudReviewStatus::Approved {
+        check_budgets(budgets_repo.clone(), notifier.clone(), after.transaction_id).await;
+
+        let trigger = AutomationTrigger::Settled {
+            transaction_id: after.transaction_id,
+        };
+        run_automations(
+            automation_repo,
+            (budgets_repo, notifier),
+            audit_repo,
+            &req,
+            trigger,
+        )
+        .await;
+    }
+
+    Ok(HttpResponse::Ok()
+        .insert_header(ContentType::json())
+        .json(web::Json(after_rest)))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::audit::util::appended;
+    use crate::fixtures::dt;
+    use actix_web::{
+        body::to_bytes,
+        http::StatusCode,
+        test,
+        web::{Data, Json, Query},
+    };
+    use chrono::NaiveDate;
+    use mockall::{mock, predicate::eq};
+
+    use crate::{
+        api::{
+            error::ApiError,
+            fraud_reviews::{
+                handlers::{decide_fraud_review, find_fraud_reviews},
+                models::{
+                    FindFraudReviewQueryRest, FraudReviewDecisionRest, FraudReviewRest,
+                    FraudReviewStatusRest, FraudReviewsRest,
+                },
+            },
+        },
+        error::RepoError,
+        models::{
+            audit::{ActorType, AuditEntry, NewAuditEntry},
+            automation::{AutomationRun, AutomationTrigger},
+            budget::{BudgetAlert, BudgetAlertDelivered, BudgetCheck},
+            fraud_review::{
+                FindFraudReviewQuery, FraudReview, FraudReviewDecision, FraudReviewStatus,
+            },
+        },
+        notifications::MockNotifier,
+        traits::{MockRepoCreate, MockRepoFind, RepoCreate, RepoGetById, RepoUpdate},
+    };
+
+    fn review(id: i32, review_status: FraudReviewStatus) -> FraudReview {
+        FraudReview {
+            id,
+            transaction_id: 70,
+            customer_id: 5,
+            reasons: r#"["new payee amount over 100000 cents"]"#.to_string(),
+            review_status,
+            reviewer_id: None,
+            review_note: None,
+            created_at: dt(),
+            reviewed_at: None,
+        }
+    }
+
+    mock! {
+        pub FR { }
+        impl RepoGetById<FraudReview> for FR {
+            fn get_by_id(&self, id: i32) -> Result<FraudReview, RepoError>;
+        }
+        impl RepoUpdate<FraudReview, FraudReviewDecision> for FR {
+            fn update(&self, id: i32, update: FraudReviewDecision) -> Result<FraudReview, RepoError>;
+        }
+    }
+
+    mock! {
+        pub BuR { }
+        impl RepoCreate<Vec<BudgetAlert>, BudgetCheck> for BuR {
+            fn create(&self, new: BudgetCheck) -> Result<Vec<BudgetAlert>, RepoError>;
+        }
+        impl RepoUpdate<BudgetAlert, BudgetAlertDelivered> for BuR {
+            fn update(&self, id: i32, update: BudgetAlertDelivered) -> Result<BudgetAlert, RepoError>;
+        }
+    }
+
+    fn budget_alert() -> BudgetAlert {
+        BudgetAlert {
+            id: 3,
+            budget_id: 4,
+            customer_id: 5,
+            period_start: NaiveDate::from_ymd_opt(2023, 8, 1).unwrap(),
+            threshold_percent: 80,
+            spent_cents: 85000,
+            budget_cents: 100000,
+            transaction_id: Some(70),
+            created_at: dt(),
+            delivered_at: None,
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_find_fraud_reviews_defaults_to_pending() {
+        let mut mock_fraud_reviews_repo = MockRepoFind::<FraudReview, FindFraudReviewQuery>::new();
+        mock_fraud_reviews_repo
+            .expect_find()
+            .with(eq(FindFraudReviewQuery {
+                review_status: Some(FraudReviewStatus::Pending),
+                customer_id: None,
+            }))
+            .times(1)
+            .returning(|_| Ok(vec![review(1, FraudReviewStatus::Pending)]));
+
+        let res = find_fraud_reviews(
+            Data::new(mock_fraud_reviews_repo),
+            1.into(),
+            Query(FindFraudReviewQueryRest {
+                review_status: None,
+                customer_id: None,
+            }),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(StatusCode::OK, res.status());
+
+        let actual: FraudReviewsRest =
+            serde_json::from_slice(&to_bytes(res.into_body()).await.unwrap()).unwrap();
+        assert_eq!(1, actual.fraud_reviews.len());
+        assert_eq!(
+            vec!["new payee amount over 100000 cents".to_string()],
+            actual.fraud_reviews[0].reasons
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_decide_fraud_review_approve_success() {
+        let staff_id = 2;
+        let review_id = 1;
+
+        let mut mock_fraud_reviews_repo = MockFR::new();
+        mock_fraud_reviews_repo
+            .expect_get_by_id()
+            .with(eq(review_id))
+            .times(1)
+            .returning(move |_| Ok(review(review_id, FraudReviewStatus::Pending)));
+        mock_fraud_reviews_repo
+            .expect_update()
+            .with(
+                eq(review_id),
+                eq(FraudReviewDecision {
+                    approve: true,
+                    reviewer_id: staff_id,
+                    note: Some("called customer, legit".to_string()),
+                }),
+            )
+            .times(1)
+            .returning(move |_, decision| {
+                let mut r = review(review_id, FraudReviewStatus::Approved);
+                r.reviewer_id = Some(decision.reviewer_id);
+                r.review_note = decision.note;
+                r.reviewed_at = Some(dt());
+                Ok(r)
+            });
+
+        let mut mock_audit_repo = MockRepoCreate::<AuditEntry, NewAuditEntry>::new();
+        mock_audit_repo
+            .expect_create()
+            .withf(|entry| {
+                entry.action == "review_fraud_hold"
+                    && entry.entity_type == "fraud_review"
+                    && entry.actor_type == ActorType::Staff
+                    && entry.before_snapshot.is_some()
+            })
+            .times(1)
+            .returning(|entry| Ok(appended(entry)));
+        mock_audit_repo
+            .expect_create()
+            .withf(|entry| {
+                entry.action == "run_automation_rule"
+                    && entry.actor_type == ActorType::System
+                    && entry.actor_id == 4
+                    && entry.entity_id == Some(71)
+            })
+            .times(1)
+            .returning(|entry| Ok(appended(entry)));
+
+        // and rounds it up into savings
+        let mut mock_automation_repo =
+            MockRepoCreate::<Vec<AutomationRun>, AutomationTrigger>::new();
+        mock_automation_repo
+            .expect_create()
+            .with(eq(AutomationTrigger::Settled { transaction_id: 70 }))
+            .times(1)
+            .returning(|_| {
+                Ok(vec![AutomationRun {
+                    id: 1,
+                    rule_id: 4,
+                    customer_id: 5,
+                    trigger_transaction_id: Some(70),
+                    run_date: None,
+                    transaction_id: 71,
+                    amount_cents: 55,
+                    created_at: dt(),
+                }])
+            });
+
+        // approving settles the transfer, which takes a budget past 80%
+        let mut mock_budgets_repo = MockBuR::new();
+        mock_budgets_repo
+            .expect_create()
+            .with(eq(BudgetCheck { transaction_id: 70 }))
+            .times(1)
+            .returning(|_| Ok(vec![budget_alert()]));
+        mock_budgets_repo
+            .expect_update()
+            .withf(|id, _| *id == 3)
+            .times(1)
+            .returning(|_, delivered| {
+                let mut alert = budget_alert();
+                alert.delivered_at = Some(delivered.delivered_at);
+                Ok(alert)
+            });
+
+        let mut mock_notifier = MockNotifier::new();
+        mock_notifier
+            .expect_notify()
+            .withf(|notification| {
+                notification.customer_id == 5
+                    && notification.kind == "budget_alert"
+                    && notification.body
+                        == "You've spent $850.00 of your $1000.00 budget this month"
+            })
+            .times(1)
+            .returning(|_| Ok(()));
+
+        let res = decide_fraud_review(
+            Data::new(mock_fraud_reviews_repo),
+            Data::new(mock_budgets_repo),
+            Data::new(mock_automation_repo),
+            Data::new(mock_audit_repo),
+            Data::new(mock_notifier),
+            test::TestRequest::post().to_http_request(),
+            (staff_id, review_id).into(),
+            Json(FraudReviewDecisionRest {
+                approve: true,
+                note: Some("called customer, legit".to_string()),
+            }),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(StatusCode::OK, res.status());
+
+        let actual: FraudReviewRest =
+            serde_json::from_slice(&to_bytes(res.into_body()).await.unwrap()).unwrap();
+        assert_eq!(FraudReviewStatusRest::Approved, actual.review_status);
+        assert_eq!(Some(staff_id), actual.reviewer_id);
+    }
+
+    #[actix_web::test]
+    async fn test_decide_fraud_review_already_decided_conflict() {
+        let mut mock_fraud_reviews_repo = MockFR::new();
+        mock_fraud_reviews_repo
+            .expect_get_by_id()
+            .times(1)
+            .returning(|_| Ok(review(1, FraudReviewStatus::Rejected)));
+        mock_fraud_reviews_repo.expect_update().times(0);
+
+        let res = decide_fraud_review(
+            Data::new(mock_fraud_reviews_repo),
+            Data::new(MockBuR::new()),
+            Data::new(MockRepoCreate::<Vec<AutomationRun>, AutomationTrigger>::new()),
+            Data::new(MockRepoCreate::<AuditEntry, NewAuditEntry>::new()),
+            Data::new(MockNotifier::new()),
+            test::TestRequest::post().to_http_request(),
+            (2, 1).into(),
+            Json(FraudReviewDecisionRest {
+                approve: true,
+                note: None,
+            }),
+        )
+        .await;
+
+        assert!(res.is_err_and(|e| { e.to_string() == ApiError::Conflict.to_string() }));
+    }
+}
@@ -0,0 +1,47 @@
+pub mod handlers;
+pub mod models;
+pub mod transform;
+
+use actix_web::web;
+
+use crate::{
+    api::fraud_reviews,
+    models::{
+        audit::{AuditEntry, NewAuditEntry},
+        automation::{AutomationRun, AutomationTrigger},
+        budget::{BudgetAlert, BudgetAlertDelivered, BudgetCheck},
+        fraud_review::{FindFraudReviewQuery, FraudReview, FraudReviewDecision},
+    },
+    notifications::Notifier,
+    traits::{RepoCreate, RepoFind, RepoGetById, RepoUpdate},
+};
+
+pub fn configure_fraud_reviews_api<FR, BuR, AtR, N, AuR>(cfg: &mut web::ServiceConfig)
+where
+    FR: RepoFind<FraudReview, FindFraudReviewQuery>
+        + RepoGetById<FraudReview>
+        + RepoUpdate<FraudReview, FraudReviewDecision>,
+    BuR: RepoCreate<Vec<BudgetAlert>, BudgetCheck> + RepoUpdate<BudgetAlert, BudgetAlertDelivered>,
+    AtR: RepoCreate<Vec<AutomationRun>, AutomationTrigger>,
+    N: Notifier,
+    AuR: RepoCreate<AuditEntry, NewAuditEntry>,
+{
+    cfg.service(
+        web::scope("/api/staff/{staff_id}/fraud-reviews")
+            .service(
+                web::resource("")
+                    .route(web::get().to(fraud_reviews::handlers::find_fraud_reviews::<FR>)),
+            )
+            .service(
+                web::resource("/{review_id}/decision").route(
+                    web::post().to(fraud_reviews::handlers::decide_fraud_review::<
+                        FR,
+                        BuR,
+                        AtR,
+                        N,
+                        AuR,
+                    >),
+                ),
+            ),
+    );
+}
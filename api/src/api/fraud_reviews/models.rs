@@ -0,0 +1,47 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Serialize, Copy, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum FraudReviewStatusRest {
+    Pending,
+    Approved,
+    Rejected,
+}
+
+#[cfg_attr(test, derive(Deserialize, PartialEq, Debug))]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FraudReviewRest {
+    pub id: i32,
+    pub transaction_id: i32,
+    pub customer_id: i32,
+    pub reasons: Vec<String>,
+    pub review_status: FraudReviewStatusRest,
+    pub reviewer_id: Option<i32>,
+    pub review_note: Option<String>,
+    pub created_at: String,
+    pub reviewed_at: Option<String>,
+}
+
+#[cfg_attr(test, derive(Deserialize))]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FraudReviewsRest {
+    pub fraud_reviews: Vec<FraudReviewRest>,
+}
+
+#[cfg_attr(test, derive(Serialize))]
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct FraudReviewDecisionRest {
+    pub approve: bool,
+    pub note: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FindFraudReviewQueryRest {
+    // pending only if not given
+    pub review_status: Option<FraudReviewStatusRest>,
+    pub customer_id: Option<i32>,
+}
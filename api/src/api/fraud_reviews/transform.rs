@@ -0,0 +1,49 @@
+use crate::models::fraud_review::{FraudReview, FraudReviewStatus};
+use crate::util::string_opt_from_naive_dt_opt;
+
+use super::models::{FraudReviewRest, FraudReviewStatusRest, FraudReviewsRest};
+
+impl From<FraudReviewStatus> for FraudReviewStatusRest {
+    fn from(status: FraudReviewStatus) -> Self {
+        match status {
+            FraudReviewStatus::Pending => FraudReviewStatusRest::Pending,
+            FraudReviewStatus::Approved => FraudReviewStatusRest::Approved,
+            FraudReviewStatus::Rejected => FraudReviewStatusRest::Rejected,
+        }
+    }
+}
+
+impl From<FraudReviewStatusRest> for FraudReviewStatus {
+    fn from(status: FraudReviewStatusRest) -> Self {
+        match status {
+            FraudReviewStatusRest::Pending => FraudReviewStatus::Pending,
+            FraudReviewStatusRest::Approved => FraudReviewStatus::Approved,
+            FraudReviewStatusRest::Rejected => FraudReviewStatus::Rejected,
+        }
+    }
+}
+
+impl From<&FraudReview> for FraudReviewRest {
+    fn from(review: &FraudReview) -> Self {
+        Self {
+            id: review.id,
+            transaction_id: review.transaction_id,
+            customer_id: review.customer_id,
+            // written by us as a json array, anything else is treated as no reasons
+            reasons: serde_json::from_str(&review.reasons).unwrap_or_default(),
+            review_status: review.review_status.into(),
+            reviewer_id: review.reviewer_id,
+            review_note: review.review_note.clone(),
+            created_at: review.created_at.to_string(),
+            reviewed_at: string_opt_from_naive_dt_opt(review.reviewed_at),
+        }
+    }
+}
+
+impl From<Vec<FraudReview>> for FraudReviewsRest {
+    fn from(reviews: Vec<FraudReview>) -> Self {
+        Self {
+            fraud_reviews: reviews.iter().map(FraudReviewRest::from).collect(),
+        }
+    }
+}
@@ -0,0 +1,1736 @@
+use actix_web::http::header::ContentType;
+use actix_web::web::{Data, Path, Query};
+use actix_web::{web, HttpRequest, HttpResponse};
+
+use super::models::{
+    FindLoanApplicationQueryRest, LoanApplicationDecisionRest, LoanApplicationRest,
+    LoanApplicationsRest, LoanInterestChargesRest, LoanRepaymentCollectionRest,
+    LoanRepaymentCollectionsRest, LoanRest, LoanScheduleRest, LoansRest, NewLoanApplicationRest,
+    NewLoanOffsetRest, NewLoanRepaymentRest, NewLoanRest,
+};
+use super::util::get_customer_loan;
+use crate::api::accounts::util::{authorise_holder, get_random_account_number};
+use crate::api::audit::util::{new_audit_entry, record_audit, snapshot};
+use crate::api::automation::models::EndOfDayQueryRest;
+use crate::api::error::ApiError;
+use crate::api::loans::models::LoanInterestChargeRest;
+use crate::api::overdrafts::util::audit_overdraft_charges;
+use crate::api::transactions::models::TransactionRest;
+use crate::error::RepoError;
+use crate::models::account::{Account, AccountStatus, AccountType};
+use crate::models::account_holder::{AccountHolder, AccountPermission, FindAccountHolderQuery};
+use crate::models::audit::{ActorType, AuditAction, AuditEntity, AuditEntry, NewAuditEntry};
+use crate::models::loan::{
+    FindLoanApplicationQuery, FindLoanInterestQuery, FindLoanQuery, LoanApplication,
+    LoanApplicationDecision, LoanApplicationStatus, LoanDetail, LoanInterestCharge,
+    LoanInterestRun, LoanOffset, LoanRepaymentCollection, LoanRepaymentRun, NewLoan,
+    NewLoanApplication, NewLoanOffset, NewLoanRepayment,
+};
+use crate::models::overdraft::OverdraftCharge;
+use crate::models::transaction::Transaction;
+use crate::traits::{RepoCreate, RepoDeleteById, RepoFind, RepoGetById, RepoUpdate};
+
+const MAX_NOTE_LEN: usize = 500;
+
+pub async fn find_loans<LoR>(
+    loans_repo: Data<LoR>,
+    path: Path<i32>,
+) -> Result<HttpResponse, actix_web::Error>
+where
+    LoR: RepoFind<LoanDetail, FindLoanQuery>,
+{
+    let customer_id = path.into_inner();
+
+    println!("Trying to get loans for customer {}", customer_id);
+
+    let loans = web::block(move || loans_repo.find(FindLoanQuery { customer_id }))
+        .await
+        .map_err(|_| ApiError::InternalError)?
+        .map_err(|_| ApiError::InternalError)?;
+
+    // enforced query on customer_id so should always be fine, but safe > sorry
+    for loan in loans.iter() {
+        if loan.loan.customer_id != customer_id {
+            return Err(ApiError::BadRequest.into());
+        }
+    }
+
+    Ok(HttpResponse::Ok()
+        .insert_header(ContentType::json())
+        .json(web::Json::<LoansRest>(loans.into())))
+}
+
+// an everyday or savings account the customer holds, money in it stops earning the loan interest
+pub async fn link_offset_account<AR, HR, LoR, AuR>(
+    accounts_repo: Data<AR>,
+    holders_repo: Data<HR>,
+    loans_repo: Data<LoR>,
+    audit_repo: Data<AuR>,
+    req: HttpRequest,
+    path: Path<(i32, i32)>,
+    payload: web::Json<NewLoanOffsetRest>,
+) -> Result<HttpResponse, actix_web::Error>
+where
+    AR: RepoGetById<Account>,
+    HR: RepoFind<AccountHolder, FindAccountHolderQuery>,
+    LoR: RepoGetById<LoanDetail> + RepoCreate<LoanOffset, NewLoanOffset>,
+    AuR: RepoCreate<AuditEntry, NewAuditEntry>,
+{
+    let (customer_id, loan_id) = path.into_inner();
+    let account_id = payload.account_id;
+
+    let before = get_customer_loan(loans_repo.clone(), customer_id, loan_id).await?;
+
+    println!(
+        "Trying to link account {} as an offset to loan {} for customer {}",
+        account_id, loan_id, customer_id
+    );
+
+    let after = web::block(move || {
+        let account = accounts_repo
+            .get_by_id(account_id)
+            .map_err(|err| match err {
+                RepoError::NotFound => ApiError::BadRequest,
+                _ => ApiError::InternalError,
+            })?;
+
+        authorise_holder(
+            holders_repo.as_ref(),
+            account.id,
+            customer_id,
+            AccountPermission::Transact,
+        )?;
+
+        if !matches!(
+            account.account_type,
+            AccountType::Transaction | AccountType::Savings
+        ) || account.account_status != AccountStatus::Active
+        {
+            return Err(ApiError::BadRequest);
+        }
+
+        loans_repo
+            .create(NewLoanOffset {
+                loan_id,
+                account_id,
+                customer_id,
+            })
+            .map_err(|err| match err {
+                RepoError::Conflict => ApiError::Conflict,
+                _ => ApiError::InternalError,
+            })?;
+
+        loans_repo
+            .get_by_id(loan_id)
+            .map_err(|_| ApiError::InternalError)
+    })
+    .await
+    .map_err(|_| ApiError::InternalError)??;
+
+    let loan_rest: LoanRest = (&after).into();
+
+    let mut audit_entry = new_audit_entry(
+        &req,
+        ActorType::Customer,
+        customer_id,
+        AuditAction::LinkOffsetAccount,
+        AuditEntity::Loan,
+        Some(loan_id),
+    );
+    audit_entry.before_snapshot = snapshot(&LoanRest::from(&before));
+    audit_entry.after_snapshot = snapshot(&loan_rest);
+    record_audit(audit_repo, audit_entry).await;
+
+    Ok(HttpResponse::Created()
+        .insert_header(ContentType::json())
+        .json(web::Json(loan_rest)))
+}
+
+pub async fn unlink_offset_account<LoR, AuR>(
+    loans_repo: Data<LoR>,
+    audit_repo: Data<AuR>,
+    req: HttpRequest,
+    path: Path<(i32, i32, i32)>,
+) -> Result<HttpResponse, actix_web::Error>
+where
+    LoR: RepoGetById<LoanDetail> + RepoDeleteById<LoanOffset>,
+    AuR: RepoCreate<AuditEntry, NewAuditEntry>,
+{
+    let (customer_id, loan_id, offset_id) = path.into_inner();
+
+    let before = get_customer_loan(loans_repo.clone(), customer_id, loan_id).await?;
+
+    if !before
+        .offsets
+        .iter()
+        .any(|offset| offset.offset.id == offset_id && offset.offset.customer_id == customer_id)
+    {
+        return Err(ApiError::NotFound.into());
+    }
+
+    println!(
+        "Trying to unlink offset {} from loan {} for customer {}",
+        offset_id, loan_id, customer_id
+    );
+
+    let after = web::block(move || {
+        loans_repo.delete_by_id(offset_id)?;
+        loans_repo.get_by_id(loan_id)
+    })
+    .await
+    .map_err(|_| ApiError::InternalError)?
+    .map_err(|_| ApiError::InternalError)?;
+
+    let loan_rest: LoanRest = (&after).into();
+
+    let mut audit_entry = new_audit_entry(
+        &req,
+        ActorType::Customer,
+        customer_id,
+        AuditAction::UnlinkOffsetAccount,
+        AuditEntity::Loan,
+        Some(loan_id),
+    );
+    audit_entry.before_snapshot = snapshot(&LoanRest::from(&before));
+    audit_entry.after_snapshot = snapshot(&loan_rest);
+    record_audit(audit_repo, audit_entry).await;
+
+    Ok(HttpResponse::Ok()
+        .insert_header(ContentType::json())
+        .json(web::Json(loan_rest)))
+}
+
+// an internal transfer from one of the customer's accounts into the loan, no more than is owed.
+// paying more than has fallen due brings the rest of the schedule down
+pub async fn repay_loan<LoR, AuR>(
+    loans_repo: Data<LoR>,
+    audit_repo: Data<AuR>,
+    req: HttpRequest,
+    path: Path<(i32, i32)>,
+    payload: web::Json<NewLoanRepaymentRest>,
+) -> Result<HttpResponse, actix_web::Error>
+where
+    LoR: RepoGetById<LoanDetail> + RepoCreate<Transaction, NewLoanRepayment>,
+    AuR: RepoCreate<AuditEntry, NewAuditEntry>,
+{
+    let (customer_id, loan_id) = path.into_inner();
+
+    if payload.amount_cents <= 0 {
+        return Err(ApiError::BadRequest.into());
+    }
+
+    get_customer_loan(loans_repo.clone(), customer_id, loan_id).await?;
+
+    let repayment = NewLoanRepayment {
+        loan_id,
+        customer_id,
+        from_account_id: payload.from_account_id,
+        amount_cents: payload.amount_cents,
+    };
+
+    println!(
+        "Trying to repay {} cents of loan {} for customer {}",
+        repayment.amount_cents, loan_id, customer_id
+    );
+
+    let transaction = web::block(move || loans_repo.create(repayment))
+        .await
+        .map_err(|_| ApiError::InternalError)?
+        .map_err(|err| match err {
+            RepoError::NotFound => ApiError::BadRequest,
+            RepoError::Conflict => ApiError::Conflict,
+            _ => ApiError::InternalError,
+        })?;
+
+    let transaction_rest: TransactionRest = (&transaction).into();
+
+    let mut audit_entry = new_audit_entry(
+        &req,
+        ActorType::Customer,
+        customer_id,
+        AuditAction::RepayLoan,
+        AuditEntity::Transaction,
+        Some(transaction.id),
+    );
+    audit_entry.after_snapshot = snapshot(&transaction_rest);
+    record_audit(audit_repo, audit_entry).await;
+
+    Ok(HttpResponse::Created()
+        .insert_header(ContentType::json())
+        .json(web::Json(transaction_rest)))
+}
+
+// every installment, paid or not, with where the loan stands today
+pub async fn get_loan_schedule<LoR>(
+    loans_repo: Data<LoR>,
+    path: Path<(i32, i32)>,
+) -> Result<HttpResponse, actix_web::Error>
+where
+    LoR: RepoGetById<LoanDetail>,
+{
+    let (customer_id, loan_id) = path.into_inner();
+
+    let loan = get_customer_loan(loans_repo, customer_id, loan_id).await?;
+
+    Ok(HttpResponse::Ok()
+        .insert_header(ContentType::json())
+        .json(web::Json::<LoanScheduleRest>((&loan).into())))
+}
+
+pub async fn find_loan_interest<LoR>(
+    loans_repo: Data<LoR>,
+    path: Path<(i32, i32)>,
+) -> Result<HttpResponse, actix_web::Error>
+where
+    LoR: RepoFind<LoanInterestCharge, FindLoanInterestQuery>,
+{
+    let (customer_id, loan_id) = path.into_inner();
+
+    println!(
+        "Trying to get interest charged on loan {} for customer {}",
+        loan_id, customer_id
+    );
+
+    let charge_query = FindLoanInterestQuery {
+        customer_id,
+        loan_id,
+    };
+
+    let charges = web::block(move || loans_repo.find(charge_query))
+        .await
+        .map_err(|_| ApiError::InternalError)?
+        .map_err(|_| ApiError::InternalError)?;
+
+    // enforced query on customer_id so should always be fine, but safe > sorry
+    for charge in charges.iter() {
+        if charge.customer_id != customer_id {
+            return Err(ApiError::BadRequest.into());
+        }
+    }
+
+    Ok(HttpResponse::Ok()
+        .insert_header(ContentType::json())
+        .json(web::Json::<LoanInterestChargesRest>(charges.into())))
+}
+
+// sets up a loan the customer already owes on, e.g. one moved over from another bank
+pub async fn open_loan<LoR, AuR>(
+    loans_repo: Data<LoR>,
+    audit_repo: Data<AuR>,
+    req: HttpRequest,
+    path: Path<i32>,
+    payload: web::Json<NewLoanRest>,
+) -> Result<HttpResponse, actix_web::Error>
+where
+    LoR: RepoCreate<LoanDetail, NewLoan>,
+    AuR: RepoCreate<AuditEntry, NewAuditEntry>,
+{
+    let staff_id = path.into_inner();
+
+    let payload = payload.into_inner();
+    let new_loan = NewLoan {
+        customer_id: payload.customer_id,
+        account_name: payload.account_name,
+        // completely random for now, same as any other account
+        account_number: get_random_account_number(),
+        owed_cents: payload.owed_cents,
+        interest_rate_bps: payload.interest_rate_bps,
+    };
+
+    if !new_loan.is_valid() {
+        return Err(ApiError::BadRequest.into());
+    }
+
+    println!(
+        "Staff {} trying to open a loan for customer {}",
+        staff_id, new_loan.customer_id
+    );
+
+    let loan = web::block(move || loans_repo.create(new_loan))
+        .await
+        .map_err(|_| ApiError::InternalError)?
+        .map_err(|_| ApiError::InternalError)?;
+
+    let loan_rest: LoanRest = (&loan).into();
+
+    let mut audit_entry = new_audit_entry(
+        &req,
+        ActorType::Staff,
+        staff_id,
+        AuditAction::OpenLoan,
+        AuditEntity::Loan,
+        Some(loan.loan.id),
+    );
+    audit_entry.after_snapshot = snapshot(&loan_rest);
+    record_audit(audit_repo, audit_entry).await;
+
+    Ok(HttpResponse::Created()
+        .insert_header(ContentType::json())
+        .json(web::Json(loan_rest)))
+}
+
+// called by whatever schedules the bank's end of day. safe to call again for the same day, each
+// loan is only charged once per day
+pub async fn run_loan_interest<LoR, AuR>(
+    loans_repo: Data<LoR>,
+    audit_repo: Data<AuR>,
+    req: HttpRequest,
+    path: Path<i32>,
+    query: Query<EndOfDayQueryRest>,
+) -> Result<HttpResponse, actix_web::Error>
+where
+    LoR: RepoCreate<Vec<LoanInterestCharge>, LoanInterestRun>,
+    AuR: RepoCreate<AuditEntry, NewAuditEntry>,
+{
+    let staff_id = path.into_inner();
+
+    let day = match &query.date {
+        Some(date) => {
+            chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d").map_err(|_| ApiError::BadRequest)?
+        }
+        None => chrono::Utc::now().date_naive(),
+    };
+
+    println!(
+        "Staff {} trying to charge loan interest for {}",
+        staff_id, day
+    );
+
+    let charges = web::block(move || loans_repo.create(LoanInterestRun { day }))
+        .await
+        .map_err(|_| ApiError::InternalError)?
+        .map_err(|_| ApiError::InternalError)?;
+
+    // interest is the system acting, on the loan it went on
+    for charge in charges.iter() {
+        let mut audit_entry = new_audit_entry(
+            &req,
+            ActorType::System,
+            charge.loan_id,
+            AuditAction::ChargeLoanInterest,
+            AuditEntity::Transaction,
+            Some(charge.transaction_id),
+        );
+        audit_entry.after_snapshot = snapshot(&LoanInterestChargeRest::from(charge));
+        record_audit(audit_repo.clone(), audit_entry).await;
+    }
+
+    Ok(HttpResponse::Ok()
+        .insert_header(ContentType::json())
+        .json(web::Json::<LoanInterestChargesRest>(charges.into())))
+}
+
+// called by whatever schedules the bank's end of day. safe to call again for the same day, only
+// what's still unpaid is taken. can't be run ahead of time
+pub async fn run_loan_repayments<LoR, AuR>(
+    loans_repo: Data<LoR>,
+    audit_repo: Data<AuR>,
+    req: HttpRequest,
+    path: Path<i32>,
+    query: Query<EndOfDayQueryRest>,
+) -> Result<HttpResponse, actix_web::Error>
+where
+    LoR: RepoCreate<Vec<LoanRepaymentCollection>, LoanRepaymentRun>,
+    AuR: RepoCreate<AuditEntry, NewAuditEntry>,
+{
+    let staff_id = path.into_inner();
+
+    let today = chrono::Utc::now().date_naive();
+    let day = match &query.date {
+        Some(date) => {
+            chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d").map_err(|_| ApiError::BadRequest)?
+        }
+        None => today,
+    };
+
+    if day > today {
+        return Err(ApiError::BadRequest.into());
+    }
+
+    println!(
+        "Staff {} trying to collect loan repayments due by {}",
+        staff_id, day
+    );
+
+    let collections = web::block(move || loans_repo.create(LoanRepaymentRun { day }))
+        .await
+        .map_err(|_| ApiError::InternalError)?
+        .map_err(|_| ApiError::InternalError)?;
+
+    // collecting is the system acting, on the loan it was due on. missed ones moved no money
+    for collection in collections.iter() {
+        let Some(transaction) = &collection.transaction else {
+            continue;
+        };
+
+        let mut audit_entry = new_audit_entry(
+            &req,
+            ActorType::System,
+            collection.loan_id,
+            AuditAction::CollectLoanRepayment,
+            AuditEntity::Transaction,
+            Some(transaction.id),
+        );
+        audit_entry.after_snapshot = snapshot(&LoanRepaymentCollectionRest::from(collection));
+        record_audit(audit_repo.clone(), audit_entry).await;
+    }
+
+    let dishonour_fees: Vec<OverdraftCharge> = collections
+        .iter()
+        .filter_map(|collection| collection.dishonour_fee.clone())
+        .collect();
+    audit_overdraft_charges(audit_repo, &req, &dishonour_fees).await;
+
+    Ok(HttpResponse::Ok()
+        .insert_header(ContentType::json())
+        .json(web::Json::<LoanRepaymentCollectionsRest>(
+            collections.into(),
+        )))
+}
+
+// the principal is paid into the disbursement account if it's approved, and repayments come out of
+// it after that
+pub async fn apply_for_loan<AR, HR, LAR, AuR>(
+    accounts_repo: Data<AR>,
+    holders_repo: Data<HR>,
+    applications_repo: Data<LAR>,
+    audit_repo: Data<AuR>,
+    req: HttpRequest,
+    path: Path<i32>,
+    payload: web::Json<NewLoanApplicationRest>,
+) -> Result<HttpResponse, actix_web::Error>
+where
+    AR: RepoGetById<Account>,
+    HR: RepoFind<AccountHolder, FindAccountHolderQuery>,
+    LAR: RepoCreate<LoanApplication, NewLoanApplication>,
+    AuR: RepoCreate<AuditEntry, NewAuditEntry>,
+{
+    let customer_id = path.into_inner();
+
+    let payload = payload.into_inner();
+    let new_application = NewLoanApplication {
+        customer_id,
+        principal_cents: payload.principal_cents,
+        term_months: payload.term_months,
+        repayment_type: payload.repayment_type.into(),
+        disbursement_account_id: payload.disbursement_account_id,
+        account_name: payload.account_name,
+    };
+
+    if !new_application.is_valid() {
+        return Err(ApiError::BadRequest.into());
+    }
+
+    println!(
+        "Trying to apply for a {} cent loan for customer {}",
+        new_application.principal_cents, customer_id
+    );
+
+    let application = web::block(move || {
+        let account = accounts_repo
+            .get_by_id(new_application.disbursement_account_id)
+            .map_err(|err| match err {
+                RepoError::NotFound => ApiError::BadRequest,
+                _ => ApiError::InternalError,
+            })?;
+
+        authorise_holder(
+            holders_repo.as_ref(),
+            account.id,
+            customer_id,
+            AccountPermission::Transact,
+        )?;
+
+        if !matches!(
+            account.account_type,
+            AccountType::Transaction | AccountType::Savings
+        ) || account.account_status != AccountStatus::Active
+        {
+            return Err(ApiError::BadRequest);
+        }
+
+        applications_repo
+            .create(new_application)
+            .map_err(|_| ApiError::InternalError)
+    })
+    .await
+    .map_err(|_| ApiError::InternalError)??;
+
+    let application_rest: LoanApplicationRest = (&application).into();
+
+    let mut audit_entry = new_audit_entry(
+        &req,
+        ActorType::Customer,
+        customer_id,
+        AuditAction::ApplyForLoan,
+        AuditEntity::LoanApplication,
+        Some(application.id),
+    );
+    audit_entry.after_snapshot = snapshot(&application_rest);
+    record_audit(audit_repo, audit_entry).await;
+
+    Ok(HttpResponse::Created()
+        .insert_header(ContentType::json())
+        .json(web::Json(application_rest)))
+}
+
+pub async fn find_loan_applications<LAR>(
+    applications_repo: Data<LAR>,
+    path: Path<i32>,
+) -> Result<HttpResponse, actix_web::Error>
+where
+    LAR: RepoFind<LoanApplication, FindLoanApplicationQuery>,
+{
+    let customer_id = path.into_inner();
+
+    println!(
+        "Trying to get loan applications for customer {}",
+        customer_id
+    );
+
+    let applications = web::block(move || {
+        applications_repo.find(FindLoanApplicationQuery {
+            customer_id: Some(customer_id),
+            application_status: None,
+        })
+    })
+    .await
+    .map_err(|_| ApiError::InternalError)?
+    .map_err(|_| ApiError::InternalError)?;
+
+    // enforced query on customer_id so should always be fine, but safe > sorry
+    for application in applications.iter() {
+        if application.customer_id != customer_id {
+            return Err(ApiError::BadRequest.into());
+        }
+    }
+
+    Ok(HttpResponse::Ok()
+        .insert_header(ContentType::json())
+        .json(web::Json::<LoanApplicationsRest>(applications.into())))
+}
+
+pub async fn staff_find_loan_applications<LAR>(
+    applications_repo: Data<LAR>,
+    path: Path<i32>,
+    query: Query<FindLoanApplicationQueryRest>,
+) -> Result<HttpResponse, actix_web::Error>
+where
+    LAR: RepoFind<LoanApplication, FindLoanApplicationQuery>,
+{
+    let staff_id = path.into_inner();
+
+    let query = FindLoanApplicationQuery {
+        customer_id: query.customer_id,
+        application_status: Some(
+            query
+                .application_status
+                .map(LoanApplicationStatus::from)
+                .unwrap_or(LoanApplicationStatus::Pending),
+        ),
+    };
+
+    println!("Trying to get loan applications for staff {}", staff_id);
+
+    let applications = web::block(move || applications_repo.find(query))
+        .await
+        .map_err(|_| ApiError::InternalError)?
+        .map_err(|_| ApiError::InternalError)?;
+
+    Ok(HttpResponse::Ok()
+        .insert_header(ContentType::json())
+        .json(web::Json::<LoanApplicationsRest>(applications.into())))
+}
+
+// approving writes the loan at the given rate and pays it out straight away
+pub async fn staff_decide_loan_application<LAR, AuR>(
+    applications_repo: Data<LAR>,
+    audit_repo: Data<AuR>,
+    req: HttpRequest,
+    path: Path<(i32, i32)>,
+    payload: web::Json<LoanApplicationDecisionRest>,
+) -> Result<HttpResponse, actix_web::Error>
+where
+    LAR: RepoGetById<LoanApplication> + RepoUpdate<LoanApplication, LoanApplicationDecision>,
+    AuR: RepoCreate<AuditEntry, NewAuditEntry>,
+{
+    let (staff_id, application_id) = path.into_inner();
+    let payload = payload.into_inner();
+
+    if payload
+        .note
+        .as_ref()
+        .is_some_and(|note| note.len() > MAX_NOTE_LEN)
+    {
+        return Err(ApiError::BadRequest.into());
+    }
+
+    let decision = LoanApplicationDecision {
+        approve: payload.approve,
+        reviewer_id: staff_id,
+        interest_rate_bps: payload.interest_rate_bps,
+        // completely random for now, same as any other account
+        account_number: get_random_account_number(),
+        note: payload.note,
+    };
+
+    if !decision.is_valid() {
+        return Err(ApiError::BadRequest.into());
+    }
+
+    println!(
+        "Trying to {} loan application {} for staff {}",
+        if decision.approve {
+            "approve"
+        } else {
+            "decline"
+        },
+        application_id,
+        staff_id
+    );
+
+    let (before, after) = web::block(move || {
+        let before = applications_repo.get_by_id(application_id)?;
+
+        if before.application_status != LoanApplicationStatus::Pending {
+            return Err(RepoError::Conflict);
+        }
+
+        let after = applications_repo.update(application_id, decision)?;
+        Ok((before, after))
+    })
+    .await
+    .map_err(|_| ApiError::InternalError)?
+    .map_err(|err: RepoError| match err {
+        RepoError::NotFound => ApiError::NotFound,
+        RepoError::Conflict => ApiError::Conflict,
+        _ => ApiError::InternalError,
+    })?;
+
+    let after_rest: LoanApplicationRest = (&after).into();
+
+    let mut audit_entry = new_audit_entry(
+        &req,
+        ActorType::Staff,
+        staff_id,
+        AuditAction::DecideLoanApplication,
+        AuditEntity::LoanApplication,
+        Some(application_id),
+    );
+    audit_entry.before_snapshot = snapshot(&LoanApplicationRest::from(&before));
+    audit_entry.after_snapshot = snapshot(&after_rest);
+    record_audit(audit_repo, audit_entry).await;
+
+    Ok(HttpResponse::Ok()
+        .insert_header(ContentType::json())
+        .json(web::Json(after_rest)))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::audit::util::appended;
+    use crate::fixtures::{account, dt, holders_repo};
+    use actix_web::{
+        body::to_bytes,
+        http::StatusCode,
+        test,
+        web::{Data, Json, Query},
+    };
+    use chrono::{Duration, NaiveDate};
+    use mockall::{mock, predicate::eq};
+
+    use crate::{
+        api::{
+            automation::models::EndOfDayQueryRest,
+            error::ApiError,
+            loans::{
+                handlers::{
+                    apply_for_loan, find_loans, get_loan_schedule, link_offset_account, open_loan,
+                    repay_loan, run_loan_interest, run_loan_repayments,
+                    staff_decide_loan_application,
+                },
+                models::{
+                    LoanApplicationDecisionRest, LoanApplicationRest, LoanApplicationStatusRest,
+                    LoanInterestChargesRest, LoanRepaymentCollectionsRest, LoanRepaymentTypeRest,
+                    LoanRest, LoanScheduleRest, LoansRest, NewLoanApplicationRest,
+                    NewLoanOffsetRest, NewLoanRepaymentRest, NewLoanRest,
+                },
+            },
+            transactions::models::{TransactionRest, TransactionStatusRest},
+        },
+        error::RepoError,
+        models::{
+            account::{Account, AccountType},
+            account_holder::HolderRole,
+            audit::{ActorType, AuditEntry, NewAuditEntry},
+            loan::{
+                FindLoanQuery, Loan, LoanApplication, LoanApplicationDecision,
+                LoanApplicationStatus, LoanDetail, LoanInterestCharge, LoanInterestRun, LoanOffset,
+                LoanOffsetAccount, LoanRepaymentCollection, LoanRepaymentRun, LoanRepaymentType,
+                LoanScheduleEntry, NewLoan, NewLoanApplication, NewLoanOffset, NewLoanRepayment,
+            },
+            overdraft::{OverdraftCharge, OverdraftChargeKind, DISHONOUR_FEE_CENTS},
+            transaction::{Transaction, TransactionStatus, TransactionType},
+        },
+        traits::{
+            MockRepoCreate, MockRepoFind, MockRepoGetById, RepoCreate, RepoGetById, RepoUpdate,
+        },
+    };
+
+    mock! {
+        pub OffsetLoR { }
+        impl RepoGetById<LoanDetail> for OffsetLoR {
+            fn get_by_id(&self, id: i32) -> Result<LoanDetail, RepoError>;
+        }
+        impl RepoCreate<LoanOffset, NewLoanOffset> for OffsetLoR {
+            fn create(&self, new: NewLoanOffset) -> Result<LoanOffset, RepoError>;
+        }
+    }
+
+    mock! {
+        pub DecideLAR { }
+        impl RepoGetById<LoanApplication> for DecideLAR {
+            fn get_by_id(&self, id: i32) -> Result<LoanApplication, RepoError>;
+        }
+        impl RepoUpdate<LoanApplication, LoanApplicationDecision> for DecideLAR {
+            fn update(
+                &self,
+                id: i32,
+                decision: LoanApplicationDecision,
+            ) -> Result<LoanApplication, RepoError>;
+        }
+    }
+
+    mock! {
+        pub RepayLoR { }
+        impl RepoGetById<LoanDetail> for RepayLoR {
+            fn get_by_id(&self, id: i32) -> Result<LoanDetail, RepoError>;
+        }
+        impl RepoCreate<Transaction, NewLoanRepayment> for RepayLoR {
+            fn create(&self, new: NewLoanRepayment) -> Result<Transaction, RepoError>;
+        }
+    }
+
+    fn loan(offset_balances: &[i64]) -> LoanDetail {
+        LoanDetail {
+            loan: Loan {
+                id: 3,
+                account_id: 60,
+                customer_id: 5,
+                interest_rate_bps: 650,
+                created_at: dt(),
+                principal_cents: None,
+                term_months: None,
+                repayment_type: None,
+                first_repayment_date: None,
+                repayment_account_id: None,
+            },
+            account: account(60, 5, AccountType::Loan, -50_000_000),
+            offsets: offset_balances
+                .iter()
+                .enumerate()
+                .map(|(i, balance_cents)| LoanOffsetAccount {
+                    offset: LoanOffset {
+                        id: i as i32 + 1,
+                        loan_id: 3,
+                        account_id: 61 + i as i32,
+                        customer_id: 5,
+                        created_at: dt(),
+                    },
+                    account: account(61 + i as i32, 5, AccountType::Transaction, *balance_cents),
+                })
+                .collect(),
+            schedule: vec![],
+        }
+    }
+
+    // a year's loan a quarter of the way in, written with installments relative to today
+    fn scheduled_loan(first_due_days_ago: i64, paid: &[i64]) -> LoanDetail {
+        let today = chrono::Utc::now().date_naive();
+        let first_due = today - Duration::days(first_due_days_ago);
+
+        let mut detail = loan(&[]);
+        detail.loan.principal_cents = Some(1_000_000);
+        detail.loan.term_months = Some(12);
+        detail.loan.repayment_type = Some(LoanRepaymentType::PrincipalAndInterest);
+        detail.loan.first_repayment_date = Some(first_due);
+        detail.loan.repayment_account_id = Some(61);
+        detail.schedule = (1..=12)
+            .map(|installment| LoanScheduleEntry {
+                id: installment,
+                loan_id: 3,
+                installment,
+                due_date: first_due + Duration::days(30 * (installment as i64 - 1)),
+                amount_cents: 88_849,
+                principal_cents: 78_849,
+                interest_cents: 10_000,
+                paid_cents: paid.get(installment as usize - 1).copied().unwrap_or(0),
+            })
+            .collect();
+        detail
+    }
+
+    fn repayment(id: i32, amount_cents: i64) -> Transaction {
+        Transaction {
+            id,
+            customer_id: 5,
+            transaction_type: TransactionType::Internal,
+            from_us: true,
+            amount_cents,
+            from_number: format!("{:09}", 61),
+            from_bsb: "123456".to_string(),
+            from_name: None,
+            to_number: format!("{:09}", 60),
+            to_bsb: "123456".to_string(),
+            to_name: None,
+            available_balance_cents: 0,
+            date_start: dt(),
+            date_end: Some(dt()),
+            transaction_status: TransactionStatus::Success,
+            reversal_of: None,
+            reversed_amount_cents: 0,
+            reversal_reason: None,
+            description: Some("Loan repayment".to_string()),
+            payer_reference: None,
+            payee_reference: Some("Loan repayment".to_string()),
+            counterpart_of: None,
+            running_balance_cents: Some(-50_000_000 + amount_cents),
+        }
+    }
+
+    fn application(application_status: LoanApplicationStatus) -> LoanApplication {
+        LoanApplication {
+            id: 8,
+            customer_id: 5,
+            principal_cents: 1_000_000,
+            term_months: 12,
+            repayment_type: LoanRepaymentType::PrincipalAndInterest,
+            disbursement_account_id: 61,
+            account_name: Some("Car loan".to_string()),
+            application_status,
+            interest_rate_bps: None,
+            loan_id: None,
+            reviewer_id: None,
+            review_note: None,
+            created_at: dt(),
+            reviewed_at: None,
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_find_loans_shows_effective_balance() {
+        let mut mock_loans_repo = MockRepoFind::<LoanDetail, FindLoanQuery>::new();
+        mock_loans_repo
+            .expect_find()
+            .with(eq(FindLoanQuery { customer_id: 5 }))
+            .times(1)
+            .returning(|_| Ok(vec![loan(&[3_000_000, 2_000_000, -5_000])]));
+
+        let res = find_loans(Data::new(mock_loans_repo), 5.into())
+            .await
+            .unwrap();
+
+        assert_eq!(StatusCode::OK, res.status());
+
+        let actual: LoansRest =
+            serde_json::from_slice(&to_bytes(res.into_body()).await.unwrap()).unwrap();
+        assert_eq!(1, actual.loans.len());
+        assert_eq!(50_000_000, actual.loans[0].owed_cents);
+        // an overdrawn offset doesn't count against the loan
+        assert_eq!(5_000_000, actual.loans[0].offset_cents);
+        assert_eq!(45_000_000, actual.loans[0].effective_owed_cents);
+        assert_eq!(3, actual.loans[0].offsets.len());
+    }
+
+    #[actix_web::test]
+    async fn test_link_offset_account_success() {
+        let mut mock_accounts_repo = MockRepoGetById::<Account>::new();
+        mock_accounts_repo
+            .expect_get_by_id()
+            .with(eq(61))
+            .times(1)
+            .returning(|id| Ok(account(id, 5, AccountType::Savings, 3_000_000)));
+
+        let mut mock_loans_repo = MockOffsetLoR::new();
+        let mut linked = false;
+        mock_loans_repo
+            .expect_get_by_id()
+            .with(eq(3))
+            .times(2)
+            .returning(move |_| {
+                let detail = if linked {
+                    loan(&[3_000_000])
+                } else {
+                    loan(&[])
+                };
+                linked = true;
+                Ok(detail)
+            });
+        mock_loans_repo
+            .expect_create()
+            .with(eq(NewLoanOffset {
+                loan_id: 3,
+                account_id: 61,
+                customer_id: 5,
+            }))
+            .times(1)
+            .returning(|new| {
+                Ok(LoanOffset {
+                    id: 1,
+                    loan_id: new.loan_id,
+                    account_id: new.account_id,
+                    customer_id: new.customer_id,
+                    created_at: dt(),
+                })
+            });
+
+        let mut mock_audit_repo = MockRepoCreate::<AuditEntry, NewAuditEntry>::new();
+        mock_audit_repo
+            .expect_create()
+            .withf(|entry| {
+                entry.actor_type == ActorType::Customer
+                    && entry.action == "link_offset_account"
+                    && entry.entity_type == "loan"
+                    && entry.entity_id == Some(3)
+                    && entry.before_snapshot.is_some()
+                    && entry.after_snapshot.is_some()
+            })
+            .times(1)
+            .returning(|entry| Ok(appended(entry)));
+
+        let res = link_offset_account(
+            Data::new(mock_accounts_repo),
+            Data::new(holders_repo([(61, 5, HolderRole::Owner)])),
+            Data::new(mock_loans_repo),
+            Data::new(mock_audit_repo),
+            test::TestRequest::post().to_http_request(),
+            (5, 3).into(),
+            Json(NewLoanOffsetRest { account_id: 61 }),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(StatusCode::CREATED, res.status());
+
+        let actual: LoanRest =
+            serde_json::from_slice(&to_bytes(res.into_body()).await.unwrap()).unwrap();
+        assert_eq!(47_000_000, actual.effective_owed_cents);
+    }
+
+    #[actix_web::test]
+    async fn test_link_offset_account_other_customers_account_error() {
+        let mut mock_accounts_repo = MockRepoGetById::<Account>::new();
+        mock_accounts_repo
+            .expect_get_by_id()
+            .times(1)
+            .returning(|id| {
+                let mut other = account(id, 5, AccountType::Savings, 3_000_000);
+                other.customer_id = 6;
+                Ok(other)
+            });
+
+        let mut mock_loans_repo = MockOffsetLoR::new();
+        mock_loans_repo
+            .expect_get_by_id()
+            .times(1)
+            .returning(|_| Ok(loan(&[])));
+        mock_loans_repo.expect_create().times(0);
+
+        let res = link_offset_account(
+            Data::new(mock_accounts_repo),
+            Data::new(holders_repo([(70, 6, HolderRole::Owner)])),
+            Data::new(mock_loans_repo),
+            Data::new(MockRepoCreate::<AuditEntry, NewAuditEntry>::new()),
+            test::TestRequest::post().to_http_request(),
+            (5, 3).into(),
+            Json(NewLoanOffsetRest { account_id: 70 }),
+        )
+        .await;
+
+        assert!(res.is_err_and(|e| { e.to_string() == ApiError::Unauthorized.to_string() }));
+    }
+
+    #[actix_web::test]
+    async fn test_link_offset_account_loan_account_error() {
+        let mut mock_accounts_repo = MockRepoGetById::<Account>::new();
+        mock_accounts_repo
+            .expect_get_by_id()
+            .times(1)
+            .returning(|id| Ok(account(id, 5, AccountType::Loan, -100_000)));
+
+        let mut mock_loans_repo = MockOffsetLoR::new();
+        mock_loans_repo
+            .expect_get_by_id()
+            .times(1)
+            .returning(|_| Ok(loan(&[])));
+
+        let res = link_offset_account(
+            Data::new(mock_accounts_repo),
+            Data::new(holders_repo([(70, 5, HolderRole::Owner)])),
+            Data::new(mock_loans_repo),
+            Data::new(MockRepoCreate::<AuditEntry, NewAuditEntry>::new()),
+            test::TestRequest::post().to_http_request(),
+            (5, 3).into(),
+            Json(NewLoanOffsetRest { account_id: 70 }),
+        )
+        .await;
+
+        assert!(res.is_err_and(|e| { e.to_string() == ApiError::BadRequest.to_string() }));
+    }
+
+    #[actix_web::test]
+    async fn test_repay_loan_audited() {
+        let mut mock_loans_repo = MockRepayLoR::new();
+        mock_loans_repo
+            .expect_get_by_id()
+            .times(1)
+            .returning(|_| Ok(loan(&[])));
+        mock_loans_repo
+            .expect_create()
+            .times(1)
+            .returning(|new_repayment| Ok(repayment(95, new_repayment.amount_cents)));
+
+        let mut mock_audit_repo = MockRepoCreate::<AuditEntry, NewAuditEntry>::new();
+        mock_audit_repo
+            .expect_create()
+            .withf(|entry| {
+                entry.actor_type == ActorType::Customer
+                    && entry.actor_id == 5
+                    && entry.action == "repay_loan"
+                    && entry.entity_type == "transaction"
+                    && entry.entity_id == Some(95)
+                    && entry.after_snapshot.is_some()
+            })
+            .times(1)
+            .returning(|entry| Ok(appended(entry)));
+
+        let res = repay_loan(
+            Data::new(mock_loans_repo),
+            Data::new(mock_audit_repo),
+            test::TestRequest::post().to_http_request(),
+            (5, 3).into(),
+            Json(NewLoanRepaymentRest {
+                from_account_id: 61,
+                amount_cents: 88_849,
+            }),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(StatusCode::CREATED, res.status());
+    }
+
+    #[actix_web::test]
+    async fn test_repay_loan_over_threshold_pending_approval() {
+        let mut mock_loans_repo = MockRepayLoR::new();
+        mock_loans_repo
+            .expect_get_by_id()
+            .times(1)
+            .returning(|_| Ok(loan(&[])));
+        // account 61 needs approval over $10,000, so the repayment waits rather than settling
+        mock_loans_repo
+            .expect_create()
+            .with(eq(NewLoanRepayment {
+                loan_id: 3,
+                customer_id: 5,
+                from_account_id: 61,
+                amount_cents: 2_000_000,
+            }))
+            .times(1)
+            .returning(|new_repayment| {
+                let mut pending = repayment(95, new_repayment.amount_cents);
+                pending.transaction_status = TransactionStatus::PendingApproval;
+                pending.date_end = None;
+                pending.running_balance_cents = None;
+                Ok(pending)
+            });
+
+        let mut mock_audit_repo = MockRepoCreate::<AuditEntry, NewAuditEntry>::new();
+        mock_audit_repo
+            .expect_create()
+            .withf(|entry| {
+                entry.action == "repay_loan"
+                    && entry.entity_id == Some(95)
+                    && entry
+                        .after_snapshot
+                        .as_ref()
+                        .is_some_and(|after| after.contains("\"pendingApproval\""))
+            })
+            .times(1)
+            .returning(|entry| Ok(appended(entry)));
+
+        let res = repay_loan(
+            Data::new(mock_loans_repo),
+            Data::new(mock_audit_repo),
+            test::TestRequest::post().to_http_request(),
+            (5, 3).into(),
+            Json(NewLoanRepaymentRest {
+                from_account_id: 61,
+                amount_cents: 2_000_000,
+            }),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(StatusCode::CREATED, res.status());
+
+        let actual: TransactionRest =
+            serde_json::from_slice(&to_bytes(res.into_body()).await.unwrap()).unwrap();
+        assert_eq!(
+            TransactionStatusRest::PendingApproval,
+            actual.transaction_status
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_repay_loan_more_than_owed_error() {
+        let mut mock_loans_repo = MockRepayLoR::new();
+        mock_loans_repo
+            .expect_get_by_id()
+            .times(1)
+            .returning(|_| Ok(loan(&[])));
+        mock_loans_repo
+            .expect_create()
+            .with(eq(NewLoanRepayment {
+                loan_id: 3,
+                customer_id: 5,
+                from_account_id: 61,
+                amount_cents: 60_000_000,
+            }))
+            .times(1)
+            .returning(|_| Err(RepoError::Conflict));
+
+        let res = repay_loan(
+            Data::new(mock_loans_repo),
+            Data::new(MockRepoCreate::<AuditEntry, NewAuditEntry>::new()),
+            test::TestRequest::post().to_http_request(),
+            (5, 3).into(),
+            Json(NewLoanRepaymentRest {
+                from_account_id: 61,
+                amount_cents: 60_000_000,
+            }),
+        )
+        .await;
+
+        assert!(res.is_err_and(|e| { e.to_string() == ApiError::Conflict.to_string() }));
+    }
+
+    #[actix_web::test]
+    async fn test_repay_other_customers_loan_error() {
+        let mut mock_loans_repo = MockRepayLoR::new();
+        mock_loans_repo
+            .expect_get_by_id()
+            .times(1)
+            .returning(|_| Ok(loan(&[])));
+        mock_loans_repo.expect_create().times(0);
+
+        let res = repay_loan(
+            Data::new(mock_loans_repo),
+            Data::new(MockRepoCreate::<AuditEntry, NewAuditEntry>::new()),
+            test::TestRequest::post().to_http_request(),
+            (6, 3).into(),
+            Json(NewLoanRepaymentRest {
+                from_account_id: 61,
+                amount_cents: 100,
+            }),
+        )
+        .await;
+
+        assert!(res.is_err_and(|e| { e.to_string() == ApiError::Unauthorized.to_string() }));
+    }
+
+    #[actix_web::test]
+    async fn test_open_loan_invalid_rate_error() {
+        let mut mock_loans_repo = MockRepoCreate::<LoanDetail, NewLoan>::new();
+        mock_loans_repo.expect_create().times(0);
+
+        let res = open_loan(
+            Data::new(mock_loans_repo),
+            Data::new(MockRepoCreate::<AuditEntry, NewAuditEntry>::new()),
+            test::TestRequest::post().to_http_request(),
+            2.into(),
+            Json(NewLoanRest {
+                customer_id: 5,
+                account_name: Some("Home loan".to_string()),
+                owed_cents: 50_000_000,
+                interest_rate_bps: 0,
+            }),
+        )
+        .await;
+
+        assert!(res.is_err_and(|e| { e.to_string() == ApiError::BadRequest.to_string() }));
+    }
+
+    #[actix_web::test]
+    async fn test_run_loan_interest_audits_each_charge() {
+        let day = NaiveDate::from_ymd_opt(2023, 9, 7).unwrap();
+
+        let mut mock_loans_repo = MockRepoCreate::<Vec<LoanInterestCharge>, LoanInterestRun>::new();
+        mock_loans_repo
+            .expect_create()
+            .with(eq(LoanInterestRun { day }))
+            .times(1)
+            .returning(move |_| {
+                Ok(vec![LoanInterestCharge {
+                    id: 1,
+                    loan_id: 3,
+                    customer_id: 5,
+                    charge_date: day,
+                    owed_cents: 50_000_000,
+                    offset_cents: 5_000_000,
+                    amount_cents: 8014,
+                    transaction_id: 90,
+                    created_at: dt(),
+                }])
+            });
+
+        let mut mock_audit_repo = MockRepoCreate::<AuditEntry, NewAuditEntry>::new();
+        mock_audit_repo
+            .expect_create()
+            .withf(|entry| {
+                entry.actor_type == ActorType::System
+                    && entry.action == "charge_loan_interest"
+                    && entry.entity_id == Some(90)
+            })
+            .times(1)
+            .returning(|entry| Ok(appended(entry)));
+
+        let res = run_loan_interest(
+            Data::new(mock_loans_repo),
+            Data::new(mock_audit_repo),
+            test::TestRequest::post().to_http_request(),
+            2.into(),
+            Query(EndOfDayQueryRest {
+                date: Some("2023-09-07".to_string()),
+            }),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(StatusCode::OK, res.status());
+
+        let actual: LoanInterestChargesRest =
+            serde_json::from_slice(&to_bytes(res.into_body()).await.unwrap()).unwrap();
+        assert_eq!(8014, actual.charges[0].amount_cents);
+    }
+
+    #[actix_web::test]
+    async fn test_get_loan_schedule_shows_arrears() {
+        // the first installment was paid, the second's 10 days overdue and only partly paid
+        let mut mock_loans_repo = MockRepoGetById::<LoanDetail>::new();
+        mock_loans_repo
+            .expect_get_by_id()
+            .with(eq(3))
+            .times(1)
+            .returning(|_| Ok(scheduled_loan(40, &[88_849, 20_000])));
+
+        let res = get_loan_schedule(Data::new(mock_loans_repo), (5, 3).into())
+            .await
+            .unwrap();
+
+        assert_eq!(StatusCode::OK, res.status());
+
+        let actual: LoanScheduleRest =
+            serde_json::from_slice(&to_bytes(res.into_body()).await.unwrap()).unwrap();
+        assert_eq!(12, actual.installments.len());
+        assert_eq!(68_849, actual.arrears_cents);
+        assert_eq!(10, actual.days_in_arrears);
+        assert_eq!(0, actual.installments[0].outstanding_cents);
+    }
+
+    #[actix_web::test]
+    async fn test_find_loans_shows_next_repayment() {
+        let mut mock_loans_repo = MockRepoFind::<LoanDetail, FindLoanQuery>::new();
+        mock_loans_repo
+            .expect_find()
+            .times(1)
+            .returning(|_| Ok(vec![scheduled_loan(5, &[88_849])]));
+
+        let res = find_loans(Data::new(mock_loans_repo), 5.into())
+            .await
+            .unwrap();
+
+        let actual: LoansRest =
+            serde_json::from_slice(&to_bytes(res.into_body()).await.unwrap()).unwrap();
+        assert_eq!(0, actual.loans[0].arrears_cents);
+        assert_eq!(
+            Some(2),
+            actual.loans[0]
+                .next_repayment
+                .as_ref()
+                .map(|next| next.installment)
+        );
+        assert_eq!(
+            Some(LoanRepaymentTypeRest::PrincipalAndInterest),
+            actual.loans[0].repayment_type
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_apply_for_loan_success() {
+        let mut mock_accounts_repo = MockRepoGetById::<Account>::new();
+        mock_accounts_repo
+            .expect_get_by_id()
+            .with(eq(61))
+            .times(1)
+            .returning(|id| Ok(account(id, 5, AccountType::Transaction, 10_000)));
+
+        let mut mock_applications_repo =
+            MockRepoCreate::<LoanApplication, NewLoanApplication>::new();
+        mock_applications_repo
+            .expect_create()
+            .with(eq(NewLoanApplication {
+                customer_id: 5,
+                principal_cents: 1_000_000,
+                term_months: 12,
+                repayment_type: LoanRepaymentType::PrincipalAndInterest,
+                disbursement_account_id: 61,
+                account_name: Some("Car loan".to_string()),
+            }))
+            .times(1)
+            .returning(|_| Ok(application(LoanApplicationStatus::Pending)));
+
+        let mut mock_audit_repo = MockRepoCreate::<AuditEntry, NewAuditEntry>::new();
+        mock_audit_repo
+            .expect_create()
+            .withf(|entry| {
+                entry.actor_type == ActorType::Customer
+                    && entry.action == "apply_for_loan"
+                    && entry.entity_type == "loan_application"
+                    && entry.entity_id == Some(8)
+            })
+            .times(1)
+            .returning(|entry| Ok(appended(entry)));
+
+        let res = apply_for_loan(
+            Data::new(mock_accounts_repo),
+            Data::new(holders_repo([(61, 5, HolderRole::Owner)])),
+            Data::new(mock_applications_repo),
+            Data::new(mock_audit_repo),
+            test::TestRequest::post().to_http_request(),
+            5.into(),
+            Json(NewLoanApplicationRest {
+                principal_cents: 1_000_000,
+                term_months: 12,
+                repayment_type: LoanRepaymentTypeRest::PrincipalAndInterest,
+                disbursement_account_id: 61,
+                account_name: Some("Car loan".to_string()),
+            }),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(StatusCode::CREATED, res.status());
+
+        let actual: LoanApplicationRest =
+            serde_json::from_slice(&to_bytes(res.into_body()).await.unwrap()).unwrap();
+        assert_eq!(
+            LoanApplicationStatusRest::Pending,
+            actual.application_status
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_apply_for_loan_into_other_customers_account_error() {
+        let mut mock_accounts_repo = MockRepoGetById::<Account>::new();
+        mock_accounts_repo
+            .expect_get_by_id()
+            .times(1)
+            .returning(|id| {
+                let mut other = account(id, 5, AccountType::Transaction, 10_000);
+                other.customer_id = 6;
+                Ok(other)
+            });
+
+        let mut mock_applications_repo =
+            MockRepoCreate::<LoanApplication, NewLoanApplication>::new();
+        mock_applications_repo.expect_create().times(0);
+
+        let res = apply_for_loan(
+            Data::new(mock_accounts_repo),
+            Data::new(holders_repo([(70, 6, HolderRole::Owner)])),
+            Data::new(mock_applications_repo),
+            Data::new(MockRepoCreate::<AuditEntry, NewAuditEntry>::new()),
+            test::TestRequest::post().to_http_request(),
+            5.into(),
+            Json(NewLoanApplicationRest {
+                principal_cents: 1_000_000,
+                term_months: 12,
+                repayment_type: LoanRepaymentTypeRest::InterestOnly,
+                disbursement_account_id: 70,
+                account_name: None,
+            }),
+        )
+        .await;
+
+        assert!(res.is_err_and(|e| { e.to_string() == ApiError::Unauthorized.to_string() }));
+    }
+
+    #[actix_web::test]
+    async fn test_apply_for_loan_into_joint_account_success() {
+        let mut mock_accounts_repo = MockRepoGetById::<Account>::new();
+        mock_accounts_repo
+            .expect_get_by_id()
+            .times(1)
+            .returning(|id| {
+                let mut joint = account(id, 5, AccountType::Transaction, 10_000);
+                joint.customer_id = 6;
+                Ok(joint)
+            });
+
+        let mut mock_applications_repo =
+            MockRepoCreate::<LoanApplication, NewLoanApplication>::new();
+        mock_applications_repo
+            .expect_create()
+            .times(1)
+            .returning(|_| Ok(application(LoanApplicationStatus::Pending)));
+
+        let mut mock_audit_repo = MockRepoCreate::<AuditEntry, NewAuditEntry>::new();
+        mock_audit_repo
+            .expect_create()
+            .times(1)
+            .returning(|entry| Ok(appended(entry)));
+
+        // customer 6's account that 5 can sign on
+        let res = apply_for_loan(
+            Data::new(mock_accounts_repo),
+            Data::new(holders_repo([
+                (70, 6, HolderRole::Owner),
+                (70, 5, HolderRole::Signatory),
+            ])),
+            Data::new(mock_applications_repo),
+            Data::new(mock_audit_repo),
+            test::TestRequest::post().to_http_request(),
+            5.into(),
+            Json(NewLoanApplicationRest {
+                principal_cents: 1_000_000,
+                term_months: 12,
+                repayment_type: LoanRepaymentTypeRest::InterestOnly,
+                disbursement_account_id: 70,
+                account_name: None,
+            }),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(StatusCode::CREATED, res.status());
+    }
+
+    #[actix_web::test]
+    async fn test_approve_loan_application_without_rate_error() {
+        let mut mock_applications_repo = MockDecideLAR::new();
+        mock_applications_repo.expect_get_by_id().times(0);
+        mock_applications_repo.expect_update().times(0);
+
+        let res = staff_decide_loan_application(
+            Data::new(mock_applications_repo),
+            Data::new(MockRepoCreate::<AuditEntry, NewAuditEntry>::new()),
+            test::TestRequest::post().to_http_request(),
+            (2, 8).into(),
+            Json(LoanApplicationDecisionRest {
+                approve: true,
+                interest_rate_bps: None,
+                note: None,
+            }),
+        )
+        .await;
+
+        assert!(res.is_err_and(|e| { e.to_string() == ApiError::BadRequest.to_string() }));
+    }
+
+    #[actix_web::test]
+    async fn test_approve_loan_application_success() {
+        let mut mock_applications_repo = MockDecideLAR::new();
+        mock_applications_repo
+            .expect_get_by_id()
+            .with(eq(8))
+            .times(1)
+            .returning(|_| Ok(application(LoanApplicationStatus::Pending)));
+        mock_applications_repo
+            .expect_update()
+            .withf(|id, decision| {
+                *id == 8
+                    && decision.approve
+                    && decision.reviewer_id == 2
+                    && decision.interest_rate_bps == Some(1_200)
+                    && decision.account_number.len() == 9
+            })
+            .times(1)
+            .returning(|_, decision| {
+                let mut approved = application(LoanApplicationStatus::Approved);
+                approved.interest_rate_bps = decision.interest_rate_bps;
+                approved.loan_id = Some(3);
+                approved.reviewer_id = Some(decision.reviewer_id);
+                approved.reviewed_at = Some(dt());
+                Ok(approved)
+            });
+
+        let mut mock_audit_repo = MockRepoCreate::<AuditEntry, NewAuditEntry>::new();
+        mock_audit_repo
+            .expect_create()
+            .withf(|entry| {
+                entry.actor_type == ActorType::Staff
+                    && entry.action == "decide_loan_application"
+                    && entry.before_snapshot.is_some()
+                    && entry.after_snapshot.is_some()
+            })
+            .times(1)
+            .returning(|entry| Ok(appended(entry)));
+
+        let res = staff_decide_loan_application(
+            Data::new(mock_applications_repo),
+            Data::new(mock_audit_repo),
+            test::TestRequest::post().to_http_request(),
+            (2, 8).into(),
+            Json(LoanApplicationDecisionRest {
+                approve: true,
+                interest_rate_bps: Some(1_200),
+                note: None,
+            }),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(StatusCode::OK, res.status());
+
+        let actual: LoanApplicationRest =
+            serde_json::from_slice(&to_bytes(res.into_body()).await.unwrap()).unwrap();
+        assert_eq!(Some(3), actual.loan_id);
+        assert_eq!(
+            LoanApplicationStatusRest::Approved,
+            actual.application_status
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_decide_loan_application_twice_error() {
+        let mut mock_applications_repo = MockDecideLAR::new();
+        mock_applications_repo
+            .expect_get_by_id()
+            .times(1)
+            .returning(|_| Ok(application(LoanApplicationStatus::Declined)));
+        mock_applications_repo.expect_update().times(0);
+
+        let res = staff_decide_loan_application(
+            Data::new(mock_applications_repo),
+            Data::new(MockRepoCreate::<AuditEntry, NewAuditEntry>::new()),
+            test::TestRequest::post().to_http_request(),
+            (2, 8).into(),
+            Json(LoanApplicationDecisionRest {
+                approve: false,
+                interest_rate_bps: None,
+                note: Some("Income doesn't support it".to_string()),
+            }),
+        )
+        .await;
+
+        assert!(res.is_err_and(|e| { e.to_string() == ApiError::Conflict.to_string() }));
+    }
+
+    #[actix_web::test]
+    async fn test_run_loan_repayments_audits_collected_and_dishonoured() {
+        let day = NaiveDate::from_ymd_opt(2023, 10, 11).unwrap();
+
+        let mut mock_loans_repo =
+            MockRepoCreate::<Vec<LoanRepaymentCollection>, LoanRepaymentRun>::new();
+        mock_loans_repo
+            .expect_create()
+            .with(eq(LoanRepaymentRun { day }))
+            .times(1)
+            .returning(move |_| {
+                Ok(vec![
+                    LoanRepaymentCollection {
+                        loan_id: 3,
+                        customer_id: 5,
+                        due_cents: 88_849,
+                        transaction: Some(repayment(95, 88_849)),
+                        dishonour_fee: None,
+                    },
+                    LoanRepaymentCollection {
+                        loan_id: 4,
+                        customer_id: 7,
+                        due_cents: 10_000,
+                        transaction: None,
+                        dishonour_fee: Some(OverdraftCharge {
+                            id: 3,
+                            account_id: 12,
+                            customer_id: 7,
+                            kind: OverdraftChargeKind::DishonourFee,
+                            charge_date: day,
+                            amount_cents: DISHONOUR_FEE_CENTS,
+                            transaction_id: 96,
+                            created_at: chrono::Utc::now().naive_utc(),
+                        }),
+                    },
+                ])
+            });
+
+        let mut mock_audit_repo = MockRepoCreate::<AuditEntry, NewAuditEntry>::new();
+        mock_audit_repo
+            .expect_create()
+            .withf(|entry| {
+                entry.actor_type == ActorType::System
+                    && entry.action == "collect_loan_repayment"
+                    && entry.entity_id == Some(95)
+            })
+            .times(1)
+            .returning(|entry| Ok(appended(entry)));
+        mock_audit_repo
+            .expect_create()
+            .withf(|entry| {
+                entry.actor_type == ActorType::System
+                    && entry.action == "charge_dishonour_fee"
+                    && entry.actor_id == 12
+                    && entry.entity_id == Some(96)
+            })
+            .times(1)
+            .returning(|entry| Ok(appended(entry)));
+
+        let res = run_loan_repayments(
+            Data::new(mock_loans_repo),
+            Data::new(mock_audit_repo),
+            test::TestRequest::post().to_http_request(),
+            2.into(),
+            Query(EndOfDayQueryRest {
+                date: Some("2023-10-11".to_string()),
+            }),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(StatusCode::OK, res.status());
+
+        let actual: LoanRepaymentCollectionsRest =
+            serde_json::from_slice(&to_bytes(res.into_body()).await.unwrap()).unwrap();
+        assert_eq!(2, actual.collections.len());
+        assert!(actual.collections[0].collected);
+        assert!(!actual.collections[1].collected);
+        assert_eq!(None, actual.collections[0].dishonour_fee_cents);
+        assert_eq!(
+            Some(DISHONOUR_FEE_CENTS),
+            actual.collections[1].dishonour_fee_cents
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_run_loan_repayments_ahead_of_time_error() {
+        let mut mock_loans_repo =
+            MockRepoCreate::<Vec<LoanRepaymentCollection>, LoanRepaymentRun>::new();
+        mock_loans_repo.expect_create().times(0);
+
+        let tomorrow = chrono::Utc::now().date_naive() + Duration::days(1);
+
+        let res = run_loan_repayments(
+            Data::new(mock_loans_repo),
+            Data::new(MockRepoCreate::<AuditEntry, NewAuditEntry>::new()),
+            test::TestRequest::post().to_http_request(),
+            2.into(),
+            Query(EndOfDayQueryRest {
+                date: Some(tomorrow.to_string()),
+            }),
+        )
+        .await;
+
+        assert!(res.is_err_and(|e| { e.to_string() == ApiError::BadRequest.to_string() }));
+    }
+}
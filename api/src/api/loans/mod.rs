@@ -0,0 +1,102 @@
+pub mod handlers;
+pub mod models;
+pub mod transform;
+pub mod util;
+
+use actix_web::web;
+
+use crate::{
+    api::loans,
+    models::{
+        account::Account,
+        account_holder::{AccountHolder, FindAccountHolderQuery},
+        audit::{AuditEntry, NewAuditEntry},
+        loan::{
+            FindLoanApplicationQuery, FindLoanInterestQuery, FindLoanQuery, LoanApplication,
+            LoanApplicationDecision, LoanDetail, LoanInterestCharge, LoanInterestRun, LoanOffset,
+            LoanRepaymentCollection, LoanRepaymentRun, NewLoan, NewLoanApplication, NewLoanOffset,
+            NewLoanRepayment,
+        },
+        transaction::Transaction,
+    },
+    traits::{RepoCreate, RepoDeleteById, RepoFind, RepoGetById, RepoUpdate},
+};
+
+pub fn configure_loans_api<LoR, LAR, AR, HR, AuR>(cfg: &mut web::ServiceConfig)
+where
+    LoR: RepoCreate<LoanDetail, NewLoan>
+        + RepoFind<LoanDetail, FindLoanQuery>
+        + RepoGetById<LoanDetail>
+        + RepoCreate<LoanOffset, NewLoanOffset>
+        + RepoDeleteById<LoanOffset>
+        + RepoCreate<Transaction, NewLoanRepayment>
+        + RepoFind<LoanInterestCharge, FindLoanInterestQuery>
+        + RepoCreate<Vec<LoanInterestCharge>, LoanInterestRun>
+        + RepoCreate<Vec<LoanRepaymentCollection>, LoanRepaymentRun>,
+    LAR: RepoCreate<LoanApplication, NewLoanApplication>
+        + RepoFind<LoanApplication, FindLoanApplicationQuery>
+        + RepoGetById<LoanApplication>
+        + RepoUpdate<LoanApplication, LoanApplicationDecision>,
+    AR: RepoGetById<Account>,
+    HR: RepoFind<AccountHolder, FindAccountHolderQuery>,
+    AuR: RepoCreate<AuditEntry, NewAuditEntry>,
+{
+    cfg.service(
+        web::scope("/api/customers/{customer_id}/loans")
+            .service(web::resource("").route(web::get().to(loans::handlers::find_loans::<LoR>)))
+            .service(
+                web::resource("/{loan_id}/offsets").route(
+                    web::post().to(loans::handlers::link_offset_account::<AR, HR, LoR, AuR>),
+                ),
+            )
+            .service(
+                web::resource("/{loan_id}/offsets/{offset_id}")
+                    .route(web::delete().to(loans::handlers::unlink_offset_account::<LoR, AuR>)),
+            )
+            .service(
+                web::resource("/{loan_id}/repayments")
+                    .route(web::post().to(loans::handlers::repay_loan::<LoR, AuR>)),
+            )
+            .service(
+                web::resource("/{loan_id}/schedule")
+                    .route(web::get().to(loans::handlers::get_loan_schedule::<LoR>)),
+            )
+            .service(
+                web::resource("/{loan_id}/interest")
+                    .route(web::get().to(loans::handlers::find_loan_interest::<LoR>)),
+            ),
+    )
+    .service(
+        web::scope("/api/customers/{customer_id}/loan-applications").service(
+            web::resource("")
+                .route(web::post().to(loans::handlers::apply_for_loan::<AR, HR, LAR, AuR>))
+                .route(web::get().to(loans::handlers::find_loan_applications::<LAR>)),
+        ),
+    )
+    .service(
+        web::scope("/api/staff/{staff_id}/loans")
+            .service(
+                web::resource("").route(web::post().to(loans::handlers::open_loan::<LoR, AuR>)),
+            )
+            .service(
+                web::resource("/end-of-day")
+                    .route(web::post().to(loans::handlers::run_loan_interest::<LoR, AuR>)),
+            )
+            .service(
+                web::resource("/repayment-run")
+                    .route(web::post().to(loans::handlers::run_loan_repayments::<LoR, AuR>)),
+            ),
+    )
+    .service(
+        web::scope("/api/staff/{staff_id}/loan-applications")
+            .service(
+                web::resource("")
+                    .route(web::get().to(loans::handlers::staff_find_loan_applications::<LAR>)),
+            )
+            .service(
+                web::resource("/{application_id}/decision").route(
+                    web::post().to(loans::handlers::staff_decide_loan_application::<LAR, AuR>),
+                ),
+            ),
+    );
+}
@@ -0,0 +1,208 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Serialize, Copy, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum LoanRepaymentTypeRest {
+    PrincipalAndInterest,
+    InterestOnly,
+}
+
+#[derive(Deserialize, Serialize, Copy, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum LoanApplicationStatusRest {
+    Pending,
+    Approved,
+    Declined,
+}
+
+#[cfg_attr(test, derive(Deserialize, PartialEq, Debug))]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LoanOffsetRest {
+    pub id: i32,
+    pub account_id: i32,
+    pub account_number: String,
+    pub account_name: Option<String>,
+    pub balance_cents: i64,
+    pub linked_at: String,
+}
+
+#[cfg_attr(test, derive(Deserialize, PartialEq, Debug))]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LoanRest {
+    pub id: i32,
+    pub customer_id: i32,
+    pub account_id: i32,
+    pub account_number: String,
+    pub bsb: String,
+    pub account_name: Option<String>,
+    pub interest_rate_bps: i32,
+    pub owed_cents: i64,
+    pub offset_cents: i64,
+    // what interest is charged on today, owed less offsets
+    pub effective_owed_cents: i64,
+    pub offsets: Vec<LoanOffsetRest>,
+    // only loans the bank wrote have terms and a schedule
+    pub principal_cents: Option<i64>,
+    pub term_months: Option<i32>,
+    pub repayment_type: Option<LoanRepaymentTypeRest>,
+    pub next_repayment: Option<LoanScheduleEntryRest>,
+    pub arrears_cents: i64,
+    pub days_in_arrears: i64,
+    pub created_at: String,
+}
+
+#[cfg_attr(test, derive(Deserialize, PartialEq, Debug))]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LoanScheduleEntryRest {
+    pub installment: i32,
+    pub due_date: String,
+    pub amount_cents: i64,
+    pub principal_cents: i64,
+    pub interest_cents: i64,
+    pub paid_cents: i64,
+    pub outstanding_cents: i64,
+}
+
+#[cfg_attr(test, derive(Deserialize))]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LoanScheduleRest {
+    pub loan_id: i32,
+    pub owed_cents: i64,
+    pub arrears_cents: i64,
+    pub days_in_arrears: i64,
+    pub installments: Vec<LoanScheduleEntryRest>,
+}
+
+#[cfg_attr(test, derive(Deserialize))]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LoansRest {
+    pub loans: Vec<LoanRest>,
+}
+
+#[cfg_attr(test, derive(Serialize))]
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct NewLoanRest {
+    pub customer_id: i32,
+    pub account_name: Option<String>,
+    pub owed_cents: i64,
+    pub interest_rate_bps: i32,
+}
+
+#[cfg_attr(test, derive(Serialize))]
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct NewLoanOffsetRest {
+    pub account_id: i32,
+}
+
+#[cfg_attr(test, derive(Serialize))]
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct NewLoanRepaymentRest {
+    pub from_account_id: i32,
+    pub amount_cents: i64,
+}
+
+#[cfg_attr(test, derive(Deserialize, PartialEq, Debug))]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LoanInterestChargeRest {
+    pub id: i32,
+    pub loan_id: i32,
+    pub customer_id: i32,
+    pub charge_date: String,
+    pub owed_cents: i64,
+    pub offset_cents: i64,
+    pub amount_cents: i64,
+    pub transaction_id: i32,
+    pub created_at: String,
+}
+
+#[cfg_attr(test, derive(Deserialize))]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LoanInterestChargesRest {
+    pub charges: Vec<LoanInterestChargeRest>,
+}
+
+#[cfg_attr(test, derive(Deserialize, PartialEq, Debug))]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LoanRepaymentCollectionRest {
+    pub loan_id: i32,
+    pub customer_id: i32,
+    pub due_cents: i64,
+    // false leaves the loan in arrears until a later run or the customer pays
+    pub collected: bool,
+    pub transaction_id: Option<i32>,
+    pub dishonour_fee_cents: Option<i64>,
+}
+
+#[cfg_attr(test, derive(Deserialize))]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LoanRepaymentCollectionsRest {
+    pub collections: Vec<LoanRepaymentCollectionRest>,
+}
+
+#[cfg_attr(test, derive(Serialize))]
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct NewLoanApplicationRest {
+    pub principal_cents: i64,
+    pub term_months: i32,
+    pub repayment_type: LoanRepaymentTypeRest,
+    pub disbursement_account_id: i32,
+    pub account_name: Option<String>,
+}
+
+#[cfg_attr(test, derive(Deserialize, PartialEq, Debug))]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LoanApplicationRest {
+    pub id: i32,
+    pub customer_id: i32,
+    pub principal_cents: i64,
+    pub term_months: i32,
+    pub repayment_type: LoanRepaymentTypeRest,
+    pub disbursement_account_id: i32,
+    pub account_name: Option<String>,
+    pub application_status: LoanApplicationStatusRest,
+    pub interest_rate_bps: Option<i32>,
+    pub loan_id: Option<i32>,
+    pub reviewer_id: Option<i32>,
+    pub review_note: Option<String>,
+    pub created_at: String,
+    pub reviewed_at: Option<String>,
+}
+
+#[cfg_attr(test, derive(Deserialize))]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LoanApplicationsRest {
+    pub applications: Vec<LoanApplicationRest>,
+}
+
+#[cfg_attr(test, derive(Serialize))]
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct LoanApplicationDecisionRest {
+    pub approve: bool,
+    // the rate the loan's written at, only for an approval
+    pub interest_rate_bps: Option<i32>,
+    pub note: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FindLoanApplicationQueryRest {
+    // pending only if not given
+    pub application_status: Option<LoanApplicationStatusRest>,
+    pub customer_id: Option<i32>,
+}
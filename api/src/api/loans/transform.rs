@@ -0,0 +1,211 @@
+use crate::models::loan::{
+    LoanApplication, LoanApplicationStatus, LoanDetail, LoanInterestCharge, LoanOffsetAccount,
+    LoanRepaymentCollection, LoanRepaymentType, LoanScheduleEntry,
+};
+use crate::util::string_opt_from_naive_dt_opt;
+
+use super::models::{
+    LoanApplicationRest, LoanApplicationStatusRest, LoanApplicationsRest, LoanInterestChargeRest,
+    LoanInterestChargesRest, LoanOffsetRest, LoanRepaymentCollectionRest,
+    LoanRepaymentCollectionsRest, LoanRepaymentTypeRest, LoanRest, LoanScheduleEntryRest,
+    LoanScheduleRest, LoansRest,
+};
+
+impl From<LoanRepaymentType> for LoanRepaymentTypeRest {
+    fn from(repayment_type: LoanRepaymentType) -> Self {
+        match repayment_type {
+            LoanRepaymentType::PrincipalAndInterest => LoanRepaymentTypeRest::PrincipalAndInterest,
+            LoanRepaymentType::InterestOnly => LoanRepaymentTypeRest::InterestOnly,
+        }
+    }
+}
+
+impl From<LoanRepaymentTypeRest> for LoanRepaymentType {
+    fn from(repayment_type: LoanRepaymentTypeRest) -> Self {
+        match repayment_type {
+            LoanRepaymentTypeRest::PrincipalAndInterest => LoanRepaymentType::PrincipalAndInterest,
+            LoanRepaymentTypeRest::InterestOnly => LoanRepaymentType::InterestOnly,
+        }
+    }
+}
+
+impl From<LoanApplicationStatus> for LoanApplicationStatusRest {
+    fn from(status: LoanApplicationStatus) -> Self {
+        match status {
+            LoanApplicationStatus::Pending => LoanApplicationStatusRest::Pending,
+            LoanApplicationStatus::Approved => LoanApplicationStatusRest::Approved,
+            LoanApplicationStatus::Declined => LoanApplicationStatusRest::Declined,
+        }
+    }
+}
+
+impl From<LoanApplicationStatusRest> for LoanApplicationStatus {
+    fn from(status: LoanApplicationStatusRest) -> Self {
+        match status {
+            LoanApplicationStatusRest::Pending => LoanApplicationStatus::Pending,
+            LoanApplicationStatusRest::Approved => LoanApplicationStatus::Approved,
+            LoanApplicationStatusRest::Declined => LoanApplicationStatus::Declined,
+        }
+    }
+}
+
+impl From<&LoanScheduleEntry> for LoanScheduleEntryRest {
+    fn from(entry: &LoanScheduleEntry) -> Self {
+        Self {
+            installment: entry.installment,
+            due_date: entry.due_date.to_string(),
+            amount_cents: entry.amount_cents,
+            principal_cents: entry.principal_cents,
+            interest_cents: entry.interest_cents,
+            paid_cents: entry.paid_cents,
+            outstanding_cents: entry.outstanding_cents(),
+        }
+    }
+}
+
+// arrears are as of today
+impl From<&LoanDetail> for LoanScheduleRest {
+    fn from(detail: &LoanDetail) -> Self {
+        let today = chrono::Utc::now().date_naive();
+        Self {
+            loan_id: detail.loan.id,
+            owed_cents: detail.owed_cents(),
+            arrears_cents: detail.arrears_cents(today),
+            days_in_arrears: detail.days_in_arrears(today),
+            installments: detail
+                .schedule
+                .iter()
+                .map(LoanScheduleEntryRest::from)
+                .collect(),
+        }
+    }
+}
+
+impl From<&LoanOffsetAccount> for LoanOffsetRest {
+    fn from(offset: &LoanOffsetAccount) -> Self {
+        Self {
+            id: offset.offset.id,
+            account_id: offset.offset.account_id,
+            account_number: offset.account.account_number.clone(),
+            account_name: offset.account.account_name.clone(),
+            balance_cents: offset.account.balance_cents,
+            linked_at: offset.offset.created_at.to_string(),
+        }
+    }
+}
+
+impl From<&LoanDetail> for LoanRest {
+    fn from(detail: &LoanDetail) -> Self {
+        let today = chrono::Utc::now().date_naive();
+        Self {
+            id: detail.loan.id,
+            customer_id: detail.loan.customer_id,
+            account_id: detail.account.id,
+            account_number: detail.account.account_number.clone(),
+            bsb: detail.account.bsb.clone(),
+            account_name: detail.account.account_name.clone(),
+            interest_rate_bps: detail.loan.interest_rate_bps,
+            owed_cents: detail.owed_cents(),
+            offset_cents: detail.offset_cents(),
+            effective_owed_cents: detail.effective_owed_cents(),
+            offsets: detail.offsets.iter().map(LoanOffsetRest::from).collect(),
+            principal_cents: detail.loan.principal_cents,
+            term_months: detail.loan.term_months,
+            repayment_type: detail.loan.repayment_type.map(LoanRepaymentTypeRest::from),
+            next_repayment: detail
+                .next_repayment(today)
+                .map(LoanScheduleEntryRest::from),
+            arrears_cents: detail.arrears_cents(today),
+            days_in_arrears: detail.days_in_arrears(today),
+            created_at: detail.loan.created_at.to_string(),
+        }
+    }
+}
+
+impl From<Vec<LoanDetail>> for LoansRest {
+    fn from(loans: Vec<LoanDetail>) -> Self {
+        Self {
+            loans: loans.iter().map(LoanRest::from).collect(),
+        }
+    }
+}
+
+impl From<&LoanInterestCharge> for LoanInterestChargeRest {
+    fn from(charge: &LoanInterestCharge) -> Self {
+        Self {
+            id: charge.id,
+            loan_id: charge.loan_id,
+            customer_id: charge.customer_id,
+            charge_date: charge.charge_date.to_string(),
+            owed_cents: charge.owed_cents,
+            offset_cents: charge.offset_cents,
+            amount_cents: charge.amount_cents,
+            transaction_id: charge.transaction_id,
+            created_at: charge.created_at.to_string(),
+        }
+    }
+}
+
+impl From<Vec<LoanInterestCharge>> for LoanInterestChargesRest {
+    fn from(charges: Vec<LoanInterestCharge>) -> Self {
+        Self {
+            charges: charges.iter().map(LoanInterestChargeRest::from).collect(),
+        }
+    }
+}
+
+impl From<&LoanRepaymentCollection> for LoanRepaymentCollectionRest {
+    fn from(collection: &LoanRepaymentCollection) -> Self {
+        Self {
+            loan_id: collection.loan_id,
+            customer_id: collection.customer_id,
+            due_cents: collection.due_cents,
+            collected: collection.transaction.is_some(),
+            transaction_id: collection.transaction.as_ref().map(|tr| tr.id),
+            dishonour_fee_cents: collection
+                .dishonour_fee
+                .as_ref()
+                .map(|fee| fee.amount_cents),
+        }
+    }
+}
+
+impl From<Vec<LoanRepaymentCollection>> for LoanRepaymentCollectionsRest {
+    fn from(collections: Vec<LoanRepaymentCollection>) -> Self {
+        Self {
+            collections: collections
+                .iter()
+                .map(LoanRepaymentCollectionRest::from)
+                .collect(),
+        }
+    }
+}
+
+impl From<&LoanApplication> for LoanApplicationRest {
+    fn from(application: &LoanApplication) -> Self {
+        Self {
+            id: application.id,
+            customer_id: application.customer_id,
+            principal_cents: application.principal_cents,
+            term_months: application.term_months,
+            repayment_type: application.repayment_type.into(),
+            disbursement_account_id: application.disbursement_account_id,
+            account_name: application.account_name.clone(),
+            application_status: application.application_status.into(),
+            interest_rate_bps: application.interest_rate_bps,
+            loan_id: application.loan_id,
+            reviewer_id: application.reviewer_id,
+            review_note: application.review_note.clone(),
+            created_at: application.created_at.to_string(),
+            reviewed_at: string_opt_from_naive_dt_opt(application.reviewed_at),
+        }
+    }
+}
+
+impl From<Vec<LoanApplication>> for LoanApplicationsRest {
+    fn from(applications: Vec<LoanApplication>) -> Self {
+        Self {
+            applications: applications.iter().map(LoanApplicationRest::from).collect(),
+        }
+    }
+}
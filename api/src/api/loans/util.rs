@@ -0,0 +1,34 @@
+use actix_web::{web, web::Data};
+
+use crate::{
+    api::error::ApiError, error::RepoError, models::loan::LoanDetail, traits::RepoGetById,
+};
+
+// the loan, as long as it's the customer's
+pub async fn get_customer_loan<LoR>(
+    loans_repo: Data<LoR>,
+    customer_id: i32,
+    loan_id: i32,
+) -> Result<LoanDetail, ApiError>
+where
+    LoR: RepoGetById<LoanDetail>,
+{
+    println!(
+        "Trying to find loan {} for customer {}",
+        loan_id, customer_id
+    );
+
+    let loan = web::block(move || loans_repo.get_by_id(loan_id))
+        .await
+        .map_err(|_| ApiError::InternalError)?
+        .map_err(|err| match err {
+            RepoError::NotFound => ApiError::NotFound,
+            _ => ApiError::InternalError,
+        })?;
+
+    if loan.loan.customer_id != customer_id {
+        return Err(ApiError::Unauthorized);
+    }
+
+    Ok(loan)
+}
@@ -1,3 +1,20 @@
 pub mod accounts;
+pub mod approvals;
+pub mod audit;
+pub mod automation;
+pub mod budgets;
+pub mod cdr;
+pub mod consents;
+pub mod disputes;
+pub mod error;
+pub mod fees;
+pub mod fraud_reviews;
+pub mod loans;
+pub mod oauth;
+pub mod overdrafts;
+pub mod payees;
+pub mod spending;
+pub mod step_up;
 pub mod transactions;
-pub mod error;
\ No newline at end of file
+pub mod transfer_limits;
+pub mod webhooks;
@@ -0,0 +1,66 @@
+use std::fmt;
+
+use actix_web::{
+    error,
+    http::{
+        header::{CacheControl, CacheDirective},
+        StatusCode,
+    },
+    HttpResponse,
+};
+use serde::Serialize;
+
+// rfc 6749 5.2, the token endpoint's errors are json with a code clients know what to do with
+#[derive(Debug, PartialEq)]
+pub enum TokenError {
+    InvalidRequest,
+    InvalidClient,
+    // bad, expired, used or revoked code or refresh token
+    InvalidGrant,
+    UnsupportedGrantType,
+    InvalidScope,
+    ServerError,
+}
+
+impl TokenError {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TokenError::InvalidRequest => "invalid_request",
+            TokenError::InvalidClient => "invalid_client",
+            TokenError::InvalidGrant => "invalid_grant",
+            TokenError::UnsupportedGrantType => "unsupported_grant_type",
+            TokenError::InvalidScope => "invalid_scope",
+            TokenError::ServerError => "server_error",
+        }
+    }
+}
+
+impl fmt::Display for TokenError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+#[cfg_attr(test, derive(serde::Deserialize, PartialEq, Debug))]
+#[derive(Serialize)]
+pub struct TokenErrorBody {
+    pub error: String,
+}
+
+impl error::ResponseError for TokenError {
+    fn error_response(&self) -> HttpResponse {
+        HttpResponse::build(self.status_code())
+            .insert_header(CacheControl(vec![CacheDirective::NoStore]))
+            .json(TokenErrorBody {
+                error: self.as_str().to_string(),
+            })
+    }
+
+    fn status_code(&self) -> StatusCode {
+        match *self {
+            TokenError::InvalidClient => StatusCode::UNAUTHORIZED,
+            TokenError::ServerError => StatusCode::INTERNAL_SERVER_ERROR,
+            _ => StatusCode::BAD_REQUEST,
+        }
+    }
+}
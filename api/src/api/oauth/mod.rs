@@ -0,0 +1,70 @@
+pub mod error;
+pub mod handlers;
+pub mod models;
+pub mod transform;
+pub mod util;
+
+use actix_web::web;
+
+use crate::{
+    api::oauth,
+    models::{
+        account::{Account, FindAccountQuery},
+        audit::{AuditEntry, NewAuditEntry},
+        oauth::{
+            CodeExchange, FindAuthorisationCodeQuery, FindOAuthClientQuery, FindOAuthTokenQuery,
+            NewOAuthAuthorisation, NewOAuthClient, OAuthAuthorisationCode, OAuthClient,
+            OAuthClientRevocation, OAuthGrant, OAuthGrantRevocation, OAuthToken,
+            OAuthTokenRevocation, TokenRefresh,
+        },
+    },
+    traits::{RepoCreate, RepoFind, RepoGetById, RepoUpdate},
+};
+
+pub fn configure_oauth_api<OR, AR, AuR>(cfg: &mut web::ServiceConfig)
+where
+    OR: RepoCreate<OAuthClient, NewOAuthClient>
+        + RepoFind<OAuthClient, FindOAuthClientQuery>
+        + RepoUpdate<OAuthClient, OAuthClientRevocation>
+        + RepoCreate<OAuthAuthorisationCode, NewOAuthAuthorisation>
+        + RepoFind<OAuthAuthorisationCode, FindAuthorisationCodeQuery>
+        + RepoGetById<OAuthGrant>
+        + RepoUpdate<OAuthGrant, OAuthGrantRevocation>
+        + RepoCreate<Vec<OAuthToken>, CodeExchange>
+        + RepoCreate<Vec<OAuthToken>, TokenRefresh>
+        + RepoFind<OAuthToken, FindOAuthTokenQuery>
+        + RepoUpdate<OAuthToken, OAuthTokenRevocation>,
+    AR: RepoFind<Account, FindAccountQuery>,
+    AuR: RepoCreate<AuditEntry, NewAuditEntry>,
+{
+    cfg.service(
+        web::scope("/oauth")
+            .service(
+                web::resource("/token").route(web::post().to(oauth::handlers::issue_token::<OR>)),
+            )
+            .service(
+                web::resource("/revoke").route(web::post().to(oauth::handlers::revoke_token::<OR>)),
+            ),
+    )
+    .service(
+        web::scope("/api/customers/{customer_id}/oauth-authorisations").service(
+            web::resource("").route(web::post().to(oauth::handlers::authorise_oauth_client::<
+                OR,
+                AR,
+                AuR,
+            >)),
+        ),
+    )
+    .service(
+        web::scope("/api/staff/{staff_id}/oauth-clients")
+            .service(
+                web::resource("")
+                    .route(web::get().to(oauth::handlers::find_oauth_clients::<OR>))
+                    .route(web::post().to(oauth::handlers::register_oauth_client::<OR, AuR>)),
+            )
+            .service(
+                web::resource("/{client_id}/revoke")
+                    .route(web::post().to(oauth::handlers::revoke_oauth_client::<OR, AuR>)),
+            ),
+    );
+}
@@ -0,0 +1,102 @@
+use serde::{Deserialize, Serialize};
+
+#[cfg_attr(test, derive(Serialize))]
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct NewOAuthClientRest {
+    pub client_name: String,
+    pub redirect_uri: String,
+    // space separated, the most the client can ask customers for
+    pub scope: String,
+    // gets a secret. public clients rely on pkce alone
+    pub confidential: bool,
+}
+
+#[cfg_attr(test, derive(Deserialize, PartialEq, Debug))]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OAuthClientRest {
+    pub id: i32,
+    pub client_id: String,
+    pub client_name: String,
+    pub redirect_uri: String,
+    pub scope: String,
+    pub confidential: bool,
+    pub created_by: i32,
+    pub created_at: String,
+    pub revoked_at: Option<String>,
+}
+
+// the secret is only ever shown here, when registering
+#[cfg_attr(test, derive(Deserialize, PartialEq, Debug))]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RegisteredOAuthClientRest {
+    pub client: OAuthClientRest,
+    pub client_secret: Option<String>,
+}
+
+// the customer saying yes on the consent screen to what the client's authorisation request asked.
+// nothing secret in it, so it's what gets audited
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct OAuthAuthorisationRequestRest {
+    pub client_id: String,
+    pub redirect_uri: String,
+    pub scope: String,
+    pub state: Option<String>,
+    pub code_challenge: String,
+    pub code_challenge_method: String,
+    // which of their accounts the customer picked to share
+    #[serde(default)]
+    pub account_ids: Vec<i32>,
+    // how long for, the most we allow if not given
+    pub sharing_duration_days: Option<i64>,
+}
+
+#[cfg_attr(test, derive(Deserialize, PartialEq, Debug))]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OAuthAuthorisationRest {
+    pub code: String,
+    pub state: Option<String>,
+    pub scope: String,
+    pub expires_at: String,
+    // where to send the customer back to, with the code and state on
+    pub redirect_to: String,
+}
+
+// the token and revocation endpoints speak oauth rather than our json, form encoded in and
+// snake case out
+#[cfg_attr(test, derive(Serialize))]
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct TokenRequestForm {
+    pub grant_type: Option<String>,
+    pub code: Option<String>,
+    pub redirect_uri: Option<String>,
+    pub code_verifier: Option<String>,
+    pub refresh_token: Option<String>,
+    pub scope: Option<String>,
+    pub client_id: Option<String>,
+    pub client_secret: Option<String>,
+}
+
+#[cfg_attr(test, derive(Deserialize, PartialEq, Debug))]
+#[derive(Serialize)]
+pub struct TokenResponse {
+    pub access_token: String,
+    pub token_type: String,
+    pub expires_in: i64,
+    pub refresh_token: String,
+    pub scope: String,
+}
+
+#[cfg_attr(test, derive(Serialize))]
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct RevocationForm {
+    pub token: Option<String>,
+    // we can tell from the token itself, so it's accepted and ignored
+    pub token_type_hint: Option<String>,
+    pub client_id: Option<String>,
+    pub client_secret: Option<String>,
+}
@@ -0,0 +1,20 @@
+use crate::models::oauth::OAuthClient;
+use crate::util::string_opt_from_naive_dt_opt;
+
+use super::models::OAuthClientRest;
+
+impl From<&OAuthClient> for OAuthClientRest {
+    fn from(client: &OAuthClient) -> Self {
+        OAuthClientRest {
+            id: client.id,
+            client_id: client.client_id.clone(),
+            client_name: client.client_name.clone(),
+            redirect_uri: client.redirect_uri.clone(),
+            scope: client.scope.clone(),
+            confidential: client.client_secret_hash.is_some(),
+            created_by: client.created_by,
+            created_at: client.created_at.to_string(),
+            revoked_at: string_opt_from_naive_dt_opt(client.revoked_at),
+        }
+    }
+}
@@ -0,0 +1,411 @@
+use actix_web::http::header::AUTHORIZATION;
+use actix_web::web::Data;
+use actix_web::{web, HttpRequest};
+use data_encoding::BASE64;
+
+use super::error::TokenError;
+use crate::api::error::ApiError;
+use crate::error::RepoError;
+use crate::models::consent::Consent;
+use crate::models::oauth::{
+    scope_includes, FindOAuthClientQuery, FindOAuthTokenQuery, OAuthClient, OAuthScope, OAuthToken,
+    OAuthTokenKind,
+};
+use crate::oauth::hash_token;
+use crate::traits::{RepoFind, RepoGetById};
+
+fn authorization_header(req: &HttpRequest, scheme: &str) -> Option<String> {
+    let header = req.headers().get(AUTHORIZATION)?.to_str().ok()?;
+    let (given, value) = header.split_once(' ')?;
+
+    given
+        .eq_ignore_ascii_case(scheme)
+        .then(|| value.trim().to_string())
+}
+
+// the live access token a request carries, None if it doesn't have one
+pub async fn bearer_token<OR>(
+    oauth_repo: Data<OR>,
+    req: &HttpRequest,
+) -> Result<Option<OAuthToken>, ApiError>
+where
+    OR: RepoFind<OAuthToken, FindOAuthTokenQuery>,
+{
+    let Some(bearer) = authorization_header(req, "Bearer") else {
+        return Ok(None);
+    };
+
+    let query = FindOAuthTokenQuery {
+        token_hash: hash_token(&bearer),
+    };
+
+    let token = web::block(move || oauth_repo.find(query))
+        .await
+        .map_err(|_| ApiError::InternalError)?
+        .map_err(|_| ApiError::InternalError)?
+        .into_iter()
+        .find(|token| token.token_kind == OAuthTokenKind::Access)
+        .filter(|token| token.is_active(chrono::Utc::now().naive_utc()))
+        .ok_or(ApiError::Unauthorized)?;
+
+    Ok(Some(token))
+}
+
+// the consent a token was issued under, which has to still be live for the token to be any use
+pub async fn token_consent<OR>(
+    oauth_repo: Data<OR>,
+    token: &OAuthToken,
+) -> Result<Consent, ApiError>
+where
+    OR: RepoGetById<Consent>,
+{
+    let grant_id = token.grant_id;
+
+    web::block(move || oauth_repo.get_by_id(grant_id))
+        .await
+        .map_err(|_| ApiError::InternalError)?
+        .map_err(|err| match err {
+            RepoError::NotFound => ApiError::Unauthorized,
+            _ => ApiError::InternalError,
+        })
+        .and_then(|consent| {
+            consent
+                .is_active(chrono::Utc::now().naive_utc())
+                .then_some(consent)
+                .ok_or(ApiError::Unauthorized)
+        })
+}
+
+// a request with a bearer token is a third party acting for the customer and can only do what the
+// token's scope and the consent behind it let it. None for anything else, which is the customer
+// themselves
+pub async fn require_scope<OR>(
+    oauth_repo: Data<OR>,
+    req: &HttpRequest,
+    customer_id: i32,
+    scope: OAuthScope,
+) -> Result<Option<Consent>, ApiError>
+where
+    OR: RepoFind<OAuthToken, FindOAuthTokenQuery> + RepoGetById<Consent>,
+{
+    let Some(token) = bearer_token(oauth_repo.clone(), req).await? else {
+        return Ok(None);
+    };
+
+    if token.customer_id != customer_id || !scope_includes(&token.scope, scope) {
+        println!(
+            "Refusing token {} for customer {}, wants {}",
+            token.id,
+            customer_id,
+            scope.as_str()
+        );
+        return Err(ApiError::Forbidden);
+    }
+
+    let consent = token_consent(oauth_repo, &token).await?;
+    if !consent.covers(scope) {
+        println!(
+            "Refusing token {}, consent {} no longer covers {}",
+            token.id,
+            consent.id,
+            scope.as_str()
+        );
+        return Err(ApiError::Forbidden);
+    }
+
+    Ok(Some(consent))
+}
+
+// an account a third party is after has to be one the customer chose to share with it
+pub fn require_consented_account(
+    consent: &Option<Consent>,
+    account_id: i32,
+) -> Result<(), ApiError> {
+    match consent {
+        Some(consent) if !consent.includes_account(account_id) => {
+            println!(
+                "Refusing account {} not shared by consent {}",
+                account_id, consent.id
+            );
+            Err(ApiError::Forbidden)
+        }
+        _ => Ok(()),
+    }
+}
+
+// client credentials from a basic header or the form (rfc 6749 2.3.1), read before the request
+// can't go along into web::block
+pub fn client_credentials(
+    req: &HttpRequest,
+    form_client_id: Option<String>,
+    form_client_secret: Option<String>,
+) -> Result<(String, Option<String>), TokenError> {
+    match authorization_header(req, "Basic") {
+        Some(basic) => {
+            let decoded = BASE64
+                .decode(basic.as_bytes())
+                .ok()
+                .and_then(|bytes| String::from_utf8(bytes).ok())
+                .ok_or(TokenError::InvalidClient)?;
+            let (id, secret) = decoded.split_once(':').ok_or(TokenError::InvalidClient)?;
+            Ok((id.to_string(), Some(secret.to_string())))
+        }
+        None => Ok((
+            form_client_id.ok_or(TokenError::InvalidClient)?,
+            form_client_secret,
+        )),
+    }
+}
+
+// public clients have no secret and mustn't send one
+pub fn authenticate_client<OR>(
+    oauth_repo: &OR,
+    client_id: String,
+    client_secret: Option<String>,
+) -> Result<OAuthClient, TokenError>
+where
+    OR: RepoFind<OAuthClient, FindOAuthClientQuery>,
+{
+    let client = oauth_repo
+        .find(FindOAuthClientQuery {
+            client_id: Some(client_id),
+        })
+        .map_err(|_| TokenError::ServerError)?
+        .into_iter()
+        .next()
+        .filter(|client| client.is_active())
+        .ok_or(TokenError::InvalidClient)?;
+
+    let secret_ok = match (
+        &client.client_secret_hash,
+        client_secret.filter(|secret| !secret.is_empty()),
+    ) {
+        (Some(hash), Some(secret)) => *hash == hash_token(&secret),
+        (None, None) => true,
+        _ => false,
+    };
+    if !secret_ok {
+        return Err(TokenError::InvalidClient);
+    }
+
+    Ok(client)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        api::{
+            error::ApiError,
+            oauth::util::{require_consented_account, require_scope},
+        },
+        error::RepoError,
+        models::{
+            consent::Consent,
+            oauth::{FindOAuthTokenQuery, OAuthScope, OAuthToken, OAuthTokenKind},
+        },
+        oauth::hash_token,
+        traits::{RepoFind, RepoGetById},
+    };
+
+    use actix_web::{test, web::Data};
+    use chrono::{Duration, Utc};
+    use mockall::{mock, predicate::eq};
+
+    mock! {
+        pub OR { }
+        impl RepoFind<OAuthToken, FindOAuthTokenQuery> for OR {
+            fn find(&self, query: FindOAuthTokenQuery) -> Result<Vec<OAuthToken>, RepoError>;
+        }
+        impl RepoGetById<Consent> for OR {
+            fn get_by_id(&self, id: i32) -> Result<Consent, RepoError>;
+        }
+    }
+
+    fn token_repo(token: OAuthToken) -> MockOR {
+        let mut mock_oauth_repo = MockOR::new();
+        mock_oauth_repo
+            .expect_find()
+            .with(eq(FindOAuthTokenQuery {
+                token_hash: hash_token("the-token"),
+            }))
+            .times(1)
+            .returning(move |_| Ok(vec![token.clone()]));
+        mock_oauth_repo
+    }
+
+    // customer 5's access token, good for reading accounts
+    fn access_token() -> OAuthToken {
+        let now = Utc::now().naive_utc();
+        OAuthToken {
+            id: 12,
+            grant_id: 4,
+            customer_id: 5,
+            token_kind: OAuthTokenKind::Access,
+            token_hash: hash_token("the-token"),
+            scope: "accounts:read".to_string(),
+            expires_at: now + Duration::minutes(60),
+            revoked_at: None,
+            created_at: now,
+        }
+    }
+
+    // what access_token was issued under, sharing account 52
+    fn consent() -> Consent {
+        let now = Utc::now().naive_utc();
+        Consent {
+            id: 4,
+            customer_id: 5,
+            client_id: "0123456789abcdef0123456789abcdef".to_string(),
+            client_name: "Budget App".to_string(),
+            scope: "accounts:read".to_string(),
+            account_ids: vec![52],
+            created_at: now,
+            updated_at: now,
+            expires_at: now + Duration::days(30),
+            revoked_at: None,
+        }
+    }
+
+    fn consent_repo(token: OAuthToken, consent: Consent) -> MockOR {
+        let mut mock_oauth_repo = token_repo(token);
+        mock_oauth_repo
+            .expect_get_by_id()
+            .with(eq(4))
+            .times(1)
+            .returning(move |_| Ok(consent.clone()));
+        mock_oauth_repo
+    }
+
+    fn bearer_request() -> actix_web::HttpRequest {
+        test::TestRequest::get()
+            .insert_header(("Authorization", "Bearer the-token"))
+            .to_http_request()
+    }
+
+    #[actix_web::test]
+    async fn test_require_scope_no_token_is_first_party() {
+        let mut mock_oauth_repo = MockOR::new();
+        mock_oauth_repo.expect_find().never();
+        mock_oauth_repo.expect_get_by_id().never();
+
+        let res = require_scope(
+            Data::new(mock_oauth_repo),
+            &test::TestRequest::get().to_http_request(),
+            5,
+            OAuthScope::PaymentsWrite,
+        )
+        .await;
+
+        assert!(res.is_ok_and(|consent| consent.is_none()));
+    }
+
+    #[actix_web::test]
+    async fn test_require_scope_success() {
+        let res = require_scope(
+            Data::new(consent_repo(access_token(), consent())),
+            &bearer_request(),
+            5,
+            OAuthScope::AccountsRead,
+        )
+        .await;
+
+        assert!(res.is_ok_and(|consent| consent.is_some_and(|c| c.id == 4)));
+    }
+
+    #[actix_web::test]
+    async fn test_require_scope_missing_scope_forbidden() {
+        let res = require_scope(
+            Data::new(token_repo(access_token())),
+            &bearer_request(),
+            5,
+            OAuthScope::TransactionsRead,
+        )
+        .await;
+
+        assert!(res.is_err_and(|e| e.to_string() == ApiError::Forbidden.to_string()));
+    }
+
+    #[actix_web::test]
+    async fn test_require_scope_other_customer_forbidden() {
+        let res = require_scope(
+            Data::new(token_repo(access_token())),
+            &bearer_request(),
+            6,
+            OAuthScope::AccountsRead,
+        )
+        .await;
+
+        assert!(res.is_err_and(|e| e.to_string() == ApiError::Forbidden.to_string()));
+    }
+
+    #[actix_web::test]
+    async fn test_require_scope_expired_or_refresh_token_unauthorized() {
+        let expired = OAuthToken {
+            expires_at: Utc::now().naive_utc() - Duration::minutes(1),
+            ..access_token()
+        };
+        let refresh = OAuthToken {
+            token_kind: OAuthTokenKind::Refresh,
+            ..access_token()
+        };
+
+        for token in [expired, refresh] {
+            let res = require_scope(
+                Data::new(token_repo(token)),
+                &bearer_request(),
+                5,
+                OAuthScope::AccountsRead,
+            )
+            .await;
+
+            assert!(res.is_err_and(|e| e.to_string() == ApiError::Unauthorized.to_string()));
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_require_scope_consent_expired_revoked_or_narrowed() {
+        let now = Utc::now().naive_utc();
+        let expired = Consent {
+            expires_at: now - Duration::minutes(1),
+            ..consent()
+        };
+        let revoked = Consent {
+            revoked_at: Some(now),
+            ..consent()
+        };
+        for consent in [expired, revoked] {
+            let res = require_scope(
+                Data::new(consent_repo(access_token(), consent)),
+                &bearer_request(),
+                5,
+                OAuthScope::AccountsRead,
+            )
+            .await;
+
+            assert!(res.is_err_and(|e| e.to_string() == ApiError::Unauthorized.to_string()));
+        }
+
+        // the token still says accounts:read but the customer has since taken it off
+        let narrowed = Consent {
+            scope: "payees:read".to_string(),
+            ..consent()
+        };
+        let res = require_scope(
+            Data::new(consent_repo(access_token(), narrowed)),
+            &bearer_request(),
+            5,
+            OAuthScope::AccountsRead,
+        )
+        .await;
+
+        assert!(res.is_err_and(|e| e.to_string() == ApiError::Forbidden.to_string()));
+    }
+
+    #[actix_web::test]
+    async fn consented_accounts_only() {
+        assert!(require_consented_account(&None, 99).is_ok());
+        assert!(require_consented_account(&Some(consent()), 52).is_ok());
+        assert!(require_consented_account(&Some(consent()), 99)
+            .is_err_and(|e| e.to_string() == ApiError::Forbidden.to_string()));
+    }
+}
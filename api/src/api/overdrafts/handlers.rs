@@ -0,0 +1,415 @@
+use actix_web::http::header::ContentType;
+use actix_web::web::{Data, Path, Query};
+use actix_web::{web, HttpRequest, HttpResponse};
+
+use super::models::{FindOverdraftChargeQueryRest, OverdraftChargesRest, OverdraftLimitUpdateRest};
+use super::util::audit_overdraft_charges;
+use crate::api::accounts::models::AccountRest;
+use crate::api::audit::util::{new_audit_entry, record_audit, snapshot};
+use crate::api::automation::models::EndOfDayQueryRest;
+use crate::api::error::ApiError;
+use crate::error::RepoError;
+use crate::models::account::{Account, AccountType};
+use crate::models::audit::{ActorType, AuditAction, AuditEntity, AuditEntry, NewAuditEntry};
+use crate::models::overdraft::{
+    FindOverdraftChargeQuery, OverdraftCharge, OverdraftInterestRun, OverdraftLimitUpdate,
+    MAX_OVERDRAFT_LIMIT_CENTS,
+};
+use crate::traits::{RepoCreate, RepoFind, RepoGetById, RepoUpdate};
+
+pub async fn find_overdraft_charges<ODR>(
+    overdrafts_repo: Data<ODR>,
+    path: Path<i32>,
+    query: Query<FindOverdraftChargeQueryRest>,
+) -> Result<HttpResponse, actix_web::Error>
+where
+    ODR: RepoFind<OverdraftCharge, FindOverdraftChargeQuery>,
+{
+    let customer_id = path.into_inner();
+
+    let charge_query = FindOverdraftChargeQuery {
+        customer_id,
+        account_id: query.account_id,
+    };
+
+    println!(
+        "Trying to get overdraft charges for customer {}",
+        customer_id
+    );
+
+    let charges = web::block(move || overdrafts_repo.find(charge_query))
+        .await
+        .map_err(|_| ApiError::InternalError)?
+        .map_err(|_| ApiError::InternalError)?;
+
+    // enforced query on customer_id so should always be fine, but safe > sorry
+    for charge in charges.iter() {
+        if charge.customer_id != customer_id {
+            return Err(ApiError::BadRequest.into());
+        }
+    }
+
+    Ok(HttpResponse::Ok()
+        .insert_header(ContentType::json())
+        .json(web::Json::<OverdraftChargesRest>(charges.into())))
+}
+
+// arranging, changing or taking away an overdraft is a staff decision. lowering it below what's
+// already owed is allowed, the customer just can't spend until they're back under
+pub async fn set_overdraft_limit<AR, ODR, AuR>(
+    accounts_repo: Data<AR>,
+    overdrafts_repo: Data<ODR>,
+    audit_repo: Data<AuR>,
+    req: HttpRequest,
+    path: Path<(i32, i32)>,
+    payload: web::Json<OverdraftLimitUpdateRest>,
+) -> Result<HttpResponse, actix_web::Error>
+where
+    AR: RepoGetById<Account>,
+    ODR: RepoUpdate<Account, OverdraftLimitUpdate>,
+    AuR: RepoCreate<AuditEntry, NewAuditEntry>,
+{
+    let (staff_id, account_id) = path.into_inner();
+
+    let update: OverdraftLimitUpdate = payload.into_inner().into();
+    if !(0..=MAX_OVERDRAFT_LIMIT_CENTS).contains(&update.limit_cents) {
+        return Err(ApiError::BadRequest.into());
+    }
+
+    println!(
+        "Staff {} trying to set overdraft limit on account {}",
+        staff_id, account_id
+    );
+
+    let (before, after) = web::block(move || {
+        let before = accounts_repo
+            .get_by_id(account_id)
+            .map_err(|err| match err {
+                RepoError::NotFound => ApiError::NotFound,
+                _ => ApiError::InternalError,
+            })?;
+
+        // only transaction accounts come with an overdraft
+        if before.account_type != AccountType::Transaction {
+            return Err(ApiError::BadRequest);
+        }
+
+        let after = overdrafts_repo
+            .update(account_id, update)
+            .map_err(|err| match err {
+                RepoError::NotFound => ApiError::NotFound,
+                _ => ApiError::InternalError,
+            })?;
+
+        Ok((before, after))
+    })
+    .await
+    .map_err(|_| ApiError::InternalError)??;
+
+    let account_rest: AccountRest = (&after).into();
+
+    let mut audit_entry = new_audit_entry(
+        &req,
+        ActorType::Staff,
+        staff_id,
+        AuditAction::SetOverdraftLimit,
+        AuditEntity::Account,
+        Some(account_id),
+    );
+    audit_entry.before_snapshot = snapshot(&AccountRest::from(&before));
+    audit_entry.after_snapshot = snapshot(&account_rest);
+    record_audit(audit_repo, audit_entry).await;
+
+    Ok(HttpResponse::Ok()
+        .insert_header(ContentType::json())
+        .json(web::Json(account_rest)))
+}
+
+// called by whatever schedules the bank's end of day. safe to call again for the same day, each
+// account is only charged once per day
+pub async fn run_overdraft_interest<ODR, AuR>(
+    overdrafts_repo: Data<ODR>,
+    audit_repo: Data<AuR>,
+    req: HttpRequest,
+    path: Path<i32>,
+    query: Query<EndOfDayQueryRest>,
+) -> Result<HttpResponse, actix_web::Error>
+where
+    ODR: RepoCreate<Vec<OverdraftCharge>, OverdraftInterestRun>,
+    AuR: RepoCreate<AuditEntry, NewAuditEntry>,
+{
+    let staff_id = path.into_inner();
+
+    let day = match &query.date {
+        Some(date) => {
+            chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d").map_err(|_| ApiError::BadRequest)?
+        }
+        None => chrono::Utc::now().date_naive(),
+    };
+
+    println!(
+        "Staff {} trying to charge overdraft interest for {}",
+        staff_id, day
+    );
+
+    let charges = web::block(move || overdrafts_repo.create(OverdraftInterestRun { day }))
+        .await
+        .map_err(|_| ApiError::InternalError)?
+        .map_err(|_| ApiError::InternalError)?;
+
+    audit_overdraft_charges(audit_repo, &req, &charges).await;
+
+    Ok(HttpResponse::Ok()
+        .insert_header(ContentType::json())
+        .json(web::Json::<OverdraftChargesRest>(charges.into())))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::audit::util::appended;
+    use crate::fixtures::{account, dt};
+    use actix_web::{
+        body::to_bytes,
+        http::StatusCode,
+        test,
+        web::{Data, Json, Query},
+    };
+    use chrono::NaiveDate;
+    use mockall::predicate::eq;
+
+    use crate::{
+        api::{
+            accounts::models::{AccountRest, OverdraftRest},
+            automation::models::EndOfDayQueryRest,
+            error::ApiError,
+            overdrafts::{
+                handlers::{find_overdraft_charges, run_overdraft_interest, set_overdraft_limit},
+                models::{
+                    FindOverdraftChargeQueryRest, OverdraftChargeKindRest, OverdraftChargesRest,
+                    OverdraftLimitUpdateRest,
+                },
+            },
+        },
+        models::{
+            account::{Account, AccountType},
+            audit::{ActorType, AuditEntry, NewAuditEntry},
+            overdraft::{
+                FindOverdraftChargeQuery, OverdraftCharge, OverdraftChargeKind,
+                OverdraftInterestRun, OverdraftLimitUpdate, DISHONOUR_FEE_CENTS,
+                OVERDRAFT_INTEREST_RATE_BPS,
+            },
+        },
+        traits::{MockRepoCreate, MockRepoFind, MockRepoGetById, MockRepoUpdate},
+    };
+
+    fn charge(id: i32, account_id: i32, kind: OverdraftChargeKind) -> OverdraftCharge {
+        OverdraftCharge {
+            id,
+            account_id,
+            customer_id: 5,
+            kind,
+            charge_date: dt().date(),
+            amount_cents: 10,
+            transaction_id: 70 + id,
+            created_at: dt(),
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_set_overdraft_limit_success() {
+        let mut mock_accounts_repo = MockRepoGetById::<Account>::new();
+        mock_accounts_repo
+            .expect_get_by_id()
+            .with(eq(52))
+            .times(1)
+            .returning(|_| {
+                Ok(Account {
+                    overdraft_limit_cents: 0,
+                    ..account(52, 5, AccountType::Transaction, -20000)
+                })
+            });
+
+        let mut mock_overdrafts_repo = MockRepoUpdate::<Account, OverdraftLimitUpdate>::new();
+        mock_overdrafts_repo
+            .expect_update()
+            .with(eq(52), eq(OverdraftLimitUpdate { limit_cents: 50000 }))
+            .times(1)
+            .returning(|_, update| {
+                Ok(Account {
+                    overdraft_limit_cents: update.limit_cents,
+                    ..account(52, 5, AccountType::Transaction, -20000)
+                })
+            });
+
+        let mut mock_audit_repo = MockRepoCreate::<AuditEntry, NewAuditEntry>::new();
+        mock_audit_repo
+            .expect_create()
+            .withf(|entry| {
+                entry.actor_type == ActorType::Staff
+                    && entry.actor_id == 2
+                    && entry.action == "set_overdraft_limit"
+                    && entry.entity_type == "account"
+                    && entry.entity_id == Some(52)
+                    && entry.before_snapshot.is_some()
+                    && entry.after_snapshot.is_some()
+            })
+            .times(1)
+            .returning(|entry| Ok(appended(entry)));
+
+        let res = set_overdraft_limit(
+            Data::new(mock_accounts_repo),
+            Data::new(mock_overdrafts_repo),
+            Data::new(mock_audit_repo),
+            test::TestRequest::put().to_http_request(),
+            (2, 52).into(),
+            Json(OverdraftLimitUpdateRest { limit_cents: 50000 }),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(StatusCode::OK, res.status());
+
+        let actual: AccountRest =
+            serde_json::from_slice(&to_bytes(res.into_body()).await.unwrap()).unwrap();
+        assert_eq!(
+            Some(OverdraftRest {
+                limit_cents: 50000,
+                used_cents: 20000,
+                remaining_cents: 30000,
+                interest_rate_bps: OVERDRAFT_INTEREST_RATE_BPS,
+                dishonour_fee_cents: DISHONOUR_FEE_CENTS,
+            }),
+            actual.overdraft
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_set_overdraft_limit_savings_error() {
+        let mut mock_accounts_repo = MockRepoGetById::<Account>::new();
+        mock_accounts_repo
+            .expect_get_by_id()
+            .times(1)
+            .returning(|_| {
+                Ok(Account {
+                    overdraft_limit_cents: 0,
+                    ..account(52, 5, AccountType::Savings, -20000)
+                })
+            });
+
+        let mut mock_overdrafts_repo = MockRepoUpdate::<Account, OverdraftLimitUpdate>::new();
+        mock_overdrafts_repo.expect_update().times(0);
+
+        let res = set_overdraft_limit(
+            Data::new(mock_accounts_repo),
+            Data::new(mock_overdrafts_repo),
+            Data::new(MockRepoCreate::<AuditEntry, NewAuditEntry>::new()),
+            test::TestRequest::put().to_http_request(),
+            (2, 52).into(),
+            Json(OverdraftLimitUpdateRest { limit_cents: 50000 }),
+        )
+        .await;
+
+        assert!(res.is_err_and(|e| { e.to_string() == ApiError::BadRequest.to_string() }));
+    }
+
+    #[actix_web::test]
+    async fn test_set_overdraft_limit_negative_error() {
+        let res = set_overdraft_limit(
+            Data::new(MockRepoGetById::<Account>::new()),
+            Data::new(MockRepoUpdate::<Account, OverdraftLimitUpdate>::new()),
+            Data::new(MockRepoCreate::<AuditEntry, NewAuditEntry>::new()),
+            test::TestRequest::put().to_http_request(),
+            (2, 52).into(),
+            Json(OverdraftLimitUpdateRest { limit_cents: -1 }),
+        )
+        .await;
+
+        assert!(res.is_err_and(|e| { e.to_string() == ApiError::BadRequest.to_string() }));
+    }
+
+    #[actix_web::test]
+    async fn test_find_overdraft_charges_success() {
+        let mut mock_overdrafts_repo =
+            MockRepoFind::<OverdraftCharge, FindOverdraftChargeQuery>::new();
+        mock_overdrafts_repo
+            .expect_find()
+            .with(eq(FindOverdraftChargeQuery {
+                customer_id: 5,
+                account_id: Some(52),
+            }))
+            .times(1)
+            .returning(|_| {
+                Ok(vec![
+                    charge(2, 52, OverdraftChargeKind::DishonourFee),
+                    charge(1, 52, OverdraftChargeKind::Interest),
+                ])
+            });
+
+        let res = find_overdraft_charges(
+            Data::new(mock_overdrafts_repo),
+            5.into(),
+            Query(FindOverdraftChargeQueryRest {
+                account_id: Some(52),
+            }),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(StatusCode::OK, res.status());
+
+        let actual: OverdraftChargesRest =
+            serde_json::from_slice(&to_bytes(res.into_body()).await.unwrap()).unwrap();
+        assert_eq!(2, actual.charges.len());
+        assert_eq!(
+            OverdraftChargeKindRest::DishonourFee,
+            actual.charges[0].kind
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_run_overdraft_interest_audits_each_charge() {
+        let day = NaiveDate::from_ymd_opt(2023, 9, 3).unwrap();
+
+        let mut mock_overdrafts_repo =
+            MockRepoCreate::<Vec<OverdraftCharge>, OverdraftInterestRun>::new();
+        mock_overdrafts_repo
+            .expect_create()
+            .with(eq(OverdraftInterestRun { day }))
+            .times(1)
+            .returning(|_| {
+                Ok(vec![
+                    charge(1, 52, OverdraftChargeKind::Interest),
+                    charge(2, 53, OverdraftChargeKind::Interest),
+                ])
+            });
+
+        let mut mock_audit_repo = MockRepoCreate::<AuditEntry, NewAuditEntry>::new();
+        mock_audit_repo
+            .expect_create()
+            .withf(|entry| {
+                entry.actor_type == ActorType::System
+                    && entry.action == "charge_overdraft_interest"
+                    && entry.entity_type == "transaction"
+            })
+            .times(2)
+            .returning(|entry| Ok(appended(entry)));
+
+        let res = run_overdraft_interest(
+            Data::new(mock_overdrafts_repo),
+            Data::new(mock_audit_repo),
+            test::TestRequest::post().to_http_request(),
+            2.into(),
+            Query(EndOfDayQueryRest {
+                date: Some("2023-09-03".to_string()),
+            }),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(StatusCode::OK, res.status());
+
+        let actual: OverdraftChargesRest =
+            serde_json::from_slice(&to_bytes(res.into_body()).await.unwrap()).unwrap();
+        assert_eq!(2, actual.charges.len());
+    }
+}
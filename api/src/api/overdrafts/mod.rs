@@ -0,0 +1,47 @@
+pub mod handlers;
+pub mod models;
+pub mod transform;
+pub mod util;
+
+use actix_web::web;
+
+use crate::{
+    api::overdrafts,
+    models::{
+        account::Account,
+        audit::{AuditEntry, NewAuditEntry},
+        overdraft::{
+            FindOverdraftChargeQuery, OverdraftCharge, OverdraftInterestRun, OverdraftLimitUpdate,
+        },
+    },
+    traits::{RepoCreate, RepoFind, RepoGetById, RepoUpdate},
+};
+
+pub fn configure_overdrafts_api<ODR, AR, AuR>(cfg: &mut web::ServiceConfig)
+where
+    ODR: RepoFind<OverdraftCharge, FindOverdraftChargeQuery>
+        + RepoUpdate<Account, OverdraftLimitUpdate>
+        + RepoCreate<Vec<OverdraftCharge>, OverdraftInterestRun>,
+    AR: RepoGetById<Account>,
+    AuR: RepoCreate<AuditEntry, NewAuditEntry>,
+{
+    cfg.service(
+        web::scope("/api/customers/{customer_id}/overdraft-charges").service(
+            web::resource("")
+                .route(web::get().to(overdrafts::handlers::find_overdraft_charges::<ODR>)),
+        ),
+    )
+    .service(
+        web::scope("/api/staff/{staff_id}/overdrafts")
+            .service(
+                web::resource("/accounts/{account_id}").route(
+                    web::put().to(overdrafts::handlers::set_overdraft_limit::<AR, ODR, AuR>),
+                ),
+            )
+            .service(
+                web::resource("/end-of-day").route(
+                    web::post().to(overdrafts::handlers::run_overdraft_interest::<ODR, AuR>),
+                ),
+            ),
+    );
+}
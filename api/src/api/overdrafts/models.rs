@@ -0,0 +1,42 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Serialize, Copy, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum OverdraftChargeKindRest {
+    Interest,
+    DishonourFee,
+}
+
+#[cfg_attr(test, derive(Deserialize, PartialEq, Debug))]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OverdraftChargeRest {
+    pub id: i32,
+    pub account_id: i32,
+    pub customer_id: i32,
+    pub kind: OverdraftChargeKindRest,
+    pub charge_date: String,
+    pub amount_cents: i64,
+    pub transaction_id: i32,
+    pub created_at: String,
+}
+
+#[cfg_attr(test, derive(Deserialize))]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OverdraftChargesRest {
+    pub charges: Vec<OverdraftChargeRest>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FindOverdraftChargeQueryRest {
+    pub account_id: Option<i32>,
+}
+
+#[cfg_attr(test, derive(Serialize))]
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct OverdraftLimitUpdateRest {
+    pub limit_cents: i64,
+}
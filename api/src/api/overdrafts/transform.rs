@@ -0,0 +1,45 @@
+use crate::models::overdraft::{OverdraftCharge, OverdraftChargeKind, OverdraftLimitUpdate};
+
+use super::models::{
+    OverdraftChargeKindRest, OverdraftChargeRest, OverdraftChargesRest, OverdraftLimitUpdateRest,
+};
+
+impl From<OverdraftChargeKind> for OverdraftChargeKindRest {
+    fn from(kind: OverdraftChargeKind) -> Self {
+        match kind {
+            OverdraftChargeKind::Interest => OverdraftChargeKindRest::Interest,
+            OverdraftChargeKind::DishonourFee => OverdraftChargeKindRest::DishonourFee,
+        }
+    }
+}
+
+impl From<&OverdraftCharge> for OverdraftChargeRest {
+    fn from(charge: &OverdraftCharge) -> Self {
+        Self {
+            id: charge.id,
+            account_id: charge.account_id,
+            customer_id: charge.customer_id,
+            kind: charge.kind.into(),
+            charge_date: charge.charge_date.to_string(),
+            amount_cents: charge.amount_cents,
+            transaction_id: charge.transaction_id,
+            created_at: charge.created_at.to_string(),
+        }
+    }
+}
+
+impl From<Vec<OverdraftCharge>> for OverdraftChargesRest {
+    fn from(charges: Vec<OverdraftCharge>) -> Self {
+        Self {
+            charges: charges.iter().map(OverdraftChargeRest::from).collect(),
+        }
+    }
+}
+
+impl From<OverdraftLimitUpdateRest> for OverdraftLimitUpdate {
+    fn from(update: OverdraftLimitUpdateRest) -> Self {
+        Self {
+            limit_cents: update.limit_cents,
+        }
+    }
+}
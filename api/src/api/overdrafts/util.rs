@@ -0,0 +1,37 @@
+use actix_web::{web::Data, HttpRequest};
+
+use super::models::OverdraftChargeRest;
+use crate::{
+    api::audit::util::{new_audit_entry, record_audit, snapshot},
+    models::{
+        audit::{ActorType, AuditAction, AuditEntity, AuditEntry, NewAuditEntry},
+        overdraft::{OverdraftCharge, OverdraftChargeKind},
+    },
+    traits::RepoCreate,
+};
+
+// charges are the system acting, on the account they came out of
+pub async fn audit_overdraft_charges<AuR>(
+    audit_repo: Data<AuR>,
+    req: &HttpRequest,
+    charges: &[OverdraftCharge],
+) where
+    AuR: RepoCreate<AuditEntry, NewAuditEntry>,
+{
+    for charge in charges.iter() {
+        let action = match charge.kind {
+            OverdraftChargeKind::Interest => AuditAction::ChargeOverdraftInterest,
+            OverdraftChargeKind::DishonourFee => AuditAction::ChargeDishonourFee,
+        };
+        let mut audit_entry = new_audit_entry(
+            req,
+            ActorType::System,
+            charge.account_id,
+            action,
+            AuditEntity::Transaction,
+            Some(charge.transaction_id),
+        );
+        audit_entry.after_snapshot = snapshot(&OverdraftChargeRest::from(charge));
+        record_audit(audit_repo.clone(), audit_entry).await;
+    }
+}
@@ -0,0 +1,528 @@
+use actix_web::http::header::ContentType;
+use actix_web::web::{Data, Path};
+use actix_web::{web, HttpRequest, HttpResponse};
+
+use super::models::{NewPayeeRest, PayeeRest, PayeeUpdateRest, PayeesRest};
+use crate::api::audit::util::{new_audit_entry, record_audit, snapshot};
+use crate::api::error::ApiError;
+use crate::error::RepoError;
+use crate::models::audit::{ActorType, AuditAction, AuditEntity, AuditEntry, NewAuditEntry};
+use crate::models::payee::{FindPayeeQuery, NewPayee, Payee, PayeeUpdate};
+use crate::models::transaction::is_valid_reference;
+use crate::traits::{RepoCreate, RepoDeleteById, RepoFind, RepoGetById, RepoUpdate};
+
+const MAX_NICKNAME_LEN: usize = 40;
+
+fn all_digits(value: &str, len: usize) -> bool {
+    value.len() == len && value.chars().all(|c| c.is_ascii_digit())
+}
+
+fn valid_nickname(nickname: &str) -> bool {
+    !nickname.is_empty() && nickname.chars().count() <= MAX_NICKNAME_LEN
+}
+
+pub async fn create_payee<PR, AuR>(
+    payees_repo: Data<PR>,
+    audit_repo: Data<AuR>,
+    req: HttpRequest,
+    path: Path<i32>,
+    payload: web::Json<NewPayeeRest>,
+) -> Result<HttpResponse, actix_web::Error>
+where
+    PR: RepoCreate<Payee, NewPayee>,
+    AuR: RepoCreate<AuditEntry, NewAuditEntry>,
+{
+    let customer_id = path.into_inner();
+
+    let mut new_payee: NewPayee = payload.into_inner().into();
+    new_payee.customer_id = customer_id;
+
+    if !valid_nickname(&new_payee.nickname)
+        || !all_digits(&new_payee.account_number, 9)
+        || !all_digits(&new_payee.bsb, 6)
+        || !is_valid_reference(&new_payee.default_reference)
+    {
+        return Err(ApiError::BadRequest.into());
+    }
+
+    println!("Trying to create payee for customer {}", customer_id);
+
+    let payee = web::block(move || payees_repo.create(new_payee))
+        .await
+        .map_err(|_| ApiError::InternalError)?
+        .map_err(|err| match err {
+            RepoError::Conflict => ApiError::Conflict,
+            _ => ApiError::InternalError,
+        })?;
+
+    let payee_rest: PayeeRest = (&payee).into();
+
+    let mut audit_entry = new_audit_entry(
+        &req,
+        ActorType::Customer,
+        customer_id,
+        AuditAction::CreatePayee,
+        AuditEntity::Payee,
+        Some(payee.id),
+    );
+    audit_entry.after_snapshot = snapshot(&payee_rest);
+    record_audit(audit_repo, audit_entry).await;
+
+    Ok(HttpResponse::Created()
+        .insert_header(ContentType::json())
+        .json(web::Json(payee_rest)))
+}
+
+pub async fn find_payees<PR>(
+    payees_repo: Data<PR>,
+    path: Path<i32>,
+) -> Result<HttpResponse, actix_web::Error>
+where
+    PR: RepoFind<Payee, FindPayeeQuery>,
+{
+    let customer_id = path.into_inner();
+
+    println!("Trying to get payees for customer {}", customer_id);
+
+    let payees = web::block(move || payees_repo.find(FindPayeeQuery { customer_id }))
+        .await
+        .map_err(|_| ApiError::InternalError)?
+        .map_err(|_| ApiError::InternalError)?;
+
+    // enforced query on customer_id so should always be fine, but safe > sorry
+    for payee in payees.iter() {
+        if payee.customer_id != customer_id {
+            return Err(ApiError::BadRequest.into());
+        }
+    }
+
+    Ok(HttpResponse::Ok()
+        .insert_header(ContentType::json())
+        .json(web::Json::<PayeesRest>(payees.into())))
+}
+
+pub async fn get_payee<PR>(
+    payees_repo: Data<PR>,
+    path: Path<(i32, i32)>,
+) -> Result<HttpResponse, actix_web::Error>
+where
+    PR: RepoGetById<Payee>,
+{
+    let (customer_id, payee_id) = path.into_inner();
+
+    println!(
+        "Trying to get payee {} for customer {}",
+        payee_id, customer_id
+    );
+
+    let payee = web::block(move || payees_repo.get_by_id(payee_id))
+        .await
+        .map_err(|_| ApiError::InternalError)?
+        .map_err(|err| match err {
+            RepoError::NotFound => ApiError::NotFound,
+            _ => ApiError::InternalError,
+        })?;
+
+    if payee.customer_id != customer_id {
+        return Err(ApiError::Unauthorized.into());
+    }
+
+    Ok(HttpResponse::Ok()
+        .insert_header(ContentType::json())
+        .json(web::Json::<PayeeRest>((&payee).into())))
+}
+
+pub async fn update_payee<PR, AuR>(
+    payees_repo: Data<PR>,
+    audit_repo: Data<AuR>,
+    req: HttpRequest,
+    path: Path<(i32, i32)>,
+    payload: web::Json<PayeeUpdateRest>,
+) -> Result<HttpResponse, actix_web::Error>
+where
+    PR: RepoGetById<Payee> + RepoUpdate<Payee, PayeeUpdate>,
+    AuR: RepoCreate<AuditEntry, NewAuditEntry>,
+{
+    let (customer_id, payee_id) = path.into_inner();
+
+    let update: PayeeUpdate = payload.into_inner().into();
+
+    if update
+        .nickname
+        .as_ref()
+        .is_some_and(|nickname| !valid_nickname(nickname))
+        || update
+            .default_reference
+            .as_ref()
+            .is_some_and(|reference| !is_valid_reference(reference))
+    {
+        return Err(ApiError::BadRequest.into());
+    }
+
+    println!(
+        "Trying to update payee {} for customer {}",
+        payee_id, customer_id
+    );
+
+    let (before, after) = web::block(move || {
+        let before = payees_repo.get_by_id(payee_id).map_err(|err| match err {
+            RepoError::NotFound => ApiError::NotFound,
+            _ => ApiError::InternalError,
+        })?;
+
+        if before.customer_id != customer_id {
+            return Err(ApiError::Unauthorized);
+        }
+
+        let after = payees_repo
+            .update(payee_id, update)
+            .map_err(|_| ApiError::InternalError)?;
+
+        Ok((before, after))
+    })
+    .await
+    .map_err(|_| ApiError::InternalError)??;
+
+    let after_rest: PayeeRest = (&after).into();
+
+    let mut audit_entry = new_audit_entry(
+        &req,
+        ActorType::Customer,
+        customer_id,
+        AuditAction::UpdatePayee,
+        AuditEntity::Payee,
+        Some(payee_id),
+    );
+    audit_entry.before_snapshot = snapshot(&PayeeRest::from(&before));
+    audit_entry.after_snapshot = snapshot(&after_rest);
+    record_audit(audit_repo, audit_entry).await;
+
+    Ok(HttpResponse::Ok()
+        .insert_header(ContentType::json())
+        .json(web::Json(after_rest)))
+}
+
+pub async fn delete_payee<PR, AuR>(
+    payees_repo: Data<PR>,
+    audit_repo: Data<AuR>,
+    req: HttpRequest,
+    path: Path<(i32, i32)>,
+) -> Result<HttpResponse, actix_web::Error>
+where
+    PR: RepoGetById<Payee> + RepoDeleteById<Payee>,
+    AuR: RepoCreate<AuditEntry, NewAuditEntry>,
+{
+    let (customer_id, payee_id) = path.into_inner();
+
+    println!(
+        "Trying to delete payee {} for customer {}",
+        payee_id, customer_id
+    );
+
+    let deleted_payee = web::block(move || {
+        let existing_payee = match payees_repo.get_by_id(payee_id) {
+            Ok(payee) => {
+                if payee.customer_id != customer_id {
+                    return Err(ApiError::Unauthorized);
+                }
+                Some(payee)
+            }
+            Err(RepoError::NotFound) => None,
+            _ => return Err(ApiError::InternalError),
+        };
+
+        payees_repo
+            .delete_by_id(payee_id)
+            .map_err(|_| ApiError::InternalError)?;
+
+        Ok(existing_payee)
+    })
+    .await
+    .map_err(|_| ApiError::InternalError)??;
+
+    // deleting something that isn't there is a no-op, nothing to audit
+    if let Some(payee) = deleted_payee {
+        let mut audit_entry = new_audit_entry(
+            &req,
+            ActorType::Customer,
+            customer_id,
+            AuditAction::DeletePayee,
+            AuditEntity::Payee,
+            Some(payee.id),
+        );
+        audit_entry.before_snapshot = snapshot(&PayeeRest::from(&payee));
+        record_audit(audit_repo, audit_entry).await;
+    }
+
+    Ok(HttpResponse::NoContent().body(""))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::audit::util::appended;
+    use crate::fixtures::dt;
+    use actix_web::{
+        body::to_bytes,
+        http::StatusCode,
+        test,
+        web::{Data, Json},
+    };
+
+    use mockall::{mock, predicate::eq};
+
+    use crate::{
+        api::{
+            error::ApiError,
+            payees::{
+                handlers::{create_payee, delete_payee, get_payee, update_payee},
+                models::{NewPayeeRest, PayeeRest, PayeeUpdateRest},
+            },
+        },
+        error::RepoError,
+        models::{
+            audit::{AuditEntry, NewAuditEntry},
+            payee::{NewPayee, Payee, PayeeUpdate},
+        },
+        traits::{MockRepoCreate, MockRepoGetById, RepoDeleteById, RepoGetById, RepoUpdate},
+    };
+
+    fn payee(id: i32, customer_id: i32) -> Payee {
+        Payee {
+            id,
+            customer_id,
+            nickname: "Landlord".to_string(),
+            account_number: "987654321".to_string(),
+            bsb: "654321".to_string(),
+            default_reference: Some("rent".to_string()),
+            created_at: dt(),
+            first_used_at: None,
+            last_used_at: None,
+        }
+    }
+
+    mock! {
+        pub PR { }
+        impl RepoGetById<Payee> for PR {
+            fn get_by_id(&self, id: i32) -> Result<Payee, RepoError>;
+        }
+        impl RepoUpdate<Payee, PayeeUpdate> for PR {
+            fn update(&self, id: i32, update: PayeeUpdate) -> Result<Payee, RepoError>;
+        }
+        impl RepoDeleteById<Payee> for PR {
+            fn delete_by_id(&self, id: i32) -> Result<(), RepoError>;
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_create_payee_success() {
+        let customer_id = 5;
+
+        let mut mock_payees_repo = MockRepoCreate::<Payee, NewPayee>::new();
+        mock_payees_repo
+            .expect_create()
+            .with(eq(NewPayee {
+                customer_id,
+                nickname: "Landlord".to_string(),
+                account_number: "987654321".to_string(),
+                bsb: "654321".to_string(),
+                default_reference: Some("rent".to_string()),
+            }))
+            .times(1)
+            .returning(move |_| Ok(payee(1, customer_id)));
+
+        let mut mock_audit_repo = MockRepoCreate::<AuditEntry, NewAuditEntry>::new();
+        mock_audit_repo
+            .expect_create()
+            .withf(|entry| entry.action == "create_payee" && entry.entity_type == "payee")
+            .times(1)
+            .returning(|entry| Ok(appended(entry)));
+
+        let res = create_payee(
+            Data::new(mock_payees_repo),
+            Data::new(mock_audit_repo),
+            test::TestRequest::post().to_http_request(),
+            customer_id.into(),
+            Json(NewPayeeRest {
+                nickname: " Landlord ".to_string(),
+                account_number: "987654321".to_string(),
+                bsb: "654321".to_string(),
+                default_reference: Some("rent".to_string()),
+            }),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(StatusCode::CREATED, res.status());
+
+        let actual: PayeeRest =
+            serde_json::from_slice(&to_bytes(res.into_body()).await.unwrap()).unwrap();
+        assert_eq!("Landlord", actual.nickname);
+        assert_eq!(None, actual.first_used_at);
+    }
+
+    #[actix_web::test]
+    async fn test_create_payee_unaudited_still_created() {
+        let mut mock_payees_repo = MockRepoCreate::<Payee, NewPayee>::new();
+        mock_payees_repo
+            .expect_create()
+            .times(1)
+            .returning(|_| Ok(payee(1, 5)));
+
+        let mut mock_audit_repo = MockRepoCreate::<AuditEntry, NewAuditEntry>::new();
+        mock_audit_repo
+            .expect_create()
+            .times(1)
+            .returning(|_| Err(RepoError::Other));
+
+        let res = create_payee(
+            Data::new(mock_payees_repo),
+            Data::new(mock_audit_repo),
+            test::TestRequest::post().to_http_request(),
+            5.into(),
+            Json(NewPayeeRest {
+                nickname: "Landlord".to_string(),
+                account_number: "987654321".to_string(),
+                bsb: "654321".to_string(),
+                default_reference: None,
+            }),
+        )
+        .await
+        .unwrap();
+
+        // the payee's there either way, a failure here would have the client add it again
+        assert_eq!(StatusCode::CREATED, res.status());
+    }
+
+    #[actix_web::test]
+    async fn test_create_payee_bad_bsb_error() {
+        let mut mock_payees_repo = MockRepoCreate::<Payee, NewPayee>::new();
+        mock_payees_repo.expect_create().times(0);
+
+        let res = create_payee(
+            Data::new(mock_payees_repo),
+            Data::new(MockRepoCreate::<AuditEntry, NewAuditEntry>::new()),
+            test::TestRequest::post().to_http_request(),
+            5.into(),
+            Json(NewPayeeRest {
+                nickname: "Landlord".to_string(),
+                account_number: "987654321".to_string(),
+                bsb: "65-321".to_string(),
+                default_reference: None,
+            }),
+        )
+        .await;
+
+        assert!(res.is_err_and(|e| { e.to_string() == ApiError::BadRequest.to_string() }));
+    }
+
+    #[actix_web::test]
+    async fn test_create_payee_duplicate_conflict() {
+        let mut mock_payees_repo = MockRepoCreate::<Payee, NewPayee>::new();
+        mock_payees_repo
+            .expect_create()
+            .times(1)
+            .returning(|_| Err(RepoError::Conflict));
+
+        let res = create_payee(
+            Data::new(mock_payees_repo),
+            Data::new(MockRepoCreate::<AuditEntry, NewAuditEntry>::new()),
+            test::TestRequest::post().to_http_request(),
+            5.into(),
+            Json(NewPayeeRest {
+                nickname: "Landlord".to_string(),
+                account_number: "987654321".to_string(),
+                bsb: "654321".to_string(),
+                default_reference: None,
+            }),
+        )
+        .await;
+
+        assert!(res.is_err_and(|e| { e.to_string() == ApiError::Conflict.to_string() }));
+    }
+
+    #[actix_web::test]
+    async fn test_get_payee_unauthorized_error() {
+        let mut mock_payees_repo = MockRepoGetById::<Payee>::new();
+        mock_payees_repo
+            .expect_get_by_id()
+            .times(1)
+            .returning(|_| Ok(payee(1, 5)));
+
+        let res = get_payee(Data::new(mock_payees_repo), (6, 1).into()).await;
+
+        assert!(res.is_err_and(|e| { e.to_string() == ApiError::Unauthorized.to_string() }));
+    }
+
+    #[actix_web::test]
+    async fn test_update_payee_clears_reference() {
+        let customer_id = 5;
+        let payee_id = 1;
+
+        let mut mock_payees_repo = MockPR::new();
+        mock_payees_repo
+            .expect_get_by_id()
+            .times(1)
+            .returning(move |_| Ok(payee(payee_id, customer_id)));
+        mock_payees_repo
+            .expect_update()
+            .with(
+                eq(payee_id),
+                eq(PayeeUpdate {
+                    nickname: None,
+                    default_reference: Some(None),
+                }),
+            )
+            .times(1)
+            .returning(move |_, _| {
+                let mut p = payee(payee_id, customer_id);
+                p.default_reference = None;
+                Ok(p)
+            });
+
+        let mut mock_audit_repo = MockRepoCreate::<AuditEntry, NewAuditEntry>::new();
+        mock_audit_repo
+            .expect_create()
+            .withf(|entry| entry.action == "update_payee" && entry.before_snapshot.is_some())
+            .times(1)
+            .returning(|entry| Ok(appended(entry)));
+
+        let res = update_payee(
+            Data::new(mock_payees_repo),
+            Data::new(mock_audit_repo),
+            test::TestRequest::patch().to_http_request(),
+            (customer_id, payee_id).into(),
+            Json(PayeeUpdateRest {
+                nickname: None,
+                default_reference: Some("".to_string()),
+            }),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(StatusCode::OK, res.status());
+
+        let actual: PayeeRest =
+            serde_json::from_slice(&to_bytes(res.into_body()).await.unwrap()).unwrap();
+        assert_eq!(None, actual.default_reference);
+    }
+
+    #[actix_web::test]
+    async fn test_delete_payee_wrong_customer_error() {
+        let mut mock_payees_repo = MockPR::new();
+        mock_payees_repo
+            .expect_get_by_id()
+            .times(1)
+            .returning(|_| Ok(payee(1, 5)));
+        mock_payees_repo.expect_delete_by_id().times(0);
+
+        let res = delete_payee(
+            Data::new(mock_payees_repo),
+            Data::new(MockRepoCreate::<AuditEntry, NewAuditEntry>::new()),
+            test::TestRequest::delete().to_http_request(),
+            (6, 1).into(),
+        )
+        .await;
+
+        assert!(res.is_err_and(|e| { e.to_string() == ApiError::Unauthorized.to_string() }));
+    }
+}
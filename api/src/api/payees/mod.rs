@@ -0,0 +1,39 @@
+pub mod handlers;
+pub mod models;
+pub mod transform;
+
+use actix_web::web;
+
+use crate::{
+    api::payees,
+    models::{
+        audit::{AuditEntry, NewAuditEntry},
+        payee::{FindPayeeQuery, NewPayee, Payee, PayeeUpdate},
+    },
+    traits::{RepoCreate, RepoDeleteById, RepoFind, RepoGetById, RepoUpdate},
+};
+
+pub fn configure_payees_api<PR, AuR>(cfg: &mut web::ServiceConfig)
+where
+    PR: RepoCreate<Payee, NewPayee>
+        + RepoFind<Payee, FindPayeeQuery>
+        + RepoGetById<Payee>
+        + RepoUpdate<Payee, PayeeUpdate>
+        + RepoDeleteById<Payee>,
+    AuR: RepoCreate<AuditEntry, NewAuditEntry>,
+{
+    cfg.service(
+        web::scope("/api/customers/{customer_id}/payees")
+            .service(
+                web::resource("")
+                    .route(web::post().to(payees::handlers::create_payee::<PR, AuR>))
+                    .route(web::get().to(payees::handlers::find_payees::<PR>)),
+            )
+            .service(
+                web::resource("/{payee_id}")
+                    .route(web::get().to(payees::handlers::get_payee::<PR>))
+                    .route(web::patch().to(payees::handlers::update_payee::<PR, AuR>))
+                    .route(web::delete().to(payees::handlers::delete_payee::<PR, AuR>)),
+            ),
+    );
+}
@@ -0,0 +1,42 @@
+use serde::{Deserialize, Serialize};
+
+#[cfg_attr(test, derive(Deserialize, PartialEq, Debug))]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PayeeRest {
+    pub id: i32,
+    pub customer_id: i32,
+    pub nickname: String,
+    pub account_number: String,
+    pub bsb: String,
+    pub default_reference: Option<String>,
+    pub created_at: String,
+    pub first_used_at: Option<String>,
+    pub last_used_at: Option<String>,
+}
+
+#[cfg_attr(test, derive(Deserialize))]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PayeesRest {
+    pub payees: Vec<PayeeRest>,
+}
+
+#[cfg_attr(test, derive(Serialize))]
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct NewPayeeRest {
+    pub nickname: String,
+    pub account_number: String,
+    pub bsb: String,
+    pub default_reference: Option<String>,
+}
+
+#[cfg_attr(test, derive(Serialize))]
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PayeeUpdateRest {
+    pub nickname: Option<String>,
+    // empty string clears it
+    pub default_reference: Option<String>,
+}
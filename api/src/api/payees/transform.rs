@@ -0,0 +1,52 @@
+use crate::models::payee::{NewPayee, Payee, PayeeUpdate};
+use crate::util::string_opt_from_naive_dt_opt;
+
+use super::models::{NewPayeeRest, PayeeRest, PayeeUpdateRest, PayeesRest};
+
+impl From<&Payee> for PayeeRest {
+    fn from(payee: &Payee) -> Self {
+        Self {
+            id: payee.id,
+            customer_id: payee.customer_id,
+            nickname: payee.nickname.clone(),
+            account_number: payee.account_number.clone(),
+            bsb: payee.bsb.clone(),
+            default_reference: payee.default_reference.clone(),
+            created_at: payee.created_at.to_string(),
+            first_used_at: string_opt_from_naive_dt_opt(payee.first_used_at),
+            last_used_at: string_opt_from_naive_dt_opt(payee.last_used_at),
+        }
+    }
+}
+
+impl From<Vec<Payee>> for PayeesRest {
+    fn from(payees: Vec<Payee>) -> Self {
+        Self {
+            payees: payees.iter().map(PayeeRest::from).collect(),
+        }
+    }
+}
+
+impl From<NewPayeeRest> for NewPayee {
+    fn from(payee: NewPayeeRest) -> Self {
+        NewPayee {
+            // comes from path, set by handler
+            customer_id: 0,
+            nickname: payee.nickname.trim().to_string(),
+            account_number: payee.account_number,
+            bsb: payee.bsb,
+            default_reference: payee.default_reference.filter(|r| !r.is_empty()),
+        }
+    }
+}
+
+impl From<PayeeUpdateRest> for PayeeUpdate {
+    fn from(update: PayeeUpdateRest) -> Self {
+        PayeeUpdate {
+            nickname: update.nickname.map(|n| n.trim().to_string()),
+            default_reference: update
+                .default_reference
+                .map(|r| Some(r).filter(|r| !r.is_empty())),
+        }
+    }
+}
@@ -0,0 +1,793 @@
+use actix_web::http::header::ContentType;
+use actix_web::web::{Data, Path, Query};
+use actix_web::{web, HttpRequest, HttpResponse};
+use chrono::NaiveDate;
+
+use super::models::{
+    CategoryOverrideRest, CategoryOverridesRest, CategoryUpdateRest, SpendingByCategoryRest,
+    SpendingByMonthRest, SpendingByPayeeRest, SpendingQueryRest, SpendingTransactionRest,
+    SpendingTransactionsRest,
+};
+use crate::api::audit::util::{new_audit_entry, record_audit, snapshot};
+use crate::api::error::ApiError;
+use crate::categories::analytics::{totals_by_category, totals_by_month, totals_by_payee};
+use crate::categories::Categoriser;
+use crate::error::RepoError;
+use crate::models::audit::{ActorType, AuditAction, AuditEntity, AuditEntry, NewAuditEntry};
+use crate::models::category::{
+    CategoryOverride, FindCategoryOverrideQuery, FindSpendingQuery, NewCategoryOverride,
+    SpendingCategory,
+};
+use crate::models::transaction::Transaction;
+use crate::traits::{RepoCreate, RepoDeleteById, RepoFind, RepoGetById};
+
+const DEFAULT_SPENDING_DAYS: i64 = 365;
+const MAX_SPENDING_DAYS: i64 = 731;
+
+struct SpendingRange {
+    from: NaiveDate,
+    to: NaiveDate,
+}
+
+fn spending_range(query: &SpendingQueryRest) -> Result<SpendingRange, ApiError> {
+    let parse_date = |date: &Option<String>| -> Result<Option<NaiveDate>, ApiError> {
+        date.as_ref()
+            .map(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d"))
+            .transpose()
+            .map_err(|_| ApiError::BadRequest)
+    };
+
+    let to = parse_date(&query.to)?.unwrap_or(chrono::Utc::now().date_naive());
+    let from =
+        parse_date(&query.from)?.unwrap_or(to - chrono::Duration::days(DEFAULT_SPENDING_DAYS));
+
+    if from > to || (to - from).num_days() > MAX_SPENDING_DAYS {
+        return Err(ApiError::BadRequest);
+    }
+
+    Ok(SpendingRange { from, to })
+}
+
+// the customer's spending in range along with their overrides, narrowed to a category if asked
+async fn load_spending<TR, CR>(
+    transactions_repo: Data<TR>,
+    overrides_repo: Data<CR>,
+    categoriser: &Categoriser,
+    customer_id: i32,
+    query: &SpendingQueryRest,
+    range: &SpendingRange,
+) -> Result<(Vec<Transaction>, Vec<CategoryOverride>), ApiError>
+where
+    TR: RepoFind<Transaction, FindSpendingQuery>,
+    CR: RepoFind<CategoryOverride, FindCategoryOverrideQuery>,
+{
+    let spending_query = FindSpendingQuery {
+        customer_id,
+        account_number: query.account_number.clone(),
+        from: range.from.and_hms_opt(0, 0, 0).unwrap(),
+        to: range
+            .to
+            .succ_opt()
+            .ok_or(ApiError::BadRequest)?
+            .and_hms_opt(0, 0, 0)
+            .unwrap(),
+    };
+
+    let (transactions, overrides) = web::block(move || {
+        let transactions = transactions_repo.find(spending_query)?;
+        let overrides = overrides_repo.find(FindCategoryOverrideQuery { customer_id })?;
+        Ok::<_, RepoError>((transactions, overrides))
+    })
+    .await
+    .map_err(|_| ApiError::InternalError)?
+    .map_err(|_| ApiError::InternalError)?;
+
+    // enforced query on customer_id so should always be fine, but safe > sorry
+    if transactions.iter().any(|tr| tr.customer_id != customer_id)
+        || overrides.iter().any(|o| o.customer_id != customer_id)
+    {
+        return Err(ApiError::BadRequest);
+    }
+
+    let transactions = match query.category {
+        Some(category) => {
+            let category: SpendingCategory = category.into();
+            transactions
+                .into_iter()
+                .filter(|tr| categoriser.categorise(tr, &overrides) == category)
+                .collect()
+        }
+        None => transactions,
+    };
+
+    Ok((transactions, overrides))
+}
+
+pub async fn spending_by_category<TR, CR>(
+    transactions_repo: Data<TR>,
+    overrides_repo: Data<CR>,
+    categoriser: Data<Categoriser>,
+    path: Path<i32>,
+    query: Query<SpendingQueryRest>,
+) -> Result<HttpResponse, actix_web::Error>
+where
+    TR: RepoFind<Transaction, FindSpendingQuery>,
+    CR: RepoFind<CategoryOverride, FindCategoryOverrideQuery>,
+{
+    let customer_id = path.into_inner();
+    let range = spending_range(&query)?;
+
+    println!(
+        "Trying to get spending by category for customer {}",
+        customer_id
+    );
+
+    let (transactions, overrides) = load_spending(
+        transactions_repo,
+        overrides_repo,
+        &categoriser,
+        customer_id,
+        &query,
+        &range,
+    )
+    .await?;
+
+    let totals = totals_by_category(&categoriser, &transactions, &overrides);
+
+    Ok(HttpResponse::Ok()
+        .insert_header(ContentType::json())
+        .json(web::Json(SpendingByCategoryRest {
+            from: range.from.to_string(),
+            to: range.to.to_string(),
+            total_cents: totals.iter().map(|t| t.total_cents).sum(),
+            categories: totals.iter().map(|t| t.into()).collect(),
+        })))
+}
+
+pub async fn spending_by_month<TR, CR>(
+    transactions_repo: Data<TR>,
+    overrides_repo: Data<CR>,
+    categoriser: Data<Categoriser>,
+    path: Path<i32>,
+    query: Query<SpendingQueryRest>,
+) -> Result<HttpResponse, actix_web::Error>
+where
+    TR: RepoFind<Transaction, FindSpendingQuery>,
+    CR: RepoFind<CategoryOverride, FindCategoryOverrideQuery>,
+{
+    let customer_id = path.into_inner();
+    let range = spending_range(&query)?;
+
+    println!(
+        "Trying to get spending by month for customer {}",
+        customer_id
+    );
+
+    let (transactions, _) = load_spending(
+        transactions_repo,
+        overrides_repo,
+        &categoriser,
+        customer_id,
+        &query,
+        &range,
+    )
+    .await?;
+
+    let totals = totals_by_month(&transactions);
+
+    Ok(HttpResponse::Ok()
+        .insert_header(ContentType::json())
+        .json(web::Json(SpendingByMonthRest {
+            from: range.from.to_string(),
+            to: range.to.to_string(),
+            months: totals.iter().map(|t| t.into()).collect(),
+        })))
+}
+
+pub async fn spending_by_payee<TR, CR>(
+    transactions_repo: Data<TR>,
+    overrides_repo: Data<CR>,
+    categoriser: Data<Categoriser>,
+    path: Path<i32>,
+    query: Query<SpendingQueryRest>,
+) -> Result<HttpResponse, actix_web::Error>
+where
+    TR: RepoFind<Transaction, FindSpendingQuery>,
+    CR: RepoFind<CategoryOverride, FindCategoryOverrideQuery>,
+{
+    let customer_id = path.into_inner();
+    let range = spending_range(&query)?;
+
+    println!(
+        "Trying to get spending by payee for customer {}",
+        customer_id
+    );
+
+    let (transactions, overrides) = load_spending(
+        transactions_repo,
+        overrides_repo,
+        &categoriser,
+        customer_id,
+        &query,
+        &range,
+    )
+    .await?;
+
+    let totals = totals_by_payee(&categoriser, &transactions, &overrides);
+
+    Ok(HttpResponse::Ok()
+        .insert_header(ContentType::json())
+        .json(web::Json(SpendingByPayeeRest {
+            from: range.from.to_string(),
+            to: range.to.to_string(),
+            payees: totals.iter().map(|t| t.into()).collect(),
+        })))
+}
+
+pub async fn find_spending_transactions<TR, CR>(
+    transactions_repo: Data<TR>,
+    overrides_repo: Data<CR>,
+    categoriser: Data<Categoriser>,
+    path: Path<i32>,
+    query: Query<SpendingQueryRest>,
+) -> Result<HttpResponse, actix_web::Error>
+where
+    TR: RepoFind<Transaction, FindSpendingQuery>,
+    CR: RepoFind<CategoryOverride, FindCategoryOverrideQuery>,
+{
+    let customer_id = path.into_inner();
+    let range = spending_range(&query)?;
+
+    println!(
+        "Trying to get categorised spending for customer {}",
+        customer_id
+    );
+
+    let (transactions, overrides) = load_spending(
+        transactions_repo,
+        overrides_repo,
+        &categoriser,
+        customer_id,
+        &query,
+        &range,
+    )
+    .await?;
+
+    Ok(HttpResponse::Ok()
+        .insert_header(ContentType::json())
+        .json(web::Json(SpendingTransactionsRest {
+            transactions: transactions
+                .iter()
+                .map(|tr| {
+                    SpendingTransactionRest::from((tr, categoriser.categorise(tr, &overrides)))
+                })
+                .collect(),
+        })))
+}
+
+// remembered against the payee account so it sticks for their past and future transactions
+pub async fn set_transaction_category<TR, CR, AuR>(
+    transactions_repo: Data<TR>,
+    overrides_repo: Data<CR>,
+    audit_repo: Data<AuR>,
+    req: HttpRequest,
+    path: Path<(i32, i32)>,
+    payload: web::Json<CategoryUpdateRest>,
+) -> Result<HttpResponse, actix_web::Error>
+where
+    TR: RepoGetById<Transaction>,
+    CR: RepoCreate<CategoryOverride, NewCategoryOverride>,
+    AuR: RepoCreate<AuditEntry, NewAuditEntry>,
+{
+    let (customer_id, transaction_id) = path.into_inner();
+    let category: SpendingCategory = payload.into_inner().category.into();
+
+    println!(
+        "Trying to set category of transaction {} for customer {}",
+        transaction_id, customer_id
+    );
+
+    let category_override = web::block(move || {
+        let transaction = transactions_repo
+            .get_by_id(transaction_id)
+            .map_err(|err| match err {
+                RepoError::NotFound => ApiError::NotFound,
+                _ => ApiError::InternalError,
+            })?;
+
+        if transaction.customer_id != customer_id {
+            return Err(ApiError::Unauthorized);
+        }
+
+        // only money going out is categorised
+        if !transaction.from_us {
+            return Err(ApiError::BadRequest);
+        }
+
+        overrides_repo
+            .create(NewCategoryOverride {
+                customer_id,
+                account_number: transaction.to_number,
+                bsb: transaction.to_bsb,
+                category,
+            })
+            .map_err(|_| ApiError::InternalError)
+    })
+    .await
+    .map_err(|_| ApiError::InternalError)??;
+
+    let override_rest: CategoryOverrideRest = (&category_override).into();
+
+    let mut audit_entry = new_audit_entry(
+        &req,
+        ActorType::Customer,
+        customer_id,
+        AuditAction::SetCategoryOverride,
+        AuditEntity::CategoryOverride,
+        Some(category_override.id),
+    );
+    audit_entry.after_snapshot = snapshot(&override_rest);
+    record_audit(audit_repo, audit_entry).await;
+
+    Ok(HttpResponse::Ok()
+        .insert_header(ContentType::json())
+        .json(web::Json(override_rest)))
+}
+
+pub async fn find_category_overrides<CR>(
+    overrides_repo: Data<CR>,
+    path: Path<i32>,
+) -> Result<HttpResponse, actix_web::Error>
+where
+    CR: RepoFind<CategoryOverride, FindCategoryOverrideQuery>,
+{
+    let customer_id = path.into_inner();
+
+    println!(
+        "Trying to get category overrides for customer {}",
+        customer_id
+    );
+
+    let overrides =
+        web::block(move || overrides_repo.find(FindCategoryOverrideQuery { customer_id }))
+            .await
+            .map_err(|_| ApiError::InternalError)?
+            .map_err(|_| ApiError::InternalError)?;
+
+    // enforced query on customer_id so should always be fine, but safe > sorry
+    for category_override in overrides.iter() {
+        if category_override.customer_id != customer_id {
+            return Err(ApiError::BadRequest.into());
+        }
+    }
+
+    Ok(HttpResponse::Ok()
+        .insert_header(ContentType::json())
+        .json(web::Json::<CategoryOverridesRest>(overrides.into())))
+}
+
+pub async fn delete_category_override<CR, AuR>(
+    overrides_repo: Data<CR>,
+    audit_repo: Data<AuR>,
+    req: HttpRequest,
+    path: Path<(i32, i32)>,
+) -> Result<HttpResponse, actix_web::Error>
+where
+    CR: RepoGetById<CategoryOverride> + RepoDeleteById<CategoryOverride>,
+    AuR: RepoCreate<AuditEntry, NewAuditEntry>,
+{
+    let (customer_id, override_id) = path.into_inner();
+
+    println!(
+        "Trying to delete category override {} for customer {}",
+        override_id, customer_id
+    );
+
+    let deleted_override = web::block(move || {
+        let existing_override = match overrides_repo.get_by_id(override_id) {
+            Ok(category_override) => {
+                if category_override.customer_id != customer_id {
+                    return Err(ApiError::Unauthorized);
+                }
+                Some(category_override)
+            }
+            Err(RepoError::NotFound) => None,
+            _ => return Err(ApiError::InternalError),
+        };
+
+        overrides_repo
+            .delete_by_id(override_id)
+            .map_err(|_| ApiError::InternalError)?;
+
+        Ok(existing_override)
+    })
+    .await
+    .map_err(|_| ApiError::InternalError)??;
+
+    // deleting something that isn't there is a no-op, nothing to audit
+    if let Some(category_override) = deleted_override {
+        let mut audit_entry = new_audit_entry(
+            &req,
+            ActorType::Customer,
+            customer_id,
+            AuditAction::DeleteCategoryOverride,
+            AuditEntity::CategoryOverride,
+            Some(category_override.id),
+        );
+        audit_entry.before_snapshot = snapshot(&CategoryOverrideRest::from(&category_override));
+        record_audit(audit_repo, audit_entry).await;
+    }
+
+    Ok(HttpResponse::NoContent().body(""))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::audit::util::appended;
+    use actix_web::{
+        body::to_bytes,
+        http::StatusCode,
+        test,
+        web::{Data, Json, Query},
+    };
+    use chrono::{NaiveDate, NaiveDateTime};
+    use mockall::{mock, predicate::eq};
+
+    use crate::{
+        api::{
+            error::ApiError,
+            spending::{
+                handlers::{
+                    delete_category_override, set_transaction_category, spending_by_category,
+                    spending_by_month,
+                },
+                models::{
+                    CategoryOverrideRest, CategoryUpdateRest, SpendingByCategoryRest,
+                    SpendingByMonthRest, SpendingCategoryRest, SpendingQueryRest,
+                },
+            },
+        },
+        categories::Categoriser,
+        error::RepoError,
+        models::{
+            audit::{AuditEntry, NewAuditEntry},
+            category::{
+                CategoryOverride, FindCategoryOverrideQuery, FindSpendingQuery,
+                NewCategoryOverride, SpendingCategory,
+            },
+            transaction::{Transaction, TransactionStatus, TransactionType},
+        },
+        traits::{MockRepoCreate, MockRepoFind, MockRepoGetById, RepoDeleteById, RepoGetById},
+    };
+
+    fn at(m: u32, d: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2023, m, d)
+            .unwrap()
+            .and_hms_opt(9, 0, 0)
+            .unwrap()
+    }
+
+    fn spend(id: i32, to: &str, name: &str, amount_cents: i64, date: NaiveDateTime) -> Transaction {
+        Transaction {
+            id,
+            customer_id: 5,
+            transaction_type: TransactionType::External,
+            from_us: true,
+            amount_cents,
+            from_number: "938573843".to_string(),
+            from_bsb: "123456".to_string(),
+            from_name: None,
+            to_number: to.to_string(),
+            to_bsb: "654321".to_string(),
+            to_name: Some(name.to_string()),
+            available_balance_cents: 0,
+            date_start: date,
+            date_end: Some(date),
+            transaction_status: TransactionStatus::Success,
+            reversal_of: None,
+            reversed_amount_cents: 0,
+            reversal_reason: None,
+            description: None,
+            payer_reference: None,
+            payee_reference: None,
+            counterpart_of: None,
+            running_balance_cents: None,
+        }
+    }
+
+    fn category_override(
+        id: i32,
+        customer_id: i32,
+        category: SpendingCategory,
+    ) -> CategoryOverride {
+        CategoryOverride {
+            id,
+            customer_id,
+            account_number: "333333333".to_string(),
+            bsb: "654321".to_string(),
+            category,
+            created_at: at(8, 1),
+            updated_at: at(8, 1),
+        }
+    }
+
+    fn query(
+        from: &str,
+        to: &str,
+        category: Option<SpendingCategoryRest>,
+    ) -> Query<SpendingQueryRest> {
+        Query(SpendingQueryRest {
+            from: Some(from.to_string()),
+            to: Some(to.to_string()),
+            account_number: None,
+            category,
+        })
+    }
+
+    mock! {
+        pub CR { }
+        impl RepoGetById<CategoryOverride> for CR {
+            fn get_by_id(&self, id: i32) -> Result<CategoryOverride, RepoError>;
+        }
+        impl RepoDeleteById<CategoryOverride> for CR {
+            fn delete_by_id(&self, id: i32) -> Result<(), RepoError>;
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_spending_by_category_success() {
+        let mut mock_transactions_repo = MockRepoFind::<Transaction, FindSpendingQuery>::new();
+        mock_transactions_repo
+            .expect_find()
+            .with(eq(FindSpendingQuery {
+                customer_id: 5,
+                account_number: None,
+                from: NaiveDate::from_ymd_opt(2023, 7, 1)
+                    .unwrap()
+                    .and_hms_opt(0, 0, 0)
+                    .unwrap(),
+                // whole of the last day is included
+                to: NaiveDate::from_ymd_opt(2023, 9, 1)
+                    .unwrap()
+                    .and_hms_opt(0, 0, 0)
+                    .unwrap(),
+            }))
+            .times(1)
+            .returning(|_| {
+                Ok(vec![
+                    spend(1, "111111111", "Coles", 5000, at(7, 10)),
+                    spend(2, "222222222", "Uber Eats", 2500, at(8, 1)),
+                    spend(3, "333333333", "Coles", 1000, at(8, 5)),
+                ])
+            });
+
+        let mut mock_overrides_repo =
+            MockRepoFind::<CategoryOverride, FindCategoryOverrideQuery>::new();
+        mock_overrides_repo
+            .expect_find()
+            .with(eq(FindCategoryOverrideQuery { customer_id: 5 }))
+            .times(1)
+            .returning(|_| Ok(vec![category_override(1, 5, SpendingCategory::Health)]));
+
+        let res = spending_by_category(
+            Data::new(mock_transactions_repo),
+            Data::new(mock_overrides_repo),
+            Data::new(Categoriser::default()),
+            5.into(),
+            query("2023-07-01", "2023-08-31", None),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(StatusCode::OK, res.status());
+
+        let actual: SpendingByCategoryRest =
+            serde_json::from_slice(&to_bytes(res.into_body()).await.unwrap()).unwrap();
+        assert_eq!(8500, actual.total_cents);
+
+        let categories: Vec<(SpendingCategoryRest, i64)> = actual
+            .categories
+            .iter()
+            .map(|c| (c.category, c.total_cents))
+            .collect();
+        assert_eq!(
+            vec![
+                (SpendingCategoryRest::Groceries, 5000),
+                (SpendingCategoryRest::Dining, 2500),
+                (SpendingCategoryRest::Health, 1000),
+            ],
+            categories
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_spending_range_too_long_error() {
+        let mut mock_transactions_repo = MockRepoFind::<Transaction, FindSpendingQuery>::new();
+        mock_transactions_repo.expect_find().times(0);
+
+        let res = spending_by_category(
+            Data::new(mock_transactions_repo),
+            Data::new(MockRepoFind::<CategoryOverride, FindCategoryOverrideQuery>::new()),
+            Data::new(Categoriser::default()),
+            5.into(),
+            query("2020-01-01", "2023-08-31", None),
+        )
+        .await;
+
+        assert!(res.is_err_and(|e| { e.to_string() == ApiError::BadRequest.to_string() }));
+    }
+
+    #[actix_web::test]
+    async fn test_spending_by_month_in_category() {
+        let mut mock_transactions_repo = MockRepoFind::<Transaction, FindSpendingQuery>::new();
+        mock_transactions_repo
+            .expect_find()
+            .times(1)
+            .returning(|_| {
+                Ok(vec![
+                    spend(1, "111111111", "Coles", 5000, at(7, 10)),
+                    spend(2, "222222222", "Uber Eats", 2500, at(8, 1)),
+                    spend(3, "111111111", "Coles", 1000, at(8, 5)),
+                ])
+            });
+
+        let mut mock_overrides_repo =
+            MockRepoFind::<CategoryOverride, FindCategoryOverrideQuery>::new();
+        mock_overrides_repo
+            .expect_find()
+            .times(1)
+            .returning(|_| Ok(vec![]));
+
+        let res = spending_by_month(
+            Data::new(mock_transactions_repo),
+            Data::new(mock_overrides_repo),
+            Data::new(Categoriser::default()),
+            5.into(),
+            query(
+                "2023-07-01",
+                "2023-08-31",
+                Some(SpendingCategoryRest::Groceries),
+            ),
+        )
+        .await
+        .unwrap();
+
+        let actual: SpendingByMonthRest =
+            serde_json::from_slice(&to_bytes(res.into_body()).await.unwrap()).unwrap();
+        let months: Vec<(&str, i64)> = actual
+            .months
+            .iter()
+            .map(|m| (m.month.as_str(), m.total_cents))
+            .collect();
+        assert_eq!(vec![("2023-07", 5000), ("2023-08", 1000)], months);
+    }
+
+    #[actix_web::test]
+    async fn test_set_transaction_category_success() {
+        let mut mock_transactions_repo = MockRepoGetById::<Transaction>::new();
+        mock_transactions_repo
+            .expect_get_by_id()
+            .with(eq(3))
+            .times(1)
+            .returning(|_| Ok(spend(3, "333333333", "Chemist", 1000, at(8, 5))));
+
+        let mut mock_overrides_repo =
+            MockRepoCreate::<CategoryOverride, NewCategoryOverride>::new();
+        mock_overrides_repo
+            .expect_create()
+            .with(eq(NewCategoryOverride {
+                customer_id: 5,
+                account_number: "333333333".to_string(),
+                bsb: "654321".to_string(),
+                category: SpendingCategory::Entertainment,
+            }))
+            .times(1)
+            .returning(|_| Ok(category_override(1, 5, SpendingCategory::Entertainment)));
+
+        let mut mock_audit_repo = MockRepoCreate::<AuditEntry, NewAuditEntry>::new();
+        mock_audit_repo
+            .expect_create()
+            .withf(|entry| {
+                entry.action == "set_category_override" && entry.entity_type == "category_override"
+            })
+            .times(1)
+            .returning(|entry| Ok(appended(entry)));
+
+        let res = set_transaction_category(
+            Data::new(mock_transactions_repo),
+            Data::new(mock_overrides_repo),
+            Data::new(mock_audit_repo),
+            test::TestRequest::put().to_http_request(),
+            (5, 3).into(),
+            Json(CategoryUpdateRest {
+                category: SpendingCategoryRest::Entertainment,
+            }),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(StatusCode::OK, res.status());
+
+        let actual: CategoryOverrideRest =
+            serde_json::from_slice(&to_bytes(res.into_body()).await.unwrap()).unwrap();
+        assert_eq!(SpendingCategoryRest::Entertainment, actual.category);
+        assert_eq!("333333333", actual.account_number);
+    }
+
+    #[actix_web::test]
+    async fn test_set_transaction_category_incoming_error() {
+        let mut incoming = spend(3, "938573843", "Everyday", 1000, at(8, 5));
+        incoming.from_us = false;
+
+        let mut mock_transactions_repo = MockRepoGetById::<Transaction>::new();
+        mock_transactions_repo
+            .expect_get_by_id()
+            .times(1)
+            .returning(move |_| Ok(incoming.clone()));
+
+        let mut mock_overrides_repo =
+            MockRepoCreate::<CategoryOverride, NewCategoryOverride>::new();
+        mock_overrides_repo.expect_create().times(0);
+
+        let res = set_transaction_category(
+            Data::new(mock_transactions_repo),
+            Data::new(mock_overrides_repo),
+            Data::new(MockRepoCreate::<AuditEntry, NewAuditEntry>::new()),
+            test::TestRequest::put().to_http_request(),
+            (5, 3).into(),
+            Json(CategoryUpdateRest {
+                category: SpendingCategoryRest::Other,
+            }),
+        )
+        .await;
+
+        assert!(res.is_err_and(|e| { e.to_string() == ApiError::BadRequest.to_string() }));
+    }
+
+    #[actix_web::test]
+    async fn test_set_transaction_category_other_customer_error() {
+        let mut mock_transactions_repo = MockRepoGetById::<Transaction>::new();
+        mock_transactions_repo
+            .expect_get_by_id()
+            .times(1)
+            .returning(|_| Ok(spend(3, "333333333", "Chemist", 1000, at(8, 5))));
+
+        let mut mock_overrides_repo =
+            MockRepoCreate::<CategoryOverride, NewCategoryOverride>::new();
+        mock_overrides_repo.expect_create().times(0);
+
+        let res = set_transaction_category(
+            Data::new(mock_transactions_repo),
+            Data::new(mock_overrides_repo),
+            Data::new(MockRepoCreate::<AuditEntry, NewAuditEntry>::new()),
+            test::TestRequest::put().to_http_request(),
+            (6, 3).into(),
+            Json(CategoryUpdateRest {
+                category: SpendingCategoryRest::Other,
+            }),
+        )
+        .await;
+
+        assert!(res.is_err_and(|e| { e.to_string() == ApiError::Unauthorized.to_string() }));
+    }
+
+    #[actix_web::test]
+    async fn test_delete_category_override_other_customer_error() {
+        let mut mock_overrides_repo = MockCR::new();
+        mock_overrides_repo
+            .expect_get_by_id()
+            .times(1)
+            .returning(|id| Ok(category_override(id, 6, SpendingCategory::Health)));
+        mock_overrides_repo.expect_delete_by_id().times(0);
+
+        let res = delete_category_override(
+            Data::new(mock_overrides_repo),
+            Data::new(MockRepoCreate::<AuditEntry, NewAuditEntry>::new()),
+            test::TestRequest::delete().to_http_request(),
+            (5, 1).into(),
+        )
+        .await;
+
+        assert!(res.is_err_and(|e| { e.to_string() == ApiError::Unauthorized.to_string() }));
+    }
+}
@@ -0,0 +1,61 @@
+pub mod handlers;
+pub mod models;
+pub mod transform;
+
+use actix_web::web;
+
+use crate::{
+    api::spending,
+    models::{
+        audit::{AuditEntry, NewAuditEntry},
+        category::{
+            CategoryOverride, FindCategoryOverrideQuery, FindSpendingQuery, NewCategoryOverride,
+        },
+        transaction::Transaction,
+    },
+    traits::{RepoCreate, RepoDeleteById, RepoFind, RepoGetById},
+};
+
+pub fn configure_spending_api<TR, CR, AuR>(cfg: &mut web::ServiceConfig)
+where
+    TR: RepoFind<Transaction, FindSpendingQuery> + RepoGetById<Transaction>,
+    CR: RepoCreate<CategoryOverride, NewCategoryOverride>
+        + RepoFind<CategoryOverride, FindCategoryOverrideQuery>
+        + RepoGetById<CategoryOverride>
+        + RepoDeleteById<CategoryOverride>,
+    AuR: RepoCreate<AuditEntry, NewAuditEntry>,
+{
+    cfg.service(
+        web::scope("/api/customers/{customer_id}/spending")
+            .service(
+                web::resource("/by-category")
+                    .route(web::get().to(spending::handlers::spending_by_category::<TR, CR>)),
+            )
+            .service(
+                web::resource("/by-month")
+                    .route(web::get().to(spending::handlers::spending_by_month::<TR, CR>)),
+            )
+            .service(
+                web::resource("/by-payee")
+                    .route(web::get().to(spending::handlers::spending_by_payee::<TR, CR>)),
+            )
+            .service(
+                web::resource("/transactions")
+                    .route(web::get().to(spending::handlers::find_spending_transactions::<TR, CR>)),
+            )
+            .service(
+                web::resource("/transactions/{transaction_id}/category").route(
+                    web::put().to(spending::handlers::set_transaction_category::<TR, CR, AuR>),
+                ),
+            )
+            .service(
+                web::resource("/overrides")
+                    .route(web::get().to(spending::handlers::find_category_overrides::<CR>)),
+            )
+            .service(
+                web::resource("/overrides/{override_id}").route(
+                    web::delete().to(spending::handlers::delete_category_override::<CR, AuR>),
+                ),
+            ),
+    );
+}
@@ -0,0 +1,135 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Serialize, Copy, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum SpendingCategoryRest {
+    Groceries,
+    Dining,
+    Transport,
+    Utilities,
+    Housing,
+    Entertainment,
+    Shopping,
+    Health,
+    Transfers,
+    Other,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SpendingQueryRest {
+    // YYYY-MM-DD, inclusive. last 365 days up to today if not given
+    pub from: Option<String>,
+    pub to: Option<String>,
+    pub account_number: Option<String>,
+    // narrows to spending in one category
+    pub category: Option<SpendingCategoryRest>,
+}
+
+#[cfg_attr(test, derive(Deserialize, PartialEq, Debug))]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CategoryTotalRest {
+    pub category: SpendingCategoryRest,
+    pub total_cents: i64,
+    pub transaction_count: usize,
+}
+
+#[cfg_attr(test, derive(Deserialize, PartialEq, Debug))]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SpendingByCategoryRest {
+    pub from: String,
+    pub to: String,
+    pub total_cents: i64,
+    pub categories: Vec<CategoryTotalRest>,
+}
+
+#[cfg_attr(test, derive(Deserialize, PartialEq, Debug))]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MonthTotalRest {
+    pub month: String,
+    pub total_cents: i64,
+    pub transaction_count: usize,
+}
+
+#[cfg_attr(test, derive(Deserialize, PartialEq, Debug))]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SpendingByMonthRest {
+    pub from: String,
+    pub to: String,
+    pub months: Vec<MonthTotalRest>,
+}
+
+#[cfg_attr(test, derive(Deserialize, PartialEq, Debug))]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PayeeTotalRest {
+    pub account_number: String,
+    pub bsb: String,
+    pub name: Option<String>,
+    pub category: SpendingCategoryRest,
+    pub total_cents: i64,
+    pub transaction_count: usize,
+}
+
+#[cfg_attr(test, derive(Deserialize, PartialEq, Debug))]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SpendingByPayeeRest {
+    pub from: String,
+    pub to: String,
+    pub payees: Vec<PayeeTotalRest>,
+}
+
+#[cfg_attr(test, derive(Deserialize, PartialEq, Debug))]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SpendingTransactionRest {
+    pub id: i32,
+    pub from_number: String,
+    pub to_number: String,
+    pub to_bsb: String,
+    pub to_name: Option<String>,
+    pub description: Option<String>,
+    // amount less anything reversed
+    pub amount_cents: i64,
+    pub date_start: String,
+    pub category: SpendingCategoryRest,
+}
+
+#[cfg_attr(test, derive(Deserialize))]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SpendingTransactionsRest {
+    pub transactions: Vec<SpendingTransactionRest>,
+}
+
+#[cfg_attr(test, derive(Deserialize, PartialEq, Debug))]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CategoryOverrideRest {
+    pub id: i32,
+    pub customer_id: i32,
+    pub account_number: String,
+    pub bsb: String,
+    pub category: SpendingCategoryRest,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[cfg_attr(test, derive(Deserialize))]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CategoryOverridesRest {
+    pub overrides: Vec<CategoryOverrideRest>,
+}
+
+#[cfg_attr(test, derive(Serialize))]
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct CategoryUpdateRest {
+    pub category: SpendingCategoryRest,
+}
@@ -0,0 +1,113 @@
+use crate::categories::analytics::{CategoryTotal, MonthTotal, PayeeTotal};
+use crate::models::category::{CategoryOverride, SpendingCategory};
+use crate::models::transaction::Transaction;
+
+use super::models::{
+    CategoryOverrideRest, CategoryOverridesRest, CategoryTotalRest, MonthTotalRest, PayeeTotalRest,
+    SpendingCategoryRest, SpendingTransactionRest,
+};
+
+impl From<SpendingCategory> for SpendingCategoryRest {
+    fn from(category: SpendingCategory) -> Self {
+        match category {
+            SpendingCategory::Groceries => SpendingCategoryRest::Groceries,
+            SpendingCategory::Dining => SpendingCategoryRest::Dining,
+            SpendingCategory::Transport => SpendingCategoryRest::Transport,
+            SpendingCategory::Utilities => SpendingCategoryRest::Utilities,
+            SpendingCategory::Housing => SpendingCategoryRest::Housing,
+            SpendingCategory::Entertainment => SpendingCategoryRest::Entertainment,
+            SpendingCategory::Shopping => SpendingCategoryRest::Shopping,
+            SpendingCategory::Health => SpendingCategoryRest::Health,
+            SpendingCategory::Transfers => SpendingCategoryRest::Transfers,
+            SpendingCategory::Other => SpendingCategoryRest::Other,
+        }
+    }
+}
+
+impl From<SpendingCategoryRest> for SpendingCategory {
+    fn from(category: SpendingCategoryRest) -> Self {
+        match category {
+            SpendingCategoryRest::Groceries => SpendingCategory::Groceries,
+            SpendingCategoryRest::Dining => SpendingCategory::Dining,
+            SpendingCategoryRest::Transport => SpendingCategory::Transport,
+            SpendingCategoryRest::Utilities => SpendingCategory::Utilities,
+            SpendingCategoryRest::Housing => SpendingCategory::Housing,
+            SpendingCategoryRest::Entertainment => SpendingCategory::Entertainment,
+            SpendingCategoryRest::Shopping => SpendingCategory::Shopping,
+            SpendingCategoryRest::Health => SpendingCategory::Health,
+            SpendingCategoryRest::Transfers => SpendingCategory::Transfers,
+            SpendingCategoryRest::Other => SpendingCategory::Other,
+        }
+    }
+}
+
+impl From<&CategoryTotal> for CategoryTotalRest {
+    fn from(total: &CategoryTotal) -> Self {
+        Self {
+            category: total.category.into(),
+            total_cents: total.total_cents,
+            transaction_count: total.transaction_count,
+        }
+    }
+}
+
+impl From<&MonthTotal> for MonthTotalRest {
+    fn from(total: &MonthTotal) -> Self {
+        Self {
+            month: total.month.clone(),
+            total_cents: total.total_cents,
+            transaction_count: total.transaction_count,
+        }
+    }
+}
+
+impl From<&PayeeTotal> for PayeeTotalRest {
+    fn from(total: &PayeeTotal) -> Self {
+        Self {
+            account_number: total.account_number.clone(),
+            bsb: total.bsb.clone(),
+            name: total.name.clone(),
+            category: total.category.into(),
+            total_cents: total.total_cents,
+            transaction_count: total.transaction_count,
+        }
+    }
+}
+
+impl From<(&Transaction, SpendingCategory)> for SpendingTransactionRest {
+    fn from((transaction, category): (&Transaction, SpendingCategory)) -> Self {
+        Self {
+            id: transaction.id,
+            from_number: transaction.from_number.clone(),
+            to_number: transaction.to_number.clone(),
+            to_bsb: transaction.to_bsb.clone(),
+            to_name: transaction.to_name.clone(),
+            description: transaction.description.clone(),
+            amount_cents: transaction.amount_cents - transaction.reversed_amount_cents,
+            date_start: transaction.date_start.to_string(),
+            category: category.into(),
+        }
+    }
+}
+
+impl From<&CategoryOverride> for CategoryOverrideRest {
+    fn from(category_override: &CategoryOverride) -> Self {
+        Self {
+            id: category_override.id,
+            customer_id: category_override.customer_id,
+            account_number: category_override.account_number.clone(),
+            bsb: category_override.bsb.clone(),
+            category: category_override.category.into(),
+            created_at: category_override.created_at.to_string(),
+            updated_at: category_override.updated_at.to_string(),
+        }
+    }
+}
+
+impl From<Vec<CategoryOverride>> for CategoryOverridesRest {
+    fn from(overrides: Vec<CategoryOverride>) -> Self {
+        Self {
+            overrides: overrides.iter().map(CategoryOverrideRest::from).collect(),
+        }
+    }
+}
@@ -0,0 +1,646 @@
+use actix_web::http::header::ContentType;
+use actix_web::web::{Data, Path};
+use actix_web::{web, HttpRequest, HttpResponse};
+
+use super::models::{OtpCodeRest, StepUpChallengeRest, TotpEnrolmentRest, TotpSecretRest};
+use super::util::require_step_up;
+use crate::api::audit::util::{new_audit_entry, record_audit, snapshot};
+use crate::api::error::ApiError;
+use crate::error::RepoError;
+use crate::models::audit::{ActorType, AuditAction, AuditEntity, AuditEntry, NewAuditEntry};
+use crate::models::step_up::{
+    NewStepUpChallenge, NewTotpEnrolment, OtpMethod, StepUpAction, StepUpAttempt, StepUpChallenge,
+    StepUpRedemption, TotpConfirmation, TotpEnrolment,
+};
+use crate::step_up::{hash_code, is_well_formed, totp, CodeSender};
+use crate::traits::{RepoCreate, RepoDeleteById, RepoGetById, RepoUpdate};
+
+// every attempt is audited, wrong codes included
+pub async fn verify_step_up_challenge<SR, AuR>(
+    step_up_repo: Data<SR>,
+    audit_repo: Data<AuR>,
+    req: HttpRequest,
+    path: Path<(i32, i32)>,
+    payload: web::Json<OtpCodeRest>,
+) -> Result<HttpResponse, actix_web::Error>
+where
+    SR: RepoGetById<StepUpChallenge>
+        + RepoGetById<TotpEnrolment>
+        + RepoUpdate<StepUpChallenge, StepUpAttempt>,
+    AuR: RepoCreate<AuditEntry, NewAuditEntry>,
+{
+    let (customer_id, challenge_id) = path.into_inner();
+    let code = payload.into_inner().code;
+
+    if !is_well_formed(&code) {
+        return Err(ApiError::BadRequest.into());
+    }
+
+    println!(
+        "Trying to verify step-up challenge {} for customer {}",
+        challenge_id, customer_id
+    );
+
+    let challenge = web::block(move || {
+        let challenge: StepUpChallenge =
+            step_up_repo
+                .get_by_id(challenge_id)
+                .map_err(|err| match err {
+                    RepoError::NotFound => ApiError::NotFound,
+                    _ => ApiError::InternalError,
+                })?;
+
+        if challenge.customer_id != customer_id {
+            return Err(ApiError::Unauthorized);
+        }
+
+        let now = chrono::Utc::now().naive_utc();
+        if !challenge.is_open(now) {
+            return Err(ApiError::Conflict);
+        }
+
+        let attempt = match challenge.otp_method {
+            OtpMethod::Delivered => StepUpAttempt {
+                passed: challenge.code_hash == Some(hash_code(&code)),
+                totp_step: None,
+            },
+            OtpMethod::Totp => {
+                let enrolment: TotpEnrolment =
+                    step_up_repo
+                        .get_by_id(customer_id)
+                        .map_err(|err| match err {
+                            // removed since the challenge was issued
+                            RepoError::NotFound => ApiError::Conflict,
+                            _ => ApiError::InternalError,
+                        })?;
+                let step = totp::verify(&enrolment.secret, &code, now, enrolment.last_used_step);
+                StepUpAttempt {
+                    passed: step.is_some(),
+                    totp_step: step,
+                }
+            }
+        };
+
+        step_up_repo
+            .update(challenge_id, attempt)
+            .map_err(|err| match err {
+                // answered, used up or expired under us
+                RepoError::Conflict => ApiError::Conflict,
+                _ => ApiError::InternalError,
+            })
+    })
+    .await
+    .map_err(|_| ApiError::InternalError)??;
+
+    let challenge_rest: StepUpChallengeRest = (&challenge).into();
+
+    let mut audit_entry = new_audit_entry(
+        &req,
+        ActorType::Customer,
+        customer_id,
+        AuditAction::VerifyStepUp,
+        AuditEntity::StepUpChallenge,
+        Some(challenge_id),
+    );
+    audit_entry.after_snapshot = snapshot(&challenge_rest);
+    record_audit(audit_repo, audit_entry).await;
+
+    if challenge.verified_at.is_none() {
+        return Err(ApiError::Unauthorized.into());
+    }
+
+    Ok(HttpResponse::Ok()
+        .insert_header(ContentType::json())
+        .json(web::Json(challenge_rest)))
+}
+
+pub async fn get_totp_enrolment<SR>(
+    step_up_repo: Data<SR>,
+    path: Path<i32>,
+) -> Result<HttpResponse, actix_web::Error>
+where
+    SR: RepoGetById<TotpEnrolment>,
+{
+    let customer_id = path.into_inner();
+
+    println!("Trying to get totp enrolment for customer {}", customer_id);
+
+    let enrolment = web::block(move || step_up_repo.get_by_id(customer_id))
+        .await
+        .map_err(|_| ApiError::InternalError)?
+        .map_err(|err| match err {
+            RepoError::NotFound => ApiError::NotFound,
+            _ => ApiError::InternalError,
+        })?;
+
+    Ok(HttpResponse::Ok()
+        .insert_header(ContentType::json())
+        .json(web::Json::<TotpEnrolmentRest>((&enrolment).into())))
+}
+
+// a new secret for the customer's app, not used for anything until it's confirmed
+pub async fn enrol_totp<SR, AuR>(
+    step_up_repo: Data<SR>,
+    audit_repo: Data<AuR>,
+    req: HttpRequest,
+    path: Path<i32>,
+) -> Result<HttpResponse, actix_web::Error>
+where
+    SR: RepoCreate<TotpEnrolment, NewTotpEnrolment>,
+    AuR: RepoCreate<AuditEntry, NewAuditEntry>,
+{
+    let customer_id = path.into_inner();
+
+    let new_enrolment = NewTotpEnrolment {
+        customer_id,
+        secret: totp::generate_secret(),
+    };
+
+    println!("Trying to enrol totp for customer {}", customer_id);
+
+    let enrolment = web::block(move || step_up_repo.create(new_enrolment))
+        .await
+        .map_err(|_| ApiError::InternalError)?
+        .map_err(|err| match err {
+            // already has a confirmed one
+            RepoError::Conflict => ApiError::Conflict,
+            _ => ApiError::InternalError,
+        })?;
+
+    // never the secret
+    let mut audit_entry = new_audit_entry(
+        &req,
+        ActorType::Customer,
+        customer_id,
+        AuditAction::EnrolTotp,
+        AuditEntity::TotpEnrolment,
+        Some(customer_id),
+    );
+    audit_entry.after_snapshot = snapshot(&TotpEnrolmentRest::from(&enrolment));
+    record_audit(audit_repo, audit_entry).await;
+
+    Ok(HttpResponse::Created()
+        .insert_header(ContentType::json())
+        .json(web::Json(TotpSecretRest {
+            provisioning_uri: totp::provisioning_uri(&enrolment.secret, customer_id),
+            secret: enrolment.secret,
+        })))
+}
+
+pub async fn confirm_totp<SR, AuR>(
+    step_up_repo: Data<SR>,
+    audit_repo: Data<AuR>,
+    req: HttpRequest,
+    path: Path<i32>,
+    payload: web::Json<OtpCodeRest>,
+) -> Result<HttpResponse, actix_web::Error>
+where
+    SR: RepoGetById<TotpEnrolment> + RepoUpdate<TotpEnrolment, TotpConfirmation>,
+    AuR: RepoCreate<AuditEntry, NewAuditEntry>,
+{
+    let customer_id = path.into_inner();
+    let code = payload.into_inner().code;
+
+    if !is_well_formed(&code) {
+        return Err(ApiError::BadRequest.into());
+    }
+
+    println!("Trying to confirm totp for customer {}", customer_id);
+
+    let enrolment = web::block(move || {
+        let enrolment = step_up_repo
+            .get_by_id(customer_id)
+            .map_err(|err| match err {
+                RepoError::NotFound => ApiError::NotFound,
+                _ => ApiError::InternalError,
+            })?;
+
+        if enrolment.is_confirmed() {
+            return Err(ApiError::Conflict);
+        }
+
+        let step = totp::verify(
+            &enrolment.secret,
+            &code,
+            chrono::Utc::now().naive_utc(),
+            None,
+        )
+        .ok_or(ApiError::Unauthorized)?;
+
+        step_up_repo
+            .update(customer_id, TotpConfirmation { step })
+            .map_err(|err| match err {
+                RepoError::Conflict => ApiError::Conflict,
+                _ => ApiError::InternalError,
+            })
+    })
+    .await
+    .map_err(|_| ApiError::InternalError)??;
+
+    let enrolment_rest: TotpEnrolmentRest = (&enrolment).into();
+
+    let mut audit_entry = new_audit_entry(
+        &req,
+        ActorType::Customer,
+        customer_id,
+        AuditAction::ConfirmTotp,
+        AuditEntity::TotpEnrolment,
+        Some(customer_id),
+    );
+    audit_entry.after_snapshot = snapshot(&enrolment_rest);
+    record_audit(audit_repo, audit_entry).await;
+
+    Ok(HttpResponse::Ok()
+        .insert_header(ContentType::json())
+        .json(web::Json(enrolment_rest)))
+}
+
+// taking off a confirmed app is itself stepped up, with a code from that app
+pub async fn remove_totp<SR, CS, AuR>(
+    step_up_repo: Data<SR>,
+    code_sender: Data<CS>,
+    audit_repo: Data<AuR>,
+    req: HttpRequest,
+    path: Path<i32>,
+) -> Result<HttpResponse, actix_web::Error>
+where
+    SR: RepoGetById<TotpEnrolment>
+        + RepoDeleteById<TotpEnrolment>
+        + RepoCreate<StepUpChallenge, NewStepUpChallenge>
+        + RepoUpdate<StepUpChallenge, StepUpRedemption>,
+    CS: CodeSender,
+    AuR: RepoCreate<AuditEntry, NewAuditEntry>,
+{
+    let customer_id = path.into_inner();
+
+    println!("Trying to remove totp for customer {}", customer_id);
+
+    let lookup_repo = step_up_repo.clone();
+    let existing = match web::block(move || lookup_repo.get_by_id(customer_id))
+        .await
+        .map_err(|_| ApiError::InternalError)?
+    {
+        Ok(enrolment) => enrolment,
+        // deleting something that isn't there is a no-op
+        Err(RepoError::NotFound) => return Ok(HttpResponse::NoContent().body("")),
+        Err(_) => return Err(ApiError::InternalError.into()),
+    };
+
+    if existing.is_confirmed() {
+        if let Some(challenge) = require_step_up(
+            step_up_repo.clone(),
+            code_sender,
+            &req,
+            customer_id,
+            StepUpAction::RemoveTotp,
+            customer_id,
+        )
+        .await?
+        {
+            return Ok(challenge);
+        }
+    }
+
+    web::block(move || step_up_repo.delete_by_id(customer_id))
+        .await
+        .map_err(|_| ApiError::InternalError)?
+        .map_err(|_| ApiError::InternalError)?;
+
+    let mut audit_entry = new_audit_entry(
+        &req,
+        ActorType::Customer,
+        customer_id,
+        AuditAction::RemoveTotp,
+        AuditEntity::TotpEnrolment,
+        Some(customer_id),
+    );
+    audit_entry.before_snapshot = snapshot(&TotpEnrolmentRest::from(&existing));
+    record_audit(audit_repo, audit_entry).await;
+
+    Ok(HttpResponse::NoContent().body(""))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::audit::util::appended;
+    use crate::fixtures::audited_where;
+    use std::sync::Mutex;
+
+    use crate::{
+        api::{
+            error::ApiError,
+            step_up::{
+                handlers::{confirm_totp, enrol_totp, verify_step_up_challenge},
+                models::{OtpCodeRest, StepUpChallengeRest, TotpSecretRest},
+            },
+        },
+        error::RepoError,
+        models::{
+            audit::{AuditEntry, NewAuditEntry},
+            step_up::{
+                NewTotpEnrolment, OtpMethod, StepUpAction, StepUpAttempt, StepUpChallenge,
+                TotpConfirmation, TotpEnrolment,
+            },
+        },
+        step_up::hash_code,
+        traits::{MockRepoCreate, RepoGetById, RepoUpdate},
+    };
+
+    use actix_web::{
+        body::to_bytes,
+        http::StatusCode,
+        test,
+        web::{Data, Json},
+    };
+    use chrono::{Duration, Utc};
+    use mockall::{mock, predicate::eq};
+
+    // mockall can't mock RepoGetById twice on one type, so verify tests use this instead
+    struct VerifySuR {
+        challenge: StepUpChallenge,
+        attempts: Mutex<Vec<StepUpAttempt>>,
+    }
+
+    impl VerifySuR {
+        fn new(challenge: StepUpChallenge) -> Self {
+            VerifySuR {
+                challenge,
+                attempts: Mutex::new(vec![]),
+            }
+        }
+    }
+
+    impl RepoGetById<StepUpChallenge> for VerifySuR {
+        fn get_by_id(&self, id: i32) -> Result<StepUpChallenge, RepoError> {
+            if id != self.challenge.id {
+                return Err(RepoError::NotFound);
+            }
+            Ok(self.challenge.clone())
+        }
+    }
+
+    // no app, the challenges here are all delivered codes
+    impl RepoGetById<TotpEnrolment> for VerifySuR {
+        fn get_by_id(&self, _: i32) -> Result<TotpEnrolment, RepoError> {
+            Err(RepoError::NotFound)
+        }
+    }
+
+    impl RepoUpdate<StepUpChallenge, StepUpAttempt> for VerifySuR {
+        fn update(&self, _: i32, attempt: StepUpAttempt) -> Result<StepUpChallenge, RepoError> {
+            let passed = attempt.passed;
+            self.attempts.lock().unwrap().push(attempt);
+            Ok(StepUpChallenge {
+                attempts: self.challenge.attempts + 1,
+                verified_at: passed.then(|| Utc::now().naive_utc()),
+                ..self.challenge.clone()
+            })
+        }
+    }
+
+    mock! {
+        pub ConfirmSuR { }
+        impl RepoGetById<TotpEnrolment> for ConfirmSuR {
+            fn get_by_id(&self, id: i32) -> Result<TotpEnrolment, RepoError>;
+        }
+        impl RepoUpdate<TotpEnrolment, TotpConfirmation> for ConfirmSuR {
+            fn update(&self, id: i32, update: TotpConfirmation) -> Result<TotpEnrolment, RepoError>;
+        }
+    }
+
+    // customer 5 closing account 52, code 123456 sent to them
+    fn delivered_challenge() -> StepUpChallenge {
+        let created_at = Utc::now().naive_utc();
+        StepUpChallenge {
+            id: 9,
+            customer_id: 5,
+            step_up_action: StepUpAction::CloseAccount,
+            entity_id: 52,
+            otp_method: OtpMethod::Delivered,
+            code_hash: Some(hash_code("123456")),
+            attempts: 0,
+            expires_at: created_at + Duration::minutes(5),
+            verified_at: None,
+            redeemed_at: None,
+            created_at,
+            to_number: None,
+            to_bsb: None,
+            amount_cents: None,
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_verify_step_up_challenge_delivered_success() {
+        let step_up_repo = Data::new(VerifySuR::new(delivered_challenge()));
+
+        let res = verify_step_up_challenge(
+            step_up_repo.clone(),
+            Data::new(audited_where("verify_step_up", |entry| {
+                entry.entity_id == Some(9)
+            })),
+            test::TestRequest::post().to_http_request(),
+            (5, 9).into(),
+            Json(OtpCodeRest {
+                code: "123456".to_string(),
+            }),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(StatusCode::OK, res.status());
+        assert_eq!(
+            vec![StepUpAttempt {
+                passed: true,
+                totp_step: None,
+            }],
+            *step_up_repo.attempts.lock().unwrap()
+        );
+
+        let actual: StepUpChallengeRest =
+            serde_json::from_slice(&to_bytes(res.into_body()).await.unwrap()).unwrap();
+        assert!(actual.verified_at.is_some());
+        assert_eq!(4, actual.attempts_left);
+    }
+
+    #[actix_web::test]
+    async fn test_verify_step_up_challenge_wrong_code_unauthorized_error() {
+        let step_up_repo = Data::new(VerifySuR::new(delivered_challenge()));
+
+        let res = verify_step_up_challenge(
+            step_up_repo.clone(),
+            Data::new(audited_where("verify_step_up", |entry| {
+                entry.entity_id == Some(9)
+            })),
+            test::TestRequest::post().to_http_request(),
+            (5, 9).into(),
+            Json(OtpCodeRest {
+                code: "654321".to_string(),
+            }),
+        )
+        .await;
+
+        assert!(res.is_err_and(|e| { e.to_string() == ApiError::Unauthorized.to_string() }));
+        assert!(!step_up_repo.attempts.lock().unwrap()[0].passed);
+    }
+
+    #[actix_web::test]
+    async fn test_verify_step_up_challenge_used_up_conflict_error() {
+        let step_up_repo = Data::new(VerifySuR::new(StepUpChallenge {
+            attempts: 5,
+            ..delivered_challenge()
+        }));
+
+        let res = verify_step_up_challenge(
+            step_up_repo.clone(),
+            Data::new(MockRepoCreate::<AuditEntry, NewAuditEntry>::new()),
+            test::TestRequest::post().to_http_request(),
+            (5, 9).into(),
+            Json(OtpCodeRest {
+                code: "123456".to_string(),
+            }),
+        )
+        .await;
+
+        assert!(res.is_err_and(|e| { e.to_string() == ApiError::Conflict.to_string() }));
+        assert!(step_up_repo.attempts.lock().unwrap().is_empty());
+    }
+
+    #[actix_web::test]
+    async fn test_verify_step_up_challenge_other_customer_unauthorized_error() {
+        let step_up_repo = Data::new(VerifySuR::new(delivered_challenge()));
+
+        let res = verify_step_up_challenge(
+            step_up_repo.clone(),
+            Data::new(MockRepoCreate::<AuditEntry, NewAuditEntry>::new()),
+            test::TestRequest::post().to_http_request(),
+            (6, 9).into(),
+            Json(OtpCodeRest {
+                code: "123456".to_string(),
+            }),
+        )
+        .await;
+
+        assert!(res.is_err_and(|e| { e.to_string() == ApiError::Unauthorized.to_string() }));
+        assert!(step_up_repo.attempts.lock().unwrap().is_empty());
+    }
+
+    #[actix_web::test]
+    async fn test_enrol_totp_success() {
+        let mut mock_step_up_repo = MockRepoCreate::<TotpEnrolment, NewTotpEnrolment>::new();
+        mock_step_up_repo
+            .expect_create()
+            .withf(|new| new.customer_id == 5 && new.secret.len() == 32)
+            .times(1)
+            .returning(|new| {
+                Ok(TotpEnrolment {
+                    customer_id: new.customer_id,
+                    secret: new.secret,
+                    confirmed_at: None,
+                    last_used_step: None,
+                    created_at: Utc::now().naive_utc(),
+                })
+            });
+
+        let mut mock_audit_repo = MockRepoCreate::<AuditEntry, NewAuditEntry>::new();
+        mock_audit_repo
+            .expect_create()
+            .withf(|entry| {
+                entry.action == "enrol_totp"
+                    && entry
+                        .after_snapshot
+                        .as_ref()
+                        .is_some_and(|snapshot| !snapshot.contains("secret"))
+            })
+            .times(1)
+            .returning(|entry| Ok(appended(entry)));
+
+        let res = enrol_totp(
+            Data::new(mock_step_up_repo),
+            Data::new(mock_audit_repo),
+            test::TestRequest::post().to_http_request(),
+            5.into(),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(StatusCode::CREATED, res.status());
+
+        let actual: TotpSecretRest =
+            serde_json::from_slice(&to_bytes(res.into_body()).await.unwrap()).unwrap();
+        assert!(actual
+            .provisioning_uri
+            .starts_with("otpauth://totp/Lesser%20Bank:5?secret="));
+        assert!(actual.provisioning_uri.contains(&actual.secret));
+    }
+
+    #[actix_web::test]
+    async fn test_enrol_totp_already_confirmed_conflict_error() {
+        let mut mock_step_up_repo = MockRepoCreate::<TotpEnrolment, NewTotpEnrolment>::new();
+        mock_step_up_repo
+            .expect_create()
+            .times(1)
+            .returning(|_| Err(RepoError::Conflict));
+
+        let res = enrol_totp(
+            Data::new(mock_step_up_repo),
+            Data::new(MockRepoCreate::<AuditEntry, NewAuditEntry>::new()),
+            test::TestRequest::post().to_http_request(),
+            5.into(),
+        )
+        .await;
+
+        assert!(res.is_err_and(|e| { e.to_string() == ApiError::Conflict.to_string() }));
+    }
+
+    #[actix_web::test]
+    async fn test_confirm_totp_malformed_code_bad_request_error() {
+        let mut mock_step_up_repo = MockConfirmSuR::new();
+        mock_step_up_repo.expect_get_by_id().never();
+        mock_step_up_repo.expect_update().never();
+
+        let res = confirm_totp(
+            Data::new(mock_step_up_repo),
+            Data::new(MockRepoCreate::<AuditEntry, NewAuditEntry>::new()),
+            test::TestRequest::post().to_http_request(),
+            5.into(),
+            Json(OtpCodeRest {
+                code: "12345".to_string(),
+            }),
+        )
+        .await;
+
+        assert!(res.is_err_and(|e| { e.to_string() == ApiError::BadRequest.to_string() }));
+    }
+
+    #[actix_web::test]
+    async fn test_confirm_totp_already_confirmed_conflict_error() {
+        let mut mock_step_up_repo = MockConfirmSuR::new();
+        mock_step_up_repo
+            .expect_get_by_id()
+            .with(eq(5))
+            .times(1)
+            .returning(|_| {
+                Ok(TotpEnrolment {
+                    customer_id: 5,
+                    secret: "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ".to_string(),
+                    confirmed_at: Some(Utc::now().naive_utc()),
+                    last_used_step: None,
+                    created_at: Utc::now().naive_utc(),
+                })
+            });
+        mock_step_up_repo.expect_update().never();
+
+        let res = confirm_totp(
+            Data::new(mock_step_up_repo),
+            Data::new(MockRepoCreate::<AuditEntry, NewAuditEntry>::new()),
+            test::TestRequest::post().to_http_request(),
+            5.into(),
+            Json(OtpCodeRest {
+                code: "123456".to_string(),
+            }),
+        )
+        .await;
+
+        assert!(res.is_err_and(|e| { e.to_string() == ApiError::Conflict.to_string() }));
+    }
+}
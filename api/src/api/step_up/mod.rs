@@ -0,0 +1,53 @@
+pub mod handlers;
+pub mod models;
+pub mod transform;
+pub mod util;
+
+use actix_web::web;
+
+use crate::{
+    api::step_up,
+    models::{
+        audit::{AuditEntry, NewAuditEntry},
+        step_up::{
+            NewStepUpChallenge, NewTotpEnrolment, StepUpAttempt, StepUpChallenge, StepUpRedemption,
+            TotpConfirmation, TotpEnrolment,
+        },
+    },
+    step_up::CodeSender,
+    traits::{RepoCreate, RepoDeleteById, RepoGetById, RepoUpdate},
+};
+
+pub fn configure_step_up_api<SR, CS, AuR>(cfg: &mut web::ServiceConfig)
+where
+    SR: RepoGetById<TotpEnrolment>
+        + RepoCreate<TotpEnrolment, NewTotpEnrolment>
+        + RepoUpdate<TotpEnrolment, TotpConfirmation>
+        + RepoDeleteById<TotpEnrolment>
+        + RepoCreate<StepUpChallenge, NewStepUpChallenge>
+        + RepoGetById<StepUpChallenge>
+        + RepoUpdate<StepUpChallenge, StepUpAttempt>
+        + RepoUpdate<StepUpChallenge, StepUpRedemption>,
+    CS: CodeSender,
+    AuR: RepoCreate<AuditEntry, NewAuditEntry>,
+{
+    cfg.service(
+        web::scope("/api/customers/{customer_id}/totp")
+            .service(
+                web::resource("")
+                    .route(web::get().to(step_up::handlers::get_totp_enrolment::<SR>))
+                    .route(web::post().to(step_up::handlers::enrol_totp::<SR, AuR>))
+                    .route(web::delete().to(step_up::handlers::remove_totp::<SR, CS, AuR>)),
+            )
+            .service(
+                web::resource("/confirm")
+                    .route(web::post().to(step_up::handlers::confirm_totp::<SR, AuR>)),
+            ),
+    )
+    .service(
+        web::scope("/api/customers/{customer_id}/step-up-challenges").service(
+            web::resource("/{challenge_id}/verify")
+                .route(web::post().to(step_up::handlers::verify_step_up_challenge::<SR, AuR>)),
+        ),
+    );
+}
@@ -0,0 +1,58 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Serialize, Copy, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum StepUpActionRest {
+    NewPayeeTransfer,
+    NewDestinationTransfer,
+    CloseAccount,
+    RemoveTotp,
+}
+
+#[derive(Deserialize, Serialize, Copy, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum OtpMethodRest {
+    Totp,
+    Delivered,
+}
+
+// what a high-risk action answers with until it's retried with a verified challenge
+#[cfg_attr(test, derive(Deserialize, PartialEq, Debug))]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StepUpChallengeRest {
+    pub id: i32,
+    pub step_up_action: StepUpActionRest,
+    pub entity_id: i32,
+    pub otp_method: OtpMethodRest,
+    pub attempts_left: i32,
+    pub expires_at: String,
+    pub verified_at: Option<String>,
+    pub created_at: String,
+}
+
+#[cfg_attr(test, derive(Serialize))]
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct OtpCodeRest {
+    pub code: String,
+}
+
+// only ever shown the once, when enrolling
+#[cfg_attr(test, derive(Deserialize, PartialEq, Debug))]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TotpSecretRest {
+    pub secret: String,
+    pub provisioning_uri: String,
+}
+
+#[cfg_attr(test, derive(Deserialize, PartialEq, Debug))]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TotpEnrolmentRest {
+    pub customer_id: i32,
+    pub confirmed: bool,
+    pub confirmed_at: Option<String>,
+    pub created_at: String,
+}
@@ -0,0 +1,72 @@
+use crate::models::step_up::{
+    OtpMethod, StepUpAction, StepUpChallenge, TotpEnrolment, MAX_ATTEMPTS,
+};
+use crate::util::string_opt_from_naive_dt_opt;
+
+use super::models::{OtpMethodRest, StepUpActionRest, StepUpChallengeRest, TotpEnrolmentRest};
+
+impl From<StepUpAction> for StepUpActionRest {
+    fn from(action: StepUpAction) -> Self {
+        match action {
+            StepUpAction::NewPayeeTransfer => StepUpActionRest::NewPayeeTransfer,
+            StepUpAction::NewDestinationTransfer => StepUpActionRest::NewDestinationTransfer,
+            StepUpAction::CloseAccount => StepUpActionRest::CloseAccount,
+            StepUpAction::RemoveTotp => StepUpActionRest::RemoveTotp,
+        }
+    }
+}
+
+impl From<StepUpActionRest> for StepUpAction {
+    fn from(action: StepUpActionRest) -> Self {
+        match action {
+            StepUpActionRest::NewPayeeTransfer => StepUpAction::NewPayeeTransfer,
+            StepUpActionRest::NewDestinationTransfer => StepUpAction::NewDestinationTransfer,
+            StepUpActionRest::CloseAccount => StepUpAction::CloseAccount,
+            StepUpActionRest::RemoveTotp => StepUpAction::RemoveTotp,
+        }
+    }
+}
+
+impl From<OtpMethod> for OtpMethodRest {
+    fn from(method: OtpMethod) -> Self {
+        match method {
+            OtpMethod::Totp => OtpMethodRest::Totp,
+            OtpMethod::Delivered => OtpMethodRest::Delivered,
+        }
+    }
+}
+
+impl From<OtpMethodRest> for OtpMethod {
+    fn from(method: OtpMethodRest) -> Self {
+        match method {
+            OtpMethodRest::Totp => OtpMethod::Totp,
+            OtpMethodRest::Delivered => OtpMethod::Delivered,
+        }
+    }
+}
+
+impl From<&StepUpChallenge> for StepUpChallengeRest {
+    fn from(challenge: &StepUpChallenge) -> Self {
+        Self {
+            id: challenge.id,
+            step_up_action: challenge.step_up_action.into(),
+            entity_id: challenge.entity_id,
+            otp_method: challenge.otp_method.into(),
+            attempts_left: (MAX_ATTEMPTS - challenge.attempts).max(0),
+            expires_at: challenge.expires_at.to_string(),
+            verified_at: string_opt_from_naive_dt_opt(challenge.verified_at),
+            created_at: challenge.created_at.to_string(),
+        }
+    }
+}
+
+impl From<&TotpEnrolment> for TotpEnrolmentRest {
+    fn from(enrolment: &TotpEnrolment) -> Self {
+        Self {
+            customer_id: enrolment.customer_id,
+            confirmed: enrolment.is_confirmed(),
+            confirmed_at: string_opt_from_naive_dt_opt(enrolment.confirmed_at),
+            created_at: enrolment.created_at.to_string(),
+        }
+    }
+}
@@ -0,0 +1,194 @@
+use actix_web::http::header::ContentType;
+use actix_web::web::Data;
+use actix_web::{web, HttpRequest, HttpResponse};
+
+use super::models::StepUpChallengeRest;
+use crate::api::error::ApiError;
+use crate::error::RepoError;
+use crate::models::step_up::{
+    NewStepUpChallenge, OtpMethod, StepUpAction, StepUpChallenge, StepUpProof, StepUpRedemption,
+    TotpEnrolment, CHALLENGE_TTL_MINUTES,
+};
+use crate::step_up::{generate_code, hash_code, CodeSender};
+use crate::traits::{RepoCreate, RepoGetById, RepoUpdate};
+
+// the verified challenge a high-risk action is retried with
+pub const STEP_UP_HEADER: &str = "X-Step-Up-Challenge";
+
+// None when the request carries a verified challenge for this action, which it uses up. otherwise
+// a new challenge, already sent if it's a delivered code, for the handler to answer with instead of
+// going ahead
+pub async fn require_step_up<SR, CS>(
+    step_up_repo: Data<SR>,
+    code_sender: Data<CS>,
+    req: &HttpRequest,
+    customer_id: i32,
+    step_up_action: StepUpAction,
+    entity_id: i32,
+) -> Result<Option<HttpResponse>, ApiError>
+where
+    SR: RepoGetById<TotpEnrolment>
+        + RepoCreate<StepUpChallenge, NewStepUpChallenge>
+        + RepoUpdate<StepUpChallenge, StepUpRedemption>,
+    CS: CodeSender,
+{
+    let redemption = StepUpRedemption::action(customer_id, step_up_action, entity_id);
+
+    if let Some(challenge_id) = presented_challenge(req)? {
+        println!(
+            "Trying to redeem step-up challenge {} for customer {}",
+            challenge_id, customer_id
+        );
+
+        web::block(move || step_up_repo.update(challenge_id, redemption))
+            .await
+            .map_err(|_| ApiError::InternalError)?
+            .map_err(|err| match err {
+                // unverified, used, expired or for something else
+                RepoError::NotFound => ApiError::Forbidden,
+                _ => ApiError::InternalError,
+            })?;
+
+        return Ok(None);
+    }
+
+    let challenge = issue_challenge(step_up_repo, code_sender, redemption).await?;
+
+    Ok(Some(challenged(&challenge)))
+}
+
+pub enum StepUp {
+    // a verified challenge for exactly this transfer, for the repo to use up along with creating it
+    Presented(StepUpProof),
+    // a new challenge for the handler to answer with instead of going ahead
+    Challenged(HttpResponse),
+}
+
+// a transfer's challenge is only checked here, it's used up in the same db transaction that creates
+// the transfer so one that fails afterwards leaves the customer their code
+pub async fn step_up_transfer<SR, CS>(
+    step_up_repo: Data<SR>,
+    code_sender: Data<CS>,
+    req: &HttpRequest,
+    redemption: StepUpRedemption,
+) -> Result<StepUp, ApiError>
+where
+    SR: RepoGetById<TotpEnrolment>
+        + RepoGetById<StepUpChallenge>
+        + RepoCreate<StepUpChallenge, NewStepUpChallenge>,
+    CS: CodeSender,
+{
+    if let Some(challenge_id) = presented_challenge(req)? {
+        println!(
+            "Trying to check step-up challenge {} for customer {}",
+            challenge_id, redemption.customer_id
+        );
+
+        let challenge: StepUpChallenge = web::block(move || step_up_repo.get_by_id(challenge_id))
+            .await
+            .map_err(|_| ApiError::InternalError)?
+            .map_err(|err| match err {
+                RepoError::NotFound => ApiError::Forbidden,
+                _ => ApiError::InternalError,
+            })?;
+
+        // unverified, used, expired or for something else
+        if !challenge.is_redeemable_for(&redemption, chrono::Utc::now().naive_utc()) {
+            return Err(ApiError::Forbidden);
+        }
+
+        return Ok(StepUp::Presented(StepUpProof {
+            challenge_id,
+            redemption,
+        }));
+    }
+
+    let challenge = issue_challenge(step_up_repo, code_sender, redemption).await?;
+
+    Ok(StepUp::Challenged(challenged(&challenge)))
+}
+
+fn presented_challenge(req: &HttpRequest) -> Result<Option<i32>, ApiError> {
+    req.headers()
+        .get(STEP_UP_HEADER)
+        .map(|header| {
+            header
+                .to_str()
+                .ok()
+                .and_then(|id| id.parse::<i32>().ok())
+                .ok_or(ApiError::BadRequest)
+        })
+        .transpose()
+}
+
+fn challenged(challenge: &StepUpChallenge) -> HttpResponse {
+    HttpResponse::Unauthorized()
+        .insert_header(ContentType::json())
+        .json(web::Json::<StepUpChallengeRest>(challenge.into()))
+}
+
+// for whatever the redemption will be, a transfer's challenge carries its destination and amount
+async fn issue_challenge<SR, CS>(
+    step_up_repo: Data<SR>,
+    code_sender: Data<CS>,
+    redemption: StepUpRedemption,
+) -> Result<StepUpChallenge, ApiError>
+where
+    SR: RepoGetById<TotpEnrolment> + RepoCreate<StepUpChallenge, NewStepUpChallenge>,
+    CS: CodeSender,
+{
+    let customer_id = redemption.customer_id;
+
+    println!(
+        "Trying to issue {:?} step-up challenge for customer {}",
+        redemption.step_up_action, customer_id
+    );
+
+    let (challenge, code) = web::block(move || {
+        let enrolment: Result<TotpEnrolment, RepoError> = step_up_repo.get_by_id(customer_id);
+        let totp_confirmed = match enrolment {
+            Ok(enrolment) => enrolment.is_confirmed(),
+            Err(RepoError::NotFound) => false,
+            Err(_) => return Err(ApiError::InternalError),
+        };
+
+        let code = (!totp_confirmed).then(generate_code);
+
+        let new_challenge = NewStepUpChallenge {
+            customer_id,
+            step_up_action: redemption.step_up_action,
+            entity_id: redemption.entity_id,
+            otp_method: if totp_confirmed {
+                OtpMethod::Totp
+            } else {
+                OtpMethod::Delivered
+            },
+            code_hash: code.as_deref().map(hash_code),
+            expires_at: chrono::Utc::now().naive_utc()
+                + chrono::Duration::minutes(CHALLENGE_TTL_MINUTES),
+            to_number: redemption.to_number,
+            to_bsb: redemption.to_bsb,
+            amount_cents: redemption.amount_cents,
+        };
+        let challenge = step_up_repo
+            .create(new_challenge)
+            .map_err(|_| ApiError::InternalError)?;
+
+        Ok((challenge, code))
+    })
+    .await
+    .map_err(|_| ApiError::InternalError)??;
+
+    if let Some(code) = code {
+        // a challenge nobody can answer just expires
+        web::block(move || code_sender.send_code(customer_id, &code))
+            .await
+            .map_err(|_| ApiError::InternalError)?
+            .map_err(|err| {
+                println!("couldn't send code for challenge {}: {}", challenge.id, err);
+                ApiError::InternalError
+            })?;
+    }
+
+    Ok(challenge)
+}
@@ -1,32 +1,133 @@
 use actix_web::http::header::ContentType;
 use actix_web::web::{Data, Path, Query};
-use actix_web::{web, HttpResponse};
+use actix_web::{web, HttpRequest, HttpResponse};
 
-use super::models::{FindTransactionQueryRest, NewInternalTransactionRest};
+use super::models::{
+    FindTransactionQueryRest, NewInternalTransactionRest, NewReversalRest, TransferQuoteRest,
+};
+use crate::api::audit::util::{new_audit_entry, record_audit, snapshot};
+use crate::api::automation::util::run_automations;
+use crate::api::budgets::util::check_budgets;
 use crate::api::error::ApiError;
+use crate::api::fees::models::AppliedFeeRest;
+use crate::api::oauth::util::{require_consented_account, require_scope};
+use crate::api::step_up::util::{step_up_transfer, StepUp};
 use crate::api::transactions::models::{TransactionRest, TransactionsRest};
-use crate::models::account::{Account, FindAccountQuery};
-use crate::models::transaction::{FindTransactionQuery, NewTransaction, Transaction};
-use crate::traits::{RepoCreate, RepoFind};
+use crate::error::RepoError;
+use crate::fraud::{FraudEngine, ScreeningContext};
+use crate::models::account::{Account, AccountType, FindAccountQuery};
+use crate::models::account_holder::{NewSignedTransfer, NewTransferSignature};
+use crate::models::audit::{ActorType, AuditAction, AuditEntity, AuditEntry, NewAuditEntry};
+use crate::models::automation::{AutomationRun, AutomationTrigger};
+use crate::models::budget::{BudgetAlert, BudgetAlertDelivered, BudgetCheck};
+use crate::models::consent::Consent;
+use crate::models::fee::{AppliedFee, FindTransferFeeQuery};
+use crate::models::fraud_review::NewHeldTransaction;
+use crate::models::oauth::{FindOAuthTokenQuery, OAuthScope, OAuthToken};
+use crate::models::payee::{Payee, PayeeUsed};
+use crate::models::step_up::{
+    NewStepUpChallenge, StepUpAction, StepUpChallenge, StepUpRedemption, TotpEnrolment,
+};
+use crate::models::transaction::{
+    FindPaymentsToQuery, FindTransactionQuery, NewReversal, NewTransaction, Transaction,
+    TransactionStatus, TransactionType, MAX_DESCRIPTION_LEN,
+};
+use crate::models::transfer_limit::{FindTransferUsageQuery, TransferLimits, TransferUsage};
+use crate::notifications::Notifier;
+use crate::step_up::CodeSender;
+use crate::traits::{RepoCreate, RepoFind, RepoGetById, RepoUpdate};
+use crate::transfers::{check_transfer, screening_since, TransferCheck};
+use crate::util::start_of_day;
 
-pub async fn new_internal_transaction<AR, TR>(
+// how long after starting a transfer a customer can pull it back themselves, staff can any time
+const CUSTOMER_REVERSAL_WINDOW_MINUTES: i64 = 30;
+const MAX_REVERSAL_REASON_LEN: usize = 140;
+
+#[allow(clippy::too_many_arguments)]
+pub async fn new_internal_transaction<AR, TR, LR, PR, BuR, AtR, N, OR, SR, CS, AuR>(
     accounts_repo: Data<AR>,
     transactions_repo: Data<TR>,
+    limits_repo: Data<LR>,
+    payees_repo: Data<PR>,
+    // handlers only get 12 extractors, budgets come with the notifier that sends their alerts
+    (budgets_repo, notifier): (Data<BuR>, Data<N>),
+    automation_repo: Data<AtR>,
+    audit_repo: Data<AuR>,
+    // likewise the checks a transfer goes through before it's made, a third party's scope, step-up
+    // then fraud screening
+    (oauth_repo, step_up_repo, code_sender, fraud_engine): (
+        Data<OR>,
+        Data<SR>,
+        Data<CS>,
+        Data<FraudEngine>,
+    ),
+    req: HttpRequest,
     path: Path<i32>,
     payload: web::Json<NewInternalTransactionRest>,
 ) -> Result<HttpResponse, actix_web::Error>
 where
     AR: RepoFind<Account, FindAccountQuery>,
-    TR: RepoCreate<Transaction, NewTransaction>,
+    TR: RepoCreate<Transaction, NewSignedTransfer>
+        + RepoCreate<Transaction, NewHeldTransaction>
+        + RepoFind<Transaction, FindTransactionQuery>
+        + RepoFind<Transaction, FindPaymentsToQuery>,
+    LR: RepoGetById<TransferLimits> + RepoFind<TransferUsage, FindTransferUsageQuery>,
+    PR: RepoGetById<Payee> + RepoUpdate<Payee, PayeeUsed>,
+    BuR: RepoCreate<Vec<BudgetAlert>, BudgetCheck> + RepoUpdate<BudgetAlert, BudgetAlertDelivered>,
+    AtR: RepoCreate<Vec<AutomationRun>, AutomationTrigger>,
+    N: Notifier,
+    OR: RepoFind<OAuthToken, FindOAuthTokenQuery> + RepoGetById<Consent>,
+    SR: RepoGetById<TotpEnrolment>
+        + RepoGetById<StepUpChallenge>
+        + RepoCreate<StepUpChallenge, NewStepUpChallenge>,
+    CS: CodeSender,
+    AuR: RepoCreate<AuditEntry, NewAuditEntry>,
 {
     let customer_id = path.into_inner();
 
-    let mut new_transaction: NewTransaction = payload.into_inner().into();
+    let consent = require_scope(oauth_repo, &req, customer_id, OAuthScope::PaymentsWrite).await?;
+
+    let payload = payload.into_inner();
+    let payee_id = payload.payee_id;
+    let to_given = payload.to_number.is_some() || payload.to_bsb.is_some();
+    let mut new_transaction: NewTransaction = payload.into();
 
     if new_transaction.customer_id != customer_id {
         return Err(ApiError::Unauthorized.into());
     }
 
+    if !new_transaction.has_valid_text() {
+        return Err(ApiError::BadRequest.into());
+    }
+
+    let payee = match payee_id {
+        Some(payee_id) => {
+            let payee = get_customer_payee(payees_repo.clone(), customer_id, payee_id).await?;
+
+            // account details come from the payee, don't let the request disagree with it
+            if to_given
+                && (new_transaction.to_number != payee.account_number
+                    || new_transaction.to_bsb != payee.bsb)
+            {
+                return Err(ApiError::BadRequest.into());
+            }
+
+            new_transaction.to_number = payee.account_number.clone();
+            new_transaction.to_bsb = payee.bsb.clone();
+            new_transaction.to_name = Some(payee.nickname.clone());
+            if new_transaction.payee_reference.is_none() {
+                new_transaction.payee_reference = payee.default_reference.clone();
+            }
+            Some(payee)
+        }
+        None => {
+            if new_transaction.to_number.is_empty() || new_transaction.to_bsb.is_empty() {
+                return Err(ApiError::BadRequest.into());
+            }
+            None
+        }
+    };
+
     let account_query = FindAccountQuery {
         customer_id,
         account_id: None,
@@ -54,47 +155,348 @@ where
     }
 
     let account_from = &accounts[0];
+    require_consented_account(&consent, account_from.id)?;
+
+    // a loan is paid into, never drawn on by transfer
+    if account_from.account_type == AccountType::Loan {
+        return Err(ApiError::BadRequest.into());
+    }
+
+    if new_transaction.to_number == account_from.account_number
+        && new_transaction.to_bsb == account_from.bsb
+    {
+        return Err(ApiError::BadRequest.into());
+    }
 
-    if account_from.available_balance_cents < new_transaction.amount_cents {
-        println!("available balance would be negative");
+    if account_from.spendable_cents() < new_transaction.amount_cents {
+        // turned away before anything is attempted, dishonour fees are only for payments the bank
+        // tries to make itself
+        println!("transfer would take the account past its overdraft limit");
         return Err(ApiError::BadRequest.into());
     }
     new_transaction.available_balance_cents =
         account_from.available_balance_cents - new_transaction.amount_cents;
     new_transaction.from_name = account_from.account_name.clone();
+    // kept against the account's primary holder whichever holder made it, so are its limits
+    new_transaction.customer_id = account_from.customer_id;
+    let ledger_customer_id = account_from.customer_id;
+
+    let now = chrono::Utc::now().naive_utc();
+    let usage_query = FindTransferUsageQuery {
+        customer_id: ledger_customer_id,
+        since: start_of_day(now),
+    };
+
+    let (limits, usage) = web::block(move || {
+        let limits = limits_repo.get_by_id(ledger_customer_id)?;
+        let usage = limits_repo.find(usage_query)?;
+        Ok((limits, usage))
+    })
+    .await
+    .map_err(|_| ApiError::InternalError)?
+    .map_err(|_: RepoError| ApiError::InternalError)?;
+
+    let history_query = FindTransactionQuery {
+        transaction_id: None,
+        customer_id,
+        account_number: Some(account_from.account_number.clone()),
+        date_from: Some(screening_since(now)),
+        date_to: None,
+        search: None,
+    };
+
+    let history_repo = transactions_repo.clone();
+    let history = web::block(move || history_repo.find(history_query))
+        .await
+        .map_err(|_| ApiError::InternalError)?
+        .map_err(|_| ApiError::InternalError)?;
+
+    let check = check_transfer(
+        &limits,
+        &usage,
+        &fraud_engine,
+        &ScreeningContext {
+            transaction: &new_transaction,
+            account: account_from,
+            history: &history,
+            payee: payee.as_ref(),
+            now,
+        },
+    );
 
     println!(
-        "Trying to create {:?} transaction for customer {}",
-        new_transaction.transaction_type, customer_id
+        "Trying to create {:?} transaction for customer {}, checks {:?}",
+        new_transaction.transaction_type, customer_id, check
     );
 
-    let transaction = web::block(move || transactions_repo.create(new_transaction))
+    match check {
+        TransferCheck::LimitExceeded(_) => return Err(ApiError::LimitExceeded.into()),
+        TransferCheck::Reject(_) => return Err(ApiError::Forbidden.into()),
+        TransferCheck::Allow | TransferCheck::Hold(_) => {}
+    }
+
+    // the first payment anywhere, saved as a payee or typed in, once everything else about the
+    // transfer checks out
+    let payments_query = FindPaymentsToQuery {
+        customer_id,
+        to_number: new_transaction.to_number.clone(),
+        to_bsb: new_transaction.to_bsb.clone(),
+    };
+    let payments_repo = transactions_repo.clone();
+    let paid_before = !web::block(move || payments_repo.find(payments_query))
         .await
         .map_err(|_| ApiError::InternalError)?
-        .map_err(|_| ApiError::InternalError)?;
+        .map_err(|_| ApiError::InternalError)?
+        .is_empty();
+
+    let new_payee = payee.as_ref().is_some_and(|payee| payee.is_new());
+    let step_up = if new_payee || !paid_before {
+        let (step_up_action, entity_id) = match payee.as_ref() {
+            Some(payee) => (StepUpAction::NewPayeeTransfer, payee.id),
+            // nothing saved to tie the challenge to, so it's the account the money's leaving
+            None => (StepUpAction::NewDestinationTransfer, account_from.id),
+        };
+        // good for this destination and amount only, not whatever else is sent from the account
+        let redemption = StepUpRedemption {
+            customer_id,
+            step_up_action,
+            entity_id,
+            to_number: Some(new_transaction.to_number.clone()),
+            to_bsb: Some(new_transaction.to_bsb.clone()),
+            amount_cents: Some(new_transaction.amount_cents),
+        };
+        match step_up_transfer(step_up_repo, code_sender, &req, redemption).await? {
+            StepUp::Presented(proof) => Some(proof),
+            StepUp::Challenged(challenge) => return Ok(challenge),
+        }
+    } else {
+        None
+    };
+
+    // signed by whoever made it, the account decides if it waits for another holder
+    let transaction = match check {
+        TransferCheck::Hold(reasons) => {
+            let held = NewHeldTransaction {
+                transaction: new_transaction,
+                reasons,
+                signed_by: customer_id,
+                step_up,
+            };
+            web::block(move || transactions_repo.create(held))
+                .await
+                .map_err(|_| ApiError::InternalError)?
+                .map_err(|err| match err {
+                    // the challenge was used up by another request since it was checked
+                    RepoError::NotFound => ApiError::Forbidden,
+                    // another transfer got in first and used up what was left of a limit
+                    RepoError::LimitExceeded => ApiError::LimitExceeded,
+                    _ => ApiError::InternalError,
+                })?
+        }
+        _ => {
+            let signed = NewSignedTransfer {
+                transaction: new_transaction,
+                signed_by: customer_id,
+                step_up,
+            };
+            web::block(move || transactions_repo.create(signed))
+                .await
+                .map_err(|_| ApiError::InternalError)?
+                .map_err(|err| match err {
+                    // no such account to receive it, the challenge was used up by another request
+                    // since it was checked, or the balance moved under us
+                    RepoError::NotFound | RepoError::Conflict => ApiError::BadRequest,
+                    RepoError::LimitExceeded => ApiError::LimitExceeded,
+                    _ => ApiError::InternalError,
+                })?
+        }
+    };
+
+    if let Some(payee) = payee {
+        let used = PayeeUsed {
+            used_at: transaction.date_start,
+        };
+        // transfer already exists, a stale last used date isn't worth failing it over
+        match web::block(move || payees_repo.update(payee.id, used)).await {
+            Ok(Ok(_)) => {}
+            _ => println!("Couldn't mark payee {} as used", payee.id),
+        }
+    }
+
+    // held, unsigned or unapproved transfers are checked once they're let go and settle
+    if transaction.is_settled() {
+        check_budgets(budgets_repo.clone(), notifier.clone(), transaction.id).await;
+    }
+
+    let transaction_rest: TransactionRest = (&transaction).into();
 
-    // TODO: put job in queue to call handler /webhook/transaction/execute-transaction
-    // that will get second account and move the money around
+    let mut audit_entry = new_audit_entry(
+        &req,
+        ActorType::Customer,
+        customer_id,
+        AuditAction::CreateTransaction,
+        AuditEntity::Transaction,
+        Some(transaction.id),
+    );
+    audit_entry.after_snapshot = snapshot(&transaction_rest);
+    record_audit(audit_repo.clone(), audit_entry).await;
+
+    // round ups on the debit, transfers that wait get theirs once they settle
+    if transaction.is_settled() {
+        let trigger = AutomationTrigger::Settled {
+            transaction_id: transaction.id,
+        };
+        run_automations(
+            automation_repo,
+            (budgets_repo, notifier),
+            audit_repo,
+            &req,
+            trigger,
+        )
+        .await;
+    }
 
     Ok(HttpResponse::Created()
         .insert_header(ContentType::json())
-        .json(web::Json::<TransactionRest>((&transaction).into())))
+        .json(web::Json(transaction_rest)))
+}
+
+async fn get_customer_payee<PR>(
+    payees_repo: Data<PR>,
+    customer_id: i32,
+    payee_id: i32,
+) -> Result<Payee, ApiError>
+where
+    PR: RepoGetById<Payee>,
+{
+    println!(
+        "Trying to find payee {} for customer {}",
+        payee_id, customer_id
+    );
+
+    let payee = web::block(move || payees_repo.get_by_id(payee_id))
+        .await
+        .map_err(|_| ApiError::InternalError)?
+        .map_err(|err| match err {
+            RepoError::NotFound => ApiError::BadRequest,
+            _ => ApiError::InternalError,
+        })?;
+
+    if payee.customer_id != customer_id {
+        return Err(ApiError::Unauthorized);
+    }
+
+    Ok(payee)
+}
+
+// discloses what a transfer would cost without making it. the fee is judged on the month so far,
+// so it can change by the time the transfer is actually made
+pub async fn quote_transaction<AR, FR>(
+    accounts_repo: Data<AR>,
+    fees_repo: Data<FR>,
+    path: Path<i32>,
+    payload: web::Json<NewInternalTransactionRest>,
+) -> Result<HttpResponse, actix_web::Error>
+where
+    AR: RepoFind<Account, FindAccountQuery>,
+    FR: RepoFind<AppliedFee, FindTransferFeeQuery>,
+{
+    let customer_id = path.into_inner();
+
+    let new_transaction: NewTransaction = payload.into_inner().into();
+
+    if new_transaction.customer_id != customer_id {
+        return Err(ApiError::Unauthorized.into());
+    }
+
+    if new_transaction.amount_cents <= 0 {
+        return Err(ApiError::BadRequest.into());
+    }
+
+    let account_query = FindAccountQuery {
+        customer_id,
+        account_id: None,
+        account_number: Some(new_transaction.from_number.clone()),
+    };
+
+    println!(
+        "Trying to quote a transfer from account {:?} for customer {}",
+        account_query.account_number, customer_id
+    );
+
+    let accounts = web::block(move || accounts_repo.find(account_query))
+        .await
+        .map_err(|_| ApiError::InternalError)?
+        .map_err(|_| ApiError::BadRequest)?;
+
+    if accounts.len() != 1 {
+        return Err(ApiError::BadRequest.into());
+    }
+
+    let account_from = accounts[0].clone();
+
+    let fee_query = FindTransferFeeQuery {
+        account_id: account_from.id,
+        transaction_type: new_transaction.transaction_type,
+    };
+
+    let fees = web::block(move || fees_repo.find(fee_query))
+        .await
+        .map_err(|_| ApiError::InternalError)?
+        .map_err(|_| ApiError::InternalError)?;
+
+    let fees_cents: i64 = fees.iter().map(|fee| fee.amount_cents).sum();
+    let total_cents = new_transaction.amount_cents + fees_cents;
+
+    Ok(HttpResponse::Ok()
+        .insert_header(ContentType::json())
+        .json(web::Json(TransferQuoteRest {
+            amount_cents: new_transaction.amount_cents,
+            fees: fees.iter().map(AppliedFeeRest::from).collect(),
+            fees_cents,
+            total_cents,
+            available_balance_after_cents: account_from.available_balance_cents - total_cents,
+            sufficient_funds: account_from.spendable_cents() >= total_cents,
+        })))
 }
 
-pub async fn find_transactions<TR>(
+pub async fn find_transactions<AR, TR, OR>(
+    accounts_repo: Data<AR>,
     transactions_repo: Data<TR>,
+    oauth_repo: Data<OR>,
+    req: HttpRequest,
     path: Path<i32>,
     query: Query<FindTransactionQueryRest>,
 ) -> Result<HttpResponse, actix_web::Error>
 where
+    AR: RepoFind<Account, FindAccountQuery>,
     TR: RepoFind<Transaction, FindTransactionQuery>,
+    OR: RepoFind<OAuthToken, FindOAuthTokenQuery> + RepoGetById<Consent>,
 {
     let customer_id = path.into_inner();
 
+    let consent =
+        require_scope(oauth_repo, &req, customer_id, OAuthScope::TransactionsRead).await?;
+
+    let search = query
+        .search
+        .as_ref()
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty());
+    if search
+        .as_ref()
+        .is_some_and(|s| s.chars().count() > MAX_DESCRIPTION_LEN)
+    {
+        return Err(ApiError::BadRequest.into());
+    }
+
     let query = FindTransactionQuery {
         transaction_id: query.transaction_id,
         customer_id,
         account_number: query.account_number.clone(),
+        date_from: None,
+        date_to: None,
+        search,
     };
 
     if query.customer_id != customer_id {
@@ -103,21 +505,2365 @@ where
 
     println!("Trying to get transactions for customer {}", customer_id);
 
-    let transactions = web::block(move || transactions_repo.find(query))
-        .await
-        .map_err(|_| ApiError::InternalError)?
-        .map_err(|_| ApiError::InternalError)?;
+    let held_query = FindAccountQuery {
+        account_id: None,
+        customer_id,
+        account_number: None,
+    };
+
+    let (mut accounts, mut transactions) = web::block(move || {
+        let accounts = accounts_repo.find(held_query)?;
+        let transactions = transactions_repo.find(query)?;
+        Ok((accounts, transactions))
+    })
+    .await
+    .map_err(|_| ApiError::InternalError)?
+    .map_err(|_: RepoError| ApiError::InternalError)?;
 
-    // enforced query on customer_id so should always be fine, but safe > sorry
-    for acc in transactions.iter() {
-        if acc.customer_id != customer_id {
+    // enforced query on held accounts so should always be fine, but safe > sorry
+    for tr in transactions.iter() {
+        if tr.customer_id != customer_id && !holds_customer_account(&accounts, tr) {
             return Err(ApiError::BadRequest.into());
         }
     }
 
+    // a third party only sees transactions on the accounts the customer shared with it
+    if let Some(consent) = &consent {
+        accounts.retain(|acc| consent.includes_account(acc.id));
+        transactions.retain(|tr| holds_customer_account(&accounts, tr));
+    }
+
     println!("Got transactions for customer {}", customer_id);
 
     Ok(HttpResponse::Ok()
         .insert_header(ContentType::json())
         .json(web::Json::<TransactionsRest>(transactions.into())))
 }
+
+pub async fn reverse_transaction<AR, TR, AuR>(
+    accounts_repo: Data<AR>,
+    transactions_repo: Data<TR>,
+    audit_repo: Data<AuR>,
+    req: HttpRequest,
+    path: Path<(i32, i32)>,
+    payload: web::Json<NewReversalRest>,
+) -> Result<HttpResponse, actix_web::Error>
+where
+    AR: RepoFind<Account, FindAccountQuery>,
+    TR: RepoGetById<Transaction> + RepoCreate<Transaction, NewReversal>,
+    AuR: RepoCreate<AuditEntry, NewAuditEntry>,
+{
+    let (customer_id, transaction_id) = path.into_inner();
+
+    let mut new_reversal: NewReversal = payload.into_inner().into();
+    new_reversal.transaction_id = transaction_id;
+
+    println!(
+        "Trying to reverse transaction {} for customer {}",
+        transaction_id, customer_id
+    );
+
+    let original = get_reversible_transaction(transactions_repo.clone(), &new_reversal).await?;
+
+    if !holds_transaction(accounts_repo, customer_id, &original).await? {
+        return Err(ApiError::Unauthorized.into());
+    }
+
+    // customers can only recall money they sent. bank charges are refunded by staff
+    if !original.from_us || original.transaction_type == TransactionType::Fee {
+        return Err(ApiError::BadRequest.into());
+    }
+
+    let window = chrono::Duration::minutes(CUSTOMER_REVERSAL_WINDOW_MINUTES);
+    if chrono::Utc::now().naive_utc() - original.date_start > window {
+        println!(
+            "Transaction {} is outside the customer reversal window",
+            transaction_id
+        );
+        return Err(ApiError::BadRequest.into());
+    }
+
+    create_reversal(
+        transactions_repo,
+        audit_repo,
+        &req,
+        (ActorType::Customer, customer_id),
+        original,
+        new_reversal,
+    )
+    .await
+}
+
+// the account on the customer's side of the transaction is among those given
+fn holds_customer_account(accounts: &[Account], transaction: &Transaction) -> bool {
+    let (number, bsb) = transaction.customer_account();
+    accounts
+        .iter()
+        .any(|acc| acc.account_number == number && acc.bsb == bsb)
+}
+
+// a customer's own entries are always theirs, otherwise they have to hold the account
+async fn holds_transaction<AR>(
+    accounts_repo: Data<AR>,
+    customer_id: i32,
+    transaction: &Transaction,
+) -> Result<bool, ApiError>
+where
+    AR: RepoFind<Account, FindAccountQuery>,
+{
+    if transaction.customer_id == customer_id {
+        return Ok(true);
+    }
+
+    let account_query = FindAccountQuery {
+        account_id: None,
+        customer_id,
+        account_number: Some(transaction.customer_account().0.to_string()),
+    };
+
+    let accounts = web::block(move || accounts_repo.find(account_query))
+        .await
+        .map_err(|_| ApiError::InternalError)?
+        .map_err(|_| ApiError::InternalError)?;
+
+    Ok(holds_customer_account(&accounts, transaction))
+}
+
+// a second holder of a both to sign account agreeing to a transfer, which goes once signed
+#[allow(clippy::too_many_arguments)]
+pub async fn sign_transaction<AR, TR, BuR, N, AtR, AuR>(
+    accounts_repo: Data<AR>,
+    transactions_repo: Data<TR>,
+    budgets_repo: Data<BuR>,
+    notifier: Data<N>,
+    automation_repo: Data<AtR>,
+    audit_repo: Data<AuR>,
+    req: HttpRequest,
+    path: Path<(i32, i32)>,
+) -> Result<HttpResponse, actix_web::Error>
+where
+    AR: RepoFind<Account, FindAccountQuery>,
+    TR: RepoGetById<Transaction> + RepoCreate<Transaction, NewTransferSignature>,
+    BuR: RepoCreate<Vec<BudgetAlert>, BudgetCheck> + RepoUpdate<BudgetAlert, BudgetAlertDelivered>,
+    N: Notifier,
+    AtR: RepoCreate<Vec<AutomationRun>, AutomationTrigger>,
+    AuR: RepoCreate<AuditEntry, NewAuditEntry>,
+{
+    let (customer_id, transaction_id) = path.into_inner();
+
+    println!(
+        "Trying to sign transaction {} for customer {}",
+        transaction_id, customer_id
+    );
+
+    let get_repo = transactions_repo.clone();
+    let original = web::block(move || get_repo.get_by_id(transaction_id))
+        .await
+        .map_err(|_| ApiError::InternalError)?
+        .map_err(|err| match err {
+            RepoError::NotFound => ApiError::NotFound,
+            _ => ApiError::InternalError,
+        })?;
+
+    if !holds_transaction(accounts_repo, customer_id, &original).await? {
+        return Err(ApiError::Unauthorized.into());
+    }
+
+    if original.transaction_status != TransactionStatus::AwaitingSignature {
+        return Err(ApiError::Conflict.into());
+    }
+
+    let signature = NewTransferSignature {
+        transaction_id,
+        customer_id,
+    };
+
+    let transaction = web::block(move || transactions_repo.create(signature))
+        .await
+        .map_err(|_| ApiError::InternalError)?
+        .map_err(|err| match err {
+            // already signed it, or someone else's signature released it first
+            RepoError::Conflict => ApiError::Conflict,
+            RepoError::NotFound => ApiError::NotFound,
+            _ => ApiError::InternalError,
+        })?;
+
+    if transaction.is_settled() {
+        check_budgets(budgets_repo.clone(), notifier.clone(), transaction.id).await;
+    }
+
+    let transaction_rest: TransactionRest = (&transaction).into();
+
+    let mut audit_entry = new_audit_entry(
+        &req,
+        ActorType::Customer,
+        customer_id,
+        AuditAction::SignTransaction,
+        AuditEntity::Transaction,
+        Some(transaction.id),
+    );
+    audit_entry.before_snapshot = snapshot(&TransactionRest::from(&original));
+    audit_entry.after_snapshot = snapshot(&transaction_rest);
+    record_audit(audit_repo.clone(), audit_entry).await;
+
+    if transaction.is_settled() {
+        let trigger = AutomationTrigger::Settled {
+            transaction_id: transaction.id,
+        };
+        run_automations(
+            automation_repo,
+            (budgets_repo, notifier),
+            audit_repo,
+            &req,
+            trigger,
+        )
+        .await;
+    }
+
+    Ok(HttpResponse::Ok()
+        .insert_header(ContentType::json())
+        .json(web::Json(transaction_rest)))
+}
+
+pub async fn staff_reverse_transaction<TR, AuR>(
+    transactions_repo: Data<TR>,
+    audit_repo: Data<AuR>,
+    req: HttpRequest,
+    path: Path<(i32, i32)>,
+    payload: web::Json<NewReversalRest>,
+) -> Result<HttpResponse, actix_web::Error>
+where
+    TR: RepoGetById<Transaction> + RepoCreate<Transaction, NewReversal>,
+    AuR: RepoCreate<AuditEntry, NewAuditEntry>,
+{
+    let (staff_id, transaction_id) = path.into_inner();
+
+    let mut new_reversal: NewReversal = payload.into_inner().into();
+    new_reversal.transaction_id = transaction_id;
+
+    println!(
+        "Trying to reverse transaction {} for staff {}",
+        transaction_id, staff_id
+    );
+
+    let original = get_reversible_transaction(transactions_repo.clone(), &new_reversal).await?;
+
+    create_reversal(
+        transactions_repo,
+        audit_repo,
+        &req,
+        (ActorType::Staff, staff_id),
+        original,
+        new_reversal,
+    )
+    .await
+}
+
+// checks that can be made before locking anything, repo checks again under lock
+async fn get_reversible_transaction<TR>(
+    transactions_repo: Data<TR>,
+    new_reversal: &NewReversal,
+) -> Result<Transaction, ApiError>
+where
+    TR: RepoGetById<Transaction>,
+{
+    if new_reversal.reason.trim().is_empty()
+        || new_reversal.reason.len() > MAX_REVERSAL_REASON_LEN
+        || new_reversal.amount_cents.is_some_and(|amount| amount <= 0)
+    {
+        return Err(ApiError::BadRequest);
+    }
+
+    let transaction_id = new_reversal.transaction_id;
+
+    let original = web::block(move || transactions_repo.get_by_id(transaction_id))
+        .await
+        .map_err(|_| ApiError::InternalError)?
+        .map_err(|err| match err {
+            RepoError::NotFound => ApiError::NotFound,
+            _ => ApiError::InternalError,
+        })?;
+
+    if !original.is_reversible() {
+        return Err(ApiError::Conflict);
+    }
+
+    if new_reversal
+        .amount_cents
+        .is_some_and(|amount| amount > original.reversible_amount_cents())
+    {
+        return Err(ApiError::BadRequest);
+    }
+
+    Ok(original)
+}
+
+async fn create_reversal<TR, AuR>(
+    transactions_repo: Data<TR>,
+    audit_repo: Data<AuR>,
+    req: &HttpRequest,
+    (actor_type, actor_id): (ActorType, i32),
+    original: Transaction,
+    new_reversal: NewReversal,
+) -> Result<HttpResponse, actix_web::Error>
+where
+    TR: RepoCreate<Transaction, NewReversal>,
+    AuR: RepoCreate<AuditEntry, NewAuditEntry>,
+{
+    let compensating = web::block(move || transactions_repo.create(new_reversal))
+        .await
+        .map_err(|_| ApiError::InternalError)?
+        .map_err(|err| match err {
+            RepoError::NotFound => ApiError::NotFound,
+            // lost a race with another reversal
+            RepoError::Conflict => ApiError::Conflict,
+            _ => ApiError::InternalError,
+        })?;
+
+    let compensating_rest: TransactionRest = (&compensating).into();
+
+    let mut audit_entry = new_audit_entry(
+        req,
+        actor_type,
+        actor_id,
+        AuditAction::ReverseTransaction,
+        AuditEntity::Transaction,
+        Some(original.id),
+    );
+    audit_entry.before_snapshot = snapshot(&TransactionRest::from(&original));
+    audit_entry.after_snapshot = snapshot(&compensating_rest);
+    record_audit(audit_repo, audit_entry).await;
+
+    Ok(HttpResponse::Created()
+        .insert_header(ContentType::json())
+        .json(web::Json(compensating_rest)))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::audit::util::appended;
+    use crate::fixtures::audit_repo;
+    use actix_web::{
+        http::StatusCode,
+        test,
+        web::{Data, Json, Query},
+    };
+    use chrono::{Duration, NaiveDate, Utc};
+    use mockall::{mock, predicate::eq};
+    use std::sync::Mutex;
+
+    use crate::{
+        api::{
+            error::ApiError,
+            step_up::{
+                models::{OtpMethodRest, StepUpActionRest, StepUpChallengeRest},
+                util::STEP_UP_HEADER,
+            },
+            transactions::{
+                handlers::{
+                    find_transactions, new_internal_transaction, quote_transaction,
+                    reverse_transaction, sign_transaction, staff_reverse_transaction,
+                },
+                models::{
+                    FindTransactionQueryRest, NewInternalTransactionRest, NewReversalRest,
+                    TransactionRest, TransactionStatusRest, TransactionsRest, TransferQuoteRest,
+                },
+            },
+        },
+        error::RepoError,
+        fraud::FraudEngine,
+        models::{
+            account::{Account, AccountStatus, AccountType, FindAccountQuery, SigningRule},
+            account_holder::{NewSignedTransfer, NewTransferSignature},
+            audit::{ActorType, AuditEntry, NewAuditEntry},
+            automation::{AutomationRun, AutomationTrigger},
+            budget::{BudgetAlert, BudgetAlertDelivered, BudgetCheck},
+            consent::Consent,
+            fee::{AppliedFee, FeeKind, FindTransferFeeQuery},
+            fraud_review::NewHeldTransaction,
+            oauth::{FindOAuthTokenQuery, OAuthToken, OAuthTokenKind},
+            payee::{Payee, PayeeUsed},
+            step_up::{
+                NewStepUpChallenge, OtpMethod, StepUpAction, StepUpChallenge, StepUpProof,
+                StepUpRedemption, TotpEnrolment,
+            },
+            transaction::{
+                FindPaymentsToQuery, FindTransactionQuery, NewReversal, Transaction,
+                TransactionStatus, TransactionType,
+            },
+            transfer_limit::{FindTransferUsageQuery, TransferLimits, TransferUsage},
+        },
+        notifications::MockNotifier,
+        step_up::MockCodeSender,
+        traits::{MockRepoCreate, MockRepoFind, RepoCreate, RepoFind, RepoGetById, RepoUpdate},
+    };
+
+    mock! {
+        pub OR { }
+        impl RepoFind<OAuthToken, FindOAuthTokenQuery> for OR {
+            fn find(&self, query: FindOAuthTokenQuery) -> Result<Vec<OAuthToken>, RepoError>;
+        }
+        impl RepoGetById<Consent> for OR {
+            fn get_by_id(&self, id: i32) -> Result<Consent, RepoError>;
+        }
+    }
+
+    mock! {
+        pub TR { }
+        impl RepoGetById<Transaction> for TR {
+            fn get_by_id(&self, id: i32) -> Result<Transaction, RepoError>;
+        }
+        impl RepoCreate<Transaction, NewReversal> for TR {
+            fn create(&self, new: NewReversal) -> Result<Transaction, RepoError>;
+        }
+    }
+
+    fn transaction(id: i32, customer_id: i32, date_start: chrono::NaiveDateTime) -> Transaction {
+        Transaction {
+            id,
+            customer_id,
+            transaction_type: TransactionType::Internal,
+            from_us: true,
+            amount_cents: 10000,
+            from_number: "123456789".to_string(),
+            from_bsb: "123456".to_string(),
+            from_name: Some("Everyday".to_string()),
+            to_number: "987654321".to_string(),
+            to_bsb: "123456".to_string(),
+            to_name: None,
+            available_balance_cents: 5000,
+            date_start,
+            date_end: Some(date_start),
+            transaction_status: TransactionStatus::Success,
+            reversal_of: None,
+            reversed_amount_cents: 0,
+            reversal_reason: None,
+            description: None,
+            payer_reference: None,
+            payee_reference: None,
+            counterpart_of: None,
+            running_balance_cents: None,
+        }
+    }
+
+    // mockall can't mock RepoCreate twice on one type, so screening tests use this instead
+    #[derive(Default)]
+    struct ScreenedTR {
+        history: Vec<Transaction>,
+        // the destination has never been paid, it has unless set
+        new_destination: bool,
+        // what the repo leaves an allowed transfer in, settled when unset
+        outcome: Option<TransactionStatus>,
+        // another transfer used up the limit between the handler's check and the repo's
+        over_limit: bool,
+        created: Mutex<Vec<NewSignedTransfer>>,
+        held: Mutex<Vec<NewHeldTransaction>>,
+    }
+
+    impl RepoFind<Transaction, FindTransactionQuery> for ScreenedTR {
+        fn find(&self, _: FindTransactionQuery) -> Result<Vec<Transaction>, RepoError> {
+            Ok(self.history.clone())
+        }
+    }
+
+    impl RepoFind<Transaction, FindPaymentsToQuery> for ScreenedTR {
+        fn find(&self, _: FindPaymentsToQuery) -> Result<Vec<Transaction>, RepoError> {
+            if self.new_destination {
+                return Ok(vec![]);
+            }
+            Ok(vec![transaction(
+                40,
+                5,
+                Utc::now().naive_utc() - Duration::days(200),
+            )])
+        }
+    }
+
+    impl RepoCreate<Transaction, NewSignedTransfer> for ScreenedTR {
+        fn create(&self, new: NewSignedTransfer) -> Result<Transaction, RepoError> {
+            if self.over_limit {
+                return Err(RepoError::LimitExceeded);
+            }
+            self.created.lock().unwrap().push(new);
+            let mut created = transaction(70, 5, Utc::now().naive_utc());
+            if let Some(transaction_status) = self.outcome {
+                created.transaction_status = transaction_status;
+                created.date_end = None;
+            }
+            Ok(created)
+        }
+    }
+
+    impl RepoCreate<Transaction, NewHeldTransaction> for ScreenedTR {
+        fn create(&self, new: NewHeldTransaction) -> Result<Transaction, RepoError> {
+            if self.over_limit {
+                return Err(RepoError::LimitExceeded);
+            }
+            self.held.lock().unwrap().push(new);
+            let mut held = transaction(70, 5, Utc::now().naive_utc());
+            held.transaction_status = TransactionStatus::Held;
+            held.date_end = None;
+            Ok(held)
+        }
+    }
+
+    fn accounts_repo(available_balance_cents: i64) -> MockRepoFind<Account, FindAccountQuery> {
+        overdraft_accounts_repo(available_balance_cents, 0)
+    }
+
+    fn overdraft_accounts_repo(
+        available_balance_cents: i64,
+        overdraft_limit_cents: i64,
+    ) -> MockRepoFind<Account, FindAccountQuery> {
+        let mut mock_accounts_repo = MockRepoFind::<Account, FindAccountQuery>::new();
+        mock_accounts_repo
+            .expect_find()
+            .times(1)
+            .returning(move |_| {
+                Ok(vec![from_account(
+                    available_balance_cents,
+                    overdraft_limit_cents,
+                )])
+            });
+        mock_accounts_repo
+    }
+
+    fn from_account(available_balance_cents: i64, overdraft_limit_cents: i64) -> Account {
+        Account {
+            id: 52,
+            customer_id: 5,
+            balance_cents: available_balance_cents,
+            account_type: AccountType::Transaction,
+            available_balance_cents,
+            account_name: Some("Everyday".to_string()),
+            date_opened: Utc::now().naive_utc(),
+            account_status: AccountStatus::Active,
+            account_number: "123456789".to_string(),
+            bsb: "123456".to_string(),
+            overdraft_limit_cents,
+            signing_rule: SigningRule::EitherToSign,
+        }
+    }
+
+    mock! {
+        pub LR { }
+        impl RepoGetById<TransferLimits> for LR {
+            fn get_by_id(&self, id: i32) -> Result<TransferLimits, RepoError>;
+        }
+        impl RepoFind<TransferUsage, FindTransferUsageQuery> for LR {
+            fn find(&self, query: FindTransferUsageQuery) -> Result<Vec<TransferUsage>, RepoError>;
+        }
+    }
+
+    fn limits_repo(used_today_cents: i64) -> MockLR {
+        let mut mock_limits_repo = MockLR::new();
+        mock_limits_repo
+            .expect_get_by_id()
+            .times(1)
+            .returning(|customer_id| Ok(TransferLimits::effective(customer_id, None)));
+        mock_limits_repo.expect_find().times(1).returning(move |_| {
+            Ok(vec![TransferUsage {
+                transaction_type: TransactionType::Internal,
+                to_number: "555555555".to_string(),
+                to_bsb: "123456".to_string(),
+                amount_cents: used_today_cents,
+            }])
+        });
+        mock_limits_repo
+    }
+
+    fn new_internal(amount_cents: i64) -> Json<NewInternalTransactionRest> {
+        Json(NewInternalTransactionRest {
+            customer_id: 5,
+            amount_cents,
+            from_number: "123456789".to_string(),
+            from_bsb: "123456".to_string(),
+            payee_id: None,
+            to_number: Some("987654321".to_string()),
+            to_bsb: Some("123456".to_string()),
+            description: None,
+            payer_reference: None,
+            payee_reference: None,
+        })
+    }
+
+    mock! {
+        pub PR { }
+        impl RepoGetById<Payee> for PR {
+            fn get_by_id(&self, id: i32) -> Result<Payee, RepoError>;
+        }
+        impl RepoUpdate<Payee, PayeeUsed> for PR {
+            fn update(&self, id: i32, update: PayeeUsed) -> Result<Payee, RepoError>;
+        }
+    }
+
+    fn payee(first_used_at: Option<chrono::NaiveDateTime>) -> Payee {
+        Payee {
+            id: 8,
+            customer_id: 5,
+            nickname: "Landlord".to_string(),
+            account_number: "444444444".to_string(),
+            bsb: "654321".to_string(),
+            default_reference: Some("UNIT 4 RENT".to_string()),
+            created_at: Utc::now().naive_utc() - Duration::days(200),
+            first_used_at,
+            last_used_at: first_used_at,
+        }
+    }
+
+    // mockall can't mock RepoGetById twice on one type, so step-up tests use this instead. no
+    // redeeming, a transfer's challenge is used up by the repo that creates it
+    #[derive(Default)]
+    struct TransferSuR {
+        // the customer's app, if they have one
+        enrolment: Option<TotpEnrolment>,
+        // what the request comes back with, if it's been stepped up
+        challenge: Option<StepUpChallenge>,
+        issued: Mutex<Vec<NewStepUpChallenge>>,
+    }
+
+    impl RepoGetById<TotpEnrolment> for TransferSuR {
+        fn get_by_id(&self, _: i32) -> Result<TotpEnrolment, RepoError> {
+            self.enrolment.clone().ok_or(RepoError::NotFound)
+        }
+    }
+
+    impl RepoGetById<StepUpChallenge> for TransferSuR {
+        fn get_by_id(&self, id: i32) -> Result<StepUpChallenge, RepoError> {
+            self.challenge
+                .clone()
+                .filter(|challenge| challenge.id == id)
+                .ok_or(RepoError::NotFound)
+        }
+    }
+
+    impl RepoCreate<StepUpChallenge, NewStepUpChallenge> for TransferSuR {
+        fn create(&self, new: NewStepUpChallenge) -> Result<StepUpChallenge, RepoError> {
+            let challenge = StepUpChallenge {
+                id: 9,
+                customer_id: new.customer_id,
+                step_up_action: new.step_up_action,
+                entity_id: new.entity_id,
+                otp_method: new.otp_method,
+                code_hash: new.code_hash.clone(),
+                attempts: 0,
+                expires_at: new.expires_at,
+                verified_at: None,
+                redeemed_at: None,
+                created_at: Utc::now().naive_utc(),
+                to_number: new.to_number.clone(),
+                to_bsb: new.to_bsb.clone(),
+                amount_cents: new.amount_cents,
+            };
+            self.issued.lock().unwrap().push(new);
+            Ok(challenge)
+        }
+    }
+
+    fn totp_enrolment() -> TotpEnrolment {
+        TotpEnrolment {
+            customer_id: 5,
+            secret: "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ".to_string(),
+            confirmed_at: Some(Utc::now().naive_utc() - Duration::days(30)),
+            last_used_step: None,
+            created_at: Utc::now().naive_utc() - Duration::days(30),
+        }
+    }
+
+    // verified for the transfer new_internal makes to a destination it hasn't paid before
+    fn verified_challenge(amount_cents: i64) -> StepUpChallenge {
+        let created_at = Utc::now().naive_utc();
+        StepUpChallenge {
+            id: 9,
+            customer_id: 5,
+            step_up_action: StepUpAction::NewDestinationTransfer,
+            entity_id: 52,
+            otp_method: OtpMethod::Totp,
+            code_hash: None,
+            attempts: 1,
+            expires_at: created_at + Duration::minutes(5),
+            verified_at: Some(created_at),
+            redeemed_at: None,
+            created_at,
+            to_number: Some("987654321".to_string()),
+            to_bsb: Some("123456".to_string()),
+            amount_cents: Some(amount_cents),
+        }
+    }
+
+    fn destination_redemption(amount_cents: i64) -> StepUpRedemption {
+        StepUpRedemption {
+            customer_id: 5,
+            step_up_action: StepUpAction::NewDestinationTransfer,
+            entity_id: 52,
+            to_number: Some("987654321".to_string()),
+            to_bsb: Some("123456".to_string()),
+            amount_cents: Some(amount_cents),
+        }
+    }
+
+    mock! {
+        pub BuR { }
+        impl RepoCreate<Vec<BudgetAlert>, BudgetCheck> for BuR {
+            fn create(&self, new: BudgetCheck) -> Result<Vec<BudgetAlert>, RepoError>;
+        }
+        impl RepoUpdate<BudgetAlert, BudgetAlertDelivered> for BuR {
+            fn update(&self, id: i32, update: BudgetAlertDelivered) -> Result<BudgetAlert, RepoError>;
+        }
+    }
+
+    // no budgets to go over
+    fn budgets_repo() -> MockBuR {
+        let mut mock_budgets_repo = MockBuR::new();
+        mock_budgets_repo.expect_create().returning(|_| Ok(vec![]));
+        mock_budgets_repo
+    }
+
+    // no rules set up
+    fn automation_repo() -> MockRepoCreate<Vec<AutomationRun>, AutomationTrigger> {
+        let mut mock_automation_repo =
+            MockRepoCreate::<Vec<AutomationRun>, AutomationTrigger>::new();
+        mock_automation_repo
+            .expect_create()
+            .returning(|_| Ok(vec![]));
+        mock_automation_repo
+    }
+
+    #[actix_web::test]
+    async fn test_new_internal_transaction_known_payee_allowed() {
+        let transactions_repo = Data::new(ScreenedTR {
+            history: vec![transaction(
+                60,
+                5,
+                Utc::now().naive_utc() - Duration::days(3),
+            )],
+            ..Default::default()
+        });
+
+        let res = new_internal_transaction(
+            Data::new(accounts_repo(1_000_000)),
+            transactions_repo.clone(),
+            Data::new(limits_repo(0)),
+            Data::new(MockPR::new()),
+            (Data::new(budgets_repo()), Data::new(MockNotifier::new())),
+            Data::new(automation_repo()),
+            Data::new(audit_repo(1)),
+            (
+                Data::new(MockOR::new()),
+                Data::new(TransferSuR::default()),
+                Data::new(MockCodeSender::new()),
+                Data::new(FraudEngine::default()),
+            ),
+            test::TestRequest::post().to_http_request(),
+            5.into(),
+            new_internal(50_000),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(StatusCode::CREATED, res.status());
+        assert_eq!(1, transactions_repo.created.lock().unwrap().len());
+        assert!(transactions_repo.held.lock().unwrap().is_empty());
+    }
+
+    #[actix_web::test]
+    async fn test_new_internal_transaction_unaudited_still_created() {
+        let transactions_repo = Data::new(ScreenedTR {
+            history: vec![transaction(
+                60,
+                5,
+                Utc::now().naive_utc() - Duration::days(3),
+            )],
+            ..Default::default()
+        });
+
+        let mut mock_audit_repo = MockRepoCreate::<AuditEntry, NewAuditEntry>::new();
+        mock_audit_repo
+            .expect_create()
+            .times(1)
+            .returning(|_| Err(RepoError::Other));
+
+        // round ups still run off a transfer that went through
+        let mut mock_automation_repo =
+            MockRepoCreate::<Vec<AutomationRun>, AutomationTrigger>::new();
+        mock_automation_repo
+            .expect_create()
+            .times(1)
+            .returning(|_| Ok(vec![]));
+
+        let res = new_internal_transaction(
+            Data::new(accounts_repo(1_000_000)),
+            transactions_repo.clone(),
+            Data::new(limits_repo(0)),
+            Data::new(MockPR::new()),
+            (Data::new(budgets_repo()), Data::new(MockNotifier::new())),
+            Data::new(mock_automation_repo),
+            Data::new(mock_audit_repo),
+            (
+                Data::new(MockOR::new()),
+                Data::new(TransferSuR::default()),
+                Data::new(MockCodeSender::new()),
+                Data::new(FraudEngine::default()),
+            ),
+            test::TestRequest::post().to_http_request(),
+            5.into(),
+            new_internal(50_000),
+        )
+        .await
+        .unwrap();
+
+        // the transfer committed, so the client has to hear it did or a retry sends it twice
+        assert_eq!(StatusCode::CREATED, res.status());
+        assert_eq!(1, transactions_repo.created.lock().unwrap().len());
+    }
+
+    #[actix_web::test]
+    async fn test_new_internal_transaction_new_payee_held() {
+        let transactions_repo = Data::new(ScreenedTR::default());
+
+        let res = new_internal_transaction(
+            Data::new(accounts_repo(1_000_000)),
+            transactions_repo.clone(),
+            Data::new(limits_repo(0)),
+            Data::new(MockPR::new()),
+            (Data::new(budgets_repo()), Data::new(MockNotifier::new())),
+            Data::new(automation_repo()),
+            Data::new(audit_repo(1)),
+            (
+                Data::new(MockOR::new()),
+                Data::new(TransferSuR::default()),
+                Data::new(MockCodeSender::new()),
+                Data::new(FraudEngine::default()),
+            ),
+            test::TestRequest::post().to_http_request(),
+            5.into(),
+            new_internal(200_000),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(StatusCode::CREATED, res.status());
+        assert!(transactions_repo.created.lock().unwrap().is_empty());
+
+        let actual: TransactionRest =
+            serde_json::from_slice(&actix_web::body::to_bytes(res.into_body()).await.unwrap())
+                .unwrap();
+        assert_eq!(TransactionStatusRest::Held, actual.transaction_status);
+
+        let held = transactions_repo.held.lock().unwrap();
+        assert_eq!(1, held.len());
+        assert_eq!(200_000, held[0].transaction.amount_cents);
+        assert!(!held[0].reasons.is_empty());
+    }
+
+    #[actix_web::test]
+    async fn test_new_internal_transaction_rejected_forbidden() {
+        let transactions_repo = Data::new(ScreenedTR::default());
+
+        // limits raised well past the amount so screening is what stops it
+        let mut raised_limits = MockLR::new();
+        raised_limits
+            .expect_get_by_id()
+            .times(1)
+            .returning(|customer_id| {
+                let mut limits = TransferLimits::effective(customer_id, None);
+                limits.per_transaction_cents = 10_000_000;
+                limits.daily_cents = 10_000_000;
+                limits.daily_internal_cents = 10_000_000;
+                limits.per_payee_daily_cents = 10_000_000;
+                Ok(limits)
+            });
+        raised_limits
+            .expect_find()
+            .times(1)
+            .returning(|_| Ok(vec![]));
+
+        let res = new_internal_transaction(
+            Data::new(accounts_repo(100_000_000)),
+            transactions_repo.clone(),
+            Data::new(raised_limits),
+            Data::new(MockPR::new()),
+            (Data::new(budgets_repo()), Data::new(MockNotifier::new())),
+            Data::new(automation_repo()),
+            Data::new(audit_repo(0)),
+            (
+                Data::new(MockOR::new()),
+                Data::new(TransferSuR::default()),
+                Data::new(MockCodeSender::new()),
+                Data::new(FraudEngine::default()),
+            ),
+            test::TestRequest::post().to_http_request(),
+            5.into(),
+            new_internal(5_000_000),
+        )
+        .await;
+
+        assert!(res.is_err_and(|e| { e.to_string() == ApiError::Forbidden.to_string() }));
+        assert!(transactions_repo.created.lock().unwrap().is_empty());
+        assert!(transactions_repo.held.lock().unwrap().is_empty());
+    }
+
+    #[actix_web::test]
+    async fn test_new_internal_transaction_over_daily_limit_error() {
+        let transactions_repo = Data::new(ScreenedTR::default());
+
+        let res = new_internal_transaction(
+            Data::new(accounts_repo(100_000_000)),
+            transactions_repo.clone(),
+            // default daily is 2m
+            Data::new(limits_repo(1_950_000)),
+            Data::new(MockPR::new()),
+            (Data::new(budgets_repo()), Data::new(MockNotifier::new())),
+            Data::new(automation_repo()),
+            Data::new(audit_repo(0)),
+            (
+                Data::new(MockOR::new()),
+                Data::new(TransferSuR::default()),
+                Data::new(MockCodeSender::new()),
+                Data::new(FraudEngine::default()),
+            ),
+            test::TestRequest::post().to_http_request(),
+            5.into(),
+            new_internal(60_000),
+        )
+        .await;
+
+        assert!(res.is_err_and(|e| { e.to_string() == ApiError::LimitExceeded.to_string() }));
+        assert!(transactions_repo.created.lock().unwrap().is_empty());
+    }
+
+    #[actix_web::test]
+    async fn test_new_internal_transaction_over_daily_limit_by_then_error() {
+        // under the limit when the handler looked, another transfer made at the same time wasn't
+        let transactions_repo = Data::new(ScreenedTR {
+            over_limit: true,
+            ..Default::default()
+        });
+
+        let res = new_internal_transaction(
+            Data::new(accounts_repo(100_000_000)),
+            transactions_repo.clone(),
+            Data::new(limits_repo(1_900_000)),
+            Data::new(MockPR::new()),
+            (Data::new(MockBuR::new()), Data::new(MockNotifier::new())),
+            Data::new(MockRepoCreate::<Vec<AutomationRun>, AutomationTrigger>::new()),
+            Data::new(audit_repo(0)),
+            (
+                Data::new(MockOR::new()),
+                Data::new(TransferSuR::default()),
+                Data::new(MockCodeSender::new()),
+                Data::new(FraudEngine::default()),
+            ),
+            test::TestRequest::post().to_http_request(),
+            5.into(),
+            new_internal(60_000),
+        )
+        .await;
+
+        assert!(res.is_err_and(|e| { e.to_string() == ApiError::LimitExceeded.to_string() }));
+        assert!(transactions_repo.created.lock().unwrap().is_empty());
+    }
+
+    #[actix_web::test]
+    async fn test_new_internal_transaction_into_overdraft() {
+        let transactions_repo = Data::new(ScreenedTR {
+            history: vec![transaction(
+                60,
+                5,
+                Utc::now().naive_utc() - Duration::days(3),
+            )],
+            ..Default::default()
+        });
+
+        let res = new_internal_transaction(
+            Data::new(overdraft_accounts_repo(10_000, 50_000)),
+            transactions_repo.clone(),
+            Data::new(limits_repo(0)),
+            Data::new(MockPR::new()),
+            (Data::new(budgets_repo()), Data::new(MockNotifier::new())),
+            Data::new(automation_repo()),
+            Data::new(audit_repo(1)),
+            (
+                Data::new(MockOR::new()),
+                Data::new(TransferSuR::default()),
+                Data::new(MockCodeSender::new()),
+                Data::new(FraudEngine::default()),
+            ),
+            test::TestRequest::post().to_http_request(),
+            5.into(),
+            new_internal(40_000),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(StatusCode::CREATED, res.status());
+        let created = transactions_repo.created.lock().unwrap();
+        assert_eq!(-30_000, created[0].transaction.available_balance_cents);
+    }
+
+    #[actix_web::test]
+    async fn test_new_internal_transaction_past_overdraft_rejected_without_fee() {
+        let transactions_repo = Data::new(ScreenedTR::default());
+
+        let res = new_internal_transaction(
+            Data::new(overdraft_accounts_repo(10_000, 50_000)),
+            transactions_repo.clone(),
+            Data::new(MockLR::new()),
+            Data::new(MockPR::new()),
+            (Data::new(MockBuR::new()), Data::new(MockNotifier::new())),
+            Data::new(MockRepoCreate::<Vec<AutomationRun>, AutomationTrigger>::new()),
+            Data::new(MockRepoCreate::<AuditEntry, NewAuditEntry>::new()),
+            (
+                Data::new(MockOR::new()),
+                Data::new(TransferSuR::default()),
+                Data::new(MockCodeSender::new()),
+                Data::new(FraudEngine::default()),
+            ),
+            test::TestRequest::post().to_http_request(),
+            5.into(),
+            new_internal(60_001),
+        )
+        .await;
+
+        assert!(res.is_err_and(|e| { e.to_string() == ApiError::BadRequest.to_string() }));
+        assert!(transactions_repo.created.lock().unwrap().is_empty());
+    }
+
+    #[actix_web::test]
+    async fn test_new_internal_transaction_insufficient_funds_no_overdraft_no_fee() {
+        let res = new_internal_transaction(
+            Data::new(accounts_repo(10_000)),
+            Data::new(ScreenedTR::default()),
+            Data::new(MockLR::new()),
+            Data::new(MockPR::new()),
+            (Data::new(MockBuR::new()), Data::new(MockNotifier::new())),
+            Data::new(MockRepoCreate::<Vec<AutomationRun>, AutomationTrigger>::new()),
+            Data::new(audit_repo(0)),
+            (
+                Data::new(MockOR::new()),
+                Data::new(TransferSuR::default()),
+                Data::new(MockCodeSender::new()),
+                Data::new(FraudEngine::default()),
+            ),
+            test::TestRequest::post().to_http_request(),
+            5.into(),
+            new_internal(10_001),
+        )
+        .await;
+
+        assert!(res.is_err_and(|e| { e.to_string() == ApiError::BadRequest.to_string() }));
+    }
+
+    #[actix_web::test]
+    async fn test_new_internal_transaction_new_payee_challenged() {
+        let transactions_repo = Data::new(ScreenedTR::default());
+
+        let mut mock_payees_repo = MockPR::new();
+        mock_payees_repo
+            .expect_get_by_id()
+            .with(eq(8))
+            .times(1)
+            .returning(|_| Ok(payee(None)));
+        mock_payees_repo.expect_update().never();
+
+        let step_up_repo = Data::new(TransferSuR {
+            enrolment: Some(totp_enrolment()),
+            ..Default::default()
+        });
+
+        let mut payload = new_internal(200_000);
+        payload.payee_id = Some(8);
+        payload.to_number = None;
+        payload.to_bsb = None;
+
+        // nothing sent, the code comes from the customer's app
+        let res = new_internal_transaction(
+            Data::new(accounts_repo(1_000_000)),
+            transactions_repo.clone(),
+            Data::new(limits_repo(0)),
+            Data::new(mock_payees_repo),
+            (Data::new(MockBuR::new()), Data::new(MockNotifier::new())),
+            Data::new(MockRepoCreate::<Vec<AutomationRun>, AutomationTrigger>::new()),
+            Data::new(audit_repo(0)),
+            (
+                Data::new(MockOR::new()),
+                step_up_repo.clone(),
+                Data::new(MockCodeSender::new()),
+                Data::new(FraudEngine::default()),
+            ),
+            test::TestRequest::post().to_http_request(),
+            5.into(),
+            payload,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(StatusCode::UNAUTHORIZED, res.status());
+        assert!(transactions_repo.created.lock().unwrap().is_empty());
+        assert!(transactions_repo.held.lock().unwrap().is_empty());
+
+        let actual: StepUpChallengeRest =
+            serde_json::from_slice(&actix_web::body::to_bytes(res.into_body()).await.unwrap())
+                .unwrap();
+        assert_eq!(StepUpActionRest::NewPayeeTransfer, actual.step_up_action);
+        assert_eq!(OtpMethodRest::Totp, actual.otp_method);
+
+        // for the payee's account and this amount only
+        let issued = step_up_repo.issued.lock().unwrap();
+        assert_eq!(1, issued.len());
+        assert_eq!(StepUpAction::NewPayeeTransfer, issued[0].step_up_action);
+        assert_eq!(8, issued[0].entity_id);
+        assert_eq!(OtpMethod::Totp, issued[0].otp_method);
+        assert!(issued[0].code_hash.is_none());
+        assert_eq!(Some("444444444".to_string()), issued[0].to_number);
+        assert_eq!(Some("654321".to_string()), issued[0].to_bsb);
+        assert_eq!(Some(200_000), issued[0].amount_cents);
+    }
+
+    #[actix_web::test]
+    async fn test_new_internal_transaction_new_destination_challenged() {
+        let transactions_repo = Data::new(ScreenedTR {
+            new_destination: true,
+            ..Default::default()
+        });
+
+        let step_up_repo = Data::new(TransferSuR::default());
+
+        let mut mock_code_sender = MockCodeSender::new();
+        mock_code_sender
+            .expect_send_code()
+            .times(1)
+            .returning(|_, _| Ok(()));
+
+        // typed in rather than a payee, small enough that screening wouldn't have stopped it
+        let res = new_internal_transaction(
+            Data::new(accounts_repo(1_000_000)),
+            transactions_repo.clone(),
+            Data::new(limits_repo(0)),
+            Data::new(MockPR::new()),
+            (Data::new(MockBuR::new()), Data::new(MockNotifier::new())),
+            Data::new(MockRepoCreate::<Vec<AutomationRun>, AutomationTrigger>::new()),
+            Data::new(audit_repo(0)),
+            (
+                Data::new(MockOR::new()),
+                step_up_repo.clone(),
+                Data::new(mock_code_sender),
+                Data::new(FraudEngine::default()),
+            ),
+            test::TestRequest::post().to_http_request(),
+            5.into(),
+            new_internal(5_000),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(StatusCode::UNAUTHORIZED, res.status());
+        assert!(transactions_repo.created.lock().unwrap().is_empty());
+        assert!(transactions_repo.held.lock().unwrap().is_empty());
+
+        let actual: StepUpChallengeRest =
+            serde_json::from_slice(&actix_web::body::to_bytes(res.into_body()).await.unwrap())
+                .unwrap();
+        assert_eq!(
+            StepUpActionRest::NewDestinationTransfer,
+            actual.step_up_action
+        );
+
+        // tied to where it's going and how much, not just the account it's leaving
+        let issued = step_up_repo.issued.lock().unwrap();
+        assert_eq!(1, issued.len());
+        assert_eq!(OtpMethod::Delivered, issued[0].otp_method);
+        assert_eq!(
+            destination_redemption(5_000),
+            StepUpRedemption {
+                customer_id: issued[0].customer_id,
+                step_up_action: issued[0].step_up_action,
+                entity_id: issued[0].entity_id,
+                to_number: issued[0].to_number.clone(),
+                to_bsb: issued[0].to_bsb.clone(),
+                amount_cents: issued[0].amount_cents,
+            }
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_new_internal_transaction_new_destination_stepped_up() {
+        let transactions_repo = Data::new(ScreenedTR {
+            new_destination: true,
+            ..Default::default()
+        });
+
+        let step_up_repo = Data::new(TransferSuR {
+            challenge: Some(verified_challenge(5_000)),
+            ..Default::default()
+        });
+
+        let res = new_internal_transaction(
+            Data::new(accounts_repo(1_000_000)),
+            transactions_repo.clone(),
+            Data::new(limits_repo(0)),
+            Data::new(MockPR::new()),
+            (Data::new(budgets_repo()), Data::new(MockNotifier::new())),
+            Data::new(automation_repo()),
+            Data::new(audit_repo(1)),
+            (
+                Data::new(MockOR::new()),
+                step_up_repo.clone(),
+                Data::new(MockCodeSender::new()),
+                Data::new(FraudEngine::default()),
+            ),
+            test::TestRequest::post()
+                .insert_header((STEP_UP_HEADER, "9"))
+                .to_http_request(),
+            5.into(),
+            new_internal(5_000),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(StatusCode::CREATED, res.status());
+        assert!(step_up_repo.issued.lock().unwrap().is_empty());
+
+        // handed to the repo to use up along with creating the transfer
+        let created = transactions_repo.created.lock().unwrap();
+        assert_eq!(1, created.len());
+        assert_eq!(
+            Some(StepUpProof {
+                challenge_id: 9,
+                redemption: destination_redemption(5_000),
+            }),
+            created[0].step_up
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_new_internal_transaction_stepped_up_for_other_amount_forbidden() {
+        let transactions_repo = Data::new(ScreenedTR {
+            new_destination: true,
+            ..Default::default()
+        });
+
+        // verified for $50, retried for $4,000
+        let step_up_repo = Data::new(TransferSuR {
+            challenge: Some(verified_challenge(5_000)),
+            ..Default::default()
+        });
+
+        let res = new_internal_transaction(
+            Data::new(accounts_repo(1_000_000)),
+            transactions_repo.clone(),
+            Data::new(limits_repo(0)),
+            Data::new(MockPR::new()),
+            (Data::new(MockBuR::new()), Data::new(MockNotifier::new())),
+            Data::new(MockRepoCreate::<Vec<AutomationRun>, AutomationTrigger>::new()),
+            Data::new(audit_repo(0)),
+            (
+                Data::new(MockOR::new()),
+                step_up_repo,
+                Data::new(MockCodeSender::new()),
+                Data::new(FraudEngine::default()),
+            ),
+            test::TestRequest::post()
+                .insert_header((STEP_UP_HEADER, "9"))
+                .to_http_request(),
+            5.into(),
+            new_internal(400_000),
+        )
+        .await;
+
+        assert!(res.is_err_and(|e| e.to_string() == ApiError::Forbidden.to_string()));
+        assert!(transactions_repo.created.lock().unwrap().is_empty());
+        assert!(transactions_repo.held.lock().unwrap().is_empty());
+    }
+
+    #[actix_web::test]
+    async fn test_new_internal_transaction_stepped_up_for_other_destination_forbidden() {
+        let transactions_repo = Data::new(ScreenedTR {
+            new_destination: true,
+            ..Default::default()
+        });
+
+        // verified for 987654321, retried to somewhere else from the same account
+        let step_up_repo = Data::new(TransferSuR {
+            challenge: Some(verified_challenge(5_000)),
+            ..Default::default()
+        });
+
+        let mut payload = new_internal(5_000);
+        payload.to_number = Some("555555555".to_string());
+
+        let res = new_internal_transaction(
+            Data::new(accounts_repo(1_000_000)),
+            transactions_repo.clone(),
+            Data::new(limits_repo(0)),
+            Data::new(MockPR::new()),
+            (Data::new(MockBuR::new()), Data::new(MockNotifier::new())),
+            Data::new(MockRepoCreate::<Vec<AutomationRun>, AutomationTrigger>::new()),
+            Data::new(audit_repo(0)),
+            (
+                Data::new(MockOR::new()),
+                step_up_repo,
+                Data::new(MockCodeSender::new()),
+                Data::new(FraudEngine::default()),
+            ),
+            test::TestRequest::post()
+                .insert_header((STEP_UP_HEADER, "9"))
+                .to_http_request(),
+            5.into(),
+            payload,
+        )
+        .await;
+
+        assert!(res.is_err_and(|e| e.to_string() == ApiError::Forbidden.to_string()));
+        assert!(transactions_repo.created.lock().unwrap().is_empty());
+        assert!(transactions_repo.held.lock().unwrap().is_empty());
+    }
+
+    #[actix_web::test]
+    async fn test_new_internal_transaction_new_payee_stepped_up() {
+        let transactions_repo = Data::new(ScreenedTR::default());
+
+        let mut mock_payees_repo = MockPR::new();
+        mock_payees_repo
+            .expect_get_by_id()
+            .with(eq(8))
+            .times(1)
+            .returning(|_| Ok(payee(None)));
+        mock_payees_repo
+            .expect_update()
+            .times(1)
+            .returning(|_, used| Ok(payee(Some(used.used_at))));
+
+        let step_up_repo = Data::new(TransferSuR {
+            challenge: Some(StepUpChallenge {
+                step_up_action: StepUpAction::NewPayeeTransfer,
+                entity_id: 8,
+                to_number: Some("444444444".to_string()),
+                to_bsb: Some("654321".to_string()),
+                ..verified_challenge(200_000)
+            }),
+            ..Default::default()
+        });
+
+        let mut payload = new_internal(200_000);
+        payload.payee_id = Some(8);
+        payload.to_number = None;
+        payload.to_bsb = None;
+
+        let res = new_internal_transaction(
+            Data::new(accounts_repo(1_000_000)),
+            transactions_repo.clone(),
+            Data::new(limits_repo(0)),
+            Data::new(mock_payees_repo),
+            (Data::new(budgets_repo()), Data::new(MockNotifier::new())),
+            Data::new(automation_repo()),
+            Data::new(audit_repo(1)),
+            (
+                Data::new(MockOR::new()),
+                step_up_repo,
+                Data::new(MockCodeSender::new()),
+                Data::new(FraudEngine::default()),
+            ),
+            test::TestRequest::post()
+                .insert_header((STEP_UP_HEADER, "9"))
+                .to_http_request(),
+            5.into(),
+            payload,
+        )
+        .await
+        .unwrap();
+
+        // stepping up doesn't get it past screening, a first payment this size is still held. the
+        // challenge goes along either way
+        assert_eq!(StatusCode::CREATED, res.status());
+        let created = transactions_repo.created.lock().unwrap();
+        let held = transactions_repo.held.lock().unwrap();
+        assert_eq!(1, created.len() + held.len());
+        let step_up = created
+            .first()
+            .map(|signed| signed.step_up.clone())
+            .or_else(|| held.first().map(|held| held.step_up.clone()))
+            .unwrap();
+        assert_eq!(Some(9), step_up.map(|proof| proof.challenge_id));
+    }
+
+    #[actix_web::test]
+    async fn test_new_internal_transaction_by_payee_id() {
+        let transactions_repo = Data::new(ScreenedTR::default());
+
+        // paid long ago, well outside the screening history
+        let mut mock_payees_repo = MockPR::new();
+        mock_payees_repo
+            .expect_get_by_id()
+            .with(eq(8))
+            .times(1)
+            .returning(|_| Ok(payee(Some(Utc::now().naive_utc() - Duration::days(180)))));
+        mock_payees_repo
+            .expect_update()
+            .withf(|id, _| *id == 8)
+            .times(1)
+            .returning(|_, used| Ok(payee(Some(used.used_at))));
+
+        let mut payload = new_internal(200_000);
+        payload.payee_id = Some(8);
+        payload.to_number = None;
+        payload.to_bsb = None;
+
+        let res = new_internal_transaction(
+            Data::new(accounts_repo(1_000_000)),
+            transactions_repo.clone(),
+            Data::new(limits_repo(0)),
+            Data::new(mock_payees_repo),
+            (Data::new(budgets_repo()), Data::new(MockNotifier::new())),
+            Data::new(automation_repo()),
+            Data::new(audit_repo(1)),
+            (
+                Data::new(MockOR::new()),
+                Data::new(TransferSuR::default()),
+                Data::new(MockCodeSender::new()),
+                Data::new(FraudEngine::default()),
+            ),
+            test::TestRequest::post().to_http_request(),
+            5.into(),
+            payload,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(StatusCode::CREATED, res.status());
+        assert!(transactions_repo.held.lock().unwrap().is_empty());
+
+        let created = transactions_repo.created.lock().unwrap();
+        assert_eq!(1, created.len());
+        assert_eq!("444444444", created[0].transaction.to_number);
+        assert_eq!("654321", created[0].transaction.to_bsb);
+        assert_eq!(Some("Landlord".to_string()), created[0].transaction.to_name);
+        assert_eq!(
+            Some("UNIT 4 RENT".to_string()),
+            created[0].transaction.payee_reference
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_new_internal_transaction_to_same_account_error() {
+        let transactions_repo = Data::new(ScreenedTR::default());
+
+        let mut payload = new_internal(1000);
+        payload.to_number = Some("123456789".to_string());
+
+        let res = new_internal_transaction(
+            Data::new(accounts_repo(1_000_000)),
+            transactions_repo.clone(),
+            Data::new(MockLR::new()),
+            Data::new(MockPR::new()),
+            (Data::new(budgets_repo()), Data::new(MockNotifier::new())),
+            Data::new(automation_repo()),
+            Data::new(audit_repo(0)),
+            (
+                Data::new(MockOR::new()),
+                Data::new(TransferSuR::default()),
+                Data::new(MockCodeSender::new()),
+                Data::new(FraudEngine::default()),
+            ),
+            test::TestRequest::post().to_http_request(),
+            5.into(),
+            payload,
+        )
+        .await;
+
+        assert!(res.is_err_and(|e| { e.to_string() == ApiError::BadRequest.to_string() }));
+        assert!(transactions_repo.created.lock().unwrap().is_empty());
+    }
+
+    #[actix_web::test]
+    async fn test_new_internal_transaction_bad_reference_error() {
+        let mut payload = new_internal(1000);
+        payload.payee_reference = Some("100% of rent".to_string());
+
+        let res = new_internal_transaction(
+            Data::new(MockRepoFind::<Account, FindAccountQuery>::new()),
+            Data::new(ScreenedTR::default()),
+            Data::new(MockLR::new()),
+            Data::new(MockPR::new()),
+            (Data::new(budgets_repo()), Data::new(MockNotifier::new())),
+            Data::new(automation_repo()),
+            Data::new(audit_repo(0)),
+            (
+                Data::new(MockOR::new()),
+                Data::new(TransferSuR::default()),
+                Data::new(MockCodeSender::new()),
+                Data::new(FraudEngine::default()),
+            ),
+            test::TestRequest::post().to_http_request(),
+            5.into(),
+            payload,
+        )
+        .await;
+
+        assert!(res.is_err_and(|e| { e.to_string() == ApiError::BadRequest.to_string() }));
+    }
+
+    #[actix_web::test]
+    async fn test_find_transactions_search() {
+        let mut mock_transactions_repo = MockRepoFind::<Transaction, FindTransactionQuery>::new();
+        mock_transactions_repo
+            .expect_find()
+            .with(eq(FindTransactionQuery {
+                transaction_id: None,
+                customer_id: 5,
+                account_number: None,
+                date_from: None,
+                date_to: None,
+                search: Some("rent".to_string()),
+            }))
+            .times(1)
+            .returning(|_| {
+                let mut tr = transaction(60, 5, Utc::now().naive_utc());
+                tr.payee_reference = Some("UNIT 4 RENT".to_string());
+                Ok(vec![tr])
+            });
+
+        let res = find_transactions(
+            Data::new(accounts_repo(0)),
+            Data::new(mock_transactions_repo),
+            Data::new(MockOR::new()),
+            test::TestRequest::get().to_http_request(),
+            5.into(),
+            Query(FindTransactionQueryRest {
+                transaction_id: None,
+                customer_id: None,
+                account_number: None,
+                search: Some("  rent ".to_string()),
+            }),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(StatusCode::OK, res.status());
+
+        let actual: TransactionsRest =
+            serde_json::from_slice(&actix_web::body::to_bytes(res.into_body()).await.unwrap())
+                .unwrap();
+        assert_eq!(
+            Some("UNIT 4 RENT".to_string()),
+            actual.transactions[0].payee_reference
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_find_transactions_token_without_scope_forbidden() {
+        let mut mock_oauth_repo = MockOR::new();
+        mock_oauth_repo.expect_find().times(1).returning(|query| {
+            let now = Utc::now().naive_utc();
+            Ok(vec![OAuthToken {
+                id: 12,
+                grant_id: 4,
+                customer_id: 5,
+                token_kind: OAuthTokenKind::Access,
+                token_hash: query.token_hash,
+                scope: "accounts:read".to_string(),
+                expires_at: now + Duration::minutes(60),
+                revoked_at: None,
+                created_at: now,
+            }])
+        });
+
+        let mut mock_transactions_repo = MockRepoFind::<Transaction, FindTransactionQuery>::new();
+        mock_transactions_repo.expect_find().never();
+
+        let res = find_transactions(
+            Data::new(MockRepoFind::<Account, FindAccountQuery>::new()),
+            Data::new(mock_transactions_repo),
+            Data::new(mock_oauth_repo),
+            test::TestRequest::get()
+                .insert_header(("Authorization", "Bearer the-token"))
+                .to_http_request(),
+            5.into(),
+            Query(FindTransactionQueryRest {
+                transaction_id: None,
+                customer_id: None,
+                account_number: None,
+                search: None,
+            }),
+        )
+        .await;
+
+        assert!(res.is_err_and(|e| e.to_string() == ApiError::Forbidden.to_string()));
+    }
+
+    #[actix_web::test]
+    async fn test_find_transactions_token_only_shared_accounts() {
+        let mut mock_oauth_repo = MockOR::new();
+        mock_oauth_repo.expect_find().times(1).returning(|query| {
+            let now = Utc::now().naive_utc();
+            Ok(vec![OAuthToken {
+                id: 12,
+                grant_id: 4,
+                customer_id: 5,
+                token_kind: OAuthTokenKind::Access,
+                token_hash: query.token_hash,
+                scope: "transactions:read".to_string(),
+                expires_at: now + Duration::minutes(60),
+                revoked_at: None,
+                created_at: now,
+            }])
+        });
+        mock_oauth_repo
+            .expect_get_by_id()
+            .with(eq(4))
+            .times(1)
+            .returning(|id| {
+                let now = Utc::now().naive_utc();
+                Ok(Consent {
+                    id,
+                    customer_id: 5,
+                    client_id: "budget-app".to_string(),
+                    client_name: "Budget App".to_string(),
+                    scope: "transactions:read".to_string(),
+                    account_ids: vec![53],
+                    created_at: now,
+                    updated_at: now,
+                    expires_at: now + Duration::days(30),
+                    revoked_at: None,
+                })
+            });
+
+        let mut mock_accounts_repo = MockRepoFind::<Account, FindAccountQuery>::new();
+        mock_accounts_repo.expect_find().times(1).returning(|_| {
+            Ok(vec![
+                from_account(5000, 0),
+                Account {
+                    id: 53,
+                    account_number: "555555555".to_string(),
+                    ..from_account(5000, 0)
+                },
+            ])
+        });
+
+        let mut mock_transactions_repo = MockRepoFind::<Transaction, FindTransactionQuery>::new();
+        mock_transactions_repo
+            .expect_find()
+            .times(1)
+            .returning(|_| {
+                let now = Utc::now().naive_utc();
+                Ok(vec![
+                    transaction(60, 5, now),
+                    Transaction {
+                        from_number: "555555555".to_string(),
+                        ..transaction(61, 5, now)
+                    },
+                ])
+            });
+
+        let res = find_transactions(
+            Data::new(mock_accounts_repo),
+            Data::new(mock_transactions_repo),
+            Data::new(mock_oauth_repo),
+            test::TestRequest::get()
+                .insert_header(("Authorization", "Bearer the-token"))
+                .to_http_request(),
+            5.into(),
+            Query(FindTransactionQueryRest {
+                transaction_id: None,
+                customer_id: None,
+                account_number: None,
+                search: None,
+            }),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(StatusCode::OK, res.status());
+
+        let actual: TransactionsRest =
+            serde_json::from_slice(&actix_web::body::to_bytes(res.into_body()).await.unwrap())
+                .unwrap();
+        assert_eq!(1, actual.transactions.len());
+        assert_eq!(61, actual.transactions[0].id);
+    }
+
+    #[actix_web::test]
+    async fn test_new_internal_transaction_no_destination_error() {
+        let mut payload = new_internal(1000);
+        payload.to_number = None;
+
+        let res = new_internal_transaction(
+            Data::new(MockRepoFind::<Account, FindAccountQuery>::new()),
+            Data::new(ScreenedTR::default()),
+            Data::new(MockLR::new()),
+            Data::new(MockPR::new()),
+            (Data::new(budgets_repo()), Data::new(MockNotifier::new())),
+            Data::new(automation_repo()),
+            Data::new(audit_repo(0)),
+            (
+                Data::new(MockOR::new()),
+                Data::new(TransferSuR::default()),
+                Data::new(MockCodeSender::new()),
+                Data::new(FraudEngine::default()),
+            ),
+            test::TestRequest::post().to_http_request(),
+            5.into(),
+            payload,
+        )
+        .await;
+
+        assert!(res.is_err_and(|e| { e.to_string() == ApiError::BadRequest.to_string() }));
+    }
+
+    fn compensating(original: &Transaction, amount_cents: i64) -> Transaction {
+        Transaction {
+            id: original.id + 1,
+            from_us: false,
+            amount_cents,
+            from_number: original.to_number.clone(),
+            to_number: original.from_number.clone(),
+            available_balance_cents: 15000,
+            reversal_of: Some(original.id),
+            reversal_reason: Some("sent to wrong account".to_string()),
+            ..original.clone()
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_reverse_transaction_success() {
+        let customer_id = 5;
+        let transaction_id = 60;
+        let original = transaction(transaction_id, customer_id, Utc::now().naive_utc());
+        let original_c = original.clone();
+
+        let mut mock_transactions_repo = MockTR::new();
+        mock_transactions_repo
+            .expect_get_by_id()
+            .with(eq(transaction_id))
+            .times(1)
+            .returning(move |_| Ok(original.clone()));
+        mock_transactions_repo
+            .expect_create()
+            .with(eq(NewReversal {
+                transaction_id,
+                amount_cents: None,
+                reason: "sent to wrong account".to_string(),
+            }))
+            .times(1)
+            .returning(move |_| Ok(compensating(&original_c, 10000)));
+
+        let mut mock_audit_repo = MockRepoCreate::<AuditEntry, NewAuditEntry>::new();
+        mock_audit_repo
+            .expect_create()
+            .withf(move |entry| {
+                entry.actor_type == ActorType::Customer
+                    && entry.actor_id == customer_id
+                    && entry.action == "reverse_transaction"
+                    && entry.entity_id == Some(transaction_id)
+            })
+            .times(1)
+            .returning(|entry| Ok(appended(entry)));
+
+        let res = reverse_transaction(
+            Data::new(MockRepoFind::<Account, FindAccountQuery>::new()),
+            Data::new(mock_transactions_repo),
+            Data::new(mock_audit_repo),
+            test::TestRequest::post().to_http_request(),
+            (customer_id, transaction_id).into(),
+            Json(NewReversalRest {
+                amount_cents: None,
+                reason: "sent to wrong account".to_string(),
+            }),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(StatusCode::CREATED, res.status());
+
+        let actual: TransactionRest =
+            serde_json::from_slice(&actix_web::body::to_bytes(res.into_body()).await.unwrap())
+                .unwrap();
+        assert_eq!(Some(transaction_id), actual.reversal_of);
+        assert!(!actual.from_us);
+        assert_eq!(10000, actual.amount_cents);
+    }
+
+    #[actix_web::test]
+    async fn test_reverse_transaction_recipient_spent_it_conflict() {
+        let customer_id = 5;
+        let transaction_id = 60;
+        let original = transaction(transaction_id, customer_id, Utc::now().naive_utc());
+
+        // the recipient's account can't cover taking it back, or it was a loan repayment
+        let mut mock_transactions_repo = MockTR::new();
+        mock_transactions_repo
+            .expect_get_by_id()
+            .with(eq(transaction_id))
+            .times(1)
+            .returning(move |_| Ok(original.clone()));
+        mock_transactions_repo
+            .expect_create()
+            .times(1)
+            .returning(|_| Err(RepoError::Conflict));
+
+        let res = reverse_transaction(
+            Data::new(MockRepoFind::<Account, FindAccountQuery>::new()),
+            Data::new(mock_transactions_repo),
+            Data::new(MockRepoCreate::<AuditEntry, NewAuditEntry>::new()),
+            test::TestRequest::post().to_http_request(),
+            (customer_id, transaction_id).into(),
+            Json(NewReversalRest {
+                amount_cents: None,
+                reason: "sent to wrong account".to_string(),
+            }),
+        )
+        .await;
+
+        assert!(res.is_err_and(|e| { e.to_string() == ApiError::Conflict.to_string() }));
+    }
+
+    #[actix_web::test]
+    async fn test_reverse_transaction_outside_window_error() {
+        let customer_id = 5;
+        let transaction_id = 60;
+        let started = Utc::now().naive_utc() - Duration::hours(2);
+
+        let mut mock_transactions_repo = MockTR::new();
+        mock_transactions_repo
+            .expect_get_by_id()
+            .with(eq(transaction_id))
+            .times(1)
+            .returning(move |_| Ok(transaction(transaction_id, customer_id, started)));
+        mock_transactions_repo.expect_create().times(0);
+
+        let res = reverse_transaction(
+            Data::new(MockRepoFind::<Account, FindAccountQuery>::new()),
+            Data::new(mock_transactions_repo),
+            Data::new(MockRepoCreate::<AuditEntry, NewAuditEntry>::new()),
+            test::TestRequest::post().to_http_request(),
+            (customer_id, transaction_id).into(),
+            Json(NewReversalRest {
+                amount_cents: Some(100),
+                reason: "changed my mind".to_string(),
+            }),
+        )
+        .await;
+
+        assert!(res.is_err_and(|e| { e.to_string() == ApiError::BadRequest.to_string() }));
+    }
+
+    #[actix_web::test]
+    async fn test_reverse_transaction_unauthorized_error() {
+        let transaction_id = 60;
+
+        let mut mock_transactions_repo = MockTR::new();
+        mock_transactions_repo
+            .expect_get_by_id()
+            .times(1)
+            .returning(move |_| Ok(transaction(transaction_id, 5, Utc::now().naive_utc())));
+        mock_transactions_repo.expect_create().times(0);
+
+        // not a holder of the account it came from
+        let mut mock_accounts_repo = MockRepoFind::<Account, FindAccountQuery>::new();
+        mock_accounts_repo
+            .expect_find()
+            .with(eq(FindAccountQuery {
+                account_id: None,
+                customer_id: 6,
+                account_number: Some("123456789".to_string()),
+            }))
+            .times(1)
+            .returning(|_| Ok(vec![]));
+
+        let res = reverse_transaction(
+            Data::new(mock_accounts_repo),
+            Data::new(mock_transactions_repo),
+            Data::new(MockRepoCreate::<AuditEntry, NewAuditEntry>::new()),
+            test::TestRequest::post().to_http_request(),
+            (6, transaction_id).into(),
+            Json(NewReversalRest {
+                amount_cents: None,
+                reason: "not mine".to_string(),
+            }),
+        )
+        .await;
+
+        assert!(res.is_err_and(|e| { e.to_string() == ApiError::Unauthorized.to_string() }));
+    }
+
+    #[actix_web::test]
+    async fn test_reverse_transaction_bank_charge_error() {
+        let transaction_id = 60;
+
+        let mut mock_transactions_repo = MockTR::new();
+        mock_transactions_repo
+            .expect_get_by_id()
+            .times(1)
+            .returning(move |_| {
+                let mut fee = transaction(transaction_id, 5, Utc::now().naive_utc());
+                fee.transaction_type = TransactionType::Fee;
+                Ok(fee)
+            });
+        mock_transactions_repo.expect_create().times(0);
+
+        let res = reverse_transaction(
+            Data::new(MockRepoFind::<Account, FindAccountQuery>::new()),
+            Data::new(mock_transactions_repo),
+            Data::new(MockRepoCreate::<AuditEntry, NewAuditEntry>::new()),
+            test::TestRequest::post().to_http_request(),
+            (5, transaction_id).into(),
+            Json(NewReversalRest {
+                amount_cents: None,
+                reason: "didn't agree to this".to_string(),
+            }),
+        )
+        .await;
+
+        assert!(res.is_err_and(|e| { e.to_string() == ApiError::BadRequest.to_string() }));
+    }
+
+    #[actix_web::test]
+    async fn test_reverse_transaction_already_reversed_conflict() {
+        let transaction_id = 60;
+        let dt = NaiveDate::from_ymd_opt(2023, 7, 22)
+            .unwrap()
+            .and_hms_opt(11, 48, 53)
+            .unwrap();
+
+        let mut mock_transactions_repo = MockTR::new();
+        mock_transactions_repo
+            .expect_get_by_id()
+            .times(1)
+            .returning(move |_| {
+                let mut tr = transaction(transaction_id, 5, dt);
+                tr.transaction_status = TransactionStatus::Reversed;
+                tr.reversed_amount_cents = tr.amount_cents;
+                Ok(tr)
+            });
+        mock_transactions_repo.expect_create().times(0);
+
+        let res = staff_reverse_transaction(
+            Data::new(mock_transactions_repo),
+            Data::new(MockRepoCreate::<AuditEntry, NewAuditEntry>::new()),
+            test::TestRequest::post().to_http_request(),
+            (1, transaction_id).into(),
+            Json(NewReversalRest {
+                amount_cents: None,
+                reason: "duplicate".to_string(),
+            }),
+        )
+        .await;
+
+        assert!(res.is_err_and(|e| { e.to_string() == ApiError::Conflict.to_string() }));
+    }
+
+    #[actix_web::test]
+    async fn test_staff_reverse_transaction_partial_success() {
+        let staff_id = 1;
+        let transaction_id = 60;
+        // well outside the customer window
+        let dt = NaiveDate::from_ymd_opt(2023, 7, 22)
+            .unwrap()
+            .and_hms_opt(11, 48, 53)
+            .unwrap();
+        let original = transaction(transaction_id, 5, dt);
+        let original_c = original.clone();
+
+        let mut mock_transactions_repo = MockTR::new();
+        mock_transactions_repo
+            .expect_get_by_id()
+            .times(1)
+            .returning(move |_| Ok(original.clone()));
+        mock_transactions_repo
+            .expect_create()
+            .withf(move |r| r.transaction_id == transaction_id && r.amount_cents == Some(2500))
+            .times(1)
+            .returning(move |_| Ok(compensating(&original_c, 2500)));
+
+        let mut mock_audit_repo = MockRepoCreate::<AuditEntry, NewAuditEntry>::new();
+        mock_audit_repo
+            .expect_create()
+            .withf(move |entry| entry.actor_type == ActorType::Staff && entry.actor_id == staff_id)
+            .times(1)
+            .returning(|entry| Ok(appended(entry)));
+
+        let res = staff_reverse_transaction(
+            Data::new(mock_transactions_repo),
+            Data::new(mock_audit_repo),
+            test::TestRequest::post().to_http_request(),
+            (staff_id, transaction_id).into(),
+            Json(NewReversalRest {
+                amount_cents: Some(2500),
+                reason: "partial refund".to_string(),
+            }),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(StatusCode::CREATED, res.status());
+
+        let actual: TransactionRest =
+            serde_json::from_slice(&actix_web::body::to_bytes(res.into_body()).await.unwrap())
+                .unwrap();
+        assert_eq!(2500, actual.amount_cents);
+        assert_eq!(TransactionStatusRest::Success, actual.transaction_status);
+    }
+
+    #[actix_web::test]
+    async fn test_staff_reverse_transaction_race_conflict() {
+        let dt = NaiveDate::from_ymd_opt(2023, 7, 22)
+            .unwrap()
+            .and_hms_opt(11, 48, 53)
+            .unwrap();
+
+        let mut mock_transactions_repo = MockTR::new();
+        mock_transactions_repo
+            .expect_get_by_id()
+            .times(1)
+            .returning(move |_| Ok(transaction(60, 5, dt)));
+        mock_transactions_repo
+            .expect_create()
+            .times(1)
+            .returning(|_| Err(RepoError::Conflict));
+
+        let res = staff_reverse_transaction(
+            Data::new(mock_transactions_repo),
+            Data::new(MockRepoCreate::<AuditEntry, NewAuditEntry>::new()),
+            test::TestRequest::post().to_http_request(),
+            (1, 60).into(),
+            Json(NewReversalRest {
+                amount_cents: None,
+                reason: "duplicate".to_string(),
+            }),
+        )
+        .await;
+
+        assert!(res.is_err_and(|e| { e.to_string() == ApiError::Conflict.to_string() }));
+    }
+
+    #[actix_web::test]
+    async fn test_quote_transaction_discloses_fees() {
+        let mut mock_fees_repo = MockRepoFind::<AppliedFee, FindTransferFeeQuery>::new();
+        mock_fees_repo
+            .expect_find()
+            .with(eq(FindTransferFeeQuery {
+                account_id: 52,
+                transaction_type: TransactionType::Internal,
+            }))
+            .times(1)
+            .returning(|_| {
+                Ok(vec![AppliedFee {
+                    kind: FeeKind::ExternalTransfer,
+                    schedule_cents: 50,
+                    amount_cents: 50,
+                    waived: false,
+                }])
+            });
+
+        let res = quote_transaction(
+            Data::new(overdraft_accounts_repo(10000, 0)),
+            Data::new(mock_fees_repo),
+            5.into(),
+            new_internal(9960),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(StatusCode::OK, res.status());
+
+        let actual: TransferQuoteRest =
+            serde_json::from_slice(&actix_web::body::to_bytes(res.into_body()).await.unwrap())
+                .unwrap();
+        assert_eq!(1, actual.fees.len());
+        assert_eq!(50, actual.fees_cents);
+        assert_eq!(10010, actual.total_cents);
+        assert_eq!(-10, actual.available_balance_after_cents);
+        assert!(!actual.sufficient_funds);
+    }
+
+    #[actix_web::test]
+    async fn test_quote_transaction_other_customer_error() {
+        let mut mock_accounts_repo = MockRepoFind::<Account, FindAccountQuery>::new();
+        mock_accounts_repo.expect_find().times(0);
+
+        let res = quote_transaction(
+            Data::new(mock_accounts_repo),
+            Data::new(MockRepoFind::<AppliedFee, FindTransferFeeQuery>::new()),
+            6.into(),
+            new_internal(100),
+        )
+        .await;
+
+        assert!(res.is_err_and(|e| { e.to_string() == ApiError::Unauthorized.to_string() }));
+    }
+
+    #[actix_web::test]
+    async fn test_new_internal_transaction_joint_holder_kept_against_primary() {
+        let transactions_repo = Data::new(ScreenedTR {
+            history: vec![transaction(
+                60,
+                5,
+                Utc::now().naive_utc() - Duration::days(3),
+            )],
+            ..Default::default()
+        });
+        let mut payload = new_internal(50_000);
+        payload.customer_id = 6;
+
+        let res = new_internal_transaction(
+            Data::new(accounts_repo(1_000_000)),
+            transactions_repo.clone(),
+            Data::new(limits_repo(0)),
+            Data::new(MockPR::new()),
+            (Data::new(budgets_repo()), Data::new(MockNotifier::new())),
+            Data::new(automation_repo()),
+            Data::new(audit_repo(1)),
+            (
+                Data::new(MockOR::new()),
+                Data::new(TransferSuR::default()),
+                Data::new(MockCodeSender::new()),
+                Data::new(FraudEngine::default()),
+            ),
+            test::TestRequest::post().to_http_request(),
+            6.into(),
+            payload,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(StatusCode::CREATED, res.status());
+        let created = transactions_repo.created.lock().unwrap();
+        assert_eq!(5, created[0].transaction.customer_id);
+        assert_eq!(6, created[0].signed_by);
+    }
+
+    #[actix_web::test]
+    async fn test_new_internal_transaction_both_to_sign_awaits_signature() {
+        let transactions_repo = Data::new(ScreenedTR {
+            history: vec![transaction(
+                60,
+                5,
+                Utc::now().naive_utc() - Duration::days(3),
+            )],
+            outcome: Some(TransactionStatus::AwaitingSignature),
+            ..Default::default()
+        });
+
+        // nothing has settled, so nothing for budgets or automations yet
+        let res = new_internal_transaction(
+            Data::new(accounts_repo(1_000_000)),
+            transactions_repo.clone(),
+            Data::new(limits_repo(0)),
+            Data::new(MockPR::new()),
+            (Data::new(MockBuR::new()), Data::new(MockNotifier::new())),
+            Data::new(MockRepoCreate::<Vec<AutomationRun>, AutomationTrigger>::new()),
+            Data::new(audit_repo(1)),
+            (
+                Data::new(MockOR::new()),
+                Data::new(TransferSuR::default()),
+                Data::new(MockCodeSender::new()),
+                Data::new(FraudEngine::default()),
+            ),
+            test::TestRequest::post().to_http_request(),
+            5.into(),
+            new_internal(50_000),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(StatusCode::CREATED, res.status());
+        assert_eq!(5, transactions_repo.created.lock().unwrap()[0].signed_by);
+
+        let actual: TransactionRest =
+            serde_json::from_slice(&actix_web::body::to_bytes(res.into_body()).await.unwrap())
+                .unwrap();
+        assert_eq!(
+            TransactionStatusRest::AwaitingSignature,
+            actual.transaction_status
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_new_internal_transaction_over_threshold_pending_approval() {
+        let transactions_repo = Data::new(ScreenedTR {
+            history: vec![transaction(
+                60,
+                5,
+                Utc::now().naive_utc() - Duration::days(3),
+            )],
+            outcome: Some(TransactionStatus::PendingApproval),
+            ..Default::default()
+        });
+
+        let res = new_internal_transaction(
+            Data::new(accounts_repo(1_000_000)),
+            transactions_repo.clone(),
+            Data::new(limits_repo(0)),
+            Data::new(MockPR::new()),
+            (Data::new(MockBuR::new()), Data::new(MockNotifier::new())),
+            Data::new(MockRepoCreate::<Vec<AutomationRun>, AutomationTrigger>::new()),
+            Data::new(audit_repo(1)),
+            (
+                Data::new(MockOR::new()),
+                Data::new(TransferSuR::default()),
+                Data::new(MockCodeSender::new()),
+                Data::new(FraudEngine::default()),
+            ),
+            test::TestRequest::post().to_http_request(),
+            5.into(),
+            new_internal(50_000),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(StatusCode::CREATED, res.status());
+
+        let actual: TransactionRest =
+            serde_json::from_slice(&actix_web::body::to_bytes(res.into_body()).await.unwrap())
+                .unwrap();
+        assert_eq!(
+            TransactionStatusRest::PendingApproval,
+            actual.transaction_status
+        );
+    }
+
+    mock! {
+        pub SignTR { }
+        impl RepoGetById<Transaction> for SignTR {
+            fn get_by_id(&self, id: i32) -> Result<Transaction, RepoError>;
+        }
+        impl RepoCreate<Transaction, NewTransferSignature> for SignTR {
+            fn create(&self, new: NewTransferSignature) -> Result<Transaction, RepoError>;
+        }
+    }
+
+    fn awaiting_signature(transaction_id: i32) -> Transaction {
+        let mut awaiting = transaction(transaction_id, 5, Utc::now().naive_utc());
+        awaiting.transaction_status = TransactionStatus::AwaitingSignature;
+        awaiting.date_end = None;
+        awaiting
+    }
+
+    #[actix_web::test]
+    async fn test_sign_transaction_second_holder_releases() {
+        let transaction_id = 70;
+
+        let mut mock_transactions_repo = MockSignTR::new();
+        mock_transactions_repo
+            .expect_get_by_id()
+            .with(eq(transaction_id))
+            .times(1)
+            .returning(move |id| Ok(awaiting_signature(id)));
+        mock_transactions_repo
+            .expect_create()
+            .with(eq(NewTransferSignature {
+                transaction_id,
+                customer_id: 6,
+            }))
+            .times(1)
+            .returning(|signature| {
+                Ok(transaction(
+                    signature.transaction_id,
+                    5,
+                    Utc::now().naive_utc(),
+                ))
+            });
+
+        let res = sign_transaction(
+            Data::new(accounts_repo(1_000_000)),
+            Data::new(mock_transactions_repo),
+            Data::new(budgets_repo()),
+            Data::new(MockNotifier::new()),
+            Data::new(automation_repo()),
+            Data::new(audit_repo(1)),
+            test::TestRequest::post().to_http_request(),
+            (6, transaction_id).into(),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(StatusCode::OK, res.status());
+
+        let actual: TransactionRest =
+            serde_json::from_slice(&actix_web::body::to_bytes(res.into_body()).await.unwrap())
+                .unwrap();
+        assert_eq!(TransactionStatusRest::Success, actual.transaction_status);
+    }
+
+    #[actix_web::test]
+    async fn test_sign_transaction_not_a_holder_error() {
+        let mut mock_transactions_repo = MockSignTR::new();
+        mock_transactions_repo
+            .expect_get_by_id()
+            .times(1)
+            .returning(|id| Ok(awaiting_signature(id)));
+        mock_transactions_repo.expect_create().times(0);
+
+        let mut mock_accounts_repo = MockRepoFind::<Account, FindAccountQuery>::new();
+        mock_accounts_repo
+            .expect_find()
+            .times(1)
+            .returning(|_| Ok(vec![]));
+
+        let res = sign_transaction(
+            Data::new(mock_accounts_repo),
+            Data::new(mock_transactions_repo),
+            Data::new(MockBuR::new()),
+            Data::new(MockNotifier::new()),
+            Data::new(MockRepoCreate::<Vec<AutomationRun>, AutomationTrigger>::new()),
+            Data::new(audit_repo(0)),
+            test::TestRequest::post().to_http_request(),
+            (7, 70).into(),
+        )
+        .await;
+
+        assert!(res.is_err_and(|e| { e.to_string() == ApiError::Unauthorized.to_string() }));
+    }
+
+    #[actix_web::test]
+    async fn test_sign_transaction_twice_conflict() {
+        let mut mock_transactions_repo = MockSignTR::new();
+        mock_transactions_repo
+            .expect_get_by_id()
+            .times(1)
+            .returning(|id| Ok(awaiting_signature(id)));
+        // the initiator's signature is already on it
+        mock_transactions_repo
+            .expect_create()
+            .times(1)
+            .returning(|_| Err(RepoError::Conflict));
+
+        let res = sign_transaction(
+            Data::new(MockRepoFind::<Account, FindAccountQuery>::new()),
+            Data::new(mock_transactions_repo),
+            Data::new(MockBuR::new()),
+            Data::new(MockNotifier::new()),
+            Data::new(MockRepoCreate::<Vec<AutomationRun>, AutomationTrigger>::new()),
+            Data::new(audit_repo(0)),
+            test::TestRequest::post().to_http_request(),
+            (5, 70).into(),
+        )
+        .await;
+
+        assert!(res.is_err_and(|e| { e.to_string() == ApiError::Conflict.to_string() }));
+    }
+
+    #[actix_web::test]
+    async fn test_sign_transaction_not_awaiting_conflict() {
+        let mut mock_transactions_repo = MockSignTR::new();
+        mock_transactions_repo
+            .expect_get_by_id()
+            .times(1)
+            .returning(|id| Ok(transaction(id, 5, Utc::now().naive_utc())));
+        mock_transactions_repo.expect_create().times(0);
+
+        let res = sign_transaction(
+            Data::new(MockRepoFind::<Account, FindAccountQuery>::new()),
+            Data::new(mock_transactions_repo),
+            Data::new(MockBuR::new()),
+            Data::new(MockNotifier::new()),
+            Data::new(MockRepoCreate::<Vec<AutomationRun>, AutomationTrigger>::new()),
+            Data::new(audit_repo(0)),
+            test::TestRequest::post().to_http_request(),
+            (5, 70).into(),
+        )
+        .await;
+
+        assert!(res.is_err_and(|e| { e.to_string() == ApiError::Conflict.to_string() }));
+    }
+}
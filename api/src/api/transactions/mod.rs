@@ -8,21 +8,96 @@ use crate::{
     api::transactions,
     models::{
         account::{Account, FindAccountQuery},
-        transaction::{FindTransactionQuery, NewTransaction, Transaction},
+        account_holder::{NewSignedTransfer, NewTransferSignature},
+        audit::{AuditEntry, NewAuditEntry},
+        automation::{AutomationRun, AutomationTrigger},
+        budget::{BudgetAlert, BudgetAlertDelivered, BudgetCheck},
+        consent::Consent,
+        fee::{AppliedFee, FindTransferFeeQuery},
+        fraud_review::NewHeldTransaction,
+        oauth::{FindOAuthTokenQuery, OAuthToken},
+        payee::{Payee, PayeeUsed},
+        step_up::{NewStepUpChallenge, StepUpChallenge, TotpEnrolment},
+        transaction::{FindPaymentsToQuery, FindTransactionQuery, NewReversal, Transaction},
+        transfer_limit::{FindTransferUsageQuery, TransferLimits, TransferUsage},
     },
-    traits::{RepoCreate, RepoFind},
+    notifications::Notifier,
+    step_up::CodeSender,
+    traits::{RepoCreate, RepoFind, RepoGetById, RepoUpdate},
 };
 
-pub fn configure_transactions_api<AR, TR>(cfg: &mut web::ServiceConfig)
-where
+pub fn configure_transactions_api<AR, TR, LR, PR, BuR, AtR, FR, N, OR, SR, CS, AuR>(
+    cfg: &mut web::ServiceConfig,
+) where
     AR: RepoFind<Account, FindAccountQuery>,
-    TR: RepoCreate<Transaction, NewTransaction> + RepoFind<Transaction, FindTransactionQuery>,
+    TR: RepoCreate<Transaction, NewSignedTransfer>
+        + RepoFind<Transaction, FindTransactionQuery>
+        + RepoFind<Transaction, FindPaymentsToQuery>
+        + RepoGetById<Transaction>
+        + RepoCreate<Transaction, NewReversal>
+        + RepoCreate<Transaction, NewHeldTransaction>
+        + RepoCreate<Transaction, NewTransferSignature>,
+    LR: RepoGetById<TransferLimits> + RepoFind<TransferUsage, FindTransferUsageQuery>,
+    PR: RepoGetById<Payee> + RepoUpdate<Payee, PayeeUsed>,
+    BuR: RepoCreate<Vec<BudgetAlert>, BudgetCheck> + RepoUpdate<BudgetAlert, BudgetAlertDelivered>,
+    AtR: RepoCreate<Vec<AutomationRun>, AutomationTrigger>,
+    FR: RepoFind<AppliedFee, FindTransferFeeQuery>,
+    N: Notifier,
+    OR: RepoFind<OAuthToken, FindOAuthTokenQuery> + RepoGetById<Consent>,
+    SR: RepoGetById<TotpEnrolment>
+        + RepoGetById<StepUpChallenge>
+        + RepoCreate<StepUpChallenge, NewStepUpChallenge>,
+    CS: CodeSender,
+    AuR: RepoCreate<AuditEntry, NewAuditEntry>,
 {
     cfg.service(
-        web::scope("/api/customers/{customer_id}/transactions").service(
-            web::resource("")
-                .route(web::post().to(transactions::handlers::new_internal_transaction::<AR, TR>))
-                .route(web::get().to(transactions::handlers::find_transactions::<TR>)),
+        web::scope("/api/customers/{customer_id}/transactions")
+            .service(
+                web::resource("")
+                    .route(
+                        web::post().to(transactions::handlers::new_internal_transaction::<
+                            AR,
+                            TR,
+                            LR,
+                            PR,
+                            BuR,
+                            AtR,
+                            N,
+                            OR,
+                            SR,
+                            CS,
+                            AuR,
+                        >),
+                    )
+                    .route(web::get().to(transactions::handlers::find_transactions::<AR, TR, OR>)),
+            )
+            .service(
+                web::resource("/quote")
+                    .route(web::post().to(transactions::handlers::quote_transaction::<AR, FR>)),
+            )
+            .service(
+                web::resource("/{transaction_id}/reversals").route(
+                    web::post().to(transactions::handlers::reverse_transaction::<AR, TR, AuR>),
+                ),
+            )
+            .service(
+                web::resource("/{transaction_id}/signatures").route(
+                    web::post().to(transactions::handlers::sign_transaction::<
+                        AR,
+                        TR,
+                        BuR,
+                        N,
+                        AtR,
+                        AuR,
+                    >),
+                ),
+            ),
+    )
+    .service(
+        web::scope("/api/staff/{staff_id}/transactions").service(
+            web::resource("/{transaction_id}/reversals").route(
+                web::post().to(transactions::handlers::staff_reverse_transaction::<TR, AuR>),
+            ),
         ),
     );
 }
@@ -1,10 +1,13 @@
 use serde::{Deserialize, Serialize};
 
+use crate::api::fees::models::AppliedFeeRest;
+
 #[derive(Deserialize, Serialize, Copy, Clone, Debug, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub enum TransactionTypeRest {
     Internal,
     External,
+    Fee,
 }
 
 #[derive(Deserialize, Serialize, Copy, Clone, Debug, PartialEq)]
@@ -13,6 +16,11 @@ pub enum TransactionStatusRest {
     Pending,
     Success,
     Error,
+    PartiallyReversed,
+    Reversed,
+    Held,
+    AwaitingSignature,
+    PendingApproval,
 }
 
 #[cfg_attr(test, derive(Deserialize, PartialEq, Debug))]
@@ -34,6 +42,14 @@ pub struct TransactionRest {
     pub date_start: String,
     pub date_end: Option<String>,
     pub transaction_status: TransactionStatusRest,
+    pub reversal_of: Option<i32>,
+    pub reversed_amount_cents: i64,
+    pub reversal_reason: Option<String>,
+    pub description: Option<String>,
+    pub payer_reference: Option<String>,
+    pub payee_reference: Option<String>,
+    pub counterpart_of: Option<i32>,
+    pub running_balance_cents: Option<i64>,
 }
 
 #[cfg_attr(test, derive(Deserialize))]
@@ -51,8 +67,37 @@ pub struct NewInternalTransactionRest {
     pub amount_cents: i64,
     pub from_number: String,
     pub from_bsb: String,
-    pub to_number: String,
-    pub to_bsb: String,
+    // either a saved payee or the account details directly
+    pub payee_id: Option<i32>,
+    pub to_number: Option<String>,
+    pub to_bsb: Option<String>,
+    pub description: Option<String>,
+    pub payer_reference: Option<String>,
+    // payee's default reference if not given
+    pub payee_reference: Option<String>,
+}
+
+// what the transfer would cost before it's made, fees waived this month still listed
+#[cfg_attr(test, derive(Deserialize, PartialEq, Debug))]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransferQuoteRest {
+    pub amount_cents: i64,
+    pub fees: Vec<AppliedFeeRest>,
+    pub fees_cents: i64,
+    pub total_cents: i64,
+    pub available_balance_after_cents: i64,
+    // false if the transfer and its fees would go past any overdraft
+    pub sufficient_funds: bool,
+}
+
+#[cfg_attr(test, derive(Serialize))]
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct NewReversalRest {
+    // full remaining amount if not given
+    pub amount_cents: Option<i64>,
+    pub reason: String,
 }
 
 #[derive(Deserialize)]
@@ -61,6 +106,8 @@ pub struct FindTransactionQueryRest {
     pub transaction_id: Option<i32>,
     // TODO: consider removing
     // if queries come from other than person owning account this is useful but i doubt we'll use it
+    #[allow(dead_code)]
     pub customer_id: Option<i32>,
     pub account_number: Option<String>,
+    pub search: Option<String>,
 }
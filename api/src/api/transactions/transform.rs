@@ -1,10 +1,11 @@
-use chrono::NaiveDateTime;
-
-use crate::models::transaction::{NewTransaction, Transaction, TransactionStatus, TransactionType};
+use crate::models::transaction::{
+    NewReversal, NewTransaction, Transaction, TransactionStatus, TransactionType,
+};
+use crate::util::string_opt_from_naive_dt_opt;
 
 use super::models::{
-    NewInternalTransactionRest, TransactionRest, TransactionStatusRest, TransactionTypeRest,
-    TransactionsRest,
+    NewInternalTransactionRest, NewReversalRest, TransactionRest, TransactionStatusRest,
+    TransactionTypeRest, TransactionsRest,
 };
 
 impl From<TransactionType> for TransactionTypeRest {
@@ -12,6 +13,7 @@ impl From<TransactionType> for TransactionTypeRest {
         match transaction_type {
             TransactionType::Internal => TransactionTypeRest::Internal,
             TransactionType::External => TransactionTypeRest::External,
+            TransactionType::Fee => TransactionTypeRest::Fee,
         }
     }
 }
@@ -22,17 +24,15 @@ impl From<TransactionStatus> for TransactionStatusRest {
             TransactionStatus::Pending => TransactionStatusRest::Pending,
             TransactionStatus::Success => TransactionStatusRest::Success,
             TransactionStatus::Error => TransactionStatusRest::Error,
+            TransactionStatus::PartiallyReversed => TransactionStatusRest::PartiallyReversed,
+            TransactionStatus::Reversed => TransactionStatusRest::Reversed,
+            TransactionStatus::Held => TransactionStatusRest::Held,
+            TransactionStatus::AwaitingSignature => TransactionStatusRest::AwaitingSignature,
+            TransactionStatus::PendingApproval => TransactionStatusRest::PendingApproval,
         }
     }
 }
 
-fn string_opt_from_naive_dt_opt(dt: Option<NaiveDateTime>) -> Option<String> {
-    match dt {
-        Some(val) => Some(val.to_string()),
-        None => None,
-    }
-}
-
 impl From<&Transaction> for TransactionRest {
     fn from(tr: &Transaction) -> Self {
         Self {
@@ -51,6 +51,14 @@ impl From<&Transaction> for TransactionRest {
             date_start: tr.date_start.to_string(),
             date_end: string_opt_from_naive_dt_opt(tr.date_end),
             transaction_status: tr.transaction_status.into(),
+            reversal_of: tr.reversal_of,
+            reversed_amount_cents: tr.reversed_amount_cents,
+            reversal_reason: tr.reversal_reason.clone(),
+            description: tr.description.clone(),
+            payer_reference: tr.payer_reference.clone(),
+            payee_reference: tr.payee_reference.clone(),
+            counterpart_of: tr.counterpart_of,
+            running_balance_cents: tr.running_balance_cents,
         }
     }
 }
@@ -58,10 +66,7 @@ impl From<&Transaction> for TransactionRest {
 impl From<Vec<Transaction>> for TransactionsRest {
     fn from(transactions: Vec<Transaction>) -> Self {
         Self {
-            transactions: transactions
-                .iter()
-                .map(|tr| TransactionRest::from(tr))
-                .collect(),
+            transactions: transactions.iter().map(TransactionRest::from).collect(),
         }
     }
 }
@@ -71,6 +76,7 @@ impl From<TransactionTypeRest> for TransactionType {
         match tr_type {
             TransactionTypeRest::Internal => TransactionType::Internal,
             TransactionTypeRest::External => TransactionType::External,
+            TransactionTypeRest::Fee => TransactionType::Fee,
         }
     }
 }
@@ -81,6 +87,11 @@ impl From<TransactionStatusRest> for TransactionStatus {
             TransactionStatusRest::Pending => TransactionStatus::Pending,
             TransactionStatusRest::Success => TransactionStatus::Success,
             TransactionStatusRest::Error => TransactionStatus::Error,
+            TransactionStatusRest::PartiallyReversed => TransactionStatus::PartiallyReversed,
+            TransactionStatusRest::Reversed => TransactionStatus::Reversed,
+            TransactionStatusRest::Held => TransactionStatus::Held,
+            TransactionStatusRest::AwaitingSignature => TransactionStatus::AwaitingSignature,
+            TransactionStatusRest::PendingApproval => TransactionStatus::PendingApproval,
         }
     }
 }
@@ -94,12 +105,33 @@ impl From<NewInternalTransactionRest> for NewTransaction {
             amount_cents: tr.amount_cents,
             from_number: tr.from_number,
             from_bsb: tr.from_bsb,
-            from_name: Some("".to_string()),
-            to_number: tr.to_number,
-            to_bsb: tr.to_bsb,
-            to_name: Some("".to_string()),
+            // set by handler from the account and payee
+            from_name: None,
+            to_number: tr.to_number.unwrap_or_default(),
+            to_bsb: tr.to_bsb.unwrap_or_default(),
+            to_name: None,
             available_balance_cents: 0,
             transaction_status: TransactionStatus::Pending,
+            reversal_of: None,
+            reversal_reason: None,
+            description: non_blank(tr.description),
+            payer_reference: non_blank(tr.payer_reference),
+            payee_reference: non_blank(tr.payee_reference),
+        }
+    }
+}
+
+fn non_blank(text: Option<String>) -> Option<String> {
+    text.map(|t| t.trim().to_string()).filter(|t| !t.is_empty())
+}
+
+impl From<NewReversalRest> for NewReversal {
+    fn from(reversal: NewReversalRest) -> Self {
+        NewReversal {
+            // comes from path, set by handler
+            transaction_id: 0,
+            amount_cents: reversal.amount_cents,
+            reason: reversal.reason,
         }
     }
 }
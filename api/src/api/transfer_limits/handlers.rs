@@ -0,0 +1,701 @@
+use actix_web::http::header::ContentType;
+use actix_web::web::{Data, Path, Query};
+use actix_web::{web, HttpRequest, HttpResponse};
+
+use super::models::{
+    CustomerTransferLimitsRest, FindTransferLimitRequestQueryRest, LimitRequestDecisionRest,
+    NewTransferLimitRequestRest, TransferLimitRequestRest, TransferLimitRequestsRest,
+    TransferLimitUpdateRest, TransferLimitsRest,
+};
+use crate::api::audit::util::{new_audit_entry, record_audit, snapshot};
+use crate::api::error::ApiError;
+use crate::error::RepoError;
+use crate::models::audit::{ActorType, AuditAction, AuditEntity, AuditEntry, NewAuditEntry};
+use crate::models::transfer_limit::{
+    FindTransferLimitRequestQuery, FindTransferUsageQuery, LimitRequestDecision,
+    LimitRequestStatus, NewTransferLimitRequest, TransferLimitKind, TransferLimitRequest,
+    TransferLimitUpdate, TransferLimits, TransferUsage,
+};
+use crate::traits::{RepoCreate, RepoFind, RepoGetById, RepoUpdate};
+use crate::util::start_of_day;
+
+const MAX_REASON_LEN: usize = 500;
+
+pub async fn get_transfer_limits<LR>(
+    limits_repo: Data<LR>,
+    path: Path<i32>,
+) -> Result<HttpResponse, actix_web::Error>
+where
+    LR: RepoGetById<TransferLimits> + RepoFind<TransferUsage, FindTransferUsageQuery>,
+{
+    let customer_id = path.into_inner();
+
+    println!("Trying to get transfer limits for customer {}", customer_id);
+
+    let usage_query = FindTransferUsageQuery {
+        customer_id,
+        since: start_of_day(chrono::Utc::now().naive_utc()),
+    };
+
+    let (limits, usage) = web::block(move || {
+        let limits = limits_repo.get_by_id(customer_id)?;
+        let usage = limits_repo.find(usage_query)?;
+        Ok((limits, usage))
+    })
+    .await
+    .map_err(|_| ApiError::InternalError)?
+    .map_err(|_: RepoError| ApiError::InternalError)?;
+
+    let limits_rest = CustomerTransferLimitsRest {
+        customer_id,
+        limits: (&limits).into(),
+        used_today_cents: usage.iter().map(|u| u.amount_cents).sum(),
+        remaining: (&limits.remaining(&usage)).into(),
+    };
+
+    Ok(HttpResponse::Ok()
+        .insert_header(ContentType::json())
+        .json(web::Json(limits_rest)))
+}
+
+// customers can only tighten their own limits, going up goes through a raise request
+pub async fn lower_transfer_limit<LR, AuR>(
+    limits_repo: Data<LR>,
+    audit_repo: Data<AuR>,
+    req: HttpRequest,
+    path: Path<i32>,
+    payload: web::Json<TransferLimitUpdateRest>,
+) -> Result<HttpResponse, actix_web::Error>
+where
+    LR: RepoGetById<TransferLimits> + RepoUpdate<TransferLimits, TransferLimitUpdate>,
+    AuR: RepoCreate<AuditEntry, NewAuditEntry>,
+{
+    let customer_id = path.into_inner();
+
+    let update = TransferLimitUpdate {
+        limit_kind: payload.limit_kind.into(),
+        amount_cents: payload.amount_cents,
+    };
+
+    if update.amount_cents < 0 {
+        return Err(ApiError::BadRequest.into());
+    }
+
+    println!(
+        "Trying to lower {:?} limit to {} for customer {}",
+        update.limit_kind, update.amount_cents, customer_id
+    );
+
+    let (before, after) = web::block(move || {
+        let before = limits_repo.get_by_id(customer_id)?;
+
+        if update.amount_cents > before.get(update.limit_kind) {
+            return Err(RepoError::Conflict);
+        }
+
+        let after = limits_repo.update(customer_id, update)?;
+        Ok((before, after))
+    })
+    .await
+    .map_err(|_| ApiError::InternalError)?
+    .map_err(|err: RepoError| match err {
+        // raising, not lowering
+        RepoError::Conflict => ApiError::BadRequest,
+        _ => ApiError::InternalError,
+    })?;
+
+    let after_rest: TransferLimitsRest = (&after).into();
+
+    let mut audit_entry = new_audit_entry(
+        &req,
+        ActorType::Customer,
+        customer_id,
+        AuditAction::UpdateTransferLimit,
+        AuditEntity::TransferLimit,
+        Some(customer_id),
+    );
+    audit_entry.before_snapshot = snapshot(&TransferLimitsRest::from(&before));
+    audit_entry.after_snapshot = snapshot(&after_rest);
+    record_audit(audit_repo, audit_entry).await;
+
+    Ok(HttpResponse::Ok()
+        .insert_header(ContentType::json())
+        .json(web::Json(after_rest)))
+}
+
+pub async fn request_limit_raise<LR, LQR, AuR>(
+    limits_repo: Data<LR>,
+    requests_repo: Data<LQR>,
+    audit_repo: Data<AuR>,
+    req: HttpRequest,
+    path: Path<i32>,
+    payload: web::Json<NewTransferLimitRequestRest>,
+) -> Result<HttpResponse, actix_web::Error>
+where
+    LR: RepoGetById<TransferLimits>,
+    LQR: RepoCreate<TransferLimitRequest, NewTransferLimitRequest>,
+    AuR: RepoCreate<AuditEntry, NewAuditEntry>,
+{
+    let customer_id = path.into_inner();
+    let payload = payload.into_inner();
+
+    if payload.reason.trim().is_empty() || payload.reason.len() > MAX_REASON_LEN {
+        return Err(ApiError::BadRequest.into());
+    }
+
+    let limit_kind: TransferLimitKind = payload.limit_kind.into();
+
+    let limits = web::block(move || limits_repo.get_by_id(customer_id))
+        .await
+        .map_err(|_| ApiError::InternalError)?
+        .map_err(|_| ApiError::InternalError)?;
+
+    // lowering doesn't need anyone's sign off
+    if payload.requested_cents <= limits.get(limit_kind) {
+        return Err(ApiError::BadRequest.into());
+    }
+
+    let new_request = NewTransferLimitRequest {
+        customer_id,
+        limit_kind,
+        requested_cents: payload.requested_cents,
+        reason: payload.reason,
+    };
+
+    println!(
+        "Trying to request {:?} limit raise to {} for customer {}",
+        new_request.limit_kind, new_request.requested_cents, customer_id
+    );
+
+    let request = web::block(move || requests_repo.create(new_request))
+        .await
+        .map_err(|_| ApiError::InternalError)?
+        .map_err(|_| ApiError::InternalError)?;
+
+    let request_rest: TransferLimitRequestRest = (&request).into();
+
+    let mut audit_entry = new_audit_entry(
+        &req,
+        ActorType::Customer,
+        customer_id,
+        AuditAction::RequestLimitRaise,
+        AuditEntity::TransferLimitRequest,
+        Some(request.id),
+    );
+    audit_entry.after_snapshot = snapshot(&request_rest);
+    record_audit(audit_repo, audit_entry).await;
+
+    Ok(HttpResponse::Created()
+        .insert_header(ContentType::json())
+        .json(web::Json(request_rest)))
+}
+
+pub async fn find_limit_raise_requests<LQR>(
+    requests_repo: Data<LQR>,
+    path: Path<i32>,
+) -> Result<HttpResponse, actix_web::Error>
+where
+    LQR: RepoFind<TransferLimitRequest, FindTransferLimitRequestQuery>,
+{
+    let customer_id = path.into_inner();
+
+    let query = FindTransferLimitRequestQuery {
+        customer_id: Some(customer_id),
+        request_status: None,
+    };
+
+    println!(
+        "Trying to get limit raise requests for customer {}",
+        customer_id
+    );
+
+    let requests = web::block(move || requests_repo.find(query))
+        .await
+        .map_err(|_| ApiError::InternalError)?
+        .map_err(|_| ApiError::InternalError)?;
+
+    // enforced query on customer_id so should always be fine, but safe > sorry
+    for request in requests.iter() {
+        if request.customer_id != customer_id {
+            return Err(ApiError::BadRequest.into());
+        }
+    }
+
+    Ok(HttpResponse::Ok()
+        .insert_header(ContentType::json())
+        .json(web::Json::<TransferLimitRequestsRest>(requests.into())))
+}
+
+pub async fn staff_find_limit_raise_requests<LQR>(
+    requests_repo: Data<LQR>,
+    path: Path<i32>,
+    query: Query<FindTransferLimitRequestQueryRest>,
+) -> Result<HttpResponse, actix_web::Error>
+where
+    LQR: RepoFind<TransferLimitRequest, FindTransferLimitRequestQuery>,
+{
+    let staff_id = path.into_inner();
+
+    let query = FindTransferLimitRequestQuery {
+        customer_id: query.customer_id,
+        request_status: Some(
+            query
+                .request_status
+                .map(LimitRequestStatus::from)
+                .unwrap_or(LimitRequestStatus::Pending),
+        ),
+    };
+
+    println!("Trying to get limit raise requests for staff {}", staff_id);
+
+    let requests = web::block(move || requests_repo.find(query))
+        .await
+        .map_err(|_| ApiError::InternalError)?
+        .map_err(|_| ApiError::InternalError)?;
+
+    Ok(HttpResponse::Ok()
+        .insert_header(ContentType::json())
+        .json(web::Json::<TransferLimitRequestsRest>(requests.into())))
+}
+
+pub async fn staff_decide_limit_raise<LQR, AuR>(
+    requests_repo: Data<LQR>,
+    audit_repo: Data<AuR>,
+    req: HttpRequest,
+    path: Path<(i32, i32)>,
+    payload: web::Json<LimitRequestDecisionRest>,
+) -> Result<HttpResponse, actix_web::Error>
+where
+    LQR: RepoGetById<TransferLimitRequest> + RepoUpdate<TransferLimitRequest, LimitRequestDecision>,
+    AuR: RepoCreate<AuditEntry, NewAuditEntry>,
+{
+    let (staff_id, request_id) = path.into_inner();
+    let payload = payload.into_inner();
+
+    if payload
+        .note
+        .as_ref()
+        .is_some_and(|note| note.len() > MAX_REASON_LEN)
+    {
+        return Err(ApiError::BadRequest.into());
+    }
+
+    let decision = LimitRequestDecision {
+        approve: payload.approve,
+        reviewer_id: staff_id,
+        note: payload.note,
+    };
+
+    println!(
+        "Trying to {} limit raise request {} for staff {}",
+        if decision.approve {
+            "approve"
+        } else {
+            "decline"
+        },
+        request_id,
+        staff_id
+    );
+
+    let (before, after) = web::block(move || {
+        let before = requests_repo.get_by_id(request_id)?;
+
+        if before.request_status != LimitRequestStatus::Pending {
+            return Err(RepoError::Conflict);
+        }
+
+        let after = requests_repo.update(request_id, decision)?;
+        Ok((before, after))
+    })
+    .await
+    .map_err(|_| ApiError::InternalError)?
+    .map_err(|err: RepoError| match err {
+        RepoError::NotFound => ApiError::NotFound,
+        RepoError::Conflict => ApiError::Conflict,
+        _ => ApiError::InternalError,
+    })?;
+
+    let after_rest: TransferLimitRequestRest = (&after).into();
+
+    let mut audit_entry = new_audit_entry(
+        &req,
+        ActorType::Staff,
+        staff_id,
+        AuditAction::DecideLimitRaise,
+        AuditEntity::TransferLimitRequest,
+        Some(request_id),
+    );
+    audit_entry.before_snapshot = snapshot(&TransferLimitRequestRest::from(&before));
+    audit_entry.after_snapshot = snapshot(&after_rest);
+    record_audit(audit_repo, audit_entry).await;
+
+    Ok(HttpResponse::Ok()
+        .insert_header(ContentType::json())
+        .json(web::Json(after_rest)))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::audit::util::appended;
+    use crate::fixtures::dt;
+    use actix_web::{
+        body::to_bytes,
+        http::StatusCode,
+        test,
+        web::{Data, Json},
+    };
+
+    use mockall::{mock, predicate::eq};
+
+    use crate::{
+        api::{
+            error::ApiError,
+            transfer_limits::{
+                handlers::{
+                    get_transfer_limits, lower_transfer_limit, request_limit_raise,
+                    staff_decide_limit_raise,
+                },
+                models::{
+                    CustomerTransferLimitsRest, LimitRequestDecisionRest, LimitRequestStatusRest,
+                    NewTransferLimitRequestRest, TransferLimitKindRest, TransferLimitRequestRest,
+                    TransferLimitUpdateRest, TransferLimitsRest,
+                },
+            },
+        },
+        error::RepoError,
+        models::{
+            audit::{AuditEntry, NewAuditEntry},
+            transaction::TransactionType,
+            transfer_limit::{
+                FindTransferUsageQuery, LimitRequestDecision, LimitRequestStatus,
+                NewTransferLimitRequest, TransferLimitKind, TransferLimitRequest,
+                TransferLimitUpdate, TransferLimits, TransferUsage,
+            },
+        },
+        traits::{MockRepoCreate, MockRepoGetById, RepoFind, RepoGetById, RepoUpdate},
+    };
+
+    fn limits(customer_id: i32) -> TransferLimits {
+        TransferLimits {
+            customer_id,
+            per_transaction_cents: 100_000,
+            daily_cents: 200_000,
+            per_payee_daily_cents: 100_000,
+            daily_internal_cents: 200_000,
+            daily_external_cents: 50_000,
+        }
+    }
+
+    fn request(id: i32, request_status: LimitRequestStatus) -> TransferLimitRequest {
+        TransferLimitRequest {
+            id,
+            customer_id: 5,
+            limit_kind: TransferLimitKind::Daily,
+            requested_cents: 500_000,
+            reason: "buying a car".to_string(),
+            request_status,
+            reviewer_id: None,
+            review_note: None,
+            created_at: dt(),
+            reviewed_at: None,
+        }
+    }
+
+    mock! {
+        pub LR { }
+        impl RepoGetById<TransferLimits> for LR {
+            fn get_by_id(&self, id: i32) -> Result<TransferLimits, RepoError>;
+        }
+        impl RepoFind<TransferUsage, FindTransferUsageQuery> for LR {
+            fn find(&self, query: FindTransferUsageQuery) -> Result<Vec<TransferUsage>, RepoError>;
+        }
+        impl RepoUpdate<TransferLimits, TransferLimitUpdate> for LR {
+            fn update(&self, id: i32, update: TransferLimitUpdate) -> Result<TransferLimits, RepoError>;
+        }
+    }
+
+    mock! {
+        pub LQR { }
+        impl RepoGetById<TransferLimitRequest> for LQR {
+            fn get_by_id(&self, id: i32) -> Result<TransferLimitRequest, RepoError>;
+        }
+        impl RepoUpdate<TransferLimitRequest, LimitRequestDecision> for LQR {
+            fn update(&self, id: i32, update: LimitRequestDecision) -> Result<TransferLimitRequest, RepoError>;
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_get_transfer_limits_remaining() {
+        let customer_id = 5;
+
+        let mut mock_limits_repo = MockLR::new();
+        mock_limits_repo
+            .expect_get_by_id()
+            .with(eq(customer_id))
+            .times(1)
+            .returning(move |_| Ok(limits(customer_id)));
+        mock_limits_repo
+            .expect_find()
+            .withf(move |query| query.customer_id == customer_id)
+            .times(1)
+            .returning(|_| {
+                Ok(vec![TransferUsage {
+                    transaction_type: TransactionType::Internal,
+                    to_number: "987654321".to_string(),
+                    to_bsb: "123456".to_string(),
+                    amount_cents: 30_000,
+                }])
+            });
+
+        let res = get_transfer_limits(Data::new(mock_limits_repo), customer_id.into())
+            .await
+            .unwrap();
+
+        assert_eq!(StatusCode::OK, res.status());
+
+        let actual: CustomerTransferLimitsRest =
+            serde_json::from_slice(&to_bytes(res.into_body()).await.unwrap()).unwrap();
+        assert_eq!(30_000, actual.used_today_cents);
+        assert_eq!(170_000, actual.remaining.daily_cents);
+        assert_eq!(50_000, actual.remaining.daily_external_cents);
+    }
+
+    #[actix_web::test]
+    async fn test_lower_transfer_limit_success() {
+        let customer_id = 5;
+
+        let mut mock_limits_repo = MockLR::new();
+        mock_limits_repo
+            .expect_get_by_id()
+            .times(1)
+            .returning(move |_| Ok(limits(customer_id)));
+        mock_limits_repo
+            .expect_update()
+            .with(
+                eq(customer_id),
+                eq(TransferLimitUpdate {
+                    limit_kind: TransferLimitKind::Daily,
+                    amount_cents: 50_000,
+                }),
+            )
+            .times(1)
+            .returning(move |_, _| {
+                let mut l = limits(customer_id);
+                l.daily_cents = 50_000;
+                Ok(l)
+            });
+
+        let mut mock_audit_repo = MockRepoCreate::<AuditEntry, NewAuditEntry>::new();
+        mock_audit_repo
+            .expect_create()
+            .withf(|entry| {
+                entry.action == "update_transfer_limit" && entry.before_snapshot.is_some()
+            })
+            .times(1)
+            .returning(|entry| Ok(appended(entry)));
+
+        let res = lower_transfer_limit(
+            Data::new(mock_limits_repo),
+            Data::new(mock_audit_repo),
+            test::TestRequest::put().to_http_request(),
+            customer_id.into(),
+            Json(TransferLimitUpdateRest {
+                limit_kind: TransferLimitKindRest::Daily,
+                amount_cents: 50_000,
+            }),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(StatusCode::OK, res.status());
+
+        let actual: TransferLimitsRest =
+            serde_json::from_slice(&to_bytes(res.into_body()).await.unwrap()).unwrap();
+        assert_eq!(50_000, actual.daily_cents);
+    }
+
+    #[actix_web::test]
+    async fn test_lower_transfer_limit_raise_error() {
+        let mut mock_limits_repo = MockLR::new();
+        mock_limits_repo
+            .expect_get_by_id()
+            .times(1)
+            .returning(|_| Ok(limits(5)));
+        mock_limits_repo.expect_update().times(0);
+
+        let res = lower_transfer_limit(
+            Data::new(mock_limits_repo),
+            Data::new(MockRepoCreate::<AuditEntry, NewAuditEntry>::new()),
+            test::TestRequest::put().to_http_request(),
+            5.into(),
+            Json(TransferLimitUpdateRest {
+                limit_kind: TransferLimitKindRest::Daily,
+                amount_cents: 300_000,
+            }),
+        )
+        .await;
+
+        assert!(res.is_err_and(|e| { e.to_string() == ApiError::BadRequest.to_string() }));
+    }
+
+    #[actix_web::test]
+    async fn test_request_limit_raise_success() {
+        let customer_id = 5;
+
+        let mut mock_limits_repo = MockRepoGetById::<TransferLimits>::new();
+        mock_limits_repo
+            .expect_get_by_id()
+            .times(1)
+            .returning(move |_| Ok(limits(customer_id)));
+
+        let mut mock_requests_repo =
+            MockRepoCreate::<TransferLimitRequest, NewTransferLimitRequest>::new();
+        mock_requests_repo
+            .expect_create()
+            .with(eq(NewTransferLimitRequest {
+                customer_id,
+                limit_kind: TransferLimitKind::Daily,
+                requested_cents: 500_000,
+                reason: "buying a car".to_string(),
+            }))
+            .times(1)
+            .returning(|_| Ok(request(1, LimitRequestStatus::Pending)));
+
+        let mut mock_audit_repo = MockRepoCreate::<AuditEntry, NewAuditEntry>::new();
+        mock_audit_repo
+            .expect_create()
+            .withf(|entry| entry.action == "request_limit_raise")
+            .times(1)
+            .returning(|entry| Ok(appended(entry)));
+
+        let res = request_limit_raise(
+            Data::new(mock_limits_repo),
+            Data::new(mock_requests_repo),
+            Data::new(mock_audit_repo),
+            test::TestRequest::post().to_http_request(),
+            customer_id.into(),
+            Json(NewTransferLimitRequestRest {
+                limit_kind: TransferLimitKindRest::Daily,
+                requested_cents: 500_000,
+                reason: "buying a car".to_string(),
+            }),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(StatusCode::CREATED, res.status());
+
+        let actual: TransferLimitRequestRest =
+            serde_json::from_slice(&to_bytes(res.into_body()).await.unwrap()).unwrap();
+        assert_eq!(LimitRequestStatusRest::Pending, actual.request_status);
+    }
+
+    #[actix_web::test]
+    async fn test_request_limit_raise_not_a_raise_error() {
+        let mut mock_limits_repo = MockRepoGetById::<TransferLimits>::new();
+        mock_limits_repo
+            .expect_get_by_id()
+            .times(1)
+            .returning(|_| Ok(limits(5)));
+
+        let mut mock_requests_repo =
+            MockRepoCreate::<TransferLimitRequest, NewTransferLimitRequest>::new();
+        mock_requests_repo.expect_create().times(0);
+
+        let res = request_limit_raise(
+            Data::new(mock_limits_repo),
+            Data::new(mock_requests_repo),
+            Data::new(MockRepoCreate::<AuditEntry, NewAuditEntry>::new()),
+            test::TestRequest::post().to_http_request(),
+            5.into(),
+            Json(NewTransferLimitRequestRest {
+                limit_kind: TransferLimitKindRest::Daily,
+                requested_cents: 200_000,
+                reason: "buying a car".to_string(),
+            }),
+        )
+        .await;
+
+        assert!(res.is_err_and(|e| { e.to_string() == ApiError::BadRequest.to_string() }));
+    }
+
+    #[actix_web::test]
+    async fn test_staff_decide_limit_raise_approve_success() {
+        let staff_id = 2;
+        let request_id = 1;
+
+        let mut mock_requests_repo = MockLQR::new();
+        mock_requests_repo
+            .expect_get_by_id()
+            .with(eq(request_id))
+            .times(1)
+            .returning(move |_| Ok(request(request_id, LimitRequestStatus::Pending)));
+        mock_requests_repo
+            .expect_update()
+            .with(
+                eq(request_id),
+                eq(LimitRequestDecision {
+                    approve: true,
+                    reviewer_id: staff_id,
+                    note: None,
+                }),
+            )
+            .times(1)
+            .returning(move |_, _| {
+                let mut r = request(request_id, LimitRequestStatus::Approved);
+                r.reviewer_id = Some(staff_id);
+                r.reviewed_at = Some(dt());
+                Ok(r)
+            });
+
+        let mut mock_audit_repo = MockRepoCreate::<AuditEntry, NewAuditEntry>::new();
+        mock_audit_repo
+            .expect_create()
+            .withf(|entry| entry.action == "decide_limit_raise")
+            .times(1)
+            .returning(|entry| Ok(appended(entry)));
+
+        let res = staff_decide_limit_raise(
+            Data::new(mock_requests_repo),
+            Data::new(mock_audit_repo),
+            test::TestRequest::post().to_http_request(),
+            (staff_id, request_id).into(),
+            Json(LimitRequestDecisionRest {
+                approve: true,
+                note: None,
+            }),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(StatusCode::OK, res.status());
+
+        let actual: TransferLimitRequestRest =
+            serde_json::from_slice(&to_bytes(res.into_body()).await.unwrap()).unwrap();
+        assert_eq!(LimitRequestStatusRest::Approved, actual.request_status);
+    }
+
+    #[actix_web::test]
+    async fn test_staff_decide_limit_raise_already_decided_conflict() {
+        let mut mock_requests_repo = MockLQR::new();
+        mock_requests_repo
+            .expect_get_by_id()
+            .times(1)
+            .returning(|_| Ok(request(1, LimitRequestStatus::Declined)));
+        mock_requests_repo.expect_update().times(0);
+
+        let res = staff_decide_limit_raise(
+            Data::new(mock_requests_repo),
+            Data::new(MockRepoCreate::<AuditEntry, NewAuditEntry>::new()),
+            test::TestRequest::post().to_http_request(),
+            (2, 1).into(),
+            Json(LimitRequestDecisionRest {
+                approve: true,
+                note: None,
+            }),
+        )
+        .await;
+
+        assert!(res.is_err_and(|e| { e.to_string() == ApiError::Conflict.to_string() }));
+    }
+}
@@ -0,0 +1,63 @@
+pub mod handlers;
+pub mod models;
+pub mod transform;
+
+use actix_web::web;
+
+use crate::{
+    api::transfer_limits,
+    models::{
+        audit::{AuditEntry, NewAuditEntry},
+        transfer_limit::{
+            FindTransferLimitRequestQuery, FindTransferUsageQuery, LimitRequestDecision,
+            NewTransferLimitRequest, TransferLimitRequest, TransferLimitUpdate, TransferLimits,
+            TransferUsage,
+        },
+    },
+    traits::{RepoCreate, RepoFind, RepoGetById, RepoUpdate},
+};
+
+pub fn configure_transfer_limits_api<LR, LQR, AuR>(cfg: &mut web::ServiceConfig)
+where
+    LR: RepoGetById<TransferLimits>
+        + RepoFind<TransferUsage, FindTransferUsageQuery>
+        + RepoUpdate<TransferLimits, TransferLimitUpdate>,
+    LQR: RepoCreate<TransferLimitRequest, NewTransferLimitRequest>
+        + RepoFind<TransferLimitRequest, FindTransferLimitRequestQuery>
+        + RepoGetById<TransferLimitRequest>
+        + RepoUpdate<TransferLimitRequest, LimitRequestDecision>,
+    AuR: RepoCreate<AuditEntry, NewAuditEntry>,
+{
+    cfg.service(
+        web::scope("/api/customers/{customer_id}/transfer-limits")
+            .service(
+                web::resource("")
+                    .route(web::get().to(transfer_limits::handlers::get_transfer_limits::<LR>))
+                    .route(
+                        web::put().to(transfer_limits::handlers::lower_transfer_limit::<LR, AuR>),
+                    ),
+            )
+            .service(
+                web::resource("/raise-requests")
+                    .route(
+                        web::post().to(transfer_limits::handlers::request_limit_raise::<
+                            LR,
+                            LQR,
+                            AuR,
+                        >),
+                    )
+                    .route(
+                        web::get().to(transfer_limits::handlers::find_limit_raise_requests::<LQR>),
+                    ),
+            ),
+    )
+    .service(
+        web::scope("/api/staff/{staff_id}/transfer-limit-requests")
+            .service(web::resource("").route(
+                web::get().to(transfer_limits::handlers::staff_find_limit_raise_requests::<LQR>),
+            ))
+            .service(web::resource("/{request_id}/decision").route(
+                web::post().to(transfer_limits::handlers::staff_decide_limit_raise::<LQR, AuR>),
+            )),
+    );
+}
@@ -0,0 +1,106 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Serialize, Copy, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum TransferLimitKindRest {
+    PerTransaction,
+    Daily,
+    PerPayeeDaily,
+    DailyInternal,
+    DailyExternal,
+}
+
+#[derive(Deserialize, Serialize, Copy, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum LimitRequestStatusRest {
+    Pending,
+    Approved,
+    Declined,
+}
+
+#[cfg_attr(test, derive(Deserialize, PartialEq, Debug))]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransferLimitsRest {
+    pub per_transaction_cents: i64,
+    pub daily_cents: i64,
+    pub per_payee_daily_cents: i64,
+    pub daily_internal_cents: i64,
+    pub daily_external_cents: i64,
+}
+
+#[cfg_attr(test, derive(Deserialize, PartialEq, Debug))]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransferAllowanceRest {
+    pub daily_cents: i64,
+    pub daily_internal_cents: i64,
+    pub daily_external_cents: i64,
+}
+
+#[cfg_attr(test, derive(Deserialize))]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CustomerTransferLimitsRest {
+    pub customer_id: i32,
+    pub limits: TransferLimitsRest,
+    pub used_today_cents: i64,
+    // per payee isn't here as it depends on the payee
+    pub remaining: TransferAllowanceRest,
+}
+
+#[cfg_attr(test, derive(Serialize))]
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TransferLimitUpdateRest {
+    pub limit_kind: TransferLimitKindRest,
+    pub amount_cents: i64,
+}
+
+#[cfg_attr(test, derive(Serialize))]
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct NewTransferLimitRequestRest {
+    pub limit_kind: TransferLimitKindRest,
+    pub requested_cents: i64,
+    pub reason: String,
+}
+
+#[cfg_attr(test, derive(Deserialize, PartialEq, Debug))]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransferLimitRequestRest {
+    pub id: i32,
+    pub customer_id: i32,
+    pub limit_kind: TransferLimitKindRest,
+    pub requested_cents: i64,
+    pub reason: String,
+    pub request_status: LimitRequestStatusRest,
+    pub reviewer_id: Option<i32>,
+    pub review_note: Option<String>,
+    pub created_at: String,
+    pub reviewed_at: Option<String>,
+}
+
+#[cfg_attr(test, derive(Deserialize))]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransferLimitRequestsRest {
+    pub requests: Vec<TransferLimitRequestRest>,
+}
+
+#[cfg_attr(test, derive(Serialize))]
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct LimitRequestDecisionRest {
+    pub approve: bool,
+    pub note: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FindTransferLimitRequestQueryRest {
+    // pending only if not given
+    pub request_status: Option<LimitRequestStatusRest>,
+    pub customer_id: Option<i32>,
+}
@@ -0,0 +1,103 @@
+use crate::models::transfer_limit::{
+    LimitRequestStatus, TransferAllowance, TransferLimitKind, TransferLimitRequest, TransferLimits,
+};
+use crate::util::string_opt_from_naive_dt_opt;
+
+use super::models::{
+    LimitRequestStatusRest, TransferAllowanceRest, TransferLimitKindRest, TransferLimitRequestRest,
+    TransferLimitRequestsRest, TransferLimitsRest,
+};
+
+impl From<TransferLimitKind> for TransferLimitKindRest {
+    fn from(kind: TransferLimitKind) -> Self {
+        match kind {
+            TransferLimitKind::PerTransaction => TransferLimitKindRest::PerTransaction,
+            TransferLimitKind::Daily => TransferLimitKindRest::Daily,
+            TransferLimitKind::PerPayeeDaily => TransferLimitKindRest::PerPayeeDaily,
+            TransferLimitKind::DailyInternal => TransferLimitKindRest::DailyInternal,
+            TransferLimitKind::DailyExternal => TransferLimitKindRest::DailyExternal,
+        }
+    }
+}
+
+impl From<TransferLimitKindRest> for TransferLimitKind {
+    fn from(kind: TransferLimitKindRest) -> Self {
+        match kind {
+            TransferLimitKindRest::PerTransaction => TransferLimitKind::PerTransaction,
+            TransferLimitKindRest::Daily => TransferLimitKind::Daily,
+            TransferLimitKindRest::PerPayeeDaily => TransferLimitKind::PerPayeeDaily,
+            TransferLimitKindRest::DailyInternal => TransferLimitKind::DailyInternal,
+            TransferLimitKindRest::DailyExternal => TransferLimitKind::DailyExternal,
+        }
+    }
+}
+
+impl From<LimitRequestStatus> for LimitRequestStatusRest {
+    fn from(status: LimitRequestStatus) -> Self {
+        match status {
+            LimitRequestStatus::Pending => LimitRequestStatusRest::Pending,
+            LimitRequestStatus::Approved => LimitRequestStatusRest::Approved,
+            LimitRequestStatus::Declined => LimitRequestStatusRest::Declined,
+        }
+    }
+}
+
+impl From<LimitRequestStatusRest> for LimitRequestStatus {
+    fn from(status: LimitRequestStatusRest) -> Self {
+        match status {
+            LimitRequestStatusRest::Pending => LimitRequestStatus::Pending,
+            LimitRequestStatusRest::Approved => LimitRequestStatus::Approved,
+            LimitRequestStatusRest::Declined => LimitRequestStatus::Declined,
+        }
+    }
+}
+
+impl From<&TransferLimits> for TransferLimitsRest {
+    fn from(limits: &TransferLimits) -> Self {
+        Self {
+            per_transaction_cents: limits.per_transaction_cents,
+            daily_cents: limits.daily_cents,
+            per_payee_daily_cents: limits.per_payee_daily_cents,
+            daily_internal_cents: limits.daily_internal_cents,
+            daily_external_cents: limits.daily_external_cents,
+        }
+    }
+}
+
+impl From<&TransferAllowance> for TransferAllowanceRest {
+    fn from(allowance: &TransferAllowance) -> Self {
+        Self {
+            daily_cents: allowance.daily_cents,
+            daily_internal_cents: allowance.daily_internal_cents,
+            daily_external_cents: allowance.daily_external_cents,
+        }
+    }
+}
+
+impl From<&TransferLimitRequest> for TransferLimitRequestRest {
+    fn from(request: &TransferLimitRequest) -> Self {
+        Self {
+            id: request.id,
+            customer_id: request.customer_id,
+            limit_kind: request.limit_kind.into(),
+            requested_cents: request.requested_cents,
+            reason: request.reason.clone(),
+            request_status: request.request_status.into(),
+            reviewer_id: request.reviewer_id,
+            review_note: request.review_note.clone(),
+            created_at: request.created_at.to_string(),
+            reviewed_at: string_opt_from_naive_dt_opt(request.reviewed_at),
+        }
+    }
+}
+
+impl From<Vec<TransferLimitRequest>> for TransferLimitRequestsRest {
+    fn from(requests: Vec<TransferLimitRequest>) -> Self {
+        Self {
+            requests: requests
+                .iter()
+                .map(TransferLimitRequestRest::from)
+                .collect(),
+        }
+    }
+}
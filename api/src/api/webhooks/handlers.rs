@@ -0,0 +1,442 @@
+use actix_web::http::header::ContentType;
+use actix_web::web::{Data, Path};
+use actix_web::{web, HttpRequest, HttpResponse};
+
+use super::models::{
+    CreatedWebhookSubscriptionRest, NewWebhookSubscriptionRest, WebhookDeliveriesRest,
+    WebhookDeliveryRest, WebhookSubscriptionRest,
+};
+use crate::api::audit::util::{new_audit_entry, record_audit, snapshot};
+use crate::api::error::ApiError;
+use crate::error::RepoError;
+use crate::models::audit::{ActorType, AuditAction, AuditEntity, AuditEntry, NewAuditEntry};
+use crate::models::webhook::{
+    event_types_string, parse_event_types, FindWebhookDeliveryQuery, FindWebhookSubscriptionQuery,
+    NewWebhookSubscription, WebhookDelivery, WebhookDeliveryLog, WebhookReplay,
+    WebhookSubscription, WebhookSubscriptionRevocation,
+};
+use crate::oauth::generate_token;
+use crate::traits::{RepoCreate, RepoFind, RepoGetById, RepoUpdate};
+use crate::util::is_valid_callback_url;
+
+pub async fn find_webhook_subscriptions<WR>(
+    webhooks_repo: Data<WR>,
+    path: Path<i32>,
+) -> Result<HttpResponse, actix_web::Error>
+where
+    WR: RepoFind<WebhookSubscription, FindWebhookSubscriptionQuery>,
+{
+    let staff_id = path.into_inner();
+
+    println!("Staff {} trying to find webhook subscriptions", staff_id);
+
+    let subscriptions = web::block(move || {
+        webhooks_repo.find(FindWebhookSubscriptionQuery {
+            include_revoked: true,
+        })
+    })
+    .await
+    .map_err(|_| ApiError::InternalError)?
+    .map_err(|_| ApiError::InternalError)?;
+
+    Ok(HttpResponse::Ok().insert_header(ContentType::json()).json(
+        subscriptions
+            .iter()
+            .map(|subscription| subscription.into())
+            .collect::<Vec<WebhookSubscriptionRest>>(),
+    ))
+}
+
+pub async fn create_webhook_subscription<WR, AuR>(
+    webhooks_repo: Data<WR>,
+    audit_repo: Data<AuR>,
+    req: HttpRequest,
+    path: Path<i32>,
+    payload: web::Json<NewWebhookSubscriptionRest>,
+) -> Result<HttpResponse, actix_web::Error>
+where
+    WR: RepoCreate<WebhookSubscription, NewWebhookSubscription>,
+    AuR: RepoCreate<AuditEntry, NewAuditEntry>,
+{
+    let staff_id = path.into_inner();
+    let payload = payload.into_inner();
+
+    let url = payload.url.trim().to_string();
+    if !is_valid_callback_url(&url) {
+        return Err(ApiError::BadRequest.into());
+    }
+    let event_types = parse_event_types(&payload.event_types).ok_or(ApiError::BadRequest)?;
+
+    let secret = generate_token();
+
+    let new_subscription = NewWebhookSubscription {
+        url,
+        event_types: event_types_string(&event_types),
+        secret: secret.clone(),
+        created_by: staff_id,
+    };
+
+    println!(
+        "Staff {} trying to subscribe {} to {}",
+        staff_id, new_subscription.url, new_subscription.event_types
+    );
+
+    let subscription = web::block(move || webhooks_repo.create(new_subscription))
+        .await
+        .map_err(|_| ApiError::InternalError)?
+        .map_err(|_| ApiError::InternalError)?;
+
+    let subscription_rest: WebhookSubscriptionRest = (&subscription).into();
+
+    let mut audit_entry = new_audit_entry(
+        &req,
+        ActorType::Staff,
+        staff_id,
+        AuditAction::CreateWebhookSubscription,
+        AuditEntity::WebhookSubscription,
+        Some(subscription.id),
+    );
+    audit_entry.after_snapshot = snapshot(&subscription_rest);
+    record_audit(audit_repo, audit_entry).await;
+
+    Ok(HttpResponse::Created()
+        .insert_header(ContentType::json())
+        .json(web::Json(CreatedWebhookSubscriptionRest {
+            subscription: subscription_rest,
+            secret,
+        })))
+}
+
+// nothing more goes out to it, deliveries still waiting are given up on
+pub async fn revoke_webhook_subscription<WR, AuR>(
+    webhooks_repo: Data<WR>,
+    audit_repo: Data<AuR>,
+    req: HttpRequest,
+    path: Path<(i32, i32)>,
+) -> Result<HttpResponse, actix_web::Error>
+where
+    WR: RepoUpdate<WebhookSubscription, WebhookSubscriptionRevocation>,
+    AuR: RepoCreate<AuditEntry, NewAuditEntry>,
+{
+    let (staff_id, id) = path.into_inner();
+
+    println!(
+        "Staff {} trying to revoke webhook subscription {}",
+        staff_id, id
+    );
+
+    let subscription = web::block(move || webhooks_repo.update(id, WebhookSubscriptionRevocation))
+        .await
+        .map_err(|_| ApiError::InternalError)?
+        .map_err(|err| match err {
+            RepoError::NotFound => ApiError::NotFound,
+            // already revoked
+            RepoError::Conflict => ApiError::Conflict,
+            _ => ApiError::InternalError,
+        })?;
+
+    let subscription_rest: WebhookSubscriptionRest = (&subscription).into();
+
+    let mut audit_entry = new_audit_entry(
+        &req,
+        ActorType::Staff,
+        staff_id,
+        AuditAction::RevokeWebhookSubscription,
+        AuditEntity::WebhookSubscription,
+        Some(subscription.id),
+    );
+    audit_entry.after_snapshot = snapshot(&subscription_rest);
+    record_audit(audit_repo, audit_entry).await;
+
+    Ok(HttpResponse::Ok()
+        .insert_header(ContentType::json())
+        .json(web::Json(subscription_rest)))
+}
+
+// the latest deliveries to the subscription and every attempt at each
+pub async fn find_webhook_deliveries<WR>(
+    webhooks_repo: Data<WR>,
+    path: Path<(i32, i32)>,
+) -> Result<HttpResponse, actix_web::Error>
+where
+    WR: RepoGetById<WebhookSubscription> + RepoFind<WebhookDeliveryLog, FindWebhookDeliveryQuery>,
+{
+    let (staff_id, subscription_id) = path.into_inner();
+
+    println!(
+        "Staff {} trying to find deliveries for webhook subscription {}",
+        staff_id, subscription_id
+    );
+
+    let deliveries = web::block(move || {
+        webhooks_repo.get_by_id(subscription_id)?;
+        webhooks_repo.find(FindWebhookDeliveryQuery { subscription_id })
+    })
+    .await
+    .map_err(|_| ApiError::InternalError)?
+    .map_err(|err| match err {
+        RepoError::NotFound => ApiError::NotFound,
+        _ => ApiError::InternalError,
+    })?;
+
+    Ok(HttpResponse::Ok()
+        .insert_header(ContentType::json())
+        .json(web::Json::<WebhookDeliveriesRest>(deliveries.into())))
+}
+
+// sends a delivered or given up on event again, with a fresh set of attempts
+pub async fn replay_webhook_delivery<WR, AuR>(
+    webhooks_repo: Data<WR>,
+    audit_repo: Data<AuR>,
+    req: HttpRequest,
+    path: Path<(i32, i32, i32)>,
+) -> Result<HttpResponse, actix_web::Error>
+where
+    WR: RepoUpdate<WebhookDelivery, WebhookReplay>,
+    AuR: RepoCreate<AuditEntry, NewAuditEntry>,
+{
+    let (staff_id, subscription_id, delivery_id) = path.into_inner();
+
+    println!(
+        "Staff {} trying to replay webhook delivery {}",
+        staff_id, delivery_id
+    );
+
+    let delivery =
+        web::block(move || webhooks_repo.update(delivery_id, WebhookReplay { subscription_id }))
+            .await
+            .map_err(|_| ApiError::InternalError)?
+            .map_err(|err| match err {
+                RepoError::NotFound => ApiError::NotFound,
+                // still pending, or the subscription's been revoked
+                RepoError::Conflict => ApiError::Conflict,
+                _ => ApiError::InternalError,
+            })?;
+
+    let delivery_rest: WebhookDeliveryRest = (&delivery).into();
+
+    let mut audit_entry = new_audit_entry(
+        &req,
+        ActorType::Staff,
+        staff_id,
+        AuditAction::ReplayWebhookDelivery,
+        AuditEntity::WebhookDelivery,
+        Some(delivery.id),
+    );
+    audit_entry.after_snapshot = snapshot(&delivery_rest);
+    record_audit(audit_repo, audit_entry).await;
+
+    Ok(HttpResponse::Ok()
+        .insert_header(ContentType::json())
+        .json(web::Json(delivery_rest)))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::fixtures::audited_where;
+    use crate::fixtures::dt;
+    use actix_web::{
+        body::to_bytes,
+        http::StatusCode,
+        test,
+        web::{Data, Json},
+    };
+
+    use mockall::predicate::eq;
+
+    use crate::{
+        api::{
+            error::ApiError,
+            webhooks::{
+                handlers::{
+                    create_webhook_subscription, replay_webhook_delivery,
+                    revoke_webhook_subscription,
+                },
+                models::{
+                    CreatedWebhookSubscriptionRest, NewWebhookSubscriptionRest,
+                    WebhookDeliveryRest, WebhookDeliveryStatusRest,
+                },
+            },
+        },
+        error::RepoError,
+        models::{
+            audit::{AuditEntry, NewAuditEntry},
+            webhook::{
+                NewWebhookSubscription, WebhookDelivery, WebhookDeliveryStatus, WebhookReplay,
+                WebhookSubscription, WebhookSubscriptionRevocation,
+            },
+        },
+        traits::{MockRepoCreate, MockRepoUpdate},
+    };
+
+    async fn create(
+        mock_webhooks_repo: MockRepoCreate<WebhookSubscription, NewWebhookSubscription>,
+        mock_audit_repo: MockRepoCreate<AuditEntry, NewAuditEntry>,
+        url: &str,
+        event_types: &[&str],
+    ) -> Result<actix_web::HttpResponse, actix_web::Error> {
+        create_webhook_subscription(
+            Data::new(mock_webhooks_repo),
+            Data::new(mock_audit_repo),
+            test::TestRequest::post().to_http_request(),
+            3.into(),
+            Json(NewWebhookSubscriptionRest {
+                url: url.to_string(),
+                event_types: event_types.iter().map(|t| t.to_string()).collect(),
+            }),
+        )
+        .await
+    }
+
+    #[actix_web::test]
+    async fn test_create_webhook_subscription_success() {
+        let mut mock_webhooks_repo =
+            MockRepoCreate::<WebhookSubscription, NewWebhookSubscription>::new();
+        mock_webhooks_repo
+            .expect_create()
+            .withf(|new| {
+                new.url == "https://example.com/hooks"
+                    && new.event_types == "transaction.created account.closed"
+                    && new.secret.len() >= 32
+                    && new.created_by == 3
+            })
+            .times(1)
+            .returning(|new| {
+                Ok(WebhookSubscription {
+                    id: 1,
+                    url: new.url,
+                    event_types: new.event_types,
+                    secret: new.secret,
+                    created_by: new.created_by,
+                    created_at: dt(),
+                    revoked_at: None,
+                })
+            });
+
+        let res = create(
+            mock_webhooks_repo,
+            audited_where("create_webhook_subscription", |entry| entry.actor_id == 3),
+            " https://example.com/hooks ",
+            &["account.closed", "transaction.created"],
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(StatusCode::CREATED, res.status());
+
+        let actual: CreatedWebhookSubscriptionRest =
+            serde_json::from_slice(&to_bytes(res.into_body()).await.unwrap()).unwrap();
+        assert_eq!(
+            vec!["transaction.created", "account.closed"],
+            actual.subscription.event_types
+        );
+        assert!(actual.secret.len() >= 32);
+    }
+
+    #[actix_web::test]
+    async fn test_create_webhook_subscription_bad_request() {
+        let cases: [(&str, &[&str]); 4] = [
+            // plain http only goes to a local receiver
+            ("http://example.com/hooks", &["transaction.created"]),
+            ("not a url", &["transaction.created"]),
+            ("https://example.com/hooks", &[]),
+            ("https://example.com/hooks", &["account.updated"]),
+        ];
+
+        for (url, event_types) in cases {
+            let mut mock_webhooks_repo =
+                MockRepoCreate::<WebhookSubscription, NewWebhookSubscription>::new();
+            mock_webhooks_repo.expect_create().never();
+
+            let res = create(
+                mock_webhooks_repo,
+                MockRepoCreate::<AuditEntry, NewAuditEntry>::new(),
+                url,
+                event_types,
+            )
+            .await;
+
+            assert!(res.is_err_and(|e| { e.to_string() == ApiError::BadRequest.to_string() }));
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_revoke_webhook_subscription_already_revoked_conflict() {
+        let mut mock_webhooks_repo =
+            MockRepoUpdate::<WebhookSubscription, WebhookSubscriptionRevocation>::new();
+        mock_webhooks_repo
+            .expect_update()
+            .with(eq(1), eq(WebhookSubscriptionRevocation))
+            .times(1)
+            .returning(|_, _| Err(RepoError::Conflict));
+
+        let res = revoke_webhook_subscription(
+            Data::new(mock_webhooks_repo),
+            Data::new(MockRepoCreate::<AuditEntry, NewAuditEntry>::new()),
+            test::TestRequest::post().to_http_request(),
+            (3, 1).into(),
+        )
+        .await;
+
+        assert!(res.is_err_and(|e| { e.to_string() == ApiError::Conflict.to_string() }));
+    }
+
+    #[actix_web::test]
+    async fn test_replay_webhook_delivery_success() {
+        let mut mock_webhooks_repo = MockRepoUpdate::<WebhookDelivery, WebhookReplay>::new();
+        mock_webhooks_repo
+            .expect_update()
+            .with(eq(7), eq(WebhookReplay { subscription_id: 1 }))
+            .times(1)
+            .returning(|id, replay| {
+                Ok(WebhookDelivery {
+                    id,
+                    subscription_id: replay.subscription_id,
+                    event_id: "0f8fad5b-d9cb-469f-a165-70867728950e".to_string(),
+                    delivery_status: WebhookDeliveryStatus::Pending,
+                    attempts: 0,
+                    next_attempt_at: Some(dt()),
+                    delivered_at: None,
+                    created_at: dt(),
+                })
+            });
+
+        let res = replay_webhook_delivery(
+            Data::new(mock_webhooks_repo),
+            Data::new(audited_where("replay_webhook_delivery", |entry| {
+                entry.actor_id == 3
+            })),
+            test::TestRequest::post().to_http_request(),
+            (3, 1, 7).into(),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(StatusCode::OK, res.status());
+
+        let actual: WebhookDeliveryRest =
+            serde_json::from_slice(&to_bytes(res.into_body()).await.unwrap()).unwrap();
+        assert_eq!(WebhookDeliveryStatusRest::Pending, actual.status);
+        assert!(actual.attempts.is_empty());
+    }
+
+    #[actix_web::test]
+    async fn test_replay_webhook_delivery_still_pending_conflict() {
+        let mut mock_webhooks_repo = MockRepoUpdate::<WebhookDelivery, WebhookReplay>::new();
+        mock_webhooks_repo
+            .expect_update()
+            .times(1)
+            .returning(|_, _| Err(RepoError::Conflict));
+
+        let res = replay_webhook_delivery(
+            Data::new(mock_webhooks_repo),
+            Data::new(MockRepoCreate::<AuditEntry, NewAuditEntry>::new()),
+            test::TestRequest::post().to_http_request(),
+            (3, 1, 7).into(),
+        )
+        .await;
+
+        assert!(res.is_err_and(|e| { e.to_string() == ApiError::Conflict.to_string() }));
+    }
+}
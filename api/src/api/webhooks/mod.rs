@@ -0,0 +1,53 @@
+pub mod handlers;
+pub mod models;
+pub mod transform;
+
+use actix_web::web;
+
+use crate::{
+    api::webhooks,
+    models::{
+        audit::{AuditEntry, NewAuditEntry},
+        webhook::{
+            FindWebhookDeliveryQuery, FindWebhookSubscriptionQuery, NewWebhookSubscription,
+            WebhookDelivery, WebhookDeliveryLog, WebhookReplay, WebhookSubscription,
+            WebhookSubscriptionRevocation,
+        },
+    },
+    traits::{RepoCreate, RepoFind, RepoGetById, RepoUpdate},
+};
+
+pub fn configure_webhooks_api<WR, AuR>(cfg: &mut web::ServiceConfig)
+where
+    WR: RepoCreate<WebhookSubscription, NewWebhookSubscription>
+        + RepoFind<WebhookSubscription, FindWebhookSubscriptionQuery>
+        + RepoGetById<WebhookSubscription>
+        + RepoUpdate<WebhookSubscription, WebhookSubscriptionRevocation>
+        + RepoFind<WebhookDeliveryLog, FindWebhookDeliveryQuery>
+        + RepoUpdate<WebhookDelivery, WebhookReplay>,
+    AuR: RepoCreate<AuditEntry, NewAuditEntry>,
+{
+    cfg.service(
+        web::scope("/api/staff/{staff_id}/webhook-subscriptions")
+            .service(
+                web::resource("")
+                    .route(web::get().to(webhooks::handlers::find_webhook_subscriptions::<WR>))
+                    .route(
+                        web::post().to(webhooks::handlers::create_webhook_subscription::<WR, AuR>),
+                    ),
+            )
+            .service(
+                web::resource("/{subscription_id}/revoke").route(
+                    web::post().to(webhooks::handlers::revoke_webhook_subscription::<WR, AuR>),
+                ),
+            )
+            .service(
+                web::resource("/{subscription_id}/deliveries")
+                    .route(web::get().to(webhooks::handlers::find_webhook_deliveries::<WR>)),
+            )
+            .service(
+                web::resource("/{subscription_id}/deliveries/{delivery_id}/replay")
+                    .route(web::post().to(webhooks::handlers::replay_webhook_delivery::<WR, AuR>)),
+            ),
+    );
+}
@@ -0,0 +1,71 @@
+use serde::{Deserialize, Serialize};
+
+#[cfg_attr(test, derive(Serialize))]
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct NewWebhookSubscriptionRest {
+    pub url: String,
+    // e.g. transaction.created, at least one
+    pub event_types: Vec<String>,
+}
+
+#[cfg_attr(test, derive(Deserialize, PartialEq, Debug))]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WebhookSubscriptionRest {
+    pub id: i32,
+    pub url: String,
+    pub event_types: Vec<String>,
+    pub created_by: i32,
+    pub created_at: String,
+    pub revoked_at: Option<String>,
+}
+
+// the secret is only ever shown here, when subscribing
+#[cfg_attr(test, derive(Deserialize, PartialEq, Debug))]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreatedWebhookSubscriptionRest {
+    pub subscription: WebhookSubscriptionRest,
+    pub secret: String,
+}
+
+#[derive(Deserialize, Serialize, Copy, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum WebhookDeliveryStatusRest {
+    Pending,
+    Delivered,
+    Failed,
+}
+
+#[cfg_attr(test, derive(Deserialize, PartialEq, Debug))]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WebhookAttemptRest {
+    pub response_status: Option<i32>,
+    pub error: Option<String>,
+    pub attempted_at: String,
+}
+
+#[cfg_attr(test, derive(Deserialize, PartialEq, Debug))]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WebhookDeliveryRest {
+    pub id: i32,
+    pub subscription_id: i32,
+    pub event_id: String,
+    pub event_type: Option<String>,
+    pub status: WebhookDeliveryStatusRest,
+    pub next_attempt_at: Option<String>,
+    pub delivered_at: Option<String>,
+    pub created_at: String,
+    // oldest first, only in the delivery log
+    pub attempts: Vec<WebhookAttemptRest>,
+}
+
+#[cfg_attr(test, derive(Deserialize))]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WebhookDeliveriesRest {
+    pub deliveries: Vec<WebhookDeliveryRest>,
+}
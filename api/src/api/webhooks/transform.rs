@@ -0,0 +1,81 @@
+use crate::models::webhook::{
+    WebhookDelivery, WebhookDeliveryAttempt, WebhookDeliveryLog, WebhookDeliveryStatus,
+    WebhookSubscription,
+};
+use crate::util::string_opt_from_naive_dt_opt;
+
+use super::models::{
+    WebhookAttemptRest, WebhookDeliveriesRest, WebhookDeliveryRest, WebhookDeliveryStatusRest,
+    WebhookSubscriptionRest,
+};
+
+impl From<&WebhookSubscription> for WebhookSubscriptionRest {
+    fn from(subscription: &WebhookSubscription) -> Self {
+        WebhookSubscriptionRest {
+            id: subscription.id,
+            url: subscription.url.clone(),
+            event_types: subscription
+                .event_types
+                .split_whitespace()
+                .map(|t| t.to_string())
+                .collect(),
+            created_by: subscription.created_by,
+            created_at: subscription.created_at.to_string(),
+            revoked_at: string_opt_from_naive_dt_opt(subscription.revoked_at),
+        }
+    }
+}
+
+impl From<WebhookDeliveryStatus> for WebhookDeliveryStatusRest {
+    fn from(status: WebhookDeliveryStatus) -> Self {
+        match status {
+            WebhookDeliveryStatus::Pending => WebhookDeliveryStatusRest::Pending,
+            WebhookDeliveryStatus::Delivered => WebhookDeliveryStatusRest::Delivered,
+            WebhookDeliveryStatus::Failed => WebhookDeliveryStatusRest::Failed,
+        }
+    }
+}
+
+impl From<&WebhookDeliveryAttempt> for WebhookAttemptRest {
+    fn from(attempt: &WebhookDeliveryAttempt) -> Self {
+        WebhookAttemptRest {
+            response_status: attempt.response_status,
+            error: attempt.error.clone(),
+            attempted_at: attempt.attempted_at.to_string(),
+        }
+    }
+}
+
+impl From<&WebhookDelivery> for WebhookDeliveryRest {
+    fn from(delivery: &WebhookDelivery) -> Self {
+        WebhookDeliveryRest {
+            id: delivery.id,
+            subscription_id: delivery.subscription_id,
+            event_id: delivery.event_id.clone(),
+            event_type: None,
+            status: delivery.delivery_status.into(),
+            next_attempt_at: string_opt_from_naive_dt_opt(delivery.next_attempt_at),
+            delivered_at: string_opt_from_naive_dt_opt(delivery.delivered_at),
+            created_at: delivery.created_at.to_string(),
+            attempts: vec![],
+        }
+    }
+}
+
+impl From<&WebhookDeliveryLog> for WebhookDeliveryRest {
+    fn from(log: &WebhookDeliveryLog) -> Self {
+        WebhookDeliveryRest {
+            event_type: Some(log.event_type.clone()),
+            attempts: log.attempts.iter().map(WebhookAttemptRest::from).collect(),
+            ..(&log.delivery).into()
+        }
+    }
+}
+
+impl From<Vec<WebhookDeliveryLog>> for WebhookDeliveriesRest {
+    fn from(logs: Vec<WebhookDeliveryLog>) -> Self {
+        WebhookDeliveriesRest {
+            deliveries: logs.iter().map(WebhookDeliveryRest::from).collect(),
+        }
+    }
+}
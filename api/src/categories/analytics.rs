@@ -0,0 +1,255 @@
+// totals over a customer's spending. amounts are what actually left the account, so anything
+// already reversed is taken off
+
+use std::collections::HashMap;
+
+use super::{payee_account, payee_name, Categoriser};
+use crate::models::{
+    category::{CategoryOverride, SpendingCategory},
+    transaction::Transaction,
+};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct CategoryTotal {
+    pub category: SpendingCategory,
+    pub total_cents: i64,
+    pub transaction_count: usize,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct MonthTotal {
+    // YYYY-MM
+    pub month: String,
+    pub total_cents: i64,
+    pub transaction_count: usize,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct PayeeTotal {
+    pub account_number: String,
+    pub bsb: String,
+    // most recent name we have for the account
+    pub name: Option<String>,
+    pub category: SpendingCategory,
+    pub total_cents: i64,
+    pub transaction_count: usize,
+}
+
+fn spent_cents(transaction: &Transaction) -> i64 {
+    transaction.amount_cents - transaction.reversed_amount_cents
+}
+
+fn counted(transactions: &[Transaction]) -> impl Iterator<Item = &Transaction> {
+    transactions.iter().filter(|tr| spent_cents(tr) > 0)
+}
+
+// largest first
+pub fn totals_by_category(
+    categoriser: &Categoriser,
+    transactions: &[Transaction],
+    overrides: &[CategoryOverride],
+) -> Vec<CategoryTotal> {
+    let mut totals: HashMap<SpendingCategory, CategoryTotal> = HashMap::new();
+
+    for tr in counted(transactions) {
+        let category = categoriser.categorise(tr, overrides);
+        let total = totals.entry(category).or_insert(CategoryTotal {
+            category,
+            total_cents: 0,
+            transaction_count: 0,
+        });
+        total.total_cents += spent_cents(tr);
+        total.transaction_count += 1;
+    }
+
+    let mut totals: Vec<CategoryTotal> = totals.into_values().collect();
+    totals.sort_by(|a, b| {
+        b.total_cents
+            .cmp(&a.total_cents)
+            .then((a.category as u8).cmp(&(b.category as u8)))
+    });
+    totals
+}
+
+// oldest first, months with nothing spent are left out
+pub fn totals_by_month(transactions: &[Transaction]) -> Vec<MonthTotal> {
+    let mut totals: Vec<MonthTotal> = vec![];
+
+    let mut sorted: Vec<&Transaction> = counted(transactions).collect();
+    sorted.sort_by_key(|tr| tr.date_start);
+
+    for tr in sorted {
+        let month = tr.date_start.format("%Y-%m").to_string();
+        match totals.last_mut() {
+            Some(total) if total.month == month => {
+                total.total_cents += spent_cents(tr);
+                total.transaction_count += 1;
+            }
+            _ => totals.push(MonthTotal {
+                month,
+                total_cents: spent_cents(tr),
+                transaction_count: 1,
+            }),
+        }
+    }
+
+    totals
+}
+
+// largest first
+pub fn totals_by_payee(
+    categoriser: &Categoriser,
+    transactions: &[Transaction],
+    overrides: &[CategoryOverride],
+) -> Vec<PayeeTotal> {
+    let mut totals: HashMap<(String, String), (PayeeTotal, chrono::NaiveDateTime)> = HashMap::new();
+
+    for tr in counted(transactions) {
+        let (number, bsb) = payee_account(tr);
+        let (total, latest) = totals
+            .entry((number.to_string(), bsb.to_string()))
+            .or_insert((
+                PayeeTotal {
+                    account_number: number.to_string(),
+                    bsb: bsb.to_string(),
+                    name: None,
+                    category: categoriser.categorise(tr, overrides),
+                    total_cents: 0,
+                    transaction_count: 0,
+                },
+                tr.date_start,
+            ));
+
+        if tr.date_start >= *latest || total.name.is_none() {
+            if let Some(name) = payee_name(tr) {
+                total.name = Some(name.to_string());
+                total.category = categoriser.categorise(tr, overrides);
+                *latest = tr.date_start;
+            }
+        }
+
+        total.total_cents += spent_cents(tr);
+        total.transaction_count += 1;
+    }
+
+    let mut totals: Vec<PayeeTotal> = totals.into_values().map(|(total, _)| total).collect();
+    totals.sort_by(|a, b| {
+        b.total_cents
+            .cmp(&a.total_cents)
+            .then(a.bsb.cmp(&b.bsb))
+            .then(a.account_number.cmp(&b.account_number))
+    });
+    totals
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{NaiveDate, NaiveDateTime};
+
+    use super::{totals_by_category, totals_by_month, totals_by_payee};
+    use crate::{
+        categories::Categoriser,
+        models::{
+            category::SpendingCategory,
+            transaction::{Transaction, TransactionStatus, TransactionType},
+        },
+    };
+
+    fn at(m: u32, d: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2023, m, d)
+            .unwrap()
+            .and_hms_opt(9, 0, 0)
+            .unwrap()
+    }
+
+    fn spend(id: i32, to: &str, name: &str, amount_cents: i64, date: NaiveDateTime) -> Transaction {
+        Transaction {
+            id,
+            customer_id: 5,
+            transaction_type: TransactionType::External,
+            from_us: true,
+            amount_cents,
+            from_number: "938573843".to_string(),
+            from_bsb: "123456".to_string(),
+            from_name: None,
+            to_number: to.to_string(),
+            to_bsb: "654321".to_string(),
+            to_name: Some(name.to_string()),
+            available_balance_cents: 0,
+            date_start: date,
+            date_end: Some(date),
+            transaction_status: TransactionStatus::Success,
+            reversal_of: None,
+            reversed_amount_cents: 0,
+            reversal_reason: None,
+            description: None,
+            payer_reference: None,
+            payee_reference: None,
+            counterpart_of: None,
+            running_balance_cents: None,
+        }
+    }
+
+    fn history() -> Vec<Transaction> {
+        let mut refunded = spend(4, "222222222", "Kmart", 3000, at(8, 2));
+        refunded.reversed_amount_cents = 1000;
+        refunded.transaction_status = TransactionStatus::PartiallyReversed;
+
+        vec![
+            spend(1, "111111111", "Coles", 5000, at(7, 10)),
+            spend(2, "111111111", "Coles Central", 2500, at(8, 1)),
+            spend(3, "333333333", "Netflix", 1599, at(8, 5)),
+            refunded,
+        ]
+    }
+
+    #[test]
+    fn category_totals_largest_first() {
+        let totals = totals_by_category(&Categoriser::default(), &history(), &[]);
+
+        let actual: Vec<(SpendingCategory, i64, usize)> = totals
+            .iter()
+            .map(|t| (t.category, t.total_cents, t.transaction_count))
+            .collect();
+        assert_eq!(
+            vec![
+                (SpendingCategory::Groceries, 7500, 2),
+                (SpendingCategory::Shopping, 2000, 1),
+                (SpendingCategory::Entertainment, 1599, 1),
+            ],
+            actual
+        );
+    }
+
+    #[test]
+    fn month_totals_oldest_first() {
+        let totals = totals_by_month(&history());
+
+        let actual: Vec<(&str, i64)> = totals
+            .iter()
+            .map(|t| (t.month.as_str(), t.total_cents))
+            .collect();
+        assert_eq!(vec![("2023-07", 5000), ("2023-08", 6099)], actual);
+    }
+
+    #[test]
+    fn payee_totals_use_latest_name() {
+        let totals = totals_by_payee(&Categoriser::default(), &history(), &[]);
+
+        assert_eq!(3, totals.len());
+        assert_eq!("111111111", totals[0].account_number);
+        assert_eq!(Some("Coles Central".to_string()), totals[0].name);
+        assert_eq!(7500, totals[0].total_cents);
+        assert_eq!(2, totals[0].transaction_count);
+        assert_eq!(SpendingCategory::Groceries, totals[0].category);
+    }
+
+    #[test]
+    fn fully_reversed_not_counted() {
+        let mut reversed = spend(5, "111111111", "Coles", 800, at(8, 9));
+        reversed.reversed_amount_cents = 800;
+        reversed.transaction_status = TransactionStatus::Reversed;
+
+        assert!(totals_by_month(&[reversed]).is_empty());
+    }
+}
@@ -0,0 +1,251 @@
+// spending categorisation. a customer's override for the payee account wins, then the first
+// keyword rule matching the transaction's text, then a fallback on the transaction type
+
+pub mod analytics;
+
+use crate::models::{
+    category::{CategoryOverride, SpendingCategory},
+    transaction::{Transaction, TransactionType},
+};
+
+#[derive(Clone)]
+pub struct KeywordRule {
+    pub category: SpendingCategory,
+    // lowercase, matched anywhere in the payee name, description or references
+    pub keywords: Vec<&'static str>,
+}
+
+impl KeywordRule {
+    fn new(category: SpendingCategory, keywords: &[&'static str]) -> KeywordRule {
+        KeywordRule {
+            category,
+            keywords: keywords.to_vec(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Categoriser {
+    rules: Vec<KeywordRule>,
+}
+
+impl Categoriser {
+    pub fn new(rules: Vec<KeywordRule>) -> Categoriser {
+        Categoriser { rules }
+    }
+
+    pub fn categorise(
+        &self,
+        transaction: &Transaction,
+        overrides: &[CategoryOverride],
+    ) -> SpendingCategory {
+        let (payee_number, payee_bsb) = payee_account(transaction);
+
+        if let Some(o) = overrides
+            .iter()
+            .find(|o| o.account_number == payee_number && o.bsb == payee_bsb)
+        {
+            return o.category;
+        }
+
+        let text = [
+            payee_name(transaction),
+            transaction.description.as_deref(),
+            transaction.payer_reference.as_deref(),
+            transaction.payee_reference.as_deref(),
+        ]
+        .iter()
+        .flatten()
+        .map(|t| t.to_lowercase())
+        .collect::<Vec<String>>()
+        .join(" ");
+
+        if let Some(rule) = self
+            .rules
+            .iter()
+            .find(|rule| rule.keywords.iter().any(|k| text.contains(k)))
+        {
+            return rule.category;
+        }
+
+        match transaction.transaction_type {
+            TransactionType::Internal => SpendingCategory::Transfers,
+            TransactionType::External | TransactionType::Fee => SpendingCategory::Other,
+        }
+    }
+}
+
+impl Default for Categoriser {
+    // earlier rules win, so the more specific ones go first
+    fn default() -> Self {
+        Categoriser::new(vec![
+            KeywordRule::new(
+                SpendingCategory::Dining,
+                &[
+                    "uber eats",
+                    "menulog",
+                    "doordash",
+                    "restaurant",
+                    "cafe",
+                    "coffee",
+                    "pizza",
+                    "mcdonald",
+                ],
+            ),
+            KeywordRule::new(
+                SpendingCategory::Groceries,
+                &[
+                    "woolworths",
+                    "coles",
+                    "aldi",
+                    "iga ",
+                    "grocer",
+                    "supermarket",
+                ],
+            ),
+            KeywordRule::new(
+                SpendingCategory::Transport,
+                &[
+                    "uber", "taxi", "opal", "myki", "fuel", "petrol", "parking", "toll",
+                ],
+            ),
+            KeywordRule::new(
+                SpendingCategory::Utilities,
+                &[
+                    "electricity",
+                    "energy",
+                    "water",
+                    "gas bill",
+                    "internet",
+                    "telstra",
+                    "optus",
+                    "phone",
+                ],
+            ),
+            KeywordRule::new(
+                SpendingCategory::Housing,
+                &["rent", "landlord", "strata", "mortgage", "real estate"],
+            ),
+            KeywordRule::new(
+                SpendingCategory::Entertainment,
+                &[
+                    "netflix", "spotify", "stan", "cinema", "steam", "concert", "tickets",
+                ],
+            ),
+            KeywordRule::new(
+                SpendingCategory::Shopping,
+                &["amazon", "ebay", "kmart", "target", "jb hi-fi", "bunnings"],
+            ),
+            KeywordRule::new(
+                SpendingCategory::Health,
+                &["pharmacy", "chemist", "medical", "dental", "physio", "gym"],
+            ),
+        ])
+    }
+}
+
+// (number, bsb) of the other side of the transaction from the customer's point of view
+pub fn payee_account(transaction: &Transaction) -> (&str, &str) {
+    if transaction.from_us {
+        (&transaction.to_number, &transaction.to_bsb)
+    } else {
+        (&transaction.from_number, &transaction.from_bsb)
+    }
+}
+
+pub fn payee_name(transaction: &Transaction) -> Option<&str> {
+    if transaction.from_us {
+        transaction.to_name.as_deref()
+    } else {
+        transaction.from_name.as_deref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::Categoriser;
+    use crate::models::{
+        category::{CategoryOverride, SpendingCategory},
+        transaction::{Transaction, TransactionStatus, TransactionType},
+    };
+
+    fn sent(to_name: Option<&str>, description: Option<&str>) -> Transaction {
+        let date = NaiveDate::from_ymd_opt(2023, 8, 25)
+            .unwrap()
+            .and_hms_opt(1, 33, 44)
+            .unwrap();
+        Transaction {
+            id: 60,
+            customer_id: 5,
+            transaction_type: TransactionType::External,
+            from_us: true,
+            amount_cents: 4500,
+            from_number: "938573843".to_string(),
+            from_bsb: "123456".to_string(),
+            from_name: Some("Everyday".to_string()),
+            to_number: "111111111".to_string(),
+            to_bsb: "654321".to_string(),
+            to_name: to_name.map(|n| n.to_string()),
+            available_balance_cents: 0,
+            date_start: date,
+            date_end: Some(date),
+            transaction_status: TransactionStatus::Success,
+            reversal_of: None,
+            reversed_amount_cents: 0,
+            reversal_reason: None,
+            description: description.map(|d| d.to_string()),
+            payer_reference: None,
+            payee_reference: None,
+            counterpart_of: None,
+            running_balance_cents: None,
+        }
+    }
+
+    #[test]
+    fn keyword_rules_in_order() {
+        let categoriser = Categoriser::default();
+
+        assert_eq!(
+            SpendingCategory::Dining,
+            categoriser.categorise(&sent(Some("Uber Eats"), None), &[])
+        );
+        assert_eq!(
+            SpendingCategory::Transport,
+            categoriser.categorise(&sent(Some("UBER TRIP"), None), &[])
+        );
+        assert_eq!(
+            SpendingCategory::Housing,
+            categoriser.categorise(&sent(Some("J Smith"), Some("August rent")), &[])
+        );
+        assert_eq!(
+            SpendingCategory::Other,
+            categoriser.categorise(&sent(Some("J Smith"), None), &[])
+        );
+    }
+
+    #[test]
+    fn override_beats_rules() {
+        let overrides = vec![CategoryOverride {
+            id: 1,
+            customer_id: 5,
+            account_number: "111111111".to_string(),
+            bsb: "654321".to_string(),
+            category: SpendingCategory::Health,
+            created_at: NaiveDate::from_ymd_opt(2023, 8, 1)
+                .unwrap()
+                .and_hms_opt(0, 0, 0)
+                .unwrap(),
+            updated_at: NaiveDate::from_ymd_opt(2023, 8, 1)
+                .unwrap()
+                .and_hms_opt(0, 0, 0)
+                .unwrap(),
+        }];
+
+        assert_eq!(
+            SpendingCategory::Health,
+            Categoriser::default().categorise(&sent(Some("Coles"), None), &overrides)
+        );
+    }
+}
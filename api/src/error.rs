@@ -6,6 +6,10 @@ use core::fmt;
 pub enum RepoError {
     NotFound,
     ConnectionError,
+    // request is valid but clashes with the current state of the entity
+    Conflict,
+    // a transfer would go over one of the customer's limits
+    LimitExceeded,
     Other,
 }
 impl fmt::Display for RepoError {
@@ -13,3 +17,25 @@ impl fmt::Display for RepoError {
         write!(f, "{:?}", *self)
     }
 }
+
+impl From<diesel::result::Error> for RepoError {
+    fn from(err: diesel::result::Error) -> Self {
+        match err {
+            diesel::result::Error::NotFound => RepoError::NotFound,
+            _ => RepoError::Other,
+        }
+    }
+}
+
+// LogNotifier can't fail, channels that can (webhooks, outbox sinks) construct these
+#[derive(Debug)]
+pub enum NotifyError {
+    // channel couldn't be reached, worth trying again later
+    Unavailable,
+    Rejected,
+}
+impl fmt::Display for NotifyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", *self)
+    }
+}
@@ -0,0 +1,118 @@
+// test fixtures shared between modules, each test sets whatever else it cares about on top
+
+use chrono::{NaiveDate, NaiveDateTime};
+
+use crate::{
+    api::audit::util::appended,
+    models::{
+        account::{Account, AccountStatus, AccountType, SigningRule},
+        account_holder::{AccountHolder, FindAccountHolderQuery, HolderRole},
+        audit::{AuditEntry, NewAuditEntry},
+    },
+    traits::{MockRepoCreate, MockRepoFind},
+};
+
+// a fixed point in time for anything that isn't compared against now
+pub fn dt() -> NaiveDateTime {
+    NaiveDate::from_ymd_opt(2023, 9, 14)
+        .unwrap()
+        .and_hms_opt(9, 10, 11)
+        .unwrap()
+}
+
+// active, no overdraft, either holder signs, numbered after its id. balance and available balance
+// are the same
+pub fn account(
+    id: i32,
+    customer_id: i32,
+    account_type: AccountType,
+    balance_cents: i64,
+) -> Account {
+    Account {
+        id,
+        customer_id,
+        balance_cents,
+        account_type,
+        available_balance_cents: balance_cents,
+        account_name: None,
+        date_opened: dt(),
+        account_status: AccountStatus::Active,
+        account_number: format!("{:09}", id),
+        bsb: "123456".to_string(),
+        overdraft_limit_cents: 0,
+        signing_rule: SigningRule::EitherToSign,
+    }
+}
+
+// (account id, customer id, role) for each holder, nobody else holds anything
+pub fn holders_repo<const N: usize>(
+    holders: [(i32, i32, HolderRole); N],
+) -> MockRepoFind<AccountHolder, FindAccountHolderQuery> {
+    let mut mock_holders_repo = MockRepoFind::<AccountHolder, FindAccountHolderQuery>::new();
+    mock_holders_repo.expect_find().returning(move |q| {
+        Ok(holders
+            .iter()
+            .filter(|(account_id, customer_id, _)| {
+                q.account_id == Some(*account_id) && q.customer_id == Some(*customer_id)
+            })
+            .map(|(account_id, customer_id, holder_role)| AccountHolder {
+                id: 1,
+                account_id: *account_id,
+                customer_id: *customer_id,
+                holder_role: *holder_role,
+                created_at: dt(),
+            })
+            .collect())
+    });
+    mock_holders_repo
+}
+
+// customer_id holds whichever account they're asked about, nobody else holds anything
+pub fn holders_repo_for(
+    customer_id: i32,
+    holder_role: HolderRole,
+) -> MockRepoFind<AccountHolder, FindAccountHolderQuery> {
+    let mut mock_holders_repo = MockRepoFind::<AccountHolder, FindAccountHolderQuery>::new();
+    mock_holders_repo.expect_find().returning(move |q| {
+        Ok(match (q.account_id, q.customer_id) {
+            (Some(account_id), Some(cid)) if cid == customer_id => vec![AccountHolder {
+                id: 1,
+                account_id,
+                customer_id,
+                holder_role,
+                created_at: dt(),
+            }],
+            _ => vec![],
+        })
+    });
+    mock_holders_repo
+}
+
+// exactly this many entries written, whatever they're for
+pub fn audit_repo(times: usize) -> MockRepoCreate<AuditEntry, NewAuditEntry> {
+    let mut mock_audit_repo = MockRepoCreate::<AuditEntry, NewAuditEntry>::new();
+    mock_audit_repo
+        .expect_create()
+        .times(times)
+        .returning(|entry| Ok(appended(entry)));
+    mock_audit_repo
+}
+
+// one entry written for the action
+pub fn audited(action: &'static str) -> MockRepoCreate<AuditEntry, NewAuditEntry> {
+    audited_where(action, |_| true)
+}
+
+// one entry written for the action, that also passes the test's own check
+pub fn audited_where<F>(action: &'static str, check: F) -> MockRepoCreate<AuditEntry, NewAuditEntry>
+where
+    F: Fn(&NewAuditEntry) -> bool + Send + 'static,
+{
+    let mut mock_audit_repo = MockRepoCreate::<AuditEntry, NewAuditEntry>::new();
+    mock_audit_repo
+        .expect_create()
+        .withf(move |entry| entry.action == action && check(entry))
+        .times(1)
+        .returning(|entry| Ok(appended(entry)));
+    mock_audit_repo
+}
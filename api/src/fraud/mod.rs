@@ -0,0 +1,101 @@
+// pre-transfer fraud screening. rules are independent, the engine runs all of them and the
+// harshest verdict wins
+
+pub mod rules;
+
+use chrono::NaiveDateTime;
+
+use crate::models::{
+    account::Account,
+    payee::Payee,
+    transaction::{NewTransaction, Transaction, TransactionStatus},
+};
+
+use rules::{NewPayeeRule, UnusualAmountRule, VelocityRule};
+
+pub struct ScreeningContext<'a> {
+    pub transaction: &'a NewTransaction,
+    pub account: &'a Account,
+    // recent transactions on the account, any order
+    pub history: &'a [Transaction],
+    // saved payee the transfer was made to, if it went by payee
+    pub payee: Option<&'a Payee>,
+    pub now: NaiveDateTime,
+}
+
+impl ScreeningContext<'_> {
+    // money that actually left the account being screened
+    pub fn outgoing_history(&self) -> impl Iterator<Item = &Transaction> {
+        let account_number = &self.account.account_number;
+        self.history.iter().filter(move |tr| {
+            tr.from_us
+                && tr.reversal_of.is_none()
+                && &tr.from_number == account_number
+                && matches!(
+                    tr.transaction_status,
+                    TransactionStatus::Pending
+                        | TransactionStatus::Success
+                        | TransactionStatus::PartiallyReversed
+                )
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum RuleVerdict {
+    Allow,
+    Hold(String),
+    Reject(String),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ScreeningOutcome {
+    Allow,
+    Hold(Vec<String>),
+    Reject(Vec<String>),
+}
+
+pub trait FraudRule: Send + Sync {
+    fn evaluate(&self, ctx: &ScreeningContext) -> RuleVerdict;
+}
+
+pub struct FraudEngine {
+    rules: Vec<Box<dyn FraudRule>>,
+}
+
+impl FraudEngine {
+    pub fn new(rules: Vec<Box<dyn FraudRule>>) -> FraudEngine {
+        FraudEngine { rules }
+    }
+
+    pub fn screen(&self, ctx: &ScreeningContext) -> ScreeningOutcome {
+        let mut holds: Vec<String> = vec![];
+        let mut rejects: Vec<String> = vec![];
+
+        for rule in self.rules.iter() {
+            match rule.evaluate(ctx) {
+                RuleVerdict::Allow => {}
+                RuleVerdict::Hold(reason) => holds.push(reason),
+                RuleVerdict::Reject(reason) => rejects.push(reason),
+            }
+        }
+
+        if !rejects.is_empty() {
+            ScreeningOutcome::Reject(rejects)
+        } else if !holds.is_empty() {
+            ScreeningOutcome::Hold(holds)
+        } else {
+            ScreeningOutcome::Allow
+        }
+    }
+}
+
+impl Default for FraudEngine {
+    fn default() -> Self {
+        FraudEngine::new(vec![
+            Box::<VelocityRule>::default(),
+            Box::<NewPayeeRule>::default(),
+            Box::<UnusualAmountRule>::default(),
+        ])
+    }
+}
@@ -0,0 +1,422 @@
+use chrono::Duration;
+
+use super::{FraudRule, RuleVerdict, ScreeningContext};
+
+// too many / too much going out in a short window
+pub struct VelocityRule {
+    pub window: Duration,
+    pub max_count: usize,
+    pub max_total_cents: i64,
+}
+
+impl Default for VelocityRule {
+    fn default() -> Self {
+        VelocityRule {
+            window: Duration::hours(1),
+            max_count: 5,
+            max_total_cents: 500_000,
+        }
+    }
+}
+
+impl FraudRule for VelocityRule {
+    fn evaluate(&self, ctx: &ScreeningContext) -> RuleVerdict {
+        let since = ctx.now - self.window;
+
+        let (count, total_cents) = ctx
+            .outgoing_history()
+            .filter(|tr| tr.date_start >= since)
+            .fold((0, 0), |(count, total), tr| {
+                (count + 1, total + tr.amount_cents)
+            });
+
+        if count + 1 > self.max_count {
+            return RuleVerdict::Hold(format!(
+                "more than {} transfers in {} minutes",
+                self.max_count,
+                self.window.num_minutes()
+            ));
+        }
+
+        if total_cents + ctx.transaction.amount_cents > self.max_total_cents {
+            return RuleVerdict::Hold(format!(
+                "more than {} cents sent in {} minutes",
+                self.max_total_cents,
+                self.window.num_minutes()
+            ));
+        }
+
+        RuleVerdict::Allow
+    }
+}
+
+// large amounts to someone the account has never paid before
+pub struct NewPayeeRule {
+    pub hold_over_cents: i64,
+    pub reject_over_cents: i64,
+    // hold if a new payee would get at least this much of the available balance
+    pub max_balance_pct: i64,
+}
+
+impl Default for NewPayeeRule {
+    fn default() -> Self {
+        NewPayeeRule {
+            hold_over_cents: 100_000,
+            reject_over_cents: 1_000_000,
+            max_balance_pct: 90,
+        }
+    }
+}
+
+impl FraudRule for NewPayeeRule {
+    fn evaluate(&self, ctx: &ScreeningContext) -> RuleVerdict {
+        let tr = ctx.transaction;
+
+        // a saved payee that's been paid before counts even if it's outside the history window
+        let seen_before = ctx.payee.is_some_and(|payee| !payee.is_new())
+            || ctx
+                .outgoing_history()
+                .any(|prev| prev.to_number == tr.to_number && prev.to_bsb == tr.to_bsb);
+
+        if seen_before {
+            return RuleVerdict::Allow;
+        }
+
+        if tr.amount_cents > self.reject_over_cents {
+            return RuleVerdict::Reject(format!(
+                "new payee amount over {} cents",
+                self.reject_over_cents
+            ));
+        }
+
+        if tr.amount_cents > self.hold_over_cents {
+            return RuleVerdict::Hold(format!(
+                "new payee amount over {} cents",
+                self.hold_over_cents
+            ));
+        }
+
+        let available = ctx.account.available_balance_cents;
+        if available > 0 && tr.amount_cents * 100 >= available * self.max_balance_pct {
+            return RuleVerdict::Hold(format!(
+                "new payee sent {}% or more of available balance",
+                self.max_balance_pct
+            ));
+        }
+
+        RuleVerdict::Allow
+    }
+}
+
+// amount well outside what the account normally sends
+pub struct UnusualAmountRule {
+    // not enough history to say what normal is below this
+    pub min_history: usize,
+    pub std_devs: f64,
+    // ignore small amounts even if they're unusual for the account
+    pub floor_cents: i64,
+}
+
+impl Default for UnusualAmountRule {
+    fn default() -> Self {
+        UnusualAmountRule {
+            min_history: 5,
+            std_devs: 3.0,
+            floor_cents: 20_000,
+        }
+    }
+}
+
+impl FraudRule for UnusualAmountRule {
+    fn evaluate(&self, ctx: &ScreeningContext) -> RuleVerdict {
+        let amount = ctx.transaction.amount_cents;
+        if amount <= self.floor_cents {
+            return RuleVerdict::Allow;
+        }
+
+        let amounts: Vec<f64> = ctx
+            .outgoing_history()
+            .map(|tr| tr.amount_cents as f64)
+            .collect();
+
+        if amounts.len() < self.min_history {
+            return RuleVerdict::Allow;
+        }
+
+        let n = amounts.len() as f64;
+        let mean = amounts.iter().sum::<f64>() / n;
+        let std_dev = (amounts.iter().map(|a| (a - mean).powi(2)).sum::<f64>() / n).sqrt();
+
+        // an account that always sends the same amount has no spread, so require at least double
+        let threshold = (mean + self.std_devs * std_dev).max(mean * 2.0);
+
+        if amount as f64 > threshold {
+            return RuleVerdict::Hold(format!(
+                "amount unusual for account, typical is around {} cents",
+                mean.round() as i64
+            ));
+        }
+
+        RuleVerdict::Allow
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fixtures::account;
+    use chrono::{Duration, NaiveDate, NaiveDateTime};
+
+    use super::{NewPayeeRule, UnusualAmountRule, VelocityRule};
+    use crate::{
+        fraud::{FraudEngine, FraudRule, RuleVerdict, ScreeningContext, ScreeningOutcome},
+        models::{
+            account::AccountType,
+            payee::Payee,
+            transaction::{NewTransaction, Transaction, TransactionStatus, TransactionType},
+        },
+    };
+
+    fn now() -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2023, 8, 10)
+            .unwrap()
+            .and_hms_opt(3, 0, 0)
+            .unwrap()
+    }
+
+    fn new_transaction(amount_cents: i64, to_number: &str) -> NewTransaction {
+        NewTransaction {
+            customer_id: 5,
+            transaction_type: TransactionType::Internal,
+            from_us: true,
+            amount_cents,
+            from_number: "000000052".to_string(),
+            from_bsb: "123456".to_string(),
+            from_name: None,
+            to_number: to_number.to_string(),
+            to_bsb: "123456".to_string(),
+            to_name: None,
+            available_balance_cents: 0,
+            transaction_status: TransactionStatus::Pending,
+            reversal_of: None,
+            reversal_reason: None,
+            description: None,
+            payer_reference: None,
+            payee_reference: None,
+        }
+    }
+
+    fn sent(id: i32, amount_cents: i64, to_number: &str, ago: Duration) -> Transaction {
+        Transaction {
+            id,
+            customer_id: 5,
+            transaction_type: TransactionType::Internal,
+            from_us: true,
+            amount_cents,
+            from_number: "000000052".to_string(),
+            from_bsb: "123456".to_string(),
+            from_name: None,
+            to_number: to_number.to_string(),
+            to_bsb: "123456".to_string(),
+            to_name: None,
+            available_balance_cents: 0,
+            date_start: now() - ago,
+            date_end: None,
+            transaction_status: TransactionStatus::Success,
+            reversal_of: None,
+            reversed_amount_cents: 0,
+            reversal_reason: None,
+            description: None,
+            payer_reference: None,
+            payee_reference: None,
+            counterpart_of: None,
+            running_balance_cents: None,
+        }
+    }
+
+    #[test]
+    fn velocity_holds_burst_of_transfers() {
+        let history: Vec<Transaction> = (1..=5)
+            .map(|i| sent(i, 1000, "111111111", Duration::minutes(i as i64 * 5)))
+            .collect();
+        let acc = account(52, 5, AccountType::Transaction, 1_000_000);
+        let tr = new_transaction(1000, "111111111");
+
+        let ctx = ScreeningContext {
+            transaction: &tr,
+            account: &acc,
+            history: &history,
+            payee: None,
+            now: now(),
+        };
+
+        assert!(matches!(
+            VelocityRule::default().evaluate(&ctx),
+            RuleVerdict::Hold(_)
+        ));
+    }
+
+    #[test]
+    fn velocity_ignores_old_transfers() {
+        let history: Vec<Transaction> = (1..=5)
+            .map(|i| sent(i, 1000, "111111111", Duration::days(i as i64)))
+            .collect();
+        let acc = account(52, 5, AccountType::Transaction, 1_000_000);
+        let tr = new_transaction(1000, "111111111");
+
+        let ctx = ScreeningContext {
+            transaction: &tr,
+            account: &acc,
+            history: &history,
+            payee: None,
+            now: now(),
+        };
+
+        assert_eq!(RuleVerdict::Allow, VelocityRule::default().evaluate(&ctx));
+    }
+
+    #[test]
+    fn new_payee_whole_balance_held() {
+        let history = vec![sent(1, 5000, "111111111", Duration::days(3))];
+        let acc = account(52, 5, AccountType::Transaction, 50_000);
+        // never paid 222222222 before, draining the account
+        let tr = new_transaction(50_000, "222222222");
+
+        let ctx = ScreeningContext {
+            transaction: &tr,
+            account: &acc,
+            history: &history,
+            payee: None,
+            now: now(),
+        };
+
+        assert!(matches!(
+            NewPayeeRule::default().evaluate(&ctx),
+            RuleVerdict::Hold(_)
+        ));
+
+        // same amount to a known payee is fine
+        let tr = new_transaction(50_000, "111111111");
+        let ctx = ScreeningContext {
+            transaction: &tr,
+            ..ctx
+        };
+        assert_eq!(RuleVerdict::Allow, NewPayeeRule::default().evaluate(&ctx));
+    }
+
+    #[test]
+    fn new_payee_huge_amount_rejected() {
+        let acc = account(52, 5, AccountType::Transaction, 100_000_000);
+        let tr = new_transaction(5_000_000, "222222222");
+
+        let ctx = ScreeningContext {
+            transaction: &tr,
+            account: &acc,
+            history: &[],
+            payee: None,
+            now: now(),
+        };
+
+        assert!(matches!(
+            NewPayeeRule::default().evaluate(&ctx),
+            RuleVerdict::Reject(_)
+        ));
+    }
+
+    #[test]
+    fn new_payee_saved_and_used_before_allowed() {
+        let acc = account(52, 5, AccountType::Transaction, 50_000);
+        let tr = new_transaction(50_000, "222222222");
+        let mut saved = Payee {
+            id: 8,
+            customer_id: 5,
+            nickname: "Landlord".to_string(),
+            account_number: "222222222".to_string(),
+            bsb: "123456".to_string(),
+            default_reference: None,
+            created_at: now() - Duration::days(200),
+            first_used_at: None,
+            last_used_at: None,
+        };
+
+        // saved but never paid is still new
+        let ctx = ScreeningContext {
+            transaction: &tr,
+            account: &acc,
+            history: &[],
+            payee: Some(&saved),
+            now: now(),
+        };
+        assert!(matches!(
+            NewPayeeRule::default().evaluate(&ctx),
+            RuleVerdict::Hold(_)
+        ));
+
+        saved.first_used_at = Some(now() - Duration::days(180));
+        let ctx = ScreeningContext {
+            transaction: &tr,
+            account: &acc,
+            history: &[],
+            payee: Some(&saved),
+            now: now(),
+        };
+        assert_eq!(RuleVerdict::Allow, NewPayeeRule::default().evaluate(&ctx));
+    }
+
+    #[test]
+    fn unusual_amount_held() {
+        let history: Vec<Transaction> = (1..=6)
+            .map(|i| {
+                sent(
+                    i,
+                    5000 + i as i64 * 100,
+                    "111111111",
+                    Duration::days(i as i64),
+                )
+            })
+            .collect();
+        let acc = account(52, 5, AccountType::Transaction, 100_000_000);
+
+        let tr = new_transaction(90_000, "111111111");
+        let ctx = ScreeningContext {
+            transaction: &tr,
+            account: &acc,
+            history: &history,
+            payee: None,
+            now: now(),
+        };
+        assert!(matches!(
+            UnusualAmountRule::default().evaluate(&ctx),
+            RuleVerdict::Hold(_)
+        ));
+
+        let tr = new_transaction(6000, "111111111");
+        let ctx = ScreeningContext {
+            transaction: &tr,
+            ..ctx
+        };
+        assert_eq!(
+            RuleVerdict::Allow,
+            UnusualAmountRule::default().evaluate(&ctx)
+        );
+    }
+
+    #[test]
+    fn engine_reject_beats_hold() {
+        let acc = account(52, 5, AccountType::Transaction, 100_000_000);
+        let tr = new_transaction(5_000_000, "222222222");
+
+        let ctx = ScreeningContext {
+            transaction: &tr,
+            account: &acc,
+            history: &[],
+            payee: None,
+            now: now(),
+        };
+
+        // over velocity total and new payee reject threshold
+        assert!(matches!(
+            FraudEngine::default().screen(&ctx),
+            ScreeningOutcome::Reject(_)
+        ));
+    }
+}
@@ -1,17 +1,63 @@
+use std::sync::Arc;
+
 use actix_cors::Cors;
 use actix_web::{dev::Server, get, web::Data, App, HttpResponse, HttpServer, Responder};
 use api::accounts::configure_accounts_api;
+use api::approvals::configure_approvals_api;
+use api::audit::configure_audit_api;
+use api::automation::configure_automation_api;
+use api::budgets::configure_budgets_api;
+use api::cdr::configure_cdr_api;
+use api::consents::configure_consents_api;
+use api::disputes::configure_disputes_api;
+use api::fees::configure_fees_api;
+use api::fraud_reviews::configure_fraud_reviews_api;
+use api::loans::configure_loans_api;
+use api::oauth::configure_oauth_api;
+use api::overdrafts::configure_overdrafts_api;
+use api::payees::configure_payees_api;
+use api::spending::configure_spending_api;
+use api::step_up::configure_step_up_api;
 use api::transactions::configure_transactions_api;
+use api::transfer_limits::configure_transfer_limits_api;
+use api::webhooks::configure_webhooks_api;
+use categories::Categoriser;
+use fraud::FraudEngine;
+use notifications::LogNotifier;
 use repository::{
-    accounts_repository::AccountsRepoImpl, transactions_repository::TransactionsRepoImpl,
+    account_holders_repository::AccountHoldersRepoImpl, accounts_repository::AccountsRepoImpl,
+    approvals_repository::ApprovalsRepoImpl, audit_repository::AuditRepoImpl,
+    automation_repository::AutomationRepoImpl, balance_history_repository::BalanceHistoryRepoImpl,
+    buckets_repository::BucketsRepoImpl, budgets_repository::BudgetsRepoImpl,
+    category_overrides_repository::CategoryOverridesRepoImpl,
+    disputes_repository::DisputesRepoImpl, fees_repository::FeesRepoImpl,
+    fraud_reviews_repository::FraudReviewsRepoImpl,
+    loan_applications_repository::LoanApplicationsRepoImpl, loans_repository::LoansRepoImpl,
+    oauth_repository::OAuthRepoImpl, outbox_repository::OutboxRepoImpl,
+    overdrafts_repository::OverdraftsRepoImpl, payees_repository::PayeesRepoImpl,
+    step_up_repository::StepUpRepoImpl, transactions_repository::TransactionsRepoImpl,
+    transfer_limit_requests_repository::TransferLimitRequestsRepoImpl,
+    transfer_limits_repository::TransferLimitsRepoImpl, webhooks_repository::WebhooksRepoImpl,
 };
+use step_up::LogCodeSender;
+use webhooks::HttpWebhookSender;
 
 mod api;
+mod categories;
 mod error;
+#[cfg(test)]
+mod fixtures;
+mod fraud;
 mod models;
+mod notifications;
+mod oauth;
+mod outbox;
 mod repository;
+mod step_up;
 mod traits;
+mod transfers;
 mod util;
+mod webhooks;
 
 #[get("/")]
 async fn hello() -> impl Responder {
@@ -23,20 +69,220 @@ pub fn create_server() -> Result<Server, std::io::Error> {
 
     let pool_r = pool.clone();
     let pool_t = pool.clone();
+    let pool_au = pool.clone();
+    let pool_d = pool.clone();
+    let pool_f = pool.clone();
+    let pool_l = pool.clone();
+    let pool_lq = pool.clone();
+    let pool_p = pool.clone();
+    let pool_b = pool.clone();
+    let pool_c = pool.clone();
+    let pool_bu = pool.clone();
+    let pool_bk = pool.clone();
+    let pool_at = pool.clone();
+    let pool_od = pool.clone();
+    let pool_fe = pool.clone();
+    let pool_lo = pool.clone();
+    let pool_la = pool.clone();
+    let pool_h = pool.clone();
+    let pool_ap = pool.clone();
+    let pool_su = pool.clone();
+    let pool_oa = pool.clone();
+    let pool_wh = pool.clone();
+    let pool_ob = pool.clone();
+
+    // one engine, shared by the transfer handler and the automation rules
+    let fraud_engine = Arc::new(FraudEngine::default());
 
     let accounts_repo = AccountsRepoImpl::new(pool_r);
     let transaction_repo = TransactionsRepoImpl::new(pool_t);
+    let audit_repo = AuditRepoImpl::new(pool_au);
+    let disputes_repo = DisputesRepoImpl::new(pool_d);
+    let fraud_reviews_repo = FraudReviewsRepoImpl::new(pool_f);
+    let limits_repo = TransferLimitsRepoImpl::new(pool_l);
+    let limit_requests_repo = TransferLimitRequestsRepoImpl::new(pool_lq);
+    let payees_repo = PayeesRepoImpl::new(pool_p);
+    let balance_repo = BalanceHistoryRepoImpl::new(pool_b);
+    let overrides_repo = CategoryOverridesRepoImpl::new(pool_c);
+    let budgets_repo = BudgetsRepoImpl::new(pool_bu, Categoriser::default());
+    let buckets_repo = BucketsRepoImpl::new(pool_bk);
+    let automation_repo = AutomationRepoImpl::new(pool_at, fraud_engine.clone());
+    let overdrafts_repo = OverdraftsRepoImpl::new(pool_od);
+    let fees_repo = FeesRepoImpl::new(pool_fe);
+    let loans_repo = LoansRepoImpl::new(pool_lo);
+    let loan_applications_repo = LoanApplicationsRepoImpl::new(pool_la);
+    let holders_repo = AccountHoldersRepoImpl::new(pool_h);
+    let approvals_repo = ApprovalsRepoImpl::new(pool_ap);
+    let step_up_repo = StepUpRepoImpl::new(pool_su);
+    let oauth_repo = OAuthRepoImpl::new(pool_oa);
+    let webhooks_repo = WebhooksRepoImpl::new(pool_wh);
+    let outbox_repo = OutboxRepoImpl::new(pool_ob);
 
     let ar_data = Data::new(accounts_repo);
     let tr_data = Data::new(transaction_repo);
+    let aur_data = Data::new(audit_repo);
+    let dr_data = Data::new(disputes_repo);
+    let fr_data = Data::new(fraud_reviews_repo);
+    let lr_data = Data::new(limits_repo);
+    let lqr_data = Data::new(limit_requests_repo);
+    let pr_data = Data::new(payees_repo);
+    let br_data = Data::new(balance_repo);
+    let cr_data = Data::new(overrides_repo);
+    let bur_data = Data::new(budgets_repo);
+    let bkr_data = Data::new(buckets_repo);
+    let atr_data = Data::new(automation_repo);
+    let odr_data = Data::new(overdrafts_repo);
+    let fer_data = Data::new(fees_repo);
+    let lor_data = Data::new(loans_repo);
+    let lar_data = Data::new(loan_applications_repo);
+    let hr_data = Data::new(holders_repo);
+    let apr_data = Data::new(approvals_repo);
+    let sur_data = Data::new(step_up_repo);
+    let oar_data = Data::new(oauth_repo);
+    let whr_data = Data::new(webhooks_repo);
+    let fraud_engine = Data::from(fraud_engine);
+    let categoriser = Data::new(Categoriser::default());
+    let notifier = Data::new(LogNotifier);
+    let code_sender = Data::new(LogCodeSender);
+
+    webhooks::spawn_delivery_worker(whr_data.clone(), Data::new(HttpWebhookSender::default()));
+    outbox::spawn_relay_worker(
+        Data::new(outbox_repo),
+        whr_data.clone(),
+        Data::new(util::get_outbox_sink()),
+    );
 
     let s = HttpServer::new(move || {
         App::new()
             .wrap(Cors::permissive())
             .app_data(ar_data.clone())
             .app_data(tr_data.clone())
-            .configure(configure_accounts_api::<AccountsRepoImpl>)
-            .configure(configure_transactions_api::<AccountsRepoImpl, TransactionsRepoImpl>)
+            .app_data(aur_data.clone())
+            .app_data(dr_data.clone())
+            .app_data(fr_data.clone())
+            .app_data(lr_data.clone())
+            .app_data(lqr_data.clone())
+            .app_data(pr_data.clone())
+            .app_data(br_data.clone())
+            .app_data(cr_data.clone())
+            .app_data(bur_data.clone())
+            .app_data(bkr_data.clone())
+            .app_data(atr_data.clone())
+            .app_data(odr_data.clone())
+            .app_data(fer_data.clone())
+            .app_data(lor_data.clone())
+            .app_data(lar_data.clone())
+            .app_data(hr_data.clone())
+            .app_data(apr_data.clone())
+            .app_data(sur_data.clone())
+            .app_data(oar_data.clone())
+            .app_data(whr_data.clone())
+            .app_data(fraud_engine.clone())
+            .app_data(categoriser.clone())
+            .app_data(notifier.clone())
+            .app_data(code_sender.clone())
+            .configure(
+                configure_accounts_api::<
+                    AccountsRepoImpl,
+                    AccountHoldersRepoImpl,
+                    BalanceHistoryRepoImpl,
+                    BucketsRepoImpl,
+                    OAuthRepoImpl,
+                    StepUpRepoImpl,
+                    LogCodeSender,
+                    AuditRepoImpl,
+                >,
+            )
+            .configure(
+                configure_transactions_api::<
+                    AccountsRepoImpl,
+                    TransactionsRepoImpl,
+                    TransferLimitsRepoImpl,
+                    PayeesRepoImpl,
+                    BudgetsRepoImpl,
+                    AutomationRepoImpl,
+                    FeesRepoImpl,
+                    LogNotifier,
+                    OAuthRepoImpl,
+                    StepUpRepoImpl,
+                    LogCodeSender,
+                    AuditRepoImpl,
+                >,
+            )
+            .configure(configure_audit_api::<AuditRepoImpl>)
+            .configure(
+                configure_disputes_api::<DisputesRepoImpl, TransactionsRepoImpl, AuditRepoImpl>,
+            )
+            .configure(
+                configure_fraud_reviews_api::<
+                    FraudReviewsRepoImpl,
+                    BudgetsRepoImpl,
+                    AutomationRepoImpl,
+                    LogNotifier,
+                    AuditRepoImpl,
+                >,
+            )
+            .configure(
+                configure_transfer_limits_api::<
+                    TransferLimitsRepoImpl,
+                    TransferLimitRequestsRepoImpl,
+                    AuditRepoImpl,
+                >,
+            )
+            .configure(configure_payees_api::<PayeesRepoImpl, AuditRepoImpl>)
+            .configure(
+                configure_spending_api::<
+                    TransactionsRepoImpl,
+                    CategoryOverridesRepoImpl,
+                    AuditRepoImpl,
+                >,
+            )
+            .configure(configure_budgets_api::<BudgetsRepoImpl, AuditRepoImpl>)
+            .configure(
+                configure_automation_api::<
+                    AutomationRepoImpl,
+                    AccountsRepoImpl,
+                    AccountHoldersRepoImpl,
+                    BudgetsRepoImpl,
+                    LogNotifier,
+                    AuditRepoImpl,
+                >,
+            )
+            .configure(
+                configure_overdrafts_api::<OverdraftsRepoImpl, AccountsRepoImpl, AuditRepoImpl>,
+            )
+            .configure(configure_fees_api::<FeesRepoImpl, AuditRepoImpl>)
+            .configure(
+                configure_loans_api::<
+                    LoansRepoImpl,
+                    LoanApplicationsRepoImpl,
+                    AccountsRepoImpl,
+                    AccountHoldersRepoImpl,
+                    AuditRepoImpl,
+                >,
+            )
+            .configure(
+                configure_approvals_api::<
+                    AccountHoldersRepoImpl,
+                    ApprovalsRepoImpl,
+                    BudgetsRepoImpl,
+                    AutomationRepoImpl,
+                    LogNotifier,
+                    AuditRepoImpl,
+                >,
+            )
+            .configure(configure_step_up_api::<StepUpRepoImpl, LogCodeSender, AuditRepoImpl>)
+            .configure(configure_oauth_api::<OAuthRepoImpl, AccountsRepoImpl, AuditRepoImpl>)
+            .configure(configure_consents_api::<OAuthRepoImpl, AuditRepoImpl>)
+            .configure(
+                configure_cdr_api::<
+                    AccountsRepoImpl,
+                    TransactionsRepoImpl,
+                    PayeesRepoImpl,
+                    OAuthRepoImpl,
+                >,
+            )
+            .configure(configure_webhooks_api::<WebhooksRepoImpl, AuditRepoImpl>)
             .service(hello)
     })
     .bind(util::get_addr())?
@@ -8,6 +8,8 @@ pub enum AccountType {
     Savings,
     Transaction,
     TermDeposit,
+    // balance is what's owed, so it sits below zero
+    Loan,
 }
 
 #[derive(diesel_derive_enum::DbEnum, Copy, Clone, Debug, PartialEq)]
@@ -17,6 +19,14 @@ pub enum AccountStatus {
     Inactive,
 }
 
+// how many holders have to sign a transfer from the account
+#[derive(diesel_derive_enum::DbEnum, Copy, Clone, Debug, PartialEq)]
+#[ExistingTypePath = "crate::models::schema::sql_types::SigningRule"]
+pub enum SigningRule {
+    EitherToSign,
+    BothToSign,
+}
+
 #[derive(Clone, Queryable, Selectable)]
 #[diesel(table_name = accounts)]
 #[diesel(check_for_backend(diesel::pg::Pg))]
@@ -31,6 +41,21 @@ pub struct Account {
     pub account_status: AccountStatus,
     pub account_number: String,
     pub bsb: String,
+    // arranged overdraft, 0 if there isn't one
+    pub overdraft_limit_cents: i64,
+    pub signing_rule: SigningRule,
+}
+
+impl Account {
+    // what a transfer can take, the available balance plus whatever is left of the overdraft
+    pub fn spendable_cents(&self) -> i64 {
+        self.available_balance_cents + self.overdraft_limit_cents
+    }
+
+    // how far into the overdraft the account is
+    pub fn overdrawn_cents(&self) -> i64 {
+        (-self.balance_cents).max(0)
+    }
 }
 
 #[derive(Insertable, Debug, PartialEq, Clone)]
@@ -44,6 +69,7 @@ pub struct NewAccount {
     pub account_number: String,
 }
 
+// accounts customer_id is a holder of, not just the ones they opened
 #[derive(Clone)]
 #[cfg_attr(test, derive(Debug, PartialEq))]
 pub struct FindAccountQuery {
@@ -51,3 +77,8 @@ pub struct FindAccountQuery {
     pub customer_id: i32,
     pub account_number: Option<String>,
 }
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SigningRuleUpdate {
+    pub signing_rule: SigningRule,
+}
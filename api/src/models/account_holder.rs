@@ -0,0 +1,91 @@
+use diesel::{Insertable, Queryable, Selectable};
+
+use super::schema::{account_holders, transfer_signatures};
+use super::step_up::StepUpProof;
+use super::transaction::NewTransaction;
+
+// a both to sign transfer goes once this many holders have signed it
+pub const REQUIRED_SIGNATURES: i64 = 2;
+
+// every holder can see the account and move money from it, only owners change how it's run
+#[derive(diesel_derive_enum::DbEnum, Copy, Clone, Debug, PartialEq)]
+#[ExistingTypePath = "crate::models::schema::sql_types::HolderRole"]
+pub enum HolderRole {
+    Owner,
+    // an authorised signatory, e.g. a bookkeeper or someone with power of attorney
+    Signatory,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum AccountPermission {
+    View,
+    Transact,
+    // closing the account, buckets, who holds it and how transfers are signed
+    Manage,
+}
+
+impl HolderRole {
+    pub fn allows(&self, permission: AccountPermission) -> bool {
+        match self {
+            HolderRole::Owner => true,
+            HolderRole::Signatory => permission != AccountPermission::Manage,
+        }
+    }
+}
+
+#[derive(Clone, Queryable, Selectable)]
+#[cfg_attr(test, derive(Debug, PartialEq))]
+#[diesel(table_name = account_holders)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct AccountHolder {
+    pub id: i32,
+    pub account_id: i32,
+    pub customer_id: i32,
+    pub holder_role: HolderRole,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+#[derive(Insertable, Debug, PartialEq, Clone)]
+#[diesel(table_name = account_holders)]
+pub struct NewAccountHolder {
+    pub account_id: i32,
+    pub customer_id: i32,
+    pub holder_role: HolderRole,
+}
+
+#[derive(Clone)]
+#[cfg_attr(test, derive(Debug, PartialEq))]
+pub struct FindAccountHolderQuery {
+    pub account_id: Option<i32>,
+    pub customer_id: Option<i32>,
+}
+
+#[derive(Insertable, Debug, PartialEq, Clone)]
+#[diesel(table_name = transfer_signatures)]
+pub struct NewTransferSignature {
+    pub transaction_id: i32,
+    pub customer_id: i32,
+}
+
+// transfer a holder made from the account, signed by them. whether it goes straight away or waits
+// on another holder's signature or approval is down to the account
+#[derive(Debug, PartialEq, Clone)]
+pub struct NewSignedTransfer {
+    pub transaction: NewTransaction,
+    pub signed_by: i32,
+    // the challenge it was stepped up with, used up along with creating it
+    pub step_up: Option<StepUpProof>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AccountPermission, HolderRole};
+
+    #[test]
+    fn signatories_cant_manage() {
+        assert!(HolderRole::Owner.allows(AccountPermission::Manage));
+        assert!(HolderRole::Signatory.allows(AccountPermission::View));
+        assert!(HolderRole::Signatory.allows(AccountPermission::Transact));
+        assert!(!HolderRole::Signatory.allows(AccountPermission::Manage));
+    }
+}
@@ -0,0 +1,150 @@
+use diesel::{Insertable, Queryable, Selectable};
+
+use super::audit::ActorType;
+use super::schema::{approval_policies, transfer_approval_events, transfer_approvals};
+
+// how long an approval can be left waiting, a month
+pub const MAX_EXPIRY_HOURS: i32 = 720;
+pub const DEFAULT_EXPIRY_HOURS: i32 = 48;
+
+#[derive(diesel_derive_enum::DbEnum, Copy, Clone, Debug, PartialEq)]
+#[ExistingTypePath = "crate::models::schema::sql_types::ApprovalStatus"]
+pub enum ApprovalStatus {
+    Pending,
+    Approved,
+    Rejected,
+    // nobody decided in time, the transfer is abandoned
+    Expired,
+}
+
+#[derive(Clone, Queryable, Selectable)]
+#[cfg_attr(test, derive(Debug, PartialEq))]
+#[diesel(table_name = approval_policies)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct ApprovalPolicy {
+    pub account_id: i32,
+    pub threshold_cents: i64,
+    pub expiry_hours: i32,
+    pub updated_at: chrono::NaiveDateTime,
+}
+
+impl ApprovalPolicy {
+    // at the threshold is fine, only over it needs someone else
+    pub fn applies_to(&self, amount_cents: i64) -> bool {
+        amount_cents > self.threshold_cents
+    }
+}
+
+#[derive(Insertable, Debug, PartialEq, Clone)]
+#[diesel(table_name = approval_policies)]
+pub struct NewApprovalPolicy {
+    pub account_id: i32,
+    pub threshold_cents: i64,
+    pub expiry_hours: i32,
+}
+
+#[derive(Clone, Queryable, Selectable)]
+#[cfg_attr(test, derive(Debug, PartialEq))]
+#[diesel(table_name = transfer_approvals)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct TransferApproval {
+    pub id: i32,
+    pub transaction_id: i32,
+    pub account_id: i32,
+    // the holder who made the transfer, who can't also approve it
+    pub requested_by: i32,
+    pub threshold_cents: i64,
+    pub approval_status: ApprovalStatus,
+    pub expires_at: chrono::NaiveDateTime,
+    pub decided_by: Option<i32>,
+    pub decided_at: Option<chrono::NaiveDateTime>,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+#[derive(Insertable, Debug, PartialEq, Clone)]
+#[diesel(table_name = transfer_approvals)]
+pub struct NewTransferApproval {
+    pub transaction_id: i32,
+    pub account_id: i32,
+    pub requested_by: i32,
+    pub threshold_cents: i64,
+    pub expires_at: chrono::NaiveDateTime,
+}
+
+#[derive(Clone, Queryable, Selectable)]
+#[cfg_attr(test, derive(Debug, PartialEq))]
+#[diesel(table_name = transfer_approval_events)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct TransferApprovalEvent {
+    pub id: i32,
+    pub approval_id: i32,
+    pub approval_status: ApprovalStatus,
+    pub note: Option<String>,
+    pub actor_type: ActorType,
+    pub actor_id: i32,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+#[derive(Insertable, Debug, PartialEq, Clone)]
+#[diesel(table_name = transfer_approval_events)]
+pub struct NewTransferApprovalEvent {
+    pub approval_id: i32,
+    pub approval_status: ApprovalStatus,
+    pub note: Option<String>,
+    pub actor_type: ActorType,
+    pub actor_id: i32,
+}
+
+// a holder of the account approving or rejecting
+#[derive(Clone, Debug, PartialEq)]
+pub struct ApprovalDecision {
+    pub approve: bool,
+    pub customer_id: i32,
+    pub note: Option<String>,
+}
+
+// abandons every pending approval that's run out of time by `now`
+#[derive(Clone, Debug, PartialEq)]
+pub struct ApprovalExpiryRun {
+    pub staff_id: i32,
+    pub now: chrono::NaiveDateTime,
+}
+
+// approvals on accounts the customer holds, newest first
+#[derive(Clone)]
+#[cfg_attr(test, derive(Debug, PartialEq))]
+pub struct FindTransferApprovalQuery {
+    pub customer_id: i32,
+    pub approval_status: Option<ApprovalStatus>,
+    pub transaction_id: Option<i32>,
+}
+
+#[derive(Clone)]
+#[cfg_attr(test, derive(Debug, PartialEq))]
+pub struct FindTransferApprovalEventQuery {
+    pub approval_id: i32,
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::ApprovalPolicy;
+
+    #[test]
+    fn only_over_the_threshold() {
+        let policy = ApprovalPolicy {
+            account_id: 52,
+            threshold_cents: 1_000_000,
+            expiry_hours: 48,
+            updated_at: NaiveDate::from_ymd_opt(2023, 9, 14)
+                .unwrap()
+                .and_hms_opt(0, 0, 0)
+                .unwrap(),
+        };
+
+        assert!(!policy.applies_to(999_999));
+        assert!(!policy.applies_to(1_000_000));
+        assert!(policy.applies_to(1_000_001));
+    }
+}
@@ -0,0 +1,423 @@
+use diesel::{Insertable, Queryable, Selectable};
+use sha2::{Digest, Sha256};
+
+use super::schema::audit_log;
+
+// prev_hash of the very first entry in the chain
+pub const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+#[derive(diesel_derive_enum::DbEnum, Copy, Clone, Debug, PartialEq)]
+#[ExistingTypePath = "crate::models::schema::sql_types::ActorType"]
+pub enum ActorType {
+    Customer,
+    Staff,
+    System,
+}
+
+impl ActorType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ActorType::Customer => "customer",
+            ActorType::Staff => "staff",
+            ActorType::System => "system",
+        }
+    }
+}
+
+// stored as text rather than a db enum so adding an action doesn't need a migration
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum AuditAction {
+    CreateAccount,
+    DeleteAccount,
+    CreateTransaction,
+    ReverseTransaction,
+    OpenDispute,
+    TransitionDispute,
+    ReviewFraudHold,
+    UpdateTransferLimit,
+    RequestLimitRaise,
+    DecideLimitRaise,
+    CreatePayee,
+    UpdatePayee,
+    DeletePayee,
+    BackfillBalanceHistory,
+    SetCategoryOverride,
+    DeleteCategoryOverride,
+    CreateBudget,
+    UpdateBudget,
+    DeleteBudget,
+    CreateBucket,
+    UpdateBucket,
+    DeleteBucket,
+    MoveBucketFunds,
+    CreateAutomationRule,
+    UpdateAutomationRule,
+    DeleteAutomationRule,
+    RunAutomationRule,
+    SetOverdraftLimit,
+    ChargeOverdraftInterest,
+    ChargeDishonourFee,
+    UpdateFeeSchedule,
+    ChargeFee,
+    OpenLoan,
+    LinkOffsetAccount,
+    UnlinkOffsetAccount,
+    RepayLoan,
+    ChargeLoanInterest,
+    ApplyForLoan,
+    DecideLoanApplication,
+    CollectLoanRepayment,
+    AddAccountHolder,
+    RemoveAccountHolder,
+    UpdateSigningRule,
+    SignTransaction,
+    SetApprovalPolicy,
+    DeleteApprovalPolicy,
+    DecideTransferApproval,
+    ExpireTransferApproval,
+    EnrolTotp,
+    ConfirmTotp,
+    RemoveTotp,
+    VerifyStepUp,
+    RegisterOAuthClient,
+    RevokeOAuthClient,
+    AuthoriseOAuthClient,
+    AmendConsent,
+    RevokeConsent,
+    CreateWebhookSubscription,
+    RevokeWebhookSubscription,
+    ReplayWebhookDelivery,
+}
+
+impl AuditAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuditAction::CreateAccount => "create_account",
+            AuditAction::DeleteAccount => "delete_account",
+            AuditAction::CreateTransaction => "create_transaction",
+            AuditAction::ReverseTransaction => "reverse_transaction",
+            AuditAction::OpenDispute => "open_dispute",
+            AuditAction::TransitionDispute => "transition_dispute",
+            AuditAction::ReviewFraudHold => "review_fraud_hold",
+            AuditAction::UpdateTransferLimit => "update_transfer_limit",
+            AuditAction::RequestLimitRaise => "request_limit_raise",
+            AuditAction::DecideLimitRaise => "decide_limit_raise",
+            AuditAction::CreatePayee => "create_payee",
+            AuditAction::UpdatePayee => "update_payee",
+            AuditAction::DeletePayee => "delete_payee",
+            AuditAction::BackfillBalanceHistory => "backfill_balance_history",
+            AuditAction::SetCategoryOverride => "set_category_override",
+            AuditAction::DeleteCategoryOverride => "delete_category_override",
+            AuditAction::CreateBudget => "create_budget",
+            AuditAction::UpdateBudget => "update_budget",
+            AuditAction::DeleteBudget => "delete_budget",
+            AuditAction::CreateBucket => "create_bucket",
+            AuditAction::UpdateBucket => "update_bucket",
+            AuditAction::DeleteBucket => "delete_bucket",
+            AuditAction::MoveBucketFunds => "move_bucket_funds",
+            AuditAction::CreateAutomationRule => "create_automation_rule",
+            AuditAction::UpdateAutomationRule => "update_automation_rule",
+            AuditAction::DeleteAutomationRule => "delete_automation_rule",
+            AuditAction::RunAutomationRule => "run_automation_rule",
+            AuditAction::SetOverdraftLimit => "set_overdraft_limit",
+            AuditAction::ChargeOverdraftInterest => "charge_overdraft_interest",
+            AuditAction::ChargeDishonourFee => "charge_dishonour_fee",
+            AuditAction::UpdateFeeSchedule => "update_fee_schedule",
+            AuditAction::ChargeFee => "charge_fee",
+            AuditAction::OpenLoan => "open_loan",
+            AuditAction::LinkOffsetAccount => "link_offset_account",
+            AuditAction::UnlinkOffsetAccount => "unlink_offset_account",
+            AuditAction::RepayLoan => "repay_loan",
+            AuditAction::ChargeLoanInterest => "charge_loan_interest",
+            AuditAction::ApplyForLoan => "apply_for_loan",
+            AuditAction::DecideLoanApplication => "decide_loan_application",
+            AuditAction::CollectLoanRepayment => "collect_loan_repayment",
+            AuditAction::AddAccountHolder => "add_account_holder",
+            AuditAction::RemoveAccountHolder => "remove_account_holder",
+            AuditAction::UpdateSigningRule => "update_signing_rule",
+            AuditAction::SignTransaction => "sign_transaction",
+            AuditAction::SetApprovalPolicy => "set_approval_policy",
+            AuditAction::DeleteApprovalPolicy => "delete_approval_policy",
+            AuditAction::DecideTransferApproval => "decide_transfer_approval",
+            AuditAction::ExpireTransferApproval => "expire_transfer_approval",
+            AuditAction::EnrolTotp => "enrol_totp",
+            AuditAction::ConfirmTotp => "confirm_totp",
+            AuditAction::RemoveTotp => "remove_totp",
+            AuditAction::VerifyStepUp => "verify_step_up",
+            AuditAction::RegisterOAuthClient => "register_oauth_client",
+            AuditAction::RevokeOAuthClient => "revoke_oauth_client",
+            AuditAction::AuthoriseOAuthClient => "authorise_oauth_client",
+            AuditAction::AmendConsent => "amend_consent",
+            AuditAction::RevokeConsent => "revoke_consent",
+            AuditAction::CreateWebhookSubscription => "create_webhook_subscription",
+            AuditAction::RevokeWebhookSubscription => "revoke_webhook_subscription",
+            AuditAction::ReplayWebhookDelivery => "replay_webhook_delivery",
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum AuditEntity {
+    Account,
+    Transaction,
+    Dispute,
+    FraudReview,
+    TransferLimit,
+    TransferLimitRequest,
+    Payee,
+    CategoryOverride,
+    Budget,
+    Bucket,
+    AutomationRule,
+    FeeSchedule,
+    Loan,
+    LoanApplication,
+    AccountHolder,
+    ApprovalPolicy,
+    TransferApproval,
+    TotpEnrolment,
+    StepUpChallenge,
+    OAuthClient,
+    OAuthGrant,
+    WebhookSubscription,
+    WebhookDelivery,
+}
+
+impl AuditEntity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuditEntity::Account => "account",
+            AuditEntity::Transaction => "transaction",
+            AuditEntity::Dispute => "dispute",
+            AuditEntity::FraudReview => "fraud_review",
+            AuditEntity::TransferLimit => "transfer_limit",
+            AuditEntity::TransferLimitRequest => "transfer_limit_request",
+            AuditEntity::Payee => "payee",
+            AuditEntity::CategoryOverride => "category_override",
+            AuditEntity::Budget => "budget",
+            AuditEntity::Bucket => "bucket",
+            AuditEntity::AutomationRule => "automation_rule",
+            AuditEntity::FeeSchedule => "fee_schedule",
+            AuditEntity::Loan => "loan",
+            AuditEntity::LoanApplication => "loan_application",
+            AuditEntity::AccountHolder => "account_holder",
+            AuditEntity::ApprovalPolicy => "approval_policy",
+            AuditEntity::TransferApproval => "transfer_approval",
+            AuditEntity::TotpEnrolment => "totp_enrolment",
+            AuditEntity::StepUpChallenge => "step_up_challenge",
+            AuditEntity::OAuthClient => "oauth_client",
+            AuditEntity::OAuthGrant => "oauth_grant",
+            AuditEntity::WebhookSubscription => "webhook_subscription",
+            AuditEntity::WebhookDelivery => "webhook_delivery",
+        }
+    }
+}
+
+#[derive(Clone, Queryable, Selectable)]
+#[cfg_attr(test, derive(Debug, PartialEq))]
+#[diesel(table_name = audit_log)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct AuditEntry {
+    pub id: i32,
+    pub actor_type: ActorType,
+    pub actor_id: i32,
+    pub action: String,
+    pub entity_type: String,
+    pub entity_id: Option<i32>,
+    pub before_snapshot: Option<String>,
+    pub after_snapshot: Option<String>,
+    pub request_method: String,
+    pub request_path: String,
+    pub request_ip: Option<String>,
+    pub user_agent: Option<String>,
+    pub created_at: chrono::NaiveDateTime,
+    pub prev_hash: String,
+    pub hash: String,
+}
+
+#[derive(Insertable, Debug, PartialEq, Clone)]
+#[diesel(table_name = audit_log)]
+pub struct NewAuditEntry {
+    pub actor_type: ActorType,
+    pub actor_id: i32,
+    pub action: String,
+    pub entity_type: String,
+    pub entity_id: Option<i32>,
+    pub before_snapshot: Option<String>,
+    pub after_snapshot: Option<String>,
+    pub request_method: String,
+    pub request_path: String,
+    pub request_ip: Option<String>,
+    pub user_agent: Option<String>,
+    // set here rather than by db since it is part of the hash
+    pub created_at: chrono::NaiveDateTime,
+    // set by repo when appending to the chain
+    pub prev_hash: String,
+    pub hash: String,
+}
+
+impl NewAuditEntry {
+    pub fn compute_hash(&self) -> String {
+        // json array so field boundaries can't be shifted around to forge a collision
+        let canonical = serde_json::json!([
+            self.prev_hash,
+            self.actor_type.as_str(),
+            self.actor_id,
+            self.action,
+            self.entity_type,
+            self.entity_id,
+            self.before_snapshot,
+            self.after_snapshot,
+            self.request_method,
+            self.request_path,
+            self.request_ip,
+            self.user_agent,
+            self.created_at.format("%Y-%m-%d %H:%M:%S%.6f").to_string(),
+        ])
+        .to_string();
+
+        format!("{:x}", Sha256::digest(canonical.as_bytes()))
+    }
+
+    // link entry onto the end of the chain
+    pub fn seal(&mut self, prev_hash: String) {
+        self.prev_hash = prev_hash;
+        self.hash = self.compute_hash();
+    }
+}
+
+impl From<&AuditEntry> for NewAuditEntry {
+    fn from(entry: &AuditEntry) -> Self {
+        NewAuditEntry {
+            actor_type: entry.actor_type,
+            actor_id: entry.actor_id,
+            action: entry.action.clone(),
+            entity_type: entry.entity_type.clone(),
+            entity_id: entry.entity_id,
+            before_snapshot: entry.before_snapshot.clone(),
+            after_snapshot: entry.after_snapshot.clone(),
+            request_method: entry.request_method.clone(),
+            request_path: entry.request_path.clone(),
+            request_ip: entry.request_ip.clone(),
+            user_agent: entry.user_agent.clone(),
+            created_at: entry.created_at,
+            prev_hash: entry.prev_hash.clone(),
+            hash: entry.hash.clone(),
+        }
+    }
+}
+
+// entries must be consecutive and ordered by id. returns id of first entry that doesn't check out
+pub fn verify_audit_chain(entries: &[AuditEntry]) -> Option<i32> {
+    let mut expected_prev: Option<&str> = None;
+
+    for entry in entries.iter() {
+        if let Some(prev) = expected_prev {
+            if entry.prev_hash != prev {
+                return Some(entry.id);
+            }
+        }
+
+        if NewAuditEntry::from(entry).compute_hash() != entry.hash {
+            return Some(entry.id);
+        }
+
+        expected_prev = Some(&entry.hash);
+    }
+
+    None
+}
+
+#[derive(Clone)]
+#[cfg_attr(test, derive(Debug, PartialEq))]
+pub struct FindAuditQuery {
+    pub actor_type: Option<ActorType>,
+    pub actor_id: Option<i32>,
+    pub action: Option<String>,
+    pub entity_type: Option<String>,
+    pub entity_id: Option<i32>,
+    // inclusive, results ordered by id ascending
+    pub from_id: Option<i32>,
+    pub limit: i64,
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::{verify_audit_chain, ActorType, AuditEntry, NewAuditEntry, GENESIS_HASH};
+
+    fn sealed_entry(id: i32, prev_hash: &str) -> AuditEntry {
+        let mut new_entry = NewAuditEntry {
+            actor_type: ActorType::Customer,
+            actor_id: 5,
+            action: "create_account".to_string(),
+            entity_type: "account".to_string(),
+            entity_id: Some(id),
+            before_snapshot: None,
+            after_snapshot: Some("{\"id\":1}".to_string()),
+            request_method: "POST".to_string(),
+            request_path: "/api/customers/5/accounts".to_string(),
+            request_ip: Some("127.0.0.1".to_string()),
+            user_agent: None,
+            created_at: NaiveDate::from_ymd_opt(2023, 8, 2)
+                .unwrap()
+                .and_hms_micro_opt(9, 10, 11, 123456)
+                .unwrap(),
+            prev_hash: "".to_string(),
+            hash: "".to_string(),
+        };
+        new_entry.seal(prev_hash.to_string());
+
+        AuditEntry {
+            id,
+            actor_type: new_entry.actor_type,
+            actor_id: new_entry.actor_id,
+            action: new_entry.action,
+            entity_type: new_entry.entity_type,
+            entity_id: new_entry.entity_id,
+            before_snapshot: new_entry.before_snapshot,
+            after_snapshot: new_entry.after_snapshot,
+            request_method: new_entry.request_method,
+            request_path: new_entry.request_path,
+            request_ip: new_entry.request_ip,
+            user_agent: new_entry.user_agent,
+            created_at: new_entry.created_at,
+            prev_hash: new_entry.prev_hash,
+            hash: new_entry.hash,
+        }
+    }
+
+    fn chain(len: i32) -> Vec<AuditEntry> {
+        let mut entries: Vec<AuditEntry> = vec![];
+        for id in 1..=len {
+            let prev = match entries.last() {
+                Some(e) => e.hash.clone(),
+                None => GENESIS_HASH.to_string(),
+            };
+            entries.push(sealed_entry(id, &prev));
+        }
+        entries
+    }
+
+    #[test]
+    fn valid_chain() {
+        assert_eq!(None, verify_audit_chain(&chain(4)));
+    }
+
+    #[test]
+    fn tampered_snapshot() {
+        let mut entries = chain(4);
+        entries[2].after_snapshot = Some("{\"id\":2}".to_string());
+
+        assert_eq!(Some(3), verify_audit_chain(&entries));
+    }
+
+    #[test]
+    fn removed_entry() {
+        let mut entries = chain(4);
+        entries.remove(1);
+
+        assert_eq!(Some(3), verify_audit_chain(&entries));
+    }
+}
@@ -0,0 +1,266 @@
+use chrono::NaiveDate;
+use diesel::{Insertable, Queryable, Selectable};
+
+use super::account::Account;
+use super::schema::{automation_rules, automation_runs};
+use super::transaction::{NewTransaction, TransactionStatus, TransactionType};
+
+// what appears on both statements for money a rule moved
+pub const ROUND_UP_REFERENCE: &str = "Round up";
+pub const SWEEP_REFERENCE: &str = "Sweep";
+
+#[derive(diesel_derive_enum::DbEnum, Copy, Clone, Debug, PartialEq)]
+#[ExistingTypePath = "crate::models::schema::sql_types::AutomationRuleKind"]
+pub enum AutomationRuleKind {
+    RoundUp,
+    SweepAbove,
+}
+
+#[derive(Clone, Queryable, Selectable)]
+#[cfg_attr(test, derive(Debug, PartialEq))]
+#[diesel(table_name = automation_rules)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct AutomationRule {
+    pub id: i32,
+    pub customer_id: i32,
+    pub kind: AutomationRuleKind,
+    pub from_account_id: i32,
+    pub to_account_id: i32,
+    pub round_to_cents: Option<i64>,
+    pub threshold_cents: Option<i64>,
+    pub enabled: bool,
+    pub created_at: chrono::NaiveDateTime,
+    pub updated_at: chrono::NaiveDateTime,
+}
+
+impl AutomationRule {
+    // the difference between a debit and the next multiple of round_to_cents, nothing if it's
+    // already a whole multiple
+    pub fn round_up_cents(&self, debit_cents: i64) -> i64 {
+        match self.round_to_cents {
+            Some(round_to) if self.kind == AutomationRuleKind::RoundUp && round_to > 0 => {
+                (round_to - debit_cents % round_to) % round_to
+            }
+            _ => 0,
+        }
+    }
+
+    // everything available above the threshold
+    pub fn sweep_cents(&self, account: &Account) -> i64 {
+        match self.threshold_cents {
+            Some(threshold) if self.kind == AutomationRuleKind::SweepAbove => {
+                (account.balance_cents.min(account.available_balance_cents) - threshold).max(0)
+            }
+            _ => 0,
+        }
+    }
+
+    // goes through the same insert and settle as any other internal transfer
+    pub fn transfer(&self, from: &Account, to: &Account, amount_cents: i64) -> NewTransaction {
+        let reference = match self.kind {
+            AutomationRuleKind::RoundUp => ROUND_UP_REFERENCE,
+            AutomationRuleKind::SweepAbove => SWEEP_REFERENCE,
+        };
+
+        // kept against the from account's primary holder like any other transfer, so are its limits
+        NewTransaction {
+            customer_id: from.customer_id,
+            transaction_type: TransactionType::Internal,
+            from_us: true,
+            amount_cents,
+            from_number: from.account_number.clone(),
+            from_bsb: from.bsb.clone(),
+            from_name: from.account_name.clone(),
+            to_number: to.account_number.clone(),
+            to_bsb: to.bsb.clone(),
+            to_name: to.account_name.clone(),
+            available_balance_cents: from.available_balance_cents - amount_cents,
+            transaction_status: TransactionStatus::Pending,
+            reversal_of: None,
+            reversal_reason: None,
+            description: None,
+            payer_reference: Some(reference.to_string()),
+            payee_reference: Some(reference.to_string()),
+        }
+    }
+}
+
+#[derive(Insertable, Debug, PartialEq, Clone)]
+#[diesel(table_name = automation_rules)]
+pub struct NewAutomationRule {
+    pub customer_id: i32,
+    pub kind: AutomationRuleKind,
+    pub from_account_id: i32,
+    pub to_account_id: i32,
+    pub round_to_cents: Option<i64>,
+    pub threshold_cents: Option<i64>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AutomationRuleUpdate {
+    pub enabled: Option<bool>,
+    // only the one that goes with the rule's kind can be changed
+    pub round_to_cents: Option<i64>,
+    pub threshold_cents: Option<i64>,
+}
+
+#[derive(Clone)]
+#[cfg_attr(test, derive(Debug, PartialEq))]
+pub struct FindAutomationRuleQuery {
+    pub customer_id: i32,
+}
+
+#[derive(Clone, Queryable, Selectable)]
+#[cfg_attr(test, derive(Debug, PartialEq))]
+#[diesel(table_name = automation_runs)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct AutomationRun {
+    pub id: i32,
+    pub rule_id: i32,
+    pub customer_id: i32,
+    pub trigger_transaction_id: Option<i32>,
+    pub run_date: Option<NaiveDate>,
+    pub transaction_id: i32,
+    pub amount_cents: i64,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+#[derive(Insertable, Debug, PartialEq, Clone)]
+#[diesel(table_name = automation_runs)]
+pub struct NewAutomationRun {
+    pub rule_id: i32,
+    pub customer_id: i32,
+    pub trigger_transaction_id: Option<i32>,
+    pub run_date: Option<NaiveDate>,
+    pub transaction_id: i32,
+    pub amount_cents: i64,
+}
+
+// newest first
+#[derive(Clone)]
+#[cfg_attr(test, derive(Debug, PartialEq))]
+pub struct FindAutomationRunQuery {
+    pub customer_id: i32,
+    pub rule_id: Option<i32>,
+}
+
+// something rules listen for. creating one runs every rule it fires and returns what they moved
+#[derive(Clone, Debug, PartialEq)]
+pub enum AutomationTrigger {
+    // round ups for a debit that just settled
+    Settled { transaction_id: i32 },
+    // every customer's sweeps, once per day
+    EndOfDay { day: NaiveDate },
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fixtures::account;
+    use chrono::NaiveDate;
+
+    use super::{AutomationRule, AutomationRuleKind, ROUND_UP_REFERENCE};
+    use crate::models::{
+        account::{Account, AccountType},
+        approval::ApprovalPolicy,
+        transaction::{TransactionStatus, TransactionType},
+    };
+
+    fn rule(kind: AutomationRuleKind) -> AutomationRule {
+        let dt = NaiveDate::from_ymd_opt(2023, 9, 1)
+            .unwrap()
+            .and_hms_opt(22, 46, 10)
+            .unwrap();
+        AutomationRule {
+            id: 1,
+            customer_id: 5,
+            kind,
+            from_account_id: 52,
+            to_account_id: 53,
+            round_to_cents: Some(100),
+            threshold_cents: Some(200000),
+            enabled: true,
+            created_at: dt,
+            updated_at: dt,
+        }
+    }
+
+    #[test]
+    fn round_up_to_next_dollar() {
+        let round_up = rule(AutomationRuleKind::RoundUp);
+
+        assert_eq!(55, round_up.round_up_cents(445));
+        assert_eq!(0, round_up.round_up_cents(500));
+        assert_eq!(99, round_up.round_up_cents(1));
+        // the wrong kind never moves anything
+        assert_eq!(0, rule(AutomationRuleKind::SweepAbove).round_up_cents(445));
+    }
+
+    #[test]
+    fn sweep_above_threshold() {
+        let sweep = rule(AutomationRuleKind::SweepAbove);
+
+        assert_eq!(
+            50000,
+            sweep.sweep_cents(&account(52, 5, AccountType::Transaction, 250000))
+        );
+        // held funds aren't swept
+        assert_eq!(
+            10000,
+            sweep.sweep_cents(&Account {
+                available_balance_cents: 210000,
+                ..account(52, 5, AccountType::Transaction, 250000)
+            })
+        );
+        assert_eq!(
+            0,
+            sweep.sweep_cents(&account(52, 5, AccountType::Transaction, 150000))
+        );
+    }
+
+    #[test]
+    fn transfer_between_rule_accounts() {
+        let transfer = rule(AutomationRuleKind::RoundUp).transfer(
+            &account(52, 5, AccountType::Transaction, 1000),
+            &account(53, 5, AccountType::Transaction, 0),
+            55,
+        );
+
+        assert_eq!(TransactionType::Internal, transfer.transaction_type);
+        assert_eq!(TransactionStatus::Pending, transfer.transaction_status);
+        assert_eq!("000000052", transfer.from_number);
+        assert_eq!("000000053", transfer.to_number);
+        assert_eq!(945, transfer.available_balance_cents);
+        assert_eq!(
+            Some(ROUND_UP_REFERENCE.to_string()),
+            transfer.payee_reference
+        );
+    }
+
+    #[test]
+    fn sweep_from_threshold_account_waits_for_approval() {
+        // set up by customer 5, who signs on customer 6's account
+        let from = account(52, 6, AccountType::Transaction, 2_500_000);
+        let policy = ApprovalPolicy {
+            account_id: 52,
+            threshold_cents: 1_000_000,
+            expiry_hours: 48,
+            updated_at: from.date_opened,
+        };
+
+        let sweep = rule(AutomationRuleKind::SweepAbove);
+        let amount_cents = sweep.sweep_cents(&from);
+        let transfer = sweep.transfer(
+            &from,
+            &account(53, 5, AccountType::Transaction, 0),
+            amount_cents,
+        );
+
+        // the account's policy is found through its primary holder, like a transfer they make
+        assert_eq!(from.customer_id, transfer.customer_id);
+        assert_eq!(
+            (from.account_number.as_str(), from.bsb.as_str()),
+            (transfer.from_number.as_str(), transfer.from_bsb.as_str())
+        );
+        assert!(policy.applies_to(transfer.amount_cents));
+    }
+}
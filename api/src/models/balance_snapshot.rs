@@ -0,0 +1,314 @@
+use chrono::{Datelike, Duration, Months, NaiveDate};
+use diesel::{Insertable, Queryable, Selectable};
+
+use super::account::Account;
+use super::schema::account_balance_snapshots;
+use super::transaction::Transaction;
+
+// balance at the end of snapshot_date (UTC), or the latest balance if that's today
+#[derive(Clone, Queryable, Selectable, Insertable)]
+#[cfg_attr(test, derive(Debug, PartialEq))]
+#[diesel(table_name = account_balance_snapshots)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct BalanceSnapshot {
+    pub account_id: i32,
+    pub snapshot_date: NaiveDate,
+    pub balance_cents: i64,
+    pub available_balance_cents: i64,
+    pub updated_at: chrono::NaiveDateTime,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum BalanceGranularity {
+    Daily,
+    // weeks end on sunday
+    Weekly,
+    Monthly,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct BalancePoint {
+    // last day of the period, or the end of the range if the period runs past it
+    pub date: NaiveDate,
+    pub balance_cents: i64,
+    pub available_balance_cents: i64,
+}
+
+#[derive(Clone)]
+#[cfg_attr(test, derive(Debug, PartialEq))]
+pub struct FindBalanceSnapshotQuery {
+    pub account_id: i32,
+    // inclusive, the latest snapshot before from is included too so the series can start there
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct BalanceBackfill {
+    pub account_id: i32,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct BalanceBackfillSummary {
+    pub account_id: i32,
+    pub transactions_updated: usize,
+    pub snapshots_written: usize,
+}
+
+fn period_end(day: NaiveDate, granularity: BalanceGranularity) -> NaiveDate {
+    match granularity {
+        BalanceGranularity::Daily => day,
+        BalanceGranularity::Weekly => {
+            day + Duration::days(6 - day.weekday().num_days_from_monday() as i64)
+        }
+        BalanceGranularity::Monthly => day
+            .with_day(1)
+            .and_then(|first| first.checked_add_months(Months::new(1)))
+            .and_then(|next| next.pred_opt())
+            .unwrap_or(day),
+    }
+}
+
+// end of period balances between from and to, carrying the last known balance forward over days
+// nothing moved. periods before the first snapshot are left out
+pub fn balance_series(
+    snapshots: &[BalanceSnapshot],
+    from: NaiveDate,
+    to: NaiveDate,
+    granularity: BalanceGranularity,
+) -> Vec<BalancePoint> {
+    let mut snapshots: Vec<&BalanceSnapshot> = snapshots.iter().collect();
+    snapshots.sort_by_key(|s| s.snapshot_date);
+
+    let mut points = vec![];
+    let mut next = 0;
+    let mut latest: Option<&BalanceSnapshot> = None;
+    let mut day = from;
+
+    while day <= to {
+        let end = period_end(day, granularity).min(to);
+
+        while next < snapshots.len() && snapshots[next].snapshot_date <= end {
+            latest = Some(snapshots[next]);
+            next += 1;
+        }
+
+        if let Some(snapshot) = latest {
+            points.push(BalancePoint {
+                date: end,
+                balance_cents: snapshot.balance_cents,
+                available_balance_cents: snapshot.available_balance_cents,
+            });
+        }
+
+        match end.succ_opt() {
+            Some(following) => day = following,
+            None => break,
+        }
+    }
+
+    points
+}
+
+#[derive(Clone)]
+#[cfg_attr(test, derive(Debug, PartialEq))]
+pub struct BackfilledBalances {
+    // (transaction id, running balance)
+    pub running: Vec<(i32, i64)>,
+    pub snapshots: Vec<BalanceSnapshot>,
+}
+
+// works back from the account's current balance through its history (newest first, only the
+// account's side of each entry) to what the balance was after each entry and at each day's end
+pub fn backfill_balances(
+    account: &Account,
+    history: &[Transaction],
+    now: chrono::NaiveDateTime,
+) -> BackfilledBalances {
+    let mut balance = account.balance_cents;
+    let mut available = account.available_balance_cents;
+
+    let mut running = vec![];
+    let mut snapshots = vec![BalanceSnapshot {
+        account_id: account.id,
+        snapshot_date: now.date(),
+        balance_cents: balance,
+        available_balance_cents: available,
+        updated_at: now,
+    }];
+
+    for tr in history.iter().filter(|tr| tr.is_settled()) {
+        let day = tr.date_start.date();
+
+        // first entry seen for a day is the last one on it
+        if snapshots.last().is_some_and(|s| s.snapshot_date > day) {
+            snapshots.push(BalanceSnapshot {
+                account_id: account.id,
+                snapshot_date: day,
+                balance_cents: balance,
+                available_balance_cents: available,
+                updated_at: now,
+            });
+        }
+
+        running.push((tr.id, balance));
+
+        balance -= tr.balance_delta_cents();
+        available -= tr.balance_delta_cents();
+    }
+
+    BackfilledBalances { running, snapshots }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{NaiveDate, NaiveDateTime};
+
+    use super::{backfill_balances, balance_series, BalanceGranularity, BalanceSnapshot};
+    use crate::models::{
+        account::{Account, AccountStatus, AccountType, SigningRule},
+        transaction::{Transaction, TransactionStatus, TransactionType},
+    };
+
+    fn date(m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2023, m, d).unwrap()
+    }
+
+    fn at(m: u32, d: u32, h: u32) -> NaiveDateTime {
+        date(m, d).and_hms_opt(h, 0, 0).unwrap()
+    }
+
+    fn snapshot(snapshot_date: NaiveDate, balance_cents: i64) -> BalanceSnapshot {
+        BalanceSnapshot {
+            account_id: 52,
+            snapshot_date,
+            balance_cents,
+            available_balance_cents: balance_cents,
+            updated_at: at(8, 23, 0),
+        }
+    }
+
+    fn entry(id: i32, from_us: bool, amount_cents: i64, date_start: NaiveDateTime) -> Transaction {
+        Transaction {
+            id,
+            customer_id: 5,
+            transaction_type: TransactionType::Internal,
+            from_us,
+            amount_cents,
+            from_number: "938573843".to_string(),
+            from_bsb: "123456".to_string(),
+            from_name: None,
+            to_number: "123456789".to_string(),
+            to_bsb: "123456".to_string(),
+            to_name: None,
+            available_balance_cents: 0,
+            date_start,
+            date_end: Some(date_start),
+            transaction_status: TransactionStatus::Success,
+            reversal_of: None,
+            reversed_amount_cents: 0,
+            reversal_reason: None,
+            description: None,
+            payer_reference: None,
+            payee_reference: None,
+            counterpart_of: None,
+            running_balance_cents: None,
+        }
+    }
+
+    #[test]
+    fn daily_series_carries_forward() {
+        let snapshots = vec![snapshot(date(7, 30), 100), snapshot(date(8, 2), 250)];
+
+        let points = balance_series(
+            &snapshots,
+            date(8, 1),
+            date(8, 3),
+            BalanceGranularity::Daily,
+        );
+
+        let actual: Vec<(NaiveDate, i64)> =
+            points.iter().map(|p| (p.date, p.balance_cents)).collect();
+        assert_eq!(
+            vec![(date(8, 1), 100), (date(8, 2), 250), (date(8, 3), 250)],
+            actual
+        );
+    }
+
+    #[test]
+    fn weekly_and_monthly_periods() {
+        let snapshots = vec![
+            snapshot(date(7, 3), 100),
+            snapshot(date(7, 12), 200),
+            snapshot(date(8, 9), 300),
+        ];
+
+        // 2023-07-02 and 2023-07-09 are sundays, range ends on a wednesday
+        let weekly = balance_series(
+            &snapshots,
+            date(7, 1),
+            date(7, 12),
+            BalanceGranularity::Weekly,
+        );
+        let actual: Vec<(NaiveDate, i64)> =
+            weekly.iter().map(|p| (p.date, p.balance_cents)).collect();
+        assert_eq!(vec![(date(7, 9), 100), (date(7, 12), 200)], actual);
+
+        let monthly = balance_series(
+            &snapshots,
+            date(7, 1),
+            date(8, 15),
+            BalanceGranularity::Monthly,
+        );
+        let actual: Vec<(NaiveDate, i64)> =
+            monthly.iter().map(|p| (p.date, p.balance_cents)).collect();
+        assert_eq!(vec![(date(7, 31), 200), (date(8, 15), 300)], actual);
+    }
+
+    #[test]
+    fn backfill_walks_back_from_current_balance() {
+        let account = Account {
+            id: 52,
+            customer_id: 5,
+            balance_cents: 1000,
+            account_type: AccountType::Transaction,
+            available_balance_cents: 1000,
+            account_name: None,
+            date_opened: at(1, 1, 0),
+            account_status: AccountStatus::Active,
+            account_number: "938573843".to_string(),
+            bsb: "123456".to_string(),
+            overdraft_limit_cents: 0,
+            signing_rule: SigningRule::EitherToSign,
+        };
+
+        let mut held = entry(4, true, 9999, at(8, 21, 12));
+        held.transaction_status = TransactionStatus::Held;
+
+        // newest first
+        let history = vec![
+            held,
+            entry(3, true, 200, at(8, 21, 9)),
+            entry(2, false, 500, at(8, 20, 15)),
+            entry(1, true, 100, at(8, 20, 10)),
+        ];
+
+        let backfilled = backfill_balances(&account, &history, at(8, 23, 1));
+
+        assert_eq!(vec![(3, 1000), (2, 1200), (1, 700)], backfilled.running);
+
+        let days: Vec<(NaiveDate, i64)> = backfilled
+            .snapshots
+            .iter()
+            .map(|s| (s.snapshot_date, s.balance_cents))
+            .collect();
+        assert_eq!(
+            vec![
+                (date(8, 23), 1000),
+                (date(8, 21), 1000),
+                (date(8, 20), 1200)
+            ],
+            days
+        );
+    }
+}
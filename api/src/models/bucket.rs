@@ -0,0 +1,208 @@
+use chrono::NaiveDate;
+use diesel::{Insertable, Queryable, Selectable};
+
+use super::schema::account_buckets;
+
+// holds whatever isn't in a named bucket
+pub const DEFAULT_BUCKET_NAME: &str = "Unallocated";
+
+#[derive(Clone, Queryable, Selectable)]
+#[cfg_attr(test, derive(Debug, PartialEq))]
+#[diesel(table_name = account_buckets)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct AccountBucket {
+    pub id: i32,
+    pub account_id: i32,
+    pub name: String,
+    pub balance_cents: i64,
+    pub goal_cents: Option<i64>,
+    pub target_date: Option<NaiveDate>,
+    pub is_default: bool,
+    pub created_at: chrono::NaiveDateTime,
+    pub updated_at: chrono::NaiveDateTime,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct BucketProgress {
+    // capped at 100
+    pub percent: i64,
+    pub remaining_cents: i64,
+    // negative once the target date has passed
+    pub days_left: Option<i64>,
+    // to reach the goal by the target date, saving evenly each month from today
+    pub monthly_needed_cents: Option<i64>,
+}
+
+impl AccountBucket {
+    // only buckets with a goal have progress
+    pub fn progress(&self, today: NaiveDate) -> Option<BucketProgress> {
+        let goal_cents = self.goal_cents?;
+        let remaining_cents = (goal_cents - self.balance_cents).max(0);
+        let days_left = self.target_date.map(|target| (target - today).num_days());
+
+        Some(BucketProgress {
+            percent: (self.balance_cents.max(0) * 100 / goal_cents).min(100),
+            remaining_cents,
+            days_left,
+            monthly_needed_cents: days_left.map(|days| {
+                // part months count as a whole one, anything due now is needed now
+                let months = (days + 29) / 30;
+                if months <= 0 {
+                    remaining_cents
+                } else {
+                    (remaining_cents + months - 1) / months
+                }
+            }),
+        })
+    }
+}
+
+// new balance for each bucket a change to the account's balance touches. deposits go to the
+// default bucket. withdrawals come out of the default bucket first, then the newest buckets, and
+// only take the default bucket below zero once every bucket is empty
+pub fn spread_balance_change(buckets: &[AccountBucket], delta_cents: i64) -> Vec<(i32, i64)> {
+    let Some(default) = buckets.iter().find(|b| b.is_default) else {
+        return vec![];
+    };
+
+    if delta_cents >= 0 {
+        return vec![(default.id, default.balance_cents + delta_cents)];
+    }
+
+    let mut owed = -delta_cents;
+    let mut changes = vec![];
+
+    let from_default = owed.min(default.balance_cents.max(0));
+    owed -= from_default;
+    let mut default_balance = default.balance_cents - from_default;
+
+    let mut named: Vec<&AccountBucket> = buckets.iter().filter(|b| !b.is_default).collect();
+    named.sort_by_key(|b| std::cmp::Reverse(b.id));
+
+    for bucket in named {
+        if owed == 0 {
+            break;
+        }
+        let taken = owed.min(bucket.balance_cents);
+        if taken > 0 {
+            owed -= taken;
+            changes.push((bucket.id, bucket.balance_cents - taken));
+        }
+    }
+
+    default_balance -= owed;
+    if default_balance != default.balance_cents {
+        changes.insert(0, (default.id, default_balance));
+    }
+
+    changes
+}
+
+#[derive(Insertable, Debug, PartialEq, Clone)]
+#[diesel(table_name = account_buckets)]
+pub struct NewAccountBucket {
+    pub account_id: i32,
+    pub name: String,
+    // moved in from the default bucket
+    pub balance_cents: i64,
+    pub goal_cents: Option<i64>,
+    pub target_date: Option<NaiveDate>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct BucketUpdate {
+    pub name: Option<String>,
+    // Some(None) clears it
+    pub goal_cents: Option<Option<i64>>,
+    pub target_date: Option<Option<NaiveDate>>,
+}
+
+#[derive(Clone)]
+#[cfg_attr(test, derive(Debug, PartialEq))]
+pub struct FindBucketQuery {
+    pub account_id: i32,
+}
+
+// money moving between two buckets of the same account, the account balance doesn't change
+#[derive(Clone, Debug, PartialEq)]
+pub struct BucketMove {
+    pub account_id: i32,
+    pub from_bucket_id: i32,
+    pub to_bucket_id: i32,
+    pub amount_cents: i64,
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::{spread_balance_change, AccountBucket};
+
+    fn bucket(id: i32, balance_cents: i64, is_default: bool) -> AccountBucket {
+        let date = NaiveDate::from_ymd_opt(2023, 8, 1)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap();
+        AccountBucket {
+            id,
+            account_id: 52,
+            name: format!("bucket {}", id),
+            balance_cents,
+            goal_cents: None,
+            target_date: None,
+            is_default,
+            created_at: date,
+            updated_at: date,
+        }
+    }
+
+    #[test]
+    fn deposits_go_to_default() {
+        let buckets = vec![bucket(1, 100, true), bucket(2, 500, false)];
+
+        assert_eq!(vec![(1, 350)], spread_balance_change(&buckets, 250));
+    }
+
+    #[test]
+    fn withdrawals_drain_default_then_newest() {
+        let buckets = vec![
+            bucket(1, 100, true),
+            bucket(2, 500, false),
+            bucket(3, 200, false),
+        ];
+
+        assert_eq!(vec![(1, 50)], spread_balance_change(&buckets, -50));
+        assert_eq!(
+            vec![(1, 0), (3, 0), (2, 400)],
+            spread_balance_change(&buckets, -400)
+        );
+        // everything gone and then some
+        assert_eq!(
+            vec![(1, -100), (3, 0), (2, 0)],
+            spread_balance_change(&buckets, -900)
+        );
+    }
+
+    #[test]
+    fn no_buckets_no_changes() {
+        assert!(spread_balance_change(&[], -50).is_empty());
+    }
+
+    #[test]
+    fn progress_towards_goal() {
+        let mut holiday = bucket(2, 150000, false);
+        holiday.goal_cents = Some(400000);
+        holiday.target_date = NaiveDate::from_ymd_opt(2023, 12, 1);
+
+        let progress = holiday
+            .progress(NaiveDate::from_ymd_opt(2023, 8, 30).unwrap())
+            .unwrap();
+
+        assert_eq!(37, progress.percent);
+        assert_eq!(250000, progress.remaining_cents);
+        assert_eq!(Some(93), progress.days_left);
+        assert_eq!(Some(62500), progress.monthly_needed_cents);
+
+        assert_eq!(None, bucket(3, 100, false).progress(NaiveDate::MIN));
+    }
+}
@@ -0,0 +1,287 @@
+use chrono::{Datelike, Months, NaiveDate};
+use diesel::{Insertable, Queryable, Selectable};
+
+use super::category::SpendingCategory;
+use super::schema::{budget_alerts, budgets};
+use super::transaction::Transaction;
+
+pub const DEFAULT_ALERT_THRESHOLDS: [i32; 2] = [80, 100];
+pub const MAX_ALERT_THRESHOLDS: usize = 5;
+pub const MAX_THRESHOLD_PERCENT: i32 = 200;
+
+#[derive(Clone, Queryable, Selectable)]
+#[cfg_attr(test, derive(Debug, PartialEq))]
+#[diesel(table_name = budgets)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct Budget {
+    pub id: i32,
+    pub customer_id: i32,
+    pub name: String,
+    // neither set means all of the customer's spending
+    pub category: Option<SpendingCategory>,
+    pub account_number: Option<String>,
+    pub amount_cents: i64,
+    pub alert_thresholds: Vec<i32>,
+    pub created_at: chrono::NaiveDateTime,
+    pub updated_at: chrono::NaiveDateTime,
+}
+
+impl Budget {
+    pub fn covers(&self, transaction: &Transaction, category: SpendingCategory) -> bool {
+        self.category.map_or(true, |c| c == category)
+            && self
+                .account_number
+                .as_ref()
+                .map_or(true, |number| *number == transaction.from_number)
+    }
+
+    // spending is everything counted as spending for the period, categorise is the customer's view
+    pub fn spent_cents<F>(&self, spending: &[Transaction], categorise: F) -> i64
+    where
+        F: Fn(&Transaction) -> SpendingCategory,
+    {
+        spending
+            .iter()
+            .filter(|tr| self.covers(tr, categorise(tr)))
+            .map(|tr| tr.amount_cents - tr.reversed_amount_cents)
+            .sum()
+    }
+
+    pub fn crossed_thresholds(&self, spent_cents: i64) -> Vec<i32> {
+        self.alert_thresholds
+            .iter()
+            .filter(|t| spent_cents * 100 >= **t as i64 * self.amount_cents)
+            .copied()
+            .collect()
+    }
+}
+
+// ascending, no repeats, each between 1 and MAX_THRESHOLD_PERCENT
+pub fn is_valid_thresholds(thresholds: &[i32]) -> bool {
+    !thresholds.is_empty()
+        && thresholds.len() <= MAX_ALERT_THRESHOLDS
+        && thresholds
+            .iter()
+            .all(|t| (1..=MAX_THRESHOLD_PERCENT).contains(t))
+        && thresholds.windows(2).all(|w| w[0] < w[1])
+}
+
+// budgets run for a UTC calendar month
+pub fn month_start(day: NaiveDate) -> NaiveDate {
+    day.with_day(1).unwrap_or(day)
+}
+
+pub fn next_month_start(day: NaiveDate) -> NaiveDate {
+    month_start(day)
+        .checked_add_months(Months::new(1))
+        .unwrap_or(day)
+}
+
+#[derive(Insertable, Debug, PartialEq, Clone)]
+#[diesel(table_name = budgets)]
+pub struct NewBudget {
+    pub customer_id: i32,
+    pub name: String,
+    pub category: Option<SpendingCategory>,
+    pub account_number: Option<String>,
+    pub amount_cents: i64,
+    pub alert_thresholds: Vec<i32>,
+}
+
+// what the budget tracks can't change, delete and re-add it for that
+#[derive(Clone, Debug, PartialEq)]
+pub struct BudgetUpdate {
+    pub name: Option<String>,
+    pub amount_cents: Option<i64>,
+    pub alert_thresholds: Option<Vec<i32>>,
+}
+
+#[derive(Clone)]
+#[cfg_attr(test, derive(Debug, PartialEq))]
+pub struct FindBudgetQuery {
+    pub customer_id: i32,
+}
+
+#[derive(Clone)]
+#[cfg_attr(test, derive(Debug, PartialEq))]
+pub struct BudgetProgress {
+    pub budget: Budget,
+    pub period_start: NaiveDate,
+    pub spent_cents: i64,
+}
+
+#[derive(Clone)]
+#[cfg_attr(test, derive(Debug, PartialEq))]
+pub struct FindBudgetProgressQuery {
+    pub customer_id: i32,
+    pub budget_id: Option<i32>,
+    // any day in the month wanted
+    pub day: NaiveDate,
+}
+
+// a transaction has settled, see whether it takes any of the customer's budgets over a threshold
+#[derive(Clone, Debug, PartialEq)]
+pub struct BudgetCheck {
+    pub transaction_id: i32,
+}
+
+#[derive(Clone, Queryable, Selectable)]
+#[cfg_attr(test, derive(Debug, PartialEq))]
+#[diesel(table_name = budget_alerts)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct BudgetAlert {
+    pub id: i32,
+    pub budget_id: i32,
+    pub customer_id: i32,
+    pub period_start: NaiveDate,
+    pub threshold_percent: i32,
+    pub spent_cents: i64,
+    pub budget_cents: i64,
+    pub transaction_id: Option<i32>,
+    pub created_at: chrono::NaiveDateTime,
+    pub delivered_at: Option<chrono::NaiveDateTime>,
+}
+
+#[derive(Insertable, Debug, PartialEq, Clone)]
+#[diesel(table_name = budget_alerts)]
+pub struct NewBudgetAlert {
+    pub budget_id: i32,
+    pub customer_id: i32,
+    pub period_start: NaiveDate,
+    pub threshold_percent: i32,
+    pub spent_cents: i64,
+    pub budget_cents: i64,
+    pub transaction_id: Option<i32>,
+}
+
+#[derive(Clone)]
+#[cfg_attr(test, derive(Debug, PartialEq))]
+pub struct FindBudgetAlertQuery {
+    pub customer_id: i32,
+    pub budget_id: Option<i32>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct BudgetAlertDelivered {
+    pub delivered_at: chrono::NaiveDateTime,
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::{is_valid_thresholds, month_start, next_month_start, Budget};
+    use crate::models::{
+        category::SpendingCategory,
+        transaction::{Transaction, TransactionStatus, TransactionType},
+    };
+
+    fn budget(category: Option<SpendingCategory>, account_number: Option<&str>) -> Budget {
+        let date = NaiveDate::from_ymd_opt(2023, 8, 1)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap();
+        Budget {
+            id: 1,
+            customer_id: 5,
+            name: "Eating out".to_string(),
+            category,
+            account_number: account_number.map(|n| n.to_string()),
+            amount_cents: 10000,
+            alert_thresholds: vec![50, 80, 100],
+            created_at: date,
+            updated_at: date,
+        }
+    }
+
+    fn spend(from_number: &str, amount_cents: i64, reversed_amount_cents: i64) -> Transaction {
+        let date = NaiveDate::from_ymd_opt(2023, 8, 28)
+            .unwrap()
+            .and_hms_opt(5, 24, 17)
+            .unwrap();
+        Transaction {
+            id: 70,
+            customer_id: 5,
+            transaction_type: TransactionType::External,
+            from_us: true,
+            amount_cents,
+            from_number: from_number.to_string(),
+            from_bsb: "123456".to_string(),
+            from_name: None,
+            to_number: "111111111".to_string(),
+            to_bsb: "654321".to_string(),
+            to_name: None,
+            available_balance_cents: 0,
+            date_start: date,
+            date_end: Some(date),
+            transaction_status: TransactionStatus::Success,
+            reversal_of: None,
+            reversed_amount_cents,
+            reversal_reason: None,
+            description: None,
+            payer_reference: None,
+            payee_reference: None,
+            counterpart_of: None,
+            running_balance_cents: None,
+        }
+    }
+
+    #[test]
+    fn spent_only_counts_covered_transactions() {
+        let spending = vec![
+            spend("938573843", 4000, 0),
+            spend("938573843", 3000, 1000),
+            spend("123456789", 2500, 0),
+        ];
+        let categorise = |tr: &Transaction| {
+            if tr.amount_cents == 2500 {
+                SpendingCategory::Groceries
+            } else {
+                SpendingCategory::Dining
+            }
+        };
+
+        assert_eq!(
+            6000,
+            budget(Some(SpendingCategory::Dining), None).spent_cents(&spending, categorise)
+        );
+        assert_eq!(
+            2500,
+            budget(None, Some("123456789")).spent_cents(&spending, categorise)
+        );
+        assert_eq!(8500, budget(None, None).spent_cents(&spending, categorise));
+    }
+
+    #[test]
+    fn thresholds_crossed() {
+        let budget = budget(None, None);
+
+        assert_eq!(Vec::<i32>::new(), budget.crossed_thresholds(4999));
+        assert_eq!(vec![50, 80], budget.crossed_thresholds(8000));
+        assert_eq!(vec![50, 80, 100], budget.crossed_thresholds(12000));
+    }
+
+    #[test]
+    fn valid_thresholds() {
+        assert!(is_valid_thresholds(&[80, 100]));
+        assert!(!is_valid_thresholds(&[]));
+        assert!(!is_valid_thresholds(&[100, 80]));
+        assert!(!is_valid_thresholds(&[80, 80]));
+        assert!(!is_valid_thresholds(&[0, 80]));
+        assert!(!is_valid_thresholds(&[10, 20, 30, 40, 50, 60]));
+    }
+
+    #[test]
+    fn month_bounds() {
+        let day = NaiveDate::from_ymd_opt(2023, 12, 28).unwrap();
+
+        assert_eq!(
+            NaiveDate::from_ymd_opt(2023, 12, 1).unwrap(),
+            month_start(day)
+        );
+        assert_eq!(
+            NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+            next_month_start(day)
+        );
+    }
+}
@@ -0,0 +1,60 @@
+use diesel::{Insertable, Queryable, Selectable};
+
+use super::schema::category_overrides;
+
+#[derive(diesel_derive_enum::DbEnum, Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[ExistingTypePath = "crate::models::schema::sql_types::SpendingCategory"]
+pub enum SpendingCategory {
+    Groceries,
+    Dining,
+    Transport,
+    Utilities,
+    Housing,
+    Entertainment,
+    Shopping,
+    Health,
+    // between accounts, ours or someone else's, when nothing more specific fits
+    Transfers,
+    Other,
+}
+
+#[derive(Clone, Queryable, Selectable)]
+#[cfg_attr(test, derive(Debug, PartialEq))]
+#[diesel(table_name = category_overrides)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct CategoryOverride {
+    pub id: i32,
+    pub customer_id: i32,
+    pub account_number: String,
+    pub bsb: String,
+    pub category: SpendingCategory,
+    pub created_at: chrono::NaiveDateTime,
+    pub updated_at: chrono::NaiveDateTime,
+}
+
+// created or replaced, there's only ever one per customer and payee account
+#[derive(Insertable, Debug, PartialEq, Clone)]
+#[diesel(table_name = category_overrides)]
+pub struct NewCategoryOverride {
+    pub customer_id: i32,
+    pub account_number: String,
+    pub bsb: String,
+    pub category: SpendingCategory,
+}
+
+#[derive(Clone)]
+#[cfg_attr(test, derive(Debug, PartialEq))]
+pub struct FindCategoryOverrideQuery {
+    pub customer_id: i32,
+}
+
+// settled outgoing transfers, what counts as spending
+#[derive(Clone)]
+#[cfg_attr(test, derive(Debug, PartialEq))]
+pub struct FindSpendingQuery {
+    pub customer_id: i32,
+    pub account_number: Option<String>,
+    // on date_start, from inclusive and to exclusive
+    pub from: chrono::NaiveDateTime,
+    pub to: chrono::NaiveDateTime,
+}
@@ -0,0 +1,62 @@
+use diesel::Insertable;
+
+use super::oauth::{scope_includes, OAuthScope};
+use super::schema::oauth_grant_accounts;
+
+// an oauth grant as the customer sees it: which app, what it can do, which accounts and until when
+#[derive(Clone)]
+#[cfg_attr(test, derive(Debug, PartialEq))]
+pub struct Consent {
+    // the grant's id
+    pub id: i32,
+    pub customer_id: i32,
+    // the app's public client_id
+    pub client_id: String,
+    pub client_name: String,
+    pub scope: String,
+    pub account_ids: Vec<i32>,
+    pub created_at: chrono::NaiveDateTime,
+    pub updated_at: chrono::NaiveDateTime,
+    pub expires_at: chrono::NaiveDateTime,
+    pub revoked_at: Option<chrono::NaiveDateTime>,
+}
+
+impl Consent {
+    pub fn is_active(&self, now: chrono::NaiveDateTime) -> bool {
+        self.revoked_at.is_none() && self.expires_at > now
+    }
+
+    pub fn covers(&self, scope: OAuthScope) -> bool {
+        scope_includes(&self.scope, scope)
+    }
+
+    pub fn includes_account(&self, account_id: i32) -> bool {
+        self.account_ids.contains(&account_id)
+    }
+}
+
+#[derive(Insertable, Debug, PartialEq, Clone)]
+#[diesel(table_name = oauth_grant_accounts)]
+pub struct NewConsentAccount {
+    pub grant_id: i32,
+    pub account_id: i32,
+}
+
+// live and past, newest first
+#[derive(Clone, Debug, PartialEq)]
+pub struct FindConsentQuery {
+    pub customer_id: i32,
+}
+
+// the consent as it should be from now on. customers can only narrow what the app was given,
+// more needs the app to ask again
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConsentAmendment {
+    pub scope: String,
+    pub account_ids: Vec<i32>,
+    pub expires_at: chrono::NaiveDateTime,
+}
+
+// same as revoking the grant, its tokens go too
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConsentRevocation;
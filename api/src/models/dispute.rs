@@ -0,0 +1,167 @@
+use diesel::{Insertable, Queryable, Selectable};
+
+use super::audit::ActorType;
+use super::schema::{dispute_events, disputes};
+
+#[derive(diesel_derive_enum::DbEnum, Copy, Clone, Debug, PartialEq)]
+#[ExistingTypePath = "crate::models::schema::sql_types::DisputeStatus"]
+pub enum DisputeStatus {
+    Open,
+    Investigating,
+    ProvisionalCredit,
+    ResolvedForCustomer,
+    ResolvedAgainstCustomer,
+}
+
+impl DisputeStatus {
+    pub fn is_resolved(&self) -> bool {
+        matches!(
+            self,
+            DisputeStatus::ResolvedForCustomer | DisputeStatus::ResolvedAgainstCustomer
+        )
+    }
+
+    pub fn can_transition_to(&self, next: DisputeStatus) -> bool {
+        match self {
+            DisputeStatus::Open => matches!(
+                next,
+                DisputeStatus::Investigating
+                    | DisputeStatus::ResolvedForCustomer
+                    | DisputeStatus::ResolvedAgainstCustomer
+            ),
+            DisputeStatus::Investigating => matches!(
+                next,
+                DisputeStatus::ProvisionalCredit
+                    | DisputeStatus::ResolvedForCustomer
+                    | DisputeStatus::ResolvedAgainstCustomer
+            ),
+            DisputeStatus::ProvisionalCredit => next.is_resolved(),
+            DisputeStatus::ResolvedForCustomer | DisputeStatus::ResolvedAgainstCustomer => false,
+        }
+    }
+
+    // money to move to (+) or from (-) the customer's account when moving into `self`,
+    // given how much has already been credited
+    pub fn credit_delta_cents(&self, amount_cents: i64, credited_cents: i64) -> i64 {
+        match self {
+            DisputeStatus::ProvisionalCredit | DisputeStatus::ResolvedForCustomer => {
+                amount_cents - credited_cents
+            }
+            DisputeStatus::ResolvedAgainstCustomer => -credited_cents,
+            DisputeStatus::Open | DisputeStatus::Investigating => 0,
+        }
+    }
+}
+
+#[derive(Clone, Queryable, Selectable)]
+#[cfg_attr(test, derive(Debug, PartialEq))]
+#[diesel(table_name = disputes)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct Dispute {
+    pub id: i32,
+    pub customer_id: i32,
+    pub transaction_id: i32,
+    pub amount_cents: i64,
+    pub reason: String,
+    pub dispute_status: DisputeStatus,
+    pub credited_cents: i64,
+    pub date_opened: chrono::NaiveDateTime,
+    pub date_resolved: Option<chrono::NaiveDateTime>,
+}
+
+#[derive(Insertable, Debug, PartialEq, Clone)]
+#[diesel(table_name = disputes)]
+pub struct NewDispute {
+    pub customer_id: i32,
+    pub transaction_id: i32,
+    pub amount_cents: i64,
+    pub reason: String,
+}
+
+#[derive(Clone, Queryable, Selectable)]
+#[cfg_attr(test, derive(Debug, PartialEq))]
+#[diesel(table_name = dispute_events)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct DisputeEvent {
+    pub id: i32,
+    pub dispute_id: i32,
+    pub dispute_status: DisputeStatus,
+    pub note: Option<String>,
+    pub actor_type: ActorType,
+    pub actor_id: i32,
+    pub transaction_id: Option<i32>,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+#[derive(Insertable, Debug, PartialEq, Clone)]
+#[diesel(table_name = dispute_events)]
+pub struct NewDisputeEvent {
+    pub dispute_id: i32,
+    pub dispute_status: DisputeStatus,
+    pub note: Option<String>,
+    pub actor_type: ActorType,
+    pub actor_id: i32,
+    pub transaction_id: Option<i32>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct DisputeTransition {
+    pub dispute_status: DisputeStatus,
+    pub note: Option<String>,
+    pub actor_type: ActorType,
+    pub actor_id: i32,
+}
+
+#[derive(Clone)]
+#[cfg_attr(test, derive(Debug, PartialEq))]
+pub struct FindDisputeQuery {
+    pub customer_id: i32,
+    pub transaction_id: Option<i32>,
+}
+
+#[derive(Clone)]
+#[cfg_attr(test, derive(Debug, PartialEq))]
+pub struct FindDisputeEventQuery {
+    pub dispute_id: i32,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DisputeStatus;
+
+    #[test]
+    fn transitions() {
+        assert!(DisputeStatus::Open.can_transition_to(DisputeStatus::Investigating));
+        assert!(!DisputeStatus::Open.can_transition_to(DisputeStatus::ProvisionalCredit));
+        assert!(DisputeStatus::ProvisionalCredit
+            .can_transition_to(DisputeStatus::ResolvedAgainstCustomer));
+        assert!(!DisputeStatus::ResolvedForCustomer.can_transition_to(DisputeStatus::Open));
+    }
+
+    #[test]
+    fn credit_deltas() {
+        // provisional credit then kept
+        assert_eq!(
+            800,
+            DisputeStatus::ProvisionalCredit.credit_delta_cents(800, 0)
+        );
+        assert_eq!(
+            0,
+            DisputeStatus::ResolvedForCustomer.credit_delta_cents(800, 800)
+        );
+        // provisional credit then clawed back
+        assert_eq!(
+            -800,
+            DisputeStatus::ResolvedAgainstCustomer.credit_delta_cents(800, 800)
+        );
+        // straight to resolved without provisional credit
+        assert_eq!(
+            800,
+            DisputeStatus::ResolvedForCustomer.credit_delta_cents(800, 0)
+        );
+        assert_eq!(
+            0,
+            DisputeStatus::ResolvedAgainstCustomer.credit_delta_cents(800, 0)
+        );
+    }
+}
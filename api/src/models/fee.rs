@@ -0,0 +1,199 @@
+use chrono::NaiveDate;
+use diesel::{Insertable, Queryable, Selectable};
+
+use super::account::AccountType;
+use super::schema::{fee_charges, fee_schedule};
+use super::transaction::TransactionType;
+
+// what the fee shows as on the customer's statement
+pub const ACCOUNT_KEEPING_DESCRIPTION: &str = "Account keeping fee";
+pub const EXTERNAL_TRANSFER_DESCRIPTION: &str = "External transfer fee";
+// biggest single fee staff can put on the schedule
+pub const MAX_FEE_CENTS: i64 = 10_000;
+
+#[derive(diesel_derive_enum::DbEnum, Copy, Clone, Debug, PartialEq)]
+#[ExistingTypePath = "crate::models::schema::sql_types::FeeKind"]
+pub enum FeeKind {
+    AccountKeeping,
+    ExternalTransfer,
+}
+
+impl FeeKind {
+    pub fn description(&self) -> &'static str {
+        match self {
+            FeeKind::AccountKeeping => ACCOUNT_KEEPING_DESCRIPTION,
+            FeeKind::ExternalTransfer => EXTERNAL_TRANSFER_DESCRIPTION,
+        }
+    }
+
+    // the per transaction fee a transfer of this type attracts, if any
+    pub fn for_transfer(transaction_type: TransactionType) -> Option<FeeKind> {
+        match transaction_type {
+            TransactionType::External => Some(FeeKind::ExternalTransfer),
+            TransactionType::Internal | TransactionType::Fee => None,
+        }
+    }
+}
+
+#[derive(Clone, Queryable, Selectable)]
+#[cfg_attr(test, derive(Debug, PartialEq))]
+#[diesel(table_name = fee_schedule)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct FeeScheduleEntry {
+    pub id: i32,
+    pub kind: FeeKind,
+    pub account_type: AccountType,
+    pub amount_cents: i64,
+    pub waiver_deposit_cents: Option<i64>,
+    pub updated_at: chrono::NaiveDateTime,
+}
+
+impl FeeScheduleUpdate {
+    pub fn is_valid(&self) -> bool {
+        (0..=MAX_FEE_CENTS).contains(&self.amount_cents)
+            && self
+                .waiver_deposit_cents
+                .map_or(true, |threshold| threshold > 0)
+    }
+}
+
+impl FeeScheduleEntry {
+    pub fn is_waived(&self, deposits_cents: i64) -> bool {
+        self.waiver_deposit_cents
+            .is_some_and(|threshold| deposits_cents >= threshold)
+    }
+
+    // what's actually owed given the month's deposits so far
+    pub fn fee(&self, deposits_cents: i64) -> AppliedFee {
+        let waived = self.is_waived(deposits_cents);
+        AppliedFee {
+            kind: self.kind,
+            schedule_cents: self.amount_cents,
+            amount_cents: if waived { 0 } else { self.amount_cents },
+            waived,
+        }
+    }
+}
+
+// one fee as it applies to one account, what gets disclosed before a transfer
+#[derive(Clone, Debug, PartialEq)]
+pub struct AppliedFee {
+    pub kind: FeeKind,
+    pub schedule_cents: i64,
+    pub amount_cents: i64,
+    pub waived: bool,
+}
+
+#[derive(Clone)]
+#[cfg_attr(test, derive(Debug, PartialEq))]
+pub struct FindFeeScheduleQuery {
+    pub kind: Option<FeeKind>,
+    pub account_type: Option<AccountType>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct FeeScheduleUpdate {
+    pub amount_cents: i64,
+    // None takes the waiver away
+    pub waiver_deposit_cents: Option<i64>,
+}
+
+#[derive(Clone, Queryable, Selectable)]
+#[cfg_attr(test, derive(Debug, PartialEq))]
+#[diesel(table_name = fee_charges)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct FeeCharge {
+    pub id: i32,
+    pub account_id: i32,
+    pub customer_id: i32,
+    pub kind: FeeKind,
+    pub period_start: Option<NaiveDate>,
+    pub trigger_transaction_id: Option<i32>,
+    pub amount_cents: i64,
+    pub transaction_id: i32,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+#[derive(Insertable, Debug, PartialEq, Clone)]
+#[diesel(table_name = fee_charges)]
+pub struct NewFeeCharge {
+    pub account_id: i32,
+    pub customer_id: i32,
+    pub kind: FeeKind,
+    pub period_start: Option<NaiveDate>,
+    pub trigger_transaction_id: Option<i32>,
+    pub amount_cents: i64,
+    pub transaction_id: i32,
+}
+
+// newest first
+#[derive(Clone)]
+#[cfg_attr(test, derive(Debug, PartialEq))]
+pub struct FindFeeChargeQuery {
+    pub customer_id: i32,
+    pub account_id: Option<i32>,
+}
+
+// the fees a transfer from the account would attract right now
+#[derive(Clone)]
+#[cfg_attr(test, derive(Debug, PartialEq))]
+pub struct FindTransferFeeQuery {
+    pub account_id: i32,
+    pub transaction_type: TransactionType,
+}
+
+// account keeping fees for every account for the month, safe to run again for the same month
+#[derive(Clone, Debug, PartialEq)]
+pub struct MonthlyFeeRun {
+    // any day in the month wanted
+    pub day: NaiveDate,
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::{AppliedFee, FeeKind, FeeScheduleEntry};
+    use crate::models::{account::AccountType, transaction::TransactionType};
+
+    fn entry(waiver_deposit_cents: Option<i64>) -> FeeScheduleEntry {
+        FeeScheduleEntry {
+            id: 1,
+            kind: FeeKind::AccountKeeping,
+            account_type: AccountType::Transaction,
+            amount_cents: 500,
+            waiver_deposit_cents,
+            updated_at: NaiveDate::from_ymd_opt(2023, 9, 6)
+                .unwrap()
+                .and_hms_opt(4, 32, 10)
+                .unwrap(),
+        }
+    }
+
+    #[test]
+    fn fee_waived_at_deposit_threshold() {
+        assert_eq!(
+            AppliedFee {
+                kind: FeeKind::AccountKeeping,
+                schedule_cents: 500,
+                amount_cents: 500,
+                waived: false,
+            },
+            entry(Some(200000)).fee(199999)
+        );
+        assert_eq!(0, entry(Some(200000)).fee(200000).amount_cents);
+        assert!(entry(Some(200000)).fee(200000).waived);
+        // no waiver on offer
+        assert_eq!(500, entry(None).fee(10_000_000).amount_cents);
+    }
+
+    #[test]
+    fn only_external_transfers_attract_transfer_fees() {
+        assert_eq!(
+            Some(FeeKind::ExternalTransfer),
+            FeeKind::for_transfer(TransactionType::External)
+        );
+        assert_eq!(None, FeeKind::for_transfer(TransactionType::Internal));
+        assert_eq!(None, FeeKind::for_transfer(TransactionType::Fee));
+    }
+}
@@ -0,0 +1,62 @@
+use diesel::{Insertable, Queryable, Selectable};
+
+use super::schema::fraud_reviews;
+use super::step_up::StepUpProof;
+use super::transaction::NewTransaction;
+
+#[derive(diesel_derive_enum::DbEnum, Copy, Clone, Debug, PartialEq)]
+#[ExistingTypePath = "crate::models::schema::sql_types::FraudReviewStatus"]
+pub enum FraudReviewStatus {
+    Pending,
+    Approved,
+    Rejected,
+}
+
+#[derive(Clone, Queryable, Selectable)]
+#[cfg_attr(test, derive(Debug, PartialEq))]
+#[diesel(table_name = fraud_reviews)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct FraudReview {
+    pub id: i32,
+    pub transaction_id: i32,
+    pub customer_id: i32,
+    // json array
+    pub reasons: String,
+    pub review_status: FraudReviewStatus,
+    pub reviewer_id: Option<i32>,
+    pub review_note: Option<String>,
+    pub created_at: chrono::NaiveDateTime,
+    pub reviewed_at: Option<chrono::NaiveDateTime>,
+}
+
+#[derive(Insertable, Debug, PartialEq, Clone)]
+#[diesel(table_name = fraud_reviews)]
+pub struct NewFraudReview {
+    pub transaction_id: i32,
+    pub customer_id: i32,
+    pub reasons: String,
+}
+
+// transaction that screening held, created along with its review in one go
+#[derive(Debug, PartialEq, Clone)]
+pub struct NewHeldTransaction {
+    pub transaction: NewTransaction,
+    pub reasons: Vec<String>,
+    // the holder who made it, it carries their signature
+    pub signed_by: i32,
+    pub step_up: Option<StepUpProof>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct FraudReviewDecision {
+    pub approve: bool,
+    pub reviewer_id: i32,
+    pub note: Option<String>,
+}
+
+#[derive(Clone)]
+#[cfg_attr(test, derive(Debug, PartialEq))]
+pub struct FindFraudReviewQuery {
+    pub review_status: Option<FraudReviewStatus>,
+    pub customer_id: Option<i32>,
+}
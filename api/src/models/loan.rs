@@ -0,0 +1,572 @@
+use chrono::{Months, NaiveDate};
+use diesel::{Insertable, Queryable, Selectable};
+
+use super::account::Account;
+use super::overdraft::OverdraftCharge;
+use super::schema::{
+    loan_applications, loan_interest_charges, loan_offsets, loan_schedule_entries, loans,
+};
+use super::transaction::Transaction;
+
+// what the interest shows as on the customer's statement
+pub const INTEREST_DESCRIPTION: &str = "Loan interest";
+pub const REPAYMENT_DESCRIPTION: &str = "Loan repayment";
+pub const DISBURSEMENT_DESCRIPTION: &str = "Loan disbursement";
+// highest rate staff can put on a loan without it going to credit
+pub const MAX_INTEREST_RATE_BPS: i32 = 3_000;
+// the most that can be applied for without going to credit
+pub const MAX_PRINCIPAL_CENTS: i64 = 200_000_000;
+pub const MAX_TERM_MONTHS: i32 = 360;
+// the accounts table's limit
+const MAX_ACCOUNT_NAME_LEN: usize = 40;
+
+const DAYS_PER_YEAR: i128 = 365;
+const MONTHS_PER_YEAR: i128 = 12;
+
+#[derive(diesel_derive_enum::DbEnum, Copy, Clone, Debug, PartialEq)]
+#[ExistingTypePath = "crate::models::schema::sql_types::LoanRepaymentType"]
+pub enum LoanRepaymentType {
+    PrincipalAndInterest,
+    // interest every month, the principal all at once with the last installment
+    InterestOnly,
+}
+
+#[derive(diesel_derive_enum::DbEnum, Copy, Clone, Debug, PartialEq)]
+#[ExistingTypePath = "crate::models::schema::sql_types::LoanApplicationStatus"]
+pub enum LoanApplicationStatus {
+    Pending,
+    Approved,
+    Declined,
+}
+
+// a day's interest on what's owed less what's sitting in the offsets, rounded to the nearest cent.
+// offsets only ever bring the interest down to nothing
+pub fn daily_interest_cents(owed_cents: i64, offset_cents: i64, interest_rate_bps: i32) -> i64 {
+    let charged_on = (owed_cents - offset_cents.max(0)).max(0) as i128;
+
+    let divisor = 10_000 * DAYS_PER_YEAR;
+    ((charged_on * interest_rate_bps as i128 + divisor / 2) / divisor) as i64
+}
+
+// a month's interest on the balance, rounded to the nearest cent. only what the schedule expects,
+// interest actually goes on daily
+pub fn monthly_interest_cents(owed_cents: i64, interest_rate_bps: i32) -> i64 {
+    let divisor = 10_000 * MONTHS_PER_YEAR;
+    ((owed_cents.max(0) as i128 * interest_rate_bps as i128 + divisor / 2) / divisor) as i64
+}
+
+// the level monthly payment that clears owed_cents over count months, rounded up to the cent so
+// it's the last installment that comes in a little under
+pub fn level_payment_cents(owed_cents: i64, interest_rate_bps: i32, count: i32) -> i64 {
+    let rate = interest_rate_bps as f64 / 10_000.0 / MONTHS_PER_YEAR as f64;
+    let payment = owed_cents as f64 * rate / (1.0 - (1.0 + rate).powi(-count));
+    payment.ceil() as i64
+}
+
+// installments fall on the same day of the month as the first, or the month's last day if it's
+// shorter
+pub fn repayment_due_date(first_repayment_date: NaiveDate, installment: i32) -> NaiveDate {
+    first_repayment_date + Months::new((installment - 1) as u32)
+}
+
+// the installments from from_installment to the end of the term that pay off owed_cents. the last
+// one takes whatever's left so the loan still finishes on time
+pub fn amortise(
+    owed_cents: i64,
+    interest_rate_bps: i32,
+    repayment_type: LoanRepaymentType,
+    first_repayment_date: NaiveDate,
+    from_installment: i32,
+    term_months: i32,
+) -> Vec<ScheduledRepayment> {
+    let count = term_months - from_installment + 1;
+    if owed_cents <= 0 || count <= 0 {
+        return vec![];
+    }
+
+    let payment_cents = level_payment_cents(owed_cents, interest_rate_bps, count);
+    let mut balance_cents = owed_cents;
+
+    (from_installment..=term_months)
+        .map(|installment| {
+            let interest_cents = monthly_interest_cents(balance_cents, interest_rate_bps);
+            let principal_cents = if installment == term_months {
+                balance_cents
+            } else {
+                match repayment_type {
+                    LoanRepaymentType::PrincipalAndInterest => {
+                        (payment_cents - interest_cents).clamp(0, balance_cents)
+                    }
+                    LoanRepaymentType::InterestOnly => 0,
+                }
+            };
+            balance_cents -= principal_cents;
+
+            ScheduledRepayment {
+                installment,
+                due_date: repayment_due_date(first_repayment_date, installment),
+                amount_cents: principal_cents + interest_cents,
+                principal_cents,
+                interest_cents,
+            }
+        })
+        .collect()
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ScheduledRepayment {
+    pub installment: i32,
+    pub due_date: NaiveDate,
+    pub amount_cents: i64,
+    pub principal_cents: i64,
+    pub interest_cents: i64,
+}
+
+#[derive(Clone, Queryable, Selectable)]
+#[cfg_attr(test, derive(Debug, PartialEq))]
+#[diesel(table_name = loans)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct Loan {
+    pub id: i32,
+    pub account_id: i32,
+    pub customer_id: i32,
+    pub interest_rate_bps: i32,
+    pub created_at: chrono::NaiveDateTime,
+    // the rest is only there for loans the bank wrote
+    pub principal_cents: Option<i64>,
+    pub term_months: Option<i32>,
+    pub repayment_type: Option<LoanRepaymentType>,
+    pub first_repayment_date: Option<NaiveDate>,
+    // where scheduled repayments are taken from
+    pub repayment_account_id: Option<i32>,
+}
+
+#[derive(Insertable, Debug, PartialEq, Clone)]
+#[diesel(table_name = loans)]
+pub struct NewLoanTerms {
+    pub account_id: i32,
+    pub customer_id: i32,
+    pub interest_rate_bps: i32,
+    pub principal_cents: Option<i64>,
+    pub term_months: Option<i32>,
+    pub repayment_type: Option<LoanRepaymentType>,
+    pub first_repayment_date: Option<NaiveDate>,
+    pub repayment_account_id: Option<i32>,
+}
+
+// opens the loan account and the loan together, owing the full amount from the start
+#[derive(Clone, Debug, PartialEq)]
+pub struct NewLoan {
+    pub customer_id: i32,
+    pub account_name: Option<String>,
+    pub account_number: String,
+    pub owed_cents: i64,
+    pub interest_rate_bps: i32,
+}
+
+impl NewLoan {
+    pub fn is_valid(&self) -> bool {
+        self.owed_cents > 0 && (1..=MAX_INTEREST_RATE_BPS).contains(&self.interest_rate_bps)
+    }
+}
+
+// a loan as the customer sees it, with the accounts it's made of and its schedule if it has one
+#[derive(Clone)]
+pub struct LoanDetail {
+    pub loan: Loan,
+    pub account: Account,
+    pub offsets: Vec<LoanOffsetAccount>,
+    pub schedule: Vec<LoanScheduleEntry>,
+}
+
+impl LoanDetail {
+    pub fn owed_cents(&self) -> i64 {
+        self.account.overdrawn_cents()
+    }
+
+    pub fn offset_cents(&self) -> i64 {
+        self.offsets
+            .iter()
+            .map(|offset| offset.account.balance_cents.max(0))
+            .sum()
+    }
+
+    // what interest is being charged on as things stand
+    pub fn effective_owed_cents(&self) -> i64 {
+        (self.owed_cents() - self.offset_cents()).max(0)
+    }
+
+    // installments that fell due before today and haven't been paid in full
+    fn overdue(&self, today: NaiveDate) -> impl Iterator<Item = &LoanScheduleEntry> {
+        self.schedule
+            .iter()
+            .filter(move |entry| entry.due_date < today && entry.outstanding_cents() > 0)
+    }
+
+    pub fn arrears_cents(&self, today: NaiveDate) -> i64 {
+        self.overdue(today)
+            .map(LoanScheduleEntry::outstanding_cents)
+            .sum()
+    }
+
+    // counted from the oldest installment still owing
+    pub fn days_in_arrears(&self, today: NaiveDate) -> i64 {
+        self.overdue(today)
+            .map(|entry| (today - entry.due_date).num_days())
+            .max()
+            .unwrap_or(0)
+    }
+
+    pub fn next_repayment(&self, today: NaiveDate) -> Option<&LoanScheduleEntry> {
+        self.schedule
+            .iter()
+            .find(|entry| entry.due_date >= today && entry.outstanding_cents() > 0)
+    }
+}
+
+#[derive(Clone)]
+pub struct LoanOffsetAccount {
+    pub offset: LoanOffset,
+    pub account: Account,
+}
+
+#[derive(Clone)]
+#[cfg_attr(test, derive(Debug, PartialEq))]
+pub struct FindLoanQuery {
+    pub customer_id: i32,
+}
+
+#[derive(Clone, Queryable, Selectable)]
+#[cfg_attr(test, derive(Debug, PartialEq))]
+#[diesel(table_name = loan_offsets)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct LoanOffset {
+    pub id: i32,
+    pub loan_id: i32,
+    pub account_id: i32,
+    pub customer_id: i32,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+// Conflict if the account already offsets a loan
+#[derive(Insertable, Debug, PartialEq, Clone)]
+#[diesel(table_name = loan_offsets)]
+pub struct NewLoanOffset {
+    pub loan_id: i32,
+    pub account_id: i32,
+    pub customer_id: i32,
+}
+
+// an internal transfer into the loan account. Conflict if it's more than what's owed or the
+// account can't cover it
+#[derive(Clone, Debug, PartialEq)]
+pub struct NewLoanRepayment {
+    pub loan_id: i32,
+    pub customer_id: i32,
+    pub from_account_id: i32,
+    pub amount_cents: i64,
+}
+
+#[derive(Clone, Queryable, Selectable)]
+#[cfg_attr(test, derive(Debug, PartialEq))]
+#[diesel(table_name = loan_interest_charges)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct LoanInterestCharge {
+    pub id: i32,
+    pub loan_id: i32,
+    pub customer_id: i32,
+    pub charge_date: NaiveDate,
+    pub owed_cents: i64,
+    pub offset_cents: i64,
+    pub amount_cents: i64,
+    pub transaction_id: i32,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+#[derive(Insertable, Debug, PartialEq, Clone)]
+#[diesel(table_name = loan_interest_charges)]
+pub struct NewLoanInterestCharge {
+    pub loan_id: i32,
+    pub customer_id: i32,
+    pub charge_date: NaiveDate,
+    pub owed_cents: i64,
+    pub offset_cents: i64,
+    pub amount_cents: i64,
+    pub transaction_id: i32,
+}
+
+#[derive(Clone, Queryable, Selectable)]
+#[cfg_attr(test, derive(Debug, PartialEq))]
+#[diesel(table_name = loan_schedule_entries)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct LoanScheduleEntry {
+    pub id: i32,
+    pub loan_id: i32,
+    pub installment: i32,
+    pub due_date: NaiveDate,
+    pub amount_cents: i64,
+    pub principal_cents: i64,
+    pub interest_cents: i64,
+    pub paid_cents: i64,
+}
+
+impl LoanScheduleEntry {
+    pub fn outstanding_cents(&self) -> i64 {
+        self.amount_cents - self.paid_cents
+    }
+}
+
+#[derive(Insertable, Debug, PartialEq, Clone)]
+#[diesel(table_name = loan_schedule_entries)]
+pub struct NewLoanScheduleEntry {
+    pub loan_id: i32,
+    pub installment: i32,
+    pub due_date: NaiveDate,
+    pub amount_cents: i64,
+    pub principal_cents: i64,
+    pub interest_cents: i64,
+}
+
+impl NewLoanScheduleEntry {
+    pub fn new(loan: &Loan, repayment: ScheduledRepayment) -> NewLoanScheduleEntry {
+        NewLoanScheduleEntry {
+            loan_id: loan.id,
+            installment: repayment.installment,
+            due_date: repayment.due_date,
+            amount_cents: repayment.amount_cents,
+            principal_cents: repayment.principal_cents,
+            interest_cents: repayment.interest_cents,
+        }
+    }
+}
+
+// newest first
+#[derive(Clone)]
+#[cfg_attr(test, derive(Debug, PartialEq))]
+pub struct FindLoanInterestQuery {
+    pub customer_id: i32,
+    pub loan_id: i32,
+}
+
+// interest on every loan for the day, on the balances everything ended the day with. safe to run
+// again for the same day
+#[derive(Clone, Debug, PartialEq)]
+pub struct LoanInterestRun {
+    pub day: NaiveDate,
+}
+
+// takes what's due on or before the day from each loan's repayment account. anything still unpaid
+// is tried again on the next run
+#[derive(Clone, Debug, PartialEq)]
+pub struct LoanRepaymentRun {
+    pub day: NaiveDate,
+}
+
+// None if the repayment account couldn't cover what was due, the loan is in arrears
+#[derive(Clone)]
+pub struct LoanRepaymentCollection {
+    pub loan_id: i32,
+    pub customer_id: i32,
+    pub due_cents: i64,
+    pub transaction: Option<Transaction>,
+    // a repayment the account couldn't cover bounces, if it has an overdraft that costs a fee
+    pub dishonour_fee: Option<OverdraftCharge>,
+}
+
+#[derive(Clone, Queryable, Selectable)]
+#[cfg_attr(test, derive(Debug, PartialEq))]
+#[diesel(table_name = loan_applications)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct LoanApplication {
+    pub id: i32,
+    pub customer_id: i32,
+    pub principal_cents: i64,
+    pub term_months: i32,
+    pub repayment_type: LoanRepaymentType,
+    pub disbursement_account_id: i32,
+    pub account_name: Option<String>,
+    pub application_status: LoanApplicationStatus,
+    pub interest_rate_bps: Option<i32>,
+    pub loan_id: Option<i32>,
+    pub reviewer_id: Option<i32>,
+    pub review_note: Option<String>,
+    pub created_at: chrono::NaiveDateTime,
+    pub reviewed_at: Option<chrono::NaiveDateTime>,
+}
+
+#[derive(Insertable, Debug, PartialEq, Clone)]
+#[diesel(table_name = loan_applications)]
+pub struct NewLoanApplication {
+    pub customer_id: i32,
+    pub principal_cents: i64,
+    pub term_months: i32,
+    pub repayment_type: LoanRepaymentType,
+    pub disbursement_account_id: i32,
+    pub account_name: Option<String>,
+}
+
+impl NewLoanApplication {
+    pub fn is_valid(&self) -> bool {
+        (1..=MAX_PRINCIPAL_CENTS).contains(&self.principal_cents)
+            && (1..=MAX_TERM_MONTHS).contains(&self.term_months)
+            && self
+                .account_name
+                .as_ref()
+                .map_or(true, |name| name.chars().count() <= MAX_ACCOUNT_NAME_LEN)
+    }
+}
+
+// approving opens the loan, pays the principal into the disbursement account and writes the
+// schedule, all or nothing. Conflict if the application's already been decided or the
+// disbursement account has been closed since
+#[derive(Clone, Debug, PartialEq)]
+pub struct LoanApplicationDecision {
+    pub approve: bool,
+    pub reviewer_id: i32,
+    // only for an approval
+    pub interest_rate_bps: Option<i32>,
+    pub account_number: String,
+    pub note: Option<String>,
+}
+
+impl LoanApplicationDecision {
+    pub fn is_valid(&self) -> bool {
+        match self.interest_rate_bps {
+            Some(rate) => self.approve && (1..=MAX_INTEREST_RATE_BPS).contains(&rate),
+            None => !self.approve,
+        }
+    }
+}
+
+#[derive(Clone)]
+#[cfg_attr(test, derive(Debug, PartialEq))]
+pub struct FindLoanApplicationQuery {
+    pub customer_id: Option<i32>,
+    pub application_status: Option<LoanApplicationStatus>,
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::{
+        amortise, daily_interest_cents, repayment_due_date, LoanRepaymentType, ScheduledRepayment,
+    };
+
+    #[test]
+    fn daily_interest_rounds_to_nearest_cent() {
+        // $500,000 at 6.5% is $89.04 a day
+        assert_eq!(8904, daily_interest_cents(50_000_000, 0, 650));
+        // $1,000 at 6.5% is 17.8c
+        assert_eq!(18, daily_interest_cents(100_000, 0, 650));
+    }
+
+    #[test]
+    fn offsets_reduce_interest() {
+        // $450,000 after a $50,000 offset is $80.14 a day
+        assert_eq!(8014, daily_interest_cents(50_000_000, 5_000_000, 650));
+        // fully offset costs nothing, and a bigger offset doesn't pay the customer
+        assert_eq!(0, daily_interest_cents(50_000_000, 50_000_000, 650));
+        assert_eq!(0, daily_interest_cents(50_000_000, 60_000_000, 650));
+        // an overdrawn offset doesn't add to the loan
+        assert_eq!(8904, daily_interest_cents(50_000_000, -10_000, 650));
+    }
+
+    fn first_due() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2023, 10, 11).unwrap()
+    }
+
+    #[test]
+    fn principal_and_interest_pays_off_on_time() {
+        // $10,000 at 12% over a year is $888.49 a month
+        let schedule = amortise(
+            1_000_000,
+            1_200,
+            LoanRepaymentType::PrincipalAndInterest,
+            first_due(),
+            1,
+            12,
+        );
+
+        assert_eq!(12, schedule.len());
+        assert_eq!(
+            ScheduledRepayment {
+                installment: 1,
+                due_date: first_due(),
+                amount_cents: 88_849,
+                principal_cents: 78_849,
+                interest_cents: 10_000,
+            },
+            schedule[0]
+        );
+        assert!(schedule[..11].iter().all(|r| r.amount_cents == 88_849));
+        // rounding the payment up leaves the last one a little short
+        assert!(schedule[11].amount_cents <= 88_849);
+        assert_eq!(
+            1_000_000,
+            schedule.iter().map(|r| r.principal_cents).sum::<i64>()
+        );
+    }
+
+    #[test]
+    fn interest_only_pays_principal_at_the_end() {
+        let schedule = amortise(
+            1_000_000,
+            1_200,
+            LoanRepaymentType::InterestOnly,
+            first_due(),
+            1,
+            12,
+        );
+
+        assert!(schedule[..11]
+            .iter()
+            .all(|r| r.amount_cents == 10_000 && r.principal_cents == 0));
+        assert_eq!(1_010_000, schedule[11].amount_cents);
+    }
+
+    #[test]
+    fn recalculated_schedule_keeps_installment_dates() {
+        // $5,000 left with 6 of 12 installments to go after paying down early
+        let schedule = amortise(
+            500_000,
+            1_200,
+            LoanRepaymentType::PrincipalAndInterest,
+            first_due(),
+            7,
+            12,
+        );
+
+        assert_eq!(6, schedule.len());
+        assert_eq!(7, schedule[0].installment);
+        assert_eq!(repayment_due_date(first_due(), 7), schedule[0].due_date);
+        assert_eq!(86_275, schedule[0].amount_cents);
+        assert_eq!(
+            500_000,
+            schedule.iter().map(|r| r.principal_cents).sum::<i64>()
+        );
+        // nothing left to schedule
+        assert!(amortise(
+            0,
+            1_200,
+            LoanRepaymentType::PrincipalAndInterest,
+            first_due(),
+            7,
+            12
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn due_dates_hold_to_month_end() {
+        let first = NaiveDate::from_ymd_opt(2024, 1, 31).unwrap();
+        assert_eq!(
+            NaiveDate::from_ymd_opt(2024, 2, 29).unwrap(),
+            repayment_due_date(first, 2)
+        );
+        assert_eq!(
+            NaiveDate::from_ymd_opt(2024, 3, 31).unwrap(),
+            repayment_due_date(first, 3)
+        );
+    }
+}
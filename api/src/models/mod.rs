@@ -1,5 +1,25 @@
 // db models. For now these are also domain models
 
 pub mod account;
+pub mod account_holder;
+pub mod approval;
+pub mod audit;
+pub mod automation;
+pub mod balance_snapshot;
+pub mod bucket;
+pub mod budget;
+pub mod category;
+pub mod consent;
+pub mod dispute;
+pub mod fee;
+pub mod fraud_review;
+pub mod loan;
+pub mod oauth;
+pub mod outbox;
+pub mod overdraft;
+pub mod payee;
 pub mod schema;
+pub mod step_up;
 pub mod transaction;
+pub mod transfer_limit;
+pub mod webhook;
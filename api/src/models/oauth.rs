@@ -0,0 +1,285 @@
+use diesel::{Insertable, Queryable, Selectable};
+
+use super::schema::{oauth_authorisation_codes, oauth_clients, oauth_grants, oauth_tokens};
+
+pub const AUTHORISATION_CODE_TTL_MINUTES: i64 = 10;
+pub const ACCESS_TOKEN_TTL_MINUTES: i64 = 60;
+// each refresh hands out a new one, so this is how long an app can go without using it
+pub const REFRESH_TOKEN_TTL_DAYS: i64 = 90;
+// the longest a customer can consent to share for, and what they get if they don't say
+pub const MAX_SHARING_DAYS: i64 = 365;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum OAuthScope {
+    AccountsRead,
+    TransactionsRead,
+    PayeesRead,
+    PaymentsWrite,
+}
+
+impl OAuthScope {
+    pub const ALL: [OAuthScope; 4] = [
+        OAuthScope::AccountsRead,
+        OAuthScope::TransactionsRead,
+        OAuthScope::PayeesRead,
+        OAuthScope::PaymentsWrite,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OAuthScope::AccountsRead => "accounts:read",
+            OAuthScope::TransactionsRead => "transactions:read",
+            OAuthScope::PayeesRead => "payees:read",
+            OAuthScope::PaymentsWrite => "payments:write",
+        }
+    }
+
+    pub fn parse(scope: &str) -> Option<OAuthScope> {
+        OAuthScope::ALL.into_iter().find(|s| s.as_str() == scope)
+    }
+
+    // limited to the accounts the customer consented to share
+    pub fn is_account_bound(&self) -> bool {
+        !matches!(self, OAuthScope::PayeesRead)
+    }
+}
+
+// space separated, as oauth sends them. None if it's empty or anything in it is unknown
+pub fn parse_scopes(scope: &str) -> Option<Vec<OAuthScope>> {
+    let mut scopes = vec![];
+    for part in scope.split_whitespace() {
+        let parsed = OAuthScope::parse(part)?;
+        if !scopes.contains(&parsed) {
+            scopes.push(parsed);
+        }
+    }
+    (!scopes.is_empty()).then_some(scopes)
+}
+
+// always in the same order so stored scopes compare equal
+pub fn scope_string(scopes: &[OAuthScope]) -> String {
+    OAuthScope::ALL
+        .into_iter()
+        .filter(|s| scopes.contains(s))
+        .map(|s| s.as_str())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+pub fn scope_includes(scope: &str, wanted: OAuthScope) -> bool {
+    scope.split_whitespace().any(|s| s == wanted.as_str())
+}
+
+#[derive(diesel_derive_enum::DbEnum, Copy, Clone, Debug, PartialEq)]
+#[ExistingTypePath = "crate::models::schema::sql_types::OauthTokenKind"]
+pub enum OAuthTokenKind {
+    Access,
+    Refresh,
+}
+
+#[derive(Clone, Queryable, Selectable)]
+#[cfg_attr(test, derive(Debug, PartialEq))]
+#[diesel(table_name = oauth_clients)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct OAuthClient {
+    pub id: i32,
+    // what the app identifies itself with, not the row id
+    pub client_id: String,
+    // none for public clients (mobile, single page apps) that can't keep a secret
+    pub client_secret_hash: Option<String>,
+    pub client_name: String,
+    pub redirect_uri: String,
+    pub scope: String,
+    pub created_by: i32,
+    pub created_at: chrono::NaiveDateTime,
+    pub revoked_at: Option<chrono::NaiveDateTime>,
+}
+
+impl OAuthClient {
+    pub fn is_active(&self) -> bool {
+        self.revoked_at.is_none()
+    }
+}
+
+#[derive(Insertable, Debug, PartialEq, Clone)]
+#[diesel(table_name = oauth_clients)]
+pub struct NewOAuthClient {
+    pub client_id: String,
+    pub client_secret_hash: Option<String>,
+    pub client_name: String,
+    pub redirect_uri: String,
+    pub scope: String,
+    pub created_by: i32,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct FindOAuthClientQuery {
+    pub client_id: Option<String>,
+}
+
+// takes every grant to the client, and their tokens, with it
+#[derive(Clone, Debug, PartialEq)]
+pub struct OAuthClientRevocation;
+
+#[derive(Clone, Queryable, Selectable)]
+#[cfg_attr(test, derive(Debug, PartialEq))]
+#[diesel(table_name = oauth_grants)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct OAuthGrant {
+    pub id: i32,
+    // the oauth_clients row id
+    pub client_id: i32,
+    pub customer_id: i32,
+    // tokens carry their own copy, this is what the customer last agreed to
+    #[allow(dead_code)]
+    pub scope: String,
+    #[allow(dead_code)]
+    pub created_at: chrono::NaiveDateTime,
+    #[allow(dead_code)]
+    pub updated_at: chrono::NaiveDateTime,
+    pub revoked_at: Option<chrono::NaiveDateTime>,
+    pub expires_at: chrono::NaiveDateTime,
+}
+
+impl OAuthGrant {
+    pub fn is_active(&self, now: chrono::NaiveDateTime) -> bool {
+        self.revoked_at.is_none() && self.expires_at > now
+    }
+}
+
+// takes the grant's tokens with it
+#[derive(Clone, Debug, PartialEq)]
+pub struct OAuthGrantRevocation;
+
+#[derive(Clone, Queryable, Selectable)]
+#[cfg_attr(test, derive(Debug, PartialEq))]
+#[diesel(table_name = oauth_authorisation_codes)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct OAuthAuthorisationCode {
+    pub id: i32,
+    pub grant_id: i32,
+    // looked up by, never read back
+    #[allow(dead_code)]
+    pub code_hash: String,
+    pub redirect_uri: String,
+    pub scope: String,
+    pub code_challenge: String,
+    pub expires_at: chrono::NaiveDateTime,
+    pub used_at: Option<chrono::NaiveDateTime>,
+    #[allow(dead_code)]
+    pub created_at: chrono::NaiveDateTime,
+}
+
+impl OAuthAuthorisationCode {
+    pub fn is_usable(&self, now: chrono::NaiveDateTime) -> bool {
+        self.used_at.is_none() && self.expires_at > now
+    }
+}
+
+// the customer agreeing to let a client in, which makes or replaces their grant to it and hands
+// back a code for the client to exchange
+#[derive(Clone, Debug, PartialEq)]
+pub struct NewOAuthAuthorisation {
+    pub client_id: i32,
+    pub customer_id: i32,
+    pub scope: String,
+    // what the grant shares and until when, replacing whatever it shared before
+    pub account_ids: Vec<i32>,
+    pub grant_expires_at: chrono::NaiveDateTime,
+    pub code_hash: String,
+    pub redirect_uri: String,
+    pub code_challenge: String,
+    pub expires_at: chrono::NaiveDateTime,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct FindAuthorisationCodeQuery {
+    pub code_hash: String,
+}
+
+#[derive(Clone, Queryable, Selectable)]
+#[cfg_attr(test, derive(Debug, PartialEq))]
+#[diesel(table_name = oauth_tokens)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct OAuthToken {
+    pub id: i32,
+    pub grant_id: i32,
+    pub customer_id: i32,
+    pub token_kind: OAuthTokenKind,
+    // looked up by, never read back
+    #[allow(dead_code)]
+    pub token_hash: String,
+    pub scope: String,
+    pub expires_at: chrono::NaiveDateTime,
+    pub revoked_at: Option<chrono::NaiveDateTime>,
+    #[allow(dead_code)]
+    pub created_at: chrono::NaiveDateTime,
+}
+
+impl OAuthToken {
+    pub fn is_active(&self, now: chrono::NaiveDateTime) -> bool {
+        self.revoked_at.is_none() && self.expires_at > now
+    }
+}
+
+#[derive(Insertable, Debug, PartialEq, Clone)]
+#[diesel(table_name = oauth_tokens)]
+pub struct NewOAuthToken {
+    pub grant_id: i32,
+    pub customer_id: i32,
+    pub token_kind: OAuthTokenKind,
+    pub token_hash: String,
+    pub scope: String,
+    pub expires_at: chrono::NaiveDateTime,
+}
+
+// whatever state it's in, callers check
+#[derive(Clone, Debug, PartialEq)]
+pub struct FindOAuthTokenQuery {
+    pub token_hash: String,
+}
+
+// a code swapped for an access and refresh token. the code can only be used the once
+#[derive(Clone, Debug, PartialEq)]
+pub struct CodeExchange {
+    pub code_id: i32,
+    pub access_token: NewOAuthToken,
+    pub refresh_token: NewOAuthToken,
+}
+
+// a refresh token swapped for a new pair, the old one is revoked in the same go
+#[derive(Clone, Debug, PartialEq)]
+pub struct TokenRefresh {
+    pub refresh_token_id: i32,
+    pub access_token: NewOAuthToken,
+    pub refresh_token: NewOAuthToken,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct OAuthTokenRevocation;
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_scopes, scope_includes, scope_string, OAuthScope};
+
+    #[test]
+    fn scopes_round_trip_in_a_fixed_order() {
+        let scopes = parse_scopes("transactions:read accounts:read accounts:read").unwrap();
+
+        assert_eq!(
+            scopes,
+            vec![OAuthScope::TransactionsRead, OAuthScope::AccountsRead]
+        );
+        assert_eq!(scope_string(&scopes), "accounts:read transactions:read");
+        assert!(scope_includes(
+            "accounts:read transactions:read",
+            OAuthScope::TransactionsRead
+        ));
+        assert!(!scope_includes(
+            "accounts:read transactions:read",
+            OAuthScope::PaymentsWrite
+        ));
+        assert_eq!(parse_scopes("accounts:read payments:read"), None);
+        assert_eq!(parse_scopes(" "), None);
+    }
+}
@@ -0,0 +1,213 @@
+use diesel::{Insertable, Queryable, Selectable};
+use serde_json::json;
+
+use super::account::Account;
+use super::schema::outbox_events;
+use super::transaction::{Transaction, TransactionStatus};
+use crate::util::random_uuid;
+
+// doubled after every failed try up to the cap. events are never given up on, whatever's
+// downstream is expected to come back
+pub const FIRST_RETRY_DELAY_SECONDS: i64 = 5;
+pub const MAX_RETRY_DELAY_SECONDS: i64 = 3600;
+// how long the relay has an event to itself before someone else can pick it up again
+pub const RELAY_LEASE_SECONDS: i64 = 60;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum EventType {
+    TransactionCreated,
+    TransactionSettled,
+    AccountOpened,
+    AccountClosed,
+}
+
+impl EventType {
+    pub const ALL: [EventType; 4] = [
+        EventType::TransactionCreated,
+        EventType::TransactionSettled,
+        EventType::AccountOpened,
+        EventType::AccountClosed,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EventType::TransactionCreated => "transaction.created",
+            EventType::TransactionSettled => "transaction.settled",
+            EventType::AccountOpened => "account.opened",
+            EventType::AccountClosed => "account.closed",
+        }
+    }
+
+    pub fn parse(event_type: &str) -> Option<EventType> {
+        EventType::ALL
+            .into_iter()
+            .find(|t| t.as_str() == event_type)
+    }
+}
+
+#[derive(Clone, Queryable, Selectable)]
+#[cfg_attr(test, derive(Debug, PartialEq))]
+#[diesel(table_name = outbox_events)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct OutboxEvent {
+    pub id: i32,
+    // what consumers dedupe on, the same however many times it's published
+    pub event_id: String,
+    pub event_type: String,
+    // the json published as is, see NewOutboxEvent::new
+    pub payload: String,
+    pub attempts: i32,
+    pub last_error: Option<String>,
+    pub next_attempt_at: chrono::NaiveDateTime,
+    pub published_at: Option<chrono::NaiveDateTime>,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+#[derive(Insertable, Debug, PartialEq, Clone)]
+#[diesel(table_name = outbox_events)]
+pub struct NewOutboxEvent {
+    pub event_id: String,
+    pub event_type: String,
+    pub payload: String,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+impl NewOutboxEvent {
+    pub fn new(
+        event_type: EventType,
+        data: serde_json::Value,
+        created_at: chrono::NaiveDateTime,
+    ) -> NewOutboxEvent {
+        let event_id = random_uuid();
+        let payload = json!({
+            "id": event_id,
+            "type": event_type.as_str(),
+            "createdAt": created_at.format("%Y-%m-%dT%H:%M:%S%.6fZ").to_string(),
+            "data": data,
+        });
+
+        NewOutboxEvent {
+            event_id,
+            event_type: event_type.as_str().to_string(),
+            payload: payload.to_string(),
+            created_at,
+        }
+    }
+}
+
+// events are kept thin, consumers go to the api for anything more
+pub fn transaction_data(transaction: &Transaction) -> serde_json::Value {
+    let (account_number, bsb) = transaction.customer_account();
+    json!({
+        "transactionId": transaction.id,
+        "customerId": transaction.customer_id,
+        "accountNumber": account_number,
+        "bsb": bsb,
+        "amountCents": transaction.balance_delta_cents(),
+        "status": status_str(transaction.transaction_status),
+    })
+}
+
+// closed accounts are deleted, so this is all anyone hears about one afterwards
+pub fn account_data(account: &Account) -> serde_json::Value {
+    json!({
+        "accountId": account.id,
+        "customerId": account.customer_id,
+        "accountNumber": account.account_number,
+        "bsb": account.bsb,
+    })
+}
+
+fn status_str(status: TransactionStatus) -> &'static str {
+    match status {
+        TransactionStatus::Pending => "pending",
+        TransactionStatus::Success => "success",
+        TransactionStatus::Error => "error",
+        TransactionStatus::PartiallyReversed => "partiallyReversed",
+        TransactionStatus::Reversed => "reversed",
+        TransactionStatus::Held => "held",
+        TransactionStatus::AwaitingSignature => "awaitingSignature",
+        TransactionStatus::PendingApproval => "pendingApproval",
+    }
+}
+
+// unpublished events whose next try is due, oldest first. finding these leases them to the caller
+// for RELAY_LEASE_SECONDS
+#[derive(Clone, Debug, PartialEq)]
+pub struct FindDueOutboxEventQuery {
+    pub now: chrono::NaiveDateTime,
+    pub limit: i64,
+}
+
+// one go at publishing an event, None if it was taken
+#[derive(Clone, Debug, PartialEq)]
+pub struct OutboxPublishAttempt {
+    pub error: Option<String>,
+    pub attempted_at: chrono::NaiveDateTime,
+}
+
+// how long after the given failed try the next one goes
+pub fn retry_delay(attempts: i32) -> chrono::Duration {
+    // 2^10 is already past the cap
+    let doublings = (attempts - 1).clamp(0, 10) as u32;
+    chrono::Duration::seconds(
+        (FIRST_RETRY_DELAY_SECONDS * 2i64.pow(doublings)).min(MAX_RETRY_DELAY_SECONDS),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::{
+        retry_delay, EventType, NewOutboxEvent, FIRST_RETRY_DELAY_SECONDS, MAX_RETRY_DELAY_SECONDS,
+    };
+
+    #[test]
+    fn event_payload_carries_its_id() {
+        let created_at = NaiveDate::from_ymd_opt(2023, 9, 21)
+            .unwrap()
+            .and_hms_opt(6, 25, 4)
+            .unwrap();
+
+        let event = NewOutboxEvent::new(
+            EventType::AccountClosed,
+            serde_json::json!({ "accountId": 2 }),
+            created_at,
+        );
+        let payload: serde_json::Value = serde_json::from_str(&event.payload).unwrap();
+
+        assert_eq!(36, event.event_id.len());
+        assert_eq!("account.closed", event.event_type);
+        assert_eq!(
+            serde_json::json!({
+                "id": event.event_id,
+                "type": "account.closed",
+                "createdAt": "2023-09-21T06:25:04.000000Z",
+                "data": { "accountId": 2 },
+            }),
+            payload
+        );
+        assert_ne!(
+            event.event_id,
+            NewOutboxEvent::new(EventType::AccountClosed, serde_json::json!({}), created_at)
+                .event_id
+        );
+    }
+
+    #[test]
+    fn retries_back_off_up_to_the_cap() {
+        assert_eq!(FIRST_RETRY_DELAY_SECONDS, retry_delay(1).num_seconds());
+        assert_eq!(FIRST_RETRY_DELAY_SECONDS * 2, retry_delay(2).num_seconds());
+        assert_eq!(MAX_RETRY_DELAY_SECONDS, retry_delay(12).num_seconds());
+        assert_eq!(MAX_RETRY_DELAY_SECONDS, retry_delay(1000).num_seconds());
+    }
+
+    #[test]
+    fn event_types_round_trip() {
+        for event_type in EventType::ALL {
+            assert_eq!(Some(event_type), EventType::parse(event_type.as_str()));
+        }
+        assert_eq!(None, EventType::parse("account.updated"));
+    }
+}
@@ -0,0 +1,110 @@
+use chrono::NaiveDate;
+use diesel::{Insertable, Queryable, Selectable};
+
+use super::schema::overdraft_charges;
+
+// bank wide for now, the limit is the only per account term
+pub const OVERDRAFT_INTEREST_RATE_BPS: i64 = 1_795;
+pub const DISHONOUR_FEE_CENTS: i64 = 1_000;
+// biggest overdraft staff can arrange without it going to credit
+pub const MAX_OVERDRAFT_LIMIT_CENTS: i64 = 5_000_000;
+// what the charge shows as on the customer's statement
+pub const INTEREST_DESCRIPTION: &str = "Overdraft interest";
+pub const DISHONOUR_FEE_DESCRIPTION: &str = "Dishonour fee";
+
+const DAYS_PER_YEAR: i128 = 365;
+
+#[derive(diesel_derive_enum::DbEnum, Copy, Clone, Debug, PartialEq)]
+#[ExistingTypePath = "crate::models::schema::sql_types::OverdraftChargeKind"]
+pub enum OverdraftChargeKind {
+    Interest,
+    DishonourFee,
+}
+
+impl OverdraftChargeKind {
+    pub fn description(&self) -> &'static str {
+        match self {
+            OverdraftChargeKind::Interest => INTEREST_DESCRIPTION,
+            OverdraftChargeKind::DishonourFee => DISHONOUR_FEE_DESCRIPTION,
+        }
+    }
+}
+
+// a day's debit interest on an end of day balance, rounded to the nearest cent. nothing if the
+// account wasn't overdrawn
+pub fn daily_interest_cents(balance_cents: i64) -> i64 {
+    if balance_cents >= 0 {
+        return 0;
+    }
+
+    let divisor = 10_000 * DAYS_PER_YEAR;
+    let owed = -(balance_cents as i128) * OVERDRAFT_INTEREST_RATE_BPS as i128;
+    ((owed + divisor / 2) / divisor) as i64
+}
+
+#[derive(Clone, Queryable, Selectable)]
+#[cfg_attr(test, derive(Debug, PartialEq))]
+#[diesel(table_name = overdraft_charges)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct OverdraftCharge {
+    pub id: i32,
+    pub account_id: i32,
+    pub customer_id: i32,
+    pub kind: OverdraftChargeKind,
+    pub charge_date: NaiveDate,
+    pub amount_cents: i64,
+    pub transaction_id: i32,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+#[derive(Insertable, Debug, PartialEq, Clone)]
+#[diesel(table_name = overdraft_charges)]
+pub struct NewOverdraftCharge {
+    pub account_id: i32,
+    pub customer_id: i32,
+    pub kind: OverdraftChargeKind,
+    pub charge_date: NaiveDate,
+    pub amount_cents: i64,
+    pub transaction_id: i32,
+}
+
+// newest first
+#[derive(Clone)]
+#[cfg_attr(test, derive(Debug, PartialEq))]
+pub struct FindOverdraftChargeQuery {
+    pub customer_id: i32,
+    pub account_id: Option<i32>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct OverdraftLimitUpdate {
+    // 0 takes the overdraft away, anything already owed stays owed
+    pub limit_cents: i64,
+}
+
+// interest on every account that ended the day overdrawn, safe to run again for the same day
+#[derive(Clone, Debug, PartialEq)]
+pub struct OverdraftInterestRun {
+    pub day: NaiveDate,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::daily_interest_cents;
+
+    #[test]
+    fn no_interest_in_credit() {
+        assert_eq!(0, daily_interest_cents(0));
+        assert_eq!(0, daily_interest_cents(150000));
+    }
+
+    #[test]
+    fn daily_interest_rounds_to_nearest_cent() {
+        // $1,000 at 17.95% is 49.18c a day
+        assert_eq!(49, daily_interest_cents(-100000));
+        // $2,000 is 98.36c
+        assert_eq!(98, daily_interest_cents(-200000));
+        // a few dollars overdrawn isn't worth a cent
+        assert_eq!(0, daily_interest_cents(-500));
+    }
+}
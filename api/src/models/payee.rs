@@ -0,0 +1,55 @@
+use diesel::{Insertable, Queryable, Selectable};
+
+use super::schema::payees;
+
+#[derive(Clone, Queryable, Selectable)]
+#[cfg_attr(test, derive(Debug, PartialEq))]
+#[diesel(table_name = payees)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct Payee {
+    pub id: i32,
+    pub customer_id: i32,
+    pub nickname: String,
+    pub account_number: String,
+    pub bsb: String,
+    pub default_reference: Option<String>,
+    pub created_at: chrono::NaiveDateTime,
+    pub first_used_at: Option<chrono::NaiveDateTime>,
+    pub last_used_at: Option<chrono::NaiveDateTime>,
+}
+
+impl Payee {
+    // saved but never actually paid
+    pub fn is_new(&self) -> bool {
+        self.first_used_at.is_none()
+    }
+}
+
+#[derive(Insertable, Debug, PartialEq, Clone)]
+#[diesel(table_name = payees)]
+pub struct NewPayee {
+    pub customer_id: i32,
+    pub nickname: String,
+    pub account_number: String,
+    pub bsb: String,
+    pub default_reference: Option<String>,
+}
+
+// where the money goes can't change, delete and re-add the payee for that
+#[derive(Clone, Debug, PartialEq)]
+pub struct PayeeUpdate {
+    pub nickname: Option<String>,
+    // Some(None) clears it
+    pub default_reference: Option<Option<String>>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct PayeeUsed {
+    pub used_at: chrono::NaiveDateTime,
+}
+
+#[derive(Clone)]
+#[cfg_attr(test, derive(Debug, PartialEq))]
+pub struct FindPayeeQuery {
+    pub customer_id: i32,
+}
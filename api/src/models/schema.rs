@@ -9,6 +9,70 @@ pub mod sql_types {
     #[diesel(postgres_type(name = "account_type"))]
     pub struct AccountType;
 
+    #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "actor_type"))]
+    pub struct ActorType;
+
+    #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "approval_status"))]
+    pub struct ApprovalStatus;
+
+    #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "automation_rule_kind"))]
+    pub struct AutomationRuleKind;
+
+    #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "dispute_status"))]
+    pub struct DisputeStatus;
+
+    #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "fee_kind"))]
+    pub struct FeeKind;
+
+    #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "fraud_review_status"))]
+    pub struct FraudReviewStatus;
+
+    #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "holder_role"))]
+    pub struct HolderRole;
+
+    #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "limit_request_status"))]
+    pub struct LimitRequestStatus;
+
+    #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "loan_application_status"))]
+    pub struct LoanApplicationStatus;
+
+    #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "loan_repayment_type"))]
+    pub struct LoanRepaymentType;
+
+    #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "oauth_token_kind"))]
+    pub struct OauthTokenKind;
+
+    #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "otp_method"))]
+    pub struct OtpMethod;
+
+    #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "overdraft_charge_kind"))]
+    pub struct OverdraftChargeKind;
+
+    #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "signing_rule"))]
+    pub struct SigningRule;
+
+    #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "spending_category"))]
+    pub struct SpendingCategory;
+
+    #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "step_up_action"))]
+    pub struct StepUpAction;
+
     #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
     #[diesel(postgres_type(name = "transaction_status"))]
     pub struct TransactionStatus;
@@ -16,12 +80,59 @@ pub mod sql_types {
     #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
     #[diesel(postgres_type(name = "transaction_type"))]
     pub struct TransactionType;
+
+    #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "transfer_limit_kind"))]
+    pub struct TransferLimitKind;
+
+    #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "webhook_delivery_status"))]
+    pub struct WebhookDeliveryStatus;
+}
+
+diesel::table! {
+    account_balance_snapshots (account_id, snapshot_date) {
+        account_id -> Int4,
+        snapshot_date -> Date,
+        balance_cents -> Int8,
+        available_balance_cents -> Int8,
+        updated_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    account_buckets (id) {
+        id -> Int4,
+        account_id -> Int4,
+        #[max_length = 40]
+        name -> Varchar,
+        balance_cents -> Int8,
+        goal_cents -> Nullable<Int8>,
+        target_date -> Nullable<Date>,
+        is_default -> Bool,
+        created_at -> Timestamptz,
+        updated_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::HolderRole;
+
+    account_holders (id) {
+        id -> Int4,
+        account_id -> Int4,
+        customer_id -> Int4,
+        holder_role -> HolderRole,
+        created_at -> Timestamptz,
+    }
 }
 
 diesel::table! {
     use diesel::sql_types::*;
     use super::sql_types::AccountStatus;
     use super::sql_types::AccountType;
+    use super::sql_types::SigningRule;
 
     accounts (id) {
         id -> Int4,
@@ -37,6 +148,472 @@ diesel::table! {
         date_opened -> Timestamptz,
         balance_cents -> Int8,
         available_balance_cents -> Int8,
+        overdraft_limit_cents -> Int8,
+        signing_rule -> SigningRule,
+    }
+}
+
+diesel::table! {
+    approval_policies (account_id) {
+        account_id -> Int4,
+        threshold_cents -> Int8,
+        expiry_hours -> Int4,
+        updated_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::ActorType;
+
+    audit_log (id) {
+        id -> Int4,
+        actor_type -> ActorType,
+        actor_id -> Int4,
+        #[max_length = 64]
+        action -> Varchar,
+        #[max_length = 32]
+        entity_type -> Varchar,
+        entity_id -> Nullable<Int4>,
+        before_snapshot -> Nullable<Text>,
+        after_snapshot -> Nullable<Text>,
+        #[max_length = 8]
+        request_method -> Varchar,
+        request_path -> Text,
+        #[max_length = 64]
+        request_ip -> Nullable<Varchar>,
+        user_agent -> Nullable<Text>,
+        created_at -> Timestamptz,
+        #[max_length = 64]
+        prev_hash -> Varchar,
+        #[max_length = 64]
+        hash -> Varchar,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::AutomationRuleKind;
+
+    automation_rules (id) {
+        id -> Int4,
+        customer_id -> Int4,
+        kind -> AutomationRuleKind,
+        from_account_id -> Int4,
+        to_account_id -> Int4,
+        round_to_cents -> Nullable<Int8>,
+        threshold_cents -> Nullable<Int8>,
+        enabled -> Bool,
+        created_at -> Timestamptz,
+        updated_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    automation_runs (id) {
+        id -> Int4,
+        rule_id -> Int4,
+        customer_id -> Int4,
+        trigger_transaction_id -> Nullable<Int4>,
+        run_date -> Nullable<Date>,
+        transaction_id -> Int4,
+        amount_cents -> Int8,
+        created_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    budget_alerts (id) {
+        id -> Int4,
+        budget_id -> Int4,
+        customer_id -> Int4,
+        period_start -> Date,
+        threshold_percent -> Int4,
+        spent_cents -> Int8,
+        budget_cents -> Int8,
+        transaction_id -> Nullable<Int4>,
+        created_at -> Timestamptz,
+        delivered_at -> Nullable<Timestamptz>,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::SpendingCategory;
+
+    budgets (id) {
+        id -> Int4,
+        customer_id -> Int4,
+        #[max_length = 40]
+        name -> Varchar,
+        category -> Nullable<SpendingCategory>,
+        #[max_length = 9]
+        account_number -> Nullable<Varchar>,
+        amount_cents -> Int8,
+        alert_thresholds -> Array<Int4>,
+        created_at -> Timestamptz,
+        updated_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::SpendingCategory;
+
+    category_overrides (id) {
+        id -> Int4,
+        customer_id -> Int4,
+        #[max_length = 9]
+        account_number -> Varchar,
+        #[max_length = 6]
+        bsb -> Varchar,
+        category -> SpendingCategory,
+        created_at -> Timestamptz,
+        updated_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    customer_transfer_limits (customer_id) {
+        customer_id -> Int4,
+        per_transaction_cents -> Nullable<Int8>,
+        daily_cents -> Nullable<Int8>,
+        per_payee_daily_cents -> Nullable<Int8>,
+        daily_internal_cents -> Nullable<Int8>,
+        daily_external_cents -> Nullable<Int8>,
+        updated_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::DisputeStatus;
+    use super::sql_types::ActorType;
+
+    dispute_events (id) {
+        id -> Int4,
+        dispute_id -> Int4,
+        dispute_status -> DisputeStatus,
+        #[max_length = 500]
+        note -> Nullable<Varchar>,
+        actor_type -> ActorType,
+        actor_id -> Int4,
+        transaction_id -> Nullable<Int4>,
+        created_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::DisputeStatus;
+
+    disputes (id) {
+        id -> Int4,
+        customer_id -> Int4,
+        transaction_id -> Int4,
+        amount_cents -> Int8,
+        #[max_length = 500]
+        reason -> Varchar,
+        dispute_status -> DisputeStatus,
+        credited_cents -> Int8,
+        date_opened -> Timestamptz,
+        date_resolved -> Nullable<Timestamptz>,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::FeeKind;
+
+    fee_charges (id) {
+        id -> Int4,
+        account_id -> Int4,
+        customer_id -> Int4,
+        kind -> FeeKind,
+        period_start -> Nullable<Date>,
+        trigger_transaction_id -> Nullable<Int4>,
+        amount_cents -> Int8,
+        transaction_id -> Int4,
+        created_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::FeeKind;
+    use super::sql_types::AccountType;
+
+    fee_schedule (id) {
+        id -> Int4,
+        kind -> FeeKind,
+        account_type -> AccountType,
+        amount_cents -> Int8,
+        waiver_deposit_cents -> Nullable<Int8>,
+        updated_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::FraudReviewStatus;
+
+    fraud_reviews (id) {
+        id -> Int4,
+        transaction_id -> Int4,
+        customer_id -> Int4,
+        reasons -> Text,
+        review_status -> FraudReviewStatus,
+        reviewer_id -> Nullable<Int4>,
+        #[max_length = 500]
+        review_note -> Nullable<Varchar>,
+        created_at -> Timestamptz,
+        reviewed_at -> Nullable<Timestamptz>,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::LoanRepaymentType;
+    use super::sql_types::LoanApplicationStatus;
+
+    loan_applications (id) {
+        id -> Int4,
+        customer_id -> Int4,
+        principal_cents -> Int8,
+        term_months -> Int4,
+        repayment_type -> LoanRepaymentType,
+        disbursement_account_id -> Int4,
+        #[max_length = 40]
+        account_name -> Nullable<Varchar>,
+        application_status -> LoanApplicationStatus,
+        interest_rate_bps -> Nullable<Int4>,
+        loan_id -> Nullable<Int4>,
+        reviewer_id -> Nullable<Int4>,
+        #[max_length = 500]
+        review_note -> Nullable<Varchar>,
+        created_at -> Timestamptz,
+        reviewed_at -> Nullable<Timestamptz>,
+    }
+}
+
+diesel::table! {
+    loan_interest_charges (id) {
+        id -> Int4,
+        loan_id -> Int4,
+        customer_id -> Int4,
+        charge_date -> Date,
+        owed_cents -> Int8,
+        offset_cents -> Int8,
+        amount_cents -> Int8,
+        transaction_id -> Int4,
+        created_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    loan_offsets (id) {
+        id -> Int4,
+        loan_id -> Int4,
+        account_id -> Int4,
+        customer_id -> Int4,
+        created_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    loan_schedule_entries (id) {
+        id -> Int4,
+        loan_id -> Int4,
+        installment -> Int4,
+        due_date -> Date,
+        amount_cents -> Int8,
+        principal_cents -> Int8,
+        interest_cents -> Int8,
+        paid_cents -> Int8,
+        dishonoured_at -> Nullable<Timestamptz>,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::LoanRepaymentType;
+
+    loans (id) {
+        id -> Int4,
+        account_id -> Int4,
+        customer_id -> Int4,
+        interest_rate_bps -> Int4,
+        created_at -> Timestamptz,
+        principal_cents -> Nullable<Int8>,
+        term_months -> Nullable<Int4>,
+        repayment_type -> Nullable<LoanRepaymentType>,
+        first_repayment_date -> Nullable<Date>,
+        repayment_account_id -> Nullable<Int4>,
+    }
+}
+
+diesel::table! {
+    oauth_authorisation_codes (id) {
+        id -> Int4,
+        grant_id -> Int4,
+        #[max_length = 64]
+        code_hash -> Varchar,
+        #[max_length = 500]
+        redirect_uri -> Varchar,
+        #[max_length = 200]
+        scope -> Varchar,
+        #[max_length = 128]
+        code_challenge -> Varchar,
+        expires_at -> Timestamptz,
+        used_at -> Nullable<Timestamptz>,
+        created_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    oauth_clients (id) {
+        id -> Int4,
+        #[max_length = 32]
+        client_id -> Varchar,
+        #[max_length = 64]
+        client_secret_hash -> Nullable<Varchar>,
+        #[max_length = 100]
+        client_name -> Varchar,
+        #[max_length = 500]
+        redirect_uri -> Varchar,
+        #[max_length = 200]
+        scope -> Varchar,
+        created_by -> Int4,
+        created_at -> Timestamptz,
+        revoked_at -> Nullable<Timestamptz>,
+    }
+}
+
+diesel::table! {
+    oauth_grant_accounts (grant_id, account_id) {
+        grant_id -> Int4,
+        account_id -> Int4,
+    }
+}
+
+diesel::table! {
+    oauth_grants (id) {
+        id -> Int4,
+        client_id -> Int4,
+        customer_id -> Int4,
+        #[max_length = 200]
+        scope -> Varchar,
+        created_at -> Timestamptz,
+        updated_at -> Timestamptz,
+        revoked_at -> Nullable<Timestamptz>,
+        expires_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::OauthTokenKind;
+
+    oauth_tokens (id) {
+        id -> Int4,
+        grant_id -> Int4,
+        customer_id -> Int4,
+        token_kind -> OauthTokenKind,
+        #[max_length = 64]
+        token_hash -> Varchar,
+        #[max_length = 200]
+        scope -> Varchar,
+        expires_at -> Timestamptz,
+        revoked_at -> Nullable<Timestamptz>,
+        created_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    outbox_events (id) {
+        id -> Int4,
+        #[max_length = 36]
+        event_id -> Varchar,
+        #[max_length = 50]
+        event_type -> Varchar,
+        payload -> Text,
+        attempts -> Int4,
+        #[max_length = 200]
+        last_error -> Nullable<Varchar>,
+        next_attempt_at -> Timestamptz,
+        published_at -> Nullable<Timestamptz>,
+        created_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::OverdraftChargeKind;
+
+    overdraft_charges (id) {
+        id -> Int4,
+        account_id -> Int4,
+        customer_id -> Int4,
+        kind -> OverdraftChargeKind,
+        charge_date -> Date,
+        amount_cents -> Int8,
+        transaction_id -> Int4,
+        created_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    payees (id) {
+        id -> Int4,
+        customer_id -> Int4,
+        #[max_length = 40]
+        nickname -> Varchar,
+        #[max_length = 9]
+        account_number -> Varchar,
+        #[max_length = 6]
+        bsb -> Varchar,
+        #[max_length = 18]
+        default_reference -> Nullable<Varchar>,
+        created_at -> Timestamptz,
+        first_used_at -> Nullable<Timestamptz>,
+        last_used_at -> Nullable<Timestamptz>,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::StepUpAction;
+    use super::sql_types::OtpMethod;
+
+    step_up_challenges (id) {
+        id -> Int4,
+        customer_id -> Int4,
+        step_up_action -> StepUpAction,
+        entity_id -> Int4,
+        otp_method -> OtpMethod,
+        #[max_length = 64]
+        code_hash -> Nullable<Varchar>,
+        attempts -> Int4,
+        expires_at -> Timestamptz,
+        verified_at -> Nullable<Timestamptz>,
+        redeemed_at -> Nullable<Timestamptz>,
+        created_at -> Timestamptz,
+        #[max_length = 9]
+        to_number -> Nullable<Varchar>,
+        #[max_length = 6]
+        to_bsb -> Nullable<Varchar>,
+        amount_cents -> Nullable<Int8>,
+    }
+}
+
+diesel::table! {
+    totp_enrolments (customer_id) {
+        customer_id -> Int4,
+        #[max_length = 32]
+        secret -> Varchar,
+        confirmed_at -> Nullable<Timestamptz>,
+        last_used_step -> Nullable<Int8>,
+        created_at -> Timestamptz,
     }
 }
 
@@ -67,10 +644,215 @@ diesel::table! {
         date_start -> Timestamptz,
         date_end -> Nullable<Timestamptz>,
         transaction_status -> TransactionStatus,
+        reversal_of -> Nullable<Int4>,
+        reversed_amount_cents -> Int8,
+        #[max_length = 140]
+        reversal_reason -> Nullable<Varchar>,
+        #[max_length = 280]
+        description -> Nullable<Varchar>,
+        #[max_length = 18]
+        payer_reference -> Nullable<Varchar>,
+        #[max_length = 18]
+        payee_reference -> Nullable<Varchar>,
+        counterpart_of -> Nullable<Int4>,
+        running_balance_cents -> Nullable<Int8>,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::ApprovalStatus;
+    use super::sql_types::ActorType;
+
+    transfer_approval_events (id) {
+        id -> Int4,
+        approval_id -> Int4,
+        approval_status -> ApprovalStatus,
+        #[max_length = 500]
+        note -> Nullable<Varchar>,
+        actor_type -> ActorType,
+        actor_id -> Int4,
+        created_at -> Timestamptz,
     }
 }
 
+diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::ApprovalStatus;
+
+    transfer_approvals (id) {
+        id -> Int4,
+        transaction_id -> Int4,
+        account_id -> Int4,
+        requested_by -> Int4,
+        threshold_cents -> Int8,
+        approval_status -> ApprovalStatus,
+        expires_at -> Timestamptz,
+        decided_by -> Nullable<Int4>,
+        decided_at -> Nullable<Timestamptz>,
+        created_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::TransferLimitKind;
+    use super::sql_types::LimitRequestStatus;
+
+    transfer_limit_requests (id) {
+        id -> Int4,
+        customer_id -> Int4,
+        limit_kind -> TransferLimitKind,
+        requested_cents -> Int8,
+        #[max_length = 500]
+        reason -> Varchar,
+        request_status -> LimitRequestStatus,
+        reviewer_id -> Nullable<Int4>,
+        #[max_length = 500]
+        review_note -> Nullable<Varchar>,
+        created_at -> Timestamptz,
+        reviewed_at -> Nullable<Timestamptz>,
+    }
+}
+
+diesel::table! {
+    transfer_signatures (id) {
+        id -> Int4,
+        transaction_id -> Int4,
+        customer_id -> Int4,
+        signed_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::WebhookDeliveryStatus;
+
+    webhook_deliveries (id) {
+        id -> Int4,
+        subscription_id -> Int4,
+        #[max_length = 36]
+        event_id -> Varchar,
+        delivery_status -> WebhookDeliveryStatus,
+        attempts -> Int4,
+        next_attempt_at -> Nullable<Timestamptz>,
+        delivered_at -> Nullable<Timestamptz>,
+        created_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    webhook_delivery_attempts (id) {
+        id -> Int4,
+        delivery_id -> Int4,
+        response_status -> Nullable<Int4>,
+        #[max_length = 200]
+        error -> Nullable<Varchar>,
+        attempted_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    webhook_events (id) {
+        #[max_length = 36]
+        id -> Varchar,
+        #[max_length = 50]
+        event_type -> Varchar,
+        payload -> Text,
+        created_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    webhook_subscriptions (id) {
+        id -> Int4,
+        #[max_length = 500]
+        url -> Varchar,
+        #[max_length = 200]
+        event_types -> Varchar,
+        #[max_length = 64]
+        secret -> Varchar,
+        created_by -> Int4,
+        created_at -> Timestamptz,
+        revoked_at -> Nullable<Timestamptz>,
+    }
+}
+
+diesel::joinable!(account_balance_snapshots -> accounts (account_id));
+diesel::joinable!(account_buckets -> accounts (account_id));
+diesel::joinable!(account_holders -> accounts (account_id));
+diesel::joinable!(approval_policies -> accounts (account_id));
+diesel::joinable!(automation_runs -> automation_rules (rule_id));
+diesel::joinable!(budget_alerts -> budgets (budget_id));
+diesel::joinable!(budget_alerts -> transactions (transaction_id));
+diesel::joinable!(dispute_events -> disputes (dispute_id));
+diesel::joinable!(dispute_events -> transactions (transaction_id));
+diesel::joinable!(disputes -> transactions (transaction_id));
+diesel::joinable!(fee_charges -> accounts (account_id));
+diesel::joinable!(fraud_reviews -> transactions (transaction_id));
+diesel::joinable!(loan_applications -> accounts (disbursement_account_id));
+diesel::joinable!(loan_applications -> loans (loan_id));
+diesel::joinable!(loan_interest_charges -> loans (loan_id));
+diesel::joinable!(loan_interest_charges -> transactions (transaction_id));
+diesel::joinable!(loan_offsets -> accounts (account_id));
+diesel::joinable!(loan_offsets -> loans (loan_id));
+diesel::joinable!(loan_schedule_entries -> loans (loan_id));
+diesel::joinable!(loans -> accounts (account_id));
+diesel::joinable!(oauth_authorisation_codes -> oauth_grants (grant_id));
+diesel::joinable!(oauth_grant_accounts -> accounts (account_id));
+diesel::joinable!(oauth_grant_accounts -> oauth_grants (grant_id));
+diesel::joinable!(oauth_grants -> oauth_clients (client_id));
+diesel::joinable!(oauth_tokens -> oauth_grants (grant_id));
+diesel::joinable!(overdraft_charges -> accounts (account_id));
+diesel::joinable!(overdraft_charges -> transactions (transaction_id));
+diesel::joinable!(transfer_approval_events -> transfer_approvals (approval_id));
+diesel::joinable!(transfer_approvals -> accounts (account_id));
+diesel::joinable!(transfer_approvals -> transactions (transaction_id));
+diesel::joinable!(transfer_signatures -> transactions (transaction_id));
+diesel::joinable!(webhook_deliveries -> webhook_events (event_id));
+diesel::joinable!(webhook_deliveries -> webhook_subscriptions (subscription_id));
+diesel::joinable!(webhook_delivery_attempts -> webhook_deliveries (delivery_id));
+
 diesel::allow_tables_to_appear_in_same_query!(
+    account_balance_snapshots,
+    account_buckets,
+    account_holders,
     accounts,
+    approval_policies,
+    audit_log,
+    automation_rules,
+    automation_runs,
+    budget_alerts,
+    budgets,
+    category_overrides,
+    customer_transfer_limits,
+    dispute_events,
+    disputes,
+    fee_charges,
+    fee_schedule,
+    fraud_reviews,
+    loan_applications,
+    loan_interest_charges,
+    loan_offsets,
+    loan_schedule_entries,
+    loans,
+    oauth_authorisation_codes,
+    oauth_clients,
+    oauth_grant_accounts,
+    oauth_grants,
+    oauth_tokens,
+    outbox_events,
+    overdraft_charges,
+    payees,
+    step_up_challenges,
+    totp_enrolments,
     transactions,
+    transfer_approval_events,
+    transfer_approvals,
+    transfer_limit_requests,
+    transfer_signatures,
+    webhook_deliveries,
+    webhook_delivery_attempts,
+    webhook_events,
+    webhook_subscriptions,
 );
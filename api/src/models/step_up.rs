@@ -0,0 +1,270 @@
+use diesel::{Insertable, Queryable, Selectable};
+
+use super::schema::{step_up_challenges, totp_enrolments};
+
+pub const CHALLENGE_TTL_MINUTES: i64 = 5;
+// wrong codes before the challenge is dead and the action has to be tried again
+pub const MAX_ATTEMPTS: i32 = 5;
+
+#[derive(diesel_derive_enum::DbEnum, Copy, Clone, Debug, PartialEq)]
+#[ExistingTypePath = "crate::models::schema::sql_types::StepUpAction"]
+pub enum StepUpAction {
+    // transfer to a saved payee that's never been paid
+    NewPayeeTransfer,
+    // transfer to account details that have never been paid from any of the customer's accounts
+    NewDestinationTransfer,
+    CloseAccount,
+    RemoveTotp,
+}
+
+#[derive(diesel_derive_enum::DbEnum, Copy, Clone, Debug, PartialEq)]
+#[ExistingTypePath = "crate::models::schema::sql_types::OtpMethod"]
+pub enum OtpMethod {
+    // from the customer's authenticator app, if they've confirmed one
+    Totp,
+    // sent to them by the CodeSender
+    Delivered,
+}
+
+#[derive(Clone, Queryable, Selectable)]
+#[cfg_attr(test, derive(Debug, PartialEq))]
+#[diesel(table_name = totp_enrolments)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct TotpEnrolment {
+    pub customer_id: i32,
+    // base32, as authenticator apps take it
+    pub secret: String,
+    pub confirmed_at: Option<chrono::NaiveDateTime>,
+    pub last_used_step: Option<i64>,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+impl TotpEnrolment {
+    pub fn is_confirmed(&self) -> bool {
+        self.confirmed_at.is_some()
+    }
+}
+
+#[derive(Insertable, Debug, PartialEq, Clone)]
+#[diesel(table_name = totp_enrolments)]
+pub struct NewTotpEnrolment {
+    pub customer_id: i32,
+    pub secret: String,
+}
+
+// the customer entered a code from their app, `step` is the one it matched
+#[derive(Clone, Debug, PartialEq)]
+pub struct TotpConfirmation {
+    pub step: i64,
+}
+
+#[derive(Clone, Queryable, Selectable)]
+#[cfg_attr(test, derive(Debug, PartialEq))]
+#[diesel(table_name = step_up_challenges)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct StepUpChallenge {
+    pub id: i32,
+    pub customer_id: i32,
+    pub step_up_action: StepUpAction,
+    pub entity_id: i32,
+    pub otp_method: OtpMethod,
+    pub code_hash: Option<String>,
+    pub attempts: i32,
+    pub expires_at: chrono::NaiveDateTime,
+    pub verified_at: Option<chrono::NaiveDateTime>,
+    pub redeemed_at: Option<chrono::NaiveDateTime>,
+    pub created_at: chrono::NaiveDateTime,
+    // what a transfer's challenge was issued for, nothing for other actions
+    pub to_number: Option<String>,
+    pub to_bsb: Option<String>,
+    pub amount_cents: Option<i64>,
+}
+
+impl StepUpChallenge {
+    // still waiting on a code
+    pub fn is_open(&self, now: chrono::NaiveDateTime) -> bool {
+        self.verified_at.is_none()
+            && self.redeemed_at.is_none()
+            && self.attempts < MAX_ATTEMPTS
+            && self.expires_at > now
+    }
+
+    // verified, unused, unexpired and issued for exactly this, the same as redeeming it checks
+    pub fn is_redeemable_for(
+        &self,
+        redemption: &StepUpRedemption,
+        now: chrono::NaiveDateTime,
+    ) -> bool {
+        self.verified_at.is_some()
+            && self.redeemed_at.is_none()
+            && self.expires_at > now
+            && self.customer_id == redemption.customer_id
+            && self.step_up_action == redemption.step_up_action
+            && self.entity_id == redemption.entity_id
+            && self.to_number == redemption.to_number
+            && self.to_bsb == redemption.to_bsb
+            && self.amount_cents == redemption.amount_cents
+    }
+}
+
+#[derive(Insertable, Debug, PartialEq, Clone)]
+#[diesel(table_name = step_up_challenges)]
+pub struct NewStepUpChallenge {
+    pub customer_id: i32,
+    pub step_up_action: StepUpAction,
+    pub entity_id: i32,
+    pub otp_method: OtpMethod,
+    pub code_hash: Option<String>,
+    pub expires_at: chrono::NaiveDateTime,
+    pub to_number: Option<String>,
+    pub to_bsb: Option<String>,
+    pub amount_cents: Option<i64>,
+}
+
+// a code was entered against the challenge. a totp code that matched carries its step, so it
+// can't be used again
+#[derive(Clone, Debug, PartialEq)]
+pub struct StepUpAttempt {
+    pub passed: bool,
+    pub totp_step: Option<i64>,
+}
+
+// the action being retried with a verified challenge, which has to be for that same action. a
+// transfer's is for the same destination and amount too
+#[derive(Clone, Debug, PartialEq)]
+pub struct StepUpRedemption {
+    pub customer_id: i32,
+    pub step_up_action: StepUpAction,
+    pub entity_id: i32,
+    pub to_number: Option<String>,
+    pub to_bsb: Option<String>,
+    pub amount_cents: Option<i64>,
+}
+
+impl StepUpRedemption {
+    // anything but a transfer
+    pub fn action(customer_id: i32, step_up_action: StepUpAction, entity_id: i32) -> Self {
+        StepUpRedemption {
+            customer_id,
+            step_up_action,
+            entity_id,
+            to_number: None,
+            to_bsb: None,
+            amount_cents: None,
+        }
+    }
+}
+
+// a challenge the request carries, used up in the same transaction as the change it's for so a
+// change that doesn't go through doesn't cost the customer their code
+#[derive(Clone, Debug, PartialEq)]
+pub struct StepUpProof {
+    pub challenge_id: i32,
+    pub redemption: StepUpRedemption,
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, NaiveDate};
+
+    use super::{OtpMethod, StepUpAction, StepUpChallenge, StepUpRedemption, MAX_ATTEMPTS};
+
+    #[test]
+    fn open_until_verified_used_up_or_expired() {
+        let now = NaiveDate::from_ymd_opt(2023, 9, 15)
+            .unwrap()
+            .and_hms_opt(9, 0, 0)
+            .unwrap();
+        let challenge = StepUpChallenge {
+            id: 1,
+            customer_id: 5,
+            step_up_action: StepUpAction::CloseAccount,
+            entity_id: 52,
+            otp_method: OtpMethod::Delivered,
+            code_hash: None,
+            attempts: 0,
+            expires_at: now + Duration::minutes(5),
+            verified_at: None,
+            redeemed_at: None,
+            created_at: now,
+            to_number: None,
+            to_bsb: None,
+            amount_cents: None,
+        };
+
+        assert!(challenge.is_open(now));
+        assert!(!challenge.is_open(now + Duration::minutes(5)));
+        assert!(!StepUpChallenge {
+            attempts: MAX_ATTEMPTS,
+            ..challenge.clone()
+        }
+        .is_open(now));
+        assert!(!StepUpChallenge {
+            verified_at: Some(now),
+            ..challenge
+        }
+        .is_open(now));
+    }
+
+    #[test]
+    fn transfer_redeemable_only_for_its_destination_and_amount() {
+        let now = NaiveDate::from_ymd_opt(2023, 9, 15)
+            .unwrap()
+            .and_hms_opt(9, 0, 0)
+            .unwrap();
+        let challenge = StepUpChallenge {
+            id: 1,
+            customer_id: 5,
+            step_up_action: StepUpAction::NewDestinationTransfer,
+            entity_id: 52,
+            otp_method: OtpMethod::Delivered,
+            code_hash: None,
+            attempts: 1,
+            expires_at: now + Duration::minutes(5),
+            verified_at: Some(now),
+            redeemed_at: None,
+            created_at: now,
+            to_number: Some("987654321".to_string()),
+            to_bsb: Some("123456".to_string()),
+            amount_cents: Some(5_000),
+        };
+        let redemption = StepUpRedemption {
+            customer_id: 5,
+            step_up_action: StepUpAction::NewDestinationTransfer,
+            entity_id: 52,
+            to_number: Some("987654321".to_string()),
+            to_bsb: Some("123456".to_string()),
+            amount_cents: Some(5_000),
+        };
+
+        assert!(challenge.is_redeemable_for(&redemption, now));
+        assert!(!challenge.is_redeemable_for(
+            &StepUpRedemption {
+                amount_cents: Some(400_000),
+                ..redemption.clone()
+            },
+            now
+        ));
+        assert!(!challenge.is_redeemable_for(
+            &StepUpRedemption {
+                to_number: Some("555555555".to_string()),
+                ..redemption.clone()
+            },
+            now
+        ));
+        assert!(!challenge.is_redeemable_for(
+            &StepUpRedemption::action(5, StepUpAction::NewDestinationTransfer, 52),
+            now
+        ));
+        assert!(!StepUpChallenge {
+            verified_at: None,
+            ..challenge.clone()
+        }
+        .is_redeemable_for(&redemption, now));
+        assert!(!StepUpChallenge {
+            redeemed_at: Some(now),
+            ..challenge
+        }
+        .is_redeemable_for(&redemption, now));
+    }
+}
@@ -1,13 +1,34 @@
 use diesel::{Insertable, Queryable, Selectable};
 
+use super::account::Account;
+use super::loan::{DISBURSEMENT_DESCRIPTION, REPAYMENT_DESCRIPTION};
 use super::schema::transactions;
 
+// NPP remittance info limit
+pub const MAX_DESCRIPTION_LEN: usize = 280;
+// BECS direct entry lodgement reference, the tightest of the rails a reference can go out on
+pub const MAX_REFERENCE_LEN: usize = 18;
+// what direct entry accepts besides letters, digits and spaces
+const REFERENCE_SPECIAL_CHARS: &str = "&'()*+,-./:;?@_";
+// the bank's own income account, where fees and interest are paid to. never a customer's
+pub const BANK_CHARGES_NUMBER: &str = "000000000";
+pub const BANK_CHARGES_BSB: &str = "123456";
+pub const BANK_CHARGES_NAME: &str = "Bank charges";
+
 #[derive(diesel_derive_enum::DbEnum, Copy, Clone, Debug, PartialEq)]
 #[ExistingTypePath = "crate::models::schema::sql_types::TransactionStatus"]
 pub enum TransactionStatus {
     Pending,
     Success,
     Error,
+    PartiallyReversed,
+    Reversed,
+    // waiting on staff fraud review before it goes anywhere
+    Held,
+    // from a both to sign account, waiting on a second holder
+    AwaitingSignature,
+    // over the account's approval threshold, waiting on a second holder to approve it
+    PendingApproval,
 }
 
 #[derive(diesel_derive_enum::DbEnum, Copy, Clone, Debug, PartialEq)]
@@ -15,8 +36,12 @@ pub enum TransactionStatus {
 pub enum TransactionType {
     Internal,
     External,
+    // interest or a fee the bank took, only ever from_us
+    Fee,
 }
 
+// one customer's view of money moving. from_us is always from that customer's side: true means it
+// left their account (from_*), false means it came into it (to_*)
 #[derive(Clone, Queryable, Selectable)]
 #[diesel(table_name = transactions)]
 #[diesel(check_for_backend(diesel::pg::Pg))]
@@ -36,6 +61,66 @@ pub struct Transaction {
     pub date_start: chrono::NaiveDateTime,
     pub date_end: Option<chrono::NaiveDateTime>,
     pub transaction_status: TransactionStatus,
+    // set on compensating transactions, id of the transaction being reversed
+    pub reversal_of: Option<i32>,
+    pub reversed_amount_cents: i64,
+    pub reversal_reason: Option<String>,
+    // free text for the customer's own statement
+    pub description: Option<String>,
+    // shown on the sender's statement
+    pub payer_reference: Option<String>,
+    // sent on to the recipient
+    pub payee_reference: Option<String>,
+    // set on the receiving customer's entry of an internal transfer, id of the sender's entry
+    pub counterpart_of: Option<i32>,
+    // balance of the customer's account straight after this, None until money has moved
+    pub running_balance_cents: Option<i64>,
+}
+
+impl Transaction {
+    // compensating transactions can't themselves be reversed, reverse the original again instead.
+    // same for the receiving side of an internal transfer, it follows the sender's entry
+    pub fn is_reversible(&self) -> bool {
+        self.reversal_of.is_none()
+            && self.counterpart_of.is_none()
+            && matches!(
+                self.transaction_status,
+                TransactionStatus::Success | TransactionStatus::PartiallyReversed
+            )
+            && self.reversible_amount_cents() > 0
+    }
+
+    pub fn reversible_amount_cents(&self) -> i64 {
+        self.amount_cents - self.reversed_amount_cents
+    }
+
+    // money has actually moved, as opposed to waiting on something or failed
+    pub fn is_settled(&self) -> bool {
+        matches!(
+            self.transaction_status,
+            TransactionStatus::Success
+                | TransactionStatus::PartiallyReversed
+                | TransactionStatus::Reversed
+        )
+    }
+
+    // what this did to the customer's account, reversals are their own entries
+    pub fn balance_delta_cents(&self) -> i64 {
+        if self.from_us {
+            -self.amount_cents
+        } else {
+            self.amount_cents
+        }
+    }
+
+    // (number, bsb) of the side of the transaction that belongs to customer_id
+    pub fn customer_account(&self) -> (&str, &str) {
+        if self.from_us {
+            (&self.from_number, &self.from_bsb)
+        } else {
+            (&self.to_number, &self.to_bsb)
+        }
+    }
 }
 
 #[derive(Insertable, Debug, PartialEq, Clone)]
@@ -54,6 +139,112 @@ pub struct NewTransaction {
     pub available_balance_cents: i64,
     // pub date_start: chrono::NaiveDateTime, // set by db
     pub transaction_status: TransactionStatus,
+    pub reversal_of: Option<i32>,
+    pub reversal_reason: Option<String>,
+    pub description: Option<String>,
+    pub payer_reference: Option<String>,
+    pub payee_reference: Option<String>,
+}
+
+impl NewTransaction {
+    // interest or a fee taken from the account, given as it is once the money has come out.
+    // settles straight away, there's no one on the other side to wait for
+    pub fn bank_charge(account: &Account, amount_cents: i64, description: &str) -> NewTransaction {
+        NewTransaction {
+            customer_id: account.customer_id,
+            transaction_type: TransactionType::Fee,
+            from_us: true,
+            amount_cents,
+            from_number: account.account_number.clone(),
+            from_bsb: account.bsb.clone(),
+            from_name: account.account_name.clone(),
+            to_number: BANK_CHARGES_NUMBER.to_string(),
+            to_bsb: BANK_CHARGES_BSB.to_string(),
+            to_name: Some(BANK_CHARGES_NAME.to_string()),
+            available_balance_cents: account.available_balance_cents,
+            transaction_status: TransactionStatus::Success,
+            reversal_of: None,
+            reversal_reason: None,
+            description: Some(description.to_string()),
+            payer_reference: None,
+            payee_reference: None,
+        }
+    }
+
+    // an internal transfer paying down the customer's own loan, settled like any other
+    pub fn loan_repayment(from: &Account, loan: &Account, amount_cents: i64) -> NewTransaction {
+        NewTransaction {
+            customer_id: from.customer_id,
+            transaction_type: TransactionType::Internal,
+            from_us: true,
+            amount_cents,
+            from_number: from.account_number.clone(),
+            from_bsb: from.bsb.clone(),
+            from_name: from.account_name.clone(),
+            to_number: loan.account_number.clone(),
+            to_bsb: loan.bsb.clone(),
+            to_name: loan.account_name.clone(),
+            available_balance_cents: from.available_balance_cents - amount_cents,
+            transaction_status: TransactionStatus::Pending,
+            reversal_of: None,
+            reversal_reason: None,
+            description: Some(REPAYMENT_DESCRIPTION.to_string()),
+            payer_reference: None,
+            payee_reference: Some(REPAYMENT_DESCRIPTION.to_string()),
+        }
+    }
+
+    // the loan account paying the principal out to the customer when the loan's drawn down
+    pub fn loan_disbursement(loan: &Account, to: &Account, amount_cents: i64) -> NewTransaction {
+        NewTransaction {
+            customer_id: loan.customer_id,
+            transaction_type: TransactionType::Internal,
+            from_us: true,
+            amount_cents,
+            from_number: loan.account_number.clone(),
+            from_bsb: loan.bsb.clone(),
+            from_name: loan.account_name.clone(),
+            to_number: to.account_number.clone(),
+            to_bsb: to.bsb.clone(),
+            to_name: to.account_name.clone(),
+            available_balance_cents: loan.available_balance_cents - amount_cents,
+            transaction_status: TransactionStatus::Pending,
+            reversal_of: None,
+            reversal_reason: None,
+            description: Some(DISBURSEMENT_DESCRIPTION.to_string()),
+            payer_reference: None,
+            payee_reference: Some(DISBURSEMENT_DESCRIPTION.to_string()),
+        }
+    }
+
+    pub fn has_valid_text(&self) -> bool {
+        is_valid_description(&self.description)
+            && is_valid_reference(&self.payer_reference)
+            && is_valid_reference(&self.payee_reference)
+    }
+}
+
+pub fn is_valid_description(description: &Option<String>) -> bool {
+    description.as_ref().map_or(true, |d| {
+        d.chars().count() <= MAX_DESCRIPTION_LEN && d.chars().all(|c| !c.is_control())
+    })
+}
+
+pub fn is_valid_reference(reference: &Option<String>) -> bool {
+    reference.as_ref().map_or(true, |r| {
+        r.chars().count() <= MAX_REFERENCE_LEN
+            && r.chars().all(|c| {
+                c.is_ascii_alphanumeric() || c == ' ' || REFERENCE_SPECIAL_CHARS.contains(c)
+            })
+    })
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct NewReversal {
+    pub transaction_id: i32,
+    // whatever is left to reverse if not given
+    pub amount_cents: Option<i64>,
+    pub reason: String,
 }
 
 #[cfg_attr(test, derive(Debug, PartialEq))]
@@ -61,6 +252,135 @@ pub struct FindTransactionQuery {
     pub transaction_id: Option<i32>,
     pub customer_id: i32,
     pub account_number: Option<String>,
-    // from number
-    // date range?
+    // on date_start, inclusive
+    pub date_from: Option<chrono::NaiveDateTime>,
+    pub date_to: Option<chrono::NaiveDateTime>,
+    // matched anywhere in description or either reference, case insensitive
+    pub search: Option<String>,
+}
+
+// the customer's payments out to an account, from any account they hold. only ones that went or
+// are on their way count, not ones held, rejected or reversed
+#[derive(Clone)]
+#[cfg_attr(test, derive(Debug, PartialEq))]
+pub struct FindPaymentsToQuery {
+    pub customer_id: i32,
+    pub to_number: String,
+    pub to_bsb: String,
+}
+
+// one of the customer's accounts over a window, not cut off at a page of results like
+// FindTransactionQuery, for callers that page through it themselves
+#[derive(Clone)]
+#[cfg_attr(test, derive(Debug, PartialEq))]
+pub struct FindAccountHistoryQuery {
+    pub customer_id: i32,
+    pub account_number: String,
+    // on date_start, both inclusive
+    pub from: chrono::NaiveDateTime,
+    pub to: chrono::NaiveDateTime,
+    pub search: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fixtures::account;
+    use chrono::NaiveDate;
+
+    use super::{
+        is_valid_description, is_valid_reference, NewTransaction, Transaction, TransactionStatus,
+        TransactionType,
+    };
+    use crate::models::{account::AccountType, approval::ApprovalPolicy};
+
+    fn received() -> Transaction {
+        let date = NaiveDate::from_ymd_opt(2023, 8, 21)
+            .unwrap()
+            .and_hms_opt(4, 46, 30)
+            .unwrap();
+        Transaction {
+            id: 61,
+            customer_id: 6,
+            transaction_type: TransactionType::Internal,
+            from_us: false,
+            amount_cents: 10000,
+            from_number: "123456789".to_string(),
+            from_bsb: "123456".to_string(),
+            from_name: Some("Everyday".to_string()),
+            to_number: "987654321".to_string(),
+            to_bsb: "123456".to_string(),
+            to_name: Some("Bills".to_string()),
+            available_balance_cents: 25000,
+            date_start: date,
+            date_end: Some(date),
+            transaction_status: TransactionStatus::Success,
+            reversal_of: None,
+            reversed_amount_cents: 0,
+            reversal_reason: None,
+            description: None,
+            payer_reference: None,
+            payee_reference: None,
+            counterpart_of: Some(60),
+            running_balance_cents: Some(25000),
+        }
+    }
+
+    #[test]
+    fn receiving_side_not_reversible() {
+        let tr = received();
+        assert_eq!(("987654321", "123456"), tr.customer_account());
+        assert!(!tr.is_reversible());
+
+        let tr = Transaction {
+            counterpart_of: None,
+            ..received()
+        };
+        assert!(tr.is_reversible());
+    }
+
+    #[test]
+    fn reference_rules() {
+        assert!(is_valid_reference(&None));
+        assert!(is_valid_reference(&Some("INV-2023/08 A1".to_string())));
+        assert!(is_valid_reference(&Some("Rent, Aug (Unit 4)".to_string())));
+        // 19 characters
+        assert!(!is_valid_reference(&Some(
+            "abcdefghijklmnopqrs".to_string()
+        )));
+        assert!(!is_valid_reference(&Some("café".to_string())));
+        assert!(!is_valid_reference(&Some("50% off".to_string())));
+    }
+
+    #[test]
+    fn description_rules() {
+        assert!(is_valid_description(&Some(
+            "Dinner at Café Ñandú".to_string()
+        )));
+        assert!(!is_valid_description(&Some("line\nbreak".to_string())));
+        assert!(!is_valid_description(&Some("x".repeat(281))));
+    }
+
+    #[test]
+    fn loan_repayment_from_threshold_account_waits_for_approval() {
+        // customer 5 repaying their loan from customer 6's account they sign on
+        let from = account(70, 6, AccountType::Transaction, 5_000_000);
+        let loan = account(80, 5, AccountType::Loan, -20_000_000);
+        let policy = ApprovalPolicy {
+            account_id: 70,
+            threshold_cents: 1_000_000,
+            expiry_hours: 48,
+            updated_at: from.date_opened,
+        };
+
+        let repayment = NewTransaction::loan_repayment(&from, &loan, 2_000_000);
+
+        // the account's policy is found through its primary holder, like a transfer they make
+        assert_eq!(from.customer_id, repayment.customer_id);
+        assert_eq!(
+            (from.account_number.as_str(), from.bsb.as_str()),
+            (repayment.from_number.as_str(), repayment.from_bsb.as_str())
+        );
+        assert_eq!(3_000_000, repayment.available_balance_cents);
+        assert!(policy.applies_to(repayment.amount_cents));
+    }
 }
@@ -0,0 +1,355 @@
+use diesel::{AsChangeset, Insertable, Queryable, Selectable};
+
+use super::schema::{customer_transfer_limits, transfer_limit_requests};
+use super::transaction::{NewTransaction, TransactionType};
+
+// bank wide defaults, used for any limit a customer hasn't had changed
+pub const DEFAULT_PER_TRANSACTION_CENTS: i64 = 1_000_000;
+pub const DEFAULT_DAILY_CENTS: i64 = 2_000_000;
+pub const DEFAULT_PER_PAYEE_DAILY_CENTS: i64 = 1_000_000;
+pub const DEFAULT_DAILY_INTERNAL_CENTS: i64 = 2_000_000;
+pub const DEFAULT_DAILY_EXTERNAL_CENTS: i64 = 1_000_000;
+
+#[derive(diesel_derive_enum::DbEnum, Copy, Clone, Debug, PartialEq)]
+#[ExistingTypePath = "crate::models::schema::sql_types::TransferLimitKind"]
+pub enum TransferLimitKind {
+    PerTransaction,
+    Daily,
+    PerPayeeDaily,
+    DailyInternal,
+    DailyExternal,
+}
+
+#[derive(diesel_derive_enum::DbEnum, Copy, Clone, Debug, PartialEq)]
+#[ExistingTypePath = "crate::models::schema::sql_types::LimitRequestStatus"]
+pub enum LimitRequestStatus {
+    Pending,
+    Approved,
+    Declined,
+}
+
+// stored overrides, None falls back to the default
+#[derive(Clone, Queryable, Selectable, Insertable, AsChangeset)]
+#[cfg_attr(test, derive(Debug, PartialEq))]
+#[diesel(table_name = customer_transfer_limits)]
+#[diesel(primary_key(customer_id))]
+#[diesel(treat_none_as_null = true)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct CustomerTransferLimits {
+    pub customer_id: i32,
+    pub per_transaction_cents: Option<i64>,
+    pub daily_cents: Option<i64>,
+    pub per_payee_daily_cents: Option<i64>,
+    pub daily_internal_cents: Option<i64>,
+    pub daily_external_cents: Option<i64>,
+    pub updated_at: chrono::NaiveDateTime,
+}
+
+impl CustomerTransferLimits {
+    pub fn none(customer_id: i32, updated_at: chrono::NaiveDateTime) -> Self {
+        CustomerTransferLimits {
+            customer_id,
+            per_transaction_cents: None,
+            daily_cents: None,
+            per_payee_daily_cents: None,
+            daily_internal_cents: None,
+            daily_external_cents: None,
+            updated_at,
+        }
+    }
+
+    pub fn set(&mut self, kind: TransferLimitKind, amount_cents: i64) {
+        let field = match kind {
+            TransferLimitKind::PerTransaction => &mut self.per_transaction_cents,
+            TransferLimitKind::Daily => &mut self.daily_cents,
+            TransferLimitKind::PerPayeeDaily => &mut self.per_payee_daily_cents,
+            TransferLimitKind::DailyInternal => &mut self.daily_internal_cents,
+            TransferLimitKind::DailyExternal => &mut self.daily_external_cents,
+        };
+        *field = Some(amount_cents);
+    }
+}
+
+// limits that actually apply to a customer
+#[derive(Clone, Debug, PartialEq)]
+pub struct TransferLimits {
+    pub customer_id: i32,
+    pub per_transaction_cents: i64,
+    pub daily_cents: i64,
+    pub per_payee_daily_cents: i64,
+    pub daily_internal_cents: i64,
+    pub daily_external_cents: i64,
+}
+
+impl TransferLimits {
+    pub fn effective(customer_id: i32, overrides: Option<CustomerTransferLimits>) -> Self {
+        let overrides = overrides.as_ref();
+        TransferLimits {
+            customer_id,
+            per_transaction_cents: overrides
+                .and_then(|o| o.per_transaction_cents)
+                .unwrap_or(DEFAULT_PER_TRANSACTION_CENTS),
+            daily_cents: overrides
+                .and_then(|o| o.daily_cents)
+                .unwrap_or(DEFAULT_DAILY_CENTS),
+            per_payee_daily_cents: overrides
+                .and_then(|o| o.per_payee_daily_cents)
+                .unwrap_or(DEFAULT_PER_PAYEE_DAILY_CENTS),
+            daily_internal_cents: overrides
+                .and_then(|o| o.daily_internal_cents)
+                .unwrap_or(DEFAULT_DAILY_INTERNAL_CENTS),
+            daily_external_cents: overrides
+                .and_then(|o| o.daily_external_cents)
+                .unwrap_or(DEFAULT_DAILY_EXTERNAL_CENTS),
+        }
+    }
+
+    pub fn get(&self, kind: TransferLimitKind) -> i64 {
+        match kind {
+            TransferLimitKind::PerTransaction => self.per_transaction_cents,
+            TransferLimitKind::Daily => self.daily_cents,
+            TransferLimitKind::PerPayeeDaily => self.per_payee_daily_cents,
+            TransferLimitKind::DailyInternal => self.daily_internal_cents,
+            TransferLimitKind::DailyExternal => self.daily_external_cents,
+        }
+    }
+
+    // what's left of each daily limit given today's outgoing transfers
+    pub fn remaining(&self, usage: &[TransferUsage]) -> TransferAllowance {
+        let used = |channel: Option<TransactionType>| -> i64 {
+            usage
+                .iter()
+                .filter(|u| channel.map_or(true, |c| u.transaction_type == c))
+                .map(|u| u.amount_cents)
+                .sum()
+        };
+
+        TransferAllowance {
+            daily_cents: (self.daily_cents - used(None)).max(0),
+            daily_internal_cents: (self.daily_internal_cents
+                - used(Some(TransactionType::Internal)))
+            .max(0),
+            daily_external_cents: (self.daily_external_cents
+                - used(Some(TransactionType::External)))
+            .max(0),
+        }
+    }
+
+    // first limit the transfer would break, if any
+    pub fn check(
+        &self,
+        usage: &[TransferUsage],
+        transaction: &NewTransaction,
+    ) -> Result<(), TransferLimitKind> {
+        let amount = transaction.amount_cents;
+
+        if amount > self.per_transaction_cents {
+            return Err(TransferLimitKind::PerTransaction);
+        }
+
+        let remaining = self.remaining(usage);
+        if amount > remaining.daily_cents {
+            return Err(TransferLimitKind::Daily);
+        }
+
+        let (channel_kind, channel_remaining) = match transaction.transaction_type {
+            TransactionType::Internal => (
+                TransferLimitKind::DailyInternal,
+                remaining.daily_internal_cents,
+            ),
+            TransactionType::External => (
+                TransferLimitKind::DailyExternal,
+                remaining.daily_external_cents,
+            ),
+            // the bank takes these, the customer's limits are for what they send
+            TransactionType::Fee => return Ok(()),
+        };
+        if amount > channel_remaining {
+            return Err(channel_kind);
+        }
+
+        let payee_used: i64 = usage
+            .iter()
+            .filter(|u| u.to_number == transaction.to_number && u.to_bsb == transaction.to_bsb)
+            .map(|u| u.amount_cents)
+            .sum();
+        if payee_used + amount > self.per_payee_daily_cents {
+            return Err(TransferLimitKind::PerPayeeDaily);
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TransferAllowance {
+    pub daily_cents: i64,
+    pub daily_internal_cents: i64,
+    pub daily_external_cents: i64,
+}
+
+// one outgoing transfer counted against today's limits, net of anything reversed
+#[derive(Clone, Queryable)]
+#[cfg_attr(test, derive(Debug, PartialEq))]
+pub struct TransferUsage {
+    pub transaction_type: TransactionType,
+    pub to_number: String,
+    pub to_bsb: String,
+    pub amount_cents: i64,
+}
+
+#[derive(Clone)]
+#[cfg_attr(test, derive(Debug, PartialEq))]
+pub struct FindTransferUsageQuery {
+    pub customer_id: i32,
+    pub since: chrono::NaiveDateTime,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TransferLimitUpdate {
+    pub limit_kind: TransferLimitKind,
+    pub amount_cents: i64,
+}
+
+#[derive(Clone, Queryable, Selectable)]
+#[cfg_attr(test, derive(Debug, PartialEq))]
+#[diesel(table_name = transfer_limit_requests)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct TransferLimitRequest {
+    pub id: i32,
+    pub customer_id: i32,
+    pub limit_kind: TransferLimitKind,
+    pub requested_cents: i64,
+    pub reason: String,
+    pub request_status: LimitRequestStatus,
+    pub reviewer_id: Option<i32>,
+    pub review_note: Option<String>,
+    pub created_at: chrono::NaiveDateTime,
+    pub reviewed_at: Option<chrono::NaiveDateTime>,
+}
+
+#[derive(Insertable, Debug, PartialEq, Clone)]
+#[diesel(table_name = transfer_limit_requests)]
+pub struct NewTransferLimitRequest {
+    pub customer_id: i32,
+    pub limit_kind: TransferLimitKind,
+    pub requested_cents: i64,
+    pub reason: String,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct LimitRequestDecision {
+    pub approve: bool,
+    pub reviewer_id: i32,
+    pub note: Option<String>,
+}
+
+#[derive(Clone)]
+#[cfg_attr(test, derive(Debug, PartialEq))]
+pub struct FindTransferLimitRequestQuery {
+    pub customer_id: Option<i32>,
+    pub request_status: Option<LimitRequestStatus>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{TransferLimitKind, TransferLimits, TransferUsage};
+    use crate::models::transaction::{NewTransaction, TransactionStatus, TransactionType};
+
+    fn limits() -> TransferLimits {
+        TransferLimits {
+            customer_id: 5,
+            per_transaction_cents: 10_000,
+            daily_cents: 20_000,
+            per_payee_daily_cents: 12_000,
+            daily_internal_cents: 15_000,
+            daily_external_cents: 5_000,
+        }
+    }
+
+    fn used(
+        transaction_type: TransactionType,
+        to_number: &str,
+        amount_cents: i64,
+    ) -> TransferUsage {
+        TransferUsage {
+            transaction_type,
+            to_number: to_number.to_string(),
+            to_bsb: "123456".to_string(),
+            amount_cents,
+        }
+    }
+
+    fn transfer(amount_cents: i64, to_number: &str) -> NewTransaction {
+        NewTransaction {
+            customer_id: 5,
+            transaction_type: TransactionType::Internal,
+            from_us: true,
+            amount_cents,
+            from_number: "123456789".to_string(),
+            from_bsb: "123456".to_string(),
+            from_name: None,
+            to_number: to_number.to_string(),
+            to_bsb: "123456".to_string(),
+            to_name: None,
+            available_balance_cents: 0,
+            transaction_status: TransactionStatus::Pending,
+            reversal_of: None,
+            reversal_reason: None,
+            description: None,
+            payer_reference: None,
+            payee_reference: None,
+        }
+    }
+
+    #[test]
+    fn effective_falls_back_to_defaults() {
+        let limits = TransferLimits::effective(5, None);
+        assert_eq!(
+            super::DEFAULT_DAILY_CENTS,
+            limits.get(TransferLimitKind::Daily)
+        );
+    }
+
+    #[test]
+    fn check_limits() {
+        let usage = vec![
+            used(TransactionType::Internal, "111111111", 8_000),
+            used(TransactionType::External, "222222222", 4_000),
+        ];
+
+        assert_eq!(
+            Ok(()),
+            limits().check(&usage, &transfer(3_000, "333333333"))
+        );
+        assert_eq!(
+            Err(TransferLimitKind::PerTransaction),
+            limits().check(&usage, &transfer(11_000, "333333333"))
+        );
+        // 12k used today, 20k daily
+        assert_eq!(
+            Err(TransferLimitKind::Daily),
+            limits().check(&usage, &transfer(9_000, "333333333"))
+        );
+        // 8k internal used, 15k internal daily
+        assert_eq!(
+            Err(TransferLimitKind::DailyInternal),
+            limits().check(&usage, &transfer(7_500, "333333333"))
+        );
+        // 8k to this payee already, 12k per payee
+        assert_eq!(
+            Err(TransferLimitKind::PerPayeeDaily),
+            limits().check(&usage, &transfer(5_000, "111111111"))
+        );
+    }
+
+    #[test]
+    fn remaining_never_negative() {
+        let usage = vec![used(TransactionType::External, "222222222", 6_000)];
+        let remaining = limits().remaining(&usage);
+
+        assert_eq!(14_000, remaining.daily_cents);
+        assert_eq!(15_000, remaining.daily_internal_cents);
+        assert_eq!(0, remaining.daily_external_cents);
+    }
+}
@@ -0,0 +1,278 @@
+use diesel::{Insertable, Queryable, Selectable};
+
+use super::outbox::{EventType, OutboxEvent};
+use super::schema::{
+    webhook_deliveries, webhook_delivery_attempts, webhook_events, webhook_subscriptions,
+};
+
+// after this many tries a delivery is given up on, replaying it starts over
+pub const MAX_DELIVERY_ATTEMPTS: i32 = 8;
+// doubled after every failed try, so the last one goes a little over four hours after the first
+pub const FIRST_RETRY_DELAY_SECONDS: i64 = 120;
+// how long the worker has a delivery to itself before someone else can pick it up again
+pub const DELIVERY_LEASE_SECONDS: i64 = 60;
+
+// None if there aren't any or anything in there is unknown
+pub fn parse_event_types(event_types: &[String]) -> Option<Vec<EventType>> {
+    let mut parsed = vec![];
+    for event_type in event_types {
+        let event_type = EventType::parse(event_type.trim())?;
+        if !parsed.contains(&event_type) {
+            parsed.push(event_type);
+        }
+    }
+    (!parsed.is_empty()).then_some(parsed)
+}
+
+// stored space separated, always in the same order
+pub fn event_types_string(event_types: &[EventType]) -> String {
+    EventType::ALL
+        .into_iter()
+        .filter(|t| event_types.contains(t))
+        .map(|t| t.as_str())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[derive(Clone, Queryable, Selectable)]
+#[cfg_attr(test, derive(Debug, PartialEq))]
+#[diesel(table_name = webhook_subscriptions)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct WebhookSubscription {
+    pub id: i32,
+    pub url: String,
+    pub event_types: String,
+    // signs deliveries, never shown again after the subscription's made
+    #[allow(dead_code)]
+    pub secret: String,
+    pub created_by: i32,
+    pub created_at: chrono::NaiveDateTime,
+    pub revoked_at: Option<chrono::NaiveDateTime>,
+}
+
+impl WebhookSubscription {
+    pub fn is_active(&self) -> bool {
+        self.revoked_at.is_none()
+    }
+
+    pub fn subscribes_to(&self, event_type: EventType) -> bool {
+        self.event_types
+            .split_whitespace()
+            .any(|t| t == event_type.as_str())
+    }
+}
+
+#[derive(Insertable, Debug, PartialEq, Clone)]
+#[diesel(table_name = webhook_subscriptions)]
+pub struct NewWebhookSubscription {
+    pub url: String,
+    pub event_types: String,
+    pub secret: String,
+    pub created_by: i32,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct FindWebhookSubscriptionQuery {
+    pub include_revoked: bool,
+}
+
+// stops new deliveries, anything still pending for it is dropped
+#[derive(Clone, Debug, PartialEq)]
+pub struct WebhookSubscriptionRevocation;
+
+#[derive(Clone, Queryable, Selectable, Insertable)]
+#[cfg_attr(test, derive(Debug, PartialEq))]
+#[diesel(table_name = webhook_events)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct WebhookEvent {
+    // what subscribers dedupe on
+    pub id: String,
+    pub event_type: String,
+    // the json body every delivery of the event sends
+    pub payload: String,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+// the outbox event as it's relayed, subscribers get the same id and body as any other consumer
+impl From<&OutboxEvent> for WebhookEvent {
+    fn from(event: &OutboxEvent) -> Self {
+        WebhookEvent {
+            id: event.event_id.clone(),
+            event_type: event.event_type.clone(),
+            payload: event.payload.clone(),
+            created_at: event.created_at,
+        }
+    }
+}
+
+#[derive(diesel_derive_enum::DbEnum, Copy, Clone, Debug, PartialEq)]
+#[ExistingTypePath = "crate::models::schema::sql_types::WebhookDeliveryStatus"]
+pub enum WebhookDeliveryStatus {
+    Pending,
+    Delivered,
+    // out of attempts, only a replay sends it again
+    Failed,
+}
+
+#[derive(Clone, Queryable, Selectable)]
+#[cfg_attr(test, derive(Debug, PartialEq))]
+#[diesel(table_name = webhook_deliveries)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct WebhookDelivery {
+    pub id: i32,
+    pub subscription_id: i32,
+    pub event_id: String,
+    pub delivery_status: WebhookDeliveryStatus,
+    pub attempts: i32,
+    pub next_attempt_at: Option<chrono::NaiveDateTime>,
+    pub delivered_at: Option<chrono::NaiveDateTime>,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+#[derive(Insertable, Debug, PartialEq, Clone)]
+#[diesel(table_name = webhook_deliveries)]
+pub struct NewWebhookDelivery {
+    pub subscription_id: i32,
+    pub event_id: String,
+    pub next_attempt_at: Option<chrono::NaiveDateTime>,
+}
+
+#[derive(Clone, Queryable, Selectable)]
+#[cfg_attr(test, derive(Debug, PartialEq))]
+#[diesel(table_name = webhook_delivery_attempts)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct WebhookDeliveryAttempt {
+    #[allow(dead_code)]
+    pub id: i32,
+    pub delivery_id: i32,
+    pub response_status: Option<i32>,
+    pub error: Option<String>,
+    pub attempted_at: chrono::NaiveDateTime,
+}
+
+// one try at sending a delivery. the subscriber answering 2xx is the only thing that counts as
+// delivered
+#[derive(Insertable, Debug, PartialEq, Clone)]
+#[diesel(table_name = webhook_delivery_attempts)]
+pub struct WebhookAttempt {
+    pub response_status: Option<i32>,
+    pub error: Option<String>,
+    pub attempted_at: chrono::NaiveDateTime,
+}
+
+impl WebhookAttempt {
+    pub fn succeeded(&self) -> bool {
+        self.response_status
+            .is_some_and(|status| (200..300).contains(&status))
+    }
+}
+
+// how long after the given try the next one goes, None once they've run out
+pub fn retry_delay(attempts: i32) -> Option<chrono::Duration> {
+    if attempts >= MAX_DELIVERY_ATTEMPTS {
+        return None;
+    }
+    let doublings = (attempts - 1).clamp(0, MAX_DELIVERY_ATTEMPTS) as u32;
+    Some(chrono::Duration::seconds(
+        FIRST_RETRY_DELAY_SECONDS * 2i64.pow(doublings),
+    ))
+}
+
+// a delivery with its log, what staff see
+#[derive(Clone)]
+#[cfg_attr(test, derive(Debug, PartialEq))]
+pub struct WebhookDeliveryLog {
+    pub delivery: WebhookDelivery,
+    pub event_type: String,
+    pub attempts: Vec<WebhookDeliveryAttempt>,
+}
+
+// newest first
+#[derive(Clone, Debug, PartialEq)]
+pub struct FindWebhookDeliveryQuery {
+    pub subscription_id: i32,
+}
+
+// everything the worker needs to send a delivery. finding these leases them to the caller for
+// DELIVERY_LEASE_SECONDS
+#[derive(Clone)]
+#[cfg_attr(test, derive(Debug, PartialEq))]
+pub struct DueWebhookDelivery {
+    pub delivery_id: i32,
+    pub url: String,
+    pub secret: String,
+    pub event: WebhookEvent,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct FindDueWebhookDeliveryQuery {
+    pub now: chrono::NaiveDateTime,
+    pub limit: i64,
+}
+
+// send it again from scratch, whatever happened to it before. has to be one of the subscription's
+#[derive(Clone, Debug, PartialEq)]
+pub struct WebhookReplay {
+    pub subscription_id: i32,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        event_types_string, parse_event_types, retry_delay, EventType, WebhookAttempt,
+        FIRST_RETRY_DELAY_SECONDS, MAX_DELIVERY_ATTEMPTS,
+    };
+
+    #[test]
+    fn event_types_round_trip() {
+        let parsed = parse_event_types(&[
+            "account.closed".to_string(),
+            " transaction.created".to_string(),
+            "account.closed".to_string(),
+        ])
+        .unwrap();
+
+        assert_eq!(
+            "transaction.created account.closed",
+            event_types_string(&parsed)
+        );
+        assert_eq!(None, parse_event_types(&[]));
+        assert_eq!(
+            None,
+            parse_event_types(&[
+                "transaction.created".to_string(),
+                "account.updated".to_string()
+            ])
+        );
+        assert_eq!(
+            Some(EventType::TransactionSettled),
+            EventType::parse("transaction.settled")
+        );
+    }
+
+    #[test]
+    fn retries_back_off_then_stop() {
+        let delays: Vec<i64> = (1..MAX_DELIVERY_ATTEMPTS)
+            .map(|attempts| retry_delay(attempts).unwrap().num_seconds())
+            .collect();
+
+        assert_eq!(FIRST_RETRY_DELAY_SECONDS, delays[0]);
+        assert!(delays.windows(2).all(|w| w[1] == w[0] * 2));
+        assert_eq!(None, retry_delay(MAX_DELIVERY_ATTEMPTS));
+    }
+
+    #[test]
+    fn only_2xx_is_delivered() {
+        let attempt = |response_status| WebhookAttempt {
+            response_status,
+            error: None,
+            attempted_at: chrono::Utc::now().naive_utc(),
+        };
+
+        assert!(attempt(Some(200)).succeeded());
+        assert!(attempt(Some(204)).succeeded());
+        assert!(!attempt(Some(301)).succeeded());
+        assert!(!attempt(Some(500)).succeeded());
+        assert!(!attempt(None).succeeded());
+    }
+}
@@ -0,0 +1,35 @@
+// customer notifications. what's sent is decided by the caller, a Notifier is just the channel it
+// goes out on
+
+use crate::error::NotifyError;
+
+#[cfg(test)]
+use mockall::automock;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Notification {
+    pub customer_id: i32,
+    // short machine readable kind, e.g. budget_alert
+    pub kind: String,
+    pub subject: String,
+    pub body: String,
+}
+
+#[cfg_attr(test, automock)]
+pub trait Notifier: 'static + Sync + Send {
+    fn notify(&self, notification: &Notification) -> Result<(), NotifyError>;
+}
+
+// stand in until there's a real channel (push, email), notifications end up in the server log
+#[derive(Clone, Default)]
+pub struct LogNotifier;
+
+impl Notifier for LogNotifier {
+    fn notify(&self, notification: &Notification) -> Result<(), NotifyError> {
+        println!(
+            "Notifying customer {} ({}): {} - {}",
+            notification.customer_id, notification.kind, notification.subject, notification.body
+        );
+        Ok(())
+    }
+}
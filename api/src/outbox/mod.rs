@@ -0,0 +1,385 @@
+// publishing what's been written to the outbox. an event only gets here once the change behind it
+// has committed, and it's tried until it's taken, so everything downstream sees it at least once
+// and should drop any event id it's already seen
+
+use std::{fs::OpenOptions, io::Write, path::PathBuf, time::Duration};
+
+use actix_web::{rt, web, web::Data};
+
+use crate::{
+    error::{NotifyError, RepoError},
+    models::{
+        outbox::{FindDueOutboxEventQuery, OutboxEvent, OutboxPublishAttempt},
+        webhook::WebhookDelivery,
+    },
+    traits::{RepoCreate, RepoFind, RepoUpdate},
+};
+
+#[cfg(test)]
+use mockall::automock;
+
+pub const EVENT_ID_HEADER: &str = "X-Event-Id";
+pub const EVENT_TYPE_HEADER: &str = "X-Event-Type";
+
+const SEND_TIMEOUT_SECONDS: u64 = 10;
+const POLL_INTERVAL_SECONDS: u64 = 1;
+const EVENTS_PER_POLL: i64 = 100;
+// what's kept of the last failure on the event
+const MAX_ERROR_LEN: usize = 200;
+
+// wherever events go once they're out of the db. publishing the same event twice has to be fine
+#[cfg_attr(test, automock)]
+pub trait OutboxSink: 'static + Sync + Send {
+    fn publish(&self, event: &OutboxEvent) -> Result<(), NotifyError>;
+}
+
+// for running locally, nothing downstream
+pub struct LogSink;
+
+impl OutboxSink for LogSink {
+    fn publish(&self, event: &OutboxEvent) -> Result<(), NotifyError> {
+        println!(
+            "Published {} {}: {}",
+            event.event_type, event.event_id, event.payload
+        );
+        Ok(())
+    }
+}
+
+// posts each event's payload to one endpoint, which has to answer 2xx to take it
+pub struct HttpSink {
+    url: String,
+    agent: ureq::Agent,
+}
+
+impl HttpSink {
+    pub fn new(url: String) -> HttpSink {
+        HttpSink {
+            url,
+            agent: ureq::AgentBuilder::new()
+                .timeout(Duration::from_secs(SEND_TIMEOUT_SECONDS))
+                .redirects(0)
+                .build(),
+        }
+    }
+}
+
+impl OutboxSink for HttpSink {
+    fn publish(&self, event: &OutboxEvent) -> Result<(), NotifyError> {
+        let res = self
+            .agent
+            .post(&self.url)
+            .set("Content-Type", "application/json")
+            .set(EVENT_ID_HEADER, &event.event_id)
+            .set(EVENT_TYPE_HEADER, &event.event_type)
+            .send_string(&event.payload);
+
+        match res {
+            Ok(response) if (200..300).contains(&response.status()) => Ok(()),
+            Ok(_) | Err(ureq::Error::Status(_, _)) => Err(NotifyError::Rejected),
+            Err(err) => {
+                println!("couldn't reach outbox endpoint {}: {}", self.url, err);
+                Err(NotifyError::Unavailable)
+            }
+        }
+    }
+}
+
+// appends each event's payload to a file as a line of json
+pub struct FileSink {
+    path: PathBuf,
+}
+
+impl FileSink {
+    pub fn new(path: PathBuf) -> FileSink {
+        FileSink { path }
+    }
+}
+
+impl OutboxSink for FileSink {
+    fn publish(&self, event: &OutboxEvent) -> Result<(), NotifyError> {
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .and_then(|mut file| file.write_all(format!("{}\n", event.payload).as_bytes()))
+            .map_err(|err| {
+                println!("couldn't write to {}: {}", self.path.display(), err);
+                NotifyError::Unavailable
+            })
+    }
+}
+
+// whichever one the environment asks for, see util::get_outbox_sink
+pub enum ConfiguredSink {
+    Log(LogSink),
+    Http(HttpSink),
+    File(FileSink),
+}
+
+impl OutboxSink for ConfiguredSink {
+    fn publish(&self, event: &OutboxEvent) -> Result<(), NotifyError> {
+        match self {
+            ConfiguredSink::Log(sink) => sink.publish(event),
+            ConfiguredSink::Http(sink) => sink.publish(event),
+            ConfiguredSink::File(sink) => sink.publish(event),
+        }
+    }
+}
+
+// one pass over whatever's due, returns how many were tried. webhook subscribers are queued up
+// first, then the event goes to the sink. if either fails both are tried again later, webhooks
+// are only queued the once
+pub fn relay_due<OR, WR, S>(
+    outbox_repo: &OR,
+    webhooks_repo: &WR,
+    sink: &S,
+    now: chrono::NaiveDateTime,
+) -> Result<usize, RepoError>
+where
+    OR: RepoFind<OutboxEvent, FindDueOutboxEventQuery>
+        + RepoUpdate<OutboxEvent, OutboxPublishAttempt>,
+    WR: RepoCreate<Vec<WebhookDelivery>, OutboxEvent>,
+    S: OutboxSink,
+{
+    let due = outbox_repo.find(FindDueOutboxEventQuery {
+        now,
+        limit: EVENTS_PER_POLL,
+    })?;
+
+    for event in due.iter() {
+        let published = webhooks_repo
+            .create(event.clone())
+            .map_err(|err| format!("couldn't queue webhooks: {}", err))
+            .and_then(|_| {
+                sink.publish(event)
+                    .map_err(|err| format!("couldn't publish: {}", err))
+            });
+
+        let attempt = OutboxPublishAttempt {
+            error: published
+                .err()
+                .map(|err| err.chars().take(MAX_ERROR_LEN).collect()),
+            attempted_at: now,
+        };
+
+        // the lease runs out and it's tried again, so a lost update only means publishing it twice
+        if let Err(err) = outbox_repo.update(event.id, attempt) {
+            println!(
+                "couldn't record attempt at publishing outbox event {}: {}",
+                event.event_id, err
+            );
+        }
+    }
+
+    Ok(due.len())
+}
+
+// polls the outbox for as long as the server's up
+pub fn spawn_relay_worker<OR, WR, S>(outbox_repo: Data<OR>, webhooks_repo: Data<WR>, sink: Data<S>)
+where
+    OR: RepoFind<OutboxEvent, FindDueOutboxEventQuery>
+        + RepoUpdate<OutboxEvent, OutboxPublishAttempt>,
+    WR: RepoCreate<Vec<WebhookDelivery>, OutboxEvent>,
+    S: OutboxSink,
+{
+    rt::spawn(async move {
+        let mut interval = rt::time::interval(Duration::from_secs(POLL_INTERVAL_SECONDS));
+        loop {
+            interval.tick().await;
+
+            let outbox_repo = outbox_repo.clone();
+            let webhooks_repo = webhooks_repo.clone();
+            let sink = sink.clone();
+            let res = web::block(move || {
+                relay_due(
+                    outbox_repo.as_ref(),
+                    webhooks_repo.as_ref(),
+                    sink.as_ref(),
+                    chrono::Utc::now().naive_utc(),
+                )
+            })
+            .await;
+
+            match res {
+                Ok(Ok(0)) => {}
+                Ok(Ok(tried)) => println!("Tried publishing {} outbox events", tried),
+                _ => println!("couldn't relay outbox events"),
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use chrono::NaiveDate;
+    use mockall::{mock, predicate::eq};
+
+    use super::{relay_due, FileSink, HttpSink, MockOutboxSink, OutboxSink, EVENT_ID_HEADER};
+    use crate::{
+        error::{NotifyError, RepoError},
+        models::{
+            outbox::{FindDueOutboxEventQuery, OutboxEvent, OutboxPublishAttempt},
+            webhook::WebhookDelivery,
+        },
+        traits::{MockRepoCreate, RepoFind, RepoUpdate},
+        util::random_uuid,
+        webhooks::tests::receiver,
+    };
+
+    fn now() -> chrono::NaiveDateTime {
+        NaiveDate::from_ymd_opt(2023, 9, 21)
+            .unwrap()
+            .and_hms_opt(6, 25, 4)
+            .unwrap()
+    }
+
+    fn event(id: i32) -> OutboxEvent {
+        let event_id = format!("6f1c8e0a-3b1f-4c55-9d0e-2a7e5b1c9f{:02}", id);
+        OutboxEvent {
+            id,
+            payload: format!(r#"{{"id":"{}","type":"transaction.created"}}"#, event_id),
+            event_id,
+            event_type: "transaction.created".to_string(),
+            attempts: 0,
+            last_error: None,
+            next_attempt_at: now(),
+            published_at: None,
+            created_at: now(),
+        }
+    }
+
+    mock! {
+        pub OR { }
+        impl RepoFind<OutboxEvent, FindDueOutboxEventQuery> for OR {
+            fn find(&self, query: FindDueOutboxEventQuery) -> Result<Vec<OutboxEvent>, RepoError>;
+        }
+        impl RepoUpdate<OutboxEvent, OutboxPublishAttempt> for OR {
+            fn update(&self, id: i32, attempt: OutboxPublishAttempt) -> Result<OutboxEvent, RepoError>;
+        }
+    }
+
+    fn outbox_repo(due: Vec<OutboxEvent>) -> MockOR {
+        let mut mock_outbox_repo = MockOR::new();
+        mock_outbox_repo
+            .expect_find()
+            .withf(|query| query.now == now())
+            .times(1)
+            .returning(move |_| Ok(due.clone()));
+        mock_outbox_repo
+    }
+
+    #[test]
+    fn relay_publishes_and_records_every_event() {
+        let mut mock_outbox_repo = outbox_repo(vec![event(1), event(2)]);
+        mock_outbox_repo
+            .expect_update()
+            .with(
+                eq(1),
+                eq(OutboxPublishAttempt {
+                    error: None,
+                    attempted_at: now(),
+                }),
+            )
+            .times(1)
+            .returning(|_, _| Err(RepoError::Other));
+        mock_outbox_repo
+            .expect_update()
+            .with(
+                eq(2),
+                eq(OutboxPublishAttempt {
+                    error: Some("couldn't publish: Unavailable".to_string()),
+                    attempted_at: now(),
+                }),
+            )
+            .times(1)
+            .returning(|_, _| Err(RepoError::Other));
+
+        let mut mock_webhooks_repo = MockRepoCreate::<Vec<WebhookDelivery>, OutboxEvent>::new();
+        mock_webhooks_repo
+            .expect_create()
+            .times(2)
+            .returning(|_| Ok(vec![]));
+
+        let mut mock_sink = MockOutboxSink::new();
+        mock_sink
+            .expect_publish()
+            .with(eq(event(1)))
+            .times(1)
+            .returning(|_| Ok(()));
+        mock_sink
+            .expect_publish()
+            .with(eq(event(2)))
+            .times(1)
+            .returning(|_| Err(NotifyError::Unavailable));
+
+        // the repo failing to record one doesn't stop the rest
+        let tried = relay_due(&mock_outbox_repo, &mock_webhooks_repo, &mock_sink, now()).unwrap();
+
+        assert_eq!(2, tried);
+    }
+
+    #[test]
+    fn relay_webhooks_failing_holds_the_event_back() {
+        let mut mock_outbox_repo = outbox_repo(vec![event(1)]);
+        mock_outbox_repo
+            .expect_update()
+            .withf(|id, attempt| {
+                *id == 1 && attempt.error == Some("couldn't queue webhooks: Other".to_string())
+            })
+            .times(1)
+            .returning(|_, _| Ok(event(1)));
+
+        let mut mock_webhooks_repo = MockRepoCreate::<Vec<WebhookDelivery>, OutboxEvent>::new();
+        mock_webhooks_repo
+            .expect_create()
+            .times(1)
+            .returning(|_| Err(RepoError::Other));
+
+        let mut mock_sink = MockOutboxSink::new();
+        mock_sink.expect_publish().never();
+
+        let tried = relay_due(&mock_outbox_repo, &mock_webhooks_repo, &mock_sink, now()).unwrap();
+
+        assert_eq!(1, tried);
+    }
+
+    #[test]
+    fn file_sink_appends_a_line_per_event() {
+        let path = std::env::temp_dir().join(format!("outbox-{}.jsonl", random_uuid()));
+        let sink = FileSink::new(path.clone());
+
+        sink.publish(&event(1)).unwrap();
+        sink.publish(&event(2)).unwrap();
+        let written = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(
+            format!("{}\n{}\n", event(1).payload, event(2).payload),
+            written
+        );
+    }
+
+    #[test]
+    fn http_sink_posts_payload_with_event_id() {
+        let (url, handle) = receiver(202);
+
+        HttpSink::new(url).publish(&event(1)).unwrap();
+        let (headers, body) = handle.join().unwrap();
+
+        assert_eq!(event(1).payload, body);
+        assert!(headers.contains(&format!("{}: {}", EVENT_ID_HEADER, event(1).event_id)));
+    }
+
+    #[test]
+    fn http_sink_error_status_is_rejected() {
+        let (url, handle) = receiver(500);
+
+        let res = HttpSink::new(url).publish(&event(1));
+        handle.join().unwrap();
+
+        assert!(matches!(res, Err(NotifyError::Rejected)));
+    }
+}
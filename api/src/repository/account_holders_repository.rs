@@ -0,0 +1,144 @@
+use diesel::{
+    prelude::*,
+    r2d2::{ConnectionManager, Pool},
+    result::DatabaseErrorKind,
+};
+
+use crate::{
+    error::RepoError,
+    models::{
+        account::{Account, SigningRule},
+        account_holder::{
+            AccountHolder, FindAccountHolderQuery, NewAccountHolder, REQUIRED_SIGNATURES,
+        },
+        schema::{account_holders, accounts, approval_policies},
+    },
+    traits::{RepoCreate, RepoDeleteById, RepoFind, RepoGetById},
+};
+
+#[derive(Clone)]
+pub struct AccountHoldersRepoImpl {
+    pub pool: Pool<ConnectionManager<PgConnection>>,
+}
+
+impl AccountHoldersRepoImpl {
+    pub fn new(pool: Pool<ConnectionManager<PgConnection>>) -> AccountHoldersRepoImpl {
+        AccountHoldersRepoImpl { pool }
+    }
+}
+
+impl RepoCreate<AccountHolder, NewAccountHolder> for AccountHoldersRepoImpl {
+    fn create(&self, new_holder: NewAccountHolder) -> Result<AccountHolder, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        diesel::insert_into(account_holders::table)
+            .values(&new_holder)
+            .returning(AccountHolder::as_returning())
+            .get_result(&mut conn)
+            .map_err(|err| match err {
+                // customer already holds the account
+                diesel::result::Error::DatabaseError(DatabaseErrorKind::UniqueViolation, _) => {
+                    RepoError::Conflict
+                }
+                _ => RepoError::Other,
+            })
+    }
+}
+
+impl RepoFind<AccountHolder, FindAccountHolderQuery> for AccountHoldersRepoImpl {
+    fn find(&self, holder_query: FindAccountHolderQuery) -> Result<Vec<AccountHolder>, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        let mut query = account_holders::table.into_boxed();
+        if let Some(account_id) = holder_query.account_id {
+            query = query.filter(account_holders::account_id.eq(account_id));
+        }
+        if let Some(customer_id) = holder_query.customer_id {
+            query = query.filter(account_holders::customer_id.eq(customer_id));
+        }
+
+        query
+            .order(account_holders::id.asc())
+            .limit(50)
+            .select(AccountHolder::as_select())
+            .load(&mut conn)
+            .map_err(|_| RepoError::Other)
+    }
+}
+
+impl RepoGetById<AccountHolder> for AccountHoldersRepoImpl {
+    fn get_by_id(&self, holder_id: i32) -> Result<AccountHolder, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        account_holders::table
+            .filter(account_holders::id.eq(holder_id))
+            .select(AccountHolder::as_select())
+            .get_result(&mut conn)
+            .map_err(RepoError::from)
+    }
+}
+
+// the primary holder can't be taken off, the account's ledger is kept against them. nor can a
+// both to sign account be left without a second signature, or one with an approval policy without
+// someone to approve
+impl RepoDeleteById<AccountHolder> for AccountHoldersRepoImpl {
+    fn delete_by_id(&self, holder_id: i32) -> Result<(), RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        conn.transaction(|conn| {
+            let Some(holder) = account_holders::table
+                .filter(account_holders::id.eq(holder_id))
+                .select(AccountHolder::as_select())
+                .get_result(conn)
+                .optional()?
+            else {
+                return Ok(());
+            };
+
+            // same lock changing the signing rule takes
+            let account = accounts::table
+                .filter(accounts::id.eq(holder.account_id))
+                .for_update()
+                .select(Account::as_select())
+                .get_result(conn)?;
+
+            if account.customer_id == holder.customer_id {
+                return Err(RepoError::Conflict);
+            }
+
+            let needs_second_holder = account.signing_rule == SigningRule::BothToSign
+                || approval_policies::table
+                    .filter(approval_policies::account_id.eq(account.id))
+                    .count()
+                    .get_result::<i64>(conn)?
+                    > 0;
+
+            if needs_second_holder {
+                let holders = account_holders::table
+                    .filter(account_holders::account_id.eq(account.id))
+                    .count()
+                    .get_result::<i64>(conn)?;
+                if holders <= REQUIRED_SIGNATURES {
+                    return Err(RepoError::Conflict);
+                }
+            }
+
+            diesel::delete(account_holders::table.filter(account_holders::id.eq(holder.id)))
+                .execute(conn)?;
+
+            Ok(())
+        })
+    }
+}
@@ -6,10 +6,16 @@ use diesel::{
 use crate::{
     error::RepoError,
     models::{
-        account::{Account, FindAccountQuery, NewAccount},
-        schema::accounts,
+        account::{
+            Account, AccountStatus, AccountType, FindAccountQuery, NewAccount, SigningRule,
+            SigningRuleUpdate,
+        },
+        account_holder::REQUIRED_SIGNATURES,
+        outbox::{account_data, EventType},
+        schema::{account_holders, accounts},
     },
-    traits::{RepoCreate, RepoDeleteById, RepoFind, RepoGetById},
+    repository::util::{open_account, record_event},
+    traits::{RepoCreate, RepoDeleteById, RepoFind, RepoGetById, RepoUpdate},
 };
 
 #[derive(Clone)]
@@ -30,11 +36,8 @@ impl RepoCreate<Account, NewAccount> for AccountsRepoImpl {
             RepoError::ConnectionError
         })?;
 
-        Ok(diesel::insert_into(accounts::table)
-            .values(&new_account)
-            .returning(Account::as_returning())
-            .get_result(&mut conn)
-            .map_err(|_| RepoError::Other)?)
+        conn.transaction(|conn| open_account(conn, &new_account))
+            .map_err(|_| RepoError::Other)
     }
 }
 
@@ -50,17 +53,20 @@ impl RepoFind<Account, FindAccountQuery> for AccountsRepoImpl {
             query = query.filter(accounts::id.eq(id));
         }
 
-        query = query.filter(accounts::customer_id.eq(account_query.customer_id));
+        let held = account_holders::table
+            .filter(account_holders::customer_id.eq(account_query.customer_id))
+            .select(account_holders::account_id);
+        query = query.filter(accounts::id.eq_any(held));
 
         if let Some(account_number) = account_query.account_number {
             query = query.filter(accounts::account_number.eq(account_number))
         }
 
-        Ok(query
+        query
             .limit(50)
             .select(Account::as_select())
             .load(&mut conn)
-            .map_err(|_| RepoError::Other)?)
+            .map_err(|_| RepoError::Other)
     }
 }
 
@@ -71,14 +77,14 @@ impl RepoGetById<Account> for AccountsRepoImpl {
             RepoError::ConnectionError
         })?;
 
-        Ok(accounts::table
+        accounts::table
             .filter(accounts::id.eq(account_id))
             .select(Account::as_select())
             .get_result(&mut conn)
             .map_err(|err| match err {
                 diesel::result::Error::NotFound => RepoError::NotFound,
                 _ => RepoError::Other,
-            })?)
+            })
     }
 }
 
@@ -89,10 +95,74 @@ impl RepoDeleteById<Account> for AccountsRepoImpl {
             RepoError::ConnectionError
         })?;
 
-        diesel::delete(accounts::table.filter(accounts::id.eq(account_id)))
-            .execute(&mut conn)
-            .map_err(|_| RepoError::Other)?;
+        // closed, not deleted. the row keeps its history, and deleting a loan's account would take
+        // the loan with it
+        conn.transaction(|conn| {
+            let Some(account) = accounts::table
+                .filter(accounts::id.eq(account_id))
+                .for_update()
+                .select(Account::as_select())
+                .get_result(conn)
+                .optional()?
+            else {
+                return Ok(());
+            };
+
+            if account.account_status == AccountStatus::Inactive {
+                return Ok(());
+            }
+            // a loan is closed by paying it off, not by its borrower
+            if account.account_type == AccountType::Loan {
+                return Err(RepoError::Conflict);
+            }
+            // money left in it has to go somewhere first, and an overdrawn one is still owed
+            if account.balance_cents != 0 || account.available_balance_cents != 0 {
+                return Err(RepoError::Conflict);
+            }
+
+            let closed = diesel::update(accounts::table.filter(accounts::id.eq(account_id)))
+                .set(accounts::account_status.eq(AccountStatus::Inactive))
+                .returning(Account::as_returning())
+                .get_result(conn)?;
+
+            record_event(conn, EventType::AccountClosed, account_data(&closed))?;
+
+            Ok(())
+        })
+    }
+}
+
+// both to sign needs someone to be the second signature
+impl RepoUpdate<Account, SigningRuleUpdate> for AccountsRepoImpl {
+    fn update(&self, account_id: i32, update: SigningRuleUpdate) -> Result<Account, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        conn.transaction(|conn| {
+            // row lock so a holder can't be removed while the rule is changing
+            accounts::table
+                .filter(accounts::id.eq(account_id))
+                .for_update()
+                .select(accounts::id)
+                .get_result::<i32>(conn)?;
+
+            if update.signing_rule == SigningRule::BothToSign {
+                let holders = account_holders::table
+                    .filter(account_holders::account_id.eq(account_id))
+                    .count()
+                    .get_result::<i64>(conn)?;
+                if holders < REQUIRED_SIGNATURES {
+                    return Err(RepoError::Conflict);
+                }
+            }
 
-        Ok(())
+            diesel::update(accounts::table.filter(accounts::id.eq(account_id)))
+                .set(accounts::signing_rule.eq(update.signing_rule))
+                .returning(Account::as_returning())
+                .get_result(conn)
+                .map_err(RepoError::from)
+        })
     }
 }
@@ -0,0 +1,335 @@
+use diesel::{
+    prelude::*,
+    r2d2::{ConnectionManager, Pool},
+};
+
+use crate::{
+    error::RepoError,
+    models::{
+        account_holder::REQUIRED_SIGNATURES,
+        approval::{
+            ApprovalDecision, ApprovalExpiryRun, ApprovalPolicy, ApprovalStatus,
+            FindTransferApprovalEventQuery, FindTransferApprovalQuery, NewApprovalPolicy,
+            NewTransferApprovalEvent, TransferApproval, TransferApprovalEvent,
+        },
+        audit::ActorType,
+        schema::{
+            account_holders, accounts, approval_policies, transactions, transfer_approval_events,
+            transfer_approvals,
+        },
+        transaction::{Transaction, TransactionStatus},
+    },
+    repository::util::{release_transfer, set_transfer_status},
+    traits::{RepoCreate, RepoDeleteById, RepoFind, RepoGetById, RepoUpdate},
+};
+
+#[derive(Clone)]
+pub struct ApprovalsRepoImpl {
+    pub pool: Pool<ConnectionManager<PgConnection>>,
+}
+
+impl ApprovalsRepoImpl {
+    pub fn new(pool: Pool<ConnectionManager<PgConnection>>) -> ApprovalsRepoImpl {
+        ApprovalsRepoImpl { pool }
+    }
+}
+
+// by account id, an account has at most one
+impl RepoGetById<ApprovalPolicy> for ApprovalsRepoImpl {
+    fn get_by_id(&self, account_id: i32) -> Result<ApprovalPolicy, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        approval_policies::table
+            .filter(approval_policies::account_id.eq(account_id))
+            .select(ApprovalPolicy::as_select())
+            .get_result(&mut conn)
+            .map_err(RepoError::from)
+    }
+}
+
+// sets or replaces the account's policy. there has to be someone besides whoever makes a transfer
+// to approve it
+impl RepoCreate<ApprovalPolicy, NewApprovalPolicy> for ApprovalsRepoImpl {
+    fn create(&self, new_policy: NewApprovalPolicy) -> Result<ApprovalPolicy, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        conn.transaction(|conn| {
+            // same lock removing a holder takes
+            accounts::table
+                .filter(accounts::id.eq(new_policy.account_id))
+                .for_update()
+                .select(accounts::id)
+                .get_result::<i32>(conn)?;
+
+            let holders = account_holders::table
+                .filter(account_holders::account_id.eq(new_policy.account_id))
+                .count()
+                .get_result::<i64>(conn)?;
+            if holders < REQUIRED_SIGNATURES {
+                return Err(RepoError::Conflict);
+            }
+
+            diesel::insert_into(approval_policies::table)
+                .values(&new_policy)
+                .on_conflict(approval_policies::account_id)
+                .do_update()
+                .set((
+                    approval_policies::threshold_cents.eq(new_policy.threshold_cents),
+                    approval_policies::expiry_hours.eq(new_policy.expiry_hours),
+                    approval_policies::updated_at.eq(chrono::Utc::now().naive_utc()),
+                ))
+                .returning(ApprovalPolicy::as_returning())
+                .get_result(conn)
+                .map_err(RepoError::from)
+        })
+    }
+}
+
+// transfers already waiting keep waiting, the policy only decides what gets caught
+impl RepoDeleteById<ApprovalPolicy> for ApprovalsRepoImpl {
+    fn delete_by_id(&self, account_id: i32) -> Result<(), RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        diesel::delete(
+            approval_policies::table.filter(approval_policies::account_id.eq(account_id)),
+        )
+        .execute(&mut conn)
+        .map_err(|_| RepoError::Other)?;
+
+        Ok(())
+    }
+}
+
+impl RepoFind<TransferApproval, FindTransferApprovalQuery> for ApprovalsRepoImpl {
+    fn find(
+        &self,
+        approval_query: FindTransferApprovalQuery,
+    ) -> Result<Vec<TransferApproval>, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        let held_accounts = account_holders::table
+            .filter(account_holders::customer_id.eq(approval_query.customer_id))
+            .select(account_holders::account_id);
+
+        let mut query = transfer_approvals::table
+            .filter(transfer_approvals::account_id.eq_any(held_accounts))
+            .into_boxed();
+
+        if let Some(approval_status) = approval_query.approval_status {
+            query = query.filter(transfer_approvals::approval_status.eq(approval_status));
+        }
+
+        if let Some(transaction_id) = approval_query.transaction_id {
+            query = query.filter(transfer_approvals::transaction_id.eq(transaction_id));
+        }
+
+        query
+            .order(transfer_approvals::created_at.desc())
+            .limit(50)
+            .select(TransferApproval::as_select())
+            .load(&mut conn)
+            .map_err(|_| RepoError::Other)
+    }
+}
+
+impl RepoGetById<TransferApproval> for ApprovalsRepoImpl {
+    fn get_by_id(&self, approval_id: i32) -> Result<TransferApproval, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        transfer_approvals::table
+            .filter(transfer_approvals::id.eq(approval_id))
+            .select(TransferApproval::as_select())
+            .get_result(&mut conn)
+            .map_err(RepoError::from)
+    }
+}
+
+// oldest first, it's a trail
+impl RepoFind<TransferApprovalEvent, FindTransferApprovalEventQuery> for ApprovalsRepoImpl {
+    fn find(
+        &self,
+        event_query: FindTransferApprovalEventQuery,
+    ) -> Result<Vec<TransferApprovalEvent>, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        transfer_approval_events::table
+            .filter(transfer_approval_events::approval_id.eq(event_query.approval_id))
+            .order(transfer_approval_events::id.asc())
+            .select(TransferApprovalEvent::as_select())
+            .load(&mut conn)
+            .map_err(|_| RepoError::Other)
+    }
+}
+
+// records the decision and releases (approve) or fails (reject) the transfer together. one that's
+// run out of time can't be decided, even if the expiry run hasn't got to it yet
+impl RepoUpdate<TransferApproval, ApprovalDecision> for ApprovalsRepoImpl {
+    fn update(
+        &self,
+        approval_id: i32,
+        decision: ApprovalDecision,
+    ) -> Result<TransferApproval, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        conn.transaction(|conn| {
+            let approval = transfer_approvals::table
+                .filter(transfer_approvals::id.eq(approval_id))
+                .for_update()
+                .select(TransferApproval::as_select())
+                .get_result(conn)?;
+
+            let now = chrono::Utc::now().naive_utc();
+
+            if approval.approval_status != ApprovalStatus::Pending || approval.expires_at <= now {
+                return Err(RepoError::Conflict);
+            }
+
+            let approval_status = if decision.approve {
+                let transfer = transactions::table
+                    .filter(transactions::id.eq(approval.transaction_id))
+                    .filter(transactions::transaction_status.eq(TransactionStatus::PendingApproval))
+                    .select(Transaction::as_select())
+                    .get_result(conn)
+                    .optional()?
+                    .ok_or(RepoError::Conflict)?;
+
+                // goes as if it had never waited
+                let pending = set_transfer_status(conn, transfer.id, TransactionStatus::Pending)?;
+                release_transfer(conn, &pending)?;
+
+                ApprovalStatus::Approved
+            } else {
+                fail_transfer(conn, approval.transaction_id, now)?;
+                ApprovalStatus::Rejected
+            };
+
+            diesel::insert_into(transfer_approval_events::table)
+                .values(&NewTransferApprovalEvent {
+                    approval_id: approval.id,
+                    approval_status,
+                    note: decision.note,
+                    actor_type: ActorType::Customer,
+                    actor_id: decision.customer_id,
+                })
+                .execute(conn)?;
+
+            diesel::update(transfer_approvals::table.filter(transfer_approvals::id.eq(approval.id)))
+                .set((
+                    transfer_approvals::approval_status.eq(approval_status),
+                    transfer_approvals::decided_by.eq(Some(decision.customer_id)),
+                    transfer_approvals::decided_at.eq(Some(now)),
+                ))
+                .returning(TransferApproval::as_returning())
+                .get_result(conn)
+                .map_err(RepoError::from)
+        })
+    }
+}
+
+// each expiry in its own db transaction so one bad row doesn't hold the rest back
+impl RepoCreate<Vec<TransferApproval>, ApprovalExpiryRun> for ApprovalsRepoImpl {
+    fn create(&self, run: ApprovalExpiryRun) -> Result<Vec<TransferApproval>, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        let due: Vec<i32> = transfer_approvals::table
+            .filter(transfer_approvals::approval_status.eq(ApprovalStatus::Pending))
+            .filter(transfer_approvals::expires_at.le(run.now))
+            .order(transfer_approvals::expires_at.asc())
+            .select(transfer_approvals::id)
+            .load(&mut conn)
+            .map_err(|_| RepoError::Other)?;
+
+        let mut expired = vec![];
+        for approval_id in due {
+            let res = conn.transaction::<_, RepoError, _>(|conn| {
+                // decided since we looked
+                let Some(approval) = transfer_approvals::table
+                    .filter(transfer_approvals::id.eq(approval_id))
+                    .filter(transfer_approvals::approval_status.eq(ApprovalStatus::Pending))
+                    .for_update()
+                    .select(TransferApproval::as_select())
+                    .get_result(conn)
+                    .optional()?
+                else {
+                    return Ok(None);
+                };
+
+                fail_transfer(conn, approval.transaction_id, run.now)?;
+
+                diesel::insert_into(transfer_approval_events::table)
+                    .values(&NewTransferApprovalEvent {
+                        approval_id: approval.id,
+                        approval_status: ApprovalStatus::Expired,
+                        note: None,
+                        actor_type: ActorType::Staff,
+                        actor_id: run.staff_id,
+                    })
+                    .execute(conn)?;
+
+                diesel::update(
+                    transfer_approvals::table.filter(transfer_approvals::id.eq(approval.id)),
+                )
+                .set((
+                    transfer_approvals::approval_status.eq(ApprovalStatus::Expired),
+                    transfer_approvals::decided_at.eq(Some(run.now)),
+                ))
+                .returning(TransferApproval::as_returning())
+                .get_result(conn)
+                .map(Some)
+                .map_err(RepoError::from)
+            });
+
+            match res {
+                Ok(Some(approval)) => expired.push(approval),
+                Ok(None) => {}
+                Err(err) => println!("couldn't expire approval {}: {}", approval_id, err),
+            }
+        }
+
+        Ok(expired)
+    }
+}
+
+// the transfer never goes, same as a rejected fraud hold
+fn fail_transfer(
+    conn: &mut PgConnection,
+    transaction_id: i32,
+    now: chrono::NaiveDateTime,
+) -> Result<(), RepoError> {
+    diesel::update(
+        transactions::table
+            .filter(transactions::id.eq(transaction_id))
+            .filter(transactions::transaction_status.eq(TransactionStatus::PendingApproval)),
+    )
+    .set((
+        transactions::transaction_status.eq(TransactionStatus::Error),
+        transactions::date_end.eq(Some(now)),
+    ))
+    .execute(conn)?;
+
+    Ok(())
+}
@@ -0,0 +1,94 @@
+use diesel::{
+    prelude::*,
+    r2d2::{ConnectionManager, Pool},
+};
+
+use crate::{
+    error::RepoError,
+    models::{
+        audit::{AuditEntry, FindAuditQuery, NewAuditEntry, GENESIS_HASH},
+        schema::audit_log,
+    },
+    traits::{RepoCreate, RepoFind},
+};
+
+#[derive(Clone)]
+pub struct AuditRepoImpl {
+    pub pool: Pool<ConnectionManager<PgConnection>>,
+}
+
+impl AuditRepoImpl {
+    pub fn new(pool: Pool<ConnectionManager<PgConnection>>) -> AuditRepoImpl {
+        AuditRepoImpl { pool }
+    }
+}
+
+impl RepoCreate<AuditEntry, NewAuditEntry> for AuditRepoImpl {
+    fn create(&self, mut new_entry: NewAuditEntry) -> Result<AuditEntry, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        conn.transaction(|conn| {
+            // serialise appends so two entries can't link to the same predecessor
+            diesel::sql_query("LOCK TABLE audit_log IN SHARE ROW EXCLUSIVE MODE").execute(conn)?;
+
+            let prev_hash = audit_log::table
+                .select(audit_log::hash)
+                .order(audit_log::id.desc())
+                .first::<String>(conn)
+                .optional()?
+                .unwrap_or_else(|| GENESIS_HASH.to_string());
+
+            new_entry.seal(prev_hash);
+
+            diesel::insert_into(audit_log::table)
+                .values(&new_entry)
+                .returning(AuditEntry::as_returning())
+                .get_result(conn)
+        })
+        .map_err(|_| RepoError::Other)
+    }
+}
+
+impl RepoFind<AuditEntry, FindAuditQuery> for AuditRepoImpl {
+    fn find(&self, audit_query: FindAuditQuery) -> Result<Vec<AuditEntry>, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        let mut query = audit_log::table.into_boxed();
+        if let Some(actor_type) = audit_query.actor_type {
+            query = query.filter(audit_log::actor_type.eq(actor_type));
+        }
+
+        if let Some(actor_id) = audit_query.actor_id {
+            query = query.filter(audit_log::actor_id.eq(actor_id));
+        }
+
+        if let Some(action) = audit_query.action {
+            query = query.filter(audit_log::action.eq(action));
+        }
+
+        if let Some(entity_type) = audit_query.entity_type {
+            query = query.filter(audit_log::entity_type.eq(entity_type));
+        }
+
+        if let Some(entity_id) = audit_query.entity_id {
+            query = query.filter(audit_log::entity_id.eq(entity_id));
+        }
+
+        if let Some(from_id) = audit_query.from_id {
+            query = query.filter(audit_log::id.ge(from_id));
+        }
+
+        query
+            .order(audit_log::id.asc())
+            .limit(audit_query.limit)
+            .select(AuditEntry::as_select())
+            .load(&mut conn)
+            .map_err(|_| RepoError::Other)
+    }
+}
@@ -0,0 +1,363 @@
+use std::sync::Arc;
+
+use chrono::NaiveDate;
+use diesel::{
+    prelude::*,
+    r2d2::{ConnectionManager, Pool},
+};
+
+use crate::{
+    error::RepoError,
+    fraud::{FraudEngine, ScreeningContext},
+    models::{
+        account::Account,
+        account_holder::{AccountPermission, HolderRole, NewSignedTransfer},
+        automation::{
+            AutomationRule, AutomationRuleKind, AutomationRuleUpdate, AutomationRun,
+            AutomationTrigger, FindAutomationRuleQuery, FindAutomationRunQuery, NewAutomationRule,
+            NewAutomationRun,
+        },
+        fraud_review::NewHeldTransaction,
+        schema::{account_holders, accounts, automation_rules, automation_runs, transactions},
+        transaction::Transaction,
+    },
+    repository::util::{
+        create_held_transfer, create_signed_transfer, lock_transfer_accounts, outgoing_since,
+        transfer_limits, transfer_usage,
+    },
+    traits::{RepoCreate, RepoDeleteById, RepoFind, RepoGetById, RepoUpdate},
+    transfers::{check_transfer, screening_since, TransferCheck},
+    util::start_of_day,
+};
+
+const MAX_RUNS: i64 = 100;
+
+#[derive(Clone)]
+pub struct AutomationRepoImpl {
+    pub pool: Pool<ConnectionManager<PgConnection>>,
+    // the same screening the customer's own transfers get
+    pub fraud_engine: Arc<FraudEngine>,
+}
+
+impl AutomationRepoImpl {
+    pub fn new(
+        pool: Pool<ConnectionManager<PgConnection>>,
+        fraud_engine: Arc<FraudEngine>,
+    ) -> AutomationRepoImpl {
+        AutomationRepoImpl { pool, fraud_engine }
+    }
+}
+
+// one rule firing in its own db transaction, so a rule that can't run doesn't stop the others.
+// triggered_by is the debit for a round up, otherwise it's the end of day sweep for run_date
+fn run_rule(
+    conn: &mut PgConnection,
+    fraud_engine: &FraudEngine,
+    rule: &AutomationRule,
+    triggered_by: Option<&Transaction>,
+    run_date: Option<NaiveDate>,
+) -> Result<Option<AutomationRun>, RepoError> {
+    conn.transaction(|conn| {
+        let mut already_ran = automation_runs::table
+            .filter(automation_runs::rule_id.eq(rule.id))
+            .into_boxed();
+        already_ran = match (triggered_by, run_date) {
+            (Some(tr), _) => {
+                already_ran.filter(automation_runs::trigger_transaction_id.eq(Some(tr.id)))
+            }
+            (None, date) => already_ran.filter(automation_runs::run_date.eq(date)),
+        };
+        if already_ran.count().get_result::<i64>(conn)? > 0 {
+            return Ok(None);
+        }
+
+        // whoever set the rule up may since have been removed from the account
+        let Some((from, holder_role)) = accounts::table
+            .inner_join(account_holders::table)
+            .filter(accounts::id.eq(rule.from_account_id))
+            .filter(account_holders::customer_id.eq(rule.customer_id))
+            .select((Account::as_select(), account_holders::holder_role))
+            .first::<(Account, HolderRole)>(conn)
+            .optional()?
+        else {
+            return Ok(None);
+        };
+        if !holder_role.allows(AccountPermission::Transact) {
+            return Ok(None);
+        }
+        let to = accounts::table
+            .filter(accounts::id.eq(rule.to_account_id))
+            .select(Account::as_select())
+            .get_result(conn)?;
+
+        let amount_cents = match triggered_by {
+            Some(tr) => rule.round_up_cents(tr.amount_cents),
+            None => rule.sweep_cents(&from),
+        };
+        // rules never dip into an overdraft, only a customer's own transfer can
+        if amount_cents == 0 || from.available_balance_cents < amount_cents {
+            return Ok(None);
+        }
+
+        // a rule's transfer goes through the same checks, signing and approval as one the customer
+        // makes themselves, signed by the customer who set the rule up
+        let new_transaction = rule.transfer(&from, &to, amount_cents);
+        lock_transfer_accounts(
+            conn,
+            new_transaction.customer_id,
+            &new_transaction.to_number,
+            &new_transaction.to_bsb,
+        )?;
+        let now = chrono::Utc::now().naive_utc();
+        let limits = transfer_limits(conn, new_transaction.customer_id)?;
+        let usage = transfer_usage(conn, new_transaction.customer_id, start_of_day(now))?;
+        let history = outgoing_since(conn, &from, screening_since(now))?;
+
+        let check = check_transfer(
+            &limits,
+            &usage,
+            fraud_engine,
+            &ScreeningContext {
+                transaction: &new_transaction,
+                account: &from,
+                history: &history,
+                payee: None,
+                now,
+            },
+        );
+
+        let transaction = match check {
+            TransferCheck::Allow => create_signed_transfer(
+                conn,
+                &NewSignedTransfer {
+                    transaction: new_transaction,
+                    signed_by: rule.customer_id,
+                    step_up: None,
+                },
+            )?,
+            TransferCheck::Hold(reasons) => create_held_transfer(
+                conn,
+                &NewHeldTransaction {
+                    transaction: new_transaction,
+                    reasons,
+                    signed_by: rule.customer_id,
+                    step_up: None,
+                },
+            )?,
+            // skipped, not failed. a sweep tries again tomorrow, a round up waits for the next debit
+            TransferCheck::LimitExceeded(_) | TransferCheck::Reject(_) => {
+                println!("automation rule {} stopped by {:?}", rule.id, check);
+                return Ok(None);
+            }
+        };
+
+        let run = diesel::insert_into(automation_runs::table)
+            .values(&NewAutomationRun {
+                rule_id: rule.id,
+                customer_id: rule.customer_id,
+                trigger_transaction_id: triggered_by.map(|tr| tr.id),
+                run_date,
+                transaction_id: transaction.id,
+                amount_cents,
+            })
+            .returning(AutomationRun::as_returning())
+            .get_result(conn)?;
+
+        Ok(Some(run))
+    })
+}
+
+impl RepoCreate<AutomationRule, NewAutomationRule> for AutomationRepoImpl {
+    fn create(&self, new_rule: NewAutomationRule) -> Result<AutomationRule, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        diesel::insert_into(automation_rules::table)
+            .values(&new_rule)
+            .returning(AutomationRule::as_returning())
+            .get_result(&mut conn)
+            .map_err(|_| RepoError::Other)
+    }
+}
+
+impl RepoFind<AutomationRule, FindAutomationRuleQuery> for AutomationRepoImpl {
+    fn find(&self, rule_query: FindAutomationRuleQuery) -> Result<Vec<AutomationRule>, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        automation_rules::table
+            .filter(automation_rules::customer_id.eq(rule_query.customer_id))
+            .order(automation_rules::id.asc())
+            .select(AutomationRule::as_select())
+            .load(&mut conn)
+            .map_err(|_| RepoError::Other)
+    }
+}
+
+impl RepoGetById<AutomationRule> for AutomationRepoImpl {
+    fn get_by_id(&self, rule_id: i32) -> Result<AutomationRule, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        automation_rules::table
+            .filter(automation_rules::id.eq(rule_id))
+            .select(AutomationRule::as_select())
+            .get_result(&mut conn)
+            .map_err(RepoError::from)
+    }
+}
+
+impl RepoUpdate<AutomationRule, AutomationRuleUpdate> for AutomationRepoImpl {
+    fn update(
+        &self,
+        rule_id: i32,
+        update: AutomationRuleUpdate,
+    ) -> Result<AutomationRule, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        conn.transaction(|conn| {
+            let rule = automation_rules::table
+                .filter(automation_rules::id.eq(rule_id))
+                .for_update()
+                .select(AutomationRule::as_select())
+                .get_result(conn)?;
+
+            diesel::update(automation_rules::table.filter(automation_rules::id.eq(rule.id)))
+                .set((
+                    automation_rules::enabled.eq(update.enabled.unwrap_or(rule.enabled)),
+                    automation_rules::round_to_cents
+                        .eq(update.round_to_cents.or(rule.round_to_cents)),
+                    automation_rules::threshold_cents
+                        .eq(update.threshold_cents.or(rule.threshold_cents)),
+                    automation_rules::updated_at.eq(diesel::dsl::now),
+                ))
+                .returning(AutomationRule::as_returning())
+                .get_result(conn)
+                .map_err(RepoError::from)
+        })
+    }
+}
+
+// runs go with it, the transfers it made stay
+impl RepoDeleteById<AutomationRule> for AutomationRepoImpl {
+    fn delete_by_id(&self, rule_id: i32) -> Result<(), RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        diesel::delete(automation_rules::table.filter(automation_rules::id.eq(rule_id)))
+            .execute(&mut conn)
+            .map_err(|_| RepoError::Other)?;
+
+        Ok(())
+    }
+}
+
+impl RepoFind<AutomationRun, FindAutomationRunQuery> for AutomationRepoImpl {
+    fn find(&self, run_query: FindAutomationRunQuery) -> Result<Vec<AutomationRun>, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        let mut query = automation_runs::table
+            .filter(automation_runs::customer_id.eq(run_query.customer_id))
+            .into_boxed();
+        if let Some(rule_id) = run_query.rule_id {
+            query = query.filter(automation_runs::rule_id.eq(rule_id));
+        }
+
+        query
+            .order(automation_runs::id.desc())
+            .limit(MAX_RUNS)
+            .select(AutomationRun::as_select())
+            .load(&mut conn)
+            .map_err(|_| RepoError::Other)
+    }
+}
+
+// fires every enabled rule listening for the trigger, returns the runs that moved money
+impl RepoCreate<Vec<AutomationRun>, AutomationTrigger> for AutomationRepoImpl {
+    fn create(&self, trigger: AutomationTrigger) -> Result<Vec<AutomationRun>, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        let (rules, triggered_by, run_date) = match trigger {
+            AutomationTrigger::Settled { transaction_id } => {
+                let transaction = transactions::table
+                    .filter(transactions::id.eq(transaction_id))
+                    .select(Transaction::as_select())
+                    .get_result(&mut conn)?;
+
+                // a rule's own transfers don't set off more rules
+                let made_by_rule = automation_runs::table
+                    .filter(automation_runs::transaction_id.eq(transaction.id))
+                    .count()
+                    .get_result::<i64>(&mut conn)?
+                    > 0;
+
+                if !transaction.from_us
+                    || !transaction.is_settled()
+                    || transaction.reversal_of.is_some()
+                    || made_by_rule
+                {
+                    return Ok(vec![]);
+                }
+
+                let rules = automation_rules::table
+                    .inner_join(
+                        accounts::table.on(accounts::id.eq(automation_rules::from_account_id)),
+                    )
+                    .filter(automation_rules::customer_id.eq(transaction.customer_id))
+                    .filter(automation_rules::kind.eq(AutomationRuleKind::RoundUp))
+                    .filter(automation_rules::enabled)
+                    .filter(accounts::account_number.eq(&transaction.from_number))
+                    .filter(accounts::bsb.eq(&transaction.from_bsb))
+                    .order(automation_rules::id.asc())
+                    .select(AutomationRule::as_select())
+                    .load(&mut conn)?;
+
+                (rules, Some(transaction), None)
+            }
+            AutomationTrigger::EndOfDay { day } => {
+                let rules = automation_rules::table
+                    .filter(automation_rules::kind.eq(AutomationRuleKind::SweepAbove))
+                    .filter(automation_rules::enabled)
+                    .order(automation_rules::id.asc())
+                    .select(AutomationRule::as_select())
+                    .load(&mut conn)?;
+
+                (rules, None, Some(day))
+            }
+        };
+
+        let mut runs = vec![];
+        for rule in rules.iter() {
+            match run_rule(
+                &mut conn,
+                &self.fraud_engine,
+                rule,
+                triggered_by.as_ref(),
+                run_date,
+            ) {
+                Ok(Some(run)) => runs.push(run),
+                Ok(None) => {}
+                Err(_) => println!("automation rule {} couldn't run", rule.id),
+            }
+        }
+
+        Ok(runs)
+    }
+}
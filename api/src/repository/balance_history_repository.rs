@@ -0,0 +1,123 @@
+use diesel::{
+    prelude::*,
+    r2d2::{ConnectionManager, Pool},
+};
+
+use crate::{
+    error::RepoError,
+    models::{
+        account::Account,
+        balance_snapshot::{
+            backfill_balances, BalanceBackfill, BalanceBackfillSummary, BalanceSnapshot,
+            FindBalanceSnapshotQuery,
+        },
+        schema::{account_balance_snapshots, accounts, transactions},
+        transaction::Transaction,
+    },
+    traits::{RepoCreate, RepoFind},
+};
+
+#[derive(Clone)]
+pub struct BalanceHistoryRepoImpl {
+    pub pool: Pool<ConnectionManager<PgConnection>>,
+}
+
+impl BalanceHistoryRepoImpl {
+    pub fn new(pool: Pool<ConnectionManager<PgConnection>>) -> BalanceHistoryRepoImpl {
+        BalanceHistoryRepoImpl { pool }
+    }
+}
+
+impl RepoFind<BalanceSnapshot, FindBalanceSnapshotQuery> for BalanceHistoryRepoImpl {
+    fn find(&self, query: FindBalanceSnapshotQuery) -> Result<Vec<BalanceSnapshot>, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        // where the balance stood going into the range
+        let opening = account_balance_snapshots::table
+            .filter(account_balance_snapshots::account_id.eq(query.account_id))
+            .filter(account_balance_snapshots::snapshot_date.lt(query.from))
+            .order(account_balance_snapshots::snapshot_date.desc())
+            .select(BalanceSnapshot::as_select())
+            .first(&mut conn)
+            .optional()?;
+
+        let in_range = account_balance_snapshots::table
+            .filter(account_balance_snapshots::account_id.eq(query.account_id))
+            .filter(account_balance_snapshots::snapshot_date.ge(query.from))
+            .filter(account_balance_snapshots::snapshot_date.le(query.to))
+            .order(account_balance_snapshots::snapshot_date.asc())
+            .select(BalanceSnapshot::as_select())
+            .load(&mut conn)?;
+
+        Ok(opening.into_iter().chain(in_range).collect())
+    }
+}
+
+// rebuilds running balances and daily snapshots for an account from its transactions
+impl RepoCreate<BalanceBackfillSummary, BalanceBackfill> for BalanceHistoryRepoImpl {
+    fn create(&self, backfill: BalanceBackfill) -> Result<BalanceBackfillSummary, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        conn.transaction(|conn| {
+            // holds the balance still while we work back from it
+            let account = accounts::table
+                .filter(accounts::id.eq(backfill.account_id))
+                .for_update()
+                .select(Account::as_select())
+                .get_result(conn)?;
+
+            // the account's side of each entry, same as finding transactions by account
+            let history = transactions::table
+                .filter(transactions::customer_id.eq(account.customer_id))
+                .filter(
+                    transactions::from_us
+                        .and(transactions::from_number.eq(&account.account_number))
+                        .and(transactions::from_bsb.eq(&account.bsb))
+                        .or(transactions::from_us
+                            .eq(false)
+                            .and(transactions::to_number.eq(&account.account_number))
+                            .and(transactions::to_bsb.eq(&account.bsb))),
+                )
+                .order((transactions::date_start.desc(), transactions::id.desc()))
+                .select(Transaction::as_select())
+                .load(conn)?;
+
+            let backfilled = backfill_balances(&account, &history, chrono::Utc::now().naive_utc());
+
+            for (transaction_id, running_balance_cents) in backfilled.running.iter() {
+                diesel::update(transactions::table.filter(transactions::id.eq(transaction_id)))
+                    .set(transactions::running_balance_cents.eq(Some(running_balance_cents)))
+                    .execute(conn)?;
+            }
+
+            for snapshot in backfilled.snapshots.iter() {
+                diesel::insert_into(account_balance_snapshots::table)
+                    .values(snapshot)
+                    .on_conflict((
+                        account_balance_snapshots::account_id,
+                        account_balance_snapshots::snapshot_date,
+                    ))
+                    .do_update()
+                    .set((
+                        account_balance_snapshots::balance_cents.eq(snapshot.balance_cents),
+                        account_balance_snapshots::available_balance_cents
+                            .eq(snapshot.available_balance_cents),
+                        account_balance_snapshots::updated_at.eq(snapshot.updated_at),
+                    ))
+                    .execute(conn)?;
+            }
+
+            Ok(BalanceBackfillSummary {
+                account_id: account.id,
+                transactions_updated: backfilled.running.len(),
+                snapshots_written: backfilled.snapshots.len(),
+            })
+        })
+    }
+}
@@ -0,0 +1,255 @@
+use diesel::{
+    prelude::*,
+    r2d2::{ConnectionManager, Pool},
+    result::DatabaseErrorKind,
+};
+
+use crate::{
+    error::RepoError,
+    models::{
+        account::Account,
+        bucket::{
+            AccountBucket, BucketMove, BucketUpdate, FindBucketQuery, NewAccountBucket,
+            DEFAULT_BUCKET_NAME,
+        },
+        schema::{account_buckets, accounts},
+    },
+    traits::{RepoCreate, RepoDeleteById, RepoFind, RepoGetById, RepoUpdate},
+};
+
+#[derive(Clone)]
+pub struct BucketsRepoImpl {
+    pub pool: Pool<ConnectionManager<PgConnection>>,
+}
+
+impl BucketsRepoImpl {
+    pub fn new(pool: Pool<ConnectionManager<PgConnection>>) -> BucketsRepoImpl {
+        BucketsRepoImpl { pool }
+    }
+}
+
+fn duplicate_name(err: diesel::result::Error) -> RepoError {
+    match err {
+        // account already has a bucket called that
+        diesel::result::Error::DatabaseError(DatabaseErrorKind::UniqueViolation, _) => {
+            RepoError::Conflict
+        }
+        err => RepoError::from(err),
+    }
+}
+
+fn set_bucket_balance(
+    conn: &mut PgConnection,
+    bucket_id: i32,
+    balance_cents: i64,
+) -> QueryResult<AccountBucket> {
+    diesel::update(account_buckets::table.filter(account_buckets::id.eq(bucket_id)))
+        .set((
+            account_buckets::balance_cents.eq(balance_cents),
+            account_buckets::updated_at.eq(diesel::dsl::now),
+        ))
+        .returning(AccountBucket::as_returning())
+        .get_result(conn)
+}
+
+// the account's buckets, locked. the first bucket on an account brings the default one with it,
+// holding the whole balance
+fn lock_buckets(conn: &mut PgConnection, account_id: i32) -> QueryResult<Vec<AccountBucket>> {
+    let account = accounts::table
+        .filter(accounts::id.eq(account_id))
+        .for_update()
+        .select(Account::as_select())
+        .get_result(conn)?;
+
+    let buckets = account_buckets::table
+        .filter(account_buckets::account_id.eq(account.id))
+        .for_update()
+        .select(AccountBucket::as_select())
+        .load(conn)?;
+
+    if !buckets.is_empty() {
+        return Ok(buckets);
+    }
+
+    let default = diesel::insert_into(account_buckets::table)
+        .values((
+            account_buckets::account_id.eq(account.id),
+            account_buckets::name.eq(DEFAULT_BUCKET_NAME),
+            account_buckets::balance_cents.eq(account.balance_cents),
+            account_buckets::is_default.eq(true),
+        ))
+        .returning(AccountBucket::as_returning())
+        .get_result(conn)?;
+
+    Ok(vec![default])
+}
+
+// the initial balance comes out of the default bucket
+impl RepoCreate<AccountBucket, NewAccountBucket> for BucketsRepoImpl {
+    fn create(&self, new_bucket: NewAccountBucket) -> Result<AccountBucket, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        conn.transaction(|conn| {
+            let buckets = lock_buckets(conn, new_bucket.account_id)?;
+            let default = buckets
+                .iter()
+                .find(|b| b.is_default)
+                .ok_or(RepoError::Other)?;
+
+            if new_bucket.balance_cents > default.balance_cents {
+                return Err(RepoError::Conflict);
+            }
+            if new_bucket.balance_cents > 0 {
+                set_bucket_balance(
+                    conn,
+                    default.id,
+                    default.balance_cents - new_bucket.balance_cents,
+                )?;
+            }
+
+            diesel::insert_into(account_buckets::table)
+                .values(&new_bucket)
+                .returning(AccountBucket::as_returning())
+                .get_result(conn)
+                .map_err(duplicate_name)
+        })
+    }
+}
+
+// default first, then oldest first
+impl RepoFind<AccountBucket, FindBucketQuery> for BucketsRepoImpl {
+    fn find(&self, bucket_query: FindBucketQuery) -> Result<Vec<AccountBucket>, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        account_buckets::table
+            .filter(account_buckets::account_id.eq(bucket_query.account_id))
+            .order((
+                account_buckets::is_default.desc(),
+                account_buckets::id.asc(),
+            ))
+            .select(AccountBucket::as_select())
+            .load(&mut conn)
+            .map_err(|_| RepoError::Other)
+    }
+}
+
+impl RepoGetById<AccountBucket> for BucketsRepoImpl {
+    fn get_by_id(&self, bucket_id: i32) -> Result<AccountBucket, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        account_buckets::table
+            .filter(account_buckets::id.eq(bucket_id))
+            .select(AccountBucket::as_select())
+            .get_result(&mut conn)
+            .map_err(RepoError::from)
+    }
+}
+
+impl RepoUpdate<AccountBucket, BucketUpdate> for BucketsRepoImpl {
+    fn update(&self, bucket_id: i32, update: BucketUpdate) -> Result<AccountBucket, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        conn.transaction(|conn| {
+            let bucket = account_buckets::table
+                .filter(account_buckets::id.eq(bucket_id))
+                .for_update()
+                .select(AccountBucket::as_select())
+                .get_result(conn)?;
+
+            diesel::update(account_buckets::table.filter(account_buckets::id.eq(bucket.id)))
+                .set((
+                    account_buckets::name.eq(update.name.unwrap_or(bucket.name)),
+                    account_buckets::goal_cents.eq(update.goal_cents.unwrap_or(bucket.goal_cents)),
+                    account_buckets::target_date
+                        .eq(update.target_date.unwrap_or(bucket.target_date)),
+                    account_buckets::updated_at.eq(diesel::dsl::now),
+                ))
+                .returning(AccountBucket::as_returning())
+                .get_result(conn)
+                .map_err(duplicate_name)
+        })
+    }
+}
+
+// whatever's left in the bucket goes back to the default one
+impl RepoDeleteById<AccountBucket> for BucketsRepoImpl {
+    fn delete_by_id(&self, bucket_id: i32) -> Result<(), RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        conn.transaction(|conn| {
+            let Some(account_id) = account_buckets::table
+                .filter(account_buckets::id.eq(bucket_id))
+                .select(account_buckets::account_id)
+                .get_result::<i32>(conn)
+                .optional()?
+            else {
+                return Ok(());
+            };
+
+            let buckets = lock_buckets(conn, account_id)?;
+            let (Some(bucket), Some(default)) = (
+                buckets.iter().find(|b| b.id == bucket_id),
+                buckets.iter().find(|b| b.is_default),
+            ) else {
+                return Ok(());
+            };
+            if bucket.is_default {
+                return Err(RepoError::Conflict);
+            }
+
+            set_bucket_balance(
+                conn,
+                default.id,
+                default.balance_cents + bucket.balance_cents,
+            )?;
+            diesel::delete(account_buckets::table.filter(account_buckets::id.eq(bucket.id)))
+                .execute(conn)?;
+
+            Ok(())
+        })
+    }
+}
+
+// returns both buckets, from then to
+impl RepoCreate<Vec<AccountBucket>, BucketMove> for BucketsRepoImpl {
+    fn create(&self, bucket_move: BucketMove) -> Result<Vec<AccountBucket>, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        conn.transaction(|conn| {
+            let buckets = lock_buckets(conn, bucket_move.account_id)?;
+            let (Some(from), Some(to)) = (
+                buckets.iter().find(|b| b.id == bucket_move.from_bucket_id),
+                buckets.iter().find(|b| b.id == bucket_move.to_bucket_id),
+            ) else {
+                return Err(RepoError::NotFound);
+            };
+
+            if bucket_move.amount_cents > from.balance_cents {
+                return Err(RepoError::Conflict);
+            }
+
+            Ok(vec![
+                set_bucket_balance(conn, from.id, from.balance_cents - bucket_move.amount_cents)?,
+                set_bucket_balance(conn, to.id, to.balance_cents + bucket_move.amount_cents)?,
+            ])
+        })
+    }
+}
@@ -0,0 +1,315 @@
+use diesel::{
+    prelude::*,
+    r2d2::{ConnectionManager, Pool},
+};
+
+use crate::{
+    categories::Categoriser,
+    error::RepoError,
+    models::{
+        budget::{
+            month_start, next_month_start, Budget, BudgetAlert, BudgetAlertDelivered, BudgetCheck,
+            BudgetProgress, BudgetUpdate, FindBudgetAlertQuery, FindBudgetProgressQuery,
+            FindBudgetQuery, NewBudget, NewBudgetAlert,
+        },
+        category::{CategoryOverride, FindSpendingQuery},
+        schema::{budget_alerts, budgets, category_overrides, transactions},
+        transaction::Transaction,
+    },
+    repository::util::find_spending,
+    traits::{RepoCreate, RepoDeleteById, RepoFind, RepoGetById, RepoUpdate},
+};
+
+#[derive(Clone)]
+pub struct BudgetsRepoImpl {
+    pub pool: Pool<ConnectionManager<PgConnection>>,
+    // spend against a category budget has to be counted the same way the customer sees it
+    pub categoriser: Categoriser,
+}
+
+impl BudgetsRepoImpl {
+    pub fn new(
+        pool: Pool<ConnectionManager<PgConnection>>,
+        categoriser: Categoriser,
+    ) -> BudgetsRepoImpl {
+        BudgetsRepoImpl { pool, categoriser }
+    }
+}
+
+// the customer's spending and overrides for the month day falls in
+fn month_spending(
+    conn: &mut PgConnection,
+    customer_id: i32,
+    day: chrono::NaiveDate,
+) -> QueryResult<(Vec<Transaction>, Vec<CategoryOverride>)> {
+    let spending = find_spending(
+        conn,
+        &FindSpendingQuery {
+            customer_id,
+            account_number: None,
+            from: month_start(day).and_hms_opt(0, 0, 0).unwrap(),
+            to: next_month_start(day).and_hms_opt(0, 0, 0).unwrap(),
+        },
+    )?;
+
+    let overrides = category_overrides::table
+        .filter(category_overrides::customer_id.eq(customer_id))
+        .select(CategoryOverride::as_select())
+        .load(conn)?;
+
+    Ok((spending, overrides))
+}
+
+impl RepoCreate<Budget, NewBudget> for BudgetsRepoImpl {
+    fn create(&self, new_budget: NewBudget) -> Result<Budget, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        diesel::insert_into(budgets::table)
+            .values(&new_budget)
+            .returning(Budget::as_returning())
+            .get_result(&mut conn)
+            .map_err(|_| RepoError::Other)
+    }
+}
+
+impl RepoFind<Budget, FindBudgetQuery> for BudgetsRepoImpl {
+    fn find(&self, budget_query: FindBudgetQuery) -> Result<Vec<Budget>, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        budgets::table
+            .filter(budgets::customer_id.eq(budget_query.customer_id))
+            .order(budgets::name.asc())
+            .select(Budget::as_select())
+            .load(&mut conn)
+            .map_err(|_| RepoError::Other)
+    }
+}
+
+impl RepoGetById<Budget> for BudgetsRepoImpl {
+    fn get_by_id(&self, budget_id: i32) -> Result<Budget, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        budgets::table
+            .filter(budgets::id.eq(budget_id))
+            .select(Budget::as_select())
+            .get_result(&mut conn)
+            .map_err(|err| match err {
+                diesel::result::Error::NotFound => RepoError::NotFound,
+                _ => RepoError::Other,
+            })
+    }
+}
+
+impl RepoUpdate<Budget, BudgetUpdate> for BudgetsRepoImpl {
+    fn update(&self, budget_id: i32, update: BudgetUpdate) -> Result<Budget, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        conn.transaction(|conn| {
+            let budget = budgets::table
+                .filter(budgets::id.eq(budget_id))
+                .for_update()
+                .select(Budget::as_select())
+                .get_result(conn)?;
+
+            diesel::update(budgets::table.filter(budgets::id.eq(budget.id)))
+                .set((
+                    budgets::name.eq(update.name.unwrap_or(budget.name)),
+                    budgets::amount_cents.eq(update.amount_cents.unwrap_or(budget.amount_cents)),
+                    budgets::alert_thresholds
+                        .eq(update.alert_thresholds.unwrap_or(budget.alert_thresholds)),
+                    budgets::updated_at.eq(diesel::dsl::now),
+                ))
+                .returning(Budget::as_returning())
+                .get_result(conn)
+                .map_err(RepoError::from)
+        })
+    }
+}
+
+// alerts go with it
+impl RepoDeleteById<Budget> for BudgetsRepoImpl {
+    fn delete_by_id(&self, budget_id: i32) -> Result<(), RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        diesel::delete(budgets::table.filter(budgets::id.eq(budget_id)))
+            .execute(&mut conn)
+            .map_err(|_| RepoError::Other)?;
+
+        Ok(())
+    }
+}
+
+impl RepoFind<BudgetProgress, FindBudgetProgressQuery> for BudgetsRepoImpl {
+    fn find(
+        &self,
+        progress_query: FindBudgetProgressQuery,
+    ) -> Result<Vec<BudgetProgress>, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        let mut query = budgets::table
+            .filter(budgets::customer_id.eq(progress_query.customer_id))
+            .into_boxed();
+
+        if let Some(budget_id) = progress_query.budget_id {
+            query = query.filter(budgets::id.eq(budget_id));
+        }
+
+        let budgets = query
+            .order(budgets::name.asc())
+            .select(Budget::as_select())
+            .load(&mut conn)?;
+
+        if budgets.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let (spending, overrides) =
+            month_spending(&mut conn, progress_query.customer_id, progress_query.day)?;
+
+        Ok(budgets
+            .into_iter()
+            .map(|budget| {
+                let spent_cents =
+                    budget.spent_cents(&spending, |tr| self.categoriser.categorise(tr, &overrides));
+                BudgetProgress {
+                    budget,
+                    period_start: month_start(progress_query.day),
+                    spent_cents,
+                }
+            })
+            .collect())
+    }
+}
+
+// raises any thresholds the transaction has taken its budgets over, returns only the new alerts
+impl RepoCreate<Vec<BudgetAlert>, BudgetCheck> for BudgetsRepoImpl {
+    fn create(&self, check: BudgetCheck) -> Result<Vec<BudgetAlert>, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        conn.transaction(|conn| {
+            let transaction = transactions::table
+                .filter(transactions::id.eq(check.transaction_id))
+                .select(Transaction::as_select())
+                .get_result(conn)?;
+
+            if !transaction.from_us || !transaction.is_settled() {
+                return Ok(vec![]);
+            }
+
+            // one check at a time per customer so a threshold can't be raised twice
+            let budgets = budgets::table
+                .filter(budgets::customer_id.eq(transaction.customer_id))
+                .for_update()
+                .select(Budget::as_select())
+                .load(conn)?;
+
+            if budgets.is_empty() {
+                return Ok(vec![]);
+            }
+
+            let day = transaction.date_start.date();
+            let (spending, overrides) = month_spending(conn, transaction.customer_id, day)?;
+
+            // a transfer between the customer's own accounts isn't in their spending
+            if !spending.iter().any(|tr| tr.id == transaction.id) {
+                return Ok(vec![]);
+            }
+
+            let category = self.categoriser.categorise(&transaction, &overrides);
+            let mut raised = vec![];
+
+            for budget in budgets
+                .iter()
+                .filter(|budget| budget.covers(&transaction, category))
+            {
+                let spent_cents =
+                    budget.spent_cents(&spending, |tr| self.categoriser.categorise(tr, &overrides));
+
+                for threshold_percent in budget.crossed_thresholds(spent_cents) {
+                    let alert = diesel::insert_into(budget_alerts::table)
+                        .values(NewBudgetAlert {
+                            budget_id: budget.id,
+                            customer_id: budget.customer_id,
+                            period_start: month_start(day),
+                            threshold_percent,
+                            spent_cents,
+                            budget_cents: budget.amount_cents,
+                            transaction_id: Some(transaction.id),
+                        })
+                        .on_conflict_do_nothing()
+                        .returning(BudgetAlert::as_returning())
+                        .get_result(conn)
+                        .optional()?;
+
+                    raised.extend(alert);
+                }
+            }
+
+            Ok(raised)
+        })
+    }
+}
+
+impl RepoFind<BudgetAlert, FindBudgetAlertQuery> for BudgetsRepoImpl {
+    fn find(&self, alert_query: FindBudgetAlertQuery) -> Result<Vec<BudgetAlert>, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        let mut query = budget_alerts::table
+            .filter(budget_alerts::customer_id.eq(alert_query.customer_id))
+            .into_boxed();
+
+        if let Some(budget_id) = alert_query.budget_id {
+            query = query.filter(budget_alerts::budget_id.eq(budget_id));
+        }
+
+        query
+            .order(budget_alerts::created_at.desc())
+            .limit(50)
+            .select(BudgetAlert::as_select())
+            .load(&mut conn)
+            .map_err(|_| RepoError::Other)
+    }
+}
+
+impl RepoUpdate<BudgetAlert, BudgetAlertDelivered> for BudgetsRepoImpl {
+    fn update(
+        &self,
+        alert_id: i32,
+        delivered: BudgetAlertDelivered,
+    ) -> Result<BudgetAlert, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        diesel::update(budget_alerts::table.filter(budget_alerts::id.eq(alert_id)))
+            .set(budget_alerts::delivered_at.eq(Some(delivered.delivered_at)))
+            .returning(BudgetAlert::as_returning())
+            .get_result(&mut conn)
+            .map_err(RepoError::from)
+    }
+}
@@ -0,0 +1,102 @@
+use diesel::{
+    prelude::*,
+    r2d2::{ConnectionManager, Pool},
+};
+
+use crate::{
+    error::RepoError,
+    models::{
+        category::{CategoryOverride, FindCategoryOverrideQuery, NewCategoryOverride},
+        schema::category_overrides,
+    },
+    traits::{RepoCreate, RepoDeleteById, RepoFind, RepoGetById},
+};
+
+#[derive(Clone)]
+pub struct CategoryOverridesRepoImpl {
+    pub pool: Pool<ConnectionManager<PgConnection>>,
+}
+
+impl CategoryOverridesRepoImpl {
+    pub fn new(pool: Pool<ConnectionManager<PgConnection>>) -> CategoryOverridesRepoImpl {
+        CategoryOverridesRepoImpl { pool }
+    }
+}
+
+// replaces the category if the customer already has an override for the account
+impl RepoCreate<CategoryOverride, NewCategoryOverride> for CategoryOverridesRepoImpl {
+    fn create(&self, new_override: NewCategoryOverride) -> Result<CategoryOverride, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        diesel::insert_into(category_overrides::table)
+            .values(&new_override)
+            .on_conflict((
+                category_overrides::customer_id,
+                category_overrides::bsb,
+                category_overrides::account_number,
+            ))
+            .do_update()
+            .set((
+                category_overrides::category.eq(new_override.category),
+                category_overrides::updated_at.eq(diesel::dsl::now),
+            ))
+            .returning(CategoryOverride::as_returning())
+            .get_result(&mut conn)
+            .map_err(|_| RepoError::Other)
+    }
+}
+
+impl RepoFind<CategoryOverride, FindCategoryOverrideQuery> for CategoryOverridesRepoImpl {
+    fn find(
+        &self,
+        override_query: FindCategoryOverrideQuery,
+    ) -> Result<Vec<CategoryOverride>, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        category_overrides::table
+            .filter(category_overrides::customer_id.eq(override_query.customer_id))
+            .order(category_overrides::updated_at.desc())
+            .select(CategoryOverride::as_select())
+            .load(&mut conn)
+            .map_err(|_| RepoError::Other)
+    }
+}
+
+impl RepoGetById<CategoryOverride> for CategoryOverridesRepoImpl {
+    fn get_by_id(&self, override_id: i32) -> Result<CategoryOverride, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        category_overrides::table
+            .filter(category_overrides::id.eq(override_id))
+            .select(CategoryOverride::as_select())
+            .get_result(&mut conn)
+            .map_err(|err| match err {
+                diesel::result::Error::NotFound => RepoError::NotFound,
+                _ => RepoError::Other,
+            })
+    }
+}
+
+impl RepoDeleteById<CategoryOverride> for CategoryOverridesRepoImpl {
+    fn delete_by_id(&self, override_id: i32) -> Result<(), RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        diesel::delete(category_overrides::table.filter(category_overrides::id.eq(override_id)))
+            .execute(&mut conn)
+            .map_err(|_| RepoError::Other)?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,263 @@
+use diesel::{
+    prelude::*,
+    r2d2::{ConnectionManager, Pool},
+    result::DatabaseErrorKind,
+};
+
+use crate::{
+    error::RepoError,
+    models::{
+        audit::ActorType,
+        dispute::{
+            Dispute, DisputeEvent, DisputeTransition, FindDisputeEventQuery, FindDisputeQuery,
+            NewDispute, NewDisputeEvent,
+        },
+        schema::{dispute_events, disputes, transactions},
+        transaction::{NewTransaction, Transaction, TransactionStatus, TransactionType},
+    },
+    repository::util::{adjust_account_balance, record_new_transaction},
+    traits::{RepoCreate, RepoFind, RepoGetById, RepoUpdate},
+};
+
+// bank side of provisional credits and claw backs
+const DISPUTES_ACCOUNT_NUMBER: &str = "000000001";
+const DISPUTES_BSB: &str = "123456";
+const DISPUTES_ACCOUNT_NAME: &str = "Lesser Bank Disputes";
+
+#[derive(Clone)]
+pub struct DisputesRepoImpl {
+    pub pool: Pool<ConnectionManager<PgConnection>>,
+}
+
+impl DisputesRepoImpl {
+    pub fn new(pool: Pool<ConnectionManager<PgConnection>>) -> DisputesRepoImpl {
+        DisputesRepoImpl { pool }
+    }
+}
+
+impl RepoCreate<Dispute, NewDispute> for DisputesRepoImpl {
+    fn create(&self, new_dispute: NewDispute) -> Result<Dispute, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        conn.transaction(|conn| {
+            let dispute = diesel::insert_into(disputes::table)
+                .values(&new_dispute)
+                .returning(Dispute::as_returning())
+                .get_result(conn)
+                .map_err(|err| match err {
+                    // partial unique index, transaction already has a live dispute
+                    diesel::result::Error::DatabaseError(DatabaseErrorKind::UniqueViolation, _) => {
+                        RepoError::Conflict
+                    }
+                    _ => RepoError::Other,
+                })?;
+
+            diesel::insert_into(dispute_events::table)
+                .values(&NewDisputeEvent {
+                    dispute_id: dispute.id,
+                    dispute_status: dispute.dispute_status,
+                    note: Some(dispute.reason.clone()),
+                    actor_type: ActorType::Customer,
+                    actor_id: dispute.customer_id,
+                    transaction_id: None,
+                })
+                .execute(conn)?;
+
+            Ok(dispute)
+        })
+    }
+}
+
+impl RepoFind<Dispute, FindDisputeQuery> for DisputesRepoImpl {
+    fn find(&self, dispute_query: FindDisputeQuery) -> Result<Vec<Dispute>, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        let mut query = disputes::table.into_boxed();
+
+        query = query.filter(disputes::customer_id.eq(dispute_query.customer_id));
+
+        if let Some(transaction_id) = dispute_query.transaction_id {
+            query = query.filter(disputes::transaction_id.eq(transaction_id));
+        }
+
+        query
+            .order(disputes::date_opened.desc())
+            .limit(50)
+            .select(Dispute::as_select())
+            .load(&mut conn)
+            .map_err(|_| RepoError::Other)
+    }
+}
+
+impl RepoGetById<Dispute> for DisputesRepoImpl {
+    fn get_by_id(&self, dispute_id: i32) -> Result<Dispute, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        disputes::table
+            .filter(disputes::id.eq(dispute_id))
+            .select(Dispute::as_select())
+            .get_result(&mut conn)
+            .map_err(|err| match err {
+                diesel::result::Error::NotFound => RepoError::NotFound,
+                _ => RepoError::Other,
+            })
+    }
+}
+
+impl RepoFind<DisputeEvent, FindDisputeEventQuery> for DisputesRepoImpl {
+    fn find(&self, event_query: FindDisputeEventQuery) -> Result<Vec<DisputeEvent>, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        dispute_events::table
+            .filter(dispute_events::dispute_id.eq(event_query.dispute_id))
+            .order((dispute_events::created_at.asc(), dispute_events::id.asc()))
+            .select(DisputeEvent::as_select())
+            .load(&mut conn)
+            .map_err(|_| RepoError::Other)
+    }
+}
+
+// moves the case on and posts any credit/claw back the new status implies, all or nothing
+impl RepoUpdate<Dispute, DisputeTransition> for DisputesRepoImpl {
+    fn update(&self, dispute_id: i32, transition: DisputeTransition) -> Result<Dispute, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        conn.transaction(|conn| {
+            let dispute = disputes::table
+                .filter(disputes::id.eq(dispute_id))
+                .for_update()
+                .select(Dispute::as_select())
+                .get_result(conn)?;
+
+            if !dispute
+                .dispute_status
+                .can_transition_to(transition.dispute_status)
+            {
+                return Err(RepoError::Conflict);
+            }
+
+            let delta_cents = transition
+                .dispute_status
+                .credit_delta_cents(dispute.amount_cents, dispute.credited_cents);
+
+            let posted_transaction_id = if delta_cents != 0 {
+                Some(post_dispute_adjustment(conn, &dispute, delta_cents)?.id)
+            } else {
+                None
+            };
+
+            let date_resolved = if transition.dispute_status.is_resolved() {
+                Some(chrono::Utc::now().naive_utc())
+            } else {
+                None
+            };
+
+            let updated = diesel::update(disputes::table.filter(disputes::id.eq(dispute.id)))
+                .set((
+                    disputes::dispute_status.eq(transition.dispute_status),
+                    disputes::credited_cents.eq(dispute.credited_cents + delta_cents),
+                    disputes::date_resolved.eq(date_resolved),
+                ))
+                .returning(Dispute::as_returning())
+                .get_result(conn)?;
+
+            diesel::insert_into(dispute_events::table)
+                .values(&NewDisputeEvent {
+                    dispute_id: dispute.id,
+                    dispute_status: transition.dispute_status,
+                    note: transition.note,
+                    actor_type: transition.actor_type,
+                    actor_id: transition.actor_id,
+                    transaction_id: posted_transaction_id,
+                })
+                .execute(conn)?;
+
+            Ok(updated)
+        })
+    }
+}
+
+// credit (delta > 0) or claw back (delta < 0) against the customer's side of the disputed transaction
+fn post_dispute_adjustment(
+    conn: &mut PgConnection,
+    dispute: &Dispute,
+    delta_cents: i64,
+) -> Result<Transaction, RepoError> {
+    let disputed = transactions::table
+        .filter(transactions::id.eq(dispute.transaction_id))
+        .select(Transaction::as_select())
+        .get_result(conn)?;
+
+    let (account_number, bsb) = disputed.customer_account();
+
+    let account =
+        adjust_account_balance(conn, account_number, bsb, delta_cents)?.ok_or(RepoError::Other)?;
+
+    let (from_number, from_bsb, from_name, to_number, to_bsb, to_name) = if delta_cents > 0 {
+        (
+            DISPUTES_ACCOUNT_NUMBER.to_string(),
+            DISPUTES_BSB.to_string(),
+            Some(DISPUTES_ACCOUNT_NAME.to_string()),
+            account_number.to_string(),
+            bsb.to_string(),
+            None,
+        )
+    } else {
+        (
+            account_number.to_string(),
+            bsb.to_string(),
+            None,
+            DISPUTES_ACCOUNT_NUMBER.to_string(),
+            DISPUTES_BSB.to_string(),
+            Some(DISPUTES_ACCOUNT_NAME.to_string()),
+        )
+    };
+
+    let adjustment = NewTransaction {
+        customer_id: dispute.customer_id,
+        transaction_type: TransactionType::Internal,
+        from_us: delta_cents < 0,
+        amount_cents: delta_cents.abs(),
+        from_number,
+        from_bsb,
+        from_name,
+        to_number,
+        to_bsb,
+        to_name,
+        available_balance_cents: account.available_balance_cents,
+        transaction_status: TransactionStatus::Success,
+        reversal_of: None,
+        reversal_reason: None,
+        description: None,
+        payer_reference: None,
+        payee_reference: None,
+    };
+
+    let adjustment = diesel::insert_into(transactions::table)
+        .values((
+            &adjustment,
+            transactions::date_end.eq(Some(chrono::Utc::now().naive_utc())),
+            transactions::running_balance_cents.eq(Some(account.balance_cents)),
+        ))
+        .returning(Transaction::as_returning())
+        .get_result(conn)?;
+
+    record_new_transaction(conn, &adjustment)?;
+
+    Ok(adjustment)
+}
@@ -0,0 +1,231 @@
+use chrono::NaiveDate;
+use diesel::{
+    prelude::*,
+    r2d2::{ConnectionManager, Pool},
+};
+
+use crate::{
+    error::RepoError,
+    models::{
+        account::{Account, AccountStatus},
+        budget::{month_start, next_month_start},
+        fee::{
+            AppliedFee, FeeCharge, FeeKind, FeeScheduleEntry, FeeScheduleUpdate,
+            FindFeeChargeQuery, FindFeeScheduleQuery, FindTransferFeeQuery, MonthlyFeeRun,
+            NewFeeCharge,
+        },
+        schema::{accounts, fee_charges, fee_schedule},
+    },
+    repository::util::{month_deposits, post_bank_charge, transfer_fees},
+    traits::{RepoCreate, RepoFind, RepoGetById, RepoUpdate},
+};
+
+const MAX_CHARGES: i64 = 100;
+
+#[derive(Clone)]
+pub struct FeesRepoImpl {
+    pub pool: Pool<ConnectionManager<PgConnection>>,
+}
+
+impl FeesRepoImpl {
+    pub fn new(pool: Pool<ConnectionManager<PgConnection>>) -> FeesRepoImpl {
+        FeesRepoImpl { pool }
+    }
+}
+
+// one account's fee for the month in its own db transaction, so an account that can't be charged
+// doesn't stop the others. None if it's already been charged or the fee was waived
+fn charge_account_keeping(
+    conn: &mut PgConnection,
+    entry: &FeeScheduleEntry,
+    account_id: i32,
+    period_start: NaiveDate,
+) -> Result<Option<FeeCharge>, RepoError> {
+    conn.transaction(|conn| {
+        let already_charged = fee_charges::table
+            .filter(fee_charges::account_id.eq(account_id))
+            .filter(fee_charges::kind.eq(FeeKind::AccountKeeping))
+            .filter(fee_charges::period_start.eq(period_start))
+            .count()
+            .get_result::<i64>(conn)?
+            > 0;
+        if already_charged {
+            return Ok(None);
+        }
+
+        let account = accounts::table
+            .filter(accounts::id.eq(account_id))
+            .select(Account::as_select())
+            .get_result(conn)?;
+
+        let fee = entry.fee(month_deposits(conn, &account, period_start)?);
+        if fee.amount_cents == 0 {
+            return Ok(None);
+        }
+
+        let transaction =
+            post_bank_charge(conn, &account, fee.amount_cents, fee.kind.description())?;
+
+        let charge = diesel::insert_into(fee_charges::table)
+            .values(&NewFeeCharge {
+                account_id,
+                customer_id: account.customer_id,
+                kind: fee.kind,
+                period_start: Some(period_start),
+                trigger_transaction_id: None,
+                amount_cents: fee.amount_cents,
+                transaction_id: transaction.id,
+            })
+            .returning(FeeCharge::as_returning())
+            .get_result(conn)?;
+
+        Ok(Some(charge))
+    })
+}
+
+impl RepoFind<FeeScheduleEntry, FindFeeScheduleQuery> for FeesRepoImpl {
+    fn find(
+        &self,
+        schedule_query: FindFeeScheduleQuery,
+    ) -> Result<Vec<FeeScheduleEntry>, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        let mut query = fee_schedule::table.into_boxed();
+        if let Some(kind) = schedule_query.kind {
+            query = query.filter(fee_schedule::kind.eq(kind));
+        }
+        if let Some(account_type) = schedule_query.account_type {
+            query = query.filter(fee_schedule::account_type.eq(account_type));
+        }
+
+        query
+            .order(fee_schedule::id.asc())
+            .select(FeeScheduleEntry::as_select())
+            .load(&mut conn)
+            .map_err(|_| RepoError::Other)
+    }
+}
+
+impl RepoGetById<FeeScheduleEntry> for FeesRepoImpl {
+    fn get_by_id(&self, entry_id: i32) -> Result<FeeScheduleEntry, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        fee_schedule::table
+            .filter(fee_schedule::id.eq(entry_id))
+            .select(FeeScheduleEntry::as_select())
+            .get_result(&mut conn)
+            .map_err(RepoError::from)
+    }
+}
+
+impl RepoUpdate<FeeScheduleEntry, FeeScheduleUpdate> for FeesRepoImpl {
+    fn update(
+        &self,
+        entry_id: i32,
+        update: FeeScheduleUpdate,
+    ) -> Result<FeeScheduleEntry, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        diesel::update(fee_schedule::table.filter(fee_schedule::id.eq(entry_id)))
+            .set((
+                fee_schedule::amount_cents.eq(update.amount_cents),
+                fee_schedule::waiver_deposit_cents.eq(update.waiver_deposit_cents),
+                fee_schedule::updated_at.eq(chrono::Utc::now().naive_utc()),
+            ))
+            .returning(FeeScheduleEntry::as_returning())
+            .get_result(&mut conn)
+            .map_err(RepoError::from)
+    }
+}
+
+impl RepoFind<FeeCharge, FindFeeChargeQuery> for FeesRepoImpl {
+    fn find(&self, charge_query: FindFeeChargeQuery) -> Result<Vec<FeeCharge>, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        let mut query = fee_charges::table
+            .filter(fee_charges::customer_id.eq(charge_query.customer_id))
+            .into_boxed();
+        if let Some(account_id) = charge_query.account_id {
+            query = query.filter(fee_charges::account_id.eq(account_id));
+        }
+
+        query
+            .order(fee_charges::id.desc())
+            .limit(MAX_CHARGES)
+            .select(FeeCharge::as_select())
+            .load(&mut conn)
+            .map_err(|_| RepoError::Other)
+    }
+}
+
+impl RepoFind<AppliedFee, FindTransferFeeQuery> for FeesRepoImpl {
+    fn find(&self, fee_query: FindTransferFeeQuery) -> Result<Vec<AppliedFee>, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        let account = accounts::table
+            .filter(accounts::id.eq(fee_query.account_id))
+            .select(Account::as_select())
+            .get_result(&mut conn)?;
+
+        transfer_fees(&mut conn, &account, fee_query.transaction_type).map_err(RepoError::from)
+    }
+}
+
+// every active account open by the end of the month, waivers judged on that month's deposits
+impl RepoCreate<Vec<FeeCharge>, MonthlyFeeRun> for FeesRepoImpl {
+    fn create(&self, run: MonthlyFeeRun) -> Result<Vec<FeeCharge>, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        let period_start = month_start(run.day);
+        let schedule: Vec<FeeScheduleEntry> = fee_schedule::table
+            .filter(fee_schedule::kind.eq(FeeKind::AccountKeeping))
+            .filter(fee_schedule::amount_cents.gt(0))
+            .select(FeeScheduleEntry::as_select())
+            .load(&mut conn)?;
+
+        let mut charges = vec![];
+        for entry in schedule.iter() {
+            let account_ids: Vec<i32> = accounts::table
+                .filter(accounts::account_type.eq(entry.account_type))
+                .filter(accounts::account_status.eq(AccountStatus::Active))
+                .filter(
+                    accounts::date_opened
+                        .lt(next_month_start(run.day).and_hms_opt(0, 0, 0).unwrap()),
+                )
+                .order(accounts::id.asc())
+                .select(accounts::id)
+                .load(&mut conn)?;
+
+            for account_id in account_ids.into_iter() {
+                match charge_account_keeping(&mut conn, entry, account_id, period_start) {
+                    Ok(Some(charge)) => charges.push(charge),
+                    Ok(None) => {}
+                    Err(_) => println!(
+                        "couldn't charge account keeping fee on account {}",
+                        account_id
+                    ),
+                }
+            }
+        }
+
+        Ok(charges)
+    }
+}
@@ -0,0 +1,145 @@
+use diesel::{
+    prelude::*,
+    r2d2::{ConnectionManager, Pool},
+};
+
+use crate::{
+    error::RepoError,
+    models::{
+        fraud_review::{FindFraudReviewQuery, FraudReview, FraudReviewDecision, FraudReviewStatus},
+        schema::{fraud_reviews, transactions},
+        transaction::{Transaction, TransactionStatus},
+    },
+    repository::util::advance_transfer,
+    traits::{RepoFind, RepoGetById, RepoUpdate},
+};
+
+#[derive(Clone)]
+pub struct FraudReviewsRepoImpl {
+    pub pool: Pool<ConnectionManager<PgConnection>>,
+}
+
+impl FraudReviewsRepoImpl {
+    pub fn new(pool: Pool<ConnectionManager<PgConnection>>) -> FraudReviewsRepoImpl {
+        FraudReviewsRepoImpl { pool }
+    }
+}
+
+impl RepoFind<FraudReview, FindFraudReviewQuery> for FraudReviewsRepoImpl {
+    fn find(&self, review_query: FindFraudReviewQuery) -> Result<Vec<FraudReview>, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        let mut query = fraud_reviews::table.into_boxed();
+
+        if let Some(review_status) = review_query.review_status {
+            query = query.filter(fraud_reviews::review_status.eq(review_status));
+        }
+
+        if let Some(customer_id) = review_query.customer_id {
+            query = query.filter(fraud_reviews::customer_id.eq(customer_id));
+        }
+
+        // oldest first, it's a work queue
+        query
+            .order(fraud_reviews::created_at.asc())
+            .limit(50)
+            .select(FraudReview::as_select())
+            .load(&mut conn)
+            .map_err(|_| RepoError::Other)
+    }
+}
+
+impl RepoGetById<FraudReview> for FraudReviewsRepoImpl {
+    fn get_by_id(&self, review_id: i32) -> Result<FraudReview, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        fraud_reviews::table
+            .filter(fraud_reviews::id.eq(review_id))
+            .select(FraudReview::as_select())
+            .get_result(&mut conn)
+            .map_err(|err| match err {
+                diesel::result::Error::NotFound => RepoError::NotFound,
+                _ => RepoError::Other,
+            })
+    }
+}
+
+// records the decision and releases (approve) or fails (reject) the held transaction together
+impl RepoUpdate<FraudReview, FraudReviewDecision> for FraudReviewsRepoImpl {
+    fn update(
+        &self,
+        review_id: i32,
+        decision: FraudReviewDecision,
+    ) -> Result<FraudReview, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        conn.transaction(|conn| {
+            let review = fraud_reviews::table
+                .filter(fraud_reviews::id.eq(review_id))
+                .for_update()
+                .select(FraudReview::as_select())
+                .get_result(conn)?;
+
+            if review.review_status != FraudReviewStatus::Pending {
+                return Err(RepoError::Conflict);
+            }
+
+            let now = chrono::Utc::now().naive_utc();
+
+            let (review_status, transaction_status, date_end) = if decision.approve {
+                // back in the normal pipeline as if it had never been held
+                (
+                    FraudReviewStatus::Approved,
+                    TransactionStatus::Pending,
+                    None,
+                )
+            } else {
+                (
+                    FraudReviewStatus::Rejected,
+                    TransactionStatus::Error,
+                    Some(now),
+                )
+            };
+
+            let released = diesel::update(
+                transactions::table
+                    .filter(transactions::id.eq(review.transaction_id))
+                    .filter(transactions::transaction_status.eq(TransactionStatus::Held)),
+            )
+            .set((
+                transactions::transaction_status.eq(transaction_status),
+                transactions::date_end.eq(date_end),
+            ))
+            .returning(Transaction::as_returning())
+            .get_result(conn)
+            .optional()?
+            .ok_or(RepoError::Conflict)?;
+
+            // cleared by review it carries on as an unheld one would, it may still need a second
+            // holder's signature or approval
+            if released.transaction_status == TransactionStatus::Pending {
+                advance_transfer(conn, &released)?;
+            }
+
+            diesel::update(fraud_reviews::table.filter(fraud_reviews::id.eq(review.id)))
+                .set((
+                    fraud_reviews::review_status.eq(review_status),
+                    fraud_reviews::reviewer_id.eq(Some(decision.reviewer_id)),
+                    fraud_reviews::review_note.eq(decision.note),
+                    fraud_reviews::reviewed_at.eq(Some(now)),
+                ))
+                .returning(FraudReview::as_returning())
+                .get_result(conn)
+                .map_err(RepoError::from)
+        })
+    }
+}
@@ -0,0 +1,208 @@
+use chrono::Months;
+use diesel::{
+    prelude::*,
+    r2d2::{ConnectionManager, Pool},
+};
+
+use crate::{
+    error::RepoError,
+    models::{
+        account::{Account, AccountStatus, AccountType, NewAccount},
+        loan::{
+            amortise, FindLoanApplicationQuery, Loan, LoanApplication, LoanApplicationDecision,
+            LoanApplicationStatus, NewLoanApplication, NewLoanTerms,
+        },
+        schema::{accounts, loan_applications, loans},
+        transaction::NewTransaction,
+    },
+    repository::util::{create_transaction, open_account, write_loan_schedule},
+    traits::{RepoCreate, RepoFind, RepoGetById, RepoUpdate},
+};
+
+#[derive(Clone)]
+pub struct LoanApplicationsRepoImpl {
+    pub pool: Pool<ConnectionManager<PgConnection>>,
+}
+
+impl LoanApplicationsRepoImpl {
+    pub fn new(pool: Pool<ConnectionManager<PgConnection>>) -> LoanApplicationsRepoImpl {
+        LoanApplicationsRepoImpl { pool }
+    }
+}
+
+// opens the loan account owing nothing, then draws it down into the disbursement account so the
+// money moving shows on both statements. the first repayment is a month out
+fn originate_loan(
+    conn: &mut PgConnection,
+    application: &LoanApplication,
+    interest_rate_bps: i32,
+    account_number: String,
+) -> Result<Loan, RepoError> {
+    let to = accounts::table
+        .filter(accounts::id.eq(application.disbursement_account_id))
+        .select(Account::as_select())
+        .get_result(conn)?;
+
+    if to.account_status != AccountStatus::Active {
+        return Err(RepoError::Conflict);
+    }
+
+    let loan_account = open_account(
+        conn,
+        &NewAccount {
+            customer_id: application.customer_id,
+            balance_cents: 0,
+            account_type: AccountType::Loan,
+            account_name: application.account_name.clone(),
+            available_balance_cents: 0,
+            account_number,
+        },
+    )?;
+
+    let first_repayment_date = chrono::Utc::now().date_naive() + Months::new(1);
+    let loan = diesel::insert_into(loans::table)
+        .values(&NewLoanTerms {
+            account_id: loan_account.id,
+            customer_id: application.customer_id,
+            interest_rate_bps,
+            principal_cents: Some(application.principal_cents),
+            term_months: Some(application.term_months),
+            repayment_type: Some(application.repayment_type),
+            first_repayment_date: Some(first_repayment_date),
+            repayment_account_id: Some(to.id),
+        })
+        .returning(Loan::as_returning())
+        .get_result(conn)?;
+
+    create_transaction(
+        conn,
+        &NewTransaction::loan_disbursement(&loan_account, &to, application.principal_cents),
+    )?;
+
+    write_loan_schedule(
+        conn,
+        &loan,
+        amortise(
+            application.principal_cents,
+            interest_rate_bps,
+            application.repayment_type,
+            first_repayment_date,
+            1,
+            application.term_months,
+        ),
+    )?;
+
+    Ok(loan)
+}
+
+impl RepoCreate<LoanApplication, NewLoanApplication> for LoanApplicationsRepoImpl {
+    fn create(&self, new_application: NewLoanApplication) -> Result<LoanApplication, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        diesel::insert_into(loan_applications::table)
+            .values(&new_application)
+            .returning(LoanApplication::as_returning())
+            .get_result(&mut conn)
+            .map_err(|_| RepoError::Other)
+    }
+}
+
+impl RepoFind<LoanApplication, FindLoanApplicationQuery> for LoanApplicationsRepoImpl {
+    fn find(
+        &self,
+        application_query: FindLoanApplicationQuery,
+    ) -> Result<Vec<LoanApplication>, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        let mut query = loan_applications::table.into_boxed();
+
+        if let Some(customer_id) = application_query.customer_id {
+            query = query.filter(loan_applications::customer_id.eq(customer_id));
+        }
+
+        if let Some(application_status) = application_query.application_status {
+            query = query.filter(loan_applications::application_status.eq(application_status));
+        }
+
+        query
+            .order(loan_applications::created_at.desc())
+            .limit(50)
+            .select(LoanApplication::as_select())
+            .load(&mut conn)
+            .map_err(|_| RepoError::Other)
+    }
+}
+
+impl RepoGetById<LoanApplication> for LoanApplicationsRepoImpl {
+    fn get_by_id(&self, application_id: i32) -> Result<LoanApplication, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        loan_applications::table
+            .filter(loan_applications::id.eq(application_id))
+            .select(LoanApplication::as_select())
+            .get_result(&mut conn)
+            .map_err(RepoError::from)
+    }
+}
+
+impl RepoUpdate<LoanApplication, LoanApplicationDecision> for LoanApplicationsRepoImpl {
+    fn update(
+        &self,
+        application_id: i32,
+        decision: LoanApplicationDecision,
+    ) -> Result<LoanApplication, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        conn.transaction(|conn| {
+            let application = loan_applications::table
+                .filter(loan_applications::id.eq(application_id))
+                .for_update()
+                .select(LoanApplication::as_select())
+                .get_result(conn)?;
+
+            if application.application_status != LoanApplicationStatus::Pending {
+                return Err(RepoError::Conflict);
+            }
+
+            let (application_status, loan_id) = match decision.interest_rate_bps {
+                Some(interest_rate_bps) if decision.approve => {
+                    let loan = originate_loan(
+                        conn,
+                        &application,
+                        interest_rate_bps,
+                        decision.account_number,
+                    )?;
+                    (LoanApplicationStatus::Approved, Some(loan.id))
+                }
+                _ => (LoanApplicationStatus::Declined, None),
+            };
+
+            diesel::update(
+                loan_applications::table.filter(loan_applications::id.eq(application.id)),
+            )
+            .set((
+                loan_applications::application_status.eq(application_status),
+                loan_applications::interest_rate_bps.eq(decision.interest_rate_bps),
+                loan_applications::loan_id.eq(loan_id),
+                loan_applications::reviewer_id.eq(Some(decision.reviewer_id)),
+                loan_applications::review_note.eq(decision.note),
+                loan_applications::reviewed_at.eq(Some(chrono::Utc::now().naive_utc())),
+            ))
+            .returning(LoanApplication::as_returning())
+            .get_result(conn)
+            .map_err(RepoError::from)
+        })
+    }
+}
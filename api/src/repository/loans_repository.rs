@@ -0,0 +1,566 @@
+use std::collections::HashMap;
+
+use chrono::{Duration, NaiveDate};
+use diesel::{
+    prelude::*,
+    r2d2::{ConnectionManager, Pool},
+    result::DatabaseErrorKind,
+};
+
+use crate::{
+    error::RepoError,
+    models::{
+        account::{Account, AccountStatus, AccountType, NewAccount},
+        account_holder::{AccountPermission, HolderRole, NewSignedTransfer},
+        loan::{
+            daily_interest_cents, FindLoanInterestQuery, FindLoanQuery, Loan, LoanDetail,
+            LoanInterestCharge, LoanInterestRun, LoanOffset, LoanOffsetAccount,
+            LoanRepaymentCollection, LoanRepaymentRun, LoanScheduleEntry, NewLoan,
+            NewLoanInterestCharge, NewLoanOffset, NewLoanRepayment, NewLoanTerms,
+            INTEREST_DESCRIPTION,
+        },
+        overdraft::{OverdraftChargeKind, DISHONOUR_FEE_CENTS},
+        schema::{
+            account_balance_snapshots, account_holders, accounts, loan_interest_charges,
+            loan_offsets, loan_schedule_entries, loans,
+        },
+        transaction::{NewTransaction, Transaction},
+    },
+    repository::util::{
+        charge_overdraft, create_signed_transfer, create_transaction, open_account,
+        post_bank_charge,
+    },
+    traits::{RepoCreate, RepoDeleteById, RepoFind, RepoGetById},
+};
+
+const MAX_CHARGES: i64 = 100;
+
+#[derive(Clone)]
+pub struct LoansRepoImpl {
+    pub pool: Pool<ConnectionManager<PgConnection>>,
+}
+
+impl LoansRepoImpl {
+    pub fn new(pool: Pool<ConnectionManager<PgConnection>>) -> LoansRepoImpl {
+        LoansRepoImpl { pool }
+    }
+}
+
+// each loan with its account and offset accounts, in the order given
+fn load_details(conn: &mut PgConnection, loans: Vec<Loan>) -> QueryResult<Vec<LoanDetail>> {
+    let loan_ids: Vec<i32> = loans.iter().map(|loan| loan.id).collect();
+
+    let offsets: Vec<(LoanOffset, Account)> = loan_offsets::table
+        .inner_join(accounts::table)
+        .filter(loan_offsets::loan_id.eq_any(&loan_ids))
+        .order(loan_offsets::id.asc())
+        .select((LoanOffset::as_select(), Account::as_select()))
+        .load(conn)?;
+
+    let schedule: Vec<LoanScheduleEntry> = loan_schedule_entries::table
+        .filter(loan_schedule_entries::loan_id.eq_any(&loan_ids))
+        .order(loan_schedule_entries::installment.asc())
+        .select(LoanScheduleEntry::as_select())
+        .load(conn)?;
+
+    let account_ids: Vec<i32> = loans.iter().map(|loan| loan.account_id).collect();
+    let mut loan_accounts: HashMap<i32, Account> = accounts::table
+        .filter(accounts::id.eq_any(&account_ids))
+        .select(Account::as_select())
+        .load(conn)?
+        .into_iter()
+        .map(|account| (account.id, account))
+        .collect();
+
+    loans
+        .into_iter()
+        .map(|loan| {
+            let account = loan_accounts
+                .remove(&loan.account_id)
+                .ok_or(diesel::result::Error::NotFound)?;
+            let offsets = offsets
+                .iter()
+                .filter(|(offset, _)| offset.loan_id == loan.id)
+                .map(|(offset, account)| LoanOffsetAccount {
+                    offset: offset.clone(),
+                    account: account.clone(),
+                })
+                .collect();
+            let schedule = schedule
+                .iter()
+                .filter(|entry| entry.loan_id == loan.id)
+                .cloned()
+                .collect();
+
+            Ok(LoanDetail {
+                loan,
+                account,
+                offsets,
+                schedule,
+            })
+        })
+        .collect()
+}
+
+// the balance each account ended the day with. an account with no snapshot on or before the day
+// hasn't moved since it was opened, so its balance now is the one it had then
+fn end_of_day_balances(
+    conn: &mut PgConnection,
+    account_ids: &[i32],
+    day: NaiveDate,
+) -> QueryResult<HashMap<i32, i64>> {
+    let mut balances: HashMap<i32, i64> = account_balance_snapshots::table
+        .filter(account_balance_snapshots::account_id.eq_any(account_ids))
+        .filter(account_balance_snapshots::snapshot_date.le(day))
+        .distinct_on(account_balance_snapshots::account_id)
+        .order((
+            account_balance_snapshots::account_id.asc(),
+            account_balance_snapshots::snapshot_date.desc(),
+        ))
+        .select((
+            account_balance_snapshots::account_id,
+            account_balance_snapshots::balance_cents,
+        ))
+        .load::<(i32, i64)>(conn)?
+        .into_iter()
+        .collect();
+
+    let unmoved: Vec<i32> = account_ids
+        .iter()
+        .filter(|id| !balances.contains_key(id))
+        .copied()
+        .collect();
+    let opened_by: Vec<(i32, i64)> = accounts::table
+        .filter(accounts::id.eq_any(&unmoved))
+        .filter(accounts::date_opened.lt((day + Duration::days(1)).and_hms_opt(0, 0, 0).unwrap()))
+        .select((accounts::id, accounts::balance_cents))
+        .load(conn)?;
+    balances.extend(opened_by);
+
+    Ok(balances)
+}
+
+// one loan's interest in its own db transaction, so a loan that can't be charged doesn't stop the
+// others. None if it's already been charged for the day
+fn charge_loan_interest(
+    conn: &mut PgConnection,
+    loan: &Loan,
+    charge_date: NaiveDate,
+    owed_cents: i64,
+    offset_cents: i64,
+    amount_cents: i64,
+) -> Result<Option<LoanInterestCharge>, RepoError> {
+    conn.transaction(|conn| {
+        let already_charged = loan_interest_charges::table
+            .filter(loan_interest_charges::loan_id.eq(loan.id))
+            .filter(loan_interest_charges::charge_date.eq(charge_date))
+            .count()
+            .get_result::<i64>(conn)?
+            > 0;
+        if already_charged {
+            return Ok(None);
+        }
+
+        let account = accounts::table
+            .filter(accounts::id.eq(loan.account_id))
+            .select(Account::as_select())
+            .get_result(conn)?;
+
+        let transaction = post_bank_charge(conn, &account, amount_cents, INTEREST_DESCRIPTION)?;
+
+        let charge = diesel::insert_into(loan_interest_charges::table)
+            .values(&NewLoanInterestCharge {
+                loan_id: loan.id,
+                customer_id: loan.customer_id,
+                charge_date,
+                owed_cents,
+                offset_cents,
+                amount_cents,
+                transaction_id: transaction.id,
+            })
+            .returning(LoanInterestCharge::as_returning())
+            .get_result(conn)?;
+
+        Ok(Some(charge))
+    })
+}
+
+// one loan's repayment in its own db transaction, so a loan that can't be collected doesn't stop
+// the others. None if there's nothing due
+fn collect_loan_repayment(
+    conn: &mut PgConnection,
+    loan: &Loan,
+    repayment_account_id: i32,
+    day: NaiveDate,
+) -> Result<Option<LoanRepaymentCollection>, RepoError> {
+    conn.transaction(|conn| {
+        let due_cents: i64 = loan_schedule_entries::table
+            .filter(loan_schedule_entries::loan_id.eq(loan.id))
+            .filter(loan_schedule_entries::due_date.le(day))
+            .select(loan_schedule_entries::amount_cents - loan_schedule_entries::paid_cents)
+            .load::<i64>(conn)?
+            .into_iter()
+            .sum();
+        if due_cents == 0 {
+            return Ok(None);
+        }
+
+        let loan_account = accounts::table
+            .filter(accounts::id.eq(loan.account_id))
+            .select(Account::as_select())
+            .get_result(conn)?;
+
+        // the last installment settles whatever the daily interest has actually left owing
+        let installments_left = loan_schedule_entries::table
+            .filter(loan_schedule_entries::loan_id.eq(loan.id))
+            .filter(loan_schedule_entries::due_date.gt(day))
+            .count()
+            .get_result::<i64>(conn)?;
+        let due_cents = if installments_left == 0 {
+            loan_account.overdrawn_cents()
+        } else {
+            due_cents.min(loan_account.overdrawn_cents())
+        };
+        if due_cents == 0 {
+            return Ok(None);
+        }
+
+        let from = accounts::table
+            .filter(accounts::id.eq(repayment_account_id))
+            .select(Account::as_select())
+            .get_result(conn)?;
+
+        // scheduled repayments never dip into an overdraft, same as automation rules
+        let active = from.account_status == AccountStatus::Active;
+        let covered = from.available_balance_cents >= due_cents;
+        // a direct debit under the loan contract the holders agreed to, so it isn't signed or
+        // approved like a transfer one of them makes
+        let transaction = if active && covered {
+            Some(create_transaction(
+                conn,
+                &NewTransaction::loan_repayment(&from, &loan_account, due_cents),
+            )?)
+        } else {
+            None
+        };
+
+        // the bank tried to take it and couldn't, which is charged for once per installment missed,
+        // not again on every run it's still unpaid
+        let dishonour_fee = if active && !covered {
+            let newly_missed = diesel::update(
+                loan_schedule_entries::table
+                    .filter(loan_schedule_entries::loan_id.eq(loan.id))
+                    .filter(loan_schedule_entries::due_date.le(day))
+                    .filter(
+                        loan_schedule_entries::paid_cents.lt(loan_schedule_entries::amount_cents),
+                    )
+                    .filter(loan_schedule_entries::dishonoured_at.is_null()),
+            )
+            .set(loan_schedule_entries::dishonoured_at.eq(Some(chrono::Utc::now().naive_utc())))
+            .execute(conn)?;
+
+            if newly_missed > 0 {
+                charge_overdraft(
+                    conn,
+                    from.id,
+                    OverdraftChargeKind::DishonourFee,
+                    day,
+                    DISHONOUR_FEE_CENTS,
+                )?
+            } else {
+                None
+            }
+        } else {
+            None
+        };
+
+        Ok(Some(LoanRepaymentCollection {
+            loan_id: loan.id,
+            customer_id: loan.customer_id,
+            due_cents,
+            transaction,
+            dishonour_fee,
+        }))
+    })
+}
+
+impl RepoCreate<LoanDetail, NewLoan> for LoansRepoImpl {
+    fn create(&self, new_loan: NewLoan) -> Result<LoanDetail, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        conn.transaction(|conn| {
+            let account = open_account(
+                conn,
+                &NewAccount {
+                    customer_id: new_loan.customer_id,
+                    balance_cents: -new_loan.owed_cents,
+                    account_type: AccountType::Loan,
+                    account_name: new_loan.account_name,
+                    available_balance_cents: -new_loan.owed_cents,
+                    account_number: new_loan.account_number,
+                },
+            )?;
+
+            let loan = diesel::insert_into(loans::table)
+                .values(&NewLoanTerms {
+                    account_id: account.id,
+                    customer_id: account.customer_id,
+                    interest_rate_bps: new_loan.interest_rate_bps,
+                    principal_cents: None,
+                    term_months: None,
+                    repayment_type: None,
+                    first_repayment_date: None,
+                    repayment_account_id: None,
+                })
+                .returning(Loan::as_returning())
+                .get_result(conn)?;
+
+            Ok(LoanDetail {
+                loan,
+                account,
+                offsets: vec![],
+                schedule: vec![],
+            })
+        })
+        .map_err(|err: diesel::result::Error| RepoError::from(err))
+    }
+}
+
+impl RepoFind<LoanDetail, FindLoanQuery> for LoansRepoImpl {
+    fn find(&self, loan_query: FindLoanQuery) -> Result<Vec<LoanDetail>, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        let loans = loans::table
+            .filter(loans::customer_id.eq(loan_query.customer_id))
+            .order(loans::id.asc())
+            .select(Loan::as_select())
+            .load(&mut conn)?;
+
+        load_details(&mut conn, loans).map_err(RepoError::from)
+    }
+}
+
+impl RepoGetById<LoanDetail> for LoansRepoImpl {
+    fn get_by_id(&self, loan_id: i32) -> Result<LoanDetail, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        let loan = loans::table
+            .filter(loans::id.eq(loan_id))
+            .select(Loan::as_select())
+            .get_result(&mut conn)?;
+
+        load_details(&mut conn, vec![loan])?
+            .pop()
+            .ok_or(RepoError::NotFound)
+    }
+}
+
+impl RepoCreate<LoanOffset, NewLoanOffset> for LoansRepoImpl {
+    fn create(&self, new_offset: NewLoanOffset) -> Result<LoanOffset, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        diesel::insert_into(loan_offsets::table)
+            .values(&new_offset)
+            .returning(LoanOffset::as_returning())
+            .get_result(&mut conn)
+            .map_err(|err| match err {
+                // account already offsets a loan, this one or another
+                diesel::result::Error::DatabaseError(DatabaseErrorKind::UniqueViolation, _) => {
+                    RepoError::Conflict
+                }
+                _ => RepoError::Other,
+            })
+    }
+}
+
+impl RepoDeleteById<LoanOffset> for LoansRepoImpl {
+    fn delete_by_id(&self, offset_id: i32) -> Result<(), RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        diesel::delete(loan_offsets::table.filter(loan_offsets::id.eq(offset_id)))
+            .execute(&mut conn)
+            .map_err(|_| RepoError::Other)?;
+
+        Ok(())
+    }
+}
+
+impl RepoCreate<Transaction, NewLoanRepayment> for LoansRepoImpl {
+    fn create(&self, repayment: NewLoanRepayment) -> Result<Transaction, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        conn.transaction(|conn| {
+            let loan_account = loans::table
+                .inner_join(accounts::table)
+                .filter(loans::id.eq(repayment.loan_id))
+                .filter(loans::customer_id.eq(repayment.customer_id))
+                .select(Account::as_select())
+                .get_result(conn)?;
+
+            // any holder who can transact on the account can repay from it
+            let (from, holder_role) = accounts::table
+                .inner_join(account_holders::table)
+                .filter(accounts::id.eq(repayment.from_account_id))
+                .filter(account_holders::customer_id.eq(repayment.customer_id))
+                .select((Account::as_select(), account_holders::holder_role))
+                .get_result::<(Account, HolderRole)>(conn)?;
+
+            if !holder_role.allows(AccountPermission::Transact) {
+                return Err(RepoError::NotFound);
+            }
+
+            if from.account_type == AccountType::Loan
+                || repayment.amount_cents > loan_account.overdrawn_cents()
+                || repayment.amount_cents > from.spendable_cents()
+            {
+                return Err(RepoError::Conflict);
+            }
+
+            // signed by whoever made it, the account decides if it waits for another holder or an
+            // approval
+            create_signed_transfer(
+                conn,
+                &NewSignedTransfer {
+                    transaction: NewTransaction::loan_repayment(
+                        &from,
+                        &loan_account,
+                        repayment.amount_cents,
+                    ),
+                    signed_by: repayment.customer_id,
+                    step_up: None,
+                },
+            )
+        })
+    }
+}
+
+impl RepoFind<LoanInterestCharge, FindLoanInterestQuery> for LoansRepoImpl {
+    fn find(
+        &self,
+        charge_query: FindLoanInterestQuery,
+    ) -> Result<Vec<LoanInterestCharge>, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        loan_interest_charges::table
+            .filter(loan_interest_charges::customer_id.eq(charge_query.customer_id))
+            .filter(loan_interest_charges::loan_id.eq(charge_query.loan_id))
+            .order(loan_interest_charges::id.desc())
+            .limit(MAX_CHARGES)
+            .select(LoanInterestCharge::as_select())
+            .load(&mut conn)
+            .map_err(|_| RepoError::Other)
+    }
+}
+
+// offsets count as they're linked when the run happens
+impl RepoCreate<Vec<LoanInterestCharge>, LoanInterestRun> for LoansRepoImpl {
+    fn create(&self, run: LoanInterestRun) -> Result<Vec<LoanInterestCharge>, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        let loans = loans::table
+            .order(loans::id.asc())
+            .select(Loan::as_select())
+            .load(&mut conn)?;
+        let offsets: Vec<(i32, i32)> = loan_offsets::table
+            .select((loan_offsets::loan_id, loan_offsets::account_id))
+            .load(&mut conn)?;
+
+        let account_ids: Vec<i32> = loans
+            .iter()
+            .map(|loan| loan.account_id)
+            .chain(offsets.iter().map(|(_, account_id)| *account_id))
+            .collect();
+        let balances = end_of_day_balances(&mut conn, &account_ids, run.day)?;
+
+        let mut charges = vec![];
+        for loan in loans.iter() {
+            // not open yet on the day
+            let Some(loan_balance_cents) = balances.get(&loan.account_id) else {
+                continue;
+            };
+            let owed_cents = (-loan_balance_cents).max(0);
+            let offset_cents: i64 = offsets
+                .iter()
+                .filter(|(loan_id, _)| *loan_id == loan.id)
+                .filter_map(|(_, account_id)| balances.get(account_id))
+                .map(|balance_cents| (*balance_cents).max(0))
+                .sum();
+
+            let interest_cents =
+                daily_interest_cents(owed_cents, offset_cents, loan.interest_rate_bps);
+            if interest_cents == 0 {
+                continue;
+            }
+
+            match charge_loan_interest(
+                &mut conn,
+                loan,
+                run.day,
+                owed_cents,
+                offset_cents,
+                interest_cents,
+            ) {
+                Ok(Some(charge)) => charges.push(charge),
+                Ok(None) => {}
+                Err(_) => println!("couldn't charge interest on loan {}", loan.id),
+            }
+        }
+
+        Ok(charges)
+    }
+}
+
+impl RepoCreate<Vec<LoanRepaymentCollection>, LoanRepaymentRun> for LoansRepoImpl {
+    fn create(&self, run: LoanRepaymentRun) -> Result<Vec<LoanRepaymentCollection>, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        let loans = loans::table
+            .filter(loans::term_months.is_not_null())
+            .order(loans::id.asc())
+            .select(Loan::as_select())
+            .load(&mut conn)?;
+
+        let mut collections = vec![];
+        for loan in loans.iter() {
+            // the repayment account's gone, staff have to sort it out with the customer
+            let Some(repayment_account_id) = loan.repayment_account_id else {
+                continue;
+            };
+
+            match collect_loan_repayment(&mut conn, loan, repayment_account_id, run.day) {
+                Ok(Some(collection)) => collections.push(collection),
+                Ok(None) => {}
+                Err(_) => println!("couldn't collect repayment on loan {}", loan.id),
+            }
+        }
+
+        Ok(collections)
+    }
+}
@@ -1,2 +1,24 @@
+pub mod account_holders_repository;
 pub mod accounts_repository;
+pub mod approvals_repository;
+pub mod audit_repository;
+pub mod automation_repository;
+pub mod balance_history_repository;
+pub mod buckets_repository;
+pub mod budgets_repository;
+pub mod category_overrides_repository;
+pub mod disputes_repository;
+pub mod fees_repository;
+pub mod fraud_reviews_repository;
+pub mod loan_applications_repository;
+pub mod loans_repository;
+pub mod oauth_repository;
+pub mod outbox_repository;
+pub mod overdrafts_repository;
+pub mod payees_repository;
+pub mod step_up_repository;
 pub mod transactions_repository;
+pub mod transfer_limit_requests_repository;
+pub mod transfer_limits_repository;
+pub mod util;
+pub mod webhooks_repository;
@@ -0,0 +1,538 @@
+use diesel::{
+    prelude::*,
+    r2d2::{ConnectionManager, Pool},
+};
+
+use crate::{
+    error::RepoError,
+    models::{
+        consent::{
+            Consent, ConsentAmendment, ConsentRevocation, FindConsentQuery, NewConsentAccount,
+        },
+        oauth::{
+            CodeExchange, FindAuthorisationCodeQuery, FindOAuthClientQuery, FindOAuthTokenQuery,
+            NewOAuthAuthorisation, NewOAuthClient, OAuthAuthorisationCode, OAuthClient,
+            OAuthClientRevocation, OAuthGrant, OAuthGrantRevocation, OAuthToken, OAuthTokenKind,
+            OAuthTokenRevocation, TokenRefresh,
+        },
+        schema::{
+            oauth_authorisation_codes, oauth_clients, oauth_grant_accounts, oauth_grants,
+            oauth_tokens,
+        },
+    },
+    traits::{RepoCreate, RepoFind, RepoGetById, RepoUpdate},
+};
+
+#[derive(Clone)]
+pub struct OAuthRepoImpl {
+    pub pool: Pool<ConnectionManager<PgConnection>>,
+}
+
+impl OAuthRepoImpl {
+    pub fn new(pool: Pool<ConnectionManager<PgConnection>>) -> OAuthRepoImpl {
+        OAuthRepoImpl { pool }
+    }
+}
+
+fn revoke_grant_tokens(
+    conn: &mut PgConnection,
+    grant_ids: Vec<i32>,
+    now: chrono::NaiveDateTime,
+) -> Result<(), RepoError> {
+    diesel::update(
+        oauth_tokens::table
+            .filter(oauth_tokens::grant_id.eq_any(grant_ids))
+            .filter(oauth_tokens::revoked_at.is_null()),
+    )
+    .set(oauth_tokens::revoked_at.eq(Some(now)))
+    .execute(conn)?;
+
+    Ok(())
+}
+
+// the grant has to still be live for tokens to be handed out on it
+fn lock_active_grant(conn: &mut PgConnection, grant_id: i32) -> Result<OAuthGrant, RepoError> {
+    let grant = oauth_grants::table
+        .filter(oauth_grants::id.eq(grant_id))
+        .for_update()
+        .select(OAuthGrant::as_select())
+        .get_result(conn)?;
+
+    if !grant.is_active(chrono::Utc::now().naive_utc()) {
+        return Err(RepoError::Conflict);
+    }
+
+    Ok(grant)
+}
+
+// revoking twice is fine, it's what a replayed refresh token does
+fn revoke_grant(conn: &mut PgConnection, grant_id: i32) -> Result<OAuthGrant, RepoError> {
+    conn.transaction(|conn| {
+        let grant = oauth_grants::table
+            .filter(oauth_grants::id.eq(grant_id))
+            .for_update()
+            .select(OAuthGrant::as_select())
+            .get_result(conn)?;
+
+        let now = chrono::Utc::now().naive_utc();
+        revoke_grant_tokens(conn, vec![grant_id], now)?;
+
+        if grant.revoked_at.is_some() {
+            return Ok(grant);
+        }
+
+        diesel::update(oauth_grants::table.find(grant_id))
+            .set(oauth_grants::revoked_at.eq(Some(now)))
+            .returning(OAuthGrant::as_returning())
+            .get_result(conn)
+            .map_err(RepoError::from)
+    })
+}
+
+fn replace_grant_accounts(
+    conn: &mut PgConnection,
+    grant_id: i32,
+    account_ids: &[i32],
+) -> Result<(), RepoError> {
+    diesel::delete(oauth_grant_accounts::table.filter(oauth_grant_accounts::grant_id.eq(grant_id)))
+        .execute(conn)?;
+
+    let accounts: Vec<NewConsentAccount> = account_ids
+        .iter()
+        .map(|account_id| NewConsentAccount {
+            grant_id,
+            account_id: *account_id,
+        })
+        .collect();
+    diesel::insert_into(oauth_grant_accounts::table)
+        .values(accounts)
+        .execute(conn)?;
+
+    Ok(())
+}
+
+// grants with their app and accounts, in the order given
+fn load_consents(
+    conn: &mut PgConnection,
+    grants: Vec<(OAuthGrant, String, String)>,
+) -> Result<Vec<Consent>, RepoError> {
+    let grant_ids: Vec<i32> = grants.iter().map(|(grant, _, _)| grant.id).collect();
+    let accounts: Vec<(i32, i32)> = oauth_grant_accounts::table
+        .filter(oauth_grant_accounts::grant_id.eq_any(grant_ids))
+        .order(oauth_grant_accounts::account_id)
+        .select((
+            oauth_grant_accounts::grant_id,
+            oauth_grant_accounts::account_id,
+        ))
+        .load(conn)?;
+
+    Ok(grants
+        .into_iter()
+        .map(|(grant, client_id, client_name)| Consent {
+            id: grant.id,
+            customer_id: grant.customer_id,
+            client_id,
+            client_name,
+            account_ids: accounts
+                .iter()
+                .filter(|(grant_id, _)| *grant_id == grant.id)
+                .map(|(_, account_id)| *account_id)
+                .collect(),
+            scope: grant.scope,
+            created_at: grant.created_at,
+            updated_at: grant.updated_at,
+            expires_at: grant.expires_at,
+            revoked_at: grant.revoked_at,
+        })
+        .collect())
+}
+
+fn get_consent(conn: &mut PgConnection, grant_id: i32) -> Result<Consent, RepoError> {
+    let grant = oauth_grants::table
+        .inner_join(oauth_clients::table)
+        .filter(oauth_grants::id.eq(grant_id))
+        .select((
+            OAuthGrant::as_select(),
+            oauth_clients::client_id,
+            oauth_clients::client_name,
+        ))
+        .get_result(conn)?;
+
+    load_consents(conn, vec![grant])?
+        .pop()
+        .ok_or(RepoError::NotFound)
+}
+
+impl RepoCreate<OAuthClient, NewOAuthClient> for OAuthRepoImpl {
+    fn create(&self, new_client: NewOAuthClient) -> Result<OAuthClient, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        diesel::insert_into(oauth_clients::table)
+            .values(&new_client)
+            .returning(OAuthClient::as_returning())
+            .get_result(&mut conn)
+            .map_err(RepoError::from)
+    }
+}
+
+impl RepoFind<OAuthClient, FindOAuthClientQuery> for OAuthRepoImpl {
+    fn find(&self, query: FindOAuthClientQuery) -> Result<Vec<OAuthClient>, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        let mut select = oauth_clients::table
+            .select(OAuthClient::as_select())
+            .order(oauth_clients::id.asc())
+            .into_boxed();
+
+        if let Some(client_id) = query.client_id {
+            select = select.filter(oauth_clients::client_id.eq(client_id));
+        }
+
+        select.load(&mut conn).map_err(RepoError::from)
+    }
+}
+
+impl RepoGetById<OAuthClient> for OAuthRepoImpl {
+    fn get_by_id(&self, id: i32) -> Result<OAuthClient, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        oauth_clients::table
+            .filter(oauth_clients::id.eq(id))
+            .select(OAuthClient::as_select())
+            .get_result(&mut conn)
+            .map_err(RepoError::from)
+    }
+}
+
+impl RepoUpdate<OAuthClient, OAuthClientRevocation> for OAuthRepoImpl {
+    fn update(&self, id: i32, _: OAuthClientRevocation) -> Result<OAuthClient, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        conn.transaction(|conn| {
+            let client = oauth_clients::table
+                .filter(oauth_clients::id.eq(id))
+                .for_update()
+                .select(OAuthClient::as_select())
+                .get_result(conn)?;
+            if !client.is_active() {
+                return Err(RepoError::Conflict);
+            }
+
+            let now = chrono::Utc::now().naive_utc();
+
+            let grant_ids = diesel::update(
+                oauth_grants::table
+                    .filter(oauth_grants::client_id.eq(id))
+                    .filter(oauth_grants::revoked_at.is_null()),
+            )
+            .set(oauth_grants::revoked_at.eq(Some(now)))
+            .returning(oauth_grants::id)
+            .get_results(conn)?;
+            revoke_grant_tokens(conn, grant_ids, now)?;
+
+            diesel::update(oauth_clients::table.filter(oauth_clients::id.eq(id)))
+                .set(oauth_clients::revoked_at.eq(Some(now)))
+                .returning(OAuthClient::as_returning())
+                .get_result(conn)
+                .map_err(RepoError::from)
+        })
+    }
+}
+
+// one live grant per client and customer, authorising again replaces its scope
+impl RepoCreate<OAuthAuthorisationCode, NewOAuthAuthorisation> for OAuthRepoImpl {
+    fn create(
+        &self,
+        authorisation: NewOAuthAuthorisation,
+    ) -> Result<OAuthAuthorisationCode, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        conn.transaction(|conn| {
+            let existing = oauth_grants::table
+                .filter(oauth_grants::client_id.eq(authorisation.client_id))
+                .filter(oauth_grants::customer_id.eq(authorisation.customer_id))
+                .filter(oauth_grants::revoked_at.is_null())
+                .for_update()
+                .select(oauth_grants::id)
+                .get_result::<i32>(conn)
+                .optional()?;
+
+            let grant_id = match existing {
+                Some(grant_id) => diesel::update(oauth_grants::table.find(grant_id))
+                    .set((
+                        oauth_grants::scope.eq(&authorisation.scope),
+                        oauth_grants::expires_at.eq(authorisation.grant_expires_at),
+                        oauth_grants::updated_at.eq(chrono::Utc::now().naive_utc()),
+                    ))
+                    .returning(oauth_grants::id)
+                    .get_result::<i32>(conn)?,
+                None => diesel::insert_into(oauth_grants::table)
+                    .values((
+                        oauth_grants::client_id.eq(authorisation.client_id),
+                        oauth_grants::customer_id.eq(authorisation.customer_id),
+                        oauth_grants::scope.eq(&authorisation.scope),
+                        oauth_grants::expires_at.eq(authorisation.grant_expires_at),
+                    ))
+                    .returning(oauth_grants::id)
+                    .get_result::<i32>(conn)?,
+            };
+            replace_grant_accounts(conn, grant_id, &authorisation.account_ids)?;
+
+            diesel::insert_into(oauth_authorisation_codes::table)
+                .values((
+                    oauth_authorisation_codes::grant_id.eq(grant_id),
+                    oauth_authorisation_codes::code_hash.eq(&authorisation.code_hash),
+                    oauth_authorisation_codes::redirect_uri.eq(&authorisation.redirect_uri),
+                    oauth_authorisation_codes::scope.eq(&authorisation.scope),
+                    oauth_authorisation_codes::code_challenge.eq(&authorisation.code_challenge),
+                    oauth_authorisation_codes::expires_at.eq(authorisation.expires_at),
+                ))
+                .returning(OAuthAuthorisationCode::as_returning())
+                .get_result(conn)
+                .map_err(RepoError::from)
+        })
+    }
+}
+
+impl RepoFind<OAuthAuthorisationCode, FindAuthorisationCodeQuery> for OAuthRepoImpl {
+    fn find(
+        &self,
+        query: FindAuthorisationCodeQuery,
+    ) -> Result<Vec<OAuthAuthorisationCode>, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        oauth_authorisation_codes::table
+            .filter(oauth_authorisation_codes::code_hash.eq(query.code_hash))
+            .select(OAuthAuthorisationCode::as_select())
+            .load(&mut conn)
+            .map_err(RepoError::from)
+    }
+}
+
+impl RepoGetById<OAuthGrant> for OAuthRepoImpl {
+    fn get_by_id(&self, id: i32) -> Result<OAuthGrant, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        oauth_grants::table
+            .filter(oauth_grants::id.eq(id))
+            .select(OAuthGrant::as_select())
+            .get_result(&mut conn)
+            .map_err(RepoError::from)
+    }
+}
+
+impl RepoUpdate<OAuthGrant, OAuthGrantRevocation> for OAuthRepoImpl {
+    fn update(&self, id: i32, _: OAuthGrantRevocation) -> Result<OAuthGrant, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        revoke_grant(&mut conn, id)
+    }
+}
+
+impl RepoCreate<Vec<OAuthToken>, CodeExchange> for OAuthRepoImpl {
+    fn create(&self, exchange: CodeExchange) -> Result<Vec<OAuthToken>, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        conn.transaction(|conn| {
+            // two exchanges racing for the same code, only one gets it
+            let used = diesel::update(
+                oauth_authorisation_codes::table
+                    .filter(oauth_authorisation_codes::id.eq(exchange.code_id))
+                    .filter(oauth_authorisation_codes::used_at.is_null()),
+            )
+            .set(oauth_authorisation_codes::used_at.eq(Some(chrono::Utc::now().naive_utc())))
+            .execute(conn)?;
+            if used == 0 {
+                return Err(RepoError::Conflict);
+            }
+
+            lock_active_grant(conn, exchange.access_token.grant_id)?;
+
+            diesel::insert_into(oauth_tokens::table)
+                .values(vec![exchange.access_token, exchange.refresh_token])
+                .returning(OAuthToken::as_returning())
+                .get_results(conn)
+                .map_err(RepoError::from)
+        })
+    }
+}
+
+impl RepoCreate<Vec<OAuthToken>, TokenRefresh> for OAuthRepoImpl {
+    fn create(&self, refresh: TokenRefresh) -> Result<Vec<OAuthToken>, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        conn.transaction(|conn| {
+            let rotated = diesel::update(
+                oauth_tokens::table
+                    .filter(oauth_tokens::id.eq(refresh.refresh_token_id))
+                    .filter(oauth_tokens::token_kind.eq(OAuthTokenKind::Refresh))
+                    .filter(oauth_tokens::revoked_at.is_null()),
+            )
+            .set(oauth_tokens::revoked_at.eq(Some(chrono::Utc::now().naive_utc())))
+            .execute(conn)?;
+            if rotated == 0 {
+                return Err(RepoError::Conflict);
+            }
+
+            lock_active_grant(conn, refresh.access_token.grant_id)?;
+
+            diesel::insert_into(oauth_tokens::table)
+                .values(vec![refresh.access_token, refresh.refresh_token])
+                .returning(OAuthToken::as_returning())
+                .get_results(conn)
+                .map_err(RepoError::from)
+        })
+    }
+}
+
+impl RepoFind<OAuthToken, FindOAuthTokenQuery> for OAuthRepoImpl {
+    fn find(&self, query: FindOAuthTokenQuery) -> Result<Vec<OAuthToken>, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        oauth_tokens::table
+            .filter(oauth_tokens::token_hash.eq(query.token_hash))
+            .select(OAuthToken::as_select())
+            .load(&mut conn)
+            .map_err(RepoError::from)
+    }
+}
+
+impl RepoUpdate<OAuthToken, OAuthTokenRevocation> for OAuthRepoImpl {
+    fn update(&self, id: i32, _: OAuthTokenRevocation) -> Result<OAuthToken, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        conn.transaction(|conn| {
+            let token = oauth_tokens::table
+                .filter(oauth_tokens::id.eq(id))
+                .for_update()
+                .select(OAuthToken::as_select())
+                .get_result(conn)?;
+            if token.revoked_at.is_some() {
+                return Ok(token);
+            }
+
+            diesel::update(oauth_tokens::table.find(id))
+                .set(oauth_tokens::revoked_at.eq(Some(chrono::Utc::now().naive_utc())))
+                .returning(OAuthToken::as_returning())
+                .get_result(conn)
+                .map_err(RepoError::from)
+        })
+    }
+}
+
+impl RepoFind<Consent, FindConsentQuery> for OAuthRepoImpl {
+    fn find(&self, query: FindConsentQuery) -> Result<Vec<Consent>, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        let grants = oauth_grants::table
+            .inner_join(oauth_clients::table)
+            .filter(oauth_grants::customer_id.eq(query.customer_id))
+            .order((oauth_grants::created_at.desc(), oauth_grants::id.desc()))
+            .limit(50)
+            .select((
+                OAuthGrant::as_select(),
+                oauth_clients::client_id,
+                oauth_clients::client_name,
+            ))
+            .load(&mut conn)?;
+
+        load_consents(&mut conn, grants)
+    }
+}
+
+impl RepoGetById<Consent> for OAuthRepoImpl {
+    fn get_by_id(&self, id: i32) -> Result<Consent, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        get_consent(&mut conn, id)
+    }
+}
+
+impl RepoUpdate<Consent, ConsentAmendment> for OAuthRepoImpl {
+    fn update(&self, id: i32, amendment: ConsentAmendment) -> Result<Consent, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        conn.transaction(|conn| {
+            lock_active_grant(conn, id)?;
+
+            diesel::update(oauth_grants::table.find(id))
+                .set((
+                    oauth_grants::scope.eq(&amendment.scope),
+                    oauth_grants::expires_at.eq(amendment.expires_at),
+                    oauth_grants::updated_at.eq(chrono::Utc::now().naive_utc()),
+                ))
+                .execute(conn)?;
+            replace_grant_accounts(conn, id, &amendment.account_ids)?;
+
+            // tokens can't outlast the consent they were issued under
+            diesel::update(
+                oauth_tokens::table
+                    .filter(oauth_tokens::grant_id.eq(id))
+                    .filter(oauth_tokens::expires_at.gt(amendment.expires_at)),
+            )
+            .set(oauth_tokens::expires_at.eq(amendment.expires_at))
+            .execute(conn)?;
+
+            get_consent(conn, id)
+        })
+    }
+}
+
+impl RepoUpdate<Consent, ConsentRevocation> for OAuthRepoImpl {
+    fn update(&self, id: i32, _: ConsentRevocation) -> Result<Consent, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        conn.transaction(|conn| {
+            revoke_grant(conn, id)?;
+            get_consent(conn, id)
+        })
+    }
+}
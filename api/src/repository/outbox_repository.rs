@@ -0,0 +1,107 @@
+use diesel::{
+    prelude::*,
+    r2d2::{ConnectionManager, Pool},
+};
+
+use crate::{
+    error::RepoError,
+    models::{
+        outbox::{
+            retry_delay, FindDueOutboxEventQuery, OutboxEvent, OutboxPublishAttempt,
+            RELAY_LEASE_SECONDS,
+        },
+        schema::outbox_events,
+    },
+    traits::{RepoFind, RepoUpdate},
+};
+
+// events are written by the other repos, in the same db transaction as whatever they're about
+// (see repository::util::record_event). this is only the relay's side
+#[derive(Clone)]
+pub struct OutboxRepoImpl {
+    pub pool: Pool<ConnectionManager<PgConnection>>,
+}
+
+impl OutboxRepoImpl {
+    pub fn new(pool: Pool<ConnectionManager<PgConnection>>) -> OutboxRepoImpl {
+        OutboxRepoImpl { pool }
+    }
+}
+
+// leases what it returns so a second relay, or this one on its next poll, leaves them alone
+impl RepoFind<OutboxEvent, FindDueOutboxEventQuery> for OutboxRepoImpl {
+    fn find(&self, query: FindDueOutboxEventQuery) -> Result<Vec<OutboxEvent>, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        conn.transaction(|conn| {
+            let due = outbox_events::table
+                .filter(outbox_events::published_at.is_null())
+                .filter(outbox_events::next_attempt_at.le(query.now))
+                .order(outbox_events::id.asc())
+                .limit(query.limit)
+                .for_update()
+                .skip_locked()
+                .select(OutboxEvent::as_select())
+                .load(conn)?;
+
+            let ids: Vec<i32> = due.iter().map(|event| event.id).collect();
+            diesel::update(outbox_events::table.filter(outbox_events::id.eq_any(ids)))
+                .set(
+                    outbox_events::next_attempt_at
+                        .eq(query.now + chrono::Duration::seconds(RELAY_LEASE_SECONDS)),
+                )
+                .execute(conn)?;
+
+            Ok(due)
+        })
+    }
+}
+
+// published, or put off until its next try
+impl RepoUpdate<OutboxEvent, OutboxPublishAttempt> for OutboxRepoImpl {
+    fn update(&self, id: i32, attempt: OutboxPublishAttempt) -> Result<OutboxEvent, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        conn.transaction(|conn| {
+            let event = outbox_events::table
+                .filter(outbox_events::id.eq(id))
+                .for_update()
+                .select(OutboxEvent::as_select())
+                .get_result(conn)?;
+
+            // a relay whose lease ran out got there first, consumers will have had it twice
+            if event.published_at.is_some() {
+                return Ok(event);
+            }
+
+            let attempts = event.attempts + 1;
+            let update = diesel::update(outbox_events::table.filter(outbox_events::id.eq(id)));
+
+            match attempt.error {
+                None => update
+                    .set((
+                        outbox_events::attempts.eq(attempts),
+                        outbox_events::published_at.eq(Some(attempt.attempted_at)),
+                    ))
+                    .returning(OutboxEvent::as_returning())
+                    .get_result(conn),
+                Some(error) => update
+                    .set((
+                        outbox_events::attempts.eq(attempts),
+                        outbox_events::last_error.eq(Some(error)),
+                        outbox_events::next_attempt_at
+                            .eq(attempt.attempted_at + retry_delay(attempts)),
+                    ))
+                    .returning(OutboxEvent::as_returning())
+                    .get_result(conn),
+            }
+            .map_err(RepoError::from)
+        })
+    }
+}
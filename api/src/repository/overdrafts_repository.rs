@@ -0,0 +1,123 @@
+use diesel::{
+    prelude::*,
+    r2d2::{ConnectionManager, Pool},
+};
+
+use crate::{
+    error::RepoError,
+    models::{
+        account::{Account, AccountType},
+        overdraft::{
+            daily_interest_cents, FindOverdraftChargeQuery, OverdraftCharge, OverdraftChargeKind,
+            OverdraftInterestRun, OverdraftLimitUpdate,
+        },
+        schema::{account_balance_snapshots, accounts, overdraft_charges},
+    },
+    repository::util::charge_overdraft,
+    traits::{RepoCreate, RepoFind, RepoUpdate},
+};
+
+const MAX_CHARGES: i64 = 100;
+
+#[derive(Clone)]
+pub struct OverdraftsRepoImpl {
+    pub pool: Pool<ConnectionManager<PgConnection>>,
+}
+
+impl OverdraftsRepoImpl {
+    pub fn new(pool: Pool<ConnectionManager<PgConnection>>) -> OverdraftsRepoImpl {
+        OverdraftsRepoImpl { pool }
+    }
+}
+
+impl RepoFind<OverdraftCharge, FindOverdraftChargeQuery> for OverdraftsRepoImpl {
+    fn find(
+        &self,
+        charge_query: FindOverdraftChargeQuery,
+    ) -> Result<Vec<OverdraftCharge>, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        let mut query = overdraft_charges::table
+            .filter(overdraft_charges::customer_id.eq(charge_query.customer_id))
+            .into_boxed();
+        if let Some(account_id) = charge_query.account_id {
+            query = query.filter(overdraft_charges::account_id.eq(account_id));
+        }
+
+        query
+            .order(overdraft_charges::id.desc())
+            .limit(MAX_CHARGES)
+            .select(OverdraftCharge::as_select())
+            .load(&mut conn)
+            .map_err(|_| RepoError::Other)
+    }
+}
+
+impl RepoUpdate<Account, OverdraftLimitUpdate> for OverdraftsRepoImpl {
+    fn update(&self, account_id: i32, update: OverdraftLimitUpdate) -> Result<Account, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        diesel::update(accounts::table.filter(accounts::id.eq(account_id)))
+            .set(accounts::overdraft_limit_cents.eq(update.limit_cents))
+            .returning(Account::as_returning())
+            .get_result(&mut conn)
+            .map_err(RepoError::from)
+    }
+}
+
+// interest goes on the balance the account ended the day with, the last snapshot on or before it
+impl RepoCreate<Vec<OverdraftCharge>, OverdraftInterestRun> for OverdraftsRepoImpl {
+    fn create(&self, run: OverdraftInterestRun) -> Result<Vec<OverdraftCharge>, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        // loans sit below zero by design, they have their own interest
+        let end_of_day: Vec<(i32, i64)> = account_balance_snapshots::table
+            .inner_join(accounts::table)
+            .filter(accounts::account_type.ne(AccountType::Loan))
+            .filter(account_balance_snapshots::snapshot_date.le(run.day))
+            .distinct_on(account_balance_snapshots::account_id)
+            .order((
+                account_balance_snapshots::account_id.asc(),
+                account_balance_snapshots::snapshot_date.desc(),
+            ))
+            .select((
+                account_balance_snapshots::account_id,
+                account_balance_snapshots::balance_cents,
+            ))
+            .load(&mut conn)?;
+
+        let mut charges = vec![];
+        for (account_id, balance_cents) in end_of_day.into_iter() {
+            let interest_cents = daily_interest_cents(balance_cents);
+            if interest_cents == 0 {
+                continue;
+            }
+
+            match charge_overdraft(
+                &mut conn,
+                account_id,
+                OverdraftChargeKind::Interest,
+                run.day,
+                interest_cents,
+            ) {
+                Ok(Some(charge)) => charges.push(charge),
+                Ok(None) => {}
+                Err(_) => println!(
+                    "couldn't charge overdraft interest on account {}",
+                    account_id
+                ),
+            }
+        }
+
+        Ok(charges)
+    }
+}
@@ -0,0 +1,149 @@
+use diesel::{
+    prelude::*,
+    r2d2::{ConnectionManager, Pool},
+    result::DatabaseErrorKind,
+};
+
+use crate::{
+    error::RepoError,
+    models::{
+        payee::{FindPayeeQuery, NewPayee, Payee, PayeeUpdate, PayeeUsed},
+        schema::payees,
+    },
+    traits::{RepoCreate, RepoDeleteById, RepoFind, RepoGetById, RepoUpdate},
+};
+
+#[derive(Clone)]
+pub struct PayeesRepoImpl {
+    pub pool: Pool<ConnectionManager<PgConnection>>,
+}
+
+impl PayeesRepoImpl {
+    pub fn new(pool: Pool<ConnectionManager<PgConnection>>) -> PayeesRepoImpl {
+        PayeesRepoImpl { pool }
+    }
+}
+
+impl RepoCreate<Payee, NewPayee> for PayeesRepoImpl {
+    fn create(&self, new_payee: NewPayee) -> Result<Payee, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        diesel::insert_into(payees::table)
+            .values(&new_payee)
+            .returning(Payee::as_returning())
+            .get_result(&mut conn)
+            .map_err(|err| match err {
+                // customer already has this account saved
+                diesel::result::Error::DatabaseError(DatabaseErrorKind::UniqueViolation, _) => {
+                    RepoError::Conflict
+                }
+                _ => RepoError::Other,
+            })
+    }
+}
+
+impl RepoFind<Payee, FindPayeeQuery> for PayeesRepoImpl {
+    fn find(&self, payee_query: FindPayeeQuery) -> Result<Vec<Payee>, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        payees::table
+            .filter(payees::customer_id.eq(payee_query.customer_id))
+            .order(payees::nickname.asc())
+            .select(Payee::as_select())
+            .load(&mut conn)
+            .map_err(|_| RepoError::Other)
+    }
+}
+
+impl RepoGetById<Payee> for PayeesRepoImpl {
+    fn get_by_id(&self, payee_id: i32) -> Result<Payee, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        payees::table
+            .filter(payees::id.eq(payee_id))
+            .select(Payee::as_select())
+            .get_result(&mut conn)
+            .map_err(|err| match err {
+                diesel::result::Error::NotFound => RepoError::NotFound,
+                _ => RepoError::Other,
+            })
+    }
+}
+
+impl RepoUpdate<Payee, PayeeUpdate> for PayeesRepoImpl {
+    fn update(&self, payee_id: i32, update: PayeeUpdate) -> Result<Payee, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        conn.transaction(|conn| {
+            let payee = payees::table
+                .filter(payees::id.eq(payee_id))
+                .for_update()
+                .select(Payee::as_select())
+                .get_result(conn)?;
+
+            diesel::update(payees::table.filter(payees::id.eq(payee.id)))
+                .set((
+                    payees::nickname.eq(update.nickname.unwrap_or(payee.nickname)),
+                    payees::default_reference
+                        .eq(update.default_reference.unwrap_or(payee.default_reference)),
+                ))
+                .returning(Payee::as_returning())
+                .get_result(conn)
+                .map_err(RepoError::from)
+        })
+    }
+}
+
+// first use is only ever set once, last use every time
+impl RepoUpdate<Payee, PayeeUsed> for PayeesRepoImpl {
+    fn update(&self, payee_id: i32, used: PayeeUsed) -> Result<Payee, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        conn.transaction(|conn| {
+            let payee = payees::table
+                .filter(payees::id.eq(payee_id))
+                .for_update()
+                .select(Payee::as_select())
+                .get_result(conn)?;
+
+            diesel::update(payees::table.filter(payees::id.eq(payee.id)))
+                .set((
+                    payees::first_used_at.eq(payee.first_used_at.or(Some(used.used_at))),
+                    payees::last_used_at.eq(Some(used.used_at)),
+                ))
+                .returning(Payee::as_returning())
+                .get_result(conn)
+                .map_err(RepoError::from)
+        })
+    }
+}
+
+impl RepoDeleteById<Payee> for PayeesRepoImpl {
+    fn delete_by_id(&self, payee_id: i32) -> Result<(), RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        diesel::delete(payees::table.filter(payees::id.eq(payee_id)))
+            .execute(&mut conn)
+            .map_err(|_| RepoError::Other)?;
+
+        Ok(())
+    }
+}
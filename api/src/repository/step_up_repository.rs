@@ -0,0 +1,224 @@
+use diesel::{
+    prelude::*,
+    r2d2::{ConnectionManager, Pool},
+};
+
+use crate::{
+    error::RepoError,
+    models::{
+        schema::{step_up_challenges, totp_enrolments},
+        step_up::{
+            NewStepUpChallenge, NewTotpEnrolment, StepUpAttempt, StepUpChallenge, StepUpRedemption,
+            TotpConfirmation, TotpEnrolment,
+        },
+    },
+    repository::util::redeem_step_up,
+    traits::{RepoCreate, RepoDeleteById, RepoGetById, RepoUpdate},
+};
+
+#[derive(Clone)]
+pub struct StepUpRepoImpl {
+    pub pool: Pool<ConnectionManager<PgConnection>>,
+}
+
+impl StepUpRepoImpl {
+    pub fn new(pool: Pool<ConnectionManager<PgConnection>>) -> StepUpRepoImpl {
+        StepUpRepoImpl { pool }
+    }
+}
+
+// by customer id, a customer has at most one
+impl RepoGetById<TotpEnrolment> for StepUpRepoImpl {
+    fn get_by_id(&self, customer_id: i32) -> Result<TotpEnrolment, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        totp_enrolments::table
+            .filter(totp_enrolments::customer_id.eq(customer_id))
+            .select(TotpEnrolment::as_select())
+            .get_result(&mut conn)
+            .map_err(RepoError::from)
+    }
+}
+
+// starting over replaces an unconfirmed enrolment, a confirmed one has to be removed first
+impl RepoCreate<TotpEnrolment, NewTotpEnrolment> for StepUpRepoImpl {
+    fn create(&self, new_enrolment: NewTotpEnrolment) -> Result<TotpEnrolment, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        conn.transaction(|conn| {
+            let existing = totp_enrolments::table
+                .filter(totp_enrolments::customer_id.eq(new_enrolment.customer_id))
+                .for_update()
+                .select(TotpEnrolment::as_select())
+                .get_result(conn)
+                .optional()?;
+            if existing.is_some_and(|enrolment| enrolment.is_confirmed()) {
+                return Err(RepoError::Conflict);
+            }
+
+            diesel::insert_into(totp_enrolments::table)
+                .values(&new_enrolment)
+                .on_conflict(totp_enrolments::customer_id)
+                .do_update()
+                .set((
+                    totp_enrolments::secret.eq(&new_enrolment.secret),
+                    totp_enrolments::last_used_step.eq(None::<i64>),
+                    totp_enrolments::created_at.eq(chrono::Utc::now().naive_utc()),
+                ))
+                .returning(TotpEnrolment::as_returning())
+                .get_result(conn)
+                .map_err(RepoError::from)
+        })
+    }
+}
+
+impl RepoUpdate<TotpEnrolment, TotpConfirmation> for StepUpRepoImpl {
+    fn update(
+        &self,
+        customer_id: i32,
+        confirmation: TotpConfirmation,
+    ) -> Result<TotpEnrolment, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        diesel::update(
+            totp_enrolments::table
+                .filter(totp_enrolments::customer_id.eq(customer_id))
+                .filter(totp_enrolments::confirmed_at.is_null()),
+        )
+        .set((
+            totp_enrolments::confirmed_at.eq(Some(chrono::Utc::now().naive_utc())),
+            totp_enrolments::last_used_step.eq(Some(confirmation.step)),
+        ))
+        .returning(TotpEnrolment::as_returning())
+        .get_result(&mut conn)
+        .optional()?
+        .ok_or(RepoError::Conflict)
+    }
+}
+
+impl RepoDeleteById<TotpEnrolment> for StepUpRepoImpl {
+    fn delete_by_id(&self, customer_id: i32) -> Result<(), RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        diesel::delete(totp_enrolments::table.filter(totp_enrolments::customer_id.eq(customer_id)))
+            .execute(&mut conn)
+            .map_err(|_| RepoError::Other)?;
+
+        Ok(())
+    }
+}
+
+impl RepoCreate<StepUpChallenge, NewStepUpChallenge> for StepUpRepoImpl {
+    fn create(&self, new_challenge: NewStepUpChallenge) -> Result<StepUpChallenge, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        diesel::insert_into(step_up_challenges::table)
+            .values(&new_challenge)
+            .returning(StepUpChallenge::as_returning())
+            .get_result(&mut conn)
+            .map_err(RepoError::from)
+    }
+}
+
+impl RepoGetById<StepUpChallenge> for StepUpRepoImpl {
+    fn get_by_id(&self, challenge_id: i32) -> Result<StepUpChallenge, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        step_up_challenges::table
+            .filter(step_up_challenges::id.eq(challenge_id))
+            .select(StepUpChallenge::as_select())
+            .get_result(&mut conn)
+            .map_err(RepoError::from)
+    }
+}
+
+// counts the attempt whether or not the code was right. a totp code is only good once, if another
+// challenge got to its step first this one fails
+impl RepoUpdate<StepUpChallenge, StepUpAttempt> for StepUpRepoImpl {
+    fn update(
+        &self,
+        challenge_id: i32,
+        attempt: StepUpAttempt,
+    ) -> Result<StepUpChallenge, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        conn.transaction(|conn| {
+            let challenge = step_up_challenges::table
+                .filter(step_up_challenges::id.eq(challenge_id))
+                .for_update()
+                .select(StepUpChallenge::as_select())
+                .get_result(conn)?;
+
+            let now = chrono::Utc::now().naive_utc();
+
+            if !challenge.is_open(now) {
+                return Err(RepoError::Conflict);
+            }
+
+            let mut passed = attempt.passed;
+            if let (true, Some(step)) = (passed, attempt.totp_step) {
+                let used = diesel::update(
+                    totp_enrolments::table
+                        .filter(totp_enrolments::customer_id.eq(challenge.customer_id))
+                        .filter(
+                            totp_enrolments::last_used_step
+                                .is_null()
+                                .or(totp_enrolments::last_used_step.lt(step)),
+                        ),
+                )
+                .set(totp_enrolments::last_used_step.eq(Some(step)))
+                .execute(conn)?;
+                passed = used == 1;
+            }
+
+            diesel::update(
+                step_up_challenges::table.filter(step_up_challenges::id.eq(challenge.id)),
+            )
+            .set((
+                step_up_challenges::attempts.eq(challenge.attempts + 1),
+                step_up_challenges::verified_at.eq(passed.then_some(now)),
+            ))
+            .returning(StepUpChallenge::as_returning())
+            .get_result(conn)
+            .map_err(RepoError::from)
+        })
+    }
+}
+
+// uses up a verified challenge, NotFound unless it's verified, unexpired, unused and for exactly
+// this action
+impl RepoUpdate<StepUpChallenge, StepUpRedemption> for StepUpRepoImpl {
+    fn update(
+        &self,
+        challenge_id: i32,
+        redemption: StepUpRedemption,
+    ) -> Result<StepUpChallenge, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        redeem_step_up(&mut conn, challenge_id, &redemption)
+    }
+}
@@ -1,15 +1,28 @@
 use diesel::{
     prelude::*,
     r2d2::{ConnectionManager, Pool},
+    result::DatabaseErrorKind,
 };
 
 use crate::{
     error::RepoError,
     models::{
-        schema::transactions,
-        transaction::{FindTransactionQuery, NewTransaction, Transaction},
+        account::AccountType,
+        account_holder::{NewSignedTransfer, NewTransferSignature},
+        category::FindSpendingQuery,
+        fraud_review::NewHeldTransaction,
+        schema::{account_holders, accounts, transactions, transfer_signatures},
+        transaction::{
+            FindAccountHistoryQuery, FindPaymentsToQuery, FindTransactionQuery, NewReversal,
+            NewTransaction, Transaction, TransactionStatus,
+        },
     },
-    traits::{RepoCreate, RepoFind},
+    repository::util::{
+        adjust_account_balance, advance_transfer, create_held_transfer, create_signed_transfer,
+        find_spending, needs_signature, recheck_transfer_limits, record_new_transaction,
+        set_transfer_status,
+    },
+    traits::{RepoCreate, RepoFind, RepoGetById},
 };
 
 #[derive(Clone)]
@@ -23,18 +36,76 @@ impl TransactionsRepoImpl {
     }
 }
 
-impl RepoCreate<Transaction, NewTransaction> for TransactionsRepoImpl {
-    fn create(&self, new_transaction: NewTransaction) -> Result<Transaction, RepoError> {
+// whatever the account needs before it goes, another signature or an approval, it waits for.
+// otherwise internal transfers settle straight away, both accounts are ours
+impl RepoCreate<Transaction, NewSignedTransfer> for TransactionsRepoImpl {
+    fn create(&self, signed: NewSignedTransfer) -> Result<Transaction, RepoError> {
         let mut conn = self.pool.get().map_err(|_| {
             println!("couldn't get db connection from pool");
             RepoError::ConnectionError
         })?;
 
-        Ok(diesel::insert_into(transactions::table)
-            .values(&new_transaction)
-            .returning(Transaction::as_returning())
-            .get_result(&mut conn)
-            .map_err(|_| RepoError::Other)?)
+        conn.transaction(|conn| {
+            recheck_transfer_limits(conn, &signed.transaction)?;
+            create_signed_transfer(conn, &signed)
+        })
+    }
+}
+
+// transaction screening held, queued for staff review. no money moves until it's approved
+impl RepoCreate<Transaction, NewHeldTransaction> for TransactionsRepoImpl {
+    fn create(&self, held: NewHeldTransaction) -> Result<Transaction, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        conn.transaction(|conn| {
+            recheck_transfer_limits(conn, &held.transaction)?;
+            create_held_transfer(conn, &held)
+        })
+    }
+}
+
+// a holder's signature on a transfer waiting for one, the last one needed lets it move on
+impl RepoCreate<Transaction, NewTransferSignature> for TransactionsRepoImpl {
+    fn create(&self, signature: NewTransferSignature) -> Result<Transaction, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        conn.transaction(|conn| {
+            // row lock so two holders signing at once can't both release it
+            let transfer = transactions::table
+                .filter(transactions::id.eq(signature.transaction_id))
+                .for_update()
+                .select(Transaction::as_select())
+                .get_result(conn)?;
+
+            if transfer.transaction_status != TransactionStatus::AwaitingSignature {
+                return Err(RepoError::Conflict);
+            }
+
+            diesel::insert_into(transfer_signatures::table)
+                .values(&signature)
+                .execute(conn)
+                .map_err(|err| match err {
+                    // can't sign the same transfer twice
+                    diesel::result::Error::DatabaseError(DatabaseErrorKind::UniqueViolation, _) => {
+                        RepoError::Conflict
+                    }
+                    _ => RepoError::Other,
+                })?;
+
+            if needs_signature(conn, &transfer)? {
+                return Ok(transfer);
+            }
+
+            let pending = set_transfer_status(conn, transfer.id, TransactionStatus::Pending)?;
+
+            advance_transfer(conn, &pending)
+        })
     }
 }
 
@@ -50,20 +121,338 @@ impl RepoFind<Transaction, FindTransactionQuery> for TransactionsRepoImpl {
             query = query.filter(transactions::id.eq(id));
         }
 
-        query = query.filter(transactions::customer_id.eq(transaction_query.customer_id));
+        // the customer's side of the row is an account they hold. their own rows stay theirs
+        // after the account's closed
+        let held_numbers = || {
+            account_holders::table
+                .inner_join(accounts::table)
+                .filter(account_holders::customer_id.eq(transaction_query.customer_id))
+                .select(accounts::account_number)
+        };
+        query = query.filter(
+            transactions::customer_id
+                .eq(transaction_query.customer_id)
+                .or(transactions::from_us.and(transactions::from_number.eq_any(held_numbers())))
+                .or(transactions::from_us
+                    .eq(false)
+                    .and(transactions::to_number.eq_any(held_numbers()))),
+        );
 
         if let Some(account_number) = transaction_query.account_number {
+            // only the side of the row that's the customer's, see Transaction::from_us
+            query = query.filter(
+                transactions::from_us
+                    .and(transactions::from_number.eq(account_number.clone()))
+                    .or(transactions::from_us
+                        .eq(false)
+                        .and(transactions::to_number.eq(account_number))),
+            );
+        }
+
+        if let Some(date_from) = transaction_query.date_from {
+            query = query.filter(transactions::date_start.ge(date_from));
+        }
+
+        if let Some(date_to) = transaction_query.date_to {
+            query = query.filter(transactions::date_start.le(date_to));
+        }
+
+        if let Some(search) = transaction_query.search {
+            let pattern = format!("%{}%", escape_like(&search));
             query = query.filter(
-                transactions::from_number
-                    .eq(account_number.clone())
-                    .or(transactions::to_number.eq(account_number)),
+                transactions::description
+                    .ilike(pattern.clone())
+                    .or(transactions::payer_reference.ilike(pattern.clone()))
+                    .or(transactions::payee_reference.ilike(pattern)),
             );
         }
 
-        Ok(query
+        query
+            .order(transactions::date_start.desc())
             .limit(50)
             .select(Transaction::as_select())
             .load(&mut conn)
-            .map_err(|_| RepoError::Other)?)
+            .map_err(|_| RepoError::Other)
+    }
+}
+
+// a long window on a busy account is still bounded
+const MAX_ACCOUNT_HISTORY: i64 = 10_000;
+
+impl RepoFind<Transaction, FindAccountHistoryQuery> for TransactionsRepoImpl {
+    fn find(&self, history_query: FindAccountHistoryQuery) -> Result<Vec<Transaction>, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        // the account, as long as the customer holds it
+        let held_number = || {
+            account_holders::table
+                .inner_join(accounts::table)
+                .filter(account_holders::customer_id.eq(history_query.customer_id))
+                .filter(accounts::account_number.eq(history_query.account_number.clone()))
+                .select(accounts::account_number)
+        };
+
+        // only the side of the row that's the customer's, see Transaction::from_us
+        let mut query = transactions::table
+            .filter(
+                transactions::from_us
+                    .and(transactions::from_number.eq_any(held_number()))
+                    .or(transactions::from_us
+                        .eq(false)
+                        .and(transactions::to_number.eq_any(held_number()))),
+            )
+            .filter(transactions::date_start.ge(history_query.from))
+            .filter(transactions::date_start.le(history_query.to))
+            .into_boxed();
+
+        if let Some(search) = history_query.search {
+            let pattern = format!("%{}%", escape_like(&search));
+            query = query.filter(
+                transactions::description
+                    .ilike(pattern.clone())
+                    .or(transactions::payer_reference.ilike(pattern.clone()))
+                    .or(transactions::payee_reference.ilike(pattern)),
+            );
+        }
+
+        query
+            .order((transactions::date_start.desc(), transactions::id.desc()))
+            .limit(MAX_ACCOUNT_HISTORY)
+            .select(Transaction::as_select())
+            .load(&mut conn)
+            .map_err(|_| RepoError::Other)
+    }
+}
+
+// only needs to know whether there are any, so stops at the first
+impl RepoFind<Transaction, FindPaymentsToQuery> for TransactionsRepoImpl {
+    fn find(&self, payments_query: FindPaymentsToQuery) -> Result<Vec<Transaction>, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        let held_numbers = account_holders::table
+            .inner_join(accounts::table)
+            .filter(account_holders::customer_id.eq(payments_query.customer_id))
+            .select(accounts::account_number);
+
+        transactions::table
+            .filter(transactions::from_us)
+            .filter(transactions::from_number.eq_any(held_numbers))
+            .filter(transactions::to_number.eq(payments_query.to_number))
+            .filter(transactions::to_bsb.eq(payments_query.to_bsb))
+            .filter(transactions::transaction_status.eq_any([
+                TransactionStatus::Pending,
+                TransactionStatus::Success,
+                TransactionStatus::PartiallyReversed,
+            ]))
+            .limit(1)
+            .select(Transaction::as_select())
+            .load(&mut conn)
+            .map_err(|_| RepoError::Other)
+    }
+}
+
+impl RepoFind<Transaction, FindSpendingQuery> for TransactionsRepoImpl {
+    fn find(&self, spending_query: FindSpendingQuery) -> Result<Vec<Transaction>, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        find_spending(&mut conn, &spending_query).map_err(|_| RepoError::Other)
+    }
+}
+
+// search text is matched literally, not as a pattern
+fn escape_like(search: &str) -> String {
+    search
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+impl RepoGetById<Transaction> for TransactionsRepoImpl {
+    fn get_by_id(&self, transaction_id: i32) -> Result<Transaction, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        transactions::table
+            .filter(transactions::id.eq(transaction_id))
+            .select(Transaction::as_select())
+            .get_result(&mut conn)
+            .map_err(|err| match err {
+                diesel::result::Error::NotFound => RepoError::NotFound,
+                _ => RepoError::Other,
+            })
+    }
+}
+
+// creates the compensating transaction, marks the original as (partially) reversed and moves
+// the money back between whichever of the two accounts live with us, all or nothing
+impl RepoCreate<Transaction, NewReversal> for TransactionsRepoImpl {
+    fn create(&self, reversal: NewReversal) -> Result<Transaction, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        conn.transaction(|conn| {
+            // row lock so two reversals of the same transaction can't both see the old amount
+            let original = transactions::table
+                .filter(transactions::id.eq(reversal.transaction_id))
+                .for_update()
+                .select(Transaction::as_select())
+                .get_result(conn)?;
+
+            if !original.is_reversible() {
+                return Err(RepoError::Conflict);
+            }
+
+            let amount_cents = reversal
+                .amount_cents
+                .unwrap_or(original.reversible_amount_cents());
+
+            if amount_cents <= 0 || amount_cents > original.reversible_amount_cents() {
+                return Err(RepoError::Conflict);
+            }
+
+            let reversed_amount_cents = original.reversed_amount_cents + amount_cents;
+            let status = if reversed_amount_cents == original.amount_cents {
+                TransactionStatus::Reversed
+            } else {
+                TransactionStatus::PartiallyReversed
+            };
+
+            diesel::update(transactions::table.filter(transactions::id.eq(original.id)))
+                .set((
+                    transactions::reversed_amount_cents.eq(reversed_amount_cents),
+                    transactions::transaction_status.eq(status),
+                ))
+                .execute(conn)?;
+
+            // money goes back to the original sender and out of the original recipient, external
+            // payees aren't ours to touch. same order as settling so the two can't deadlock
+            let from = (original.from_bsb.as_str(), original.from_number.as_str());
+            let to = (original.to_bsb.as_str(), original.to_number.as_str());
+            let (refunded, recalled_from) = if from < to {
+                let refunded = adjust_account_balance(conn, from.1, from.0, amount_cents)?;
+                let recalled_from = adjust_account_balance(conn, to.1, to.0, -amount_cents)?;
+                (refunded, recalled_from)
+            } else {
+                let recalled_from = adjust_account_balance(conn, to.1, to.0, -amount_cents)?;
+                let refunded = adjust_account_balance(conn, from.1, from.0, amount_cents)?;
+                (refunded, recalled_from)
+            };
+
+            if let Some(recalled_from) = &recalled_from {
+                // a repayment has already gone against the loan's schedule, taking it back would
+                // put the debt back without the installments it paid
+                if recalled_from.account_type == AccountType::Loan {
+                    return Err(RepoError::Conflict);
+                }
+                // the row's locked by the update, the recipient can't have spent it since we looked
+                if recalled_from.spendable_cents() < 0 {
+                    return Err(RepoError::Conflict);
+                }
+            }
+
+            let compensating = NewTransaction {
+                customer_id: original.customer_id,
+                transaction_type: original.transaction_type,
+                from_us: !original.from_us,
+                amount_cents,
+                from_number: original.to_number.clone(),
+                from_bsb: original.to_bsb.clone(),
+                from_name: original.to_name.clone(),
+                to_number: original.from_number.clone(),
+                to_bsb: original.from_bsb.clone(),
+                to_name: original.from_name.clone(),
+                available_balance_cents: refunded
+                    .as_ref()
+                    .map_or(0, |acc| acc.available_balance_cents),
+                transaction_status: TransactionStatus::Success,
+                reversal_of: Some(original.id),
+                reversal_reason: Some(reversal.reason),
+                // money is going the other way, so are the references
+                description: original.description.clone(),
+                payer_reference: original.payee_reference.clone(),
+                payee_reference: original.payer_reference.clone(),
+            };
+
+            let now = chrono::Utc::now().naive_utc();
+
+            let compensating = diesel::insert_into(transactions::table)
+                .values((
+                    &compensating,
+                    transactions::date_end.eq(Some(now)),
+                    // only known if the original sender's account is ours
+                    transactions::running_balance_cents.eq(refunded.map(|acc| acc.balance_cents)),
+                ))
+                .returning(Transaction::as_returning())
+                .get_result(conn)?;
+
+            record_new_transaction(conn, &compensating)?;
+
+            // the recipient's entry of an internal transfer gets reversed alongside it
+            let received = transactions::table
+                .filter(transactions::counterpart_of.eq(original.id))
+                .for_update()
+                .select(Transaction::as_select())
+                .get_result(conn)
+                .optional()?;
+
+            if let Some(received) = received {
+                diesel::update(transactions::table.filter(transactions::id.eq(received.id)))
+                    .set((
+                        transactions::reversed_amount_cents.eq(reversed_amount_cents),
+                        transactions::transaction_status.eq(status),
+                    ))
+                    .execute(conn)?;
+
+                let recalled = NewTransaction {
+                    customer_id: received.customer_id,
+                    transaction_type: received.transaction_type,
+                    from_us: true,
+                    amount_cents,
+                    from_number: received.to_number.clone(),
+                    from_bsb: received.to_bsb.clone(),
+                    from_name: received.to_name.clone(),
+                    to_number: received.from_number.clone(),
+                    to_bsb: received.from_bsb.clone(),
+                    to_name: received.from_name.clone(),
+                    available_balance_cents: recalled_from
+                        .as_ref()
+                        .map_or(0, |acc| acc.available_balance_cents),
+                    transaction_status: TransactionStatus::Success,
+                    reversal_of: Some(received.id),
+                    reversal_reason: compensating.reversal_reason.clone(),
+                    description: None,
+                    payer_reference: None,
+                    payee_reference: received.payee_reference.clone(),
+                };
+
+                let recalled = diesel::insert_into(transactions::table)
+                    .values((
+                        &recalled,
+                        transactions::date_end.eq(Some(now)),
+                        transactions::counterpart_of.eq(Some(compensating.id)),
+                        transactions::running_balance_cents
+                            .eq(recalled_from.map(|acc| acc.balance_cents)),
+                    ))
+                    .returning(Transaction::as_returning())
+                    .get_result(conn)?;
+
+                record_new_transaction(conn, &recalled)?;
+            }
+
+            Ok(compensating)
+        })
     }
 }
@@ -0,0 +1,146 @@
+use diesel::{
+    prelude::*,
+    r2d2::{ConnectionManager, Pool},
+};
+
+use crate::{
+    error::RepoError,
+    models::{
+        schema::transfer_limit_requests,
+        transfer_limit::{
+            FindTransferLimitRequestQuery, LimitRequestDecision, LimitRequestStatus,
+            NewTransferLimitRequest, TransferLimitRequest,
+        },
+    },
+    repository::util::set_customer_limit,
+    traits::{RepoCreate, RepoFind, RepoGetById, RepoUpdate},
+};
+
+#[derive(Clone)]
+pub struct TransferLimitRequestsRepoImpl {
+    pub pool: Pool<ConnectionManager<PgConnection>>,
+}
+
+impl TransferLimitRequestsRepoImpl {
+    pub fn new(pool: Pool<ConnectionManager<PgConnection>>) -> TransferLimitRequestsRepoImpl {
+        TransferLimitRequestsRepoImpl { pool }
+    }
+}
+
+impl RepoCreate<TransferLimitRequest, NewTransferLimitRequest> for TransferLimitRequestsRepoImpl {
+    fn create(
+        &self,
+        new_request: NewTransferLimitRequest,
+    ) -> Result<TransferLimitRequest, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        diesel::insert_into(transfer_limit_requests::table)
+            .values(&new_request)
+            .returning(TransferLimitRequest::as_returning())
+            .get_result(&mut conn)
+            .map_err(|_| RepoError::Other)
+    }
+}
+
+impl RepoFind<TransferLimitRequest, FindTransferLimitRequestQuery>
+    for TransferLimitRequestsRepoImpl
+{
+    fn find(
+        &self,
+        request_query: FindTransferLimitRequestQuery,
+    ) -> Result<Vec<TransferLimitRequest>, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        let mut query = transfer_limit_requests::table.into_boxed();
+
+        if let Some(customer_id) = request_query.customer_id {
+            query = query.filter(transfer_limit_requests::customer_id.eq(customer_id));
+        }
+
+        if let Some(request_status) = request_query.request_status {
+            query = query.filter(transfer_limit_requests::request_status.eq(request_status));
+        }
+
+        query
+            .order(transfer_limit_requests::created_at.desc())
+            .limit(50)
+            .select(TransferLimitRequest::as_select())
+            .load(&mut conn)
+            .map_err(|_| RepoError::Other)
+    }
+}
+
+impl RepoGetById<TransferLimitRequest> for TransferLimitRequestsRepoImpl {
+    fn get_by_id(&self, request_id: i32) -> Result<TransferLimitRequest, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        transfer_limit_requests::table
+            .filter(transfer_limit_requests::id.eq(request_id))
+            .select(TransferLimitRequest::as_select())
+            .get_result(&mut conn)
+            .map_err(|err| match err {
+                diesel::result::Error::NotFound => RepoError::NotFound,
+                _ => RepoError::Other,
+            })
+    }
+}
+
+// records the decision and, if approved, raises the customer's limit in the same go
+impl RepoUpdate<TransferLimitRequest, LimitRequestDecision> for TransferLimitRequestsRepoImpl {
+    fn update(
+        &self,
+        request_id: i32,
+        decision: LimitRequestDecision,
+    ) -> Result<TransferLimitRequest, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        conn.transaction(|conn| {
+            let request = transfer_limit_requests::table
+                .filter(transfer_limit_requests::id.eq(request_id))
+                .for_update()
+                .select(TransferLimitRequest::as_select())
+                .get_result(conn)?;
+
+            if request.request_status != LimitRequestStatus::Pending {
+                return Err(RepoError::Conflict);
+            }
+
+            let request_status = if decision.approve {
+                set_customer_limit(
+                    conn,
+                    request.customer_id,
+                    request.limit_kind,
+                    request.requested_cents,
+                )?;
+                LimitRequestStatus::Approved
+            } else {
+                LimitRequestStatus::Declined
+            };
+
+            diesel::update(
+                transfer_limit_requests::table.filter(transfer_limit_requests::id.eq(request.id)),
+            )
+            .set((
+                transfer_limit_requests::request_status.eq(request_status),
+                transfer_limit_requests::reviewer_id.eq(Some(decision.reviewer_id)),
+                transfer_limit_requests::review_note.eq(decision.note),
+                transfer_limit_requests::reviewed_at.eq(Some(chrono::Utc::now().naive_utc())),
+            ))
+            .returning(TransferLimitRequest::as_returning())
+            .get_result(conn)
+            .map_err(RepoError::from)
+        })
+    }
+}
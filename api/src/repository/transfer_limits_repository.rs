@@ -0,0 +1,69 @@
+use diesel::{
+    prelude::*,
+    r2d2::{ConnectionManager, Pool},
+};
+
+use crate::{
+    error::RepoError,
+    models::transfer_limit::{
+        FindTransferUsageQuery, TransferLimitUpdate, TransferLimits, TransferUsage,
+    },
+    repository::util::{set_customer_limit, transfer_limits, transfer_usage},
+    traits::{RepoFind, RepoGetById, RepoUpdate},
+};
+
+#[derive(Clone)]
+pub struct TransferLimitsRepoImpl {
+    pub pool: Pool<ConnectionManager<PgConnection>>,
+}
+
+impl TransferLimitsRepoImpl {
+    pub fn new(pool: Pool<ConnectionManager<PgConnection>>) -> TransferLimitsRepoImpl {
+        TransferLimitsRepoImpl { pool }
+    }
+}
+
+// by customer id, every customer has limits even if it's just the defaults
+impl RepoGetById<TransferLimits> for TransferLimitsRepoImpl {
+    fn get_by_id(&self, customer_id: i32) -> Result<TransferLimits, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        transfer_limits(&mut conn, customer_id).map_err(|_| RepoError::Other)
+    }
+}
+
+impl RepoUpdate<TransferLimits, TransferLimitUpdate> for TransferLimitsRepoImpl {
+    fn update(
+        &self,
+        customer_id: i32,
+        update: TransferLimitUpdate,
+    ) -> Result<TransferLimits, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        let overrides = conn
+            .transaction(|conn| {
+                set_customer_limit(conn, customer_id, update.limit_kind, update.amount_cents)
+            })
+            .map_err(|_| RepoError::Other)?;
+
+        Ok(TransferLimits::effective(customer_id, Some(overrides)))
+    }
+}
+
+impl RepoFind<TransferUsage, FindTransferUsageQuery> for TransferLimitsRepoImpl {
+    fn find(&self, usage_query: FindTransferUsageQuery) -> Result<Vec<TransferUsage>, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        transfer_usage(&mut conn, usage_query.customer_id, usage_query.since)
+            .map_err(|_| RepoError::Other)
+    }
+}
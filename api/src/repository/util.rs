@@ -0,0 +1,996 @@
+// helpers shared between repos, meant to be called inside a db transaction
+
+use chrono::{NaiveDate, NaiveDateTime};
+use diesel::prelude::*;
+
+use crate::{
+    error::RepoError,
+    models::{
+        account::{Account, AccountType, NewAccount, SigningRule},
+        account_holder::{
+            HolderRole, NewAccountHolder, NewSignedTransfer, NewTransferSignature,
+            REQUIRED_SIGNATURES,
+        },
+        approval::{
+            ApprovalPolicy, ApprovalStatus, NewTransferApproval, NewTransferApprovalEvent,
+            TransferApproval,
+        },
+        audit::ActorType,
+        balance_snapshot::BalanceSnapshot,
+        bucket::{spread_balance_change, AccountBucket},
+        budget::{month_start, next_month_start},
+        category::FindSpendingQuery,
+        fee::{AppliedFee, FeeKind, FeeScheduleEntry, NewFeeCharge},
+        fraud_review::{NewFraudReview, NewHeldTransaction},
+        loan::{amortise, Loan, LoanScheduleEntry, NewLoanScheduleEntry, ScheduledRepayment},
+        outbox::{account_data, transaction_data, EventType, NewOutboxEvent},
+        overdraft::{NewOverdraftCharge, OverdraftCharge, OverdraftChargeKind},
+        schema::{
+            account_balance_snapshots, account_buckets, account_holders, accounts,
+            approval_policies, customer_transfer_limits, fee_charges, fee_schedule, fraud_reviews,
+            loan_schedule_entries, loans, outbox_events, overdraft_charges, step_up_challenges,
+            transactions, transfer_approval_events, transfer_approvals, transfer_signatures,
+        },
+        step_up::{StepUpChallenge, StepUpRedemption},
+        transaction::{NewTransaction, Transaction, TransactionStatus, TransactionType},
+        transfer_limit::{
+            CustomerTransferLimits, TransferLimitKind, TransferLimits, TransferUsage,
+        },
+    },
+    util::start_of_day,
+};
+
+// goes into the outbox with the change it's about, and is published once that's committed
+pub fn record_event(
+    conn: &mut PgConnection,
+    event_type: EventType,
+    data: serde_json::Value,
+) -> QueryResult<()> {
+    let event = NewOutboxEvent::new(event_type, data, chrono::Utc::now().naive_utc());
+
+    diesel::insert_into(outbox_events::table)
+        .values(&event)
+        .execute(conn)?;
+
+    Ok(())
+}
+
+// a row that's just gone into transactions. anything that went in already settled is settled as
+// far as anyone downstream is concerned too
+pub fn record_new_transaction(
+    conn: &mut PgConnection,
+    transaction: &Transaction,
+) -> QueryResult<()> {
+    let data = transaction_data(transaction);
+
+    record_event(conn, EventType::TransactionCreated, data.clone())?;
+    if transaction.is_settled() {
+        record_event(conn, EventType::TransactionSettled, data)?;
+    }
+
+    Ok(())
+}
+
+// whoever opens an account is its first owner, and stays its primary holder
+pub fn open_account(conn: &mut PgConnection, new_account: &NewAccount) -> QueryResult<Account> {
+    let account = diesel::insert_into(accounts::table)
+        .values(new_account)
+        .returning(Account::as_returning())
+        .get_result(conn)?;
+
+    diesel::insert_into(account_holders::table)
+        .values(&NewAccountHolder {
+            account_id: account.id,
+            customer_id: account.customer_id,
+            holder_role: HolderRole::Owner,
+        })
+        .execute(conn)?;
+
+    record_event(conn, EventType::AccountOpened, account_data(&account))?;
+
+    Ok(account)
+}
+
+// moves both balances by delta_cents and keeps today's snapshot and any savings buckets in step,
+// returns the account if it's ours
+pub fn adjust_account_balance(
+    conn: &mut PgConnection,
+    account_number: &str,
+    bsb: &str,
+    delta_cents: i64,
+) -> QueryResult<Option<Account>> {
+    let account = diesel::update(
+        accounts::table
+            .filter(accounts::account_number.eq(account_number))
+            .filter(accounts::bsb.eq(bsb)),
+    )
+    .set((
+        accounts::balance_cents.eq(accounts::balance_cents + delta_cents),
+        accounts::available_balance_cents.eq(accounts::available_balance_cents + delta_cents),
+    ))
+    .returning(Account::as_returning())
+    .get_result(conn)
+    .optional()?;
+
+    if let Some(account) = &account {
+        let now = chrono::Utc::now().naive_utc();
+        let snapshot = BalanceSnapshot {
+            account_id: account.id,
+            snapshot_date: now.date(),
+            balance_cents: account.balance_cents,
+            available_balance_cents: account.available_balance_cents,
+            updated_at: now,
+        };
+
+        diesel::insert_into(account_balance_snapshots::table)
+            .values(&snapshot)
+            .on_conflict((
+                account_balance_snapshots::account_id,
+                account_balance_snapshots::snapshot_date,
+            ))
+            .do_update()
+            .set((
+                account_balance_snapshots::balance_cents.eq(snapshot.balance_cents),
+                account_balance_snapshots::available_balance_cents
+                    .eq(snapshot.available_balance_cents),
+                account_balance_snapshots::updated_at.eq(snapshot.updated_at),
+            ))
+            .execute(conn)?;
+
+        spread_over_buckets(conn, account.id, delta_cents)?;
+    }
+
+    Ok(account)
+}
+
+// bucket totals are checked against the account balance when the db transaction commits
+pub fn spread_over_buckets(
+    conn: &mut PgConnection,
+    account_id: i32,
+    delta_cents: i64,
+) -> QueryResult<()> {
+    let buckets = account_buckets::table
+        .filter(account_buckets::account_id.eq(account_id))
+        .for_update()
+        .select(AccountBucket::as_select())
+        .load(conn)?;
+
+    let now = chrono::Utc::now().naive_utc();
+    for (bucket_id, balance_cents) in spread_balance_change(&buckets, delta_cents) {
+        diesel::update(account_buckets::table.filter(account_buckets::id.eq(bucket_id)))
+            .set((
+                account_buckets::balance_cents.eq(balance_cents),
+                account_buckets::updated_at.eq(now),
+            ))
+            .execute(conn)?;
+    }
+
+    Ok(())
+}
+
+// the transfer path, internal transfers settle straight away. any transfer fee goes on with it
+pub fn create_transaction(
+    conn: &mut PgConnection,
+    new_transaction: &NewTransaction,
+) -> Result<Transaction, RepoError> {
+    let mut transaction = diesel::insert_into(transactions::table)
+        .values(new_transaction)
+        .returning(Transaction::as_returning())
+        .get_result(conn)?;
+
+    record_new_transaction(conn, &transaction)?;
+
+    if transaction.transaction_type == TransactionType::Internal
+        && transaction.transaction_status == TransactionStatus::Pending
+    {
+        transaction = settle_internal_transfer(conn, &transaction)?;
+    }
+
+    charge_transfer_fees(conn, &transaction)?;
+
+    Ok(transaction)
+}
+
+// settled money into the account from anywhere but the customer's own accounts, for the month day
+// falls in. what fee waivers are judged on
+pub fn month_deposits(
+    conn: &mut PgConnection,
+    account: &Account,
+    day: chrono::NaiveDate,
+) -> QueryResult<i64> {
+    let own_accounts: Vec<(String, String)> = accounts::table
+        .filter(accounts::customer_id.eq(account.customer_id))
+        .select((accounts::account_number, accounts::bsb))
+        .load(conn)?;
+
+    let deposits: Vec<(String, String, i64)> = transactions::table
+        .filter(transactions::customer_id.eq(account.customer_id))
+        .filter(transactions::from_us.eq(false))
+        .filter(transactions::reversal_of.is_null())
+        .filter(transactions::to_number.eq(&account.account_number))
+        .filter(transactions::to_bsb.eq(&account.bsb))
+        .filter(transactions::transaction_status.eq_any([
+            TransactionStatus::Success,
+            TransactionStatus::PartiallyReversed,
+        ]))
+        .filter(transactions::date_start.ge(month_start(day).and_hms_opt(0, 0, 0).unwrap()))
+        .filter(transactions::date_start.lt(next_month_start(day).and_hms_opt(0, 0, 0).unwrap()))
+        .select((
+            transactions::from_number,
+            transactions::from_bsb,
+            transactions::amount_cents - transactions::reversed_amount_cents,
+        ))
+        .load(conn)?;
+
+    Ok(deposits
+        .into_iter()
+        .filter(|(number, bsb, _)| {
+            !own_accounts
+                .iter()
+                .any(|own| own.0 == *number && own.1 == *bsb)
+        })
+        .map(|(_, _, amount_cents)| amount_cents)
+        .sum())
+}
+
+// what a transfer of this type out of the account attracts today, waived ones included
+pub fn transfer_fees(
+    conn: &mut PgConnection,
+    account: &Account,
+    transaction_type: TransactionType,
+) -> QueryResult<Vec<AppliedFee>> {
+    let Some(kind) = FeeKind::for_transfer(transaction_type) else {
+        return Ok(vec![]);
+    };
+
+    let entry = fee_schedule::table
+        .filter(fee_schedule::kind.eq(kind))
+        .filter(fee_schedule::account_type.eq(account.account_type))
+        .select(FeeScheduleEntry::as_select())
+        .get_result(conn)
+        .optional()?;
+
+    match entry {
+        Some(entry) if entry.amount_cents > 0 => {
+            let today = chrono::Utc::now().date_naive();
+            let deposits_cents = month_deposits(conn, account, today)?;
+            Ok(vec![entry.fee(deposits_cents)])
+        }
+        _ => Ok(vec![]),
+    }
+}
+
+// charged once the transfer has actually been made, held ones wait for approval
+pub fn charge_transfer_fees(
+    conn: &mut PgConnection,
+    transfer: &Transaction,
+) -> Result<(), RepoError> {
+    if !transfer.from_us || FeeKind::for_transfer(transfer.transaction_type).is_none() {
+        return Ok(());
+    }
+
+    let account = accounts::table
+        .filter(accounts::customer_id.eq(transfer.customer_id))
+        .filter(accounts::account_number.eq(&transfer.from_number))
+        .filter(accounts::bsb.eq(&transfer.from_bsb))
+        .select(Account::as_select())
+        .get_result(conn)?;
+
+    for fee in transfer_fees(conn, &account, transfer.transaction_type)? {
+        if fee.amount_cents == 0 {
+            continue;
+        }
+
+        let charged = post_bank_charge(conn, &account, fee.amount_cents, fee.kind.description())?;
+
+        diesel::insert_into(fee_charges::table)
+            .values(&NewFeeCharge {
+                account_id: account.id,
+                customer_id: account.customer_id,
+                kind: fee.kind,
+                period_start: None,
+                trigger_transaction_id: Some(transfer.id),
+                amount_cents: fee.amount_cents,
+                transaction_id: charged.id,
+            })
+            .execute(conn)?;
+    }
+
+    Ok(())
+}
+
+// a transfer from a both to sign account that's still short of signatures
+pub fn needs_signature(conn: &mut PgConnection, transfer: &Transaction) -> QueryResult<bool> {
+    let signing_rule = accounts::table
+        .filter(accounts::customer_id.eq(transfer.customer_id))
+        .filter(accounts::account_number.eq(&transfer.from_number))
+        .filter(accounts::bsb.eq(&transfer.from_bsb))
+        .select(accounts::signing_rule)
+        .get_result::<SigningRule>(conn)
+        .optional()?;
+
+    if signing_rule != Some(SigningRule::BothToSign) {
+        return Ok(false);
+    }
+
+    let signatures = transfer_signatures::table
+        .filter(transfer_signatures::transaction_id.eq(transfer.id))
+        .count()
+        .get_result::<i64>(conn)?;
+
+    Ok(signatures < REQUIRED_SIGNATURES)
+}
+
+// a pending transfer that was waiting on something and has been let go. internal ones settle
+// straight away and fees are charged, same as if it had never waited
+pub fn release_transfer(
+    conn: &mut PgConnection,
+    transfer: &Transaction,
+) -> Result<Transaction, RepoError> {
+    let released = if transfer.transaction_type == TransactionType::Internal {
+        settle_internal_transfer(conn, transfer)?
+    } else {
+        transfer.clone()
+    };
+
+    charge_transfer_fees(conn, &released)?;
+
+    Ok(released)
+}
+
+// a pending transfer that's through whatever it was waiting on moves on to the next thing it has
+// to wait for, a second holder's signature then a second holder's approval, or goes
+pub fn advance_transfer(
+    conn: &mut PgConnection,
+    transfer: &Transaction,
+) -> Result<Transaction, RepoError> {
+    if needs_signature(conn, transfer)? {
+        return set_transfer_status(conn, transfer.id, TransactionStatus::AwaitingSignature)
+            .map_err(RepoError::from);
+    }
+
+    if let Some((account_id, policy)) = approval_policy_for(conn, transfer)? {
+        request_approval(conn, transfer, account_id, &policy)?;
+        return set_transfer_status(conn, transfer.id, TransactionStatus::PendingApproval)
+            .map_err(RepoError::from);
+    }
+
+    release_transfer(conn, transfer)
+}
+
+// uses up a verified challenge, NotFound unless it's verified, unexpired, unused and for exactly
+// this action. a transfer's has to be for the same destination and amount as well
+pub fn redeem_step_up(
+    conn: &mut PgConnection,
+    challenge_id: i32,
+    redemption: &StepUpRedemption,
+) -> Result<StepUpChallenge, RepoError> {
+    let now = chrono::Utc::now().naive_utc();
+
+    diesel::update(
+        step_up_challenges::table
+            .filter(step_up_challenges::id.eq(challenge_id))
+            .filter(step_up_challenges::customer_id.eq(redemption.customer_id))
+            .filter(step_up_challenges::step_up_action.eq(redemption.step_up_action))
+            .filter(step_up_challenges::entity_id.eq(redemption.entity_id))
+            .filter(step_up_challenges::to_number.is_not_distinct_from(&redemption.to_number))
+            .filter(step_up_challenges::to_bsb.is_not_distinct_from(&redemption.to_bsb))
+            .filter(step_up_challenges::amount_cents.is_not_distinct_from(redemption.amount_cents))
+            .filter(step_up_challenges::verified_at.is_not_null())
+            .filter(step_up_challenges::redeemed_at.is_null())
+            .filter(step_up_challenges::expires_at.gt(now)),
+    )
+    .set(step_up_challenges::redeemed_at.eq(Some(now)))
+    .returning(StepUpChallenge::as_returning())
+    .get_result(conn)
+    .map_err(RepoError::from)
+}
+
+// a transfer that's been through the checks, with the signature of the holder (or the rule's owner)
+// who made it. whatever the account needs before it goes, another signature or an approval, it
+// waits for
+pub fn create_signed_transfer(
+    conn: &mut PgConnection,
+    signed: &NewSignedTransfer,
+) -> Result<Transaction, RepoError> {
+    if let Some(proof) = &signed.step_up {
+        redeem_step_up(conn, proof.challenge_id, &proof.redemption)?;
+    }
+
+    let transaction = diesel::insert_into(transactions::table)
+        .values(&signed.transaction)
+        .returning(Transaction::as_returning())
+        .get_result(conn)?;
+
+    record_new_transaction(conn, &transaction)?;
+
+    diesel::insert_into(transfer_signatures::table)
+        .values(&NewTransferSignature {
+            transaction_id: transaction.id,
+            customer_id: signed.signed_by,
+        })
+        .execute(conn)?;
+
+    advance_transfer(conn, &transaction)
+}
+
+// screening held it, queued for staff review. no money moves until it's approved
+pub fn create_held_transfer(
+    conn: &mut PgConnection,
+    held: &NewHeldTransaction,
+) -> Result<Transaction, RepoError> {
+    if let Some(proof) = &held.step_up {
+        redeem_step_up(conn, proof.challenge_id, &proof.redemption)?;
+    }
+
+    let reasons = serde_json::to_string(&held.reasons).map_err(|_| RepoError::Other)?;
+
+    let mut new_transaction = held.transaction.clone();
+    new_transaction.transaction_status = TransactionStatus::Held;
+
+    let transaction = diesel::insert_into(transactions::table)
+        .values(&new_transaction)
+        .returning(Transaction::as_returning())
+        .get_result(conn)?;
+
+    record_new_transaction(conn, &transaction)?;
+
+    diesel::insert_into(fraud_reviews::table)
+        .values(&NewFraudReview {
+            transaction_id: transaction.id,
+            customer_id: transaction.customer_id,
+            reasons,
+        })
+        .execute(conn)?;
+
+    diesel::insert_into(transfer_signatures::table)
+        .values(&NewTransferSignature {
+            transaction_id: transaction.id,
+            customer_id: held.signed_by,
+        })
+        .execute(conn)?;
+
+    Ok(transaction)
+}
+
+pub fn set_transfer_status(
+    conn: &mut PgConnection,
+    transaction_id: i32,
+    transaction_status: TransactionStatus,
+) -> QueryResult<Transaction> {
+    diesel::update(transactions::table.filter(transactions::id.eq(transaction_id)))
+        .set(transactions::transaction_status.eq(transaction_status))
+        .returning(Transaction::as_returning())
+        .get_result(conn)
+}
+
+// the from account's policy, if the transfer is over its threshold and hasn't already been put up
+// for approval
+pub fn approval_policy_for(
+    conn: &mut PgConnection,
+    transfer: &Transaction,
+) -> QueryResult<Option<(i32, ApprovalPolicy)>> {
+    let policy = accounts::table
+        .inner_join(approval_policies::table)
+        .filter(accounts::customer_id.eq(transfer.customer_id))
+        .filter(accounts::account_number.eq(&transfer.from_number))
+        .filter(accounts::bsb.eq(&transfer.from_bsb))
+        .select((accounts::id, ApprovalPolicy::as_select()))
+        .get_result::<(i32, ApprovalPolicy)>(conn)
+        .optional()?;
+
+    let Some((account_id, policy)) = policy else {
+        return Ok(None);
+    };
+
+    if !policy.applies_to(transfer.amount_cents) {
+        return Ok(None);
+    }
+
+    let requested = transfer_approvals::table
+        .filter(transfer_approvals::transaction_id.eq(transfer.id))
+        .count()
+        .get_result::<i64>(conn)?;
+
+    Ok((requested == 0).then_some((account_id, policy)))
+}
+
+// the first signature is whoever made the transfer. anything from before transfers carried one
+// falls back to the primary holder
+fn request_approval(
+    conn: &mut PgConnection,
+    transfer: &Transaction,
+    account_id: i32,
+    policy: &ApprovalPolicy,
+) -> QueryResult<TransferApproval> {
+    let requested_by = transfer_signatures::table
+        .filter(transfer_signatures::transaction_id.eq(transfer.id))
+        .order(transfer_signatures::id.asc())
+        .select(transfer_signatures::customer_id)
+        .first::<i32>(conn)
+        .optional()?
+        .unwrap_or(transfer.customer_id);
+
+    let now = chrono::Utc::now().naive_utc();
+    let approval = diesel::insert_into(transfer_approvals::table)
+        .values(&NewTransferApproval {
+            transaction_id: transfer.id,
+            account_id,
+            requested_by,
+            threshold_cents: policy.threshold_cents,
+            expires_at: now + chrono::Duration::hours(policy.expiry_hours as i64),
+        })
+        .returning(TransferApproval::as_returning())
+        .get_result(conn)?;
+
+    diesel::insert_into(transfer_approval_events::table)
+        .values(&NewTransferApprovalEvent {
+            approval_id: approval.id,
+            approval_status: ApprovalStatus::Pending,
+            note: None,
+            actor_type: ActorType::Customer,
+            actor_id: requested_by,
+        })
+        .execute(conn)?;
+
+    Ok(approval)
+}
+
+// moves the money for an internal transfer and writes the receiving customer's entry for it,
+// returns the sender's entry settled with its actual running balance
+pub fn settle_internal_transfer(
+    conn: &mut PgConnection,
+    sent: &Transaction,
+) -> Result<Transaction, RepoError> {
+    let from = (sent.from_bsb.as_str(), sent.from_number.as_str());
+    let to = (sent.to_bsb.as_str(), sent.to_number.as_str());
+    if from == to {
+        return Err(RepoError::Conflict);
+    }
+
+    // always touch the two accounts in the same order so opposite transfers can't deadlock
+    let (sender, receiver) = if from < to {
+        let sender = adjust_account_balance(conn, from.1, from.0, -sent.amount_cents)?;
+        let receiver = adjust_account_balance(conn, to.1, to.0, sent.amount_cents)?;
+        (sender, receiver)
+    } else {
+        let receiver = adjust_account_balance(conn, to.1, to.0, sent.amount_cents)?;
+        let sender = adjust_account_balance(conn, from.1, from.0, -sent.amount_cents)?;
+        (sender, receiver)
+    };
+
+    let sender = sender.ok_or(RepoError::NotFound)?;
+    let receiver = receiver.ok_or(RepoError::NotFound)?;
+
+    // a loan account only ever sends when the bank draws it down, customers can't transfer from one
+    if sender.spendable_cents() < 0 && sender.account_type != AccountType::Loan {
+        return Err(RepoError::Conflict);
+    }
+
+    // a loan can be paid off but not paid into credit
+    if receiver.account_type == AccountType::Loan {
+        if receiver.balance_cents > 0 {
+            return Err(RepoError::Conflict);
+        }
+        apply_loan_repayment(
+            conn,
+            &receiver,
+            sent.amount_cents,
+            chrono::Utc::now().date_naive(),
+        )?;
+    }
+
+    let now = chrono::Utc::now().naive_utc();
+
+    let settled = diesel::update(transactions::table.filter(transactions::id.eq(sent.id)))
+        .set((
+            transactions::transaction_status.eq(TransactionStatus::Success),
+            transactions::date_end.eq(Some(now)),
+            transactions::available_balance_cents.eq(sender.available_balance_cents),
+            transactions::running_balance_cents.eq(Some(sender.balance_cents)),
+        ))
+        .returning(Transaction::as_returning())
+        .get_result(conn)?;
+
+    record_event(
+        conn,
+        EventType::TransactionSettled,
+        transaction_data(&settled),
+    )?;
+
+    // the sender's description is their own note, only the payee reference goes across
+    let received = NewTransaction {
+        customer_id: receiver.customer_id,
+        transaction_type: TransactionType::Internal,
+        from_us: false,
+        amount_cents: sent.amount_cents,
+        from_number: sent.from_number.clone(),
+        from_bsb: sent.from_bsb.clone(),
+        from_name: sent.from_name.clone(),
+        to_number: sent.to_number.clone(),
+        to_bsb: sent.to_bsb.clone(),
+        to_name: receiver.account_name,
+        available_balance_cents: receiver.available_balance_cents,
+        transaction_status: TransactionStatus::Success,
+        reversal_of: None,
+        reversal_reason: None,
+        description: None,
+        payer_reference: None,
+        payee_reference: sent.payee_reference.clone(),
+    };
+
+    let received = diesel::insert_into(transactions::table)
+        .values((
+            &received,
+            transactions::date_end.eq(Some(now)),
+            transactions::counterpart_of.eq(Some(settled.id)),
+            transactions::running_balance_cents.eq(Some(receiver.balance_cents)),
+        ))
+        .returning(Transaction::as_returning())
+        .get_result(conn)?;
+
+    record_new_transaction(conn, &received)?;
+
+    Ok(settled)
+}
+
+// money into a loan pays off whatever's fallen due, oldest first. anything more is an early
+// repayment, it comes off the principal and what's left of the schedule is worked out again
+pub fn apply_loan_repayment(
+    conn: &mut PgConnection,
+    loan_account: &Account,
+    amount_cents: i64,
+    today: NaiveDate,
+) -> QueryResult<()> {
+    // loans opened already owing don't have a schedule to keep
+    let Some(loan) = loans::table
+        .filter(loans::account_id.eq(loan_account.id))
+        .filter(loans::term_months.is_not_null())
+        .select(Loan::as_select())
+        .get_result(conn)
+        .optional()?
+    else {
+        return Ok(());
+    };
+
+    let due = loan_schedule_entries::table
+        .filter(loan_schedule_entries::loan_id.eq(loan.id))
+        .filter(loan_schedule_entries::due_date.le(today))
+        .filter(loan_schedule_entries::paid_cents.lt(loan_schedule_entries::amount_cents))
+        .order(loan_schedule_entries::installment.asc())
+        .for_update()
+        .select(LoanScheduleEntry::as_select())
+        .load(conn)?;
+
+    let mut left_cents = amount_cents;
+    for entry in due.iter() {
+        if left_cents == 0 {
+            break;
+        }
+        let paid_cents = left_cents.min(entry.outstanding_cents());
+        diesel::update(loan_schedule_entries::table.filter(loan_schedule_entries::id.eq(entry.id)))
+            .set(
+                loan_schedule_entries::paid_cents
+                    .eq(loan_schedule_entries::paid_cents + paid_cents),
+            )
+            .execute(conn)?;
+        left_cents -= paid_cents;
+    }
+
+    if left_cents > 0 || loan_account.overdrawn_cents() == 0 {
+        reschedule_loan(conn, &loan, loan_account.overdrawn_cents(), today)?;
+    }
+
+    Ok(())
+}
+
+// rewrites the installments not yet due so they pay off owed_cents by the end of the term. a loan
+// that's paid off has nothing left to pay at all
+pub fn reschedule_loan(
+    conn: &mut PgConnection,
+    loan: &Loan,
+    owed_cents: i64,
+    today: NaiveDate,
+) -> QueryResult<()> {
+    let (Some(term_months), Some(repayment_type), Some(first_repayment_date)) = (
+        loan.term_months,
+        loan.repayment_type,
+        loan.first_repayment_date,
+    ) else {
+        return Ok(());
+    };
+
+    if owed_cents == 0 {
+        diesel::delete(
+            loan_schedule_entries::table
+                .filter(loan_schedule_entries::loan_id.eq(loan.id))
+                .filter(loan_schedule_entries::paid_cents.lt(loan_schedule_entries::amount_cents)),
+        )
+        .execute(conn)?;
+        return Ok(());
+    }
+
+    let Some(from_installment) = diesel::delete(
+        loan_schedule_entries::table
+            .filter(loan_schedule_entries::loan_id.eq(loan.id))
+            .filter(loan_schedule_entries::due_date.gt(today)),
+    )
+    .returning(loan_schedule_entries::installment)
+    .get_results::<i32>(conn)?
+    .into_iter()
+    .min() else {
+        return Ok(());
+    };
+
+    write_loan_schedule(
+        conn,
+        loan,
+        amortise(
+            owed_cents,
+            loan.interest_rate_bps,
+            repayment_type,
+            first_repayment_date,
+            from_installment,
+            term_months,
+        ),
+    )
+}
+
+pub fn write_loan_schedule(
+    conn: &mut PgConnection,
+    loan: &Loan,
+    repayments: Vec<ScheduledRepayment>,
+) -> QueryResult<()> {
+    let entries: Vec<NewLoanScheduleEntry> = repayments
+        .into_iter()
+        .map(|repayment| NewLoanScheduleEntry::new(loan, repayment))
+        .collect();
+
+    diesel::insert_into(loan_schedule_entries::table)
+        .values(&entries)
+        .execute(conn)?;
+
+    Ok(())
+}
+
+// takes interest or a fee out of the account. only the customer's side is written, the bank's
+// ledger isn't an account here. charges aren't held to the overdraft limit
+pub fn post_bank_charge(
+    conn: &mut PgConnection,
+    account: &Account,
+    amount_cents: i64,
+    description: &str,
+) -> Result<Transaction, RepoError> {
+    let charged =
+        adjust_account_balance(conn, &account.account_number, &account.bsb, -amount_cents)?
+            .ok_or(RepoError::NotFound)?;
+
+    let charge = diesel::insert_into(transactions::table)
+        .values((
+            &NewTransaction::bank_charge(&charged, amount_cents, description),
+            transactions::date_end.eq(Some(chrono::Utc::now().naive_utc())),
+            transactions::running_balance_cents.eq(Some(charged.balance_cents)),
+        ))
+        .returning(Transaction::as_returning())
+        .get_result(conn)?;
+
+    record_new_transaction(conn, &charge)?;
+
+    Ok(charge)
+}
+
+// one charge in its own db transaction, so an account that can't be charged doesn't stop the
+// others. None if it's already been charged for that day
+pub fn charge_overdraft(
+    conn: &mut PgConnection,
+    account_id: i32,
+    kind: OverdraftChargeKind,
+    charge_date: NaiveDate,
+    amount_cents: i64,
+) -> Result<Option<OverdraftCharge>, RepoError> {
+    conn.transaction(|conn| {
+        let already_charged = overdraft_charges::table
+            .filter(overdraft_charges::account_id.eq(account_id))
+            .filter(overdraft_charges::kind.eq(kind))
+            .filter(overdraft_charges::charge_date.eq(charge_date))
+            .count()
+            .get_result::<i64>(conn)?
+            > 0;
+        if already_charged {
+            return Ok(None);
+        }
+
+        let account = accounts::table
+            .filter(accounts::id.eq(account_id))
+            .select(Account::as_select())
+            .get_result(conn)?;
+
+        let transaction = post_bank_charge(conn, &account, amount_cents, kind.description())?;
+
+        let charge = diesel::insert_into(overdraft_charges::table)
+            .values(&NewOverdraftCharge {
+                account_id,
+                customer_id: account.customer_id,
+                kind,
+                charge_date,
+                amount_cents,
+                transaction_id: transaction.id,
+            })
+            .returning(OverdraftCharge::as_returning())
+            .get_result(conn)?;
+
+        Ok(Some(charge))
+    })
+}
+
+// every customer has limits even if it's just the defaults
+pub fn transfer_limits(conn: &mut PgConnection, customer_id: i32) -> QueryResult<TransferLimits> {
+    let overrides = customer_transfer_limits::table
+        .filter(customer_transfer_limits::customer_id.eq(customer_id))
+        .select(CustomerTransferLimits::as_select())
+        .get_result(conn)
+        .optional()?;
+
+    Ok(TransferLimits::effective(customer_id, overrides))
+}
+
+// outgoing transfers since the given time. held ones count, the money is spoken for. bank charges
+// don't, the customer didn't send them
+pub fn transfer_usage(
+    conn: &mut PgConnection,
+    customer_id: i32,
+    since: NaiveDateTime,
+) -> QueryResult<Vec<TransferUsage>> {
+    transactions::table
+        .filter(transactions::customer_id.eq(customer_id))
+        .filter(transactions::from_us.eq(true))
+        .filter(transactions::transaction_type.ne(TransactionType::Fee))
+        .filter(transactions::reversal_of.is_null())
+        .filter(transactions::date_start.ge(since))
+        .filter(transactions::transaction_status.eq_any([
+            TransactionStatus::Pending,
+            TransactionStatus::Success,
+            TransactionStatus::PartiallyReversed,
+            TransactionStatus::Held,
+        ]))
+        .select((
+            transactions::transaction_type,
+            transactions::to_number,
+            transactions::to_bsb,
+            transactions::amount_cents - transactions::reversed_amount_cents,
+        ))
+        .load(conn)
+}
+
+// row locks on every account whose transfers count against the customer's limits, and on the
+// account being paid if it's ours, taken in the same order settling takes them so they can't
+// deadlock with it. nothing else can add to the customer's usage until the db transaction ends
+pub fn lock_transfer_accounts(
+    conn: &mut PgConnection,
+    customer_id: i32,
+    to_number: &str,
+    to_bsb: &str,
+) -> QueryResult<()> {
+    accounts::table
+        .filter(
+            accounts::customer_id
+                .eq(customer_id)
+                .or(accounts::account_number
+                    .eq(to_number)
+                    .and(accounts::bsb.eq(to_bsb))),
+        )
+        .order((accounts::bsb.asc(), accounts::account_number.asc()))
+        .for_update()
+        .select(accounts::id)
+        .load::<i32>(conn)?;
+
+    Ok(())
+}
+
+// the limits again, with the customer's accounts locked. the handler checks them before the db
+// transaction starts, so two transfers made at once could both have got through that
+pub fn recheck_transfer_limits(
+    conn: &mut PgConnection,
+    transaction: &NewTransaction,
+) -> Result<(), RepoError> {
+    lock_transfer_accounts(
+        conn,
+        transaction.customer_id,
+        &transaction.to_number,
+        &transaction.to_bsb,
+    )?;
+
+    let now = chrono::Utc::now().naive_utc();
+    let limits = transfer_limits(conn, transaction.customer_id)?;
+    let usage = transfer_usage(conn, transaction.customer_id, start_of_day(now))?;
+
+    limits.check(&usage, transaction).map_err(|limit_kind| {
+        println!("transfer would go over the {:?} limit", limit_kind);
+        RepoError::LimitExceeded
+    })
+}
+
+// what's left the account since the given time, for screening to look back over
+pub fn outgoing_since(
+    conn: &mut PgConnection,
+    account: &Account,
+    since: NaiveDateTime,
+) -> QueryResult<Vec<Transaction>> {
+    transactions::table
+        .filter(transactions::from_us)
+        .filter(transactions::from_number.eq(&account.account_number))
+        .filter(transactions::from_bsb.eq(&account.bsb))
+        .filter(transactions::date_start.ge(since))
+        .select(Transaction::as_select())
+        .load(conn)
+}
+
+// overrides one of the customer's limits, creating their overrides row if they don't have one
+pub fn set_customer_limit(
+    conn: &mut PgConnection,
+    customer_id: i32,
+    kind: TransferLimitKind,
+    amount_cents: i64,
+) -> QueryResult<CustomerTransferLimits> {
+    let now = chrono::Utc::now().naive_utc();
+
+    let mut limits = customer_transfer_limits::table
+        .filter(customer_transfer_limits::customer_id.eq(customer_id))
+        .for_update()
+        .select(CustomerTransferLimits::as_select())
+        .get_result(conn)
+        .optional()?
+        .unwrap_or(CustomerTransferLimits::none(customer_id, now));
+
+    limits.set(kind, amount_cents);
+    limits.updated_at = now;
+
+    diesel::insert_into(customer_transfer_limits::table)
+        .values(&limits)
+        .on_conflict(customer_transfer_limits::customer_id)
+        .do_update()
+        .set(&limits)
+        .returning(CustomerTransferLimits::as_returning())
+        .get_result(conn)
+}
+
+// settled money out of the customer's accounts, not counting moves between their own accounts
+pub fn find_spending(
+    conn: &mut PgConnection,
+    spending_query: &FindSpendingQuery,
+) -> QueryResult<Vec<Transaction>> {
+    let own_accounts: Vec<(String, String)> = accounts::table
+        .filter(accounts::customer_id.eq(spending_query.customer_id))
+        .select((accounts::account_number, accounts::bsb))
+        .load(conn)?;
+
+    let mut query = transactions::table
+        .filter(transactions::customer_id.eq(spending_query.customer_id))
+        .filter(transactions::from_us)
+        .filter(transactions::reversal_of.is_null())
+        .filter(transactions::transaction_status.eq_any([
+            TransactionStatus::Success,
+            TransactionStatus::PartiallyReversed,
+        ]))
+        .filter(transactions::date_start.ge(spending_query.from))
+        .filter(transactions::date_start.lt(spending_query.to))
+        .into_boxed();
+
+    if let Some(account_number) = &spending_query.account_number {
+        query = query.filter(transactions::from_number.eq(account_number.clone()));
+    }
+
+    let spending = query
+        .order(transactions::date_start.desc())
+        .select(Transaction::as_select())
+        .load(conn)?;
+
+    Ok(spending
+        .into_iter()
+        .filter(|tr| {
+            !own_accounts
+                .iter()
+                .any(|(number, bsb)| tr.to_number == *number && tr.to_bsb == *bsb)
+        })
+        .collect())
+}
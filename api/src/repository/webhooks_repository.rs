@@ -0,0 +1,374 @@
+use diesel::{
+    prelude::*,
+    r2d2::{ConnectionManager, Pool},
+};
+
+use crate::{
+    error::RepoError,
+    models::{
+        outbox::{EventType, OutboxEvent},
+        schema::{
+            webhook_deliveries, webhook_delivery_attempts, webhook_events, webhook_subscriptions,
+        },
+        webhook::{
+            retry_delay, DueWebhookDelivery, FindDueWebhookDeliveryQuery, FindWebhookDeliveryQuery,
+            FindWebhookSubscriptionQuery, NewWebhookDelivery, NewWebhookSubscription,
+            WebhookAttempt, WebhookDelivery, WebhookDeliveryAttempt, WebhookDeliveryLog,
+            WebhookDeliveryStatus, WebhookEvent, WebhookReplay, WebhookSubscription,
+            WebhookSubscriptionRevocation, DELIVERY_LEASE_SECONDS,
+        },
+    },
+    traits::{RepoCreate, RepoFind, RepoGetById, RepoUpdate},
+};
+
+// staff only ever look at the latest ones
+const MAX_DELIVERY_LOG: i64 = 50;
+
+#[derive(Clone)]
+pub struct WebhooksRepoImpl {
+    pub pool: Pool<ConnectionManager<PgConnection>>,
+}
+
+impl WebhooksRepoImpl {
+    pub fn new(pool: Pool<ConnectionManager<PgConnection>>) -> WebhooksRepoImpl {
+        WebhooksRepoImpl { pool }
+    }
+}
+
+fn lock_delivery(conn: &mut PgConnection, id: i32) -> Result<WebhookDelivery, RepoError> {
+    webhook_deliveries::table
+        .filter(webhook_deliveries::id.eq(id))
+        .for_update()
+        .select(WebhookDelivery::as_select())
+        .get_result(conn)
+        .map_err(RepoError::from)
+}
+
+impl RepoCreate<WebhookSubscription, NewWebhookSubscription> for WebhooksRepoImpl {
+    fn create(
+        &self,
+        new_subscription: NewWebhookSubscription,
+    ) -> Result<WebhookSubscription, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        diesel::insert_into(webhook_subscriptions::table)
+            .values(&new_subscription)
+            .returning(WebhookSubscription::as_returning())
+            .get_result(&mut conn)
+            .map_err(RepoError::from)
+    }
+}
+
+impl RepoFind<WebhookSubscription, FindWebhookSubscriptionQuery> for WebhooksRepoImpl {
+    fn find(
+        &self,
+        query: FindWebhookSubscriptionQuery,
+    ) -> Result<Vec<WebhookSubscription>, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        let mut select = webhook_subscriptions::table
+            .select(WebhookSubscription::as_select())
+            .order(webhook_subscriptions::id.asc())
+            .into_boxed();
+
+        if !query.include_revoked {
+            select = select.filter(webhook_subscriptions::revoked_at.is_null());
+        }
+
+        select.load(&mut conn).map_err(RepoError::from)
+    }
+}
+
+impl RepoGetById<WebhookSubscription> for WebhooksRepoImpl {
+    fn get_by_id(&self, id: i32) -> Result<WebhookSubscription, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        webhook_subscriptions::table
+            .filter(webhook_subscriptions::id.eq(id))
+            .select(WebhookSubscription::as_select())
+            .get_result(&mut conn)
+            .map_err(RepoError::from)
+    }
+}
+
+impl RepoUpdate<WebhookSubscription, WebhookSubscriptionRevocation> for WebhooksRepoImpl {
+    fn update(
+        &self,
+        id: i32,
+        _: WebhookSubscriptionRevocation,
+    ) -> Result<WebhookSubscription, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        conn.transaction(|conn| {
+            let subscription = webhook_subscriptions::table
+                .filter(webhook_subscriptions::id.eq(id))
+                .for_update()
+                .select(WebhookSubscription::as_select())
+                .get_result(conn)?;
+            if !subscription.is_active() {
+                return Err(RepoError::Conflict);
+            }
+
+            diesel::update(
+                webhook_deliveries::table
+                    .filter(webhook_deliveries::subscription_id.eq(id))
+                    .filter(webhook_deliveries::delivery_status.eq(WebhookDeliveryStatus::Pending)),
+            )
+            .set((
+                webhook_deliveries::delivery_status.eq(WebhookDeliveryStatus::Failed),
+                webhook_deliveries::next_attempt_at.eq(None::<chrono::NaiveDateTime>),
+            ))
+            .execute(conn)?;
+
+            diesel::update(webhook_subscriptions::table.filter(webhook_subscriptions::id.eq(id)))
+                .set(webhook_subscriptions::revoked_at.eq(Some(chrono::Utc::now().naive_utc())))
+                .returning(WebhookSubscription::as_returning())
+                .get_result(conn)
+                .map_err(RepoError::from)
+        })
+    }
+}
+
+// the event and its deliveries go in together, or not at all if nobody's listening. the relay
+// hands the same event over again if publishing it fails further on, it's only fanned out once
+impl RepoCreate<Vec<WebhookDelivery>, OutboxEvent> for WebhooksRepoImpl {
+    fn create(&self, outbox_event: OutboxEvent) -> Result<Vec<WebhookDelivery>, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        // nothing subscribes to anything that isn't known
+        let Some(event_type) = EventType::parse(&outbox_event.event_type) else {
+            return Ok(vec![]);
+        };
+
+        conn.transaction(|conn| {
+            let subscriptions: Vec<WebhookSubscription> = webhook_subscriptions::table
+                .filter(webhook_subscriptions::revoked_at.is_null())
+                .select(WebhookSubscription::as_select())
+                .load::<WebhookSubscription>(conn)?
+                .into_iter()
+                .filter(|subscription| subscription.subscribes_to(event_type))
+                .collect();
+            if subscriptions.is_empty() {
+                return Ok(vec![]);
+            }
+
+            let event = WebhookEvent::from(&outbox_event);
+            let inserted = diesel::insert_into(webhook_events::table)
+                .values(&event)
+                .on_conflict_do_nothing()
+                .execute(conn)?;
+            if inserted == 0 {
+                return Ok(vec![]);
+            }
+
+            let now = chrono::Utc::now().naive_utc();
+            let deliveries: Vec<NewWebhookDelivery> = subscriptions
+                .iter()
+                .map(|subscription| NewWebhookDelivery {
+                    subscription_id: subscription.id,
+                    event_id: event.id.clone(),
+                    next_attempt_at: Some(now),
+                })
+                .collect();
+
+            diesel::insert_into(webhook_deliveries::table)
+                .values(deliveries)
+                .returning(WebhookDelivery::as_returning())
+                .get_results(conn)
+                .map_err(RepoError::from)
+        })
+    }
+}
+
+// leases what it returns so a second worker, or this one on its next poll, leaves them alone
+impl RepoFind<DueWebhookDelivery, FindDueWebhookDeliveryQuery> for WebhooksRepoImpl {
+    fn find(
+        &self,
+        query: FindDueWebhookDeliveryQuery,
+    ) -> Result<Vec<DueWebhookDelivery>, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        conn.transaction(|conn| {
+            let due: Vec<(i32, String, String, WebhookEvent)> = webhook_deliveries::table
+                .inner_join(webhook_subscriptions::table)
+                .inner_join(webhook_events::table)
+                .filter(webhook_deliveries::delivery_status.eq(WebhookDeliveryStatus::Pending))
+                .filter(webhook_deliveries::next_attempt_at.le(query.now))
+                .filter(webhook_subscriptions::revoked_at.is_null())
+                .order(webhook_deliveries::next_attempt_at.asc())
+                .limit(query.limit)
+                .for_update()
+                .skip_locked()
+                .select((
+                    webhook_deliveries::id,
+                    webhook_subscriptions::url,
+                    webhook_subscriptions::secret,
+                    WebhookEvent::as_select(),
+                ))
+                .load(conn)?;
+
+            let ids: Vec<i32> = due.iter().map(|(id, _, _, _)| *id).collect();
+            diesel::update(webhook_deliveries::table.filter(webhook_deliveries::id.eq_any(ids)))
+                .set(
+                    webhook_deliveries::next_attempt_at
+                        .eq(query.now + chrono::Duration::seconds(DELIVERY_LEASE_SECONDS)),
+                )
+                .execute(conn)?;
+
+            Ok(due
+                .into_iter()
+                .map(|(delivery_id, url, secret, event)| DueWebhookDelivery {
+                    delivery_id,
+                    url,
+                    secret,
+                    event,
+                })
+                .collect())
+        })
+    }
+}
+
+// logs the attempt, then it's delivered, due again later or given up on
+impl RepoUpdate<WebhookDelivery, WebhookAttempt> for WebhooksRepoImpl {
+    fn update(&self, id: i32, attempt: WebhookAttempt) -> Result<WebhookDelivery, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        conn.transaction(|conn| {
+            let delivery = lock_delivery(conn, id)?;
+
+            diesel::insert_into(webhook_delivery_attempts::table)
+                .values((webhook_delivery_attempts::delivery_id.eq(id), &attempt))
+                .execute(conn)?;
+
+            let attempts = delivery.attempts + 1;
+            // revoked or replayed while it was out, the attempt's logged but that's all
+            if delivery.delivery_status != WebhookDeliveryStatus::Pending {
+                return Ok(delivery);
+            }
+
+            let (delivery_status, next_attempt_at, delivered_at) = if attempt.succeeded() {
+                (
+                    WebhookDeliveryStatus::Delivered,
+                    None,
+                    Some(attempt.attempted_at),
+                )
+            } else {
+                match retry_delay(attempts) {
+                    Some(delay) => (
+                        WebhookDeliveryStatus::Pending,
+                        Some(attempt.attempted_at + delay),
+                        None,
+                    ),
+                    None => (WebhookDeliveryStatus::Failed, None, None),
+                }
+            };
+
+            diesel::update(webhook_deliveries::table.filter(webhook_deliveries::id.eq(id)))
+                .set((
+                    webhook_deliveries::attempts.eq(attempts),
+                    webhook_deliveries::delivery_status.eq(delivery_status),
+                    webhook_deliveries::next_attempt_at.eq(next_attempt_at),
+                    webhook_deliveries::delivered_at.eq(delivered_at),
+                ))
+                .returning(WebhookDelivery::as_returning())
+                .get_result(conn)
+                .map_err(RepoError::from)
+        })
+    }
+}
+
+impl RepoFind<WebhookDeliveryLog, FindWebhookDeliveryQuery> for WebhooksRepoImpl {
+    fn find(&self, query: FindWebhookDeliveryQuery) -> Result<Vec<WebhookDeliveryLog>, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        let deliveries: Vec<(WebhookDelivery, String)> = webhook_deliveries::table
+            .inner_join(webhook_events::table)
+            .filter(webhook_deliveries::subscription_id.eq(query.subscription_id))
+            .order(webhook_deliveries::id.desc())
+            .limit(MAX_DELIVERY_LOG)
+            .select((WebhookDelivery::as_select(), webhook_events::event_type))
+            .load(&mut conn)?;
+
+        let delivery_ids: Vec<i32> = deliveries.iter().map(|(d, _)| d.id).collect();
+        let attempts: Vec<WebhookDeliveryAttempt> = webhook_delivery_attempts::table
+            .filter(webhook_delivery_attempts::delivery_id.eq_any(delivery_ids))
+            .order(webhook_delivery_attempts::id.asc())
+            .select(WebhookDeliveryAttempt::as_select())
+            .load(&mut conn)?;
+
+        Ok(deliveries
+            .into_iter()
+            .map(|(delivery, event_type)| WebhookDeliveryLog {
+                attempts: attempts
+                    .iter()
+                    .filter(|attempt| attempt.delivery_id == delivery.id)
+                    .cloned()
+                    .collect(),
+                delivery,
+                event_type,
+            })
+            .collect())
+    }
+}
+
+impl RepoUpdate<WebhookDelivery, WebhookReplay> for WebhooksRepoImpl {
+    fn update(&self, id: i32, replay: WebhookReplay) -> Result<WebhookDelivery, RepoError> {
+        let mut conn = self.pool.get().map_err(|_| {
+            println!("couldn't get db connection from pool");
+            RepoError::ConnectionError
+        })?;
+
+        conn.transaction(|conn| {
+            let delivery = lock_delivery(conn, id)?;
+            if delivery.subscription_id != replay.subscription_id {
+                return Err(RepoError::NotFound);
+            }
+
+            let subscription = webhook_subscriptions::table
+                .filter(webhook_subscriptions::id.eq(delivery.subscription_id))
+                .select(WebhookSubscription::as_select())
+                .get_result(conn)?;
+            // still on its way, or nowhere left to send it
+            if delivery.delivery_status == WebhookDeliveryStatus::Pending
+                || !subscription.is_active()
+            {
+                return Err(RepoError::Conflict);
+            }
+
+            diesel::update(webhook_deliveries::table.filter(webhook_deliveries::id.eq(id)))
+                .set((
+                    webhook_deliveries::delivery_status.eq(WebhookDeliveryStatus::Pending),
+                    webhook_deliveries::attempts.eq(0),
+                    webhook_deliveries::next_attempt_at.eq(Some(chrono::Utc::now().naive_utc())),
+                    webhook_deliveries::delivered_at.eq(None::<chrono::NaiveDateTime>),
+                ))
+                .returning(WebhookDelivery::as_returning())
+                .get_result(conn)
+                .map_err(RepoError::from)
+        })
+    }
+}
@@ -0,0 +1,68 @@
+// one-time codes for stepping up before high-risk actions. a customer with a confirmed
+// authenticator app uses totp, anyone else gets a code sent to them by a CodeSender
+
+pub mod totp;
+
+use rand::Rng;
+use sha2::{Digest, Sha256};
+
+use crate::error::NotifyError;
+
+#[cfg(test)]
+use mockall::automock;
+
+pub const CODE_DIGITS: usize = 6;
+
+#[cfg_attr(test, automock)]
+pub trait CodeSender: 'static + Sync + Send {
+    fn send_code(&self, customer_id: i32, code: &str) -> Result<(), NotifyError>;
+}
+
+// stand in until there's a real channel (sms, email), codes end up in the server log
+#[derive(Clone, Default)]
+pub struct LogCodeSender;
+
+impl CodeSender for LogCodeSender {
+    fn send_code(&self, customer_id: i32, code: &str) -> Result<(), NotifyError> {
+        println!(
+            "Sending verification code {} to customer {}",
+            code, customer_id
+        );
+        Ok(())
+    }
+}
+
+pub fn generate_code() -> String {
+    let code = rand::thread_rng().gen_range(0..10u32.pow(CODE_DIGITS as u32));
+    format!("{:0width$}", code, width = CODE_DIGITS)
+}
+
+// delivered codes are only kept hashed
+pub fn hash_code(code: &str) -> String {
+    format!("{:x}", Sha256::digest(code.as_bytes()))
+}
+
+pub fn is_well_formed(code: &str) -> bool {
+    code.len() == CODE_DIGITS && code.chars().all(|c| c.is_ascii_digit())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{generate_code, hash_code, is_well_formed};
+
+    #[test]
+    fn generated_codes_are_six_digits() {
+        for _ in 0..100 {
+            assert!(is_well_formed(&generate_code()));
+        }
+        assert!(!is_well_formed("12345"));
+        assert!(!is_well_formed("12345a"));
+    }
+
+    #[test]
+    fn hash_is_stable() {
+        assert_eq!(hash_code("123456"), hash_code("123456"));
+        assert_ne!(hash_code("123456"), hash_code("123457"));
+        assert_eq!(64, hash_code("123456").len());
+    }
+}
@@ -0,0 +1,118 @@
+// rfc 6238 time based codes, sha1 and 30 second steps like every authenticator app defaults to
+
+use chrono::NaiveDateTime;
+use data_encoding::BASE32_NOPAD;
+use hmac::{Hmac, Mac};
+use rand::Rng;
+use sha1::Sha1;
+
+use super::CODE_DIGITS;
+
+const STEP_SECONDS: i64 = 30;
+// steps either side of now a code is still taken from, for clocks that have drifted
+const SKEW_STEPS: i64 = 1;
+const SECRET_BYTES: usize = 20;
+const ISSUER: &str = "Lesser Bank";
+
+pub fn generate_secret() -> String {
+    let secret: [u8; SECRET_BYTES] = rand::thread_rng().gen();
+    BASE32_NOPAD.encode(&secret)
+}
+
+// what an authenticator app scans to add the account
+pub fn provisioning_uri(secret: &str, customer_id: i32) -> String {
+    let issuer = ISSUER.replace(' ', "%20");
+    format!(
+        "otpauth://totp/{}:{}?secret={}&issuer={}&digits={}&period={}",
+        issuer, customer_id, secret, issuer, CODE_DIGITS, STEP_SECONDS
+    )
+}
+
+pub fn step_at(now: NaiveDateTime) -> i64 {
+    now.timestamp() / STEP_SECONDS
+}
+
+// the step the code is for, if it's for one near enough to now and after last_used_step
+pub fn verify(
+    secret: &str,
+    code: &str,
+    now: NaiveDateTime,
+    last_used_step: Option<i64>,
+) -> Option<i64> {
+    let key = BASE32_NOPAD.decode(secret.as_bytes()).ok()?;
+    let current = step_at(now);
+
+    (current - SKEW_STEPS..=current + SKEW_STEPS)
+        .filter(|step| last_used_step.map_or(true, |used| *step > used))
+        .find(|step| code_for_step(&key, *step) == code)
+}
+
+fn code_for_step(key: &[u8], step: i64) -> String {
+    let mut mac = Hmac::<Sha1>::new_from_slice(key).expect("hmac takes any key length");
+    mac.update(&step.to_be_bytes());
+    let digest = mac.finalize().into_bytes();
+
+    // dynamic truncation, rfc 4226 5.3
+    let offset = (digest[digest.len() - 1] & 0x0f) as usize;
+    let binary = u32::from_be_bytes([
+        digest[offset] & 0x7f,
+        digest[offset + 1],
+        digest[offset + 2],
+        digest[offset + 3],
+    ]);
+
+    format!(
+        "{:0width$}",
+        binary % 10u32.pow(CODE_DIGITS as u32),
+        width = CODE_DIGITS
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDateTime;
+    use data_encoding::BASE32_NOPAD;
+
+    use super::{code_for_step, generate_secret, step_at, verify};
+
+    // rfc 6238 appendix b, sha1 key, last six of the eight digits
+    const RFC_KEY: &[u8] = b"12345678901234567890";
+
+    fn at(timestamp: i64) -> NaiveDateTime {
+        NaiveDateTime::from_timestamp_opt(timestamp, 0).unwrap()
+    }
+
+    #[test]
+    fn rfc_test_vectors() {
+        assert_eq!("287082", code_for_step(RFC_KEY, step_at(at(59))));
+        assert_eq!("081804", code_for_step(RFC_KEY, step_at(at(1111111109))));
+        assert_eq!("050471", code_for_step(RFC_KEY, step_at(at(1111111111))));
+        assert_eq!("005924", code_for_step(RFC_KEY, step_at(at(1234567890))));
+        assert_eq!("279037", code_for_step(RFC_KEY, step_at(at(2000000000))));
+    }
+
+    #[test]
+    fn verify_takes_adjacent_steps_once() {
+        let secret = BASE32_NOPAD.encode(RFC_KEY);
+        let now = at(1111111109);
+        let step = step_at(now);
+
+        assert_eq!(Some(step), verify(&secret, "081804", now, None));
+        // the app's clock a step behind
+        assert_eq!(
+            Some(step),
+            verify(&secret, "081804", at(1111111109 + 30), None)
+        );
+        assert_eq!(None, verify(&secret, "081804", at(1111111109 + 60), None));
+        // already used
+        assert_eq!(None, verify(&secret, "081804", now, Some(step)));
+        assert_eq!(None, verify(&secret, "000000", now, None));
+    }
+
+    #[test]
+    fn generated_secret_decodes() {
+        let secret = generate_secret();
+        assert_eq!(32, secret.len());
+        assert_eq!(20, BASE32_NOPAD.decode(secret.as_bytes()).unwrap().len());
+    }
+}
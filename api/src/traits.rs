@@ -28,3 +28,10 @@ pub trait RepoGetById<T: 'static + Sync + Send>: 'static + Sync + Send {
 pub trait RepoDeleteById<T: 'static + Sync + Send>: 'static + Sync + Send {
     fn delete_by_id(&self, id: i32) -> Result<(), RepoError>;
 }
+
+#[cfg_attr(test, automock)]
+pub trait RepoUpdate<T: 'static + Sync + Send, U: 'static + Sync + Send>:
+    'static + Sync + Send
+{
+    fn update(&self, id: i32, update: U) -> Result<T, RepoError>;
+}
@@ -0,0 +1,140 @@
+// the checks a transfer goes through before it's made, whether the customer is making it or one of
+// their automation rules is
+
+use chrono::NaiveDateTime;
+
+use crate::{
+    fraud::{FraudEngine, ScreeningContext, ScreeningOutcome},
+    models::transfer_limit::{TransferLimitKind, TransferLimits, TransferUsage},
+};
+
+// how far back screening looks at the account's outgoing transfers
+pub const SCREENING_HISTORY_DAYS: i64 = 90;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum TransferCheck {
+    // made and signed, the account decides if it waits for another holder or an approval
+    Allow,
+    // made but held for staff review
+    Hold(Vec<String>),
+    LimitExceeded(TransferLimitKind),
+    Reject(Vec<String>),
+}
+
+pub fn screening_since(now: NaiveDateTime) -> NaiveDateTime {
+    now - chrono::Duration::days(SCREENING_HISTORY_DAYS)
+}
+
+// limits first, there's no point screening a transfer that can't be made anyway
+pub fn check_transfer(
+    limits: &TransferLimits,
+    usage: &[TransferUsage],
+    fraud_engine: &FraudEngine,
+    ctx: &ScreeningContext,
+) -> TransferCheck {
+    if let Err(limit_kind) = limits.check(usage, ctx.transaction) {
+        return TransferCheck::LimitExceeded(limit_kind);
+    }
+
+    match fraud_engine.screen(ctx) {
+        ScreeningOutcome::Allow => TransferCheck::Allow,
+        ScreeningOutcome::Hold(reasons) => TransferCheck::Hold(reasons),
+        ScreeningOutcome::Reject(reasons) => TransferCheck::Reject(reasons),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fixtures::account;
+    use chrono::{NaiveDate, NaiveDateTime};
+
+    use super::{check_transfer, TransferCheck};
+    use crate::{
+        fraud::{FraudEngine, FraudRule, RuleVerdict, ScreeningContext},
+        models::{
+            account::AccountType,
+            transaction::{NewTransaction, TransactionStatus, TransactionType},
+            transfer_limit::{TransferLimitKind, TransferLimits, DEFAULT_PER_TRANSACTION_CENTS},
+        },
+    };
+
+    struct FixedRule(RuleVerdict);
+
+    impl FraudRule for FixedRule {
+        fn evaluate(&self, _: &ScreeningContext) -> RuleVerdict {
+            self.0.clone()
+        }
+    }
+
+    fn now() -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2023, 9, 22)
+            .unwrap()
+            .and_hms_opt(3, 0, 0)
+            .unwrap()
+    }
+
+    fn new_transaction(amount_cents: i64) -> NewTransaction {
+        NewTransaction {
+            customer_id: 5,
+            transaction_type: TransactionType::Internal,
+            from_us: true,
+            amount_cents,
+            from_number: "000000052".to_string(),
+            from_bsb: "123456".to_string(),
+            from_name: None,
+            to_number: "938573844".to_string(),
+            to_bsb: "123456".to_string(),
+            to_name: None,
+            available_balance_cents: 100_000_000 - amount_cents,
+            transaction_status: TransactionStatus::Pending,
+            reversal_of: None,
+            reversal_reason: None,
+            description: None,
+            payer_reference: None,
+            payee_reference: None,
+        }
+    }
+
+    fn check(amount_cents: i64, verdict: RuleVerdict) -> TransferCheck {
+        let engine = FraudEngine::new(vec![Box::new(FixedRule(verdict))]);
+        let transaction = new_transaction(amount_cents);
+        let account = account(52, 5, AccountType::Transaction, 100_000_000);
+
+        check_transfer(
+            &TransferLimits::effective(5, None),
+            &[],
+            &engine,
+            &ScreeningContext {
+                transaction: &transaction,
+                account: &account,
+                history: &[],
+                payee: None,
+                now: now(),
+            },
+        )
+    }
+
+    #[test]
+    fn test_check_transfer_allowed() {
+        assert_eq!(TransferCheck::Allow, check(5_000, RuleVerdict::Allow));
+    }
+
+    #[test]
+    fn test_check_transfer_held_by_screening() {
+        assert_eq!(
+            TransferCheck::Hold(vec!["odd".to_string()]),
+            check(5_000, RuleVerdict::Hold("odd".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_check_transfer_limits_before_screening() {
+        assert_eq!(
+            TransferCheck::LimitExceeded(TransferLimitKind::PerTransaction),
+            check(
+                DEFAULT_PER_TRANSACTION_CENTS + 1,
+                RuleVerdict::Reject("odd".to_string())
+            )
+        );
+    }
+}
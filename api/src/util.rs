@@ -1,9 +1,15 @@
 // shared util functions
 
+use chrono::NaiveDateTime;
 use diesel::r2d2::{self, ConnectionManager, Pool};
 use diesel::PgConnection;
 use dotenvy::dotenv;
+use rand::RngCore;
 use std::env;
+use std::net::{IpAddr, Ipv4Addr};
+use url::{Host, Url};
+
+use crate::outbox::{ConfiguredSink, FileSink, HttpSink, LogSink};
 
 pub fn get_addr() -> (String, u16) {
     let default_host = "localhost";
@@ -38,3 +44,168 @@ pub fn get_db_pool() -> Pool<ConnectionManager<PgConnection>> {
         .build(manager)
         .expect("Failed to create pool.")
 }
+
+// where outbox events are published, logged unless OUTBOX_SINK says otherwise
+pub fn get_outbox_sink() -> ConfiguredSink {
+    const DEFAULT_OUTBOX_FILE_PATH: &str = "outbox.jsonl";
+
+    let sink = match env::var("OUTBOX_SINK") {
+        Ok(v) => v,
+        Err(_) => "log".to_string(),
+    };
+
+    println!("publishing outbox events to {}", sink);
+
+    match sink.as_str() {
+        "log" => ConfiguredSink::Log(LogSink),
+        "http" => ConfiguredSink::Http(HttpSink::new(
+            env::var("OUTBOX_HTTP_URL").expect("OUTBOX_HTTP_URL must be set for the http sink"),
+        )),
+        "file" => ConfiguredSink::File(FileSink::new(
+            match env::var("OUTBOX_FILE_PATH") {
+                Ok(v) => v,
+                Err(_) => DEFAULT_OUTBOX_FILE_PATH.to_string(),
+            }
+            .into(),
+        )),
+        other => panic!("unknown OUTBOX_SINK {}", other),
+    }
+}
+
+pub fn string_opt_from_naive_dt_opt(dt: Option<NaiveDateTime>) -> Option<String> {
+    dt.map(|val| val.to_string())
+}
+
+// limits and anything else "per day" run on UTC days
+pub fn start_of_day(dt: NaiveDateTime) -> NaiveDateTime {
+    dt.date().and_time(chrono::NaiveTime::MIN)
+}
+
+// a random uuid, for ids other systems see
+pub fn random_uuid() -> String {
+    let mut bytes = [0u8; 16];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    // version 4, variant 1
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+    let hex = data_encoding::HEXLOWER.encode(&bytes);
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[0..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..32]
+    )
+}
+
+// plain http is only for something on this machine being developed against
+pub fn is_local_host(host: &str) -> bool {
+    host == "localhost" || host == "127.0.0.1"
+}
+
+// anything reachable from outside, so not our own network, the cloud metadata address or loopback
+pub fn is_public_address(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            !(ip.is_private()
+                || ip.is_loopback()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_documentation()
+                || a == 0
+                // carrier grade nat
+                || (a == 100 && (64..128).contains(&b)))
+        }
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(mapped) => is_public_address(IpAddr::V4(mapped)),
+            None => {
+                let first = ip.segments()[0];
+                !(ip.is_loopback()
+                    || ip.is_unspecified()
+                    // unique local and link local
+                    || (first & 0xfe00) == 0xfc00
+                    || (first & 0xffc0) == 0xfe80)
+            }
+        },
+    }
+}
+
+// somewhere we send customers' codes or our own requests to: https to a public host, or plain http
+// to this machine. parsed rather than prefix matched, so a host that only starts with localhost
+// doesn't pass for it
+pub fn is_valid_callback_url(raw: &str) -> bool {
+    if raw.len() > 500 || raw.chars().any(|c| c.is_whitespace()) {
+        return false;
+    }
+    let Ok(url) = Url::parse(raw) else {
+        return false;
+    };
+    if url.fragment().is_some() || !url.username().is_empty() || url.password().is_some() {
+        return false;
+    }
+
+    match (url.scheme(), url.host()) {
+        ("http", Some(Host::Domain(domain))) => is_local_host(domain),
+        ("http", Some(Host::Ipv4(ip))) => ip == Ipv4Addr::LOCALHOST,
+        ("https", Some(Host::Domain(_))) => true,
+        ("https", Some(Host::Ipv4(ip))) => is_public_address(IpAddr::V4(ip)),
+        ("https", Some(Host::Ipv6(ip))) => is_public_address(IpAddr::V6(ip)),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::IpAddr;
+
+    use super::{is_public_address, is_valid_callback_url};
+
+    #[test]
+    fn callback_urls_are_https_or_local() {
+        assert!(is_valid_callback_url("https://partner.example/callback"));
+        assert!(is_valid_callback_url("http://localhost:3000/callback"));
+        assert!(is_valid_callback_url("http://127.0.0.1:3000/callback"));
+        assert!(!is_valid_callback_url("http://partner.example/callback"));
+        assert!(!is_valid_callback_url(
+            "https://partner.example/callback#frag"
+        ));
+        assert!(!is_valid_callback_url("partner://callback"));
+        assert!(!is_valid_callback_url("https://partner.example/a b"));
+    }
+
+    #[test]
+    fn callback_urls_only_local_by_exact_host() {
+        assert!(!is_valid_callback_url("http://localhost.evil.example/cb"));
+        assert!(!is_valid_callback_url("http://127.0.0.1.evil.example/cb"));
+        assert!(!is_valid_callback_url("http://localhost@evil.example/cb"));
+    }
+
+    #[test]
+    fn callback_urls_not_to_private_addresses() {
+        assert!(is_valid_callback_url("https://93.184.216.34/hooks"));
+        assert!(!is_valid_callback_url("https://10.0.0.5/hooks"));
+        assert!(!is_valid_callback_url(
+            "https://169.254.169.254/latest/meta-data"
+        ));
+        assert!(!is_valid_callback_url("https://127.0.0.1/hooks"));
+        assert!(!is_valid_callback_url("https://[::1]/hooks"));
+        assert!(!is_valid_callback_url("https://[fd00::1]/hooks"));
+        assert!(!is_valid_callback_url("https://[::ffff:192.168.1.1]/hooks"));
+    }
+
+    #[test]
+    fn public_addresses() {
+        let public = |ip: &str| is_public_address(ip.parse::<IpAddr>().unwrap());
+
+        assert!(public("8.8.8.8"));
+        assert!(public("2606:4700::1111"));
+        assert!(!public("192.168.1.1"));
+        assert!(!public("172.16.0.1"));
+        assert!(!public("100.64.0.1"));
+        assert!(!public("0.0.0.0"));
+        assert!(!public("fe80::1"));
+    }
+}
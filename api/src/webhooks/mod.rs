@@ -0,0 +1,441 @@
+// sending queued webhook deliveries. what's queued is decided when an event is published, this
+// signs and posts them, and keeps at it until the subscriber takes one or attempts run out
+
+use std::io;
+use std::net::{SocketAddr, ToSocketAddrs};
+use std::time::Duration;
+
+use actix_web::{rt, web, web::Data};
+use data_encoding::HEXLOWER;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use crate::{
+    error::{NotifyError, RepoError},
+    models::webhook::{
+        DueWebhookDelivery, FindDueWebhookDeliveryQuery, WebhookAttempt, WebhookDelivery,
+    },
+    traits::{RepoFind, RepoUpdate},
+    util::{is_local_host, is_public_address},
+};
+
+#[cfg(test)]
+use mockall::automock;
+
+pub const EVENT_ID_HEADER: &str = "X-Webhook-Id";
+pub const EVENT_TYPE_HEADER: &str = "X-Webhook-Event";
+pub const SIGNATURE_HEADER: &str = "X-Webhook-Signature";
+
+const SEND_TIMEOUT_SECONDS: u64 = 10;
+const POLL_INTERVAL_SECONDS: u64 = 5;
+const DELIVERIES_PER_POLL: i64 = 50;
+// what's kept of a failure in the delivery log
+const MAX_ERROR_LEN: usize = 200;
+
+// the url was checked when it was subscribed, but its name can point anywhere by the time we send.
+// only public addresses are connected to, bar the local receiver the url was let through for
+fn resolve_public(netloc: &str) -> io::Result<Vec<SocketAddr>> {
+    let addrs: Vec<SocketAddr> = netloc.to_socket_addrs()?.collect();
+    let host = netloc.rsplit_once(':').map_or(netloc, |(host, _)| host);
+    if is_local_host(host) {
+        return Ok(addrs);
+    }
+
+    let public: Vec<SocketAddr> = addrs
+        .into_iter()
+        .filter(|addr| is_public_address(addr.ip()))
+        .collect();
+    if public.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            format!("{} isn't a public address", host),
+        ));
+    }
+
+    Ok(public)
+}
+
+// t=<unix seconds>,v1=<hex hmac sha256 of "<t>.<body>">. the timestamp is signed too so a captured
+// delivery can't be replayed at the subscriber later on
+pub fn signature(secret: &str, timestamp: i64, body: &str) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("hmac takes any key length");
+    mac.update(format!("{}.{}", timestamp, body).as_bytes());
+    format!(
+        "t={},v1={}",
+        timestamp,
+        HEXLOWER.encode(&mac.finalize().into_bytes())
+    )
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SignedWebhook {
+    pub url: String,
+    pub event_id: String,
+    pub event_type: String,
+    pub signature: String,
+    pub body: String,
+}
+
+impl SignedWebhook {
+    pub fn new(due: &DueWebhookDelivery, now: chrono::NaiveDateTime) -> SignedWebhook {
+        SignedWebhook {
+            url: due.url.clone(),
+            event_id: due.event.id.clone(),
+            event_type: due.event.event_type.clone(),
+            signature: signature(&due.secret, now.timestamp(), &due.event.payload),
+            body: due.event.payload.clone(),
+        }
+    }
+}
+
+#[cfg_attr(test, automock)]
+pub trait WebhookSender: 'static + Sync + Send {
+    // whatever status the subscriber answered with, Err if it couldn't be reached at all
+    fn send(&self, webhook: &SignedWebhook) -> Result<u16, NotifyError>;
+}
+
+pub struct HttpWebhookSender {
+    agent: ureq::Agent,
+}
+
+impl Default for HttpWebhookSender {
+    fn default() -> Self {
+        HttpWebhookSender {
+            agent: ureq::AgentBuilder::new()
+                .timeout(Duration::from_secs(SEND_TIMEOUT_SECONDS))
+                // a redirect isn't the subscriber taking it
+                .redirects(0)
+                .resolver(resolve_public)
+                .build(),
+        }
+    }
+}
+
+impl WebhookSender for HttpWebhookSender {
+    fn send(&self, webhook: &SignedWebhook) -> Result<u16, NotifyError> {
+        let res = self
+            .agent
+            .post(&webhook.url)
+            .set("Content-Type", "application/json")
+            .set(EVENT_ID_HEADER, &webhook.event_id)
+            .set(EVENT_TYPE_HEADER, &webhook.event_type)
+            .set(SIGNATURE_HEADER, &webhook.signature)
+            .send_string(&webhook.body);
+
+        match res {
+            Ok(response) => Ok(response.status()),
+            Err(ureq::Error::Status(status, _)) => Ok(status),
+            Err(err) => {
+                println!("couldn't reach webhook subscriber {}: {}", webhook.url, err);
+                Err(NotifyError::Unavailable)
+            }
+        }
+    }
+}
+
+// one pass over whatever's due, returns how many were tried
+pub fn deliver_due<WR, S>(
+    webhooks_repo: &WR,
+    sender: &S,
+    now: chrono::NaiveDateTime,
+) -> Result<usize, RepoError>
+where
+    WR: RepoFind<DueWebhookDelivery, FindDueWebhookDeliveryQuery>
+        + RepoUpdate<WebhookDelivery, WebhookAttempt>,
+    S: WebhookSender,
+{
+    let due = webhooks_repo.find(FindDueWebhookDeliveryQuery {
+        now,
+        limit: DELIVERIES_PER_POLL,
+    })?;
+
+    for delivery in due.iter() {
+        let attempt = match sender.send(&SignedWebhook::new(delivery, now)) {
+            Ok(status) => WebhookAttempt {
+                response_status: Some(status as i32),
+                error: None,
+                attempted_at: now,
+            },
+            Err(err) => WebhookAttempt {
+                response_status: None,
+                error: Some(err.to_string().chars().take(MAX_ERROR_LEN).collect()),
+                attempted_at: now,
+            },
+        };
+
+        // the lease runs out and it's tried again, so a lost update only means sending it twice
+        if let Err(err) = webhooks_repo.update(delivery.delivery_id, attempt) {
+            println!(
+                "couldn't record attempt at webhook delivery {}: {}",
+                delivery.delivery_id, err
+            );
+        }
+    }
+
+    Ok(due.len())
+}
+
+// polls for due deliveries for as long as the server's up
+pub fn spawn_delivery_worker<WR, S>(webhooks_repo: Data<WR>, sender: Data<S>)
+where
+    WR: RepoFind<DueWebhookDelivery, FindDueWebhookDeliveryQuery>
+        + RepoUpdate<WebhookDelivery, WebhookAttempt>,
+    S: WebhookSender,
+{
+    rt::spawn(async move {
+        let mut interval = rt::time::interval(Duration::from_secs(POLL_INTERVAL_SECONDS));
+        loop {
+            interval.tick().await;
+
+            let webhooks_repo = webhooks_repo.clone();
+            let sender = sender.clone();
+            let res = web::block(move || {
+                deliver_due(
+                    webhooks_repo.as_ref(),
+                    sender.as_ref(),
+                    chrono::Utc::now().naive_utc(),
+                )
+            })
+            .await;
+
+            match res {
+                Ok(Ok(0)) => {}
+                Ok(Ok(tried)) => println!("Tried {} webhook deliveries", tried),
+                _ => println!("couldn't deliver webhooks"),
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::{
+        io::{BufRead, BufReader, Read, Write},
+        net::TcpListener,
+        sync::Mutex,
+        thread,
+    };
+
+    use chrono::NaiveDate;
+    use mockall::predicate::eq;
+
+    use super::{
+        deliver_due, resolve_public, signature, HttpWebhookSender, MockWebhookSender,
+        SignedWebhook, WebhookSender, EVENT_ID_HEADER, SIGNATURE_HEADER,
+    };
+    use crate::{
+        error::{NotifyError, RepoError},
+        models::webhook::{
+            DueWebhookDelivery, FindDueWebhookDeliveryQuery, WebhookAttempt, WebhookDelivery,
+            WebhookEvent,
+        },
+        traits::{RepoFind, RepoUpdate},
+    };
+
+    fn now() -> chrono::NaiveDateTime {
+        NaiveDate::from_ymd_opt(2023, 9, 20)
+            .unwrap()
+            .and_hms_opt(4, 18, 37)
+            .unwrap()
+    }
+
+    fn due(delivery_id: i32, url: &str) -> DueWebhookDelivery {
+        DueWebhookDelivery {
+            delivery_id,
+            url: url.to_string(),
+            secret: "shh".to_string(),
+            event: WebhookEvent {
+                id: "6f1c8e0a-3b1f-4c55-9d0e-2a7e5b1c9f00".to_string(),
+                event_type: "transaction.created".to_string(),
+                payload: r#"{"id":"6f1c8e0a-3b1f-4c55-9d0e-2a7e5b1c9f00","data":{}}"#.to_string(),
+                created_at: now(),
+            },
+        }
+    }
+
+    // a subscriber on a local port, answers every request with status and hands back what it got
+    pub(crate) fn receiver(status: u16) -> (String, thread::JoinHandle<(Vec<String>, String)>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/hooks", listener.local_addr().unwrap());
+
+        let handle = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+
+            let mut headers = vec![];
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line.trim().is_empty() {
+                    break;
+                }
+                headers.push(line.trim().to_string());
+            }
+
+            let content_length = headers
+                .iter()
+                .find_map(|h| {
+                    h.to_lowercase()
+                        .strip_prefix("content-length:")
+                        .map(|v| v.trim().parse::<usize>().unwrap())
+                })
+                .unwrap_or(0);
+            let mut body = vec![0u8; content_length];
+            reader.read_exact(&mut body).unwrap();
+
+            reader
+                .get_mut()
+                .write_all(
+                    format!(
+                        "HTTP/1.1 {} Whatever\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                        status
+                    )
+                    .as_bytes(),
+                )
+                .unwrap();
+
+            (headers, String::from_utf8(body).unwrap())
+        });
+
+        (url, handle)
+    }
+
+    #[derive(Default)]
+    struct RecordingWR {
+        due: Vec<DueWebhookDelivery>,
+        attempts: Mutex<Vec<(i32, WebhookAttempt)>>,
+    }
+
+    impl RepoFind<DueWebhookDelivery, FindDueWebhookDeliveryQuery> for RecordingWR {
+        fn find(
+            &self,
+            _: FindDueWebhookDeliveryQuery,
+        ) -> Result<Vec<DueWebhookDelivery>, RepoError> {
+            Ok(self.due.clone())
+        }
+    }
+
+    impl RepoUpdate<WebhookDelivery, WebhookAttempt> for RecordingWR {
+        fn update(&self, id: i32, attempt: WebhookAttempt) -> Result<WebhookDelivery, RepoError> {
+            self.attempts.lock().unwrap().push((id, attempt));
+            Err(RepoError::Other)
+        }
+    }
+
+    #[test]
+    fn only_public_addresses_resolved() {
+        assert!(resolve_public("127.0.0.1:9000").is_ok());
+        assert!(resolve_public("93.184.216.34:443").is_ok());
+        assert!(resolve_public("10.0.0.5:443").is_err());
+        assert!(resolve_public("169.254.169.254:80").is_err());
+        assert!(resolve_public("[::1]:443").is_err());
+    }
+
+    #[test]
+    fn signature_covers_timestamp_and_body() {
+        let signed = signature("shh", 1695183517, r#"{"id":"1"}"#);
+
+        assert!(signed.starts_with("t=1695183517,v1="));
+        assert_eq!(signed, signature("shh", 1695183517, r#"{"id":"1"}"#));
+        assert_ne!(signed, signature("shh", 1695183518, r#"{"id":"1"}"#));
+        assert_ne!(signed, signature("shh", 1695183517, r#"{"id":"2"}"#));
+        assert_ne!(signed, signature("other", 1695183517, r#"{"id":"1"}"#));
+    }
+
+    #[test]
+    fn http_sender_posts_signed_body() {
+        let (url, handle) = receiver(204);
+        let webhook = SignedWebhook::new(&due(1, &url), now());
+
+        let status = HttpWebhookSender::default().send(&webhook).unwrap();
+        let (headers, body) = handle.join().unwrap();
+
+        assert_eq!(204, status);
+        assert_eq!(webhook.body, body);
+        assert!(headers.iter().any(|h| h.starts_with("POST /hooks ")));
+        assert!(headers.contains(&format!(
+            "{}: {}",
+            SIGNATURE_HEADER,
+            signature("shh", now().timestamp(), &body)
+        )));
+        assert!(headers.contains(&format!("{}: {}", EVENT_ID_HEADER, webhook.event_id)));
+    }
+
+    #[test]
+    fn http_sender_passes_on_error_status() {
+        let (url, handle) = receiver(503);
+
+        let status = HttpWebhookSender::default()
+            .send(&SignedWebhook::new(&due(1, &url), now()))
+            .unwrap();
+        handle.join().unwrap();
+
+        assert_eq!(503, status);
+    }
+
+    #[test]
+    fn http_sender_unreachable() {
+        // nothing listens once the listener's dropped
+        let url = {
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            format!("http://{}/hooks", listener.local_addr().unwrap())
+        };
+
+        let res = HttpWebhookSender::default().send(&SignedWebhook::new(&due(1, &url), now()));
+
+        assert!(matches!(res, Err(NotifyError::Unavailable)));
+    }
+
+    #[test]
+    fn deliver_due_records_every_attempt() {
+        let webhooks_repo = RecordingWR {
+            due: vec![
+                due(1, "https://one.example.com"),
+                due(2, "https://two.example.com"),
+            ],
+            ..Default::default()
+        };
+
+        let mut sender = MockWebhookSender::new();
+        sender
+            .expect_send()
+            .withf(|webhook| webhook.url == "https://one.example.com")
+            .times(1)
+            .returning(|_| Ok(200));
+        sender
+            .expect_send()
+            .with(eq(SignedWebhook::new(
+                &due(2, "https://two.example.com"),
+                now(),
+            )))
+            .times(1)
+            .returning(|_| Err(NotifyError::Unavailable));
+
+        // the repo failing to record one doesn't stop the rest
+        let tried = deliver_due(&webhooks_repo, &sender, now()).unwrap();
+
+        assert_eq!(2, tried);
+        assert_eq!(
+            vec![
+                (
+                    1,
+                    WebhookAttempt {
+                        response_status: Some(200),
+                        error: None,
+                        attempted_at: now(),
+                    }
+                ),
+                (
+                    2,
+                    WebhookAttempt {
+                        response_status: None,
+                        error: Some("Unavailable".to_string()),
+                        attempted_at: now(),
+                    }
+                ),
+            ],
+            *webhooks_repo.attempts.lock().unwrap()
+        );
+    }
+}